@@ -0,0 +1,59 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use nalgebra::Point3;
+use opossum::{
+    joule, millimeter, nanometer,
+    position_distributions::{PositionDistribution, SobolDist},
+    ray::Ray,
+    surface::{Asphere, AsphericProfile, TriangulatedSurface, geo_surface::GeoSurface},
+    utils::geom_transformation::Isometry,
+};
+
+fn criterion_tessellated_asphere(c: &mut Criterion) {
+    let asphere = Asphere::new(
+        millimeter!(50.0),
+        AsphericProfile::new(-0.8, vec![1.0e2]).unwrap(),
+        Isometry::identity(),
+    )
+    .unwrap();
+    let mut surface = TriangulatedSurface::from_sag(
+        millimeter!(10.0),
+        51,
+        |x, y| asphere.sag((x * x + y * y).sqrt()),
+        Isometry::identity(),
+    )
+    .unwrap();
+    let rays: Vec<Ray> = SobolDist::new(millimeter!(10.0), millimeter!(10.0), 100_000)
+        .unwrap()
+        .generate()
+        .iter()
+        .map(|p| {
+            Ray::new_collimated(
+                Point3::new(p.x, p.y, millimeter!(-10.0)),
+                nanometer!(1000.0),
+                joule!(1.0),
+            )
+            .unwrap()
+        })
+        .collect();
+    let mut group = c.benchmark_group("tessellated asphere (1e5 rays)");
+    group.sample_size(10);
+    group.bench_function("bvh", |b| {
+        b.iter(|| {
+            rays.iter()
+                .filter_map(|r| surface.calc_intersect_and_normal(r))
+                .count()
+        })
+    });
+    surface.set_accelerated(false);
+    group.bench_function("brute force", |b| {
+        b.iter(|| {
+            rays.iter()
+                .filter_map(|r| surface.calc_intersect_and_normal(r))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_tessellated_asphere);
+criterion_main!(benches);
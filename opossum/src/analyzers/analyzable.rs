@@ -2,7 +2,8 @@
 #![warn(missing_docs)]
 use crate::{
     analyzers::{
-        energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
+        energy::AnalysisEnergy, gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
     },
     optic_node::OpticNode,
 };
@@ -11,7 +12,7 @@ use std::fmt::Display;
 
 /// Marker trait for an optical node that can be analyzed
 pub trait Analyzable:
-    OpticNode + AnalysisEnergy + AnalysisRayTrace + AnalysisGhostFocus + Send
+    OpticNode + AnalysisEnergy + AnalysisRayTrace + AnalysisGhostFocus + AnalysisGaussianBeam + Send
 {
 }
 impl Debug for dyn Analyzable {
@@ -0,0 +1,302 @@
+#![warn(missing_docs)]
+//! Beam caustic of a ray tracing analysis
+//!
+//! The caustic is determined from the position histories of all rays leaving the scenery. Each ray path is sampled at
+//! regular intervals of its geometric propagation distance. At each sampling distance, the energy-weighted RMS radius of
+//! the ray positions is calculated perpendicular to the mean propagation direction. Hence, the caustic also follows
+//! folded beam paths (e.g. after mirrors).
+use crate::{
+    error::{OpmResult, OpossumError},
+    plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
+    properties::Proptype,
+    rays::Rays,
+    utils::usize_to_f64,
+};
+use nalgebra::{Matrix3, MatrixXx2, Vector3};
+use plotters::style::RGBAColor;
+use serde::{Deserialize, Serialize};
+use uom::si::{
+    energy::joule,
+    f64::Length,
+    length::{meter, millimeter},
+};
+
+/// Energy-weighted moments of the ray positions at a given propagation distance.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CausticSample {
+    nr_of_rays: usize,
+    /// sum of the ray energies (in J)
+    energy: f64,
+    /// energy-weighted sum of the positions (in J m)
+    first_moment: Vector3<f64>,
+    /// energy-weighted sum of the outer products of the positions (in J m²)
+    second_moment: Matrix3<f64>,
+    /// energy-weighted sum of the propagation directions
+    direction: Vector3<f64>,
+}
+impl CausticSample {
+    fn add(&mut self, position: &Vector3<f64>, direction: &Vector3<f64>, energy: f64) {
+        self.nr_of_rays += 1;
+        self.energy += energy;
+        self.first_moment += energy * position;
+        self.second_moment += energy * position * position.transpose();
+        self.direction += energy * direction;
+    }
+    fn merge(&mut self, other: &Self) {
+        self.nr_of_rays += other.nr_of_rays;
+        self.energy += other.energy;
+        self.first_moment += other.first_moment;
+        self.second_moment += other.second_moment;
+        self.direction += other.direction;
+    }
+    /// Returns the RMS radius (in m) perpendicular to the mean propagation direction.
+    fn rms_radius(&self) -> Option<f64> {
+        if self.energy <= 0.0 {
+            return None;
+        }
+        let mean = self.first_moment / self.energy;
+        let covariance = self.second_moment / self.energy - mean * mean.transpose();
+        let direction = self.direction.try_normalize(f64::EPSILON)?;
+        let radius_sq = covariance.trace() - (direction.transpose() * covariance * direction)[0];
+        Some(radius_sq.max(0.0).sqrt())
+    }
+}
+
+/// The beam caustic (RMS beam radius vs. propagation distance) of a ray tracing analysis.
+///
+/// The caustic only contains sampling distances, which have been reached by all (valid) rays added. Hence, for ray
+/// bundles leaving the scenery on different output ports, the caustic ends with the shortest beam path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BeamCaustic {
+    step: Length,
+    samples: Vec<CausticSample>,
+}
+impl BeamCaustic {
+    /// Creates a new (empty) [`BeamCaustic`] sampled at regular intervals of the given propagation distance.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given step is not positive and finite.
+    pub fn new(step: Length) -> OpmResult<Self> {
+        if !step.is_normal() || step.is_sign_negative() {
+            return Err(OpossumError::Analysis(
+                "caustic sampling step must be positive and finite".into(),
+            ));
+        }
+        Ok(Self {
+            step,
+            samples: Vec::new(),
+        })
+    }
+    /// Returns the sampling step of this [`BeamCaustic`].
+    #[must_use]
+    pub const fn step(&self) -> Length {
+        self.step
+    }
+    /// Add the (valid) rays of the given [`Rays`] to this [`BeamCaustic`].
+    ///
+    /// The path of each ray is reconstructed from its position history and its current position.
+    pub fn add_rays(&mut self, rays: &Rays) {
+        let step = self.step.get::<meter>();
+        for ray in rays.iter().filter(|r| r.valid()) {
+            let energy = ray.energy().get::<joule>();
+            let positions = ray.position_history_with_current();
+            let mut sample_idx = 0;
+            let mut distance = 0.0;
+            for i in 1..positions.nrows() {
+                let start = Vector3::from_fn(|j, _| positions[(i - 1, j)].get::<meter>());
+                let end = Vector3::from_fn(|j, _| positions[(i, j)].get::<meter>());
+                let segment = end - start;
+                let length = segment.norm();
+                let Some(direction) = segment.try_normalize(f64::EPSILON) else {
+                    continue;
+                };
+                while usize_to_f64(sample_idx) * step <= distance + length {
+                    let position = start + (usize_to_f64(sample_idx) * step - distance) * direction;
+                    if self.samples.len() <= sample_idx {
+                        self.samples.push(CausticSample::default());
+                    }
+                    self.samples[sample_idx].add(&position, &direction, energy);
+                    sample_idx += 1;
+                }
+                distance += length;
+            }
+        }
+    }
+    /// Merge another [`BeamCaustic`] (e.g. from a subgroup) into this one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the sampling steps of both caustics differ.
+    pub fn merge(&mut self, other: &Self) -> OpmResult<()> {
+        if self.step != other.step {
+            return Err(OpossumError::Analysis(
+                "cannot merge beam caustics with different sampling steps".into(),
+            ));
+        }
+        if self.samples.len() < other.samples.len() {
+            self.samples
+                .resize(other.samples.len(), CausticSample::default());
+        }
+        for (sample, other_sample) in self.samples.iter_mut().zip(&other.samples) {
+            sample.merge(other_sample);
+        }
+        Ok(())
+    }
+    /// Returns the RMS beam radius as function of the propagation distance.
+    ///
+    /// Only distances reached by all rays are returned.
+    #[must_use]
+    pub fn radii(&self) -> Vec<(Length, Length)> {
+        let Some(first) = self.samples.first() else {
+            return Vec::new();
+        };
+        self.samples
+            .iter()
+            .enumerate()
+            .take_while(|(_, sample)| sample.nr_of_rays == first.nr_of_rays)
+            .filter_map(|(i, sample)| {
+                sample
+                    .rms_radius()
+                    .map(|r| (usize_to_f64(i) * self.step, Length::new::<meter>(r)))
+            })
+            .collect()
+    }
+    /// Returns the propagation distance and the RMS radius of the narrowest sample of this [`BeamCaustic`].
+    ///
+    /// This function returns `None` if the caustic is empty.
+    #[must_use]
+    pub fn waist(&self) -> Option<(Length, Length)> {
+        self.radii()
+            .into_iter()
+            .min_by(|a, b| a.1.value.total_cmp(&b.1.value))
+    }
+}
+impl From<BeamCaustic> for Proptype {
+    fn from(value: BeamCaustic) -> Self {
+        Self::BeamCaustic(value)
+    }
+}
+impl Plottable for BeamCaustic {
+    fn get_plot_series(
+        &self,
+        plt_type: &mut PlotType,
+        _legend: bool,
+    ) -> OpmResult<Option<Vec<PlotSeries>>> {
+        let radii = self.radii();
+        let mut xy_data = MatrixXx2::zeros(radii.len());
+        for (i, (z, w)) in radii.iter().enumerate() {
+            xy_data[(i, 0)] = z.get::<millimeter>();
+            xy_data[(i, 1)] = w.get::<millimeter>();
+        }
+        match plt_type {
+            PlotType::Line2D(_) | PlotType::Scatter2D(_) => Ok(Some(vec![PlotSeries::new(
+                &PlotData::Dim2 { xy_data },
+                RGBAColor(255, 0, 0, 1.),
+                None,
+            )])),
+            _ => Ok(None),
+        }
+    }
+    fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
+        plt_params
+            .set(&PlotArgs::XLabel("propagation distance in mm".into()))?
+            .set(&PlotArgs::YLabel("rms beam radius in mm".into()))?
+            .set(&PlotArgs::PlotSize((1200, 800)))?
+            .set(&PlotArgs::AxisEqual(false))?;
+        Ok(())
+    }
+    fn get_plot_type(&self, plt_params: &PlotParameters) -> PlotType {
+        PlotType::Line2D(plt_params.clone())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{joule, millimeter, nanometer, ray::Ray};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::vector;
+
+    fn converging_rays() -> Rays {
+        let mut rays = Rays::default();
+        for (x, y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            let mut ray = Ray::new(
+                millimeter!(x, y, 0.0),
+                vector![-x / 100.0, -y / 100.0, 1.0],
+                nanometer!(1000.0),
+                joule!(1.0),
+            )
+            .unwrap();
+            ray.propagate(millimeter!(200.0)).unwrap();
+            rays.add_ray(ray);
+        }
+        rays
+    }
+    #[test]
+    fn new() {
+        assert!(BeamCaustic::new(millimeter!(0.0)).is_err());
+        assert!(BeamCaustic::new(millimeter!(-1.0)).is_err());
+        assert!(BeamCaustic::new(millimeter!(f64::NAN)).is_err());
+        assert!(BeamCaustic::new(millimeter!(f64::INFINITY)).is_err());
+        let caustic = BeamCaustic::new(millimeter!(1.0)).unwrap();
+        assert_eq!(caustic.step(), millimeter!(1.0));
+        assert!(caustic.radii().is_empty());
+        assert!(caustic.waist().is_none());
+    }
+    #[test]
+    fn converging() {
+        let mut caustic = BeamCaustic::new(millimeter!(10.0)).unwrap();
+        caustic.add_rays(&converging_rays());
+        let radii = caustic.radii();
+        assert_eq!(radii.len(), 21);
+        assert_abs_diff_eq!(radii[0].1.get::<millimeter>(), 1.0, epsilon = 1e-9);
+        let (z, w) = caustic.waist().unwrap();
+        assert_abs_diff_eq!(z.get::<millimeter>(), 100.0, epsilon = 1.0);
+        assert_abs_diff_eq!(w.get::<millimeter>(), 0.0, epsilon = 1e-3);
+        // symmetric behind the focus
+        assert_abs_diff_eq!(radii[20].1.get::<millimeter>(), 1.0, epsilon = 1e-3);
+    }
+    #[test]
+    fn diverging() {
+        let mut rays = Rays::default();
+        for x in [1.0, -1.0] {
+            let mut ray = Ray::new(
+                millimeter!(x, 0.0, 0.0),
+                vector![x / 100.0, 0.0, 1.0],
+                nanometer!(1000.0),
+                joule!(1.0),
+            )
+            .unwrap();
+            ray.propagate(millimeter!(100.0)).unwrap();
+            ray.propagate(millimeter!(100.0)).unwrap();
+            rays.add_ray(ray);
+        }
+        let mut caustic = BeamCaustic::new(millimeter!(50.0)).unwrap();
+        caustic.add_rays(&rays);
+        let radii = caustic.radii();
+        assert_eq!(radii.len(), 5);
+        for (z, w) in radii {
+            assert_abs_diff_eq!(
+                w.get::<millimeter>(),
+                1.0 + z.get::<millimeter>() / 100.0,
+                epsilon = 1e-3
+            );
+        }
+        assert_abs_diff_eq!(caustic.waist().unwrap().0.value, 0.0);
+    }
+    #[test]
+    fn merge() {
+        let mut caustic = BeamCaustic::new(millimeter!(10.0)).unwrap();
+        caustic.add_rays(&converging_rays());
+        let mut merged = BeamCaustic::new(millimeter!(10.0)).unwrap();
+        merged.merge(&caustic).unwrap();
+        assert_eq!(merged, caustic);
+        merged.merge(&caustic).unwrap();
+        assert_eq!(merged.radii(), caustic.radii());
+        assert!(
+            merged
+                .merge(&BeamCaustic::new(millimeter!(1.0)).unwrap())
+                .is_err()
+        );
+    }
+}
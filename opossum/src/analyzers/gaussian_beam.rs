@@ -0,0 +1,536 @@
+#![warn(missing_docs)]
+//! Paraxial Gaussian beam analysis
+//!
+//! This analyzer propagates the complex beam parameter `q` of a fundamental Gaussian beam along the optical axis of a
+//! scenery using the ABCD matrices of the individual nodes. The beam radius `w` and the radius of curvature `R` of the
+//! wavefront are determined at each node and reported as a beam caustic `w(z)`.
+use super::Analyzer;
+use crate::{
+    error::{OpmResult, OpossumError},
+    millimeter, nanometer,
+    nodes::NodeGroup,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
+    properties::{Properties, Proptype},
+    reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
+    utils::usize_to_f64,
+};
+use log::info;
+use nalgebra::{Matrix2, MatrixXx2};
+use num::Complex;
+use plotters::style::RGBAColor;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use uom::si::{
+    f64::Length,
+    length::{meter, millimeter},
+};
+use uuid::Uuid;
+
+/// Number of points used for sampling the beam caustic between two nodes.
+const NR_OF_SAMPLES_PER_SEGMENT: usize = 50;
+
+/// Configuration for performing a Gaussian beam analysis.
+///
+/// The input beam is a fundamental Gaussian beam defined by its vacuum wavelength, its waist radius (1/e² intensity
+/// radius) and the position of the waist relative to the source. A positive waist position denotes a waist located
+/// behind the source (in propagation direction), a negative value a (virtual) waist in front of the source.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct GaussianBeamConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    wavelength: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    waist_radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    waist_position: Length,
+}
+impl GaussianBeamConfig {
+    /// Creates a new [`GaussianBeamConfig`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the wavelength or the waist radius is not positive and finite.
+    ///   - the waist position is not finite.
+    pub fn new(
+        wavelength: Length,
+        waist_radius: Length,
+        waist_position: Length,
+    ) -> OpmResult<Self> {
+        if !wavelength.is_normal() || wavelength.is_sign_negative() {
+            return Err(OpossumError::Analysis(
+                "wavelength must be positive and finite".into(),
+            ));
+        }
+        if !waist_radius.is_normal() || waist_radius.is_sign_negative() {
+            return Err(OpossumError::Analysis(
+                "waist radius must be positive and finite".into(),
+            ));
+        }
+        if !waist_position.is_finite() {
+            return Err(OpossumError::Analysis(
+                "waist position must be finite".into(),
+            ));
+        }
+        Ok(Self {
+            wavelength,
+            waist_radius,
+            waist_position,
+        })
+    }
+    /// Returns the wavelength of this [`GaussianBeamConfig`].
+    #[must_use]
+    pub const fn wavelength(&self) -> Length {
+        self.wavelength
+    }
+    /// Returns the waist radius of this [`GaussianBeamConfig`].
+    #[must_use]
+    pub const fn waist_radius(&self) -> Length {
+        self.waist_radius
+    }
+    /// Returns the waist position (relative to the source) of this [`GaussianBeamConfig`].
+    #[must_use]
+    pub const fn waist_position(&self) -> Length {
+        self.waist_position
+    }
+}
+impl Default for GaussianBeamConfig {
+    fn default() -> Self {
+        Self {
+            wavelength: nanometer!(1000.0),
+            waist_radius: millimeter!(1.0),
+            waist_position: Length::default(),
+        }
+    }
+}
+/// Parameters of a Gaussian beam at a given position along the optical axis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeamParameters {
+    /// name of the node at which the parameters are determined
+    pub node: String,
+    /// position along the (unfolded) optical axis
+    pub position: Length,
+    /// beam radius (1/e² intensity radius)
+    pub radius: Length,
+    /// radius of curvature of the wavefront (infinite for a flat wavefront)
+    pub curvature: Length,
+}
+/// Caustic of a Gaussian beam along the optical axis of a scenery.
+///
+/// The positions along the optical axis are determined by the distances between the nodes. The extent of elements
+/// along the optical axis (e.g. the center thickness of a lens) is not considered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GaussianBeamProfile {
+    nodes: Vec<BeamParameters>,
+    caustic: Vec<(Length, Length)>,
+}
+impl GaussianBeamProfile {
+    /// Returns the beam parameters at the input and the output of each node along the beam path.
+    #[must_use]
+    pub fn nodes(&self) -> &[BeamParameters] {
+        &self.nodes
+    }
+    /// Returns the sampled beam radius `w(z)` along the optical axis.
+    #[must_use]
+    pub fn caustic(&self) -> &[(Length, Length)] {
+        &self.caustic
+    }
+}
+impl From<GaussianBeamProfile> for Proptype {
+    fn from(value: GaussianBeamProfile) -> Self {
+        Self::GaussianBeamProfile(value)
+    }
+}
+impl Plottable for GaussianBeamProfile {
+    fn get_plot_series(
+        &self,
+        plt_type: &mut PlotType,
+        _legend: bool,
+    ) -> OpmResult<Option<Vec<PlotSeries>>> {
+        let mut xy_data = MatrixXx2::zeros(self.caustic.len());
+        for (i, (z, w)) in self.caustic.iter().enumerate() {
+            xy_data[(i, 0)] = z.get::<millimeter>();
+            xy_data[(i, 1)] = w.get::<millimeter>();
+        }
+        match plt_type {
+            PlotType::Line2D(_) | PlotType::Scatter2D(_) => Ok(Some(vec![PlotSeries::new(
+                &PlotData::Dim2 { xy_data },
+                RGBAColor(255, 0, 0, 1.),
+                None,
+            )])),
+            _ => Ok(None),
+        }
+    }
+    fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
+        plt_params
+            .set(&PlotArgs::XLabel("position in mm".into()))?
+            .set(&PlotArgs::YLabel("beam radius in mm".into()))?
+            .set(&PlotArgs::PlotSize((1200, 800)))?
+            .set(&PlotArgs::AxisEqual(false))?;
+        Ok(())
+    }
+    fn get_plot_type(&self, plt_params: &PlotParameters) -> PlotType {
+        PlotType::Line2D(plt_params.clone())
+    }
+}
+/// Trait for implementing the Gaussian beam analysis.
+pub trait AnalysisGaussianBeam: OpticNode {
+    /// Returns the paraxial ABCD matrix of this [`OpticNode`] for the given (vacuum) wavelength.
+    ///
+    /// The matrix acts on the ray vector `(y, θ)` (distance in meters and angle in radians) and describes the
+    /// propagation from the input to the output of the node, which are both located in the ambient medium with the
+    /// given refractive index. Reflective elements are described in the unfolded beam path. `None` is returned if the
+    /// node cannot be described by a (rotationally symmetric) ABCD matrix. This is also the default implementation.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the matrix cannot be calculated (e.g. the refractive index of the
+    /// material is not defined at the given wavelength).
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(None)
+    }
+}
+/// Analyzer for propagating a Gaussian beam through a scenery
+#[derive(Default, Debug)]
+pub struct GaussianBeamAnalyzer {
+    config: GaussianBeamConfig,
+}
+impl GaussianBeamAnalyzer {
+    /// Creates a new [`GaussianBeamAnalyzer`].
+    #[must_use]
+    pub const fn new(config: GaussianBeamConfig) -> Self {
+        Self { config }
+    }
+    /// Returns a reference to the config of this [`GaussianBeamAnalyzer`].
+    #[must_use]
+    pub const fn config(&self) -> &GaussianBeamConfig {
+        &self.config
+    }
+    /// Returns the [`Uuid`] of the (single) source node of the given scenery.
+    fn source(scenery: &NodeGroup) -> OpmResult<Uuid> {
+        let mut sources = Vec::new();
+        for node_ref in scenery.nodes() {
+            let node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            if node.node_type() == "source" {
+                sources.push(node.node_attr().uuid());
+            }
+        }
+        if sources.len() != 1 {
+            return Err(OpossumError::Analysis(
+                "Gaussian beam analysis requires exactly one source in the scenery".into(),
+            ));
+        }
+        Ok(sources[0])
+    }
+    /// Propagate the Gaussian beam through the given scenery and determine its [`GaussianBeamProfile`].
+    ///
+    /// Starting at the source, the beam follows the first connected output port of each node. Nested groups are not
+    /// supported.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the scenery does not contain exactly one source.
+    ///   - a node along the beam path does not provide an ABCD matrix.
+    ///   - the resulting beam is not a valid Gaussian beam (e.g. due to a non-physical ABCD matrix).
+    pub fn profile(&self, scenery: &NodeGroup) -> OpmResult<GaussianBeamProfile> {
+        let wavelength = self.config.wavelength.get::<meter>();
+        let n_ambient = scenery
+            .ambient_idx()
+            .get_refractive_index(self.config.wavelength)?;
+        let rayleigh_length =
+            PI * n_ambient * self.config.waist_radius.get::<meter>().powi(2) / wavelength;
+        let mut q = Complex::new(-self.config.waist_position.get::<meter>(), rayleigh_length);
+        let beam_parameters = |node: &str, z: f64, q: Complex<f64>| {
+            let inv_q = q.inv();
+            if !inv_q.im.is_normal() || inv_q.im.is_sign_positive() {
+                return Err(OpossumError::Analysis(format!(
+                    "invalid Gaussian beam after node '{node}'"
+                )));
+            }
+            Ok(BeamParameters {
+                node: node.to_owned(),
+                position: Length::new::<meter>(z),
+                radius: Length::new::<meter>((-wavelength / (PI * n_ambient * inv_q.im)).sqrt()),
+                curvature: Length::new::<meter>(inv_q.re.recip()),
+            })
+        };
+        let connections = scenery.connections();
+        let mut profile = GaussianBeamProfile::default();
+        let mut node_id = Self::source(scenery)?;
+        let mut z = 0.0;
+        loop {
+            let node_ref = scenery.node(node_id)?;
+            let node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            let name = node.name();
+            let matrix = if node.enabled() {
+                node.abcd_matrix(self.config.wavelength, n_ambient)?
+            } else {
+                Some(Matrix2::identity())
+            };
+            let Some(mut matrix) = matrix else {
+                return Err(OpossumError::Analysis(format!(
+                    "node '{name}' ({}) does not support Gaussian beam analysis",
+                    node.node_type()
+                )));
+            };
+            if node.inverted() {
+                matrix.swap((0, 0), (1, 1));
+            }
+            profile.nodes.push(beam_parameters(&name, z, q)?);
+            q = (matrix[(0, 0)] * q + matrix[(0, 1)]) / (matrix[(1, 0)] * q + matrix[(1, 1)]);
+            profile.nodes.push(beam_parameters(&name, z, q)?);
+            let output_ports = node.ports().names(&PortType::Output);
+            drop(node);
+            let Some((_, _, target, _, distance)) = output_ports.iter().find_map(|port| {
+                connections
+                    .iter()
+                    .find(|c| c.0 == node_id && c.1 == *port)
+                    .cloned()
+            }) else {
+                break;
+            };
+            let distance = distance.get::<meter>();
+            for i in 0..=NR_OF_SAMPLES_PER_SEGMENT {
+                let dz = distance * usize_to_f64(i) / usize_to_f64(NR_OF_SAMPLES_PER_SEGMENT);
+                let parameters = beam_parameters(&name, z + dz, q + dz)?;
+                profile
+                    .caustic
+                    .push((parameters.position, parameters.radius));
+            }
+            z += distance;
+            q += distance;
+            node_id = target;
+        }
+        Ok(profile)
+    }
+}
+impl Analyzer for GaussianBeamAnalyzer {
+    fn analyze(&self, scenery: &mut NodeGroup) -> OpmResult<()> {
+        let scenery_name = if scenery.node_attr().name().is_empty() {
+            String::new()
+        } else {
+            format!(" '{}'", scenery.node_attr().name())
+        };
+        info!("Performing Gaussian beam analysis of scenery{scenery_name}.");
+        let profile = self.profile(scenery)?;
+        if let Some(last) = profile.nodes().last() {
+            info!(
+                "Beam radius at '{}': {:.3} mm",
+                last.node,
+                last.radius.get::<millimeter>()
+            );
+        }
+        Ok(())
+    }
+    fn report(&self, scenery: &NodeGroup) -> OpmResult<AnalysisReport> {
+        let mut report = scenery.toplevel_report()?;
+        report.set_analysis_type("Gaussian Beam Analysis");
+        let profile = self.profile(scenery)?;
+        let mut props = Properties::default();
+        for (i, parameters) in profile.nodes().iter().enumerate() {
+            let side = if i % 2 == 0 { "input" } else { "output" };
+            props.create(
+                &format!("{} ({side})", parameters.node),
+                "beam radius and radius of curvature",
+                format!(
+                    "z = {:.3} mm, w = {:.4} mm, R = {:.3} mm",
+                    parameters.position.get::<millimeter>(),
+                    parameters.radius.get::<millimeter>(),
+                    parameters.curvature.get::<millimeter>()
+                )
+                .into(),
+            )?;
+        }
+        props.create(
+            "Caustic",
+            "beam radius along the optical axis",
+            profile.into(),
+        )?;
+        let mut node_report = NodeReport::new(
+            "gaussian beam",
+            "Gaussian beam",
+            &Self::source(scenery)?.as_simple().to_string(),
+            props,
+        );
+        node_report.set_show_item(true);
+        report.add_node_report(node_report);
+        Ok(report)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        analyzers::Analyzable,
+        joule,
+        lightdata::{energy_data_builder::EnergyDataBuilder, light_data_builder::LightDataBuilder},
+        nodes::{CylindricLens, Dummy, EnergyMeter, Lens, ParaxialSurface, Source, ThinMirror},
+    };
+    use approx::assert_relative_eq;
+
+    fn source() -> Source {
+        Source::new(
+            "source",
+            LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+                vec![(nanometer!(1000.0), joule!(1.0))],
+                nanometer!(1.0),
+            )),
+        )
+    }
+    /// Scenery consisting of a source, a given node and a detector at the given distances.
+    fn scenery<T: Analyzable + Clone + 'static>(node: T, d1: Length, d2: Length) -> NodeGroup {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery.add_node(source()).unwrap();
+        let i_node = scenery.add_node(node).unwrap();
+        let i_det = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_node, "input_1", d1)
+            .unwrap();
+        scenery
+            .connect_nodes(i_node, "output_1", i_det, "input_1", d2)
+            .unwrap();
+        scenery
+    }
+    #[test]
+    fn config() {
+        let config = GaussianBeamConfig::default();
+        assert_eq!(config.wavelength(), nanometer!(1000.0));
+        assert_eq!(config.waist_radius(), millimeter!(1.0));
+        assert_eq!(config.waist_position(), millimeter!(0.0));
+        assert!(
+            GaussianBeamConfig::new(nanometer!(0.0), millimeter!(1.0), millimeter!(0.0)).is_err()
+        );
+        assert!(
+            GaussianBeamConfig::new(nanometer!(f64::NAN), millimeter!(1.0), millimeter!(0.0))
+                .is_err()
+        );
+        assert!(
+            GaussianBeamConfig::new(nanometer!(1000.0), millimeter!(-1.0), millimeter!(0.0))
+                .is_err()
+        );
+        assert!(
+            GaussianBeamConfig::new(
+                nanometer!(1000.0),
+                millimeter!(1.0),
+                millimeter!(f64::INFINITY)
+            )
+            .is_err()
+        );
+        let config =
+            GaussianBeamConfig::new(nanometer!(500.0), millimeter!(2.0), millimeter!(-10.0))
+                .unwrap();
+        assert_eq!(config.waist_position(), millimeter!(-10.0));
+    }
+    #[test]
+    fn free_space() {
+        let scenery = scenery(Dummy::default(), millimeter!(100.0), millimeter!(100.0));
+        let config =
+            GaussianBeamConfig::new(nanometer!(1000.0), millimeter!(0.1), millimeter!(50.0))
+                .unwrap();
+        let profile = GaussianBeamAnalyzer::new(config).profile(&scenery).unwrap();
+        assert_eq!(profile.nodes().len(), 6);
+        let rayleigh_length = PI * 0.1e-3_f64.powi(2) / 1000.0e-9;
+        // waist is located 50 mm behind the source
+        let w = |z: f64| 0.1e-3 * (1.0 + ((z - 0.05) / rayleigh_length).powi(2)).sqrt();
+        let r = |z: f64| (z - 0.05) * (1.0 + (rayleigh_length / (z - 0.05)).powi(2));
+        for (parameters, z) in profile.nodes().iter().zip([0.0, 0.0, 0.1, 0.1, 0.2, 0.2]) {
+            assert_relative_eq!(parameters.position.get::<meter>(), z);
+            assert_relative_eq!(parameters.radius.get::<meter>(), w(z), max_relative = 1e-12);
+            assert_relative_eq!(
+                parameters.curvature.get::<meter>(),
+                r(z),
+                max_relative = 1e-9
+            );
+        }
+        assert_eq!(profile.nodes()[2].node, "dummy");
+        assert_eq!(profile.caustic().len(), 2 * (NR_OF_SAMPLES_PER_SEGMENT + 1));
+        let waist = profile
+            .caustic()
+            .iter()
+            .map(|(_, w)| w.get::<meter>())
+            .fold(f64::INFINITY, f64::min);
+        assert_relative_eq!(waist, 0.1e-3, max_relative = 1e-12);
+    }
+    #[test]
+    fn focusing() {
+        let f = 0.1;
+        let s = 0.05;
+        let scenery = scenery(
+            ParaxialSurface::new("lens", millimeter!(100.0)).unwrap(),
+            Length::new::<meter>(s),
+            millimeter!(100.0),
+        );
+        let config = GaussianBeamConfig::default();
+        let profile = GaussianBeamAnalyzer::new(config).profile(&scenery).unwrap();
+        // focusing of a Gaussian beam by a thin lens (waist at the source)
+        let z_r = PI * 1.0e-3_f64.powi(2) / 1000.0e-9;
+        let denominator = (s - f).powi(2) + z_r.powi(2);
+        let s_prime = f + f * f * (s - f) / denominator;
+        let w0_prime = 1.0e-3 * f / denominator.sqrt();
+        let z_r_prime = PI * w0_prime.powi(2) / 1000.0e-9;
+        let w_det = w0_prime * (1.0 + ((0.1 - s_prime) / z_r_prime).powi(2)).sqrt();
+        let det = profile.nodes().last().unwrap();
+        assert_relative_eq!(det.radius.get::<meter>(), w_det, max_relative = 1e-9);
+        // beam radius is not changed by a thin lens
+        assert_relative_eq!(
+            profile.nodes()[2].radius.get::<meter>(),
+            profile.nodes()[3].radius.get::<meter>()
+        );
+    }
+    #[test]
+    fn thick_lens_and_mirror() {
+        let lens = Lens::default();
+        let n_lens = 1.5;
+        let m = lens.abcd_matrix(nanometer!(1000.0), 1.0).unwrap().unwrap();
+        assert_relative_eq!(m.determinant(), 1.0, epsilon = 1e-12);
+        let (r1, r2, d) = (0.5, -0.5, 0.01);
+        let inv_f =
+            (n_lens - 1.0) * (1.0 / r1 - 1.0 / r2 + (n_lens - 1.0) * d / (n_lens * r1 * r2));
+        assert_relative_eq!(m[(1, 0)], -inv_f, max_relative = 1e-12);
+        let mirror = ThinMirror::default()
+            .with_curvature(millimeter!(-200.0))
+            .unwrap();
+        let m = mirror
+            .abcd_matrix(nanometer!(1000.0), 1.0)
+            .unwrap()
+            .unwrap();
+        assert_relative_eq!(m[(1, 0)], -1.0 / 0.1);
+    }
+    #[test]
+    fn unsupported_node() {
+        let mut scenery = scenery(
+            CylindricLens::default(),
+            millimeter!(100.0),
+            millimeter!(100.0),
+        );
+        let analyzer = GaussianBeamAnalyzer::default();
+        assert!(analyzer.analyze(&mut scenery).is_err());
+    }
+    #[test]
+    fn no_source() {
+        let mut scenery = NodeGroup::default();
+        scenery.add_node(EnergyMeter::default()).unwrap();
+        let analyzer = GaussianBeamAnalyzer::default();
+        assert!(analyzer.analyze(&mut scenery).is_err());
+    }
+    #[test]
+    fn report() {
+        let mut scenery = scenery(
+            ParaxialSurface::new("lens", millimeter!(100.0)).unwrap(),
+            millimeter!(50.0),
+            millimeter!(100.0),
+        );
+        let analyzer = GaussianBeamAnalyzer::default();
+        analyzer.analyze(&mut scenery).unwrap();
+        assert!(analyzer.report(&scenery).is_ok());
+    }
+}
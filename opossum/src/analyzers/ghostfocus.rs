@@ -2,10 +2,15 @@
 #![warn(missing_docs)]
 use log::{info, warn};
 use nalgebra::{MatrixXx2, MatrixXx3, Vector3};
+use num::Zero;
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, hash_map::Values};
-use uom::si::{f64::Length, length::millimeter, radiant_exposure::joule_per_square_centimeter};
+use uom::si::{
+    f64::{Energy, Length},
+    length::millimeter,
+    radiant_exposure::joule_per_square_centimeter,
+};
 use uuid::Uuid;
 
 use crate::{
@@ -22,15 +27,21 @@ use crate::{
     },
     rays::Rays,
     reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
-    surface::hit_map::fluence_estimator::FluenceEstimator,
+    surface::hit_map::{
+        bounce_energy_decay::BounceEnergyDecay, fluence_estimator::FluenceEstimator,
+    },
 };
 
 use super::{Analyzer, AnalyzerType, RayTraceConfig, raytrace::AnalysisRayTrace};
-#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Configuration for performing a ghost focus analysis
 pub struct GhostFocusConfig {
     max_bounces: usize,
     fluence_estimator: FluenceEstimator,
+    #[serde(default = "Energy::zero")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    min_energy_per_ray: Energy,
 }
 
 impl GhostFocusConfig {
@@ -53,12 +64,36 @@ impl GhostFocusConfig {
     pub const fn set_fluence_estimator(&mut self, fluence_estimator: FluenceEstimator) {
         self.fluence_estimator = fluence_estimator;
     }
+    /// Returns the minimum energy per ray of this [`GhostFocusConfig`].
+    #[must_use]
+    pub const fn min_energy_per_ray(&self) -> Energy {
+        self.min_energy_per_ray
+    }
+    /// Sets the minimum energy per ray of this [`GhostFocusConfig`].
+    ///
+    /// Rays with an energy below this limit are removed from the ray bundles after each node. Since the energy of
+    /// parasitic reflections drops quickly with the number of bounces, this considerably reduces the number of rays to
+    /// be traced. A value of zero (default) disables the pruning.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given energy limit is negative or not finite.
+    pub fn set_min_energy_per_ray(&mut self, min_energy_per_ray: Energy) -> OpmResult<()> {
+        if !min_energy_per_ray.is_finite() || min_energy_per_ray.is_sign_negative() {
+            return Err(OpossumError::Analysis(
+                "minimum energy must be >=0.0 and finite".into(),
+            ));
+        }
+        self.min_energy_per_ray = min_energy_per_ray;
+        Ok(())
+    }
 }
 impl Default for GhostFocusConfig {
     fn default() -> Self {
         Self {
             max_bounces: 1,
             fluence_estimator: FluenceEstimator::Voronoi,
+            min_energy_per_ray: Energy::zero(),
         }
     }
 }
@@ -142,6 +177,27 @@ impl Analyzer for GhostFocusAnalyzer {
             let node_name = &node.name();
             let hit_maps = node.hit_maps();
             drop(node);
+            let mut decay_props = Properties::default();
+            let mut surface_names = hit_maps.keys().collect::<Vec<_>>();
+            surface_names.sort();
+            for surface_name in surface_names {
+                let decay = BounceEnergyDecay::from(&hit_maps[surface_name]);
+                if !decay.is_empty() {
+                    decay_props.create(
+                        surface_name,
+                        "energy hitting this surface per bounce level",
+                        decay.into(),
+                    )?;
+                }
+            }
+            if !decay_props.is_empty() {
+                analysis_report.add_node_report(NodeReport::new(
+                    "energy decay",
+                    &format!("Energy per bounce of node '{node_name}'"),
+                    &Uuid::new_v4().as_simple().to_string(),
+                    decay_props,
+                ));
+            }
             for hit_map in &hit_maps {
                 let critical_positions = hit_map.1.critical_fluences();
                 let node = node_ref
@@ -611,12 +667,15 @@ impl Plottable for GhostFocusHistory {
 #[cfg(test)]
 mod test_ghost_focus_config {
     use super::GhostFocusConfig;
-    use crate::surface::hit_map::fluence_estimator::FluenceEstimator;
+    use crate::{joule, surface::hit_map::fluence_estimator::FluenceEstimator};
+    use num::Zero;
+    use uom::si::f64::Energy;
     #[test]
     fn default() {
         let c = GhostFocusConfig::default();
         assert_eq!(c.max_bounces, 1);
         assert_eq!(c.fluence_estimator, FluenceEstimator::Voronoi);
+        assert_eq!(c.min_energy_per_ray, Energy::zero());
     }
     #[test]
     fn set_max_bounces() {
@@ -630,6 +689,15 @@ mod test_ghost_focus_config {
         c.set_fluence_estimator(FluenceEstimator::HelperRays);
         assert_eq!(c.fluence_estimator(), &FluenceEstimator::HelperRays);
     }
+    #[test]
+    fn set_min_energy_per_ray() {
+        let mut c = GhostFocusConfig::default();
+        assert!(c.set_min_energy_per_ray(joule!(-0.1)).is_err());
+        assert!(c.set_min_energy_per_ray(joule!(f64::NAN)).is_err());
+        assert!(c.set_min_energy_per_ray(joule!(f64::INFINITY)).is_err());
+        c.set_min_energy_per_ray(joule!(0.1)).unwrap();
+        assert_eq!(c.min_energy_per_ray(), joule!(0.1));
+    }
 }
 
 #[cfg(test)]
@@ -644,6 +712,8 @@ mod test_ghost_focus_analyzer {
         nodes::{Lens, NodeGroup, SpotDiagram, ThinMirror, round_collimated_ray_source},
         optic_node::{Alignable, OpticNode},
         optic_ports::PortType,
+        refractive_index::RefrIndexConst,
+        surface::hit_map::bounce_energy_decay::BounceEnergyDecay,
     };
     #[test]
     fn empty_report() {
@@ -652,6 +722,65 @@ mod test_ghost_focus_analyzer {
         analyzer.report(&scenery).unwrap();
     }
     #[test]
+    fn etalon_energy_decay() {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(1.0), joule!(1.), 1).unwrap())
+            .unwrap();
+        let mut etalon = Lens::new(
+            "etalon",
+            millimeter!(f64::INFINITY),
+            millimeter!(f64::NEG_INFINITY),
+            millimeter!(5.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        etalon
+            .set_coating(
+                &PortType::Input,
+                "input_1",
+                &CoatingType::ConstantR { reflectivity: 0.5 },
+            )
+            .unwrap();
+        etalon
+            .set_coating(
+                &PortType::Output,
+                "output_1",
+                &CoatingType::ConstantR { reflectivity: 0.5 },
+            )
+            .unwrap();
+        let i_e = scenery.add_node(etalon).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_e, "input_1", millimeter!(10.0))
+            .unwrap();
+        let mut config = GhostFocusConfig::default();
+        config.set_max_bounces(5);
+        let analyzer = GhostFocusAnalyzer::new(config);
+        analyzer.analyze(&mut scenery).unwrap();
+        let hit_maps = scenery
+            .node(i_e)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .hit_maps();
+        for surface in ["input_1", "output_1"] {
+            let decay = BounceEnergyDecay::from(&hit_maps[surface]);
+            let energies = decay
+                .energies()
+                .iter()
+                .map(|e| e.value)
+                .filter(|e| *e > 0.0)
+                .collect::<Vec<f64>>();
+            assert!(energies.len() > 2);
+            // energy stored in the etalon decreases with each round trip
+            for e in energies.windows(2) {
+                assert!(e[1] < e[0]);
+            }
+        }
+        analyzer.report(&scenery).unwrap();
+    }
+    #[test]
     #[ignore]
     fn report() {
         let mut scenery = NodeGroup::default();
@@ -6,25 +6,38 @@
 //! Fourier propagation could be performed. The result of an analysis run can be written to a JSON structure
 //! and / or exported as a PDF report.
 pub mod analyzable;
+pub mod caustic;
 pub mod energy;
+pub mod gaussian_beam;
 pub mod ghostfocus;
+pub mod progress;
 pub mod raytrace;
+pub mod system_transmission;
+pub mod trace_statistics;
+pub mod wavelength_sweep;
 
 use crate::{error::OpmResult, nodes::NodeGroup, reporting::analysis_report::AnalysisReport};
 pub use analyzable::Analyzable;
+pub use caustic::BeamCaustic;
+pub use gaussian_beam::GaussianBeamConfig;
 pub use ghostfocus::GhostFocusConfig;
+pub use progress::AnalysisProgress;
 pub use raytrace::RayTraceConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use strum::EnumIter;
 use strum::IntoEnumIterator;
+pub use system_transmission::SystemTransmissionConfig;
+pub use trace_statistics::TraceStatistics;
 use utoipa::ToSchema;
+pub use wavelength_sweep::WavelengthSweep;
 
 /// Type of analysis to be performed.
 ///
 /// While the individual analyzers are implemented as traits, this enum is necessary for serialization / desrialization.
 #[non_exhaustive]
 #[derive(EnumIter, PartialEq, Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum AnalyzerType {
     /// Simple energy flow analysis of an optical spectrum.
     ///
@@ -42,6 +55,18 @@ pub enum AnalyzerType {
     /// given number of bounces.
     #[schema(value_type=())]
     GhostFocus(GhostFocusConfig),
+    /// Spectral transmission analysis.
+    ///
+    /// This mode performs an energy flow analysis with a flat (white) spectrum emitted by all sources and determines the
+    /// spectral transmission of the system at a given detector.
+    #[schema(value_type=())]
+    SystemTransmission(SystemTransmissionConfig),
+    /// Gaussian beam analysis.
+    ///
+    /// This mode propagates a fundamental Gaussian beam through a scenery using paraxial ABCD matrices and determines
+    /// the beam radius and wavefront curvature at each node.
+    #[schema(value_type=())]
+    GaussianBeam(GaussianBeamConfig),
 }
 impl AnalyzerType {
     /// Returns the available analyzer types.
@@ -59,6 +84,8 @@ impl Display for AnalyzerType {
             Self::Energy => "Energy",
             Self::RayTrace(_) => "RayTracing",
             Self::GhostFocus(_) => "GhostFocus",
+            Self::SystemTransmission(_) => "SystemTransmission",
+            Self::GaussianBeam(_) => "GaussianBeam",
         };
         write!(f, "{msg}")
     }
@@ -77,6 +104,20 @@ mod test {
             format!("{}", AnalyzerType::GhostFocus(GhostFocusConfig::default())),
             "GhostFocus"
         );
+        assert_eq!(
+            format!(
+                "{}",
+                AnalyzerType::SystemTransmission(SystemTransmissionConfig::default())
+            ),
+            "SystemTransmission"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                AnalyzerType::GaussianBeam(GaussianBeamConfig::default())
+            ),
+            "GaussianBeam"
+        );
     }
     #[test]
     fn debug() {
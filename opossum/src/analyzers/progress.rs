@@ -0,0 +1,113 @@
+#![warn(missing_docs)]
+//! Progress reporting during an analysis
+//!
+//! Ray-based analyses ([`RayTrace`](super::AnalyzerType::RayTrace) and [`GhostFocus`](super::AnalyzerType::GhostFocus))
+//! report an [`AnalysisProgress`] before each node of a [`NodeGroup`](crate::nodes::NodeGroup) is analyzed. The progress is
+//! forwarded to a hook, which is installed for the duration of
+//! [`OpmDocument::analyze_with_progress`](crate::OpmDocument::analyze_with_progress). If no hook is installed, the
+//! progress information is not even assembled.
+use crate::utils::usize_to_f64;
+use std::cell::RefCell;
+
+/// Progress information of a running analysis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisProgress {
+    /// name of the node about to be analyzed
+    pub node: String,
+    /// index of this node in the (topologically sorted) group currently being analyzed
+    pub node_index: usize,
+    /// number of nodes of the group currently being analyzed
+    pub nr_of_nodes: usize,
+    /// current bounce level (pass) of a ghost focus analysis (always 0 for a ray tracing analysis)
+    pub bounce: usize,
+    /// number of (valid) rays arriving at this node
+    pub nr_of_rays: usize,
+}
+impl AnalysisProgress {
+    /// Returns the fraction of nodes of the current group, which have already been processed.
+    ///
+    /// **Note**: This is not the fraction of processed rays. All rays arriving at a node are propagated at once and the
+    /// total number of rays of an analysis is not known in advance (e.g. due to ray splitting or ghost reflections).
+    /// Hence, the number of processed nodes serves as progress measure. The number of rays currently being processed
+    /// is given by `nr_of_rays`.
+    #[must_use]
+    pub fn node_fraction(&self) -> f64 {
+        if self.nr_of_nodes == 0 {
+            return 1.0;
+        }
+        usize_to_f64(self.node_index) / usize_to_f64(self.nr_of_nodes)
+    }
+}
+
+type ProgressHook = Box<dyn Fn(&AnalysisProgress)>;
+
+thread_local! {
+    static PROGRESS_HOOK: RefCell<Option<ProgressHook>> = const { RefCell::new(None) };
+}
+
+/// Removes the installed hook when dropped (also during unwinding).
+struct HookGuard;
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        PROGRESS_HOOK.with(|hook| hook.borrow_mut().take());
+    }
+}
+
+/// Run `f` with the given progress hook installed for the current thread.
+pub(crate) fn with_progress_hook<R>(hook: ProgressHook, f: impl FnOnce() -> R) -> R {
+    PROGRESS_HOOK.with(|h| *h.borrow_mut() = Some(hook));
+    let _guard = HookGuard;
+    f()
+}
+
+/// Report the progress assembled by `progress` to the installed hook.
+///
+/// `progress` is only evaluated if a hook is installed.
+pub(crate) fn report_progress(progress: impl FnOnce() -> AnalysisProgress) {
+    PROGRESS_HOOK.with(|hook| {
+        if let Some(hook) = hook.borrow().as_ref() {
+            hook(&progress());
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::rc::Rc;
+
+    fn progress(node_index: usize, nr_of_nodes: usize) -> AnalysisProgress {
+        AnalysisProgress {
+            node: "test".into(),
+            node_index,
+            nr_of_nodes,
+            bounce: 0,
+            nr_of_rays: 0,
+        }
+    }
+    #[test]
+    fn node_fraction() {
+        assert_abs_diff_eq!(progress(0, 4).node_fraction(), 0.0);
+        assert_abs_diff_eq!(progress(2, 4).node_fraction(), 0.5);
+        assert_abs_diff_eq!(progress(0, 0).node_fraction(), 1.0);
+    }
+    #[test]
+    fn hook() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        report_progress(|| panic!("progress must not be assembled without hook"));
+        let calls_clone = calls.clone();
+        let result = with_progress_hook(
+            Box::new(move |p| calls_clone.borrow_mut().push(p.node_index)),
+            || {
+                report_progress(|| progress(0, 2));
+                report_progress(|| progress(1, 2));
+                42
+            },
+        );
+        assert_eq!(result, 42);
+        assert_eq!(*calls.borrow(), [0, 1]);
+        // hook is removed afterwards
+        report_progress(|| panic!("progress must not be assembled without hook"));
+    }
+}
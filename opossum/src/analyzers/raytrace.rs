@@ -10,10 +10,10 @@ use crate::{
     optic_node::OpticNode,
     optic_ports::PortType,
     picojoule,
-    properties::Proptype,
+    properties::{Properties, Proptype},
     rays::Rays,
     refractive_index::RefractiveIndexType,
-    reporting::analysis_report::AnalysisReport,
+    reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
 };
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
@@ -44,12 +44,94 @@ impl Analyzer for RayTracingAnalyzer {
         AnalysisRayTrace::calc_node_positions(scenery, LightResult::default(), &self.config)?;
         scenery.reset_data();
         info!("Performing ray tracing analysis of scenery{scenery_name}.");
-        AnalysisRayTrace::analyze(scenery, LightResult::default(), &self.config)?;
+        let light_result =
+            AnalysisRayTrace::analyze(scenery, LightResult::default(), &self.config)?;
+        // light on the output ports of the scenery itself also leaves the scenery
+        for light_data in light_result.values() {
+            if let LightData::Geometric(rays) = light_data {
+                scenery.trace_statistics_mut().add_terminated(rays);
+                scenery.add_to_beam_caustic(rays);
+            }
+        }
         Ok(())
     }
     fn report(&self, scenery: &NodeGroup) -> OpmResult<AnalysisReport> {
         let mut report = scenery.toplevel_report()?;
-        report.set_analysis_type("Ray Tracing Analysis");
+        if self.config.chief_ray_only() {
+            report.set_analysis_type("Ray Tracing Analysis (chief ray only)");
+        } else {
+            report.set_analysis_type("Ray Tracing Analysis");
+        }
+        report.set_trace_statistics(scenery.trace_statistics().clone());
+        if let Some(beam_caustic) = scenery.beam_caustic() {
+            let mut props = Properties::default();
+            if let Some((distance, radius)) = beam_caustic.waist() {
+                props.create(
+                    "waist position",
+                    "propagation distance of the narrowest beam",
+                    distance.into(),
+                )?;
+                props.create(
+                    "waist radius",
+                    "rms radius of the narrowest beam",
+                    radius.into(),
+                )?;
+            }
+            props.create(
+                "Caustic",
+                "rms beam radius vs. propagation distance",
+                beam_caustic.clone().into(),
+            )?;
+            let mut node_report = NodeReport::new(
+                "beam caustic",
+                "Beam caustic",
+                &scenery.node_attr().uuid().as_simple().to_string(),
+                props,
+            );
+            node_report.set_show_item(true);
+            report.add_node_report(node_report);
+        }
+        let clipping = scenery.aperture_clipping()?;
+        if !clipping.is_empty() {
+            let scenery_uuid = scenery.node_attr().uuid().as_simple().to_string();
+            let mut props = Properties::default();
+            for (node_name, port_name, surf_clipping) in clipping {
+                let mut prop_name = format!("{node_name} ({port_name})");
+                let mut idx = 1;
+                while props.contains(&prop_name) {
+                    idx += 1;
+                    prop_name = format!("{node_name} ({port_name}) #{idx}");
+                }
+                let mut surf_props = Properties::default();
+                surf_props.create(
+                    "incident energy",
+                    "energy incident on the aperture",
+                    surf_clipping.incident().into(),
+                )?;
+                surf_props.create(
+                    "transmitted energy",
+                    "energy transmitted by the aperture",
+                    surf_clipping.transmitted().into(),
+                )?;
+                surf_props.create(
+                    "clipped fraction",
+                    "fraction of the incident energy clipped by the aperture",
+                    surf_clipping.clipped_fraction().unwrap_or_default().into(),
+                )?;
+                props.create(
+                    &prop_name,
+                    "aperture clipping of the surface",
+                    NodeReport::new("surface", &prop_name, &scenery_uuid, surf_props).into(),
+                )?;
+            }
+            let node_report = NodeReport::new(
+                "aperture clipping",
+                "Aperture clipping",
+                &scenery_uuid,
+                props,
+            );
+            report.add_node_report(node_report);
+        }
         Ok(report)
     }
 }
@@ -111,7 +193,9 @@ pub trait AnalysisRayTrace: OpticNode {
             )));
         };
         let missed_surface_strategy = match analyzer_type {
-            AnalyzerType::Energy => &MissedSurfaceStrategy::Stop,
+            AnalyzerType::Energy
+            | AnalyzerType::SystemTransmission(_)
+            | AnalyzerType::GaussianBeam(_) => &MissedSurfaceStrategy::Stop,
             AnalyzerType::RayTrace(ray_trace_config) => &ray_trace_config.missed_surface_strategy,
             AnalyzerType::GhostFocus(_) => &MissedSurfaceStrategy::Ignore,
         };
@@ -128,7 +212,7 @@ pub trait AnalysisRayTrace: OpticNode {
                 surf.add_to_rays_cache(reflected, backward);
             }
 
-            rays.apodize(surf.aperture(), iso)?;
+            surf.apodize_rays(rays, iso)?;
             if let AnalyzerType::RayTrace(config) = analyzer_type {
                 rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
             }
@@ -160,14 +244,16 @@ pub trait AnalysisRayTrace: OpticNode {
             return Err(OpossumError::Analysis("no surface found".into()));
         };
         let missed_surface_strategy = match analyzer_type {
-            AnalyzerType::Energy => &MissedSurfaceStrategy::Stop,
+            AnalyzerType::Energy
+            | AnalyzerType::SystemTransmission(_)
+            | AnalyzerType::GaussianBeam(_) => &MissedSurfaceStrategy::Stop,
             AnalyzerType::RayTrace(ray_trace_config) => &ray_trace_config.missed_surface_strategy,
             AnalyzerType::GhostFocus(_) => &MissedSurfaceStrategy::Ignore,
         };
         for rays in &mut *rays_bundle {
             rays.refract_on_surface(surf, None, true, missed_surface_strategy)?;
 
-            apodized |= rays.apodize(surf.aperture(), &iso)?;
+            apodized |= surf.apodize_rays(rays, &iso)?;
             if apodized {
                 warn!(
                     "Rays have been apodized at input aperture of {optic_name}. Results might not be accurate."
@@ -225,14 +311,16 @@ pub trait AnalysisRayTrace: OpticNode {
             return Ok(LightResult::default());
         };
         if let LightData::Geometric(rays) = data {
+            let mut rays_bundle = vec![rays.clone()];
             self.pass_through_detector_surface(
                 in_port,
-                &mut vec![rays.clone()],
+                &mut rays_bundle,
                 &AnalyzerType::RayTrace(config.clone()),
             )?;
+            // only forward the light of this analysis, even if the detector has accumulated light of previous ones
             Ok(LightResult::from([(
                 out_port.into(),
-                self.get_light_data_mut().unwrap().clone(),
+                LightData::Geometric(rays_bundle.swap_remove(0)),
             )]))
         } else {
             Ok(LightResult::from([(out_port.into(), data.clone())]))
@@ -248,6 +336,9 @@ pub trait AnalysisRayTrace: OpticNode {
     fn set_light_data(&mut self, _ld: LightData) {}
 
     ///returns the necessary node attributes for ray tracing
+    ///
+    /// Temperature-dependent refractive index models without an explicitly set operating temperature are evaluated at the
+    /// ambient temperature of the global configuration.
     /// # Errors
     /// This function errors if the node attributes: Isometry, Refractive Index or Center Thickness cannot be read,
     fn get_node_attributes_ray_trace(
@@ -273,12 +364,17 @@ pub trait AnalysisRayTrace: OpticNode {
             degree!(0.)
         };
 
-        Ok((index_model.clone(), *center_thickness, angle))
+        Ok((
+            index_model.at_ambient_temperature(self.ambient_temperature()),
+            *center_thickness,
+            angle,
+        ))
     }
 }
 
 /// Strategy to use if a [`Ray`](crate::ray::Ray) misses a surface
 #[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum MissedSurfaceStrategy {
     /// The [`Ray`](crate::ray::Ray) it is set as invalid and does no longer propagate.
     Stop,
@@ -292,19 +388,43 @@ impl Default for MissedSurfaceStrategy {
     }
 }
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Configuration data for a rays tracing analysis.
 ///
 /// The config contains the following info
 // ///  - ray tracing mode (see [`RayTracingMode`])
-///   - minimum energy / ray
+///   - minimum energy / ray (and whether rays below this limit are removed between nodes)
 ///   - maximum number of bounces (reflections) / ray
 ///   - maximum number of refractions / ray
+///   - (optional) wavelength used for aligning the optical axis
+///   - chief-ray-only mode (for fast layout checks)
+///   - (optional) seed for the random number generation of sources
+///   - number of round trips through groups with a defined feedback path
+///   - (optional) sampling step for recording the beam caustic
 pub struct RayTraceConfig {
     //mode: RayTracingMode,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     min_energy_per_ray: Energy,
     max_number_of_bounces: usize,
     max_number_of_refractions: usize,
     missed_surface_strategy: MissedSurfaceStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
+    alignment_wavelength: Option<Length>,
+    #[serde(default)]
+    chief_ray_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(default = "default_round_trips")]
+    round_trips: usize,
+    #[serde(default)]
+    prune_low_energy_rays: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
+    caustic_step: Option<Length>,
+}
+const fn default_round_trips() -> usize {
+    1
 }
 impl Default for RayTraceConfig {
     /// Create a default config for a ray tracing analysis with the following parameters:
@@ -312,12 +432,24 @@ impl Default for RayTraceConfig {
     ///   - maximum number of bounces / ray: `1000`
     ///   - maximum number of refractions / ray: `1000`
     ///   - missed surface strategy: ray is stopped
+    ///   - alignment wavelength: not set (defined by the source)
+    ///   - chief ray only: `false`
+    ///   - seed: not set (non-reproducible random sources)
+    ///   - round trips: `1` (single pass)
+    ///   - pruning of low-energy rays: `false`
+    ///   - caustic sampling step: not set (no beam caustic recorded)
     fn default() -> Self {
         Self {
             min_energy_per_ray: picojoule!(1.0),
             max_number_of_bounces: 1000,
             max_number_of_refractions: 1000,
             missed_surface_strategy: MissedSurfaceStrategy::default(),
+            alignment_wavelength: None,
+            chief_ray_only: false,
+            seed: None,
+            round_trips: default_round_trips(),
+            prune_low_energy_rays: false,
+            caustic_step: None,
         }
     }
 }
@@ -347,6 +479,19 @@ impl RayTraceConfig {
         self.min_energy_per_ray = min_energy_per_ray;
         Ok(())
     }
+    /// Returns `true` if rays below the minimum energy per ray are removed from the ray bundles between nodes.
+    #[must_use]
+    pub const fn prune_low_energy_rays(&self) -> bool {
+        self.prune_low_energy_rays
+    }
+    /// Sets the pruning of low-energy rays of this [`RayTraceConfig`].
+    ///
+    /// If set, all rays with an energy below [`min_energy_per_ray`](RayTraceConfig::min_energy_per_ray) are completely
+    /// removed from the ray bundles after each node instead of only being marked as invalid. This speeds up analyses
+    /// with many lossy surfaces. The pruned rays are still considered in the [`TraceStatistics`](super::TraceStatistics).
+    pub const fn set_prune_low_energy_rays(&mut self, prune_low_energy_rays: bool) {
+        self.prune_low_energy_rays = prune_low_energy_rays;
+    }
     /// Returns the maximum number of bounces of this [`RayTraceConfig`].
     #[must_use]
     pub const fn max_number_of_bounces(&self) -> usize {
@@ -377,22 +522,148 @@ impl RayTraceConfig {
     ) {
         self.missed_surface_strategy = missed_surface_strategy;
     }
+    /// Returns the alignment wavelength of this [`RayTraceConfig`].
+    ///
+    /// If set, this wavelength overrides the alignment wavelength of all sources while calculating the node positions.
+    #[must_use]
+    pub const fn alignment_wavelength(&self) -> Option<Length> {
+        self.alignment_wavelength
+    }
+    /// Sets the alignment wavelength of this [`RayTraceConfig`].
+    ///
+    /// The optical axis is then calculated using a ray of this wavelength, independent of the spectrum of the sources. This is
+    /// useful, e.g., for aligning dispersive setups at their design wavelength. Setting `None` restores the default behaviour.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given wavelength is not positive and finite.
+    pub fn set_alignment_wavelength(&mut self, wavelength: Option<Length>) -> OpmResult<()> {
+        if let Some(wvl) = wavelength
+            && (wvl.is_sign_negative() || !wvl.is_normal())
+        {
+            return Err(OpossumError::Analysis(
+                "alignment wavelength must be positive and finite".into(),
+            ));
+        }
+        self.alignment_wavelength = wavelength;
+        Ok(())
+    }
+    /// Returns `true` if only the chief ray should be traced.
+    #[must_use]
+    pub const fn chief_ray_only(&self) -> bool {
+        self.chief_ray_only
+    }
+    /// Sets the chief-ray-only mode of this [`RayTraceConfig`].
+    ///
+    /// If set, all sources only emit their chief ray (the ray along the optical axis, see
+    /// [`Rays::get_optical_axis_ray`]) instead of the full ray bundle. This allows for very fast sanity checks of
+    /// a layout, since detectors then simply show the landing position of the chief ray.
+    pub const fn set_chief_ray_only(&mut self, chief_ray_only: bool) {
+        self.chief_ray_only = chief_ray_only;
+    }
+    /// Returns the random seed of this [`RayTraceConfig`].
+    #[must_use]
+    pub const fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    /// Sets the random seed of this [`RayTraceConfig`].
+    ///
+    /// If set, all sources generate their random ray distributions (e.g. [`Random`](crate::position_distributions::Random))
+    /// using a random number generator initialized with this seed. Hence, two analyses with the same seed produce
+    /// identical [`Rays`]. Since the rays of each source are sampled sequentially, this guarantee holds regardless of the
+    /// number of threads used. Note, that all sources use the same seed. Setting `None` (default) restores the
+    /// non-reproducible behaviour.
+    pub const fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+    /// Returns the number of round trips of this [`RayTraceConfig`].
+    #[must_use]
+    pub const fn round_trips(&self) -> usize {
+        self.round_trips
+    }
+    /// Sets the number of round trips of this [`RayTraceConfig`].
+    ///
+    /// For each [`NodeGroup`] with a defined feedback path (see [`NodeGroup::set_round_trip_ports`]), the light leaving the
+    /// feedback output port is re-injected into the feedback input port until the group has been traversed the given number
+    /// of times. This allows for modelling closed beam paths such as laser cavities. Groups without a feedback path are
+    /// always traversed only once.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given number of round trips is zero.
+    pub fn set_round_trips(&mut self, round_trips: usize) -> OpmResult<()> {
+        if round_trips == 0 {
+            return Err(OpossumError::Analysis(
+                "number of round trips must be >= 1".into(),
+            ));
+        }
+        self.round_trips = round_trips;
+        Ok(())
+    }
+    /// Returns the sampling step of the beam caustic of this [`RayTraceConfig`].
+    #[must_use]
+    pub const fn caustic_step(&self) -> Option<Length> {
+        self.caustic_step
+    }
+    /// Sets the sampling step of the beam caustic of this [`RayTraceConfig`].
+    ///
+    /// If set, the RMS radius of all rays leaving the scenery is recorded at regular intervals of the given propagation
+    /// distance along the full beam path (see [`BeamCaustic`](super::BeamCaustic)). The resulting caustic is added to
+    /// the analysis report. Setting `None` (default) disables the recording.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given step is not positive and finite.
+    pub fn set_caustic_step(&mut self, caustic_step: Option<Length>) -> OpmResult<()> {
+        if let Some(step) = caustic_step
+            && (step.is_sign_negative() || !step.is_normal())
+        {
+            return Err(OpossumError::Analysis(
+                "caustic sampling step must be positive and finite".into(),
+            ));
+        }
+        self.caustic_step = caustic_step;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        joule, millimeter,
-        nodes::{ParaxialSurface, round_collimated_ray_source},
+        aperture::{Aperture, CircleConfig},
+        joule, millimeter, nanometer,
+        nodes::{EnergyMeter, ParaxialSurface, round_collimated_ray_source},
+        ray::TerminationReason,
         utils::test_helper::test_helper::check_logs,
     };
+    use approx::assert_relative_eq;
+    use uom::si::length::millimeter;
     #[test]
     fn config_default() {
         let rt_conf = RayTraceConfig::default();
         assert_eq!(rt_conf.max_number_of_bounces(), 1000);
         assert_eq!(rt_conf.max_number_of_refractions(), 1000);
         assert_eq!(rt_conf.min_energy_per_ray(), picojoule!(1.0));
+        assert_eq!(rt_conf.seed(), None);
+        assert_eq!(rt_conf.round_trips(), 1);
+        assert!(!rt_conf.prune_low_energy_rays());
+        assert_eq!(rt_conf.caustic_step(), None);
+    }
+    #[test]
+    fn config_set_round_trips() {
+        let mut rt_conf = RayTraceConfig::default();
+        assert!(rt_conf.set_round_trips(0).is_err());
+        rt_conf.set_round_trips(5).unwrap();
+        assert_eq!(rt_conf.round_trips(), 5);
+    }
+    #[test]
+    fn config_set_seed() {
+        let mut rt_conf = RayTraceConfig::default();
+        rt_conf.set_seed(Some(42));
+        assert_eq!(rt_conf.seed(), Some(42));
+        rt_conf.set_seed(None);
+        assert_eq!(rt_conf.seed(), None);
     }
     #[test]
     fn config_set_min_energy() {
@@ -421,10 +692,41 @@ mod test {
         assert_eq!(rt_conf.max_number_of_refractions, 456);
     }
     #[test]
+    fn config_set_alignment_wavelength() {
+        let mut rt_conf = RayTraceConfig::default();
+        assert_eq!(rt_conf.alignment_wavelength(), None);
+        assert!(
+            rt_conf
+                .set_alignment_wavelength(Some(nanometer!(0.0)))
+                .is_err()
+        );
+        assert!(
+            rt_conf
+                .set_alignment_wavelength(Some(nanometer!(-500.0)))
+                .is_err()
+        );
+        assert!(
+            rt_conf
+                .set_alignment_wavelength(Some(nanometer!(f64::INFINITY)))
+                .is_err()
+        );
+        assert!(
+            rt_conf
+                .set_alignment_wavelength(Some(nanometer!(f64::NAN)))
+                .is_err()
+        );
+        rt_conf
+            .set_alignment_wavelength(Some(nanometer!(633.0)))
+            .unwrap();
+        assert_eq!(rt_conf.alignment_wavelength(), Some(nanometer!(633.0)));
+        rt_conf.set_alignment_wavelength(None).unwrap();
+        assert_eq!(rt_conf.alignment_wavelength(), None);
+    }
+    #[test]
     fn config_debug() {
         assert_eq!(
             format!("{:?}", RayTraceConfig::default()),
-            "RayTraceConfig { min_energy_per_ray: 1e-12 m^2 kg^1 s^-2, max_number_of_bounces: 1000, max_number_of_refractions: 1000, missed_surface_strategy: Stop }"
+            "RayTraceConfig { min_energy_per_ray: 1e-12 m^2 kg^1 s^-2, max_number_of_bounces: 1000, max_number_of_refractions: 1000, missed_surface_strategy: Stop, alignment_wavelength: None, chief_ray_only: false, seed: None, round_trips: 1, prune_low_energy_rays: false, caustic_step: None }"
         );
     }
     #[test]
@@ -466,6 +768,225 @@ mod test {
         analyzer.report(&scenery).unwrap();
     }
     #[test]
+    fn analyze_chief_ray_only() {
+        let mut group = NodeGroup::default();
+        let i_src = group
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(5.0), 3).unwrap())
+            .unwrap();
+        let i_l1 = group
+            .add_node(ParaxialSurface::new("f=100", millimeter!(100.0)).unwrap())
+            .unwrap();
+        let i_em = group.add_node(EnergyMeter::default()).unwrap();
+        group
+            .connect_nodes(i_src, "output_1", i_l1, "input_1", millimeter!(50.0))
+            .unwrap();
+        group
+            .connect_nodes(i_l1, "output_1", i_em, "input_1", millimeter!(50.0))
+            .unwrap();
+        let mut config = RayTraceConfig::default();
+        config.set_chief_ray_only(true);
+        let analyzer = RayTracingAnalyzer::new(config);
+        analyzer.analyze(&mut group).unwrap();
+        let node = group.node(i_em).unwrap();
+        let report = node.optical_ref.lock().unwrap().node_report("").unwrap();
+        let Proptype::Energy(energy) = report.properties().get("Energy").unwrap() else {
+            panic!("wrong proptype")
+        };
+        // only a single chief ray (with an energy of 1 J) is traced
+        assert_eq!(*energy, joule!(1.0));
+    }
+    fn paraxial_test_scenery(nr_of_lenses: usize) -> NodeGroup {
+        let mut group = NodeGroup::default();
+        let mut prev = group
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        for _ in 0..nr_of_lenses {
+            let lens = group
+                .add_node(ParaxialSurface::new("f=100", millimeter!(100.0)).unwrap())
+                .unwrap();
+            group
+                .connect_nodes(prev, "output_1", lens, "input_1", millimeter!(50.0))
+                .unwrap();
+            prev = lens;
+        }
+        let i_em = group.add_node(EnergyMeter::default()).unwrap();
+        group
+            .connect_nodes(prev, "output_1", i_em, "input_1", millimeter!(50.0))
+            .unwrap();
+        group
+    }
+    #[test]
+    fn trace_statistics() {
+        let mut group = paraxial_test_scenery(1);
+        let analyzer = RayTracingAnalyzer::default();
+        analyzer.analyze(&mut group).unwrap();
+        let report = analyzer.report(&group).unwrap();
+        let stats = report.trace_statistics().unwrap();
+        let nr_of_rays = stats.rays_launched();
+        assert!(nr_of_rays > 0);
+        assert_eq!(stats.rays_arrived(), nr_of_rays);
+        assert_eq!(stats.rays_lost(), 0);
+        assert_eq!(stats.surface_interactions(), nr_of_rays);
+        assert_eq!(stats.max_bounces(), 0);
+        assert_relative_eq!(stats.energy_in().value, 1.0, max_relative = 1e-12);
+        assert_relative_eq!(stats.energy_out().value, 1.0, max_relative = 1e-12);
+    }
+    #[test]
+    fn trace_statistics_lost_rays() {
+        let mut group = paraxial_test_scenery(2);
+        let mut config = RayTraceConfig::default();
+        config.set_max_number_of_refractions(1);
+        let analyzer = RayTracingAnalyzer::new(config);
+        analyzer.analyze(&mut group).unwrap();
+        let stats = group.trace_statistics();
+        assert_eq!(stats.rays_arrived(), 0);
+        assert_eq!(stats.rays_lost(), stats.rays_launched());
+        assert_eq!(
+            stats.nr_of_terminations(TerminationReason::MaxRefractions),
+            stats.rays_launched()
+        );
+        assert_relative_eq!(stats.energy_out().value, 0.0);
+    }
+    #[test]
+    fn aperture_clipping() {
+        let mut group = paraxial_test_scenery(1);
+        let analyzer = RayTracingAnalyzer::default();
+        analyzer.analyze(&mut group).unwrap();
+        assert!(group.aperture_clipping().unwrap().is_empty());
+        let i_lens = group
+            .nodes()
+            .iter()
+            .find(|n| n.optical_ref.lock().unwrap().name() == "f=100")
+            .unwrap()
+            .uuid();
+        group
+            .node(i_lens)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .set_aperture(
+                &PortType::Input,
+                "input_1",
+                &Aperture::BinaryCircle(
+                    CircleConfig::new(millimeter!(5.0), millimeter!(0.0, 0.0)).unwrap(),
+                ),
+            )
+            .unwrap();
+        analyzer.analyze(&mut group).unwrap();
+        let clipping = group.aperture_clipping().unwrap();
+        assert_eq!(clipping.len(), 1);
+        let (node_name, port_name, surf_clipping) = &clipping[0];
+        assert_eq!(node_name, "f=100");
+        assert_eq!(port_name, "input_1");
+        assert_relative_eq!(surf_clipping.incident().value, 1.0, max_relative = 1e-12);
+        let fraction = surf_clipping.clipped_fraction().unwrap();
+        assert!(fraction > 0.0 && fraction < 1.0);
+        let markdown = analyzer.report(&group).unwrap().to_markdown(1);
+        assert!(markdown.contains("f=100 (input_1) (surface)"));
+        assert!(markdown.contains("| incident energy |"));
+        assert!(markdown.contains("| transmitted energy |"));
+        assert!(markdown.contains("| clipped fraction |"));
+    }
+    #[test]
+    fn trace_statistics_subgroup() {
+        let mut sub_group = NodeGroup::default();
+        let i_l1 = sub_group
+            .add_node(ParaxialSurface::new("f=100", millimeter!(100.0)).unwrap())
+            .unwrap();
+        let i_em = sub_group.add_node(EnergyMeter::default()).unwrap();
+        sub_group
+            .connect_nodes(i_l1, "output_1", i_em, "input_1", millimeter!(50.0))
+            .unwrap();
+        sub_group
+            .map_input_port(i_l1, "input_1", "input_1")
+            .unwrap();
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let i_sub = scenery.add_node(sub_group).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_sub, "input_1", millimeter!(50.0))
+            .unwrap();
+        let analyzer = RayTracingAnalyzer::default();
+        analyzer.analyze(&mut scenery).unwrap();
+        let stats = scenery.trace_statistics();
+        assert!(stats.rays_launched() > 0);
+        assert_eq!(stats.rays_arrived(), stats.rays_launched());
+        assert_eq!(stats.rays_lost(), 0);
+        assert_eq!(stats.surface_interactions(), stats.rays_launched());
+    }
+    #[test]
+    fn config_set_caustic_step() {
+        let mut rt_conf = RayTraceConfig::default();
+        assert!(rt_conf.set_caustic_step(Some(millimeter!(0.0))).is_err());
+        assert!(rt_conf.set_caustic_step(Some(millimeter!(-1.0))).is_err());
+        assert!(
+            rt_conf
+                .set_caustic_step(Some(millimeter!(f64::NAN)))
+                .is_err()
+        );
+        rt_conf.set_caustic_step(Some(millimeter!(1.0))).unwrap();
+        assert_eq!(rt_conf.caustic_step(), Some(millimeter!(1.0)));
+        rt_conf.set_caustic_step(None).unwrap();
+        assert_eq!(rt_conf.caustic_step(), None);
+    }
+    fn caustic_test_scenery(focal_length: Length) -> NodeGroup {
+        let mut group = NodeGroup::default();
+        let i_src = group
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let i_l1 = group
+            .add_node(ParaxialSurface::new("lens", focal_length).unwrap())
+            .unwrap();
+        let i_em = group.add_node(EnergyMeter::default()).unwrap();
+        group
+            .connect_nodes(i_src, "output_1", i_l1, "input_1", millimeter!(50.0))
+            .unwrap();
+        group
+            .connect_nodes(i_l1, "output_1", i_em, "input_1", millimeter!(200.0))
+            .unwrap();
+        group
+    }
+    #[test]
+    fn beam_caustic() {
+        let mut group = caustic_test_scenery(millimeter!(100.0));
+        let analyzer = RayTracingAnalyzer::default();
+        analyzer.analyze(&mut group).unwrap();
+        assert!(group.beam_caustic().is_none());
+        let mut config = RayTraceConfig::default();
+        config.set_caustic_step(Some(millimeter!(5.0))).unwrap();
+        let analyzer = RayTracingAnalyzer::new(config);
+        analyzer.analyze(&mut group).unwrap();
+        let caustic = group.beam_caustic().unwrap();
+        let radii = caustic.radii();
+        assert!(radii.len() > 40);
+        // collimated up to the lens
+        assert_relative_eq!(radii[0].1.value, radii[10].1.value, max_relative = 1e-9);
+        // converging to the focus 100 mm behind the lens
+        let (position, radius) = caustic.waist().unwrap();
+        assert_relative_eq!(position.get::<millimeter>(), 150.0, max_relative = 0.05);
+        assert!(radius < radii[0].1 / 10.0);
+        let report = analyzer.report(&group).unwrap();
+        assert!(report.trace_statistics().is_some());
+    }
+    #[test]
+    fn beam_caustic_diverging() {
+        let mut group = caustic_test_scenery(millimeter!(-100.0));
+        let mut config = RayTraceConfig::default();
+        config.set_caustic_step(Some(millimeter!(10.0))).unwrap();
+        let analyzer = RayTracingAnalyzer::new(config);
+        analyzer.analyze(&mut group).unwrap();
+        let caustic = group.beam_caustic().unwrap();
+        let radii = caustic.radii();
+        let r_0 = radii[0].1.value;
+        // the beam radius grows linearly behind the lens
+        assert_relative_eq!(radii[15].1.value, 2.0 * r_0, max_relative = 1e-2);
+        assert_relative_eq!(radii[20].1.value, 2.5 * r_0, max_relative = 1e-2);
+        assert_eq!(caustic.waist().unwrap().0, millimeter!(0.0));
+    }
+    #[test]
     #[ignore]
     fn integration_test() {
         // simulate simple system for integration test
@@ -0,0 +1,486 @@
+#![warn(missing_docs)]
+//! Analyzer for the spectral transmission of an optical system.
+//!
+//! This analyzer replaces the light of all sources of a scenery by a flat (white) spectrum, performs an energy flow
+//! analysis and compares the spectrum arriving at a given detector with the launched spectrum. The resulting
+//! transmission `T(λ) = out / in` is reported as a plot.
+use super::{Analyzer, energy::AnalysisEnergy};
+use crate::{
+    error::{OpmResult, OpossumError},
+    light_result::LightResult,
+    lightdata::{
+        LightData, energy_data_builder::EnergyDataBuilder, light_data_builder::LightDataBuilder,
+    },
+    nanometer,
+    nodes::NodeGroup,
+    optic_node::OpticNode,
+    plottable::{PlotArgs, PlotParameters, PlotSeries, PlotType, Plottable},
+    properties::{Properties, Proptype},
+    reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
+    spectrum::Spectrum,
+    utils::usize_to_f64,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use uom::si::f64::Length;
+use uuid::Uuid;
+
+/// Configuration for performing a system transmission analysis.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct SystemTransmissionConfig {
+    detector: Option<Uuid>,
+    #[cfg_attr(feature = "json_schema", schemars(with = "std::ops::Range<f64>"))]
+    range: Range<Length>,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    resolution: Length,
+}
+impl SystemTransmissionConfig {
+    /// Creates a new [`SystemTransmissionConfig`].
+    ///
+    /// The transmission is determined at the detector node with the given [`Uuid`] for the given wavelength range and
+    /// resolution.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the wavelength range or the resolution is invalid (see [`Spectrum::new`]).
+    pub fn new(detector: Uuid, range: Range<Length>, resolution: Length) -> OpmResult<Self> {
+        Spectrum::new(range.clone(), resolution)?;
+        Ok(Self {
+            detector: Some(detector),
+            range,
+            resolution,
+        })
+    }
+    /// Returns the [`Uuid`] of the detector node of this [`SystemTransmissionConfig`].
+    ///
+    /// This function returns `None` if no detector has been set (e.g. for a default configuration).
+    #[must_use]
+    pub const fn detector(&self) -> Option<Uuid> {
+        self.detector
+    }
+    /// Sets the [`Uuid`] of the detector node of this [`SystemTransmissionConfig`].
+    pub const fn set_detector(&mut self, detector: Uuid) {
+        self.detector = Some(detector);
+    }
+    /// Returns the [`Uuid`] of the detector node or an error if no detector has been set.
+    fn detector_or_err(&self) -> OpmResult<Uuid> {
+        self.detector.ok_or_else(|| {
+            OpossumError::Analysis(
+                "no detector node specified for the system transmission analysis".into(),
+            )
+        })
+    }
+    /// Returns the wavelength range of this [`SystemTransmissionConfig`].
+    #[must_use]
+    pub fn range(&self) -> Range<Length> {
+        self.range.clone()
+    }
+    /// Returns the wavelength resolution of this [`SystemTransmissionConfig`].
+    #[must_use]
+    pub const fn resolution(&self) -> Length {
+        self.resolution
+    }
+    /// Returns the flat (white) spectrum launched by all sources during the analysis.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the wavelength range or the resolution is invalid.
+    pub fn flat_spectrum(&self) -> OpmResult<Spectrum> {
+        let mut spectrum = Spectrum::new(self.range.clone(), self.resolution)?;
+        spectrum.map_mut(|(lambda, _)| (*lambda, 1.0));
+        Ok(spectrum)
+    }
+}
+impl Default for SystemTransmissionConfig {
+    fn default() -> Self {
+        Self {
+            detector: None,
+            range: nanometer!(400.0)..nanometer!(1100.0),
+            resolution: nanometer!(1.0),
+        }
+    }
+}
+/// Spectral transmission of an optical system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemTransmission {
+    transmission: Spectrum,
+}
+impl SystemTransmission {
+    /// Calculate the [`SystemTransmission`] from the spectrum launched into the system and the spectrum arriving at the
+    /// detector.
+    #[must_use]
+    pub fn new(launched: &Spectrum, detected: &Spectrum) -> Self {
+        let mut transmission = detected.clone();
+        transmission.div_or_zero(launched);
+        Self { transmission }
+    }
+    /// Returns the transmission spectrum of this [`SystemTransmission`].
+    #[must_use]
+    pub const fn spectrum(&self) -> &Spectrum {
+        &self.transmission
+    }
+    /// Returns the transmission at the given wavelength or `None` if the wavelength is outside the analyzed range.
+    #[must_use]
+    pub fn transmission(&self, wavelength: &Length) -> Option<f64> {
+        self.transmission.get_value(wavelength)
+    }
+}
+impl From<SystemTransmission> for Proptype {
+    fn from(value: SystemTransmission) -> Self {
+        Self::SystemTransmission(value)
+    }
+}
+impl Plottable for SystemTransmission {
+    fn get_plot_series(
+        &self,
+        plt_type: &mut PlotType,
+        legend: bool,
+    ) -> OpmResult<Option<Vec<PlotSeries>>> {
+        self.transmission.get_plot_series(plt_type, legend)
+    }
+    fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
+        plt_params
+            .set(&PlotArgs::XLabel("wavelength in nm".into()))?
+            .set(&PlotArgs::YLabel("transmission".into()))?
+            .set(&PlotArgs::PlotSize((1200, 800)))?
+            .set(&PlotArgs::AxisEqual(false))?;
+        Ok(())
+    }
+    fn get_plot_type(&self, plt_params: &PlotParameters) -> PlotType {
+        PlotType::Line2D(plt_params.clone())
+    }
+}
+/// Analyzer for determining the spectral transmission of a system
+#[derive(Default, Debug)]
+pub struct SystemTransmissionAnalyzer {
+    config: SystemTransmissionConfig,
+}
+impl SystemTransmissionAnalyzer {
+    /// Creates a new [`SystemTransmissionAnalyzer`].
+    #[must_use]
+    pub const fn new(config: SystemTransmissionConfig) -> Self {
+        Self { config }
+    }
+    /// Returns a reference to the config of this [`SystemTransmissionAnalyzer`].
+    #[must_use]
+    pub const fn config(&self) -> &SystemTransmissionConfig {
+        &self.config
+    }
+    /// Returns the [`Uuid`]s of all sources of the given scenery including those in (nested) groups.
+    fn sources(scenery: &NodeGroup) -> OpmResult<Vec<Uuid>> {
+        let mut sources = Vec::new();
+        for node_ref in scenery.nodes() {
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            if let Ok(group) = node.as_group_mut() {
+                sources.append(&mut Self::sources(group)?);
+            } else if node.node_type() == "source" {
+                sources.push(node.node_attr().uuid());
+            }
+        }
+        Ok(sources)
+    }
+    /// Determine the [`SystemTransmission`] from the spectrum recorded by the detector node.
+    ///
+    /// This function must be called after [`Analyzer::analyze`] has been performed on the scenery.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - no detector node has been specified or it could not be found.
+    ///   - the detector did not receive any light or does not store a spectrum.
+    pub fn transmission(&self, scenery: &NodeGroup) -> OpmResult<SystemTransmission> {
+        let nr_of_sources = Self::sources(scenery)?.len();
+        let detector_ref = scenery.node_recursive(self.config.detector_or_err()?)?;
+        let mut detector = detector_ref
+            .optical_ref
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+        let Some(LightData::Energy(detected)) = detector.get_light_data_mut() else {
+            return Err(OpossumError::Analysis(format!(
+                "detector '{}' did not record a spectrum",
+                detector.name()
+            )));
+        };
+        let mut launched = self.config.flat_spectrum()?;
+        launched.scale_vertical(&usize_to_f64(nr_of_sources))?;
+        Ok(SystemTransmission::new(&launched, detected))
+    }
+}
+impl Analyzer for SystemTransmissionAnalyzer {
+    fn analyze(&self, scenery: &mut NodeGroup) -> OpmResult<()> {
+        let scenery_name = if scenery.node_attr().name().is_empty() {
+            String::new()
+        } else {
+            format!(" '{}'", scenery.node_attr().name())
+        };
+        let detector = self.config.detector_or_err()?;
+        scenery.node_recursive(detector)?;
+        info!("Performing system transmission analysis of scenery{scenery_name}.");
+        let flat_light: Proptype = Some(LightDataBuilder::Energy(EnergyDataBuilder::Raw(
+            self.config.flat_spectrum()?,
+        )))
+        .into();
+        let mut original_light = Vec::new();
+        for uuid in Self::sources(scenery)? {
+            let node_ref = scenery.node_recursive(uuid)?;
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            let light = node.node_attr().get_property("light data")?.clone();
+            node.node_attr_mut()
+                .set_property("light data", flat_light.clone())?;
+            original_light.push((uuid, light));
+        }
+        if original_light.is_empty() {
+            return Err(OpossumError::Analysis("no source found in scenery".into()));
+        }
+        let result = AnalysisEnergy::analyze(scenery, LightResult::default());
+        // restore the light of the sources
+        for (uuid, light) in original_light {
+            let node_ref = scenery.node_recursive(uuid)?;
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            node.node_attr_mut().set_property("light data", light)?;
+        }
+        result?;
+        Ok(())
+    }
+    fn report(&self, scenery: &NodeGroup) -> OpmResult<AnalysisReport> {
+        let detector = self.config.detector_or_err()?;
+        let mut report = scenery.toplevel_report()?;
+        report.set_analysis_type("System Transmission Analysis");
+        let transmission = self.transmission(scenery)?;
+        let mut props = Properties::default();
+        props.create(
+            "Transmission",
+            "spectral transmission of the system",
+            transmission.into(),
+        )?;
+        let mut node_report = NodeReport::new(
+            "system transmission",
+            "System transmission",
+            &detector.as_simple().to_string(),
+            props,
+        );
+        node_report.set_show_item(true);
+        report.add_node_report(node_report);
+        Ok(report)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule,
+        nodes::{Dummy, EnergyMeter, FilterType, IdealFilter, Source, Spectrometer},
+        spectrum_helper::{self, generate_filter_spectrum},
+    };
+    use approx::assert_abs_diff_eq;
+    use num::Zero;
+
+    fn bandpass_scene() -> (NodeGroup, Uuid) {
+        let mut scenery = NodeGroup::default();
+        let light_data_builder = LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+            vec![(nanometer!(633.0), joule!(1.0))],
+            nanometer!(1.0),
+        ));
+        let i_src = scenery
+            .add_node(Source::new("source", light_data_builder))
+            .unwrap();
+        let mut bandpass = generate_filter_spectrum(
+            nanometer!(400.0)..nanometer!(800.0),
+            nanometer!(1.0),
+            &spectrum_helper::FilterType::LongPassStep {
+                cut_off: nanometer!(500.0),
+            },
+        )
+        .unwrap();
+        bandpass.filter(
+            &generate_filter_spectrum(
+                nanometer!(400.0)..nanometer!(800.0),
+                nanometer!(1.0),
+                &spectrum_helper::FilterType::ShortPassStep {
+                    cut_off: nanometer!(600.0),
+                },
+            )
+            .unwrap(),
+        );
+        let i_f = scenery
+            .add_node(IdealFilter::new("bandpass", &FilterType::Spectrum(bandpass)).unwrap())
+            .unwrap();
+        let i_sm = scenery.add_node(Spectrometer::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_f, "input_1", Length::zero())
+            .unwrap();
+        scenery
+            .connect_nodes(i_f, "output_1", i_sm, "input_1", Length::zero())
+            .unwrap();
+        (scenery, i_sm)
+    }
+    #[test]
+    fn config() {
+        let config = SystemTransmissionConfig::default();
+        assert!(config.detector().is_none());
+        assert_eq!(config.range(), nanometer!(400.0)..nanometer!(1100.0));
+        assert_eq!(config.resolution(), nanometer!(1.0));
+        let uuid = Uuid::new_v4();
+        let config = SystemTransmissionConfig::new(
+            uuid,
+            nanometer!(500.0)..nanometer!(600.0),
+            nanometer!(0.5),
+        )
+        .unwrap();
+        assert_eq!(config.detector(), Some(uuid));
+        assert!(
+            SystemTransmissionConfig::new(
+                uuid,
+                nanometer!(600.0)..nanometer!(500.0),
+                nanometer!(0.5)
+            )
+            .is_err()
+        );
+        assert!(
+            SystemTransmissionConfig::new(
+                uuid,
+                nanometer!(500.0)..nanometer!(600.0),
+                nanometer!(0.0)
+            )
+            .is_err()
+        );
+    }
+    #[test]
+    fn flat_spectrum() {
+        let config = SystemTransmissionConfig::default();
+        let s = config.flat_spectrum().unwrap();
+        assert!(s.data_vec().iter().all(|v| *v == 1.0));
+    }
+    #[test]
+    fn analyze_no_source() {
+        let mut scenery = NodeGroup::default();
+        let i_m = scenery.add_node(EnergyMeter::default()).unwrap();
+        let mut config = SystemTransmissionConfig::default();
+        config.set_detector(i_m);
+        let analyzer = SystemTransmissionAnalyzer::new(config);
+        assert!(analyzer.analyze(&mut scenery).is_err());
+    }
+    #[test]
+    fn analyze_no_detector() {
+        let (mut scenery, _) = bandpass_scene();
+        let analyzer = SystemTransmissionAnalyzer::default();
+        assert!(analyzer.analyze(&mut scenery).is_err());
+        assert!(analyzer.report(&scenery).is_err());
+    }
+    #[test]
+    fn analyze_wrong_detector() {
+        let (mut scenery, _) = bandpass_scene();
+        let mut config = SystemTransmissionConfig::default();
+        config.set_detector(Uuid::new_v4());
+        let analyzer = SystemTransmissionAnalyzer::new(config);
+        assert!(analyzer.analyze(&mut scenery).is_err());
+    }
+    #[test]
+    fn analyze_nested_source() {
+        let mut group = NodeGroup::new("source group");
+        let i_src = group
+            .add_node(Source::new(
+                "source",
+                LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+                    vec![(nanometer!(633.0), joule!(1.0))],
+                    nanometer!(1.0),
+                )),
+            ))
+            .unwrap();
+        let i_d = group.add_node(Dummy::default()).unwrap();
+        group
+            .connect_nodes(i_src, "output_1", i_d, "input_1", Length::zero())
+            .unwrap();
+        group.map_output_port(i_d, "output_1", "output_1").unwrap();
+        let mut scenery = NodeGroup::default();
+        let i_g = scenery.add_node(group).unwrap();
+        let i_sm = scenery.add_node(Spectrometer::default()).unwrap();
+        scenery
+            .connect_nodes(i_g, "output_1", i_sm, "input_1", Length::zero())
+            .unwrap();
+        let mut config = SystemTransmissionConfig::default();
+        config.set_detector(i_sm);
+        let analyzer = SystemTransmissionAnalyzer::new(config);
+        analyzer.analyze(&mut scenery).unwrap();
+        let transmission = analyzer.transmission(&scenery).unwrap();
+        for wvl in [450.0, 633.0, 1000.0] {
+            assert_abs_diff_eq!(
+                transmission.transmission(&nanometer!(wvl)).unwrap(),
+                1.0,
+                epsilon = 1e-10
+            );
+        }
+    }
+    #[test]
+    fn analyze_bandpass() {
+        let (mut scenery, i_sm) = bandpass_scene();
+        let config = SystemTransmissionConfig::new(
+            i_sm,
+            nanometer!(400.0)..nanometer!(800.0),
+            nanometer!(1.0),
+        )
+        .unwrap();
+        let analyzer = SystemTransmissionAnalyzer::new(config);
+        analyzer.analyze(&mut scenery).unwrap();
+        let transmission = analyzer.transmission(&scenery).unwrap();
+        for wvl in [450.0, 495.0, 605.0, 700.0] {
+            assert_abs_diff_eq!(
+                transmission.transmission(&nanometer!(wvl)).unwrap(),
+                0.0,
+                epsilon = 1e-10
+            );
+        }
+        for wvl in [505.0, 550.0, 595.0] {
+            assert_abs_diff_eq!(
+                transmission.transmission(&nanometer!(wvl)).unwrap(),
+                1.0,
+                epsilon = 1e-10
+            );
+        }
+        assert!(analyzer.report(&scenery).is_ok());
+    }
+    #[test]
+    fn analyze_restores_source() {
+        let (mut scenery, i_sm) = bandpass_scene();
+        let i_src = scenery.nodes()[0].uuid();
+        let light_before = scenery
+            .node(i_src)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .node_attr()
+            .get_property("light data")
+            .unwrap()
+            .clone();
+        let config = SystemTransmissionConfig {
+            detector: Some(i_sm),
+            ..Default::default()
+        };
+        SystemTransmissionAnalyzer::new(config)
+            .analyze(&mut scenery)
+            .unwrap();
+        let light_after = scenery
+            .node(i_src)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .node_attr()
+            .get_property("light data")
+            .unwrap()
+            .clone();
+        assert_eq!(format!("{light_before:?}"), format!("{light_after:?}"));
+    }
+}
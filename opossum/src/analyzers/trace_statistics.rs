@@ -0,0 +1,221 @@
+#![warn(missing_docs)]
+//! Aggregated statistics of a ray tracing run.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use uom::si::f64::Energy;
+
+use crate::{joule, ray::TerminationReason, rays::Rays};
+
+/// Aggregated statistics of a ray tracing analysis.
+///
+/// The statistics are collected from the rays emitted by all sources and the rays leaving the scenery (i.e. rays on
+/// unconnected output ports). A ray split into several parts (e.g. by a beam splitter) is counted once for each part
+/// leaving the scenery.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceStatistics {
+    rays_launched: usize,
+    rays_arrived: usize,
+    terminations: BTreeMap<TerminationReason, usize>,
+    surface_interactions: usize,
+    max_bounces: usize,
+    energy_in: Energy,
+    energy_out: Energy,
+    energy_pruned: Energy,
+}
+impl Default for TraceStatistics {
+    fn default() -> Self {
+        Self {
+            rays_launched: 0,
+            rays_arrived: 0,
+            terminations: BTreeMap::new(),
+            surface_interactions: 0,
+            max_bounces: 0,
+            energy_in: joule!(0.0),
+            energy_out: joule!(0.0),
+            energy_pruned: joule!(0.0),
+        }
+    }
+}
+impl TraceStatistics {
+    /// Add the [`Rays`] emitted by a source to this [`TraceStatistics`].
+    pub fn add_launched(&mut self, rays: &Rays) {
+        for ray in rays.iter() {
+            self.rays_launched += 1;
+            self.energy_in += ray.energy();
+        }
+    }
+    /// Add [`Rays`] leaving the scenery (terminal rays) to this [`TraceStatistics`].
+    ///
+    /// Valid rays are counted as arrived, invalid rays are counted by their [`TerminationReason`].
+    pub fn add_terminated(&mut self, rays: &Rays) {
+        for ray in rays.iter() {
+            self.surface_interactions += ray.number_of_bounces() + ray.number_of_refractions();
+            self.max_bounces = self.max_bounces.max(ray.number_of_bounces());
+            if ray.valid() {
+                self.rays_arrived += 1;
+                self.energy_out += ray.energy();
+            } else {
+                *self
+                    .terminations
+                    .entry(ray.termination_reason().unwrap_or(TerminationReason::Other))
+                    .or_default() += 1;
+            }
+        }
+    }
+    /// Add [`Rays`] removed from the ray bundles during the analysis (e.g. due to an energy threshold) to this
+    /// [`TraceStatistics`].
+    ///
+    /// The rays are counted like terminated rays (see [`add_terminated`](TraceStatistics::add_terminated)). In addition,
+    /// their energy is accumulated as pruned energy.
+    pub fn add_pruned(&mut self, rays: &Rays) {
+        self.add_terminated(rays);
+        for ray in rays.iter() {
+            self.energy_pruned += ray.energy();
+        }
+    }
+    /// Merge the statistics of another [`TraceStatistics`] (e.g. from a subgroup) into this one.
+    pub fn merge(&mut self, other: &Self) {
+        self.rays_launched += other.rays_launched;
+        self.rays_arrived += other.rays_arrived;
+        for (reason, count) in &other.terminations {
+            *self.terminations.entry(*reason).or_default() += count;
+        }
+        self.surface_interactions += other.surface_interactions;
+        self.max_bounces = self.max_bounces.max(other.max_bounces);
+        self.energy_in += other.energy_in;
+        self.energy_out += other.energy_out;
+        self.energy_pruned += other.energy_pruned;
+    }
+    /// Returns the total number of rays launched by all sources.
+    #[must_use]
+    pub const fn rays_launched(&self) -> usize {
+        self.rays_launched
+    }
+    /// Returns the number of (valid) rays leaving the scenery.
+    #[must_use]
+    pub const fn rays_arrived(&self) -> usize {
+        self.rays_arrived
+    }
+    /// Returns the number of terminated (invalid) rays leaving the scenery.
+    #[must_use]
+    pub fn rays_lost(&self) -> usize {
+        self.terminations.values().sum()
+    }
+    /// Returns the number of terminated rays for each [`TerminationReason`].
+    #[must_use]
+    pub const fn terminations(&self) -> &BTreeMap<TerminationReason, usize> {
+        &self.terminations
+    }
+    /// Returns the number of rays terminated for the given [`TerminationReason`].
+    #[must_use]
+    pub fn nr_of_terminations(&self, reason: TerminationReason) -> usize {
+        self.terminations.get(&reason).copied().unwrap_or_default()
+    }
+    /// Returns the total number of surface interactions (refractions and reflections) of all rays leaving the scenery.
+    #[must_use]
+    pub const fn surface_interactions(&self) -> usize {
+        self.surface_interactions
+    }
+    /// Returns the maximum number of bounces (reflections) of a single ray.
+    #[must_use]
+    pub const fn max_bounces(&self) -> usize {
+        self.max_bounces
+    }
+    /// Returns the total energy of all rays launched by the sources.
+    #[must_use]
+    pub const fn energy_in(&self) -> Energy {
+        self.energy_in
+    }
+    /// Returns the total energy of all (valid) rays leaving the scenery.
+    #[must_use]
+    pub const fn energy_out(&self) -> Energy {
+        self.energy_out
+    }
+    /// Returns the total energy of all rays removed from the ray bundles during the analysis.
+    #[must_use]
+    pub const fn energy_pruned(&self) -> Energy {
+        self.energy_pruned
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{millimeter, nanometer, position_distributions::Hexapolar};
+    use approx::assert_relative_eq;
+
+    fn test_rays() -> Rays {
+        Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap()
+    }
+    #[test]
+    fn default() {
+        let stats = TraceStatistics::default();
+        assert_eq!(stats.rays_launched(), 0);
+        assert_eq!(stats.rays_arrived(), 0);
+        assert_eq!(stats.rays_lost(), 0);
+        assert!(stats.terminations().is_empty());
+        assert_eq!(stats.surface_interactions(), 0);
+        assert_eq!(stats.max_bounces(), 0);
+        assert_eq!(stats.energy_in(), joule!(0.0));
+        assert_eq!(stats.energy_out(), joule!(0.0));
+        assert_eq!(stats.energy_pruned(), joule!(0.0));
+    }
+    #[test]
+    fn add_launched_and_terminated() {
+        let mut stats = TraceStatistics::default();
+        let mut rays = test_rays();
+        stats.add_launched(&rays);
+        assert_eq!(stats.rays_launched(), 7);
+        assert_relative_eq!(stats.energy_in().value, 1.0);
+        rays.filter_by_nr_of_bounces(0);
+        let mut iter = rays.iter_mut();
+        iter.next().unwrap().terminate(TerminationReason::Aperture);
+        iter.next().unwrap().set_invalid();
+        stats.add_terminated(&rays);
+        assert_eq!(stats.rays_arrived(), 5);
+        assert_eq!(stats.rays_lost(), 2);
+        assert_eq!(stats.nr_of_terminations(TerminationReason::Aperture), 1);
+        assert_eq!(stats.nr_of_terminations(TerminationReason::Other), 1);
+        assert_eq!(stats.nr_of_terminations(TerminationReason::MaxBounces), 0);
+        assert_relative_eq!(stats.energy_out().value, 5.0 / 7.0);
+    }
+    #[test]
+    fn add_pruned() {
+        let mut stats = TraceStatistics::default();
+        let mut rays = test_rays();
+        let pruned = rays.prune_by_threshold_energy(joule!(1.0)).unwrap();
+        stats.add_pruned(&pruned);
+        assert_eq!(stats.rays_arrived(), 0);
+        assert_eq!(
+            stats.nr_of_terminations(TerminationReason::EnergyThreshold),
+            7
+        );
+        assert_relative_eq!(stats.energy_pruned().value, 1.0);
+        assert_relative_eq!(stats.energy_out().value, 0.0);
+    }
+    #[test]
+    fn merge() {
+        let mut stats = TraceStatistics::default();
+        let mut rays = test_rays();
+        stats.add_launched(&rays);
+        rays.iter_mut()
+            .next()
+            .unwrap()
+            .terminate(TerminationReason::MissedSurface);
+        stats.add_terminated(&rays);
+        let mut merged = stats.clone();
+        merged.merge(&stats);
+        assert_eq!(merged.rays_launched(), 14);
+        assert_eq!(merged.rays_arrived(), 12);
+        assert_eq!(
+            merged.nr_of_terminations(TerminationReason::MissedSurface),
+            2
+        );
+        assert_relative_eq!(merged.energy_in().value, 2.0);
+    }
+}
@@ -0,0 +1,388 @@
+#![warn(missing_docs)]
+//! Wavelength sweep over a scenery
+//!
+//! A wavelength sweep repeats a given analysis for a series of monochromatic source wavelengths. For each wavelength,
+//! the light of all sources of the scenery is replaced by monochromatic light (keeping all other source parameters)
+//! and the energy arriving at a given detector node is recorded. The result `E(λ)` is reported as a plot.
+use super::Analyzer;
+use crate::{
+    error::{OpmResult, OpossumError},
+    joule,
+    lightdata::{LightData, light_data_builder::LightDataBuilder},
+    nodes::NodeGroup,
+    optic_node::OpticNode,
+    plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
+    properties::{Properties, Proptype},
+    reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
+    utils::usize_to_f64,
+};
+use log::info;
+use nalgebra::MatrixXx2;
+use plotters::style::RGBAColor;
+use serde::{Deserialize, Serialize};
+use uom::si::{
+    energy::joule,
+    f64::{Energy, Length},
+    length::nanometer,
+};
+use uuid::Uuid;
+
+/// Definition of a wavelength sweep.
+///
+/// The sweep consists of `steps` equidistant wavelengths between `start` and `stop` (both included).
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct WavelengthSweep {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    start: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    stop: Length,
+    steps: usize,
+    detector: Uuid,
+}
+impl WavelengthSweep {
+    /// Creates a new [`WavelengthSweep`].
+    ///
+    /// The energy is recorded at the detector node with the given [`Uuid`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the start or stop wavelength is not positive and finite.
+    ///   - the stop wavelength is not larger than the start wavelength.
+    ///   - the number of steps is smaller than two.
+    pub fn new(start: Length, stop: Length, steps: usize, detector: Uuid) -> OpmResult<Self> {
+        if !start.is_normal() || start.is_sign_negative() || !stop.is_normal() {
+            return Err(OpossumError::Analysis(
+                "start and stop wavelength must be positive and finite".into(),
+            ));
+        }
+        if stop <= start {
+            return Err(OpossumError::Analysis(
+                "stop wavelength must be larger than start wavelength".into(),
+            ));
+        }
+        if steps < 2 {
+            return Err(OpossumError::Analysis(
+                "a wavelength sweep requires at least two steps".into(),
+            ));
+        }
+        Ok(Self {
+            start,
+            stop,
+            steps,
+            detector,
+        })
+    }
+    /// Returns the start wavelength of this [`WavelengthSweep`].
+    #[must_use]
+    pub const fn start(&self) -> Length {
+        self.start
+    }
+    /// Returns the stop wavelength of this [`WavelengthSweep`].
+    #[must_use]
+    pub const fn stop(&self) -> Length {
+        self.stop
+    }
+    /// Returns the number of wavelengths of this [`WavelengthSweep`].
+    #[must_use]
+    pub const fn steps(&self) -> usize {
+        self.steps
+    }
+    /// Returns the [`Uuid`] of the detector node of this [`WavelengthSweep`].
+    #[must_use]
+    pub const fn detector(&self) -> Uuid {
+        self.detector
+    }
+    /// Returns the wavelengths of this [`WavelengthSweep`].
+    #[must_use]
+    pub fn wavelengths(&self) -> Vec<Length> {
+        let step = (self.stop - self.start) / usize_to_f64(self.steps - 1);
+        (0..self.steps)
+            .map(|i| self.start + step * usize_to_f64(i))
+            .collect()
+    }
+    /// Perform the given analysis for all wavelengths of this [`WavelengthSweep`].
+    ///
+    /// The light of the (toplevel) sources is restored after the sweep. This function returns the recorded
+    /// [`WavelengthSweepResult`] together with an [`AnalysisReport`]. The report consists of the report of the given
+    /// analyzer for the last wavelength extended by a plot of the [`WavelengthSweepResult`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the scenery does not contain a source or the light of a source cannot be made monochromatic (see
+    ///     [`LightDataBuilder::with_wavelength`]).
+    ///   - the analysis fails for one of the wavelengths.
+    ///   - the detector node could not be found or did not record any light.
+    pub fn run(
+        &self,
+        analyzer: &dyn Analyzer,
+        scenery: &mut NodeGroup,
+    ) -> OpmResult<(WavelengthSweepResult, AnalysisReport)> {
+        let mut original_light = Vec::new();
+        for node_ref in scenery.nodes() {
+            let uuid = node_ref.uuid();
+            let node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            if node.node_type() == "source" {
+                let Proptype::LightDataBuilder(light) =
+                    node.node_attr().get_property("light data")?.clone()
+                else {
+                    return Err(OpossumError::Analysis(
+                        "cannot read light data of source".into(),
+                    ));
+                };
+                original_light.push((uuid, light));
+            }
+        }
+        if original_light.is_empty() {
+            return Err(OpossumError::Analysis("no source found in scenery".into()));
+        }
+        let result = self.sweep(analyzer, scenery, &original_light);
+        // restore the light of the sources
+        for (uuid, light) in original_light {
+            let node_ref = scenery.node(uuid)?;
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            node.node_attr_mut()
+                .set_property("light data", light.into())?;
+        }
+        let (sweep_result, mut report) = result?;
+        let mut props = Properties::default();
+        props.create(
+            "Detected energy",
+            "energy at the detector vs. source wavelength",
+            sweep_result.clone().into(),
+        )?;
+        let mut node_report = NodeReport::new(
+            "wavelength sweep",
+            "Wavelength sweep",
+            &self.detector.as_simple().to_string(),
+            props,
+        );
+        node_report.set_show_item(true);
+        report.add_node_report(node_report);
+        Ok((sweep_result, report))
+    }
+    fn sweep(
+        &self,
+        analyzer: &dyn Analyzer,
+        scenery: &mut NodeGroup,
+        original_light: &[(Uuid, Option<LightDataBuilder>)],
+    ) -> OpmResult<(WavelengthSweepResult, AnalysisReport)> {
+        let wavelengths = self.wavelengths();
+        let mut sweep_result = WavelengthSweepResult::default();
+        let mut report = None;
+        for (i, wavelength) in wavelengths.iter().enumerate() {
+            info!(
+                "Wavelength sweep step {}/{}: {:.3} nm",
+                i + 1,
+                wavelengths.len(),
+                wavelength.get::<nanometer>()
+            );
+            for (uuid, light) in original_light {
+                let Some(light) = light else {
+                    return Err(OpossumError::Analysis("source has no light defined".into()));
+                };
+                let monochromatic: Proptype = Some(light.with_wavelength(*wavelength)?).into();
+                let node_ref = scenery.node(*uuid)?;
+                let mut node = node_ref
+                    .optical_ref
+                    .lock()
+                    .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+                node.node_attr_mut()
+                    .set_property("light data", monochromatic)?;
+            }
+            analyzer.analyze(scenery)?;
+            sweep_result
+                .data
+                .push((*wavelength, self.detected_energy(scenery)?));
+            if i + 1 == wavelengths.len() {
+                report = Some(analyzer.report(scenery)?);
+            }
+            scenery.clear_edges();
+            scenery.reset_data();
+        }
+        let report =
+            report.ok_or_else(|| OpossumError::Analysis("empty wavelength sweep".into()))?;
+        Ok((sweep_result, report))
+    }
+    /// Returns the total energy recorded by the detector node.
+    fn detected_energy(&self, scenery: &NodeGroup) -> OpmResult<Energy> {
+        let detector_ref = scenery.node_recursive(self.detector)?;
+        let mut detector = detector_ref
+            .optical_ref
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+        let name = detector.name();
+        match detector.get_light_data_mut() {
+            Some(LightData::Energy(s)) => Ok(joule!(s.total_energy())),
+            Some(LightData::Geometric(r)) => Ok(r.total_energy()),
+            Some(LightData::GhostFocus(r)) => {
+                Ok(r.iter().map(crate::rays::Rays::total_energy).sum())
+            }
+            _ => Err(OpossumError::Analysis(format!(
+                "detector '{name}' did not record any light"
+            ))),
+        }
+    }
+}
+/// Detected energy vs. source wavelength as determined by a [`WavelengthSweep`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WavelengthSweepResult {
+    data: Vec<(Length, Energy)>,
+}
+impl WavelengthSweepResult {
+    /// Returns the (wavelength, energy) pairs of this [`WavelengthSweepResult`].
+    #[must_use]
+    pub fn data(&self) -> &[(Length, Energy)] {
+        &self.data
+    }
+}
+impl From<WavelengthSweepResult> for Proptype {
+    fn from(value: WavelengthSweepResult) -> Self {
+        Self::WavelengthSweep(value)
+    }
+}
+impl Plottable for WavelengthSweepResult {
+    fn get_plot_series(
+        &self,
+        plt_type: &mut PlotType,
+        _legend: bool,
+    ) -> OpmResult<Option<Vec<PlotSeries>>> {
+        let mut xy_data = MatrixXx2::zeros(self.data.len());
+        for (i, (wavelength, energy)) in self.data.iter().enumerate() {
+            xy_data[(i, 0)] = wavelength.get::<nanometer>();
+            xy_data[(i, 1)] = energy.get::<joule>();
+        }
+        match plt_type {
+            PlotType::Line2D(_) | PlotType::Scatter2D(_) => Ok(Some(vec![PlotSeries::new(
+                &PlotData::Dim2 { xy_data },
+                RGBAColor(255, 0, 0, 1.),
+                None,
+            )])),
+            _ => Ok(None),
+        }
+    }
+    fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
+        plt_params
+            .set(&PlotArgs::XLabel("wavelength in nm".into()))?
+            .set(&PlotArgs::YLabel("energy in J".into()))?
+            .set(&PlotArgs::PlotSize((1200, 800)))?
+            .set(&PlotArgs::AxisEqual(false))?;
+        Ok(())
+    }
+    fn get_plot_type(&self, plt_params: &PlotParameters) -> PlotType {
+        PlotType::Line2D(plt_params.clone())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        analyzers::energy::EnergyAnalyzer,
+        lightdata::energy_data_builder::EnergyDataBuilder,
+        nanometer,
+        nodes::{EnergyMeter, FilterType, IdealFilter, Source},
+        spectrum_helper::{self, generate_filter_spectrum},
+    };
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+    use num::Zero;
+
+    fn filter_scene() -> (NodeGroup, Uuid, Uuid) {
+        let mut scenery = NodeGroup::default();
+        let light_data_builder = LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+            vec![(nanometer!(1000.0), joule!(2.0))],
+            nanometer!(1.0),
+        ));
+        let i_src = scenery
+            .add_node(Source::new("source", light_data_builder))
+            .unwrap();
+        let longpass = generate_filter_spectrum(
+            nanometer!(400.0)..nanometer!(800.0),
+            nanometer!(1.0),
+            &spectrum_helper::FilterType::LongPassStep {
+                cut_off: nanometer!(600.0),
+            },
+        )
+        .unwrap();
+        let i_f = scenery
+            .add_node(IdealFilter::new("longpass", &FilterType::Spectrum(longpass)).unwrap())
+            .unwrap();
+        let i_em = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_f, "input_1", Length::zero())
+            .unwrap();
+        scenery
+            .connect_nodes(i_f, "output_1", i_em, "input_1", Length::zero())
+            .unwrap();
+        (scenery, i_src, i_em)
+    }
+    #[test]
+    fn new() {
+        let uuid = Uuid::new_v4();
+        let sweep = WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 5, uuid).unwrap();
+        assert_eq!(sweep.start(), nanometer!(500.0));
+        assert_eq!(sweep.stop(), nanometer!(700.0));
+        assert_eq!(sweep.steps(), 5);
+        assert_eq!(sweep.detector(), uuid);
+        assert!(WavelengthSweep::new(nanometer!(0.0), nanometer!(700.0), 5, uuid).is_err());
+        assert!(
+            WavelengthSweep::new(nanometer!(500.0), nanometer!(f64::INFINITY), 5, uuid).is_err()
+        );
+        assert!(WavelengthSweep::new(nanometer!(700.0), nanometer!(500.0), 5, uuid).is_err());
+        assert!(WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 1, uuid).is_err());
+    }
+    #[test]
+    fn wavelengths() {
+        let sweep =
+            WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 5, Uuid::nil()).unwrap();
+        let wavelengths = sweep.wavelengths();
+        assert_eq!(wavelengths.len(), 5);
+        for (wavelength, expected) in wavelengths.iter().zip([500.0, 550.0, 600.0, 650.0, 700.0]) {
+            assert_relative_eq!(wavelength.get::<nanometer>(), expected);
+        }
+    }
+    #[test]
+    fn run() {
+        let (mut scenery, i_src, i_em) = filter_scene();
+        let light = |scenery: &NodeGroup| {
+            let node_ref = scenery.node(i_src).unwrap();
+            let node = node_ref.optical_ref.lock().unwrap();
+            format!("{:?}", node.node_attr().get_property("light data").unwrap())
+        };
+        let light_before = light(&scenery);
+        let sweep = WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 5, i_em).unwrap();
+        let (result, _) = sweep.run(&EnergyAnalyzer::default(), &mut scenery).unwrap();
+        assert_eq!(result.data().len(), 5);
+        // the long pass filter blocks all wavelengths below 600 nm
+        for (i, expected) in [(0, 0.0), (1, 0.0), (3, 2.0), (4, 2.0)] {
+            let (wavelength, energy) = result.data()[i];
+            assert_relative_eq!(
+                wavelength.get::<nanometer>(),
+                500.0 + 50.0 * usize_to_f64(i)
+            );
+            assert_abs_diff_eq!(energy.get::<joule>(), expected, epsilon = 1e-10);
+        }
+        assert_eq!(light(&scenery), light_before);
+    }
+    #[test]
+    fn run_no_source() {
+        let mut scenery = NodeGroup::default();
+        let i_em = scenery.add_node(EnergyMeter::default()).unwrap();
+        let sweep = WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 5, i_em).unwrap();
+        assert!(sweep.run(&EnergyAnalyzer::default(), &mut scenery).is_err());
+    }
+    #[test]
+    fn run_wrong_detector() {
+        let (mut scenery, _, _) = filter_scene();
+        let sweep =
+            WavelengthSweep::new(nanometer!(500.0), nanometer!(700.0), 5, Uuid::new_v4()).unwrap();
+        assert!(sweep.run(&EnergyAnalyzer::default(), &mut scenery).is_err());
+    }
+}
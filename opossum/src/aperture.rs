@@ -39,7 +39,6 @@ use crate::{
     utils::math_distribution_functions::ellipse,
 };
 use core::f64;
-use earcutr::earcut;
 use nalgebra::{Isometry2, Matrix2xX, MatrixXx2, Point2, Vector2};
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
@@ -53,6 +52,7 @@ use uom::si::{
 ///
 /// Each aperture can act as a "hole" or "obstruction"
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum ApertureType {
     /// the [`Aperture`] shape acts as a hole. The inner part of the shape is transparent.
     #[default]
@@ -63,19 +63,26 @@ pub enum ApertureType {
 
 /// Different aperture types
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum Aperture {
     /// completely transparent aperture. This is the default.
     #[default]
     None,
     /// binary (either transparent or opaque) circular aperture defined by a radius and center point
     BinaryCircle(CircleConfig),
+    /// binary (either transparent or opaque) annular aperture defined by an inner and outer radius and center point
+    BinaryAnnulus(AnnulusConfig),
     /// binary (either transparent or opaque) rectangular aperture defined by width and height as well as its center point
     BinaryRectangle(RectangleConfig),
+    /// binary (either transparent or opaque) elliptical aperture defined by its two semi-axes as well as its center point
+    BinaryEllipse(EllipseConfig),
     /// binary (either transparent or opaque) polygonial aperture defined by a set of 2D points. This polygon can also be
     /// non-convex but should not intersect.
     BinaryPolygon(PolygonConfig),
     /// variable transmission aperture using a 2D Gaussian function.
     Gaussian(GaussianConfig),
+    /// soft-edged circular aperture with a Gaussian transmission profile which fully blocks beyond a cutoff radius.
+    GaussianApodizer(GaussianApodizerConfig),
     /// a stack of an arbitrary number of the above apertures. The transmission factor at a given point is the
     /// product of all indiviual aperture on the stack (subtractive apodization).
     Stack(StackConfig),
@@ -93,9 +100,12 @@ impl Aperture {
         match self {
             Self::None => 1.0,
             Self::BinaryCircle(circle) => circle.apodize(point),
+            Self::BinaryAnnulus(annulus) => annulus.apodize(point),
             Self::BinaryRectangle(rectangle) => rectangle.apodize(point),
+            Self::BinaryEllipse(ellipse) => ellipse.apodize(point),
             Self::BinaryPolygon(p) => p.apodize(point),
             Self::Gaussian(g) => g.apodize(point),
+            Self::GaussianApodizer(g) => g.apodize(point),
             Self::Stack(s) => s.apodize(point),
         }
     }
@@ -120,8 +130,11 @@ pub trait Apodize {
 }
 /// Configuration data for a circular aperture.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct CircleConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     center: Point2<Length>,
     aperture_type: ApertureType,
 }
@@ -179,11 +192,104 @@ impl Apodize for CircleConfig {
         transmission
     }
 }
+/// Configuration data for an annular aperture.
+///
+/// The aperture is transparent between the inner and outer radius. This is useful e.g. for modelling the central
+/// obstruction of a telescope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct AnnulusConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    inner_radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    outer_radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
+    center: Point2<Length>,
+    aperture_type: ApertureType,
+}
+impl AnnulusConfig {
+    /// Create a new [`AnnulusConfig`] from a given inner and outer radius and a center point.
+    ///
+    /// By default the aperture has the aperture type [`ApertureType::Hole`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the inner radius is negative or not finite.
+    ///   - the outer radius is not positive and finite.
+    ///   - the inner radius is not smaller than the outer radius.
+    ///   - the center point is not finite.
+    pub fn new(
+        inner_radius: Length,
+        outer_radius: Length,
+        center: Point2<Length>,
+    ) -> OpmResult<Self> {
+        if !inner_radius.is_finite() || inner_radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "inner radius must be >= 0.0 and finite".into(),
+            ));
+        }
+        if !outer_radius.is_normal() || outer_radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "outer radius must be positive and finite".into(),
+            ));
+        }
+        if inner_radius >= outer_radius {
+            return Err(OpossumError::Other(
+                "inner radius must be smaller than outer radius".into(),
+            ));
+        }
+        if !center.x.is_finite() || !center.y.is_finite() {
+            return Err(OpossumError::Other("center point must be finite".into()));
+        }
+        Ok(Self {
+            inner_radius,
+            outer_radius,
+            center,
+            aperture_type: ApertureType::default(),
+        })
+    }
+    /// Returns the inner radius of this [`AnnulusConfig`].
+    #[must_use]
+    pub const fn inner_radius(&self) -> Length {
+        self.inner_radius
+    }
+    /// Returns the outer radius of this [`AnnulusConfig`].
+    #[must_use]
+    pub const fn outer_radius(&self) -> Length {
+        self.outer_radius
+    }
+}
+impl Apodize for AnnulusConfig {
+    fn set_aperture_type(&mut self, aperture_type: ApertureType) {
+        self.aperture_type = aperture_type;
+    }
+    fn apodize(&self, point: &Point2<Length>) -> f64 {
+        let dx = (point.x - self.center.x).get::<meter>();
+        let dy = (point.y - self.center.y).get::<meter>();
+        let r_sq = dx.mul_add(dx, dy * dy);
+        let mut transmission = if r_sq >= self.inner_radius.get::<meter>().powi(2)
+            && r_sq <= self.outer_radius.get::<meter>().powi(2)
+        {
+            1.0
+        } else {
+            0.0
+        };
+        if matches!(self.aperture_type, ApertureType::Obstruction) {
+            transmission = 1.0 - transmission;
+        }
+        transmission
+    }
+}
 /// Configuration data for a rectangular aperture.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct RectangleConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     width: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     height: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     center: Point2<Length>,
     aperture_type: ApertureType,
 }
@@ -242,74 +348,124 @@ impl Apodize for RectangleConfig {
         transmission
     }
 }
+/// Configuration data for an elliptical aperture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct EllipseConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "(f64, f64)"))]
+    semi_axes: (Length, Length),
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
+    center: Point2<Length>,
+    aperture_type: ApertureType,
+}
+impl EllipseConfig {
+    /// Create a new elliptical aperture configuration by given semi-axes `(a_x, a_y)` and the center point.
+    ///
+    /// By default the aperture has the aperture type [`ApertureType::Hole`].
+    /// # Errors
+    ///
+    /// This function will return an error if the semi-axes are negative, NaN or Infinity and / or the center point is indefinite.
+    pub fn new(semi_axes: (Length, Length), center: Point2<Length>) -> OpmResult<Self> {
+        if semi_axes.0.is_normal()
+            && semi_axes.0.is_sign_positive()
+            && semi_axes.1.is_normal()
+            && semi_axes.1.is_sign_positive()
+            && center.coords[0].is_finite()
+            && center.coords[1].is_finite()
+        {
+            Ok(Self {
+                semi_axes,
+                center,
+                aperture_type: ApertureType::default(),
+            })
+        } else {
+            Err(OpossumError::Other("semi-axes must be positive".into()))
+        }
+    }
+    /// Returns the semi-axes `(a_x, a_y)` of this [`EllipseConfig`].
+    #[must_use]
+    pub const fn semi_axes(&self) -> (Length, Length) {
+        self.semi_axes
+    }
+}
+impl Apodize for EllipseConfig {
+    fn set_aperture_type(&mut self, aperture_type: ApertureType) {
+        self.aperture_type = aperture_type;
+    }
+    fn apodize(&self, point: &Point2<Length>) -> f64 {
+        let x = ((point.x - self.center.x) / self.semi_axes.0).get::<ratio>();
+        let y = ((point.y - self.center.y) / self.semi_axes.1).get::<ratio>();
+        let mut transmission = if x.mul_add(x, y.powi(2)) <= 1.0 {
+            1.0
+        } else {
+            0.0
+        };
+        if matches!(self.aperture_type, ApertureType::Obstruction) {
+            transmission = 1.0 - transmission;
+        }
+        transmission
+    }
+}
 /// Configuration of a polygonal aperture defined by a given set of points.
+///
+/// The polygon might be convex or concave (e.g. for modelling mechanical mounts or spiders). Whether a point lies within
+/// the polygon is determined by a ray casting (even-odd) test. Points on the outline are considered to lie within the polygon.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct PolygonConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<[f64; 2]>"))]
     points: Vec<Point2<Length>>,
     aperture_type: ApertureType,
-    triangle_indices: Vec<Vec<usize>>,
 }
 impl PolygonConfig {
     /// Create a new polygonal aperture configuration by a set of given 2D points.
     ///
-    /// The order of the points must follow the outline of the polygon. Otherwise intersections may occur.
+    /// The order of the points must follow the outline of the polygon. The polygon is implicitly closed, i.e. the last
+    /// point is connected to the first one. Both winding orders (clockwise and counter-clockwise) are supported. For
+    /// self-intersecting outlines, the even-odd rule applies (i.e. regions enclosed twice are considered outside).
     /// By default the aperture has the aperture type [`ApertureType::Hole`].
     ///
     /// # Errors
     ///
-    /// This function will return an error if the number of points is less than three, so that no polygon can be created.
+    /// This function will return an error if
+    ///   - the number of points is less than three, so that no polygon can be created.
+    ///   - any point has non-finite coordinates.
     pub fn new(points: Vec<Point2<Length>>) -> OpmResult<Self> {
         if points.len() < 3 {
             return Err(OpossumError::Other("less than 3 points given".into()));
         }
+        if points.iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            return Err(OpossumError::Other("polygon points must be finite".into()));
+        }
         Ok(Self {
-            triangle_indices: Self::triangulate(&points)?,
             points,
             aperture_type: ApertureType::default(),
         })
     }
-
-    fn triangulate(points: &[Point2<Length>]) -> OpmResult<Vec<Vec<usize>>> {
-        let polygon_vertices_flat = points
-            .iter()
-            .flat_map(|p| vec![p.x.get::<meter>(), p.y.get::<meter>()])
-            .collect::<Vec<f64>>();
-
-        let triangulated_indices = earcut(polygon_vertices_flat.as_slice(), &[], 2)
-            .map_err(|e| OpossumError::Other(format!("Triangulation of polygon failed:{e}")))?;
-        let mut chunked_indices = Vec::<Vec<usize>>::with_capacity(triangulated_indices.len() / 3);
-        for chunk in triangulated_indices.chunks(3) {
-            chunked_indices.push(Vec::<usize>::from(chunk));
-        }
-        Ok(chunked_indices)
+    /// Returns the outline points of this [`PolygonConfig`].
+    #[must_use]
+    pub fn points(&self) -> &[Point2<Length>] {
+        &self.points
     }
-
     /// checks, if a point lies within this [`PolygonConfig`]
-    /// # Panics
-    /// This function panics if the triangulation fails
     #[must_use]
     pub fn in_polygon(&self, point: &Point2<Length>) -> bool {
+        let (px, py) = (point.x.get::<meter>(), point.y.get::<meter>());
         let mut in_polygon = false;
-        for tri in &self.triangle_indices {
-            let p1 = self.points[tri[0]];
-            let p2 = self.points[tri[1]];
-            let p3 = self.points[tri[2]];
-
-            let denominator =
-                (p2[1] - p3[1]).mul_add(p1[0] - p3[0], (p3[0] - p2[0]) * (p1[1] - p3[1]));
-            let a = (((p2[1] - p3[1])
-                .mul_add(point.x - p3[0], (p3[0] - p2[0]) * (point.y - p3[1])))
-                / denominator)
-                .value;
-            let b = (((p3[1] - p1[1])
-                .mul_add(point.x - p3[0], (p1[0] - p3[0]) * (point.y - p3[1])))
-                / denominator)
-                .value;
-            let c = 1. - a - b;
-
-            if (0. ..=1.).contains(&a) && (0. ..=1.).contains(&b) && (0. ..=1.).contains(&c) {
-                in_polygon = true;
-                break;
+        for (p1, p2) in self.points.iter().zip(self.points.iter().cycle().skip(1)) {
+            let (x1, y1) = (p1.x.get::<meter>(), p1.y.get::<meter>());
+            let (x2, y2) = (p2.x.get::<meter>(), p2.y.get::<meter>());
+            // points on the outline are considered inside
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let cross = dx.mul_add(py - y1, -(dy * (px - x1)));
+            let dot = dx.mul_add(px - x1, dy * (py - y1));
+            let length_sq = dx.mul_add(dx, dy * dy);
+            if cross.abs() <= 1e-12 * length_sq && (0.0..=length_sq).contains(&dot) {
+                return true;
+            }
+            // count crossings of a ray from the point towards +x with the edge
+            if (y1 > py) != (y2 > py) && px < (dx * (py - y1)).mul_add(1.0 / dy, x1) {
+                in_polygon = !in_polygon;
             }
         }
         in_polygon
@@ -330,8 +486,11 @@ impl Apodize for PolygonConfig {
 
 /// Configuration data for a Gaussian aperture.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct GaussianConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "(f64, f64)"))]
     sigma: (Length, Length),
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     center: Point2<Length>,
     aperture_type: ApertureType,
 }
@@ -381,8 +540,84 @@ impl Apodize for GaussianConfig {
         transmission
     }
 }
+/// Configuration data for a soft-edged (apodizing) circular aperture.
+///
+/// The transmission follows a radial Gaussian profile `exp(-2 r² / w²)`, where `w` is the radius at which the
+/// transmission has dropped to 1/e². Beyond the cutoff radius, the aperture is fully opaque. Such an aperture avoids
+/// the hard edges of binary apertures, e.g. for modelling serrated apertures in high-power laser chains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct GaussianApodizerConfig {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    cutoff_radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
+    center: Point2<Length>,
+    aperture_type: ApertureType,
+}
+impl GaussianApodizerConfig {
+    /// Create a new [`GaussianApodizerConfig`] from the given 1/e² transmission radius, cutoff radius and center point.
+    ///
+    /// By default the aperture has the aperture type [`ApertureType::Hole`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the radii are not positive and finite or the center point is not finite.
+    pub fn new(radius: Length, cutoff_radius: Length, center: Point2<Length>) -> OpmResult<Self> {
+        if !radius.is_normal() || radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "radius must be positive and finite".into(),
+            ));
+        }
+        if !cutoff_radius.is_normal() || cutoff_radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "cutoff radius must be positive and finite".into(),
+            ));
+        }
+        if !center.x.is_finite() || !center.y.is_finite() {
+            return Err(OpossumError::Other("center point must be finite".into()));
+        }
+        Ok(Self {
+            radius,
+            cutoff_radius,
+            center,
+            aperture_type: ApertureType::default(),
+        })
+    }
+    /// Returns the 1/e² transmission radius of this [`GaussianApodizerConfig`].
+    #[must_use]
+    pub const fn radius(&self) -> Length {
+        self.radius
+    }
+    /// Returns the cutoff radius of this [`GaussianApodizerConfig`].
+    #[must_use]
+    pub const fn cutoff_radius(&self) -> Length {
+        self.cutoff_radius
+    }
+}
+impl Apodize for GaussianApodizerConfig {
+    fn set_aperture_type(&mut self, aperture_type: ApertureType) {
+        self.aperture_type = aperture_type;
+    }
+    fn apodize(&self, point: &Point2<Length>) -> f64 {
+        let dx = point.x - self.center.x;
+        let dy = point.y - self.center.y;
+        let r_sq = dx * dx + dy * dy;
+        let mut transmission = if r_sq <= self.cutoff_radius * self.cutoff_radius {
+            (-2.0 * (r_sq / (self.radius * self.radius)).get::<ratio>()).exp()
+        } else {
+            0.0
+        };
+        if matches!(self.aperture_type, ApertureType::Obstruction) {
+            transmission = 1.0 - transmission;
+        }
+        transmission
+    }
+}
 /// Configuration of an aperture stack
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct StackConfig {
     apertures: Vec<Aperture>,
     aperture_type: ApertureType,
@@ -415,16 +650,10 @@ impl Apodize for StackConfig {
         transmission
     }
 }
-fn plot_circle(conf: &CircleConfig) -> Vec<PlotSeries> {
+fn plot_circle(center: &Point2<Length>, radius: Length) -> Vec<PlotSeries> {
     let circle_points = ellipse(
-        (
-            conf.center.x.get::<millimeter>(),
-            conf.center.y.get::<millimeter>(),
-        ),
-        (
-            conf.radius.get::<millimeter>(),
-            conf.radius.get::<millimeter>(),
-        ),
+        (center.x.get::<millimeter>(), center.y.get::<millimeter>()),
+        (radius.get::<millimeter>(), radius.get::<millimeter>()),
         100,
     )
     .unwrap();
@@ -452,7 +681,14 @@ impl Plottable for Aperture {
         let plt_series_opt = match plt_type {
             PlotType::Line2D(_) | PlotType::Scatter2D(_) => match self {
                 Self::None => None,
-                Self::BinaryCircle(conf) => Some(plot_circle(conf)),
+                Self::BinaryCircle(conf) => Some(plot_circle(&conf.center, conf.radius)),
+                Self::BinaryAnnulus(conf) => {
+                    let mut series = plot_circle(&conf.center, conf.outer_radius);
+                    if conf.inner_radius.is_normal() {
+                        series.extend(plot_circle(&conf.center, conf.inner_radius));
+                    }
+                    Some(series)
+                }
                 Self::BinaryRectangle(conf) => {
                     let center_x = conf.center.x.get::<millimeter>();
                     let center_y = conf.center.y.get::<millimeter>();
@@ -483,7 +719,31 @@ impl Plottable for Aperture {
                         series_label,
                     )])
                 }
-
+                Self::BinaryEllipse(conf) => {
+                    let ellipse_points = ellipse(
+                        (
+                            conf.center.x.get::<millimeter>(),
+                            conf.center.y.get::<millimeter>(),
+                        ),
+                        (
+                            conf.semi_axes.0.get::<millimeter>(),
+                            conf.semi_axes.1.get::<millimeter>(),
+                        ),
+                        100,
+                    )?;
+                    let xy_data = Matrix2xX::from_vec(
+                        ellipse_points
+                            .iter()
+                            .flat_map(|p| vec![p.x, p.y])
+                            .collect::<Vec<f64>>(),
+                    )
+                    .transpose();
+                    Some(vec![PlotSeries::new(
+                        &PlotData::Dim2 { xy_data },
+                        RGBAColor(0, 0, 0, 1.),
+                        Some("Aperture".to_owned()),
+                    )])
+                }
                 Self::BinaryPolygon(conf) => {
                     let mut xy_data = MatrixXx2::from_element(conf.points.len(), 0.);
                     for (row, p) in conf.points.iter().enumerate() {
@@ -521,6 +781,11 @@ impl Plottable for Aperture {
                         Some("Gaussian Aperture 2-sigma".to_owned()),
                     )])
                 }
+                Self::GaussianApodizer(conf) => {
+                    let mut series = plot_circle(&conf.center, conf.radius);
+                    series.extend(plot_circle(&conf.center, conf.cutoff_radius));
+                    Some(series)
+                }
                 Self::Stack(conf) => {
                     let mut aperture_series_vec =
                         Vec::<PlotSeries>::with_capacity(conf.apertures.len());
@@ -599,11 +864,25 @@ mod test {
         assert!(RectangleConfig::new(meter!(2.0), meter!(1.0), p).is_err());
     }
     #[test]
+    fn ellipse_config() {
+        let p = meter!(0.0, 0.0);
+        let e = EllipseConfig::new((meter!(2.0), meter!(1.0)), p).unwrap();
+        assert_eq!(e.semi_axes(), (meter!(2.0), meter!(1.0)));
+        assert!(EllipseConfig::new((meter!(0.0), meter!(1.0)), p).is_err());
+        assert!(EllipseConfig::new((meter!(-1.0), meter!(1.0)), p).is_err());
+        assert!(EllipseConfig::new((meter!(1.0), meter!(f64::NAN)), p).is_err());
+        assert!(EllipseConfig::new((meter!(1.0), meter!(f64::INFINITY)), p).is_err());
+        let p = meter!(f64::NAN, 0.0);
+        assert!(EllipseConfig::new((meter!(1.0), meter!(1.0)), p).is_err());
+    }
+    #[test]
     fn polygon_config() {
         let ok_points = vec![meter!(0.0, 0.0), meter!(2.0, 0.0), meter!(1.0, 1.0)];
         assert!(PolygonConfig::new(ok_points).is_ok());
         let too_little_points = vec![meter!(0.0, 0.0), meter!(2.0, 0.0)];
         assert!(PolygonConfig::new(too_little_points).is_err());
+        let non_finite_points = vec![meter!(0.0, 0.0), meter!(2.0, 0.0), meter!(1.0, f64::NAN)];
+        assert!(PolygonConfig::new(non_finite_points).is_err());
     }
     #[test]
     fn gaussian_config() {
@@ -641,6 +920,38 @@ mod test {
         assert_eq!(ap.apodization_factor(&meter!(0.0, 0.0)), 1.0);
     }
     #[test]
+    fn annulus_config() {
+        let center = meter!(0.0, 0.0);
+        assert!(AnnulusConfig::new(meter!(0.0), meter!(1.0), center).is_ok());
+        assert!(AnnulusConfig::new(meter!(-0.1), meter!(1.0), center).is_err());
+        assert!(AnnulusConfig::new(meter!(f64::NAN), meter!(1.0), center).is_err());
+        assert!(AnnulusConfig::new(meter!(0.5), meter!(0.0), center).is_err());
+        assert!(AnnulusConfig::new(meter!(0.5), meter!(f64::INFINITY), center).is_err());
+        assert!(AnnulusConfig::new(meter!(1.0), meter!(1.0), center).is_err());
+        assert!(AnnulusConfig::new(meter!(1.5), meter!(1.0), center).is_err());
+        assert!(AnnulusConfig::new(meter!(0.5), meter!(1.0), meter!(f64::NAN, 0.0)).is_err());
+        let a = AnnulusConfig::new(meter!(0.5), meter!(1.0), center).unwrap();
+        assert_eq!(a.inner_radius(), meter!(0.5));
+        assert_eq!(a.outer_radius(), meter!(1.0));
+    }
+    #[test]
+    fn binary_annulus() {
+        let a = AnnulusConfig::new(meter!(0.5), meter!(1.0), meter!(1.0, 1.0)).unwrap();
+        let ap = Aperture::BinaryAnnulus(a);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.4, 1.0)), 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.5, 1.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 0.25)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(2.0, 1.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(2.1, 1.0)), 0.0);
+        let mut a = AnnulusConfig::new(meter!(0.5), meter!(1.0), meter!(1.0, 1.0)).unwrap();
+        a.set_aperture_type(ApertureType::Obstruction);
+        let ap = Aperture::BinaryAnnulus(a);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.75, 1.0)), 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(2.1, 1.0)), 1.0);
+    }
+    #[test]
     fn binary_rectangle() {
         let r = RectangleConfig::new(meter!(1.0), meter!(2.0), meter!(1.0, 1.0)).unwrap();
         let ap = Aperture::BinaryRectangle(r);
@@ -658,6 +969,21 @@ mod test {
         assert_eq!(ap.apodization_factor(&meter!(0.0, 0.0)), 1.0);
     }
     #[test]
+    fn binary_ellipse() {
+        let e = EllipseConfig::new((meter!(2.0), meter!(1.0)), meter!(1.0, 1.0)).unwrap();
+        let ap = Aperture::BinaryEllipse(e);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(3.0, 1.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 2.0)), 1.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 2.1)), 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(2.5, 1.8)), 0.0);
+        let mut e = EllipseConfig::new((meter!(2.0), meter!(1.0)), meter!(1.0, 1.0)).unwrap();
+        e.set_aperture_type(ApertureType::Obstruction);
+        let ap = Aperture::BinaryEllipse(e);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 2.1)), 1.0);
+    }
+    #[test]
     fn binary_polygon() {
         let poly = PolygonConfig::new(vec![
             meter!(0.0, 0.0),
@@ -681,6 +1007,88 @@ mod test {
         assert_eq!(ap.apodization_factor(&meter!(2.0, 1.0)), 1.0);
     }
     #[test]
+    fn polygon_concave() {
+        // U-shaped outline (counter-clockwise)
+        let outline = vec![
+            meter!(0.0, 0.0),
+            meter!(3.0, 0.0),
+            meter!(3.0, 3.0),
+            meter!(2.0, 3.0),
+            meter!(2.0, 1.0),
+            meter!(1.0, 1.0),
+            meter!(1.0, 3.0),
+            meter!(0.0, 3.0),
+        ];
+        let mut reversed = outline.clone();
+        reversed.reverse();
+        for points in [outline, reversed] {
+            let poly = PolygonConfig::new(points).unwrap();
+            assert!(poly.in_polygon(&meter!(0.5, 2.5)));
+            assert!(poly.in_polygon(&meter!(2.5, 2.5)));
+            assert!(poly.in_polygon(&meter!(1.5, 0.5)));
+            assert!(poly.in_polygon(&meter!(1.5, 1.0)));
+            assert!(poly.in_polygon(&meter!(3.0, 1.5)));
+            assert!(!poly.in_polygon(&meter!(1.5, 2.0)));
+            assert!(!poly.in_polygon(&meter!(1.5, 3.0)));
+            assert!(!poly.in_polygon(&meter!(-0.5, 1.0)));
+            assert!(!poly.in_polygon(&meter!(3.5, 1.0)));
+            // ray passes exactly through vertices
+            assert!(!poly.in_polygon(&meter!(-1.0, 3.0)));
+            assert!(!poly.in_polygon(&meter!(-1.0, 1.0)));
+            assert!(poly.in_polygon(&meter!(0.5, 1.0)));
+        }
+    }
+    #[test]
+    fn polygon_self_intersecting() {
+        // bow tie
+        let poly = PolygonConfig::new(vec![
+            meter!(0.0, 0.0),
+            meter!(2.0, 2.0),
+            meter!(2.0, 0.0),
+            meter!(0.0, 2.0),
+        ])
+        .unwrap();
+        assert!(poly.in_polygon(&meter!(0.2, 1.0)));
+        assert!(poly.in_polygon(&meter!(1.8, 1.0)));
+        assert!(!poly.in_polygon(&meter!(1.0, 0.2)));
+        assert!(!poly.in_polygon(&meter!(1.0, 1.8)));
+    }
+    #[test]
+    fn gaussian_apodizer_config() {
+        let center = meter!(0.0, 0.0);
+        assert!(GaussianApodizerConfig::new(meter!(0.0), meter!(1.0), center).is_err());
+        assert!(GaussianApodizerConfig::new(meter!(-1.0), meter!(1.0), center).is_err());
+        assert!(GaussianApodizerConfig::new(meter!(f64::NAN), meter!(1.0), center).is_err());
+        assert!(GaussianApodizerConfig::new(meter!(1.0), meter!(0.0), center).is_err());
+        assert!(GaussianApodizerConfig::new(meter!(1.0), meter!(f64::INFINITY), center).is_err());
+        assert!(
+            GaussianApodizerConfig::new(meter!(1.0), meter!(1.0), meter!(f64::NAN, 0.0)).is_err()
+        );
+        let g = GaussianApodizerConfig::new(meter!(1.0), meter!(2.0), center).unwrap();
+        assert_eq!(g.radius(), meter!(1.0));
+        assert_eq!(g.cutoff_radius(), meter!(2.0));
+    }
+    #[test]
+    fn gaussian_apodizer() {
+        let g = GaussianApodizerConfig::new(meter!(1.0), meter!(1.5), meter!(1.0, 1.0)).unwrap();
+        let ap = Aperture::GaussianApodizer(g);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 1.0);
+        assert_relative_eq!(ap.apodization_factor(&meter!(2.0, 1.0)), f64::exp(-2.0));
+        assert_relative_eq!(ap.apodization_factor(&meter!(1.0, 0.5)), f64::exp(-0.5));
+        assert!(ap.apodization_factor(&meter!(1.0, 2.4)) > 0.0);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 2.6)), 0.0);
+        let mut g =
+            GaussianApodizerConfig::new(meter!(1.0), meter!(1.5), meter!(1.0, 1.0)).unwrap();
+        g.set_aperture_type(ApertureType::Obstruction);
+        let ap = Aperture::GaussianApodizer(g);
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 1.0)), 0.0);
+        assert_relative_eq!(
+            ap.apodization_factor(&meter!(2.0, 1.0)),
+            1.0 - f64::exp(-2.0)
+        );
+        assert_eq!(ap.apodization_factor(&meter!(1.0, 2.6)), 1.0);
+    }
+    #[test]
     fn gaussian() {
         let g = GaussianConfig::new((meter!(1.0), meter!(1.0)), meter!(1.0, 1.0)).unwrap();
         let ap = Aperture::Gaussian(g);
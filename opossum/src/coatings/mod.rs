@@ -7,13 +7,18 @@ use nalgebra::Vector3;
 mod constant_r;
 mod fresnel;
 mod ideal_ar;
+mod multilayer;
+mod tabulated;
 
 pub use constant_r::ConstantR;
 pub use fresnel::Fresnel;
 pub use ideal_ar::IdealAR;
+pub use multilayer::{MultilayerStack, StackResponse};
 use serde::{Deserialize, Serialize};
+pub use tabulated::TabulatedCoating;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Enum for different types of optical coatings
 pub enum CoatingType {
     /// Perfect anti-reflective coating. Reflectivity is always 0.0
@@ -26,6 +31,10 @@ pub enum CoatingType {
     },
     /// Fesnel reflection (e.g. uncaoted surface)
     Fresnel,
+    /// Dielectric thin-film coating consisting of a stack of layers
+    Multilayer(MultilayerStack),
+    /// Coating with tabulated (e.g. measured) reflectance depending on wavelength and angle of incidence
+    Tabulated(TabulatedCoating),
 }
 impl CoatingType {
     /// Calculate the reflectivity of a coating hit by a given [`Ray`] on a [`GeoSurface`](crate::surface::geo_surface::GeoSurface)
@@ -53,6 +62,8 @@ impl CoatingType {
                 let c = Fresnel;
                 Ok(c.calc_reflectivity(incoming_ray, surface_normal, n2))
             }
+            Self::Multilayer(stack) => stack.calc_reflectivity(incoming_ray, surface_normal, n2),
+            Self::Tabulated(c) => Ok(c.calc_reflectivity(incoming_ray, surface_normal, n2)),
         }
     }
 }
@@ -0,0 +1,371 @@
+#![warn(missing_docs)]
+use super::CoatingType;
+use crate::{
+    error::{OpmResult, OpossumError},
+    ray::Ray,
+    refractive_index::RefractiveIndexType,
+};
+use nalgebra::{Matrix2, Vector2, Vector3};
+use num::Complex;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use uom::si::{
+    angle::radian,
+    f64::{Angle, Length},
+    length::meter,
+};
+
+/// Reflectance and transmittance of a [`MultilayerStack`] for s- and p-polarized light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StackResponse {
+    /// reflectance for s-polarized light
+    pub r_s: f64,
+    /// reflectance for p-polarized light
+    pub r_p: f64,
+    /// transmittance for s-polarized light
+    pub t_s: f64,
+    /// transmittance for p-polarized light
+    pub t_p: f64,
+}
+impl StackResponse {
+    /// Returns the reflectance for unpolarized light (average of s- and p-polarization).
+    #[must_use]
+    pub fn reflectance(&self) -> f64 {
+        f64::midpoint(self.r_s, self.r_p)
+    }
+    /// Returns the transmittance for unpolarized light (average of s- and p-polarization).
+    #[must_use]
+    pub fn transmittance(&self) -> f64 {
+        f64::midpoint(self.t_s, self.t_p)
+    }
+}
+/// Dielectric thin-film coating consisting of a stack of layers.
+///
+/// Each layer is given by its refractive index and its (physical) thickness. The first layer is adjacent to the
+/// incident medium, the last layer to the substrate (i.e. the medium following the surface). Reflectance and
+/// transmittance are calculated using the characteristic matrix method (see e.g. H. A. Macleod, "Thin-Film Optical
+/// Filters"). Absorption within the layers is neglected.
+///
+/// During ray tracing, an (50/50) unpolarized beam is assumed (as for the [`Fresnel`](super::Fresnel) coating).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct MultilayerStack {
+    #[cfg_attr(
+        feature = "json_schema",
+        schemars(with = "Vec<(RefractiveIndexType, f64)>")
+    )]
+    layers: Vec<(RefractiveIndexType, Length)>,
+}
+#[derive(Clone, Copy)]
+enum Polarization {
+    S,
+    P,
+}
+impl MultilayerStack {
+    /// Create a new [`MultilayerStack`] from the given layers (refractive index, thickness).
+    ///
+    /// An empty stack represents an uncoated surface.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any layer thickness is negative or not finite.
+    pub fn new(layers: Vec<(RefractiveIndexType, Length)>) -> OpmResult<Self> {
+        if layers
+            .iter()
+            .any(|(_, thickness)| thickness.is_sign_negative() || !thickness.is_finite())
+        {
+            return Err(OpossumError::Other(
+                "layer thickness must be >= 0.0 and finite".into(),
+            ));
+        }
+        Ok(Self { layers })
+    }
+    /// Returns the layers of this [`MultilayerStack`].
+    #[must_use]
+    pub fn layers(&self) -> &[(RefractiveIndexType, Length)] {
+        &self.layers
+    }
+    /// Calculate reflectance and transmittance of this [`MultilayerStack`] for s- and p-polarized light.
+    ///
+    /// The light with the given `wavelength` (in vacuum) hits the stack from the incident medium (refractive index
+    /// `n_incident`) under the given angle of incidence. The stack is deposited on a substrate with the refractive
+    /// index `n_substrate`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the wavelength is not positive and finite.
+    ///   - the angle of incidence is not within (-90°, 90°).
+    ///   - any refractive index is not positive and finite or cannot be calculated for the given wavelength.
+    pub fn calc_response(
+        &self,
+        wavelength: Length,
+        angle: Angle,
+        n_incident: f64,
+        n_substrate: f64,
+    ) -> OpmResult<StackResponse> {
+        if !wavelength.is_normal() || wavelength.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "wavelength must be positive and finite".into(),
+            ));
+        }
+        let angle = angle.get::<radian>();
+        if !angle.is_finite() || angle.abs() >= PI / 2.0 {
+            return Err(OpossumError::Other(
+                "angle of incidence must be within (-90°, 90°)".into(),
+            ));
+        }
+        let layers = self
+            .layers
+            .iter()
+            .map(|(index, thickness)| {
+                Ok((
+                    index.get_refractive_index(wavelength)?,
+                    thickness.get::<meter>(),
+                ))
+            })
+            .collect::<OpmResult<Vec<(f64, f64)>>>()?;
+        if [n_incident, n_substrate]
+            .iter()
+            .chain(layers.iter().map(|(n, _)| n))
+            .any(|n| !n.is_normal() || n.is_sign_negative())
+        {
+            return Err(OpossumError::Other(
+                "refractive indices must be positive and finite".into(),
+            ));
+        }
+        let k0 = 2.0 * PI / wavelength.get::<meter>();
+        // invariant of Snell's law: n * sin(theta)
+        let n_sin = n_incident * angle.sin();
+        let response = |polarization| {
+            let eta_incident = admittance(n_incident, n_sin, polarization);
+            let eta_substrate = admittance(n_substrate, n_sin, polarization);
+            let matrix = layers
+                .iter()
+                .fold(Matrix2::identity(), |matrix, (n, thickness)| {
+                    let eta = admittance(*n, n_sin, polarization);
+                    let delta = cos_theta(*n, n_sin) * (k0 * n * thickness);
+                    let layer_matrix = Matrix2::new(
+                        delta.cos(),
+                        Complex::<f64>::i() * delta.sin() / eta,
+                        Complex::<f64>::i() * eta * delta.sin(),
+                        delta.cos(),
+                    );
+                    matrix * layer_matrix
+                });
+            let bc = matrix * Vector2::new(Complex::new(1.0, 0.0), eta_substrate);
+            let denominator = eta_incident * bc.x + bc.y;
+            let r = (eta_incident * bc.x - bc.y) / denominator;
+            let t = 4.0 * eta_incident.re * eta_substrate.re / denominator.norm_sqr();
+            (r.norm_sqr(), t)
+        };
+        let (r_s, t_s) = response(Polarization::S);
+        let (r_p, t_p) = response(Polarization::P);
+        Ok(StackResponse { r_s, r_p, t_s, t_p })
+    }
+    /// Calculate the reflectivity of this [`MultilayerStack`] for an incoming [`Ray`] on a surface with the given
+    /// `surface_normal` at the intersection point and the refractive index `n2` of the following medium (substrate).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the refractive indices of the layers cannot be calculated.
+    pub fn calc_reflectivity(
+        &self,
+        incoming_ray: &Ray,
+        surface_normal: Vector3<f64>,
+        n2: f64,
+    ) -> OpmResult<f64> {
+        // Note: invert surface normal, since it is the "output_1" direction.
+        let alpha = incoming_ray.direction().angle(&(-1.0 * surface_normal));
+        let response = self.calc_response(
+            incoming_ray.wavelength(),
+            Angle::new::<radian>(alpha),
+            incoming_ray.refractive_index(),
+            n2,
+        )?;
+        Ok(response.reflectance())
+    }
+}
+/// Cosine of the (complex) propagation angle in a medium with refractive index `n`.
+fn cos_theta(n: f64, n_sin: f64) -> Complex<f64> {
+    Complex::new((n_sin / n).mul_add(-(n_sin / n), 1.0), 0.0).sqrt()
+}
+/// Tilted optical admittance (in units of the free-space admittance) of a medium with refractive index `n`.
+fn admittance(n: f64, n_sin: f64, polarization: Polarization) -> Complex<f64> {
+    let cos_theta = cos_theta(n, n_sin);
+    match polarization {
+        Polarization::S => cos_theta * n,
+        Polarization::P => Complex::new(n, 0.0) / cos_theta,
+    }
+}
+impl From<MultilayerStack> for CoatingType {
+    fn from(coating: MultilayerStack) -> Self {
+        Self::Multilayer(coating)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        coatings::{Coating, Fresnel},
+        degree, joule, nanometer,
+        refractive_index::RefrIndexConst,
+    };
+    use approx::assert_abs_diff_eq;
+    use nalgebra::vector;
+
+    fn layer(n: f64, thickness: Length) -> (RefractiveIndexType, Length) {
+        (RefrIndexConst::new(n).unwrap().into(), thickness)
+    }
+    /// Layer with an optical thickness of a quarter of the given wavelength.
+    fn quarter_wave(n: f64, wavelength: Length) -> (RefractiveIndexType, Length) {
+        layer(n, wavelength / (4.0 * n))
+    }
+    #[test]
+    fn new() {
+        assert!(MultilayerStack::new(vec![layer(1.5, nanometer!(-1.0))]).is_err());
+        assert!(MultilayerStack::new(vec![layer(1.5, nanometer!(f64::NAN))]).is_err());
+        assert!(MultilayerStack::new(vec![layer(1.5, nanometer!(f64::INFINITY))]).is_err());
+        let stack = MultilayerStack::new(vec![layer(1.5, nanometer!(100.0))]).unwrap();
+        assert_eq!(stack.layers().len(), 1);
+        assert!(MultilayerStack::new(vec![]).is_ok());
+    }
+    #[test]
+    fn calc_response_wrong_params() {
+        let stack = MultilayerStack::new(vec![]).unwrap();
+        assert!(
+            stack
+                .calc_response(nanometer!(0.0), degree!(0.0), 1.0, 1.5)
+                .is_err()
+        );
+        assert!(
+            stack
+                .calc_response(nanometer!(f64::NAN), degree!(0.0), 1.0, 1.5)
+                .is_err()
+        );
+        assert!(
+            stack
+                .calc_response(nanometer!(1000.0), degree!(90.0), 1.0, 1.5)
+                .is_err()
+        );
+        assert!(
+            stack
+                .calc_response(nanometer!(1000.0), degree!(f64::NAN), 1.0, 1.5)
+                .is_err()
+        );
+        assert!(
+            stack
+                .calc_response(nanometer!(1000.0), degree!(0.0), 0.0, 1.5)
+                .is_err()
+        );
+        assert!(
+            stack
+                .calc_response(nanometer!(1000.0), degree!(0.0), 1.0, f64::INFINITY)
+                .is_err()
+        );
+    }
+    #[test]
+    fn uncoated_equals_fresnel() {
+        let stack = MultilayerStack::new(vec![]).unwrap();
+        let response = stack
+            .calc_response(nanometer!(1000.0), degree!(0.0), 1.0, 1.5)
+            .unwrap();
+        assert_abs_diff_eq!(response.r_s, 0.04, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.r_p, 0.04, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.t_s, 0.96, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.t_p, 0.96, epsilon = 1e-12);
+        let mut ray = Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap();
+        ray.set_direction(vector![0.0, 1.0, 1.0]).unwrap();
+        let surface_normal = vector![0.0, 0.0, -1.0];
+        assert_abs_diff_eq!(
+            stack.calc_reflectivity(&ray, surface_normal, 1.5).unwrap(),
+            Fresnel.calc_reflectivity(&ray, surface_normal, 1.5),
+            epsilon = 1e-12
+        );
+    }
+    #[test]
+    fn quarter_wave_ar() {
+        let wavelength = nanometer!(1053.0);
+        let stack = MultilayerStack::new(vec![quarter_wave(1.5_f64.sqrt(), wavelength)]).unwrap();
+        let response = stack
+            .calc_response(wavelength, degree!(0.0), 1.0, 1.5)
+            .unwrap();
+        assert_abs_diff_eq!(response.reflectance(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.transmittance(), 1.0, epsilon = 1e-12);
+        // off design wavelength, the coating still reduces reflection compared to the uncoated surface
+        let response = stack
+            .calc_response(nanometer!(800.0), degree!(0.0), 1.0, 1.5)
+            .unwrap();
+        assert!(response.reflectance() > 0.0);
+        assert!(response.reflectance() < 0.04);
+    }
+    #[test]
+    fn quarter_wave_hr() {
+        let wavelength = nanometer!(1053.0);
+        let (n_h, n_l, n_sub) = (2.3, 1.45, 1.5);
+        let nr_of_pairs = 8;
+        let mut layers = Vec::new();
+        for _ in 0..nr_of_pairs {
+            layers.push(quarter_wave(n_h, wavelength));
+            layers.push(quarter_wave(n_l, wavelength));
+        }
+        let stack = MultilayerStack::new(layers).unwrap();
+        let response = stack
+            .calc_response(wavelength, degree!(0.0), 1.0, n_sub)
+            .unwrap();
+        let y = (n_h / n_l).powi(2 * nr_of_pairs) * n_sub;
+        let expected = ((1.0 - y) / (1.0 + y)).powi(2);
+        assert_abs_diff_eq!(response.r_s, expected, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.r_p, expected, epsilon = 1e-12);
+        assert!(response.reflectance() > 0.99);
+    }
+    #[test]
+    fn energy_conservation() {
+        let stack = MultilayerStack::new(vec![
+            layer(2.3, nanometer!(120.0)),
+            layer(1.45, nanometer!(190.0)),
+            layer(2.3, nanometer!(80.0)),
+        ])
+        .unwrap();
+        for angle in [0.0, 20.0, 45.0, 70.0] {
+            let response = stack
+                .calc_response(nanometer!(1000.0), degree!(angle), 1.0, 1.5)
+                .unwrap();
+            assert_abs_diff_eq!(response.r_s + response.t_s, 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(response.r_p + response.t_p, 1.0, epsilon = 1e-12);
+        }
+        // s- and p-polarization differ at oblique incidence
+        let response = stack
+            .calc_response(nanometer!(1000.0), degree!(45.0), 1.0, 1.5)
+            .unwrap();
+        assert!((response.r_s - response.r_p).abs() > 1e-3);
+    }
+    #[test]
+    fn total_internal_reflection() {
+        let stack = MultilayerStack::new(vec![layer(1.45, nanometer!(500.0))]).unwrap();
+        let response = stack
+            .calc_response(nanometer!(1000.0), degree!(60.0), 1.5, 1.0)
+            .unwrap();
+        assert_abs_diff_eq!(response.r_s, 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(response.r_p, 1.0, epsilon = 1e-12);
+        assert_eq!(response.t_s, 0.0);
+        assert_eq!(response.t_p, 0.0);
+    }
+    #[test]
+    fn coating_type() {
+        let wavelength = nanometer!(1000.0);
+        let stack = MultilayerStack::new(vec![quarter_wave(1.5_f64.sqrt(), wavelength)]).unwrap();
+        let coating: CoatingType = stack.into();
+        assert!(matches!(coating, CoatingType::Multilayer(_)));
+        let serialized = ron::to_string(&coating).unwrap();
+        let coating: CoatingType = ron::from_str(&serialized).unwrap();
+        let ray = Ray::origin_along_z(wavelength, joule!(1.0)).unwrap();
+        assert_abs_diff_eq!(
+            coating
+                .calc_reflectivity(&ray, vector![0.0, 0.0, -1.0], 1.5)
+                .unwrap(),
+            0.0,
+            epsilon = 1e-12
+        );
+    }
+}
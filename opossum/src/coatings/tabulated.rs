@@ -0,0 +1,461 @@
+#![warn(missing_docs)]
+//! Tabulated coating model
+//!
+//! This model interpolates measured reflectance data given as a function of wavelength and angle of incidence.
+use super::{Coating, CoatingType};
+use crate::{
+    error::{OpmResult, OpossumError},
+    ray::Ray,
+};
+use csv::{ReaderBuilder, StringRecord, Trim};
+use log::warn;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use uom::si::{
+    angle::{degree, radian},
+    f64::{Angle, Length},
+    length::nanometer,
+};
+
+/// Coating model based on a table of measured reflectance values.
+///
+/// The table consists of a list of wavelengths, a list of angles of incidence and the corresponding reflectance for
+/// each combination (one row per wavelength, one column per angle). Values in between are bilinearly interpolated.
+/// Outside the tabulated range, the boundary values are used. In this case, a warning is issued once.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct TabulatedCoating {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<f64>"))]
+    wavelengths: Vec<Length>,
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<f64>"))]
+    angles: Vec<Angle>,
+    reflectance: Vec<Vec<f64>>,
+    #[serde(skip)]
+    out_of_range_warned: Arc<AtomicBool>,
+}
+impl TabulatedCoating {
+    /// Create a new tabulated coating model.
+    ///
+    /// The `reflectance` table must contain one row for each wavelength with one value for each angle of incidence.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the wavelengths or angles are empty.
+    ///   - the table is not rectangular or does not match the number of wavelengths and angles.
+    ///   - the wavelengths are not positive, finite and strictly increasing.
+    ///   - the angles are not within [0°, 90°] and strictly increasing.
+    ///   - the reflectance values are not within [0.0, 1.0].
+    pub fn new(
+        wavelengths: Vec<Length>,
+        angles: Vec<Angle>,
+        reflectance: Vec<Vec<f64>>,
+    ) -> OpmResult<Self> {
+        if wavelengths.is_empty() || angles.is_empty() {
+            return Err(OpossumError::Other(
+                "tabulated data must not be empty".into(),
+            ));
+        }
+        if reflectance.len() != wavelengths.len()
+            || reflectance.iter().any(|row| row.len() != angles.len())
+        {
+            return Err(OpossumError::Other(
+                "reflectance table must contain one row per wavelength and one column per angle"
+                    .into(),
+            ));
+        }
+        if wavelengths
+            .iter()
+            .any(|w| !w.is_normal() || w.is_sign_negative())
+        {
+            return Err(OpossumError::Other(
+                "wavelengths must be positive and finite".into(),
+            ));
+        }
+        if wavelengths.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(OpossumError::Other(
+                "wavelengths must be strictly increasing".into(),
+            ));
+        }
+        if angles
+            .iter()
+            .any(|a| !(0.0..=90.0).contains(&a.get::<degree>()))
+        {
+            return Err(OpossumError::Other(
+                "angles of incidence must be within [0°, 90°]".into(),
+            ));
+        }
+        if angles.windows(2).any(|a| a[1] <= a[0]) {
+            return Err(OpossumError::Other(
+                "angles of incidence must be strictly increasing".into(),
+            ));
+        }
+        if reflectance
+            .iter()
+            .flatten()
+            .any(|r| !(0.0..=1.0).contains(r))
+        {
+            return Err(OpossumError::Other(
+                "reflectance values must be within [0.0, 1.0]".into(),
+            ));
+        }
+        Ok(Self {
+            wavelengths,
+            angles,
+            reflectance,
+            out_of_range_warned: Arc::default(),
+        })
+    }
+    /// Create a new tabulated coating model from a CSV file.
+    ///
+    /// The first row contains the angles of incidence in degrees (the first cell of this row is ignored and might
+    /// contain a label). Each following row starts with the wavelength in nanometers followed by the reflectance values
+    /// for the respective angles, e.g.
+    ///
+    /// ```text
+    /// wl\aoi, 0.0,  45.0
+    /// 1000.0, 0.99, 0.95
+    /// 1100.0, 0.98, 0.90
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the file could not be opened or read.
+    ///   - the file contains malformed lines.
+    ///   - the data is not valid (see [`TabulatedCoating::new`]).
+    pub fn from_csv(path: &Path) -> OpmResult<Self> {
+        let file = File::open(path).map_err(|e| OpossumError::Other(e.to_string()))?;
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(file);
+        let mut angles: Option<Vec<Angle>> = None;
+        let mut wavelengths = Vec::new();
+        let mut reflectance = Vec::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| OpossumError::Other(e.to_string()))?;
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            let values = parse_record(&record, line)?;
+            if angles.is_none() {
+                angles = Some(values.into_iter().map(Angle::new::<degree>).collect());
+            } else {
+                wavelengths.push(Length::new::<nanometer>(values[0]));
+                reflectance.push(values[1..].to_vec());
+            }
+        }
+        let Some(angles) = angles else {
+            return Err(OpossumError::Other("no data found in file".into()));
+        };
+        Self::new(wavelengths, angles, reflectance)
+    }
+    /// Returns the tabulated wavelengths of this [`TabulatedCoating`].
+    #[must_use]
+    pub fn wavelengths(&self) -> &[Length] {
+        &self.wavelengths
+    }
+    /// Returns the tabulated angles of incidence of this [`TabulatedCoating`].
+    #[must_use]
+    pub fn angles(&self) -> &[Angle] {
+        &self.angles
+    }
+    /// Get the (interpolated) reflectance for the given wavelength and angle of incidence.
+    #[must_use]
+    pub fn reflectance(&self, wavelength: Length, angle: Angle) -> f64 {
+        let first_wvl = self.wavelengths[0];
+        let last_wvl = self.wavelengths[self.wavelengths.len() - 1];
+        let first_angle = self.angles[0];
+        let last_angle = self.angles[self.angles.len() - 1];
+        if (wavelength < first_wvl
+            || wavelength > last_wvl
+            || angle < first_angle
+            || angle > last_angle)
+            && !self.out_of_range_warned.swap(true, Ordering::Relaxed)
+        {
+            warn!(
+                "wavelength {:.3} nm or angle of incidence {:.3}° outside tabulated coating range ({:.3} nm - {:.3} nm, {:.3}° - {:.3}°) ... using boundary values",
+                wavelength.get::<nanometer>(),
+                angle.get::<degree>(),
+                first_wvl.get::<nanometer>(),
+                last_wvl.get::<nanometer>(),
+                first_angle.get::<degree>(),
+                last_angle.get::<degree>()
+            );
+        }
+        let (i0, i1, t) = bracket(&self.wavelengths, wavelength.max(first_wvl).min(last_wvl));
+        let (j0, j1, u) = bracket(&self.angles, angle.max(first_angle).min(last_angle));
+        let row0 = u.mul_add(
+            self.reflectance[i0][j1] - self.reflectance[i0][j0],
+            self.reflectance[i0][j0],
+        );
+        let row1 = u.mul_add(
+            self.reflectance[i1][j1] - self.reflectance[i1][j0],
+            self.reflectance[i1][j0],
+        );
+        t.mul_add(row1 - row0, row0)
+    }
+    /// Get the (interpolated) transmittance (1.0 - reflectance) for the given wavelength and angle of incidence.
+    #[must_use]
+    pub fn transmittance(&self, wavelength: Length, angle: Angle) -> f64 {
+        1.0 - self.reflectance(wavelength, angle)
+    }
+}
+/// Find the table indices enclosing the (already clamped) value `x` and the relative position in between.
+fn bracket<T>(table: &[T], x: T) -> (usize, usize, f64)
+where
+    T: PartialOrd + Copy + std::ops::Sub<Output = T> + std::ops::Div<Output = uom::si::f64::Ratio>,
+{
+    let idx = table.partition_point(|v| *v < x);
+    if idx == 0 {
+        return (0, 0, 0.0);
+    }
+    let t = ((x - table[idx - 1]) / (table[idx] - table[idx - 1])).value;
+    (idx - 1, idx, t)
+}
+fn parse_record(record: &StringRecord, line: usize) -> OpmResult<Vec<f64>> {
+    let mut values = record.iter();
+    // The first cell of the header line might contain a label
+    let first = values.next().unwrap_or_default();
+    let first = if line == 0 {
+        first.parse::<f64>().ok()
+    } else {
+        Some(first.parse::<f64>().map_err(|_| {
+            OpossumError::Other(format!("could not parse wavelength in line {}", line + 1))
+        })?)
+    };
+    let values = values
+        .map(|value| {
+            value.parse::<f64>().map_err(|_| {
+                OpossumError::Other(format!("could not parse value in line {}", line + 1))
+            })
+        })
+        .collect::<OpmResult<Vec<f64>>>()?;
+    Ok(first.into_iter().chain(values).collect())
+}
+impl Coating for TabulatedCoating {
+    fn calc_reflectivity(&self, incoming_ray: &Ray, surface_normal: Vector3<f64>, _n2: f64) -> f64 {
+        // Note: invert surface normal, since it is the "output_1" direction.
+        let alpha = incoming_ray.direction().angle(&(-1.0 * surface_normal));
+        self.reflectance(incoming_ray.wavelength(), Angle::new::<radian>(alpha))
+    }
+}
+impl From<TabulatedCoating> for CoatingType {
+    fn from(coating: TabulatedCoating) -> Self {
+        Self::Tabulated(coating)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{degree, joule, nanometer};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::vector;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn coating() -> TabulatedCoating {
+        TabulatedCoating::new(
+            vec![nanometer!(1000.0), nanometer!(1100.0)],
+            vec![degree!(0.0), degree!(40.0)],
+            vec![vec![0.9, 0.5], vec![0.7, 0.1]],
+        )
+        .unwrap()
+    }
+    #[test]
+    fn new() {
+        let wvls = vec![nanometer!(1000.0), nanometer!(1100.0)];
+        let angles = vec![degree!(0.0), degree!(40.0)];
+        let table = vec![vec![0.9, 0.5], vec![0.7, 0.1]];
+        assert!(TabulatedCoating::new(vec![], angles.clone(), vec![]).is_err());
+        assert!(TabulatedCoating::new(wvls.clone(), vec![], vec![vec![], vec![]]).is_err());
+        assert!(TabulatedCoating::new(wvls.clone(), angles.clone(), vec![vec![0.9, 0.5]]).is_err());
+        assert!(
+            TabulatedCoating::new(
+                wvls.clone(),
+                angles.clone(),
+                vec![vec![0.9, 0.5], vec![0.7]]
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                vec![nanometer!(1100.0), nanometer!(1000.0)],
+                angles.clone(),
+                table.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                vec![nanometer!(0.0), nanometer!(1000.0)],
+                angles.clone(),
+                table.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                wvls.clone(),
+                vec![degree!(40.0), degree!(0.0)],
+                table.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                wvls.clone(),
+                vec![degree!(-1.0), degree!(40.0)],
+                table.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                wvls.clone(),
+                vec![degree!(0.0), degree!(91.0)],
+                table.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            TabulatedCoating::new(
+                wvls.clone(),
+                angles.clone(),
+                vec![vec![0.9, 1.5], vec![0.7, 0.1]]
+            )
+            .is_err()
+        );
+        let c = TabulatedCoating::new(wvls.clone(), angles.clone(), table).unwrap();
+        assert_eq!(c.wavelengths(), wvls.as_slice());
+        assert_eq!(c.angles(), angles.as_slice());
+    }
+    #[test]
+    fn reflectance() {
+        let c = coating();
+        assert_abs_diff_eq!(c.reflectance(nanometer!(1000.0), degree!(0.0)), 0.9);
+        assert_abs_diff_eq!(c.reflectance(nanometer!(1100.0), degree!(40.0)), 0.1);
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1000.0), degree!(20.0)),
+            0.7,
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(0.0)),
+            0.8,
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(20.0)),
+            0.55,
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(
+            c.transmittance(nanometer!(1050.0), degree!(20.0)),
+            0.45,
+            epsilon = 1e-12
+        );
+    }
+    #[test]
+    fn reflectance_out_of_range() {
+        let c = coating();
+        assert_abs_diff_eq!(c.reflectance(nanometer!(900.0), degree!(0.0)), 0.9);
+        assert_abs_diff_eq!(c.reflectance(nanometer!(1200.0), degree!(60.0)), 0.1);
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(80.0)),
+            0.3,
+            epsilon = 1e-12
+        );
+        assert!(c.out_of_range_warned.load(Ordering::Relaxed));
+    }
+    #[test]
+    fn reflectance_single_angle() {
+        let c = TabulatedCoating::new(
+            vec![nanometer!(1000.0), nanometer!(1100.0)],
+            vec![degree!(0.0)],
+            vec![vec![0.2], vec![0.4]],
+        )
+        .unwrap();
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(30.0)),
+            0.3,
+            epsilon = 1e-12
+        );
+    }
+    #[test]
+    fn calc_reflectivity() {
+        let c = coating();
+        let mut ray = Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap();
+        let surface_normal = vector![0.0, 0.0, -1.0];
+        assert_abs_diff_eq!(c.calc_reflectivity(&ray, surface_normal, 1.5), 0.9);
+        ray.set_direction(vector![0.0, f64::tan(20.0_f64.to_radians()), 1.0])
+            .unwrap();
+        assert_abs_diff_eq!(
+            c.calc_reflectivity(&ray, surface_normal, 1.5),
+            0.7,
+            epsilon = 1e-12
+        );
+        let coating_type: CoatingType = c.into();
+        assert_abs_diff_eq!(
+            coating_type
+                .calc_reflectivity(&ray, surface_normal, 1.5)
+                .unwrap(),
+            0.7,
+            epsilon = 1e-12
+        );
+    }
+    #[test]
+    fn from_csv() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "wl\\aoi, 0.0, 40.0").unwrap();
+        writeln!(file, "1000.0, 0.9, 0.5").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "1100.0, 0.7, 0.1").unwrap();
+        let c = TabulatedCoating::from_csv(file.path()).unwrap();
+        assert_eq!(c.wavelengths(), [nanometer!(1000.0), nanometer!(1100.0)]);
+        assert_eq!(c.angles(), [degree!(0.0), degree!(40.0)]);
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(20.0)),
+            0.55,
+            epsilon = 1e-12
+        );
+    }
+    #[test]
+    fn from_csv_invalid() {
+        assert!(TabulatedCoating::from_csv(Path::new("./invalid_file.csv")).is_err());
+        let file = NamedTempFile::new().unwrap();
+        assert!(TabulatedCoating::from_csv(file.path()).is_err());
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "wl\\aoi, 0.0, 40.0").unwrap();
+        writeln!(file, "1000.0, 0.9").unwrap();
+        assert!(TabulatedCoating::from_csv(file.path()).is_err());
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "wl\\aoi, 0.0, 40.0").unwrap();
+        writeln!(file, "1000.0, 0.9, abc").unwrap();
+        assert!(TabulatedCoating::from_csv(file.path()).is_err());
+    }
+    #[test]
+    fn serde() {
+        let c = coating();
+        let serialized = ron::to_string(&CoatingType::from(c)).unwrap();
+        let deserialized: CoatingType = ron::from_str(&serialized).unwrap();
+        let CoatingType::Tabulated(c) = deserialized else {
+            panic!("wrong coating type");
+        };
+        assert_abs_diff_eq!(
+            c.reflectance(nanometer!(1050.0), degree!(20.0)),
+            0.55,
+            epsilon = 1e-12
+        );
+    }
+}
@@ -5,12 +5,13 @@
 use crate::{
     error::{OpmResult, OpossumError},
     get_version,
+    reporting::ReportFormat,
 };
 use std::io::{BufReader, BufWriter};
 
 use clap::builder::Str;
 use clap::{Parser, builder::OsStr};
-use log::info;
+use log::{LevelFilter, info};
 use rprompt::prompt_reply_from_bufread;
 use std::{io::Write, string::String};
 use std::{
@@ -23,6 +24,14 @@ pub struct Args {
     pub file_path: PathBuf,
     /// destination directory of the report. if not defined, same directory as the filepath for the optical setup is used
     pub report_directory: PathBuf,
+    /// output format of the analysis reports
+    pub report_format: ReportFormat,
+    /// resolution (number of grid points in x and y) of fluence maps. if not defined, the value of the model file is used
+    pub fluence_grid: Option<(usize, usize)>,
+    /// if true, previous outputs in the report directory are preserved and the results are written into a timestamped subdirectory
+    pub no_clean: bool,
+    /// if true, the optical setup is only validated without performing an analysis
+    pub check: bool,
 }
 #[derive(Parser)]
 #[command(author, version = Str::from(&get_version()), about, long_about = None)]
@@ -39,6 +48,48 @@ pub struct PartialArgs {
     /// destination directory of the report. if not defined, same directory as the filepath for the optical setup is used
     #[arg(short, long)]
     report_directory: Option<String>,
+
+    /// output format of the report ("ron", "html" or "markdown"). "html" creates a self-contained html file. default: "ron"
+    #[arg(long)]
+    report_format: Option<String>,
+
+    /// resolution of the fluence maps given as "WxH" (e.g. "200x150"). if not defined, the value of the model file is used
+    #[arg(long)]
+    fluence_grid: Option<String>,
+
+    /// preserve previous outputs of the report directory and write the results into a timestamped subdirectory instead
+    #[arg(long, alias = "append")]
+    no_clean: bool,
+
+    /// only validate the optical setup (connectivity, ports, source data) without performing an analysis. exits with a
+    /// non-zero status if any problems are found
+    #[arg(long)]
+    check: bool,
+
+    /// only show warnings and errors. overrides the `RUST_LOG` environment variable
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// show debug messages (use -vv for trace messages). overrides the `RUST_LOG` environment variable
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+impl PartialArgs {
+    /// Returns the logging level selected by the `--quiet` or `--verbose` flags.
+    ///
+    /// Returns `None` if none of these flags is given. In this case, the logging level should be determined by the
+    /// environment (`RUST_LOG`) or the default level.
+    #[must_use]
+    pub const fn log_level(&self) -> Option<LevelFilter> {
+        if self.quiet {
+            return Some(LevelFilter::Warn);
+        }
+        match self.verbose {
+            0 => None,
+            1 => Some(LevelFilter::Debug),
+            _ => Some(LevelFilter::Trace),
+        }
+    }
 }
 
 /// Checks if the passed file path is valid.
@@ -79,6 +130,27 @@ fn eval_report_directory_input(report_path: &str) -> Option<PathBuf> {
         None
     }
 }
+/// Parses a fluence grid resolution string of the form "WxH" (e.g. "200x150").
+/// # Attributes
+/// * `grid`: String description of the grid resolution
+/// # Returns
+/// * Returns an [`OpmResult<(usize, usize)>`] containing the number of grid points in x and y direction.
+/// # Errors
+/// Returns an [`OpossumError::Console`] if the string is not of the form "WxH" or one of the dimensions is not a positive integer.
+fn parse_fluence_grid(grid: &str) -> OpmResult<(usize, usize)> {
+    let invalid = || {
+        OpossumError::Console(format!(
+            "invalid fluence grid '{grid}'. Expected two positive integers in the form WxH (e.g. 200x150)"
+        ))
+    };
+    let (nr_x, nr_y) = grid.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+    let nr_x = nr_x.trim().parse::<usize>().map_err(|_| invalid())?;
+    let nr_y = nr_y.trim().parse::<usize>().map_err(|_| invalid())?;
+    if nr_x == 0 || nr_y == 0 {
+        return Err(invalid());
+    }
+    Ok((nr_x, nr_y))
+}
 /// Creates the prompt string that is displayed in the console, depending on the flag and if the passed input for the respective flag is valid
 /// # Attributes
 /// * `flag`:       Respective argument flag. "f" for file path of the optical setup, "a" for analyzer to be used and "r" for the report directory.
@@ -173,10 +245,26 @@ impl TryFrom<PartialArgs> for Args {
             report_directory
         };
         info!("Report directory: {}", report_directory.display());
+        let report_format = part_args
+            .report_format
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| OpossumError::Console(format!("{e}")))?
+            .unwrap_or_default();
+        let fluence_grid = part_args
+            .fluence_grid
+            .as_deref()
+            .map(parse_fluence_grid)
+            .transpose()?;
 
         Ok(Self {
             file_path,
             report_directory,
+            report_format,
+            fluence_grid,
+            no_clean: part_args.no_clean,
+            check: part_args.check,
         })
     }
 }
@@ -365,30 +453,93 @@ GBB?        .BBB:  PBBPYYYJJ7^    YBBY        .GBBG#&&#BBBBBBBB#&&#Y.    .:^!YBB
             file_path: Some(path_valid.clone()),
             analyzer: Some("e".to_owned()),
             report_directory: Some("".to_owned()),
+            report_format: None,
+            fluence_grid: None,
+            no_clean: false,
+            check: false,
+            quiet: false,
+            verbose: 0,
         };
 
         let args = Args {
             file_path: PathBuf::from(path_valid.clone()),
             report_directory: PathBuf::from(get_parent_dir(&PathBuf::from(path_valid.clone()))),
+            report_format: ReportFormat::Ron,
+            fluence_grid: None,
+            no_clean: false,
+            check: false,
         };
 
         let args_from = Args::try_from(part_args).unwrap();
 
         assert_eq!(args.file_path, args_from.file_path);
         assert_eq!(args.report_directory, args_from.report_directory);
+        assert_eq!(args.report_format, args_from.report_format);
 
         let part_args = PartialArgs {
             file_path: Some(path_valid.clone()),
             analyzer: Some("e".to_owned()),
             report_directory: Some("./files_for_testing/".to_owned()),
+            report_format: Some("html".to_owned()),
+            fluence_grid: Some("200x150".to_owned()),
+            no_clean: true,
+            check: false,
+            quiet: false,
+            verbose: 0,
         };
 
         let args = Args {
             file_path: PathBuf::from(path_valid.clone()),
             report_directory: PathBuf::from("./files_for_testing/"),
+            report_format: ReportFormat::Html,
+            fluence_grid: Some((200, 150)),
+            no_clean: true,
+            check: false,
         };
         let args_from = Args::try_from(part_args).unwrap();
         assert_eq!(args.report_directory, args_from.report_directory);
+        assert_eq!(args.report_format, args_from.report_format);
+        assert_eq!(args.fluence_grid, args_from.fluence_grid);
+        assert_eq!(args.no_clean, args_from.no_clean);
+
+        let part_args = PartialArgs {
+            file_path: Some(path_valid.clone()),
+            analyzer: Some("e".to_owned()),
+            report_directory: Some("./files_for_testing/".to_owned()),
+            report_format: Some("pdf".to_owned()),
+            fluence_grid: None,
+            no_clean: false,
+            check: false,
+            quiet: false,
+            verbose: 0,
+        };
+        assert!(Args::try_from(part_args).is_err());
+
+        let part_args = PartialArgs {
+            file_path: Some(path_valid.clone()),
+            analyzer: Some("e".to_owned()),
+            report_directory: Some("./files_for_testing/".to_owned()),
+            report_format: None,
+            fluence_grid: Some("200x".to_owned()),
+            no_clean: false,
+            check: false,
+            quiet: false,
+            verbose: 0,
+        };
+        assert!(Args::try_from(part_args).is_err());
+    }
+    #[test]
+    fn parse_fluence_grid_test() {
+        assert_eq!(parse_fluence_grid("200x150").unwrap(), (200, 150));
+        assert_eq!(parse_fluence_grid(" 64X48 ").unwrap(), (64, 48));
+        assert!(parse_fluence_grid("").is_err());
+        assert!(parse_fluence_grid("200").is_err());
+        assert!(parse_fluence_grid("200x").is_err());
+        assert!(parse_fluence_grid("0x150").is_err());
+        assert!(parse_fluence_grid("200x0").is_err());
+        assert!(parse_fluence_grid("-200x150").is_err());
+        assert!(parse_fluence_grid("200.5x150").is_err());
+        assert!(parse_fluence_grid("200x150x3").is_err());
     }
 
     #[test]
@@ -469,6 +620,8 @@ GBB?        .BBB:  PBBPYYYJJ7^    YBBY        .GBBG#&&#BBBBBBBB#&&#Y.    .:^!YBB
             "./files_for_testing/opm/opticscenery.opm",
             "-r",
             "./files_for_testing/",
+            "--fluence-grid",
+            "200x150",
         ];
         let part_args = PartialArgs::parse_from(arg_vec);
         let fpath = part_args.file_path.unwrap();
@@ -476,5 +629,32 @@ GBB?        .BBB:  PBBPYYYJJ7^    YBBY        .GBBG#&&#BBBBBBBB#&&#Y.    .:^!YBB
 
         assert_eq!(fpath, "./files_for_testing/opm/opticscenery.opm");
         assert_eq!(r_dir, "./files_for_testing/");
+        assert_eq!(part_args.fluence_grid.unwrap(), "200x150");
+        assert!(!part_args.no_clean);
+
+        let part_args = PartialArgs::parse_from(vec!["opossum", "--no-clean"]);
+        assert!(part_args.no_clean);
+        let part_args = PartialArgs::parse_from(vec!["opossum", "--append"]);
+        assert!(part_args.no_clean);
+        assert!(!part_args.check);
+        let part_args = PartialArgs::parse_from(vec!["opossum", "--check"]);
+        assert!(part_args.check);
+    }
+    #[test]
+    fn log_level_test() {
+        assert_eq!(PartialArgs::parse_from(vec!["opossum"]).log_level(), None);
+        assert_eq!(
+            PartialArgs::parse_from(vec!["opossum", "-q"]).log_level(),
+            Some(LevelFilter::Warn)
+        );
+        assert_eq!(
+            PartialArgs::parse_from(vec!["opossum", "--verbose"]).log_level(),
+            Some(LevelFilter::Debug)
+        );
+        assert_eq!(
+            PartialArgs::parse_from(vec!["opossum", "-vv"]).log_level(),
+            Some(LevelFilter::Trace)
+        );
+        assert!(PartialArgs::try_parse_from(vec!["opossum", "-q", "-v"]).is_err());
     }
 }
@@ -4,8 +4,123 @@ use num::ToPrimitive;
 
 use crate::error::OpmResult;
 use crate::optic_ports::{OpticPorts, PortType};
-use std::fmt::Write as _;
+use std::fmt::{Display, Write as _};
+
+/// Layout direction of a `dot` diagram.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RankDir {
+    /// nodes are arranged from top to bottom
+    #[default]
+    TopBottom,
+    /// nodes are arranged from left to right
+    LeftRight,
+}
+impl RankDir {
+    /// Returns the Graphviz `rankdir` attribute value of this [`RankDir`].
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::TopBottom => "TB",
+            Self::LeftRight => "LR",
+        }
+    }
+}
+impl Display for RankDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+/// Line style of the edges (connections) of a `dot` diagram.
+///
+/// Edges connected to a disabled node are always drawn dashed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeStyle {
+    /// solid line (Graphviz default)
+    #[default]
+    Solid,
+    /// dashed line
+    Dashed,
+    /// dotted line
+    Dotted,
+    /// bold line
+    Bold,
+}
+impl EdgeStyle {
+    /// Returns the edge attribute string (including a leading separator) of this [`EdgeStyle`].
+    ///
+    /// The string is empty for [`EdgeStyle::Solid`] since this is the Graphviz default.
+    #[must_use]
+    pub const fn attribute(&self) -> &'static str {
+        match self {
+            Self::Solid => "",
+            Self::Dashed => ", style=dashed",
+            Self::Dotted => ", style=dotted",
+            Self::Bold => ", style=bold",
+        }
+    }
+}
+/// Options controlling the layout and styling of the generated `dot` diagram.
+///
+/// The [`Default`] options reproduce the standard diagram (top-to-bottom layout, 10pt `Courier-monospace` font and
+/// solid edges without port or data annotations).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotOptions {
+    /// layout direction of the graph
+    pub rankdir: RankDir,
+    /// font used for the graph label, the nodes and the edges
+    pub fontname: String,
+    /// font size (in pt) used for the graph label, the nodes and the edges
+    pub fontsize: usize,
+    /// line style of the edges
+    pub edge_style: EdgeStyle,
+    /// annotate each edge with the names of its source and target ports
+    pub port_labels: bool,
+    /// annotate each edge with a summary of the light data flowing across it (if an analysis has been performed)
+    pub data_labels: bool,
+}
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            rankdir: RankDir::default(),
+            fontname: "Courier-monospace".into(),
+            fontsize: 10,
+            edge_style: EdgeStyle::default(),
+            port_labels: false,
+            data_labels: false,
+        }
+    }
+}
+impl DotOptions {
+    /// Creates new default [`DotOptions`] with the given layout direction.
+    #[must_use]
+    pub fn with_rankdir(rankdir: RankDir) -> Self {
+        Self {
+            rankdir,
+            ..Self::default()
+        }
+    }
+}
 
+/// Modifies the dot representation of a node such that it is displayed as disabled.
+///
+/// The node is greyed out and drawn with a dashed outline. `node_color` is the (background) color of the node as
+/// given by [`Dottable::node_color`]. Groups in expanded view are rendered as a dashed, grey cluster.
+#[must_use]
+pub fn disabled_node_dot(node_dot: &str, node_color: &str) -> String {
+    node_dot
+        .replacen(
+            "shape=plaintext\n",
+            "shape=plaintext\n\t\tcolor=gray\n\t\tfontcolor=gray\n",
+            1,
+        )
+        .replacen(
+            "cluster=true\n\t",
+            "cluster=true\n\tstyle=dashed\n\tcolor=gray\n\tfontcolor=gray\n\t",
+            1,
+        )
+        .replace(&format!("BGCOLOR=\"{node_color}\""), "BGCOLOR=\"gray90\"")
+        .replace("STYLE=\"ROUNDED\"", "STYLE=\"ROUNDED,DASHED\"")
+}
 /// This trait deals with the translation of the [`NodeGroup`](crate::nodes::NodeGroup) structure to the dot-file
 /// format which is needed to visualize the graphs
 pub trait Dottable {
@@ -19,14 +134,19 @@ pub trait Dottable {
         name: &str,
         inverted: bool,
         ports: &OpticPorts,
-        rankdir: &str,
+        options: &DotOptions,
     ) -> OpmResult<String> {
         let inv_string = if inverted { " (inv)" } else { "" };
         let node_name = format!("{name}{inv_string}");
         let mut dot_str = format!("\ti{node_index} [\n\t\tshape=plaintext\n");
         let mut indent_level = 2;
 
-        dot_str.push_str(&self.add_html_like_labels(&node_name, &mut indent_level, ports, rankdir));
+        dot_str.push_str(&self.add_html_like_labels(
+            &node_name,
+            &mut indent_level,
+            ports,
+            options.rankdir.as_str(),
+        ));
         Ok(dot_str)
     }
     /// Create the dot-string of each defined port
@@ -350,6 +470,7 @@ pub trait Dottable {
 
 #[cfg(test)]
 mod test {
+    use super::*;
     use crate::{
         lightdata::light_data_builder::LightDataBuilder,
         nodes::{BeamSplitter, Dummy, EnergyMeter, Metertype, NodeGroup, Source},
@@ -372,13 +493,51 @@ mod test {
 
         let scenery = NodeGroup::new("Test");
 
-        let scenery_dot_str_tb = scenery.toplevel_dot("TB").unwrap();
-        let scenery_dot_str_lr = scenery.toplevel_dot("LR").unwrap();
+        let scenery_dot_str_tb = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        let scenery_dot_str_lr = scenery
+            .toplevel_dot(&DotOptions::with_rankdir(RankDir::LeftRight))
+            .unwrap();
 
         assert_eq!(file_content_tb.clone(), scenery_dot_str_tb);
         assert_eq!(file_content_lr.clone(), scenery_dot_str_lr);
     }
     #[test]
+    fn dot_options() {
+        let options = DotOptions::default();
+        assert_eq!(options.rankdir, RankDir::TopBottom);
+        assert_eq!(options.fontname, "Courier-monospace");
+        assert_eq!(options.fontsize, 10);
+        assert_eq!(options.edge_style, EdgeStyle::Solid);
+        assert_eq!(RankDir::LeftRight.to_string(), "LR");
+        assert_eq!(EdgeStyle::Solid.attribute(), "");
+        assert_eq!(EdgeStyle::Dotted.attribute(), ", style=dotted");
+    }
+    #[test]
+    fn to_dot_custom_options() {
+        let mut scenery = NodeGroup::new("Test");
+        let i_d1 = scenery.add_node(Dummy::default()).unwrap();
+        let i_d2 = scenery.add_node(Dummy::default()).unwrap();
+        scenery
+            .connect_nodes(i_d1, "output_1", i_d2, "input_1", Length::zero())
+            .unwrap();
+        let default_dot = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        assert!(!default_dot.contains("style="));
+        let options = DotOptions {
+            rankdir: RankDir::LeftRight,
+            fontname: "Helvetica".into(),
+            fontsize: 14,
+            edge_style: EdgeStyle::Bold,
+            ..DotOptions::default()
+        };
+        let dot = scenery.toplevel_dot(&options).unwrap();
+        assert!(dot.contains("rankdir = \"LR\";"));
+        assert!(dot.contains("\tfontsize = 14;"));
+        assert!(dot.contains("node [fontname=\"Helvetica\" fontsize = 14]"));
+        assert!(dot.contains("edge [fontname=\"Helvetica\" fontsize = 14]"));
+        assert!(!dot.contains("Courier-monospace"));
+        assert!(dot.contains(", style=bold]"));
+    }
+    #[test]
     #[ignore]
     fn to_dot_with_node() {
         let file_content_tb = get_file_content("./files_for_testing/dot/to_dot_w_node_TB.dot");
@@ -386,8 +545,10 @@ mod test {
 
         let mut scenery = NodeGroup::default();
         scenery.add_node(Dummy::new("Test")).unwrap();
-        let scenery_dot_str_tb = scenery.toplevel_dot("TB").unwrap();
-        let scenery_dot_str_lr = scenery.toplevel_dot("LR").unwrap();
+        let scenery_dot_str_tb = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        let scenery_dot_str_lr = scenery
+            .toplevel_dot(&DotOptions::with_rankdir(RankDir::LeftRight))
+            .unwrap();
 
         assert_eq!(file_content_tb.clone(), scenery_dot_str_tb);
         assert_eq!(file_content_lr.clone(), scenery_dot_str_lr);
@@ -428,8 +589,10 @@ mod test {
             .connect_nodes(i_bs, "out2_trans2_refl1", i_d2, "input_1", Length::zero())
             .unwrap();
 
-        let scenery_dot_str_tb = scenery.toplevel_dot("TB").unwrap();
-        let scenery_dot_str_lr = scenery.toplevel_dot("LR").unwrap();
+        let scenery_dot_str_tb = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        let scenery_dot_str_lr = scenery
+            .toplevel_dot(&DotOptions::with_rankdir(RankDir::LeftRight))
+            .unwrap();
 
         assert_eq!(file_content_tb.clone(), scenery_dot_str_tb);
         assert_eq!(file_content_lr.clone(), scenery_dot_str_lr);
@@ -500,8 +663,10 @@ mod test {
             .unwrap();
         let file_content_tb = get_file_content("./files_for_testing/dot/group_dot_TB.dot");
         let file_content_lr = get_file_content("./files_for_testing/dot/group_dot_LR.dot");
-        let scenery_dot_str_tb = scenery.toplevel_dot("TB").unwrap();
-        let scenery_dot_str_lr = scenery.toplevel_dot("LR").unwrap();
+        let scenery_dot_str_tb = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        let scenery_dot_str_lr = scenery
+            .toplevel_dot(&DotOptions::with_rankdir(RankDir::LeftRight))
+            .unwrap();
 
         assert_eq!(file_content_tb.clone(), scenery_dot_str_tb);
         assert_eq!(file_content_lr.clone(), scenery_dot_str_lr);
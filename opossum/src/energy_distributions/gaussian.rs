@@ -0,0 +1,135 @@
+//! Radial Gaussian energy distribution
+use super::EnergyDistribution;
+use crate::error::{OpmResult, OpossumError};
+use kahan::KahanSummator;
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+use uom::si::{
+    energy::joule,
+    f64::{Energy, Length},
+};
+
+/// Rotationally symmetric Gaussian energy distribution centered at the origin.
+///
+/// The energy of each point is weighted by `exp(-2 r² / w²)`, where `w` is the 1/e² radius of the beam. The weights
+/// are normalized, such that the energies of all given points sum up to the total energy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct Gaussian {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    total_energy: Energy,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius: Length,
+}
+impl Gaussian {
+    /// Create a new radial Gaussian energy-distribution generator [`Gaussian`].
+    /// # Attributes
+    /// - `total_energy`: total energy to distribute within the construction points
+    /// - `radius`: 1/e² radius of the distribution
+    /// # Errors
+    /// This function will return an error if
+    ///   - the energy is non-finite, zero or below zero
+    ///   - the radius is non-finite, zero or below zero
+    pub fn new(total_energy: Energy, radius: Length) -> OpmResult<Self> {
+        if !total_energy.get::<joule>().is_normal()
+            || total_energy.get::<joule>().is_sign_negative()
+        {
+            return Err(OpossumError::Other(
+                "Energy must be greater than zero finite!".into(),
+            ));
+        }
+        if !radius.is_normal() || radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "Radius must be greater than zero and finite!".into(),
+            ));
+        }
+        Ok(Self {
+            total_energy,
+            radius,
+        })
+    }
+    /// Returns the 1/e² radius of this [`Gaussian`].
+    #[must_use]
+    pub const fn radius(&self) -> Length {
+        self.radius
+    }
+}
+impl EnergyDistribution for Gaussian {
+    fn apply(&self, input: &[Point2<Length>]) -> Vec<Energy> {
+        let weights = input
+            .iter()
+            .map(|p| {
+                let r_sq = ((p.x * p.x + p.y * p.y) / (self.radius * self.radius)).value;
+                f64::exp(-2.0 * r_sq)
+            })
+            .collect::<Vec<f64>>();
+        let total_weight: f64 = weights.iter().kahan_sum().sum();
+        weights
+            .iter()
+            .map(|w| self.total_energy * *w / total_weight)
+            .collect::<Vec<Energy>>()
+    }
+
+    fn get_total_energy(&self) -> Energy {
+        self.total_energy
+    }
+}
+impl From<Gaussian> for super::EnergyDistType {
+    fn from(g: Gaussian) -> Self {
+        Self::Gaussian(g)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, millimeter,
+        position_distributions::{Hexapolar, PositionDistribution},
+    };
+    use approx::assert_relative_eq;
+    #[test]
+    fn new() {
+        assert!(Gaussian::new(joule!(0.), millimeter!(1.)).is_err());
+        assert!(Gaussian::new(joule!(-1.), millimeter!(1.)).is_err());
+        assert!(Gaussian::new(joule!(f64::NAN), millimeter!(1.)).is_err());
+        assert!(Gaussian::new(joule!(f64::INFINITY), millimeter!(1.)).is_err());
+        assert!(Gaussian::new(joule!(1.), millimeter!(0.)).is_err());
+        assert!(Gaussian::new(joule!(1.), millimeter!(-1.)).is_err());
+        assert!(Gaussian::new(joule!(1.), millimeter!(f64::NAN)).is_err());
+        assert!(Gaussian::new(joule!(1.), millimeter!(f64::INFINITY)).is_err());
+        let g = Gaussian::new(joule!(2.), millimeter!(1.)).unwrap();
+        assert_eq!(g.get_total_energy(), joule!(2.));
+        assert_eq!(g.radius(), millimeter!(1.));
+    }
+    #[test]
+    fn apply() {
+        let g = Gaussian::new(joule!(2.), millimeter!(1.)).unwrap();
+        let energies = g.apply(&[
+            millimeter!(0., 0.),
+            millimeter!(1., 0.),
+            millimeter!(0., -1.),
+        ]);
+        assert_eq!(energies.len(), 3);
+        let total: f64 = energies.iter().map(|e| e.get::<joule>()).sum();
+        assert_relative_eq!(total, 2., epsilon = 1e-12);
+        assert_relative_eq!((energies[1] / energies[0]).value, f64::exp(-2.));
+        assert_relative_eq!(energies[2].get::<joule>(), energies[1].get::<joule>());
+    }
+    #[test]
+    fn apply_hexapolar() {
+        let g = Gaussian::new(joule!(1.), millimeter!(1.)).unwrap();
+        let points = Hexapolar::new(millimeter!(2.), 5)
+            .unwrap()
+            .generate()
+            .iter()
+            .map(|p| Point2::new(p.x, p.y))
+            .collect::<Vec<_>>();
+        let energies = g.apply(&points);
+        let total: f64 = energies.iter().map(|e| e.get::<joule>()).sum();
+        assert_relative_eq!(total, 1., epsilon = 1e-12);
+        let center = energies[0];
+        assert!(energies.iter().all(|e| *e <= center));
+        // points on the same ring carry the same energy
+        assert_relative_eq!(energies[1].get::<joule>(), energies[2].get::<joule>());
+    }
+}
@@ -16,11 +16,16 @@ use uom::si::{
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct General2DGaussian {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     total_energy: Energy,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     mu_xy: Point2<Length>,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     sigma_xy: Point2<Length>,
     power: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     theta: Angle,
     rectangular: bool,
 }
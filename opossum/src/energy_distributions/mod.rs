@@ -1,8 +1,12 @@
 //! Module for handling energy distributions
+pub mod gaussian;
 pub mod general_gaussian;
+pub mod super_gaussian;
 pub mod uniform;
+pub use gaussian::Gaussian;
 pub use general_gaussian::General2DGaussian;
 use serde::{Deserialize, Serialize};
+pub use super_gaussian::SuperGaussian;
 pub use uniform::UniformDist;
 
 use crate::joule;
@@ -38,9 +42,12 @@ pub trait EnergyDistribution {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum EnergyDistType {
     Uniform(UniformDist),
     General2DGaussian(general_gaussian::General2DGaussian),
+    Gaussian(Gaussian),
+    SuperGaussian(SuperGaussian),
 }
 
 impl EnergyDistType {
@@ -49,6 +56,8 @@ impl EnergyDistType {
         match self {
             Self::Uniform(dist) => dist,
             Self::General2DGaussian(dist) => dist,
+            Self::Gaussian(dist) => dist,
+            Self::SuperGaussian(dist) => dist,
         }
     }
 }
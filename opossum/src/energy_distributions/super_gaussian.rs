@@ -0,0 +1,200 @@
+//! Radial super-Gaussian (flat-top) energy distribution
+use super::EnergyDistribution;
+use crate::error::{OpmResult, OpossumError};
+use kahan::KahanSummator;
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::LN_2;
+use uom::si::{
+    energy::joule,
+    f64::{Energy, Length},
+};
+
+/// Rotationally symmetric super-Gaussian energy distribution centered at the origin.
+///
+/// The energy of each point is weighted by `exp(-2 (r² / w²)ⁿ)`, where `n` is the order of the distribution and
+/// `w` its radius. Following this convention, the radius `w` denotes the 1/e² radius for all orders, i.e. the radius
+/// where the weight has dropped to 1/e² of its peak value. The corresponding full width at half maximum is given
+/// by [`SuperGaussian::fwhm`].
+///
+/// An order of 1 yields a standard Gaussian distribution (see [`Gaussian`](super::Gaussian)), while for large orders the
+/// distribution approaches a top-hat of radius `w`. The weights are normalized, such that the energies of all given
+/// points sum up to the total energy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct SuperGaussian {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    total_energy: Energy,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius: Length,
+    order: f64,
+}
+impl SuperGaussian {
+    /// Create a new radial super-Gaussian energy-distribution generator [`SuperGaussian`].
+    /// # Attributes
+    /// - `total_energy`: total energy to distribute within the construction points
+    /// - `radius`: 1/e² radius of the distribution
+    /// - `order`: order of the distribution (1 for a Gaussian)
+    /// # Errors
+    /// This function will return an error if
+    ///   - the energy is non-finite, zero or below zero
+    ///   - the radius is non-finite, zero or below zero
+    ///   - the order is non-finite, zero or below zero
+    pub fn new(total_energy: Energy, radius: Length, order: f64) -> OpmResult<Self> {
+        if !total_energy.get::<joule>().is_normal()
+            || total_energy.get::<joule>().is_sign_negative()
+        {
+            return Err(OpossumError::Other(
+                "Energy must be greater than zero finite!".into(),
+            ));
+        }
+        if !radius.is_normal() || radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "Radius must be greater than zero and finite!".into(),
+            ));
+        }
+        if !order.is_normal() || order.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "Order must be greater than zero and finite!".into(),
+            ));
+        }
+        Ok(Self {
+            total_energy,
+            radius,
+            order,
+        })
+    }
+    /// Returns the 1/e² radius of this [`SuperGaussian`].
+    #[must_use]
+    pub const fn radius(&self) -> Length {
+        self.radius
+    }
+    /// Returns the order of this [`SuperGaussian`].
+    #[must_use]
+    pub const fn order(&self) -> f64 {
+        self.order
+    }
+    /// Returns the full width at half maximum of this [`SuperGaussian`].
+    #[must_use]
+    pub fn fwhm(&self) -> Length {
+        2.0 * self.radius * (LN_2 / 2.0).powf(0.5 / self.order)
+    }
+}
+impl EnergyDistribution for SuperGaussian {
+    fn apply(&self, input: &[Point2<Length>]) -> Vec<Energy> {
+        let weights = input
+            .iter()
+            .map(|p| {
+                let r_sq = ((p.x * p.x + p.y * p.y) / (self.radius * self.radius)).value;
+                f64::exp(-2.0 * r_sq.powf(self.order))
+            })
+            .collect::<Vec<f64>>();
+        let total_weight: f64 = weights.iter().kahan_sum().sum();
+        weights
+            .iter()
+            .map(|w| self.total_energy * *w / total_weight)
+            .collect::<Vec<Energy>>()
+    }
+
+    fn get_total_energy(&self) -> Energy {
+        self.total_energy
+    }
+}
+impl From<SuperGaussian> for super::EnergyDistType {
+    fn from(g: SuperGaussian) -> Self {
+        Self::SuperGaussian(g)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        energy_distributions::Gaussian,
+        joule, millimeter,
+        position_distributions::{Grid, Hexapolar, PositionDistribution},
+    };
+    use approx::assert_relative_eq;
+    use uom::si::length::millimeter;
+    #[test]
+    fn new() {
+        assert!(SuperGaussian::new(joule!(0.), millimeter!(1.), 2.).is_err());
+        assert!(SuperGaussian::new(joule!(f64::NAN), millimeter!(1.), 2.).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(0.), 2.).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(f64::INFINITY), 2.).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(1.), 0.).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(1.), -2.).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(1.), f64::NAN).is_err());
+        assert!(SuperGaussian::new(joule!(1.), millimeter!(1.), f64::INFINITY).is_err());
+        let g = SuperGaussian::new(joule!(2.), millimeter!(1.), 4.).unwrap();
+        assert_eq!(g.get_total_energy(), joule!(2.));
+        assert_eq!(g.radius(), millimeter!(1.));
+        assert_eq!(g.order(), 4.);
+    }
+    #[test]
+    fn fwhm() {
+        let g = SuperGaussian::new(joule!(1.), millimeter!(1.), 1.).unwrap();
+        assert_relative_eq!(
+            g.fwhm().get::<millimeter>(),
+            f64::sqrt(2.0 * LN_2),
+            epsilon = 1e-12
+        );
+        let g = SuperGaussian::new(joule!(1.), millimeter!(1.), 1000.).unwrap();
+        assert_relative_eq!(g.fwhm().get::<millimeter>(), 2.0, epsilon = 2e-3);
+    }
+    #[test]
+    fn apply_order_one_is_gaussian() {
+        let points = Hexapolar::new(millimeter!(2.), 5)
+            .unwrap()
+            .generate()
+            .iter()
+            .map(|p| Point2::new(p.x, p.y))
+            .collect::<Vec<_>>();
+        let super_gaussian = SuperGaussian::new(joule!(1.), millimeter!(1.), 1.)
+            .unwrap()
+            .apply(&points);
+        let gaussian = Gaussian::new(joule!(1.), millimeter!(1.))
+            .unwrap()
+            .apply(&points);
+        for (s, g) in super_gaussian.iter().zip(gaussian.iter()) {
+            assert_relative_eq!(s.get::<joule>(), g.get::<joule>(), epsilon = 1e-15);
+        }
+    }
+    #[test]
+    fn apply_flat_top() {
+        let g = SuperGaussian::new(joule!(1.), millimeter!(1.), 20.).unwrap();
+        let energies = g.apply(&[
+            millimeter!(0., 0.),
+            millimeter!(0.5, 0.),
+            millimeter!(1., 0.),
+            millimeter!(0., 2.),
+        ]);
+        assert_relative_eq!(
+            energies[1].get::<joule>(),
+            energies[0].get::<joule>(),
+            max_relative = 1e-5
+        );
+        assert_relative_eq!(
+            (energies[2] / energies[0]).value,
+            f64::exp(-2.),
+            epsilon = 1e-12
+        );
+        assert!(energies[3].get::<joule>() < 1e-12);
+    }
+    #[test]
+    fn apply_normalization() {
+        let g = SuperGaussian::new(joule!(3.), millimeter!(1.), 5.).unwrap();
+        for points in [
+            Hexapolar::new(millimeter!(2.), 7).unwrap().generate(),
+            Grid::new((millimeter!(3.), millimeter!(3.)), (21, 21))
+                .unwrap()
+                .generate(),
+        ] {
+            let points = points
+                .iter()
+                .map(|p| Point2::new(p.x, p.y))
+                .collect::<Vec<_>>();
+            let total: f64 = g.apply(&points).iter().map(|e| e.get::<joule>()).sum();
+            assert_relative_eq!(total, 3., epsilon = 1e-12);
+        }
+    }
+}
@@ -11,7 +11,9 @@ use uom::si::{
 use super::EnergyDistribution;
 use crate::error::{OpmResult, OpossumError};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct UniformDist {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     total_energy: Energy,
 }
 
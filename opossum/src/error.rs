@@ -15,6 +15,8 @@ pub enum OpossumError {
     OpticGroup(String),
     /// (mostly internal) errors while dealing with optical ports.
     OpticPort(String),
+    /// a port is already connected (e.g. a second connection to the same input port)
+    PortAlreadyConnected(String),
     /// mostly runtime errors occuring during the analysis of a scenery
     Analysis(String),
     /// errors while handling optical spectra
@@ -42,6 +44,9 @@ impl Display for OpossumError {
             Self::OpticPort(m) => {
                 write!(f, "OpticPort:{m}")
             }
+            Self::PortAlreadyConnected(m) => {
+                write!(f, "PortAlreadyConnected:{m}")
+            }
             Self::Analysis(m) => {
                 write!(f, "Analysis:{m}")
             }
@@ -89,6 +94,10 @@ mod test {
             format!("{}", OpossumError::OpticPort("test".to_string())),
             "OpticPort:test"
         );
+        assert_eq!(
+            format!("{}", OpossumError::PortAlreadyConnected("test".to_string())),
+            "PortAlreadyConnected:test"
+        );
         assert_eq!(
             format!("{}", OpossumError::Analysis("test".to_string())),
             "Analysis:test"
@@ -0,0 +1,71 @@
+use crate::nodes::fluence_detector::Fluence;
+use nalgebra::Point2;
+use num::Zero;
+use uom::si::f64::{Area, Energy, Length};
+
+/// Ratio of the support radius to the standard deviation (along one axis) of the 2D Epanechnikov kernel
+const RADIUS_PER_SIGMA: f64 = 2.449_489_742_783_178;
+pub struct Epanechnikov2D {
+    mean: Point2<Length>,
+    radius_square: Area,
+    amplitude: Fluence,
+}
+impl Epanechnikov2D {
+    /// Creates a new [`Epanechnikov2D`] kernel with the same standard deviation `sigma` as a [`Gaussian2D`](super::gaussian::Gaussian2D).
+    pub fn new(mean: Point2<Length>, sigma: Length, weight: Energy) -> Self {
+        let radius = RADIUS_PER_SIGMA * sigma;
+        let radius_square = radius * radius;
+        Self {
+            mean,
+            radius_square,
+            amplitude: 2.0 * weight / (std::f64::consts::PI * radius_square),
+        }
+    }
+    fn distance_squared(point1: &Point2<Length>, point2: &Point2<Length>) -> Area {
+        (point1.x - point2.x) * (point1.x - point2.x)
+            + (point1.y - point2.y) * (point1.y - point2.y)
+    }
+    pub fn value(&self, point: Point2<Length>) -> Fluence {
+        let dist_square = Self::distance_squared(&point, &self.mean);
+        if dist_square < self.radius_square {
+            self.amplitude * (1.0 - (dist_square / self.radius_square).value)
+        } else {
+            Fluence::zero()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Epanechnikov2D;
+    use crate::{joule, millimeter, nodes::fluence_detector::Fluence, utils::griddata::linspace};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::DMatrix;
+    use uom::si::{f64::Ratio, ratio::ratio};
+
+    #[test]
+    fn check_norm() {
+        let g = Epanechnikov2D::new(millimeter!(0.0, 0.0), millimeter!(10.0), joule!(1.0));
+        let nr_of_points = (120, 120);
+        let grid_element_area = millimeter!(200.0) * millimeter!(200.0)
+            / Ratio::new::<ratio>((nr_of_points.0 * nr_of_points.1) as f64);
+
+        let x_pos = linspace(-100.0, 100.0, nr_of_points.0).unwrap();
+        let y_pos = linspace(-100.0, 100.0, nr_of_points.1).unwrap();
+        let mut field = DMatrix::<Fluence>::zeros(nr_of_points.0, nr_of_points.1);
+        for i_x in 0..nr_of_points.0 {
+            for i_y in 0..nr_of_points.1 {
+                field[(i_x, i_y)] = g.value(millimeter!(x_pos[i_x], y_pos[i_y]));
+            }
+        }
+        let total_energy = field.sum() * grid_element_area;
+        assert_abs_diff_eq!(total_energy.value, 1.0, epsilon = 0.03);
+    }
+    #[test]
+    fn compact_support() {
+        let g = Epanechnikov2D::new(millimeter!(0.0, 0.0), millimeter!(1.0), joule!(1.0));
+        assert!(g.value(millimeter!(0.0, 2.4)).value > 0.0);
+        assert_eq!(g.value(millimeter!(0.0, 2.5)).value, 0.0);
+        assert_eq!(g.value(millimeter!(10.0, 0.0)).value, 0.0);
+    }
+}
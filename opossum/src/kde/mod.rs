@@ -1,28 +1,102 @@
 //! Kernel density estimator
 
+mod epanechnikov;
 mod gaussian;
+mod top_hat;
 use crate::{
     error::OpmResult,
     millimeter,
     nodes::fluence_detector::Fluence,
+    properties::Proptype,
     utils::{f64_to_usize, math_utils::distance_2d_point, usize_to_f64},
 };
+use epanechnikov::Epanechnikov2D;
 use gaussian::Gaussian2D;
 use nalgebra::{DMatrix, Point2, point};
 use num::Zero;
 use rayon::prelude::*;
-use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, ops::Range};
+use top_hat::TopHat2D;
 use uom::si::f64::{Area, Energy, Length};
 
+/// Strategy for selecting the band width of a [`Kde`]
+///
+/// The band width (i.e. the standard deviation of the Gaussian kernel) determines the trade-off between noise and
+/// smoothing of the estimated fluence. A too large band width smears out small structures and underestimates the peak
+/// fluence, a too small band width leads to a noisy distribution. The automatic rules scale with the size of the hit
+/// point distribution and decrease with the number of hit points. Hence, dense and small beams are not over-smoothed.
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BandwidthSelection {
+    /// Scott's rule of thumb based on the standard deviation of the hit point positions
+    ///
+    /// The band width is `h = σ * n^(-1/6)` (for two dimensions), where `σ` is the energy-weighted standard deviation of
+    /// the hit point positions and `n` the effective number of hit points (`(Σw)² / Σw²` for energies `w`). This rule is
+    /// optimal for Gaussian-like distributions but tends to over-smooth multimodal or sharp-edged (e.g. flat-top) beams.
+    Scott,
+    /// Silverman's rule of thumb based on the distribution of the distances between the hit points
+    ///
+    /// The band width is `h = 0.9 * min(σ_d, q_d / 1.34) * n^(-1/5)`, where `σ_d` is the standard deviation and `q_d` the
+    /// upper quartile of all pairwise distances between the hit points. Using the distances makes this rule more robust
+    /// against outliers and non-Gaussian beam profiles. Note, that the calculation scales quadratically with the number
+    /// of hit points.
+    #[default]
+    Silverman,
+    /// Fixed (user-defined) band width
+    ///
+    /// The given band width is used regardless of the hit point distribution. This is useful for comparing fluence maps
+    /// of different beams with identical smoothing or if the typical size of the structures of interest is known.
+    Fixed(Length),
+}
+
+/// Shape of the kernel of a [`Kde`]
+///
+/// For all kernels, the band width denotes the standard deviation of the kernel along one axis. Hence, the
+/// [`BandwidthSelection`] rules can be used regardless of the kernel shape.
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub enum KdeKernel {
+    /// Gaussian kernel
+    ///
+    /// The kernel is smooth but has an infinite support. Hence, each hit point contributes to the fluence far away from
+    /// its position, which may lead to an underestimation of the peak fluence of combined distributions.
+    #[default]
+    Gaussian,
+    /// Epanechnikov kernel `K(r) ∝ 1 - r² / R²` with a compact support radius `R = √6 * h`
+    ///
+    /// This kernel has the lowest mean integrated squared error and does not leak energy into the far tails.
+    Epanechnikov,
+    /// Top-hat (uniform disc) kernel with a radius of `2 * h`
+    ///
+    /// This kernel has a compact support but leads to a rather noisy fluence distribution.
+    TopHat,
+}
+impl Display for KdeKernel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gaussian => write!(f, "Gaussian"),
+            Self::Epanechnikov => write!(f, "Epanechnikov"),
+            Self::TopHat => write!(f, "Top-hat"),
+        }
+    }
+}
+impl From<KdeKernel> for Proptype {
+    fn from(value: KdeKernel) -> Self {
+        Self::KdeKernel(value)
+    }
+}
+
 pub struct Kde {
     hit_map: Vec<(Point2<Length>, Energy)>,
     band_width: Length,
+    kernel: KdeKernel,
 }
 impl Default for Kde {
     fn default() -> Self {
         Self {
             hit_map: Vec::default(),
             band_width: millimeter!(1.0),
+            kernel: KdeKernel::default(),
         }
     }
 }
@@ -30,6 +104,10 @@ impl Kde {
     pub fn set_hit_map(&mut self, hit_map: Vec<(Point2<Length>, Energy)>) {
         self.hit_map = hit_map;
     }
+    /// Sets the [`KdeKernel`] of this [`Kde`].
+    pub const fn set_kernel(&mut self, kernel: KdeKernel) {
+        self.kernel = kernel;
+    }
     /// Sets the band width of this [`Kde`].
     ///
     /// # Errors
@@ -78,6 +156,65 @@ impl Kde {
             }
         }
     }
+    /// Returns the energy-weighted standard deviation of the hit point positions and the effective number of hit points.
+    ///
+    /// If the hit points do not carry any (positive) energy, all points are weighted equally.
+    fn positions_std_dev(&self) -> (Length, f64) {
+        let energies = self
+            .hit_map
+            .iter()
+            .map(|hit| hit.1.value.max(0.0))
+            .collect::<Vec<f64>>();
+        let weights = if energies.iter().sum::<f64>() > 0.0 {
+            energies
+        } else {
+            vec![1.0; self.hit_map.len()]
+        };
+        let sum_weights = weights.iter().sum::<f64>();
+        let (sum_x, sum_y) = self
+            .hit_map
+            .iter()
+            .zip(&weights)
+            .fold((Length::zero(), Length::zero()), |sum, (hit, weight)| {
+                (sum.0 + *weight * hit.0.x, sum.1 + *weight * hit.0.y)
+            });
+        let (mean_x, mean_y) = (sum_x / sum_weights, sum_y / sum_weights);
+        let variance =
+            self.hit_map
+                .iter()
+                .zip(&weights)
+                .fold(Area::zero(), |sum, (hit, weight)| {
+                    sum + *weight
+                        * ((hit.0.x - mean_x) * (hit.0.x - mean_x)
+                            + (hit.0.y - mean_y) * (hit.0.y - mean_y))
+                })
+                / (2.0 * sum_weights);
+        let nr_of_points = sum_weights * sum_weights / weights.iter().map(|w| w * w).sum::<f64>();
+        (variance.sqrt(), nr_of_points)
+    }
+    /// Returns the band width of this [`Kde`] determined with the given [`BandwidthSelection`] strategy.
+    ///
+    /// The estimation strategies return `NaN` if the band width cannot be determined (e.g. less than two hit points).
+    #[must_use]
+    pub fn bandwidth(&self, selection: &BandwidthSelection) -> Length {
+        match selection {
+            BandwidthSelection::Silverman => self.bandwidth_estimate(),
+            BandwidthSelection::Scott => match self.hit_map.len() {
+                0 | 1 => millimeter!(f64::NAN),
+                _ => {
+                    // Scott's rule of thumb for two dimensions
+                    let (std_dev, nr_of_points) = self.positions_std_dev();
+                    let bw = std_dev * nr_of_points.powf(-1.0 / 6.0);
+                    if bw.is_zero() {
+                        millimeter!(f64::NAN)
+                    } else {
+                        bw
+                    }
+                }
+            },
+            BandwidthSelection::Fixed(band_width) => *band_width,
+        }
+    }
     #[must_use]
     pub fn bandwidth_estimate(&self) -> Length {
         match self.hit_map.len() {
@@ -105,10 +242,18 @@ impl Kde {
     }
     #[must_use]
     pub fn kde_value(&self, point: Point2<Length>) -> Fluence {
-        self.hit_map
-            .iter()
-            .map(|hit| Gaussian2D::new(hit.0, self.band_width, hit.1).value(point))
-            .sum()
+        let hits = self.hit_map.iter();
+        match self.kernel {
+            KdeKernel::Gaussian => hits
+                .map(|hit| Gaussian2D::new(hit.0, self.band_width, hit.1).value(point))
+                .sum(),
+            KdeKernel::Epanechnikov => hits
+                .map(|hit| Epanechnikov2D::new(hit.0, self.band_width, hit.1).value(point))
+                .sum(),
+            KdeKernel::TopHat => hits
+                .map(|hit| TopHat2D::new(hit.0, self.band_width, hit.1).value(point))
+                .sum(),
+        }
     }
     #[must_use]
     pub fn kde_2d(
@@ -138,7 +283,7 @@ impl Kde {
 mod test {
     use approx::assert_abs_diff_eq;
 
-    use super::Kde;
+    use super::{BandwidthSelection, Kde, KdeKernel};
     use crate::{joule, meter, millimeter};
     use core::f64;
     #[test]
@@ -148,6 +293,32 @@ mod test {
         assert_eq!(kde.band_width, millimeter!(1.0));
     }
     #[test]
+    fn set_kernel() {
+        let mut kde = Kde::default();
+        assert_eq!(kde.kernel, KdeKernel::Gaussian);
+        kde.set_kernel(KdeKernel::Epanechnikov);
+        assert_eq!(kde.kernel, KdeKernel::Epanechnikov);
+    }
+    #[test]
+    fn kernel_fmt() {
+        assert_eq!(format!("{}", KdeKernel::Gaussian), "Gaussian");
+        assert_eq!(format!("{}", KdeKernel::Epanechnikov), "Epanechnikov");
+        assert_eq!(format!("{}", KdeKernel::TopHat), "Top-hat");
+    }
+    #[test]
+    fn kde_value_compact_kernels() {
+        let mut kde = Kde::default();
+        kde.set_hit_map(vec![(millimeter!(0.0, 0.0), joule!(1.0))]);
+        kde.set_band_width(millimeter!(1.0)).unwrap();
+        // the Gaussian kernel has an infinite support
+        assert!(kde.kde_value(millimeter!(3.0, 0.0)).value > 0.0);
+        for kernel in [KdeKernel::Epanechnikov, KdeKernel::TopHat] {
+            kde.set_kernel(kernel);
+            assert!(kde.kde_value(millimeter!(0.0, 0.0)).value > 0.0);
+            assert_eq!(kde.kde_value(millimeter!(3.0, 0.0)).value, 0.0);
+        }
+    }
+    #[test]
     fn set_hit_map() {
         let mut kde = Kde::default();
         let hit_map = vec![(millimeter!(1.0, 2.0), joule!(3.0))];
@@ -230,6 +401,50 @@ mod test {
         assert_eq!(Kde::distances_iqr(&lengths), meter!(28.0));
     }
     #[test]
+    fn bandwidth() {
+        let mut kde = Kde::default();
+        assert!(kde.bandwidth(&BandwidthSelection::Scott).is_nan());
+        assert!(kde.bandwidth(&BandwidthSelection::Silverman).is_nan());
+        assert_eq!(
+            kde.bandwidth(&BandwidthSelection::Fixed(millimeter!(2.0))),
+            millimeter!(2.0)
+        );
+        let hit_map = vec![
+            (millimeter!(0.0, 0.0), joule!(0.0)),
+            (millimeter!(0.0, 0.0), joule!(0.0)),
+        ];
+        kde.set_hit_map(hit_map);
+        assert!(kde.bandwidth(&BandwidthSelection::Scott).is_nan());
+        let hit_map = vec![
+            (millimeter!(1.0, 0.0), joule!(0.0)),
+            (millimeter!(-1.0, 0.0), joule!(0.0)),
+            (millimeter!(0.0, 1.0), joule!(0.0)),
+            (millimeter!(0.0, -1.0), joule!(0.0)),
+        ];
+        kde.set_hit_map(hit_map);
+        assert_abs_diff_eq!(
+            kde.bandwidth(&BandwidthSelection::Scott).value,
+            f64::sqrt(0.5) * 4.0_f64.powf(-1.0 / 6.0) * 1.0e-3
+        );
+        // energy weighting: only two points effectively contribute
+        let hit_map = vec![
+            (millimeter!(1.0, 0.0), joule!(1.0)),
+            (millimeter!(-1.0, 0.0), joule!(1.0)),
+            (millimeter!(0.0, 10.0), joule!(0.0)),
+            (millimeter!(0.0, -10.0), joule!(0.0)),
+        ];
+        kde.set_hit_map(hit_map);
+        assert_abs_diff_eq!(
+            kde.bandwidth(&BandwidthSelection::Scott).value,
+            f64::sqrt(0.5) * 2.0_f64.powf(-1.0 / 6.0) * 1.0e-3,
+            epsilon = 1.0e-15
+        );
+        assert_eq!(
+            kde.bandwidth(&BandwidthSelection::Silverman),
+            kde.bandwidth_estimate()
+        );
+    }
+    #[test]
     fn bandwidth_estimate() {
         let mut kde = Kde::default();
         assert!(kde.bandwidth_estimate().is_nan());
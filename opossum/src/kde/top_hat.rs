@@ -0,0 +1,72 @@
+use crate::nodes::fluence_detector::Fluence;
+use nalgebra::Point2;
+use num::Zero;
+use uom::si::f64::{Area, Energy, Length};
+
+pub struct TopHat2D {
+    mean: Point2<Length>,
+    radius_square: Area,
+    amplitude: Fluence,
+}
+impl TopHat2D {
+    /// Creates a new [`TopHat2D`] kernel with the same standard deviation `sigma` as a [`Gaussian2D`](super::gaussian::Gaussian2D).
+    ///
+    /// The kernel is a uniform disc with a radius of `2 * sigma`.
+    pub fn new(mean: Point2<Length>, sigma: Length, weight: Energy) -> Self {
+        let radius = 2.0 * sigma;
+        let radius_square = radius * radius;
+        Self {
+            mean,
+            radius_square,
+            amplitude: weight / (std::f64::consts::PI * radius_square),
+        }
+    }
+    fn distance_squared(point1: &Point2<Length>, point2: &Point2<Length>) -> Area {
+        (point1.x - point2.x) * (point1.x - point2.x)
+            + (point1.y - point2.y) * (point1.y - point2.y)
+    }
+    pub fn value(&self, point: Point2<Length>) -> Fluence {
+        if Self::distance_squared(&point, &self.mean) < self.radius_square {
+            self.amplitude
+        } else {
+            Fluence::zero()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TopHat2D;
+    use crate::{joule, millimeter, nodes::fluence_detector::Fluence, utils::griddata::linspace};
+    use approx::assert_abs_diff_eq;
+    use nalgebra::DMatrix;
+    use uom::si::{f64::Ratio, ratio::ratio};
+
+    #[test]
+    fn check_norm() {
+        let g = TopHat2D::new(millimeter!(0.0, 0.0), millimeter!(20.0), joule!(1.0));
+        let nr_of_points = (120, 120);
+        let grid_element_area = millimeter!(200.0) * millimeter!(200.0)
+            / Ratio::new::<ratio>((nr_of_points.0 * nr_of_points.1) as f64);
+
+        let x_pos = linspace(-100.0, 100.0, nr_of_points.0).unwrap();
+        let y_pos = linspace(-100.0, 100.0, nr_of_points.1).unwrap();
+        let mut field = DMatrix::<Fluence>::zeros(nr_of_points.0, nr_of_points.1);
+        for i_x in 0..nr_of_points.0 {
+            for i_y in 0..nr_of_points.1 {
+                field[(i_x, i_y)] = g.value(millimeter!(x_pos[i_x], y_pos[i_y]));
+            }
+        }
+        let total_energy = field.sum() * grid_element_area;
+        assert_abs_diff_eq!(total_energy.value, 1.0, epsilon = 0.05);
+    }
+    #[test]
+    fn compact_support() {
+        let g = TopHat2D::new(millimeter!(0.0, 0.0), millimeter!(1.0), joule!(1.0));
+        assert_abs_diff_eq!(
+            g.value(millimeter!(0.0, 1.9)).value,
+            g.value(millimeter!(0.0, 0.0)).value
+        );
+        assert_eq!(g.value(millimeter!(0.0, 2.1)).value, 0.0);
+    }
+}
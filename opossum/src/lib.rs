@@ -48,7 +48,7 @@ pub mod utils;
 
 use chrono::DateTime;
 pub use opm_document::OpmDocument;
-pub use optic_scenery_rsc::SceneryResources;
+pub use optic_scenery_rsc::{DEFAULT_FLUENCE_GRID, SceneryResources};
 
 /// Return the version information of the currently built OPOSSUM executable.
 ///
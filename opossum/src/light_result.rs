@@ -4,6 +4,7 @@
 use crate::{
     error::{OpmResult, OpossumError},
     lightdata::LightData,
+    optic_ports::{OpticPorts, PortType},
     rays::Rays,
 };
 use std::collections::HashMap;
@@ -36,3 +37,39 @@ pub fn light_rays_to_light_result(light_rays: LightRays) -> LightResult {
     }
     light_result
 }
+/// Passes the incoming light unchanged through a node with the given ports.
+///
+/// This is used for bypassing disabled nodes. The light arriving at the n-th input port (in alphabetical order) leaves
+/// the node at the n-th output port. Light arriving at input ports without a corresponding output port is discarded.
+#[must_use]
+pub fn bypass<T: Clone>(incoming_data: &LightDings<T>, ports: &OpticPorts) -> LightDings<T> {
+    ports
+        .names(&PortType::Input)
+        .iter()
+        .zip(ports.names(&PortType::Output))
+        .filter_map(|(in_port, out_port)| {
+            incoming_data
+                .get(in_port)
+                .map(|data| (out_port, data.clone()))
+        })
+        .collect()
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn bypass_ports() {
+        let mut ports = OpticPorts::default();
+        ports.add(&PortType::Input, "input_1").unwrap();
+        ports.add(&PortType::Input, "input_2").unwrap();
+        ports.add(&PortType::Output, "output_1").unwrap();
+        let incoming = LightResult::from([
+            ("input_1".into(), LightData::Fourier),
+            ("input_2".into(), LightData::Fourier),
+        ]);
+        let outgoing = bypass(&incoming, &ports);
+        assert_eq!(outgoing.len(), 1);
+        assert!(outgoing.contains_key("output_1"));
+        assert!(bypass(&LightResult::default(), &ports).is_empty());
+    }
+}
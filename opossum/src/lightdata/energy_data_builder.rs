@@ -17,13 +17,18 @@ use super::LightData;
 
 /// Builder for the generation of energy spectra.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum EnergyDataBuilder {
     /// Build a spectrum from raw data.
     Raw(Spectrum),
     /// Build a spectrum from a (CSV) file.
     FromFile(PathBuf),
     /// Build a spectrum from a set of (narrow) laser lines (center wavelength, energy) and a given spectrum resolution.
-    LaserLines(Vec<(Length, Energy)>, Length),
+    LaserLines(
+        #[cfg_attr(feature = "json_schema", schemars(with = "Vec<(f64, f64)>"))]
+        Vec<(Length, Energy)>,
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))] Length,
+    ),
 }
 impl EnergyDataBuilder {
     /// Build the spectrum from the builder.
@@ -4,12 +4,20 @@
 //! This builder allows easier serialization / deserialization in OPM files.
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use uom::si::f64::Length;
 
 use super::{LightData, energy_data_builder::EnergyDataBuilder, ray_data_builder::RayDataBuilder};
-use crate::{error::OpmResult, properties::Proptype};
+use crate::{
+    error::{OpmResult, OpossumError},
+    joule,
+    properties::Proptype,
+    spectral_distribution::LaserLines,
+    spectrum::Spectrum,
+};
 
 /// Builder for the generation of [`LightData`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum LightDataBuilder {
     /// Builder for the generation of [`LightData::Energy`].
     Energy(EnergyDataBuilder),
@@ -26,12 +34,102 @@ impl LightDataBuilder {
     ///
     /// This function will return an error if the concrete implementation of the builder fails.
     pub fn build(self) -> OpmResult<LightData> {
+        self.build_seeded(None)
+    }
+    /// Create [`LightData`] from the builder definition using an optional random `seed`.
+    ///
+    /// See [`RayDataBuilder::build_seeded`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the concrete implementation of the builder fails.
+    pub fn build_seeded(self, seed: Option<u64>) -> OpmResult<LightData> {
         match self {
             Self::Energy(e) => e.build(),
-            Self::Geometric(r) => r.build(),
+            Self::Geometric(r) => r.build_seeded(seed),
             Self::Fourier => Ok(LightData::Fourier),
         }
     }
+    /// Returns a copy of this builder emitting monochromatic light of the given wavelength.
+    ///
+    /// All other parameters (such as the total energy or the position distribution) are kept. An energy spectrum is
+    /// replaced by a single laser line carrying the total energy of the spectrum.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the given wavelength is not positive and finite.
+    ///   - the builder contains raw [`Rays`](crate::rays::Rays), whose wavelengths cannot be changed.
+    ///   - the spectrum of an [`EnergyDataBuilder::FromFile`] cannot be read.
+    pub fn with_wavelength(&self, wavelength: Length) -> OpmResult<Self> {
+        if !wavelength.is_normal() || wavelength.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "wavelength must be positive and finite".into(),
+            ));
+        }
+        let builder = match self {
+            Self::Energy(EnergyDataBuilder::LaserLines(lines, resolution)) => {
+                let energy = lines.iter().map(|(_, e)| *e).sum();
+                Self::Energy(EnergyDataBuilder::LaserLines(
+                    vec![(wavelength, energy)],
+                    *resolution,
+                ))
+            }
+            Self::Energy(EnergyDataBuilder::Raw(spectrum)) => {
+                Self::Energy(Self::laser_line(spectrum, wavelength))
+            }
+            Self::Energy(EnergyDataBuilder::FromFile(path)) => {
+                Self::Energy(Self::laser_line(&Spectrum::from_csv(path)?, wavelength))
+            }
+            Self::Geometric(RayDataBuilder::Raw(_)) => {
+                return Err(OpossumError::Other(
+                    "cannot change the wavelength of raw ray data".into(),
+                ));
+            }
+            Self::Geometric(RayDataBuilder::Collimated {
+                pos_dist,
+                energy_dist,
+                ..
+            }) => Self::Geometric(RayDataBuilder::Collimated {
+                pos_dist: pos_dist.clone(),
+                energy_dist: energy_dist.clone(),
+                spect_dist: LaserLines::new(vec![(wavelength, 1.0)])?.into(),
+            }),
+            Self::Geometric(RayDataBuilder::PointSrc {
+                pos_dist,
+                energy_dist,
+                reference_length,
+                ..
+            }) => Self::Geometric(RayDataBuilder::PointSrc {
+                pos_dist: pos_dist.clone(),
+                energy_dist: energy_dist.clone(),
+                spect_dist: LaserLines::new(vec![(wavelength, 1.0)])?.into(),
+                reference_length: *reference_length,
+            }),
+            Self::Geometric(RayDataBuilder::Image {
+                file_path,
+                pixel_size,
+                total_energy,
+                cone_angle,
+                ..
+            }) => Self::Geometric(RayDataBuilder::Image {
+                file_path: file_path.clone(),
+                pixel_size: *pixel_size,
+                total_energy: *total_energy,
+                wave_length: wavelength,
+                cone_angle: *cone_angle,
+            }),
+            Self::Fourier => Self::Fourier,
+        };
+        Ok(builder)
+    }
+    /// Single laser line carrying the total energy of the given spectrum.
+    fn laser_line(spectrum: &Spectrum, wavelength: Length) -> EnergyDataBuilder {
+        EnergyDataBuilder::LaserLines(
+            vec![(wavelength, joule!(spectrum.total_energy()))],
+            spectrum.average_resolution(),
+        )
+    }
 }
 
 impl Display for LightDataBuilder {
@@ -51,7 +149,11 @@ impl From<Option<LightDataBuilder>> for Proptype {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{joule, nanometer, rays::Rays};
+    use crate::{
+        energy_distributions::UniformDist, millimeter, nanometer,
+        position_distributions::Hexapolar, rays::Rays,
+    };
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
 
     #[test]
     fn from_light_data_builder_to_proptype() {
@@ -88,4 +190,74 @@ mod tests {
         let light_data = light_data_builder.build().unwrap();
         assert!(matches!(light_data, LightData::Geometric(_)));
     }
+    #[test]
+    fn with_wavelength() {
+        let light_data_builder = LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+            vec![
+                (nanometer!(1000.0), joule!(1.0)),
+                (nanometer!(500.0), joule!(2.0)),
+            ],
+            nanometer!(1.0),
+        ));
+        assert!(light_data_builder.with_wavelength(nanometer!(0.0)).is_err());
+        assert!(
+            light_data_builder
+                .with_wavelength(nanometer!(f64::NAN))
+                .is_err()
+        );
+        assert_eq!(
+            light_data_builder
+                .with_wavelength(nanometer!(633.0))
+                .unwrap(),
+            LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+                vec![(nanometer!(633.0), joule!(3.0))],
+                nanometer!(1.0),
+            ))
+        );
+        let mut spectrum =
+            Spectrum::new(nanometer!(400.0)..nanometer!(800.0), nanometer!(1.0)).unwrap();
+        spectrum.add_single_peak(nanometer!(500.0), 2.0).unwrap();
+        let light_data_builder = LightDataBuilder::Energy(EnergyDataBuilder::Raw(spectrum));
+        let LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(lines, resolution)) =
+            light_data_builder
+                .with_wavelength(nanometer!(633.0))
+                .unwrap()
+        else {
+            panic!("wrong builder type")
+        };
+        assert_eq!(lines[0].0, nanometer!(633.0));
+        assert_abs_diff_eq!(lines[0].1.value, 2.0);
+        assert_abs_diff_eq!(resolution.value, nanometer!(1.0).value);
+        let light_data_builder = LightDataBuilder::Geometric(RayDataBuilder::Collimated {
+            pos_dist: Hexapolar::new(millimeter!(1.0), 1).unwrap().into(),
+            energy_dist: UniformDist::new(joule!(1.0)).unwrap().into(),
+            spect_dist: LaserLines::new(vec![(nanometer!(1000.0), 1.0)])
+                .unwrap()
+                .into(),
+        });
+        let LightData::Geometric(rays) = light_data_builder
+            .with_wavelength(nanometer!(633.0))
+            .unwrap()
+            .build()
+            .unwrap()
+        else {
+            panic!("wrong light data type")
+        };
+        let wavelengths = rays.get_unique_wavelengths(true);
+        assert_eq!(wavelengths.len(), 1);
+        assert_relative_eq!(wavelengths[0].value, nanometer!(633.0).value);
+        assert_abs_diff_eq!(rays.total_energy().value, 1.0);
+        let light_data_builder = LightDataBuilder::Geometric(RayDataBuilder::Raw(Rays::default()));
+        assert!(
+            light_data_builder
+                .with_wavelength(nanometer!(633.0))
+                .is_err()
+        );
+        assert_eq!(
+            LightDataBuilder::Fourier
+                .with_wavelength(nanometer!(633.0))
+                .unwrap(),
+            LightDataBuilder::Fourier
+        );
+    }
 }
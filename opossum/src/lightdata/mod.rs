@@ -4,10 +4,13 @@
 pub mod energy_data_builder;
 pub mod light_data_builder;
 pub mod ray_data_builder;
-use crate::{joule, rays::Rays, spectrum::Spectrum};
+use crate::{joule, properties::proptype::format_quantity, rays::Rays, spectrum::Spectrum};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
-use uom::{fmt::DisplayStyle::Abbreviation, si::energy::joule};
+use uom::{
+    fmt::DisplayStyle::Abbreviation,
+    si::{energy::joule, f64::Length, length::meter},
+};
 
 /// Data structure defining the light properties.
 ///
@@ -26,6 +29,56 @@ pub enum LightData {
     /// placeholder value for future Fourier optics analysis, nothing implementd yet.
     Fourier,
 }
+impl LightData {
+    /// Returns a short (single line) summary of this [`LightData`].
+    ///
+    /// The summary contains the number of rays (if applicable), the total energy and the wavelength range. `None` is
+    /// returned for data types without a meaningful summary (e.g. [`LightData::Fourier`]).
+    #[must_use]
+    pub fn summary(&self) -> Option<String> {
+        match self {
+            Self::Energy(s) => {
+                let range = s.range();
+                Some(format!(
+                    "E = {}, {}",
+                    format_quantity(joule, joule!(s.total_energy())).trim(),
+                    format_wavelength_range(range.start, range.end)
+                ))
+            }
+            Self::Geometric(rays) => {
+                let mut summary = format!(
+                    "{} rays, E = {}",
+                    rays.nr_of_rays(true),
+                    format_quantity(joule, rays.total_energy()).trim()
+                );
+                if let Some(range) = rays.wavelength_range() {
+                    summary += &format!(", {}", format_wavelength_range(range.start, range.end));
+                }
+                Some(summary)
+            }
+            Self::GhostFocus(bundles) => {
+                let energy = bundles.iter().map(Rays::total_energy).sum();
+                Some(format!(
+                    "{} ray bundles, E = {}",
+                    bundles.len(),
+                    format_quantity(joule, energy).trim()
+                ))
+            }
+            Self::Fourier => None,
+        }
+    }
+}
+fn format_wavelength_range(min: Length, max: Length) -> String {
+    if min == max {
+        format!("λ = {}", format_quantity(meter, min).trim())
+    } else {
+        format!(
+            "λ = {} - {}",
+            format_quantity(meter, min).trim(),
+            format_quantity(meter, max).trim()
+        )
+    }
+}
 impl Display for LightData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -43,7 +96,8 @@ impl Display for LightData {
 #[cfg(test)]
 mod test {
     use crate::{
-        lightdata::light_data_builder::LightDataBuilder, properties::Proptype,
+        lightdata::light_data_builder::LightDataBuilder, millimeter, nanometer,
+        position_distributions::Hexapolar, properties::Proptype,
         spectrum_helper::create_visible_spec,
     };
 
@@ -62,6 +116,22 @@ mod test {
         assert_eq!(format!("{ld}"), "Energy: 0 J");
     }
     #[test]
+    fn summary() {
+        assert!(LightData::Fourier.summary().is_none());
+        let ld = LightData::Geometric(Rays::default());
+        assert_eq!(ld.summary().unwrap(), "0 rays, E = 0.000 J");
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(7.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap();
+        let ld = LightData::Geometric(rays.clone());
+        assert_eq!(ld.summary().unwrap(), "7 rays, E = 7.000 J, λ = 1.000 μm");
+        let ld = LightData::GhostFocus(vec![rays.clone(), rays]);
+        assert_eq!(ld.summary().unwrap(), "2 ray bundles, E = 14.000 J");
+    }
+    #[test]
     fn debug() {
         assert_eq!(format!("{:?}", LightData::Fourier), "Fourier");
     }
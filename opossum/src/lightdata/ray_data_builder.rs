@@ -6,19 +6,27 @@ use std::{fmt::Display, path::PathBuf};
 
 use super::LightData;
 use crate::{
-    energy_distributions::EnergyDistType, error::OpmResult, position_distributions::PosDistType,
-    rays::Rays, spectral_distribution::SpecDistType,
+    energy_distributions::EnergyDistType,
+    error::OpmResult,
+    position_distributions::{PosDistType, PositionDistribution},
+    rays::Rays,
+    spectral_distribution::SpecDistType,
 };
+use nalgebra::Point3;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
 use uom::si::{
     f64::{Angle, Energy, Length},
     length::meter,
 };
+use uuid::Builder;
 
 /// Builder for the generation of [`LightData::Geometric`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum RayDataBuilder {
     /// Raw [`Rays`] data.
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     Raw(Rays),
     /// Collimated [`Rays`] data with a given [`PosDistType`], [`EnergyDistType`], and [`SpecDistType`].
     Collimated {
@@ -40,6 +48,7 @@ pub enum RayDataBuilder {
         /// Wavelength of the rays.
         spect_dist: SpecDistType,
         /// Length
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
         reference_length: Length,
     },
     /// A bundle of rays emitted from a 2D black & white image specified by its file path, the actual (x/y) dimenstions of the image as well as the
@@ -48,12 +57,16 @@ pub enum RayDataBuilder {
         /// path to the image file
         file_path: PathBuf,
         /// x & y dimensions of the image
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
         pixel_size: Length,
         /// total energy
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
         total_energy: Energy,
         /// wavelength
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
         wave_length: Length,
         /// cone angle of each point src per pixel
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
         cone_angle: Angle,
     },
 }
@@ -64,6 +77,16 @@ impl RayDataBuilder {
     /// # Errors
     /// This function will return an error if the concrete implementation of the builder fails.
     pub fn build(self) -> OpmResult<LightData> {
+        self.build_seeded(None)
+    }
+    /// Create [`LightData::Geometric`] from the builder definition using an optional random `seed`.
+    ///
+    /// If a `seed` is given, random position distributions are generated reproducibly and the uuid of the ray bundle
+    /// is derived from the seed, i.e. identical seeds result in identical [`Rays`]. For `None`, this function behaves like [`build`](RayDataBuilder::build).
+    ///
+    /// # Errors
+    /// This function will return an error if the concrete implementation of the builder fails.
+    pub fn build_seeded(self, seed: Option<u64>) -> OpmResult<LightData> {
         match self {
             Self::Raw(rays) => Ok(LightData::Geometric(rays)),
             Self::Collimated {
@@ -71,11 +94,12 @@ impl RayDataBuilder {
                 energy_dist,
                 spect_dist,
             } => {
-                let rays = Rays::new_collimated_with_spectrum(
+                let mut rays = Rays::new_collimated_with_spectrum(
                     spect_dist.generate(),
                     energy_dist.generate(),
-                    pos_dist.generate(),
+                    &SeededDistribution::new(pos_dist.generate(), seed),
                 )?;
+                set_seeded_uuid(&mut rays, seed);
                 Ok(LightData::Geometric(rays))
             }
             Self::PointSrc {
@@ -84,12 +108,13 @@ impl RayDataBuilder {
                 spect_dist,
                 reference_length,
             } => {
-                let rays = Rays::new_point_src_with_spectrum(
+                let mut rays = Rays::new_point_src_with_spectrum(
                     spect_dist.generate(),
                     energy_dist.generate(),
-                    pos_dist.generate(),
+                    &SeededDistribution::new(pos_dist.generate(), seed),
                     reference_length,
                 )?;
+                set_seeded_uuid(&mut rays, seed);
                 Ok(LightData::Geometric(rays))
             }
             Self::Image {
@@ -109,6 +134,32 @@ impl RayDataBuilder {
     }
 }
 
+/// Replaces the (random) uuid of the given ray bundle by one derived from `seed` (if given).
+fn set_seeded_uuid(rays: &mut Rays, seed: Option<u64>) {
+    if let Some(seed) = seed {
+        let bytes = StdRng::seed_from_u64(seed).random::<[u8; 16]>();
+        rays.set_uuid(Builder::from_random_bytes(bytes).into_uuid());
+    }
+}
+/// Wrapper of a [`PositionDistribution`] which generates its points using an optional random seed
+struct SeededDistribution<'a> {
+    dist: &'a dyn PositionDistribution,
+    seed: Option<u64>,
+}
+impl<'a> SeededDistribution<'a> {
+    const fn new(dist: &'a dyn PositionDistribution, seed: Option<u64>) -> Self {
+        Self { dist, seed }
+    }
+}
+impl PositionDistribution for SeededDistribution<'_> {
+    fn generate(&self) -> Vec<Point3<Length>> {
+        self.seed.map_or_else(
+            || self.dist.generate(),
+            |seed| self.dist.generate_seeded(seed),
+        )
+    }
+}
+
 impl Display for RayDataBuilder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1,12 +1,16 @@
 //! Main function of Opossum
 #![warn(missing_docs)]
+use chrono::Local;
 use clap::Parser;
 use env_logger::Env;
 use log::{error, info, warn};
-use opossum::reporting::analysis_report::AnalysisReport;
+use opossum::reporting::{
+    ReportFormat, analysis_report::AnalysisReport, markdown_report::markdown_report,
+};
 use opossum::{
     OpmDocument,
     console::{Args, PartialArgs},
+    dottable::DotOptions,
     error::{OpmResult, OpossumError},
     nodes::NodeGroup,
 };
@@ -15,6 +19,7 @@ use std::{
     fs::{File, create_dir, remove_dir_all},
     io::{self, Write},
     path::{Path, PathBuf},
+    process::ExitCode,
 };
 
 fn read_and_parse_model(path: &Path) -> OpmResult<OpmDocument> {
@@ -47,7 +52,7 @@ fn create_dot_or_report_file_instance(
 fn create_dot_file(dot_path: &Path, scenery: &NodeGroup) -> OpmResult<()> {
     let mut output = create_dot_or_report_file_instance(dot_path, "scenery", "dot", "diagram")?;
 
-    write!(output, "{}", scenery.toplevel_dot("")?)
+    write!(output, "{}", scenery.toplevel_dot(&DotOptions::default())?)
         .map_err(|e| OpossumError::Other(format!("writing diagram file (.dot) failed: {e}")))?;
 
     let mut output = create_dot_or_report_file_instance(dot_path, "scenery", "svg", "diagram")?;
@@ -67,6 +72,22 @@ fn create_data_dir(report_directory: &Path) -> OpmResult<()> {
     create_dir(&data_dir)
         .map_err(|e| OpossumError::Other(format!("creating data directory failed: {e}")))
 }
+/// Create a new, timestamped subdirectory of the given report directory.
+///
+/// This is used for preserving the outputs of previous runs. If a directory with the same timestamp already exists,
+/// a numbered suffix is appended.
+fn create_timestamped_report_dir(report_directory: &Path) -> OpmResult<PathBuf> {
+    let timestamp = Local::now().format("run_%Y%m%d_%H%M%S").to_string();
+    let mut run_dir = report_directory.join(&timestamp);
+    let mut suffix = 1;
+    while run_dir.exists() {
+        run_dir = report_directory.join(format!("{timestamp}_{suffix}"));
+        suffix += 1;
+    }
+    create_dir(&run_dir)
+        .map_err(|e| OpossumError::Other(format!("creating report directory failed: {e}")))?;
+    Ok(run_dir)
+}
 fn create_report_and_data_files(
     report_directory: &Path,
     report: &AnalysisReport,
@@ -87,39 +108,128 @@ fn create_report_and_data_files(
     report.to_html_report()?.generate_html(&report_path)?;
     Ok(())
 }
+/// Create a self-contained html report (with all plots embedded) instead of the report data file.
+fn create_self_contained_report_file(
+    report_directory: &Path,
+    report: &AnalysisReport,
+    report_number: usize,
+) -> OpmResult<()> {
+    report.export_data(report_directory)?;
+    let report_path = create_f_path(report_directory, &format!("report_{report_number}"), "html");
+    info!(
+        "Write self-contained html report to {}",
+        report_path.display()
+    );
+    report
+        .to_html_report()?
+        .generate_self_contained_html(&report_path)
+}
+/// Create a Markdown report of all analyses (with links to the exported data files).
+fn create_markdown_report_file(
+    report_directory: &Path,
+    reports: &[AnalysisReport],
+) -> OpmResult<()> {
+    let mut output =
+        create_dot_or_report_file_instance(report_directory, "report", "md", "markdown report")?;
+    for report in reports {
+        report.export_data(report_directory)?;
+    }
+    write!(output, "{}", markdown_report(reports))
+        .map_err(|e| OpossumError::Other(format!("writing markdown report failed: {e}")))
+}
 
-fn opossum() -> OpmResult<()> {
-    // by default, log everything from level `info` and up.
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+/// Validate the model and log all found problems.
+///
+/// Returns a failure exit code if any problems were found.
+fn check_model(document: &OpmDocument) -> OpmResult<ExitCode> {
+    info!("Validating model...");
+    let warnings = document.validate()?;
+    for warning in &warnings {
+        warn!("{warning}");
+    }
+    info!("Validation finished with {} warning(s)", warnings.len());
+    Ok(if warnings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+fn opossum() -> OpmResult<ExitCode> {
+    let partial_args = PartialArgs::parse();
+    // by default, log everything from level `info` and up. The `--quiet` / `--verbose` flags override the environment.
+    if let Some(log_level) = partial_args.log_level() {
+        env_logger::Builder::new().filter_level(log_level).init();
+    } else {
+        env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    }
     warn!(
         "Current work dir: {}",
         env::current_dir().unwrap().display()
     );
     // parse CLI arguments
-    let opossum_args = Args::try_from(PartialArgs::parse())?;
+    let opossum_args = Args::try_from(partial_args)?;
 
     // read scenery model from file and deserialize it
     let mut document = read_and_parse_model(&opossum_args.file_path)?;
+    if let Some(fluence_grid) = opossum_args.fluence_grid {
+        info!(
+            "Using fluence grid of {}x{} points",
+            fluence_grid.0, fluence_grid.1
+        );
+        document.set_fluence_grid(Some(fluence_grid))?;
+    }
+    if opossum_args.check {
+        return check_model(&document);
+    }
+    let report_directory = if opossum_args.no_clean {
+        let run_dir = create_timestamped_report_dir(&opossum_args.report_directory)?;
+        info!(
+            "Preserving previous outputs, writing to {}",
+            run_dir.display()
+        );
+        run_dir
+    } else {
+        opossum_args.report_directory
+    };
     // create the dot file of the scenery
-    create_data_dir(&opossum_args.report_directory)?;
-    create_dot_file(&opossum_args.report_directory, document.scenery())?;
+    create_data_dir(&report_directory)?;
+    create_dot_file(&report_directory, document.scenery())?;
     let reports = document.analyze()?;
-    for report in reports.iter().enumerate() {
-        create_report_and_data_files(&opossum_args.report_directory, report.1, report.0)?;
+    match opossum_args.report_format {
+        ReportFormat::Ron => {
+            for report in reports.iter().enumerate() {
+                create_report_and_data_files(&report_directory, report.1, report.0)?;
+            }
+        }
+        ReportFormat::Html => {
+            for report in reports.iter().enumerate() {
+                create_self_contained_report_file(&report_directory, report.1, report.0)?;
+            }
+        }
+        ReportFormat::Markdown => {
+            create_markdown_report_file(&report_directory, &reports)?;
+        }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 /// OPOSSUM main function
 ///
-/// This function is only a wrapper for the `opossum()` function and does general error handling.
-fn main() {
-    if let Err(e) = opossum() {
+/// This function is only a wrapper for the `opossum()` function and does general error handling. The program exits
+/// with a non-zero status if an error occurred or if the validation (`--check`) found any problems.
+fn main() -> ExitCode {
+    opossum().unwrap_or_else(|e| {
         error!("{e}");
-    }
+        ExitCode::FAILURE
+    })
 }
 #[cfg(test)]
 mod test {
     use super::*;
+    use opossum::{
+        analyzers::AnalyzerType,
+        joule, millimeter,
+        nodes::{Dummy, round_collimated_ray_source},
+    };
     use std::fs;
 
     #[test]
@@ -145,4 +255,56 @@ mod test {
         );
         assert!(report_file.is_err());
     }
+    #[test]
+    fn create_html_report_file_test() {
+        let mut document =
+            read_and_parse_model(Path::new("./files_for_testing/opm/opticscenery.opm")).unwrap();
+        let reports = document.analyze().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        create_data_dir(tmp_dir.path()).unwrap();
+        create_self_contained_report_file(tmp_dir.path(), &reports[0], 0).unwrap();
+        assert!(tmp_dir.path().join("report_0.html").exists());
+        assert!(!tmp_dir.path().join("report_0.ron").exists());
+    }
+    #[test]
+    fn create_markdown_report_file_test() {
+        let mut document =
+            read_and_parse_model(Path::new("./files_for_testing/opm/opticscenery.opm")).unwrap();
+        let reports = document.analyze().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        create_data_dir(tmp_dir.path()).unwrap();
+        create_markdown_report_file(tmp_dir.path(), &reports).unwrap();
+        let markdown = fs::read_to_string(tmp_dir.path().join("report.md")).unwrap();
+        assert!(markdown.starts_with("# OPOSSUM Analysis Report"));
+    }
+    #[test]
+    fn check_model_test() {
+        let document =
+            read_and_parse_model(Path::new("./files_for_testing/opm/opticscenery.opm")).unwrap();
+        assert_eq!(check_model(&document).unwrap(), ExitCode::FAILURE);
+        let mut scenery = NodeGroup::default();
+        let src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(1.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let dummy = scenery.add_node(Dummy::default()).unwrap();
+        scenery
+            .connect_nodes(src, "output_1", dummy, "input_1", millimeter!(10.0))
+            .unwrap();
+        let mut document = OpmDocument::new(scenery);
+        document.add_analyzer(AnalyzerType::Energy);
+        assert_eq!(check_model(&document).unwrap(), ExitCode::SUCCESS);
+    }
+    #[test]
+    fn create_timestamped_report_dir_test() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let run_dir1 = create_timestamped_report_dir(tmp_dir.path()).unwrap();
+        let run_dir2 = create_timestamped_report_dir(tmp_dir.path()).unwrap();
+        assert!(run_dir1.is_dir());
+        assert!(run_dir2.is_dir());
+        assert_ne!(run_dir1, run_dir2);
+        assert_eq!(run_dir1.parent(), Some(tmp_dir.path()));
+        assert!(
+            create_timestamped_report_dir(Path::new("./files_for_testing/_not_valid/")).is_err()
+        );
+    }
 }
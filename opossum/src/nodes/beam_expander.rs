@@ -0,0 +1,140 @@
+#![warn(missing_docs)]
+//! Helper function for creating afocal telescopes (beam expanders / reducers)
+use super::{NodeGroup, ParaxialSurface};
+use crate::{
+    error::{OpmResult, OpossumError},
+    optic_node::OpticNode,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uom::si::f64::Length;
+
+/// Type of a beam expander created by [`beam_expander`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpanderType {
+    /// two positive lenses with an intermediate (real) focus
+    #[default]
+    Keplerian,
+    /// a positive and a negative lens without an intermediate focus
+    Galilean,
+}
+impl Display for ExpanderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keplerian => write!(f, "Keplerian"),
+            Self::Galilean => write!(f, "Galilean"),
+        }
+    }
+}
+
+/// Create an (ideal) afocal beam expander.
+///
+/// This is a convenience function, which generates a [`NodeGroup`] consisting of two ideal lenses ([`ParaxialSurface`]s)
+/// separated such that a collimated input beam leaves the group collimated, expanded by the given `magnification`. A
+/// magnification < 1.0 results in a beam reducer. The absolute value of the focal length of the input lens is given by
+/// `focal_length_in`. The output lens focal length is scaled accordingly:
+///   - [`ExpanderType::Keplerian`]: both lenses are positive. The lens distance is `focal_length_in * (1 + magnification)`.
+///     The image is inverted.
+///   - [`ExpanderType::Galilean`]: for an expander, the input lens is negative, for a reducer the output lens. The lens
+///     distance is `focal_length_in * |magnification - 1|`. Hence, a magnification of 1.0 is not possible.
+///
+/// The group has the mapped ports `input_1` and `output_1`. The magnification is stored as `magnification` property of the
+/// group for documentation purposes. Since the internal nodes are normal group members, they can still be inspected.
+///
+/// # Errors
+///
+/// This function will return an error if
+///   - the magnification is not positive and finite.
+///   - the magnification is 1.0 for a [`ExpanderType::Galilean`] expander.
+///   - `focal_length_in` is not positive and finite.
+pub fn beam_expander(
+    name: &str,
+    magnification: f64,
+    focal_length_in: Length,
+    expander_type: ExpanderType,
+) -> OpmResult<NodeGroup> {
+    if !magnification.is_normal() || magnification.is_sign_negative() {
+        return Err(OpossumError::Other(
+            "magnification must be positive and finite".into(),
+        ));
+    }
+    if !focal_length_in.is_normal() || focal_length_in.is_sign_negative() {
+        return Err(OpossumError::Other(
+            "focal length must be positive and finite".into(),
+        ));
+    }
+    let focal_length_out = focal_length_in * magnification;
+    let (focal_length_in, focal_length_out) = match expander_type {
+        ExpanderType::Keplerian => (focal_length_in, focal_length_out),
+        ExpanderType::Galilean => {
+            if (magnification - 1.0).abs() < f64::EPSILON {
+                return Err(OpossumError::Other(
+                    "a Galilean telescope cannot have a magnification of 1.0".into(),
+                ));
+            }
+            if magnification > 1.0 {
+                (-focal_length_in, focal_length_out)
+            } else {
+                (focal_length_in, -focal_length_out)
+            }
+        }
+    };
+    let mut group = NodeGroup::new(name);
+    group.node_attr_mut().create_property(
+        "magnification",
+        "magnification of the beam expander",
+        magnification.into(),
+    )?;
+    let lens_in = group.add_node(ParaxialSurface::new("input lens", focal_length_in)?)?;
+    let lens_out = group.add_node(ParaxialSurface::new("output lens", focal_length_out)?)?;
+    group.connect_nodes(
+        lens_in,
+        "output_1",
+        lens_out,
+        "input_1",
+        focal_length_in + focal_length_out,
+    )?;
+    group.map_input_port(lens_in, "input_1", "input_1")?;
+    group.map_output_port(lens_out, "output_1", "output_1")?;
+    Ok(group)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{millimeter, optic_ports::PortType, properties::Proptype};
+    #[test]
+    fn expander_type_display() {
+        assert_eq!(format!("{}", ExpanderType::Keplerian), "Keplerian");
+        assert_eq!(format!("{}", ExpanderType::Galilean), "Galilean");
+    }
+    #[test]
+    fn beam_expander_errors() {
+        let f = millimeter!(100.0);
+        assert!(beam_expander("test", 0.0, f, ExpanderType::Keplerian).is_err());
+        assert!(beam_expander("test", -2.0, f, ExpanderType::Keplerian).is_err());
+        assert!(beam_expander("test", f64::NAN, f, ExpanderType::Keplerian).is_err());
+        assert!(beam_expander("test", f64::INFINITY, f, ExpanderType::Keplerian).is_err());
+        assert!(beam_expander("test", 2.0, millimeter!(0.0), ExpanderType::Keplerian).is_err());
+        assert!(beam_expander("test", 2.0, millimeter!(-10.0), ExpanderType::Galilean).is_err());
+        assert!(beam_expander("test", 1.0, f, ExpanderType::Galilean).is_err());
+        assert!(beam_expander("test", 1.0, f, ExpanderType::Keplerian).is_ok());
+    }
+    #[test]
+    fn beam_expander_group() {
+        for expander_type in [ExpanderType::Keplerian, ExpanderType::Galilean] {
+            for magnification in [0.5, 3.0] {
+                let group =
+                    beam_expander("test", magnification, millimeter!(50.0), expander_type).unwrap();
+                assert_eq!(group.name(), "test");
+                assert_eq!(group.nr_of_nodes(), 2);
+                assert_eq!(group.ports().names(&PortType::Input), vec!["input_1"]);
+                assert_eq!(group.ports().names(&PortType::Output), vec!["output_1"]);
+                let Ok(Proptype::F64(m)) = group.properties().get("magnification") else {
+                    panic!("magnification property not found")
+                };
+                assert_eq!(*m, magnification);
+            }
+        }
+    }
+}
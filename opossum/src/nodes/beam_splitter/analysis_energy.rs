@@ -72,6 +72,26 @@ mod test {
         assert_eq!(energy, 0.4);
     }
     #[test]
+    fn analyze_polarizing() {
+        let config = SplittingConfig::Polarizing {
+            p_transmission: 0.9,
+            s_transmission: 0.1,
+        };
+        let mut node = BeamSplitter::new("test", &config).unwrap();
+        let mut input = LightResult::default();
+        input.insert(
+            "input_1".into(),
+            LightData::Energy(create_he_ne_spec(1.0).unwrap()),
+        );
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        for port in ["out1_trans1_refl2", "out2_trans2_refl1"] {
+            let Some(LightData::Energy(s)) = output.get(port) else {
+                panic!("no spectrum at port {port}");
+            };
+            assert_abs_diff_eq!(s.total_energy(), 0.5);
+        }
+    }
+    #[test]
     fn analyze_two_input() {
         let mut node = BeamSplitter::new("test", &SplittingConfig::Ratio(0.6)).unwrap();
         let mut input = LightResult::default();
@@ -96,6 +96,8 @@ impl AnalysisRayTrace for BeamSplitter {
 #[cfg(test)]
 mod test {
     use approx::assert_abs_diff_eq;
+    use nalgebra::Vector2;
+    use num::{Complex, Zero};
 
     use crate::{
         analyzers::{RayTraceConfig, raytrace::AnalysisRayTrace},
@@ -146,6 +148,45 @@ mod test {
         assert_eq!(energy, 0.4);
     }
     #[test]
+    fn analyze_polarizing() {
+        let config = SplittingConfig::Polarizing {
+            p_transmission: 1.0,
+            s_transmission: 0.0,
+        };
+        let mut node = BeamSplitter::new("pbs", &config).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut input = LightResult::default();
+        let mut rays = Rays::default();
+        let mut p_ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(1.0)).unwrap();
+        p_ray
+            .set_polarization(Some(Vector2::new(Complex::new(1.0, 0.0), Complex::zero())))
+            .unwrap();
+        rays.add_ray(p_ray);
+        let mut s_ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(0.5)).unwrap();
+        s_ray
+            .set_polarization(Some(Vector2::new(Complex::zero(), Complex::new(1.0, 0.0))))
+            .unwrap();
+        rays.add_ray(s_ray);
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(0.2)).unwrap(),
+        );
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(transmitted)) = output.get("out1_trans1_refl2") else {
+            panic!("no rays at output port 1");
+        };
+        let Some(LightData::Geometric(reflected)) = output.get("out2_trans2_refl1") else {
+            panic!("no rays at output port 2");
+        };
+        // p ray + half of the unpolarized ray
+        assert_abs_diff_eq!(transmitted.total_energy().value, 1.1, epsilon = 1e-12);
+        // s ray + half of the unpolarized ray
+        assert_abs_diff_eq!(reflected.total_energy().value, 0.6, epsilon = 1e-12);
+    }
+    #[test]
     fn analyze_two_input() {
         let mut node = BeamSplitter::new("test", &SplittingConfig::Ratio(0.6)).unwrap();
         node.set_isometry(Isometry::identity()).unwrap();
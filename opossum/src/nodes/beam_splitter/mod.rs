@@ -1,6 +1,7 @@
 #![warn(missing_docs)]
 
 mod analysis_energy;
+mod analysis_gaussian_beam;
 mod analysis_ghostfocus;
 mod analysis_raytrace;
 
@@ -25,6 +26,9 @@ use std::sync::{Arc, Mutex};
 #[opm_node("lightpink")]
 /// An ideal beamsplitter node with a given splitting ratio.
 ///
+/// The splitting ratio may be fixed, wavelength-dependent or polarization-dependent (polarizing beam splitter). See
+/// [`SplittingConfig`] for details.
+///
 /// ## Optical Ports
 ///   - Inputs
 ///     - `input_1`
@@ -106,6 +110,17 @@ impl BeamSplitter {
             match in1 {
                 LightData::Energy(spectrum) => {
                     match self.splitting_config() {
+                        // spectra carry no polarization information -> scalar ratio behavior
+                        SplittingConfig::Polarizing { p_transmission, s_transmission } => {
+                            let r = f64::midpoint(p_transmission, s_transmission);
+                            let mut s = spectrum.clone();
+                            s.scale_vertical(&r)?;
+                            let out1_spectrum = Some(s);
+                            let mut s = spectrum.clone();
+                            s.scale_vertical(&(1.0 - r))?;
+                            let out2_spectrum = Some(s);
+                            Ok((out1_spectrum, out2_spectrum))
+                        },
                         SplittingConfig::Ratio(r) => {
                             let mut s = spectrum.clone();
                             s.scale_vertical(&r)?;
@@ -178,7 +193,9 @@ impl BeamSplitter {
         };
         let refraction_intended = true;
         let missed_surface_strategy = match analyzer_type {
-            AnalyzerType::Energy => &MissedSurfaceStrategy::Stop,
+            AnalyzerType::Energy
+            | AnalyzerType::SystemTransmission(_)
+            | AnalyzerType::GaussianBeam(_) => &MissedSurfaceStrategy::Stop,
             AnalyzerType::RayTrace(ray_trace_config) => ray_trace_config.missed_surface_strategy(),
             AnalyzerType::GhostFocus(_) => &MissedSurfaceStrategy::Ignore,
         };
@@ -186,6 +203,7 @@ impl BeamSplitter {
             match input_1 {
                 LightData::Geometric(r) => {
                     let mut rays = r.clone();
+                    let iso = self.effective_surface_iso(in1_port)?;
                     if let Some(surf) = self.get_optic_surface_mut(in1_port) {
                         rays.refract_on_surface(
                             surf,
@@ -193,17 +211,7 @@ impl BeamSplitter {
                             refraction_intended,
                             missed_surface_strategy,
                         )?;
-
-                        match self.ports().aperture(&PortType::Input, in1_port) {
-                            Some(aperture) => {
-                                rays.apodize(aperture, &self.effective_surface_iso(in1_port)?)?;
-                            }
-                            _ => {
-                                return Err(OpossumError::OpticPort(
-                                    "input aperture not found".into(),
-                                ));
-                            }
-                        }
+                        surf.apodize_rays(&mut rays, &iso)?;
                     } else {
                         return Err(OpossumError::OpticPort(
                             "input optic surface not found".into(),
@@ -226,6 +234,7 @@ impl BeamSplitter {
             match input_2 {
                 LightData::Geometric(r) => {
                     let mut rays = r.clone();
+                    let iso = self.effective_surface_iso(in2_port)?;
                     if let Some(surf) = self.get_optic_surface_mut(in2_port) {
                         rays.refract_on_surface(
                             surf,
@@ -233,16 +242,7 @@ impl BeamSplitter {
                             refraction_intended,
                             missed_surface_strategy,
                         )?;
-                        match self.ports().aperture(&PortType::Input, in2_port) {
-                            Some(aperture) => {
-                                rays.apodize(aperture, &self.effective_surface_iso(in2_port)?)?;
-                            }
-                            _ => {
-                                return Err(OpossumError::OpticPort(
-                                    "input aperture not found".into(),
-                                ));
-                            }
-                        }
+                        surf.apodize_rays(&mut rays, &iso)?;
                         let split_rays = rays.split(&splitting_config)?;
                         (rays, split_rays)
                     } else {
@@ -264,27 +264,19 @@ impl BeamSplitter {
         in_ray2.merge(&split1);
         let iso = self.effective_surface_iso(out1_port)?;
 
-        match self.ports().aperture(&PortType::Output, out1_port) {
-            Some(aperture) => {
-                in_ray1.apodize(aperture, &iso)?;
-                if let AnalyzerType::RayTrace(config) = analyzer_type {
-                    in_ray1.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                }
-            }
-            _ => {
-                return Err(OpossumError::OpticPort("ouput aperture not found".into()));
-            }
+        let Some(surf) = self.get_optic_surface_mut(out1_port) else {
+            return Err(OpossumError::OpticPort("ouput aperture not found".into()));
+        };
+        surf.apodize_rays(&mut in_ray1, &iso)?;
+        if let AnalyzerType::RayTrace(config) = analyzer_type {
+            in_ray1.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
         }
-        match self.ports().aperture(&PortType::Output, out2_port) {
-            Some(aperture) => {
-                in_ray2.apodize(aperture, &iso)?;
-                if let AnalyzerType::RayTrace(config) = analyzer_type {
-                    in_ray2.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                }
-            }
-            _ => {
-                return Err(OpossumError::OpticPort("ouput aperture not found".into()));
-            }
+        let Some(surf) = self.get_optic_surface_mut(out2_port) else {
+            return Err(OpossumError::OpticPort("ouput aperture not found".into()));
+        };
+        surf.apodize_rays(&mut in_ray2, &iso)?;
+        if let AnalyzerType::RayTrace(config) = analyzer_type {
+            in_ray2.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
         }
         Ok((
             Some(LightData::Geometric(in_ray1)),
@@ -347,6 +339,16 @@ mod test {
         assert_eq!(splitter.name(), "test");
         assert!(BeamSplitter::new("test", &SplittingConfig::Ratio(-0.01)).is_err());
         assert!(BeamSplitter::new("test", &SplittingConfig::Ratio(1.01)).is_err());
+        assert!(
+            BeamSplitter::new(
+                "test",
+                &SplittingConfig::Polarizing {
+                    p_transmission: 1.0,
+                    s_transmission: -0.1
+                }
+            )
+            .is_err()
+        );
     }
     #[test]
     fn inverted() {
@@ -0,0 +1,5 @@
+use super::CylindricLens;
+use crate::analyzers::gaussian_beam::AnalysisGaussianBeam;
+
+// a cylindric lens is not rotationally symmetric and thus cannot be described by a single ABCD matrix
+impl AnalysisGaussianBeam for CylindricLens {}
@@ -3,6 +3,7 @@
 
 use super::node_attr::NodeAttr;
 use crate::{
+    degree,
     error::{OpmResult, OpossumError},
     meter, millimeter,
     optic_node::OpticNode,
@@ -14,18 +15,22 @@ use crate::{
     utils::geom_transformation::Isometry,
 };
 use log::warn;
+use nalgebra::Point3;
 use num::Zero;
 use opm_macros_lib::OpmNode;
 use std::sync::{Arc, Mutex};
-use uom::si::f64::Length;
+use uom::si::f64::{Angle, Length};
 
 mod analysis_energy;
+mod analysis_gaussian_beam;
 mod analysis_ghostfocus;
 mod analysis_raytrace;
 
 #[derive(OpmNode, Debug, Clone)]
 #[opm_node("aqua")]
-/// A real cylindric lens with spherical (or flat) surfaces. By default, the curvature is aligned along the (local) y axis.
+/// A real cylindric lens with spherical (or flat) surfaces. By default, the cylinder axis (i.e. the direction without
+/// curvature) is aligned along the (local) y axis, so that the lens focuses in the x-z plane. The cylinder axis can be
+/// rotated around the optical axis using [`CylindricLens::with_cylinder_axis`].
 ///
 /// # Curvature convention:
 /// - negative curvature on the input will be a concave (defocusing) surface
@@ -45,6 +50,7 @@ mod analysis_raytrace;
 ///   - `rear curvature`
 ///   - `center thickness`
 ///   - `refractive index`
+///   - `cylinder axis`
 pub struct CylindricLens {
     node_attr: NodeAttr,
 }
@@ -82,6 +88,13 @@ impl Default for CylindricLens {
                 RefractiveIndexType::Const(RefrIndexConst::new(1.5).unwrap()).into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "cylinder axis",
+                "rotation angle of the cylinder axis around the optical axis with respect to the y axis",
+                degree!(0.0).into(),
+            )
+            .unwrap();
         let mut cyl_lens = Self { node_attr };
         cyl_lens.update_surfaces().unwrap();
         cyl_lens
@@ -140,11 +153,41 @@ impl CylindricLens {
         cyl_lens.update_surfaces()?;
         Ok(cyl_lens)
     }
+    /// Returns / modifies a [`CylindricLens`] with a given orientation of the cylinder axis.
+    ///
+    /// The angle denotes the rotation of the cylinder axis around the (local) optical axis with respect to the y axis.
+    /// An angle of 90° thus leads to a lens focusing in the y-z plane.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the angle is not finite or the node properties cannot be set.
+    pub fn with_cylinder_axis(mut self, angle: Angle) -> OpmResult<Self> {
+        if !angle.is_finite() {
+            return Err(OpossumError::Other(
+                "cylinder axis angle must be finite".into(),
+            ));
+        }
+        self.node_attr.set_property("cylinder axis", angle.into())?;
+        self.update_surfaces()?;
+        Ok(self)
+    }
+    /// Returns the rotation angle of the cylinder axis of this [`CylindricLens`] with respect to the y axis.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property `cylinder axis` cannot be read.
+    pub fn cylinder_axis(&self) -> OpmResult<Angle> {
+        let Ok(Proptype::Angle(angle)) = self.node_attr.get_property("cylinder axis") else {
+            return Err(OpossumError::Analysis("cannot read cylinder axis".into()));
+        };
+        Ok(*angle)
+    }
 }
 
 impl OpticNode for CylindricLens {
     fn update_surfaces(&mut self) -> OpmResult<()> {
         let node_iso = self.effective_node_iso().unwrap_or_else(Isometry::identity);
+        let axis_angle = self.cylinder_axis()?;
         let Ok(Proptype::Length(front_curvature)) = self.node_attr.get_property("front curvature")
         else {
             return Err(OpossumError::Analysis("cannot read front curvature".into()));
@@ -155,8 +198,10 @@ impl OpticNode for CylindricLens {
                 Isometry::identity(),
             )
         } else {
-            let anchor_point_iso_front =
-                Isometry::new(meter!(0., 0., front_curvature.value), radian!(0., 0., 0.))?;
+            let anchor_point_iso_front = Isometry::new(
+                meter!(0., 0., front_curvature.value),
+                Point3::new(Angle::zero(), Angle::zero(), axis_angle),
+            )?;
             (
                 GeoSurfaceRef(Arc::new(Mutex::new(Cylinder::new(
                     *front_curvature,
@@ -194,7 +239,7 @@ impl OpticNode for CylindricLens {
         } else {
             let anchor_point_iso_rear = Isometry::new(
                 meter!(0., 0., (*rear_curvature + *center_thickness).value),
-                radian!(0., 0., 0.),
+                Point3::new(Angle::zero(), Angle::zero(), axis_angle),
             )?;
             (
                 GeoSurfaceRef(Arc::new(Mutex::new(Cylinder::new(
@@ -241,7 +286,7 @@ mod test {
     use super::*;
     use crate::{
         analyzers::{RayTraceConfig, energy::AnalysisEnergy, raytrace::AnalysisRayTrace},
-        joule,
+        degree, joule,
         light_result::LightResult,
         lightdata::LightData,
         millimeter, nanometer,
@@ -277,6 +322,7 @@ mod test {
             panic!()
         };
         assert_eq!((*index).get_refractive_index(Length::zero()).unwrap(), 1.5);
+        assert_eq!(node.cylinder_axis().unwrap(), degree!(0.0));
     }
     #[test]
     fn new() {
@@ -335,6 +381,86 @@ mod test {
         );
     }
     #[test]
+    fn with_cylinder_axis() {
+        assert!(
+            CylindricLens::default()
+                .with_cylinder_axis(degree!(f64::NAN))
+                .is_err()
+        );
+        let node = CylindricLens::default()
+            .with_cylinder_axis(degree!(90.0))
+            .unwrap();
+        assert_eq!(node.cylinder_axis().unwrap(), degree!(90.0));
+    }
+    /// Propagate the output rays of a plano-convex cylindric lens to the plane, where the rays cross the lens' focusing
+    /// plane and return the extent of the spot (in x and y) in this plane.
+    fn line_focus_extent(axis: Angle) -> (f64, f64) {
+        let mut node = CylindricLens::new(
+            "test",
+            millimeter!(100.0),
+            millimeter!(f64::INFINITY),
+            millimeter!(5.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap()
+        .with_cylinder_axis(axis)
+        .unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(2.0), 3).unwrap(),
+        )
+        .unwrap();
+        let mut incoming_data = LightResult::default();
+        incoming_data.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, incoming_data, &RayTraceConfig::default())
+                .unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!()
+        };
+        // focal plane: mean axis crossing of all deflected rays
+        let crossings: Vec<f64> = rays
+            .iter()
+            .filter_map(|ray| {
+                let (pos, dir) = (ray.position(), ray.direction());
+                let radial = pos.x.value.mul_add(dir.x, pos.y.value * dir.y);
+                if radial == 0.0 {
+                    return None;
+                }
+                let radial_dir = dir.x.mul_add(dir.x, dir.y * dir.y);
+                Some((-radial / radial_dir).mul_add(dir.z, pos.z.value))
+            })
+            .collect();
+        #[allow(clippy::cast_precision_loss)]
+        let focus_z = crossings.iter().sum::<f64>() / crossings.len() as f64;
+        let (mut x_range, mut y_range) = (
+            (f64::INFINITY, f64::NEG_INFINITY),
+            (f64::INFINITY, f64::NEG_INFINITY),
+        );
+        for ray in rays {
+            let (pos, dir) = (ray.position(), ray.direction());
+            let t = (focus_z - pos.z.value) / dir.z;
+            let x = t.mul_add(dir.x, pos.x.value);
+            let y = t.mul_add(dir.y, pos.y.value);
+            x_range = (x_range.0.min(x), x_range.1.max(x));
+            y_range = (y_range.0.min(y), y_range.1.max(y));
+        }
+        (x_range.1 - x_range.0, y_range.1 - y_range.0)
+    }
+    #[test]
+    fn analyze_line_focus() {
+        // cylinder axis along y: focusing in x only
+        let (x_extent, y_extent) = line_focus_extent(degree!(0.0));
+        assert!(x_extent < 5.0e-5);
+        assert!(y_extent > 3.5e-3);
+        // cylinder axis along x: focusing in y only
+        let (x_extent, y_extent) = line_focus_extent(degree!(90.0));
+        assert!(x_extent > 3.5e-3);
+        assert!(y_extent < 5.0e-5);
+    }
+    #[test]
     fn inverted() {
         test_inverted::<CylindricLens>()
     }
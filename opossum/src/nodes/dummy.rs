@@ -1,11 +1,13 @@
 #![warn(missing_docs)]
+use nalgebra::Matrix2;
 use opm_macros_lib::OpmNode;
+use uom::si::f64::Length;
 
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
-        raytrace::AnalysisRayTrace,
+        RayTraceConfig, energy::AnalysisEnergy, gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
     },
     error::{OpmResult, OpossumError},
     light_result::LightResult,
@@ -70,6 +72,11 @@ impl AnalysisGhostFocus for Dummy {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for Dummy {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for Dummy {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -103,24 +110,16 @@ impl AnalysisRayTrace for Dummy {
                     refraction_intended,
                     config.missed_surface_strategy(),
                 )?;
-                match self.ports().aperture(&PortType::Input, in_port) {
-                    Some(aperture) => {
-                        rays.apodize(aperture, &iso)?;
-                        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("input aperture not found".into()));
-                    }
-                }
-                match self.ports().aperture(&PortType::Output, out_port) {
-                    Some(aperture) => {
-                        rays.apodize(aperture, &iso)?;
-                        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("output aperture not found".into()));
-                    }
-                }
+                let Some(surf) = self.get_optic_surface_mut(in_port) else {
+                    return Err(OpossumError::OpticPort("input aperture not found".into()));
+                };
+                surf.apodize_rays(&mut rays, &iso)?;
+                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+                let Some(surf) = self.get_optic_surface_mut(out_port) else {
+                    return Err(OpossumError::OpticPort("output aperture not found".into()));
+                };
+                surf.apodize_rays(&mut rays, &iso)?;
+                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
                 Ok(LightResult::from([(
                     out_port.into(),
                     LightData::Geometric(rays),
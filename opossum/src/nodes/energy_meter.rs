@@ -1,28 +1,33 @@
 #![warn(missing_docs)]
 use crate::{
     analyzers::{
-        RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
-        raytrace::AnalysisRayTrace,
+        RayTraceConfig, energy::AnalysisEnergy, gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
     },
-    error::OpmResult,
+    error::{OpmResult, OpossumError},
     joule,
     light_result::{LightRays, LightResult},
     lightdata::LightData,
     optic_node::OpticNode,
     optic_ports::PortType,
     properties::{Properties, Proptype},
+    rays::Rays,
     reporting::node_report::NodeReport,
+    spectrum::Spectrum,
 };
 use log::warn;
+use nalgebra::Matrix2;
+use num::Zero;
 use opm_macros_lib::OpmNode;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
-use uom::si::f64::Energy;
+use uom::si::f64::{Energy, Length};
 
 use super::node_attr::NodeAttr;
 
 #[non_exhaustive]
 #[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Type of the [`EnergyMeter`]. This is currently not used.
 pub enum Metertype {
     /// an ideal energy meter
@@ -58,6 +63,10 @@ impl From<Metertype> for Proptype {
 ///   - `name`
 ///   - `inverted`
 ///   - `meter type`
+///   - `spectral resolution`
+///
+/// If a spectral resolution is set, the meter additionally records the spectral breakdown of the detected energy. In
+/// this case, the report contains the energy spectrum, which is also exported as CSV file (energy per wavelength slot).
 ///
 /// During analysis, the output port contains a replica of the input port similar to a [`Dummy`](crate::nodes::Dummy) node. This way,
 /// different dectector nodes can be "stacked" or used somewhere in between arbitrary optic nodes.
@@ -80,6 +89,13 @@ impl Default for EnergyMeter {
                 Metertype::default().into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "spectral resolution",
+                "wavelength resolution of the recorded energy spectrum (if any)",
+                Proptype::LengthOption(None),
+            )
+            .unwrap();
         let mut em = Self {
             light_data: None,
             node_attr,
@@ -128,6 +144,65 @@ impl EnergyMeter {
             .set_property("meter type", meter_type.into())
             .unwrap();
     }
+    /// Returns the spectral resolution of this [`EnergyMeter`].
+    ///
+    /// `None` means that no energy spectrum is recorded.
+    /// # Panics
+    /// This function panics if
+    /// - the property "spectral resolution" does not exist.
+    /// - the data format is wrong.
+    #[must_use]
+    pub fn spectral_resolution(&self) -> Option<Length> {
+        if let Ok(Proptype::LengthOption(resolution)) =
+            self.node_attr.get_property("spectral resolution")
+        {
+            *resolution
+        } else {
+            panic!("wrong data format")
+        }
+    }
+    /// Sets the spectral resolution of this [`EnergyMeter`].
+    ///
+    /// If set, the meter records the spectral breakdown of the detected energy in addition to the total energy.
+    /// # Errors
+    /// This function returns an error if the given resolution is not positive and finite.
+    pub fn set_spectral_resolution(&mut self, resolution: Option<Length>) -> OpmResult<()> {
+        if let Some(resolution) = resolution
+            && (!resolution.is_finite() || resolution <= Length::zero())
+        {
+            return Err(OpossumError::Properties(
+                "spectral resolution must be positive and finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("spectral resolution", Proptype::LengthOption(resolution))
+    }
+    /// Returns the energy spectrum of the light detected by this [`EnergyMeter`].
+    ///
+    /// This function returns `None` if no spectral resolution is set, no light data has been recorded or the
+    /// light data does not contain any valid rays.
+    #[must_use]
+    pub fn spectrum(&self) -> Option<Spectrum> {
+        let resolution = self.spectral_resolution()?;
+        match self.light_data.as_ref()? {
+            LightData::Energy(s) => {
+                let range = s.range();
+                let mut binned =
+                    Spectrum::new(range.start..range.end + resolution, resolution).ok()?;
+                binned.resample(s);
+                Some(binned)
+            }
+            LightData::Geometric(r) => r.to_spectrum(&resolution).ok(),
+            LightData::Fourier => None,
+            LightData::GhostFocus(r) => {
+                let mut all_rays = Rays::default();
+                for rays in r {
+                    all_rays.merge(rays);
+                }
+                all_rays.to_spectrum(&resolution).ok()
+            }
+        }
+    }
 }
 impl OpticNode for EnergyMeter {
     fn update_surfaces(&mut self) -> OpmResult<()> {
@@ -157,6 +232,15 @@ impl OpticNode for EnergyMeter {
                 .create("Energy", "Output energy", "no data".into())
                 .unwrap();
         }
+        if let Some(spectrum) = self.spectrum() {
+            props
+                .create(
+                    "Spectrum",
+                    "energy per wavelength slot",
+                    Proptype::EnergySpectrum(spectrum),
+                )
+                .unwrap();
+        }
         props
             .create(
                 "Model",
@@ -215,6 +299,11 @@ impl AnalysisGhostFocus for EnergyMeter {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for EnergyMeter {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for EnergyMeter {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -246,9 +335,10 @@ impl AnalysisRayTrace for EnergyMeter {
 mod test {
     use super::*;
     use crate::{
-        nodes::test_helper::test_helper::*, optic_ports::PortType,
+        millimeter, nanometer, nodes::test_helper::test_helper::*, optic_ports::PortType, ray::Ray,
         spectrum_helper::create_he_ne_spec,
     };
+    use approx::assert_relative_eq;
     #[test]
     fn default() {
         let mut node = EnergyMeter::default();
@@ -371,4 +461,77 @@ mod test {
             panic!("could not read Energy property");
         }
     }
+    #[test]
+    fn set_spectral_resolution() {
+        let mut meter = EnergyMeter::default();
+        assert!(meter.spectral_resolution().is_none());
+        assert!(
+            meter
+                .set_spectral_resolution(Some(nanometer!(0.0)))
+                .is_err()
+        );
+        assert!(
+            meter
+                .set_spectral_resolution(Some(nanometer!(-1.0)))
+                .is_err()
+        );
+        assert!(
+            meter
+                .set_spectral_resolution(Some(nanometer!(f64::INFINITY)))
+                .is_err()
+        );
+        meter
+            .set_spectral_resolution(Some(nanometer!(1.0)))
+            .unwrap();
+        assert_eq!(meter.spectral_resolution(), Some(nanometer!(1.0)));
+        meter.set_spectral_resolution(None).unwrap();
+        assert!(meter.spectral_resolution().is_none());
+    }
+    #[test]
+    fn spectrum() {
+        let mut meter = EnergyMeter::default();
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1000.0), joule!(1.0)).unwrap(),
+        );
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1064.0), joule!(0.5)).unwrap(),
+        );
+        meter.set_light_data(LightData::Geometric(rays));
+        assert!(meter.spectrum().is_none());
+        let report = meter.node_report("123").unwrap();
+        assert!(!report.properties().contains("Spectrum"));
+        meter
+            .set_spectral_resolution(Some(nanometer!(1.0)))
+            .unwrap();
+        let spectrum = meter.spectrum().unwrap();
+        assert_relative_eq!(spectrum.total_energy(), 1.5, max_relative = 1e-9);
+        let energies = spectrum.slot_energies();
+        let pump: f64 = energies
+            .iter()
+            .filter(|(w, _)| *w < nanometer!(1032.0))
+            .map(|(_, e)| e.get::<uom::si::energy::joule>())
+            .sum();
+        assert_relative_eq!(pump, 1.0, max_relative = 1e-9);
+        let report = meter.node_report("123").unwrap();
+        assert!(matches!(
+            report.properties().get("Spectrum"),
+            Ok(Proptype::EnergySpectrum(_))
+        ));
+        if let Ok(Proptype::Energy(e)) = report.properties().get("Energy") {
+            assert_eq!(e, &joule!(1.5));
+        } else {
+            panic!("could not read Energy property");
+        }
+    }
+    #[test]
+    fn spectrum_energy_data() {
+        let mut meter = EnergyMeter::default();
+        meter
+            .set_spectral_resolution(Some(nanometer!(1.0)))
+            .unwrap();
+        meter.set_light_data(LightData::Energy(create_he_ne_spec(1.0).unwrap()));
+        let spectrum = meter.spectrum().unwrap();
+        assert_relative_eq!(spectrum.total_energy(), 1.0, max_relative = 1e-3);
+    }
 }
@@ -1,17 +1,17 @@
 //! Data structure for holding a fluence map.
-use std::ops::Range;
+use std::{ops::Range, path::Path};
 
 use super::Fluence;
 use crate::{
     J_per_cm2,
-    error::OpmResult,
-    joule,
+    error::{OpmResult, OpossumError},
+    joule, meter,
     plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
     properties::Proptype,
     surface::hit_map::fluence_estimator::FluenceEstimator,
-    utils::{griddata::linspace, usize_to_f64},
+    utils::{f64_to_usize, griddata::linspace, usize_to_f64},
 };
-use nalgebra::{DMatrix, DVector};
+use nalgebra::{DMatrix, DVector, Point2};
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
 use uom::si::{
@@ -102,6 +102,38 @@ impl FluenceData {
             self.interp_distribution.clone(),
         )
     }
+    /// Write the fluence distribution of this [`FluenceData`] to a CSV file.
+    ///
+    /// The file contains the fluence grid in J/cm². The first line holds the x coordinates (in mm) of the grid
+    /// columns, preceded by an empty cell. Each following line represents one grid row and starts with its y
+    /// coordinate (in mm) followed by the fluence values of this row. Undefined grid points are written as `NaN`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written.
+    pub fn to_csv(&self, path: &Path) -> OpmResult<()> {
+        let (x_axis, y_axis, distribution) = self.get_fluence_distribution();
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        let header = std::iter::once(String::new())
+            .chain(x_axis.iter().map(|x| x.get::<millimeter>().to_string()));
+        writer
+            .write_record(header)
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        for (y, row) in y_axis.iter().zip(distribution.row_iter()) {
+            let record = std::iter::once(y.get::<millimeter>().to_string()).chain(
+                row.iter()
+                    .map(|fluence| fluence.get::<joule_per_square_centimeter>().to_string()),
+            );
+            writer
+                .write_record(record)
+                .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
     /// Returns length of the x data points (columns)
     #[must_use]
     pub fn len_x(&self) -> usize {
@@ -124,6 +156,49 @@ impl FluenceData {
     pub fn peak(&self) -> Fluence {
         self.peak
     }
+    /// Returns the peak fluence of this [`FluenceData`] together with its position (x, y).
+    ///
+    /// The position refers to the grid point coordinates as returned by [`FluenceData::get_fluence_distribution`].
+    /// Non-finite fluence values are ignored. If the distribution does not contain any finite value, the returned
+    /// position is `NaN`.
+    #[must_use]
+    pub fn peak_fluence(&self) -> (Fluence, Point2<Length>) {
+        let nrows = self.len_y();
+        let peak = self
+            .interp_distribution
+            .iter()
+            .enumerate()
+            .filter(|(_, fluence)| fluence.is_finite())
+            .fold(
+                None,
+                |max: Option<(usize, Fluence)>, (idx, fluence)| match max {
+                    Some((_, max_fluence)) if max_fluence >= *fluence => max,
+                    _ => Some((idx, *fluence)),
+                },
+            );
+        // coordinate of a grid point along an axis
+        let coordinate = |idx: usize, len: usize, range: &Range<Length>| {
+            if len < 2 {
+                range.start
+            } else {
+                range.start + (range.end - range.start) * usize_to_f64(idx) / usize_to_f64(len - 1)
+            }
+        };
+        peak.map_or_else(
+            || (self.peak, Point2::new(meter!(f64::NAN), meter!(f64::NAN))),
+            |(idx, fluence)| {
+                // the matrix is stored in column-major order
+                let (row, col) = (idx % nrows, idx / nrows);
+                (
+                    fluence,
+                    Point2::new(
+                        coordinate(col, self.len_x(), &self.x_range),
+                        coordinate(row, nrows, &self.y_range),
+                    ),
+                )
+            },
+        )
+    }
     /// Returns the total energy of this [`FluenceData`].
     #[must_use]
     pub fn total_energy(&self) -> Energy {
@@ -138,6 +213,146 @@ impl FluenceData {
         }
         energy
     }
+    /// Resample this [`FluenceData`] onto a new grid with the given number of `(columns, rows)`.
+    ///
+    /// The new grid covers the same x and y range as the original one. The fluence values are bilinearly interpolated
+    /// and finally scaled such that the total energy (see [`FluenceData::total_energy`]) is conserved.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the number of columns or rows is zero or the original distribution is empty.
+    pub fn resample(&self, shape: (usize, usize)) -> OpmResult<Self> {
+        let (cols, rows) = shape;
+        if cols == 0 || rows == 0 {
+            return Err(OpossumError::Other(
+                "number of columns and rows must be > 0".into(),
+            ));
+        }
+        let (old_rows, old_cols) = self.shape();
+        if old_rows == 0 || old_cols == 0 {
+            return Err(OpossumError::Other(
+                "cannot resample an empty fluence distribution".into(),
+            ));
+        }
+        // position of a new grid point in (fractional) pixel coordinates of the old grid
+        let old_coordinate = |idx: usize, new_len: usize, old_len: usize| {
+            if new_len < 2 {
+                0.0
+            } else {
+                usize_to_f64(idx) * usize_to_f64(old_len - 1) / usize_to_f64(new_len - 1)
+            }
+        };
+        let mut distribution = DMatrix::<Fluence>::from_element(rows, cols, J_per_cm2!(0.0));
+        for row in 0..rows {
+            let y = old_coordinate(row, rows, old_rows);
+            let y0 = f64_to_usize(y.floor()).min(old_rows - 1);
+            let y1 = (y0 + 1).min(old_rows - 1);
+            let fy = y - usize_to_f64(y0);
+            for col in 0..cols {
+                let x = old_coordinate(col, cols, old_cols);
+                let x0 = f64_to_usize(x.floor()).min(old_cols - 1);
+                let x1 = (x0 + 1).min(old_cols - 1);
+                let fx = x - usize_to_f64(x0);
+                let d = &self.interp_distribution;
+                distribution[(row, col)] = d[(y0, x0)] * ((1.0 - fx) * (1.0 - fy))
+                    + d[(y0, x1)] * (fx * (1.0 - fy))
+                    + d[(y1, x0)] * ((1.0 - fx) * fy)
+                    + d[(y1, x1)] * (fx * fy);
+            }
+        }
+        let mut resampled = Self::new(
+            distribution,
+            self.x_range.clone(),
+            self.y_range.clone(),
+            self.estimator.clone(),
+        );
+        let scaling = (self.total_energy() / resampled.total_energy()).value;
+        if scaling.is_finite() {
+            resampled
+                .interp_distribution
+                .apply(|fluence| *fluence *= scaling);
+            resampled.peak *= scaling;
+        }
+        Ok(resampled)
+    }
+    /// Returns the fluence-weighted centroid (x, y) of this [`FluenceData`].
+    ///
+    /// Non-finite fluence values are ignored. This function returns `None` if the distribution contains no energy.
+    #[must_use]
+    pub fn centroid(&self) -> Option<Point2<Length>> {
+        let (x, y, distribution) = self.get_fluence_distribution();
+        let mut sum = J_per_cm2!(0.0);
+        let mut x_sum = J_per_cm2!(0.0) * meter!(0.0);
+        let mut y_sum = J_per_cm2!(0.0) * meter!(0.0);
+        for (col, column) in distribution.column_iter().enumerate() {
+            for (row, fluence) in column.iter().enumerate() {
+                if fluence.is_finite() {
+                    sum += *fluence;
+                    x_sum += *fluence * x[col];
+                    y_sum += *fluence * y[row];
+                }
+            }
+        }
+        if sum.value.is_normal() {
+            Some(Point2::new(x_sum / sum, y_sum / sum))
+        } else {
+            None
+        }
+    }
+    /// Returns the energy-weighted centroid of a stack of [`FluenceData`] (e.g. the fluence maps of individual wavelengths).
+    ///
+    /// The centroid of each map (see [`FluenceData::centroid`]) is weighted by its total energy. Maps without energy are ignored.
+    /// This function returns `None` if the stack contains no energy at all.
+    #[must_use]
+    pub fn weighted_centroid(stack: &[Self]) -> Option<Point2<Length>> {
+        let mut energy_sum = joule!(0.0);
+        let mut x_sum = joule!(0.0) * meter!(0.0);
+        let mut y_sum = joule!(0.0) * meter!(0.0);
+        for fluence_data in stack {
+            if let Some(centroid) = fluence_data.centroid() {
+                let energy = fluence_data.total_energy();
+                energy_sum += energy;
+                x_sum += energy * centroid.x;
+                y_sum += energy * centroid.y;
+            }
+        }
+        if energy_sum.value.is_normal() {
+            Some(Point2::new(x_sum / energy_sum, y_sum / energy_sum))
+        } else {
+            None
+        }
+    }
+}
+impl std::ops::Sub for &FluenceData {
+    type Output = OpmResult<FluenceData>;
+    /// Subtract two fluence maps (e.g. in order to isolate the contribution of a ghost focus).
+    ///
+    /// Both maps must be defined on the same grid (i.e. identical shape and axes ranges). Negative values are kept. The
+    /// [`FluenceEstimator`] of the result is taken from the left-hand side.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the grids of both maps do not match.
+    fn sub(self, rhs: Self) -> OpmResult<FluenceData> {
+        if self.shape() != rhs.shape() || self.x_range != rhs.x_range || self.y_range != rhs.y_range
+        {
+            return Err(OpossumError::Other(
+                "fluence maps must be defined on the same grid".into(),
+            ));
+        }
+        Ok(FluenceData::new(
+            &self.interp_distribution - &rhs.interp_distribution,
+            self.x_range.clone(),
+            self.y_range.clone(),
+            self.estimator.clone(),
+        ))
+    }
+}
+impl std::ops::Sub for FluenceData {
+    type Output = OpmResult<Self>;
+    fn sub(self, rhs: Self) -> OpmResult<Self> {
+        &self - &rhs
+    }
 }
 impl Plottable for FluenceData {
     fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
@@ -163,7 +378,7 @@ impl Plottable for FluenceData {
         let (nrows, ncols) = self.interp_distribution.shape();
 
         match plt_type {
-            PlotType::ColorMesh(_) => {
+            PlotType::ColorMesh(_) | PlotType::Contour(_) => {
                 let plt_data = PlotData::ColorMesh {
                     x_dat_n: linspace(
                         self.x_range.start.get::<millimeter>(),
@@ -197,13 +412,15 @@ impl Plottable for FluenceData {
 mod test {
     use super::FluenceData;
     use crate::{
-        J_per_cm2, J_per_m2, joule, meter,
-        plottable::{PlotType, Plottable},
+        J_per_cm2, J_per_m2, joule, meter, millimeter,
+        plottable::{PlotData, PlotParameters, PlotType, Plottable},
         properties::Proptype,
         surface::hit_map::fluence_estimator::FluenceEstimator,
     };
+    use approx::assert_relative_eq;
     use assert_matches::assert_matches;
-    use nalgebra::{dmatrix, vector};
+    use nalgebra::{DMatrix, Point2, dmatrix, vector};
+    use uom::si::length::millimeter;
     #[test]
     fn into_proptype() {
         let fluence_data = FluenceData::new(
@@ -287,6 +504,74 @@ mod test {
         assert_eq!(fluence_data.peak(), J_per_cm2!(4.0));
     }
     #[test]
+    fn peak_fluence() {
+        let fluence_data = FluenceData::new(
+            dmatrix![
+                J_per_cm2!(1.0), J_per_cm2!(f64::INFINITY), J_per_cm2!(2.0);
+                J_per_cm2!(3.0), J_per_cm2!(5.0), J_per_cm2!(4.0)],
+            meter!(0.0)..meter!(2.0),
+            meter!(-1.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        let (peak, position) = fluence_data.peak_fluence();
+        assert_eq!(peak, J_per_cm2!(5.0));
+        assert_eq!(position, Point2::new(meter!(1.0), meter!(1.0)));
+        let fluence_data = FluenceData::new(
+            dmatrix![J_per_cm2!(f64::NAN)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        let (_, position) = fluence_data.peak_fluence();
+        assert!(position.x.is_nan());
+        assert!(position.y.is_nan());
+    }
+    #[test]
+    fn sub() {
+        let fluence_data1 = FluenceData::new(
+            dmatrix![
+                J_per_cm2!(1.0), J_per_cm2!(2.0);
+                J_per_cm2!(3.0), J_per_cm2!(4.0)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        let fluence_data2 = FluenceData::new(
+            dmatrix![
+                J_per_cm2!(2.0), J_per_cm2!(1.0);
+                J_per_cm2!(1.0), J_per_cm2!(1.0)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Voronoi,
+        );
+        let difference = (&fluence_data1 - &fluence_data2).unwrap();
+        assert_eq!(
+            difference.interp_distribution(),
+            &dmatrix![
+                J_per_cm2!(-1.0), J_per_cm2!(1.0);
+                J_per_cm2!(2.0), J_per_cm2!(3.0)]
+        );
+        assert_eq!(difference.peak(), J_per_cm2!(3.0));
+        assert_eq!(difference.estimator(), &FluenceEstimator::Binning);
+        assert!((fluence_data1.clone() - fluence_data2).is_ok());
+        let other_range = FluenceData::new(
+            dmatrix![
+                J_per_cm2!(1.0), J_per_cm2!(2.0);
+                J_per_cm2!(3.0), J_per_cm2!(4.0)],
+            meter!(0.0)..meter!(2.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        assert!((&fluence_data1 - &other_range).is_err());
+        let other_shape = FluenceData::new(
+            dmatrix![J_per_cm2!(1.0), J_per_cm2!(2.0)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        assert!((&fluence_data1 - &other_shape).is_err());
+    }
+    #[test]
     fn total_energy() {
         let fluence_data = FluenceData::new(
             dmatrix![
@@ -299,6 +584,88 @@ mod test {
         assert_eq!(fluence_data.total_energy(), joule!(5.0));
     }
     #[test]
+    fn resample_wrong_shape() {
+        let fluence_data = FluenceData::new(
+            dmatrix![
+                J_per_m2!(4.0), J_per_m2!(8.0);
+                J_per_m2!(8.0), J_per_m2!(4.0)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        assert!(fluence_data.resample((0, 2)).is_err());
+        assert!(fluence_data.resample((2, 0)).is_err());
+    }
+    #[test]
+    fn resample() {
+        let distribution = DMatrix::from_fn(21, 31, |r, c| {
+            let x = (c as f64 - 15.0) / 8.0;
+            let y = (r as f64 - 10.0) / 8.0;
+            J_per_cm2!((-(x * x + y * y)).exp())
+        });
+        let fluence_data = FluenceData::new(
+            distribution,
+            meter!(-0.01)..meter!(0.01),
+            meter!(-0.01)..meter!(0.01),
+            FluenceEstimator::Binning,
+        );
+        let down = fluence_data.resample((16, 11)).unwrap();
+        assert_eq!(down.shape(), (11, 16));
+        assert_relative_eq!(
+            down.total_energy().value,
+            fluence_data.total_energy().value,
+            max_relative = 1e-12
+        );
+        let up = down.resample((31, 21)).unwrap();
+        assert_eq!(up.shape(), (21, 31));
+        assert_relative_eq!(
+            up.total_energy().value,
+            fluence_data.total_energy().value,
+            max_relative = 1e-12
+        );
+        let peak = fluence_data.peak().value;
+        for (orig, resampled) in fluence_data
+            .interp_distribution()
+            .iter()
+            .zip(up.interp_distribution().iter())
+        {
+            assert!((orig.value - resampled.value).abs() < 0.05 * peak);
+        }
+    }
+    fn gaussian_spot(center: (f64, f64), peak: f64) -> FluenceData {
+        let distribution = DMatrix::from_fn(41, 41, |r, c| {
+            let x = (c as f64 - 20.0 - center.0) / 3.0;
+            let y = (r as f64 - 20.0 - center.1) / 3.0;
+            J_per_cm2!(peak * (-(x * x + y * y)).exp())
+        });
+        FluenceData::new(
+            distribution,
+            millimeter!(-20.0)..millimeter!(20.0),
+            millimeter!(-20.0)..millimeter!(20.0),
+            FluenceEstimator::Binning,
+        )
+    }
+    #[test]
+    fn centroid() {
+        let spot = gaussian_spot((5.0, -3.0), 1.0);
+        let centroid = spot.centroid().unwrap();
+        assert_relative_eq!(centroid.x.get::<millimeter>(), 5.0, epsilon = 1e-6);
+        assert_relative_eq!(centroid.y.get::<millimeter>(), -3.0, epsilon = 1e-6);
+        let empty = gaussian_spot((0.0, 0.0), 0.0);
+        assert!(empty.centroid().is_none());
+    }
+    #[test]
+    fn weighted_centroid() {
+        assert!(FluenceData::weighted_centroid(&[]).is_none());
+        let red = gaussian_spot((-5.0, 0.0), 1.0);
+        let green = gaussian_spot((5.0, 0.0), 3.0);
+        let empty = gaussian_spot((10.0, 10.0), 0.0);
+        let centroid = FluenceData::weighted_centroid(&[red, green, empty]).unwrap();
+        // spots carry energies 1:3 -> centroid at 3/4 of the distance from red to green
+        assert_relative_eq!(centroid.x.get::<millimeter>(), 2.5, epsilon = 1e-6);
+        assert_relative_eq!(centroid.y.get::<millimeter>(), 0.0, epsilon = 1e-6);
+    }
+    #[test]
     fn get_plot_type() {
         let fluence_data = FluenceData::new(
             dmatrix![
@@ -313,4 +680,47 @@ mod test {
             PlotType::ColorMesh(_)
         );
     }
+    #[test]
+    fn get_plot_series_contour() {
+        let fluence_data = FluenceData::new(
+            dmatrix![
+                J_per_m2!(4.0), J_per_m2!(8.0);
+                J_per_m2!(8.0), J_per_m2!(4.0)],
+            meter!(0.0)..meter!(1.0),
+            meter!(0.0)..meter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        let mut plt_type = PlotType::Contour(PlotParameters::default());
+        let plt_series = fluence_data
+            .get_plot_series(&mut plt_type, false)
+            .unwrap()
+            .unwrap();
+        assert_matches!(
+            plt_series[0].get_plot_series_data(),
+            PlotData::ColorMesh { .. }
+        );
+    }
+    #[test]
+    fn to_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("fluence.csv");
+        let fluence_data = FluenceData::new(
+            dmatrix![
+                J_per_cm2!(1.0), J_per_cm2!(2.0), J_per_cm2!(3.0);
+                J_per_cm2!(4.0), J_per_cm2!(5.0), J_per_cm2!(f64::NAN)],
+            millimeter!(0.0)..millimeter!(2.0),
+            millimeter!(-1.0)..millimeter!(1.0),
+            FluenceEstimator::Binning,
+        );
+        fluence_data.to_csv(&path).unwrap();
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(&path)
+            .unwrap();
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], vec!["", "0", "1", "2"]);
+        assert_eq!(records[1], vec!["-1", "1", "2", "3"]);
+        assert_eq!(records[2], vec!["1", "4", "5", "NaN"]);
+    }
 }
@@ -5,21 +5,26 @@ pub mod fluence_data;
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
         raytrace::AnalysisRayTrace,
     },
     error::OpmResult,
+    kde::KdeKernel,
     light_result::{LightRays, LightResult},
     lightdata::LightData,
     optic_node::OpticNode,
     optic_ports::PortType,
+    optic_scenery_rsc::DEFAULT_FLUENCE_GRID,
     properties::{Properties, Proptype},
     rays::Rays,
     reporting::node_report::NodeReport,
     surface::hit_map::fluence_estimator::FluenceEstimator,
 };
 use log::warn;
+use nalgebra::Matrix2;
 use opm_macros_lib::OpmNode;
+use uom::si::f64::Length;
 
 /// alias for uom `RadiantExposure`, as this name is rather uncommon to use for laser scientists
 pub type Fluence = uom::si::f64::RadiantExposure;
@@ -28,7 +33,8 @@ pub type Fluence = uom::si::f64::RadiantExposure;
 ///
 /// It simply calculates the fluence (spatial energy distribution) of an incoming [`Ray`](crate::ray::Ray) bundle. The used algorithm
 /// for calculating a fluence map is specified with the property `fluence estimator`. By default, the Voronoi estimator is
-/// used ([`FluenceEstimator::Voronoi`]). See [`FluenceEstimator`] for further options.
+/// used ([`FluenceEstimator::Voronoi`]). See [`FluenceEstimator`] for further options. For the kernel density estimator
+/// ([`FluenceEstimator::KDE`]), the shape of the kernel can be selected with the property `kde kernel` (see [`KdeKernel`]).
 ///
 /// ## Optical Ports
 ///   - Inputs
@@ -39,6 +45,7 @@ pub type Fluence = uom::si::f64::RadiantExposure;
 /// ## Properties
 ///   - `name`
 ///   - `fluence estimator`
+///   - `kde kernel`
 ///
 /// During analysis, the output port contains a replica of the input port similar to a [`Dummy`](crate::nodes::Dummy) node. This way,
 /// different dectector nodes can be "stacked" or used somewhere within the optical setup.
@@ -61,6 +68,13 @@ impl Default for FluenceDetector {
                 FluenceEstimator::Voronoi.into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "kde kernel",
+                "kernel shape of the kernel density estimator",
+                KdeKernel::default().into(),
+            )
+            .unwrap();
         let mut fld = Self {
             node_attr,
             apodization_warning: false,
@@ -80,6 +94,67 @@ impl FluenceDetector {
         fld.node_attr.set_name(name);
         fld
     }
+    /// Returns the [`FluenceEstimator`] of this [`FluenceDetector`].
+    /// # Panics
+    /// This function panics if
+    /// - the property `fluence estimator` does not exist.
+    /// - the data format is wrong.
+    #[must_use]
+    pub fn estimator(&self) -> FluenceEstimator {
+        if let Ok(Proptype::FluenceEstimator(estimator)) =
+            self.node_attr.get_property("fluence estimator")
+        {
+            estimator.clone()
+        } else {
+            panic!("wrong data format")
+        }
+    }
+    /// Sets the [`FluenceEstimator`] of this [`FluenceDetector`].
+    ///
+    /// # Errors
+    /// This function returns an error if
+    /// - the property `fluence estimator` can not be set.
+    pub fn set_estimator(&mut self, estimator: FluenceEstimator) -> OpmResult<()> {
+        self.node_attr
+            .set_property("fluence estimator", estimator.into())?;
+        Ok(())
+    }
+    /// Returns the [`KdeKernel`] of this [`FluenceDetector`] used by the [`FluenceEstimator::KDE`] estimator.
+    /// # Panics
+    /// This function panics if
+    /// - the property `kde kernel` does not exist.
+    /// - the data format is wrong.
+    #[must_use]
+    pub fn kde_kernel(&self) -> KdeKernel {
+        if let Ok(Proptype::KdeKernel(kernel)) = self.node_attr.get_property("kde kernel") {
+            *kernel
+        } else {
+            panic!("wrong data format")
+        }
+    }
+    /// Sets the [`KdeKernel`] of this [`FluenceDetector`] used by the [`FluenceEstimator::KDE`] estimator.
+    ///
+    /// # Errors
+    /// This function returns an error if
+    /// - the property `kde kernel` can not be set.
+    pub fn set_kde_kernel(&mut self, kernel: KdeKernel) -> OpmResult<()> {
+        self.node_attr.set_property("kde kernel", kernel.into())?;
+        Ok(())
+    }
+    /// Returns the resolution (number of grid points in x and y) of the fluence map of this [`FluenceDetector`].
+    ///
+    /// The resolution is taken from the global configuration. If not set there, [`DEFAULT_FLUENCE_GRID`] is used.
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    #[must_use]
+    pub fn fluence_grid(&self) -> (usize, usize) {
+        self.global_conf()
+            .as_ref()
+            .and_then(|conf| conf.lock().expect("Mutex lock failed").fluence_grid)
+            .unwrap_or(DEFAULT_FLUENCE_GRID)
+    }
 }
 impl OpticNode for FluenceDetector {
     fn set_apodization_warning(&mut self, apodized: bool) {
@@ -100,7 +175,31 @@ impl OpticNode for FluenceDetector {
         else {
             return None;
         };
-        if let Ok(fluence_data) = hit_map.calc_fluence_map((100, 83), estimator) {
+        let incompatible_estimator = hit_map
+            .get_first_hitpoints()
+            .is_some_and(|hit_points| !estimator.is_compatible(hit_points));
+        if incompatible_estimator {
+            warn!(
+                "fluence estimator {estimator} of node '{}' does not match the recorded hit points",
+                self.name()
+            );
+        }
+        if let Ok(fluence_data) =
+            hit_map.calc_fluence_map(self.fluence_grid(), estimator, self.kde_kernel())
+        {
+            if incompatible_estimator {
+                props
+                    .create(
+                        "Estimator warning",
+                        "warning during fluence estimation",
+                        format!(
+                            "Fluence estimator {estimator} not compatible with the recorded hit points. Using {} instead.",
+                            fluence_data.estimator()
+                        )
+                        .into(),
+                    )
+                    .unwrap();
+            }
             props
                 .create(
                     &format!("Fluence ({})", fluence_data.estimator()),
@@ -122,6 +221,22 @@ impl OpticNode for FluenceDetector {
                     Proptype::Energy(fluence_data.total_energy()),
                 )
                 .unwrap();
+            if let Some(centroid) = fluence_data.centroid() {
+                props
+                    .create(
+                        "Centroid x",
+                        "x position of the fluence-weighted centroid",
+                        Proptype::Length(centroid.x),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "Centroid y",
+                        "y position of the fluence-weighted centroid",
+                        Proptype::Length(centroid.y),
+                    )
+                    .unwrap();
+            }
             if self.apodization_warning {
                 props
                     .create(
@@ -190,6 +305,11 @@ impl AnalysisGhostFocus for FluenceDetector {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for FluenceDetector {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for FluenceDetector {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -221,7 +341,16 @@ mod test {
     use super::*;
     use crate::lightdata::LightData;
     use crate::optic_ports::PortType;
+    use crate::{
+        SceneryResources,
+        aperture::{Aperture, CircleConfig},
+        joule, millimeter, nanometer,
+        optic_ref::OpticRef,
+        position_distributions::Grid,
+        utils::geom_transformation::Isometry,
+    };
     use crate::{nodes::test_helper::test_helper::*, spectrum_helper::create_he_ne_spec};
+    use std::sync::{Arc, Mutex};
     #[test]
     fn default() {
         let mut node = FluenceDetector::default();
@@ -237,6 +366,38 @@ mod test {
         assert_eq!(meter.name(), "test");
     }
     #[test]
+    fn estimator() {
+        let mut node = FluenceDetector::default();
+        assert_eq!(node.estimator(), FluenceEstimator::Voronoi);
+        node.set_estimator(FluenceEstimator::KDE).unwrap();
+        assert_eq!(node.estimator(), FluenceEstimator::KDE);
+        // the estimator is stored in the OPM file
+        let optic_ref = OpticRef::new(Arc::new(Mutex::new(node)), None);
+        let serialized = ron::to_string(&optic_ref).unwrap();
+        let optic_ref: OpticRef = ron::from_str(&serialized).unwrap();
+        let node = optic_ref.optical_ref.lock().unwrap();
+        assert!(matches!(
+            node.node_attr().get_property("fluence estimator"),
+            Ok(Proptype::FluenceEstimator(FluenceEstimator::KDE))
+        ));
+    }
+    #[test]
+    fn kde_kernel() {
+        let mut node = FluenceDetector::default();
+        assert_eq!(node.kde_kernel(), KdeKernel::Gaussian);
+        node.set_kde_kernel(KdeKernel::Epanechnikov).unwrap();
+        assert_eq!(node.kde_kernel(), KdeKernel::Epanechnikov);
+        // the kernel is stored in the OPM file
+        let optic_ref = OpticRef::new(Arc::new(Mutex::new(node)), None);
+        let serialized = ron::to_string(&optic_ref).unwrap();
+        let optic_ref: OpticRef = ron::from_str(&serialized).unwrap();
+        let node = optic_ref.optical_ref.lock().unwrap();
+        assert!(matches!(
+            node.node_attr().get_property("kde kernel"),
+            Ok(Proptype::KdeKernel(KdeKernel::Epanechnikov))
+        ));
+    }
+    #[test]
     fn ports() {
         let meter = FluenceDetector::default();
         assert_eq!(meter.ports().names(&PortType::Input), vec!["input_1"]);
@@ -300,4 +461,94 @@ mod test {
         let output = output.clone().unwrap();
         assert_eq!(*output, input_light);
     }
+    #[test]
+    fn report_with_dropped_rays() {
+        let mut node = FluenceDetector::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let config = CircleConfig::new(millimeter!(3.0), millimeter!(0.0, 0.0)).unwrap();
+        node.set_aperture(&PortType::Input, "input_1", &Aperture::BinaryCircle(config))
+            .unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Grid::new((millimeter!(9.0), millimeter!(9.0)), (20, 20)).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let report = node.node_report("").unwrap();
+        let Ok(Proptype::FluenceData(fluence_data)) = report.properties().get("Fluence (Voronoi)")
+        else {
+            panic!("no fluence data found")
+        };
+        assert_eq!(fluence_data.interp_distribution().shape(), (83, 100));
+        assert!(fluence_data.peak().is_finite());
+    }
+    #[test]
+    fn report_with_incompatible_estimator() {
+        let mut node = FluenceDetector::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        node.set_estimator(FluenceEstimator::HelperRays).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Grid::new((millimeter!(9.0), millimeter!(9.0)), (20, 20)).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let report = node.node_report("").unwrap();
+        assert!(report.properties().get("Fluence (Voronoi)").is_ok());
+        assert!(report.properties().get("Estimator warning").is_ok());
+    }
+    #[test]
+    fn report_with_fluence_grid() {
+        let mut node = FluenceDetector::default();
+        assert_eq!(node.fluence_grid(), DEFAULT_FLUENCE_GRID);
+        node.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources {
+            fluence_grid: Some((40, 30)),
+            ..Default::default()
+        }))));
+        assert_eq!(node.fluence_grid(), (40, 30));
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Grid::new((millimeter!(9.0), millimeter!(9.0)), (20, 20)).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let report = node.node_report("").unwrap();
+        let Ok(Proptype::FluenceData(fluence_data)) = report.properties().get("Fluence (Voronoi)")
+        else {
+            panic!("no fluence data found")
+        };
+        assert_eq!(fluence_data.interp_distribution().shape(), (30, 40));
+    }
+    #[test]
+    fn report_with_kde_kernel() {
+        let mut node = FluenceDetector::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        node.set_estimator(FluenceEstimator::KDE).unwrap();
+        node.set_kde_kernel(KdeKernel::Epanechnikov).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Grid::new((millimeter!(9.0), millimeter!(9.0)), (20, 20)).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let report = node.node_report("").unwrap();
+        let Ok(Proptype::FluenceData(fluence_data)) = report.properties().get("Fluence (KDE)")
+        else {
+            panic!("no fluence data found")
+        };
+        assert!(fluence_data.peak().is_finite());
+    }
 }
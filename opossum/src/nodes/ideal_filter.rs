@@ -2,28 +2,144 @@
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
         raytrace::AnalysisRayTrace,
     },
     error::{OpmResult, OpossumError},
     light_result::{LightRays, LightResult},
     lightdata::LightData,
+    micrometer,
     optic_node::OpticNode,
     optic_ports::PortType,
     properties::Proptype,
     rays::Rays,
     spectrum::Spectrum,
 };
+use nalgebra::Matrix2;
 use opm_macros_lib::OpmNode;
 use serde::{Deserialize, Serialize};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use uom::si::f64::Length;
 
 /// Config data for an [`IdealFilter`].
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum FilterType {
     /// a fixed (wavelength-independant) transmission value. Must be between 0.0 and 1.0
     Constant(f64),
     /// filter based on given transmission spectrum.
     Spectrum(Spectrum),
+    /// filter based on a tabulated transmission curve (e.g. loaded from a CSV file).
+    TransmissionCurve(TransmissionCurve),
+}
+/// A tabulated transmission curve T(λ) of a filter.
+///
+/// The transmission between the data points is linearly interpolated. Outside the range of the curve, the
+/// transmission of the first or last data point, respectively, is used (clamping). The data points are stored together
+/// with the (optional) path of the file they were loaded from. Hence, a model using this curve does not depend on the
+/// file being present.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct TransmissionCurve {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuf>,
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<(f64, f64)>"))]
+    data: Vec<(Length, f64)>,
+}
+impl TransmissionCurve {
+    /// Creates a new [`TransmissionCurve`] from a list of (wavelength, transmission) data points.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the list is empty.
+    ///   - the wavelengths are not positive, finite and strictly ascending.
+    ///   - a transmission value is outside the interval [0.0; 1.0].
+    pub fn new(data: Vec<(Length, f64)>) -> OpmResult<Self> {
+        if data.is_empty() {
+            return Err(OpossumError::Other(
+                "transmission curve must contain at least one data point".into(),
+            ));
+        }
+        if data
+            .iter()
+            .any(|(wavelength, _)| !wavelength.is_finite() || wavelength.value <= 0.0)
+        {
+            return Err(OpossumError::Other(
+                "wavelengths of transmission curve must be positive and finite".into(),
+            ));
+        }
+        if data.windows(2).any(|w| w[1].0 <= w[0].0) {
+            return Err(OpossumError::Other(
+                "wavelengths of transmission curve must be strictly ascending".into(),
+            ));
+        }
+        if data
+            .iter()
+            .any(|(_, transmission)| !(0.0..=1.0).contains(transmission))
+        {
+            return Err(OpossumError::Other(
+                "transmission values must be in interval [0.0; 1.0]".into(),
+            ));
+        }
+        Ok(Self { file: None, data })
+    }
+    /// Loads a [`TransmissionCurve`] from a CSV file.
+    ///
+    /// The file format is identical to [`Spectrum::from_csv`]: Each line contains the wavelength in nanometers and the
+    /// transmission in percent separated by a semicolon. The file path is stored together with the data.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be read or parsed or if the data is invalid (see
+    /// [`TransmissionCurve::new`]).
+    pub fn from_csv(path: &Path) -> OpmResult<Self> {
+        let spectrum = Spectrum::from_csv(path)?;
+        let data = spectrum
+            .iter()
+            .map(|(wavelength, transmission)| (micrometer!(*wavelength), *transmission))
+            .collect();
+        let mut curve = Self::new(data)?;
+        curve.file = Some(path.to_path_buf());
+        Ok(curve)
+    }
+    /// Returns the path of the file this [`TransmissionCurve`] was loaded from (if any).
+    #[must_use]
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+    /// Returns the wavelength range of this [`TransmissionCurve`].
+    #[must_use]
+    pub fn range(&self) -> Range<Length> {
+        self.data[0].0..self.data[self.data.len() - 1].0
+    }
+    /// Returns `true` if the given wavelength is within the (closed) range of this [`TransmissionCurve`].
+    #[must_use]
+    pub fn contains(&self, wavelength: Length) -> bool {
+        let range = self.range();
+        range.start <= wavelength && wavelength <= range.end
+    }
+    /// Returns the transmission at the given wavelength.
+    ///
+    /// The value is linearly interpolated between the data points. Outside the range of the curve, the transmission of
+    /// the nearest data point is returned.
+    #[must_use]
+    pub fn transmission(&self, wavelength: Length) -> f64 {
+        let idx = self.data.partition_point(|(w, _)| *w < wavelength);
+        if idx == 0 {
+            return self.data[0].1;
+        }
+        if idx == self.data.len() {
+            return self.data[idx - 1].1;
+        }
+        let (left, right) = (self.data[idx - 1], self.data[idx]);
+        let ratio = ((wavelength - left.0) / (right.0 - left.0)).value;
+        left.1.mul_add(1.0 - ratio, right.1 * ratio)
+    }
 }
 impl From<FilterType> for Proptype {
     fn from(f: FilterType) -> Self {
@@ -34,6 +150,9 @@ impl From<FilterType> for Proptype {
 #[opm_node("darkgray")]
 /// An ideal filter with given transmission or optical density.
 ///
+/// The transmission can be constant, given by a transmission spectrum or by a tabulated [`TransmissionCurve`] (e.g. loaded from
+/// a CSV file of a real interference or colored-glass filter).
+///
 /// ## Optical Ports
 ///   - Inputs
 ///     - `front`
@@ -142,7 +261,7 @@ impl IdealFilter {
     pub fn optical_density(&self) -> Option<f64> {
         match self.filter_type() {
             FilterType::Constant(t) => Some(-1.0 * f64::log10(t)),
-            FilterType::Spectrum(_) => None,
+            FilterType::Spectrum(_) | FilterType::TransmissionCurve(_) => None,
         }
     }
 }
@@ -183,6 +302,11 @@ impl AnalysisGhostFocus for IdealFilter {
         }
     }
 }
+impl AnalysisGaussianBeam for IdealFilter {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for IdealFilter {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -229,24 +353,16 @@ impl AnalysisRayTrace for IdealFilter {
             config.missed_surface_strategy(),
         )?;
         rays.filter_energy(&self.filter_type())?;
-        match self.ports().aperture(&PortType::Input, in_port) {
-            Some(aperture) => {
-                rays.apodize(aperture, &iso)?;
-                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-            }
-            _ => {
-                return Err(OpossumError::OpticPort("input aperture not found".into()));
-            }
-        }
-        match self.ports().aperture(&PortType::Output, out_port) {
-            Some(aperture) => {
-                rays.apodize(aperture, &iso)?;
-                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-            }
-            _ => {
-                return Err(OpossumError::OpticPort("output aperture not found".into()));
-            }
-        }
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let Some(surf) = self.get_optic_surface_mut(out_port) else {
+            return Err(OpossumError::OpticPort("output aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
         let light_data = LightData::Geometric(rays);
         Ok(LightResult::from([(out_port.into(), light_data)]))
     }
@@ -412,4 +528,96 @@ mod test {
         let expected_output_light = LightData::Energy(create_he_ne_spec(0.5).unwrap());
         assert_eq!(*output, expected_output_light);
     }
+    #[test]
+    fn transmission_curve_new() {
+        assert!(TransmissionCurve::new(vec![]).is_err());
+        assert!(TransmissionCurve::new(vec![(nanometer!(-500.0), 0.5)]).is_err());
+        assert!(TransmissionCurve::new(vec![(nanometer!(f64::NAN), 0.5)]).is_err());
+        assert!(TransmissionCurve::new(vec![(nanometer!(500.0), 1.1)]).is_err());
+        assert!(
+            TransmissionCurve::new(vec![(nanometer!(600.0), 0.5), (nanometer!(500.0), 0.5)])
+                .is_err()
+        );
+        assert!(
+            TransmissionCurve::new(vec![(nanometer!(500.0), 0.5), (nanometer!(500.0), 0.5)])
+                .is_err()
+        );
+        let curve = TransmissionCurve::new(vec![(nanometer!(500.0), 0.5)]).unwrap();
+        assert!(curve.file().is_none());
+        assert_eq!(curve.transmission(nanometer!(400.0)), 0.5);
+        assert_eq!(curve.transmission(nanometer!(600.0)), 0.5);
+    }
+    #[test]
+    fn transmission_curve_from_csv() {
+        assert!(TransmissionCurve::from_csv(Path::new("./invalid_file.csv")).is_err());
+        let path = Path::new("./files_for_testing/spectrum/test_filter.csv");
+        let curve = TransmissionCurve::from_csv(path).unwrap();
+        assert_eq!(curve.file(), Some(path));
+        assert_eq!(curve.range(), micrometer!(0.5)..micrometer!(0.505));
+        assert!(curve.contains(micrometer!(0.5)));
+        assert!(curve.contains(micrometer!(0.505)));
+        assert!(!curve.contains(nanometer!(499.0)));
+    }
+    #[test]
+    fn transmission_curve_transmission() {
+        let curve = TransmissionCurve::new(vec![
+            (nanometer!(500.0), 0.2),
+            (nanometer!(600.0), 0.8),
+            (nanometer!(700.0), 0.4),
+        ])
+        .unwrap();
+        assert_abs_diff_eq!(curve.transmission(nanometer!(500.0)), 0.2, epsilon = 1e-12);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(550.0)), 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(600.0)), 0.8, epsilon = 1e-12);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(650.0)), 0.6, epsilon = 1e-12);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(700.0)), 0.4, epsilon = 1e-12);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(400.0)), 0.2);
+        assert_abs_diff_eq!(curve.transmission(nanometer!(800.0)), 0.4);
+    }
+    #[test]
+    fn transmission_curve_serde() {
+        let curve =
+            TransmissionCurve::from_csv(Path::new("./files_for_testing/spectrum/test_filter.csv"))
+                .unwrap();
+        let filter_type = FilterType::TransmissionCurve(curve);
+        let serialized = ron::ser::to_string(&filter_type).unwrap();
+        let deserialized: FilterType = ron::from_str(&serialized).unwrap();
+        assert_eq!(filter_type, deserialized);
+    }
+    #[test]
+    fn analyze_transmission_curve() {
+        let curve =
+            TransmissionCurve::new(vec![(nanometer!(500.0), 0.2), (nanometer!(1500.0), 0.8)])
+                .unwrap();
+        let mut node = IdealFilter::new("test", &FilterType::TransmissionCurve(curve)).unwrap();
+        assert_eq!(node.optical_density(), None);
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut input = LightResult::default();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap();
+        input.insert("input_1".into(), LightData::Geometric(rays.clone()));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(output)) = output.get("output_1") else {
+            panic!("wrong data LightData format")
+        };
+        assert_abs_diff_eq!(
+            output.total_energy().get::<joule>(),
+            0.5 * rays.total_energy().get::<joule>(),
+            epsilon = 1e-12
+        );
+        let mut input = LightResult::default();
+        let spectrum = create_he_ne_spec(1.0).unwrap();
+        input.insert("input_1".into(), LightData::Energy(spectrum.clone()));
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        let Some(LightData::Energy(output)) = output.get("output_1") else {
+            panic!("wrong data LightData format")
+        };
+        // the He-Ne line (632.8 nm) is attenuated according to the curve
+        assert!(output.total_energy() < spectrum.total_energy());
+    }
 }
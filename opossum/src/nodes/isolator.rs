@@ -0,0 +1,399 @@
+#![warn(missing_docs)]
+//! Ideal Faraday isolator
+use super::node_attr::NodeAttr;
+use crate::{
+    analyzers::{
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
+        raytrace::AnalysisRayTrace,
+    },
+    error::{OpmResult, OpossumError},
+    light_result::{LightRays, LightResult},
+    lightdata::LightData,
+    nodes::FilterType,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    rays::Rays,
+    utils::geom_transformation::Isometry,
+};
+use nalgebra::{Matrix2, Vector3};
+use opm_macros_lib::OpmNode;
+use uom::si::f64::Length;
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("darkorange")]
+/// An ideal Faraday isolator.
+///
+/// The isolator transmits light propagating in forward direction (along the local z axis of the node) attenuated by
+/// its insertion loss. Light propagating in backward direction is attenuated by the isolation. Both values are given in
+/// dB. The direction is determined individually for each ray from its propagation direction. Hence, back-reflected rays
+/// during a ghost focus analysis are suppressed accordingly. The polarization state of the rays is not modified.
+///
+/// For an inverted isolator, forward and backward directions are swapped. During an energy analysis, which does not
+/// contain direction information, the light is assumed to propagate in forward direction unless the node is inverted.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `isolation`
+///   - `insertion loss`
+pub struct FaradayIsolator {
+    node_attr: NodeAttr,
+}
+unsafe impl Send for FaradayIsolator {}
+
+impl Default for FaradayIsolator {
+    /// Create a Faraday isolator with an isolation of 30 dB and no insertion loss.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("faraday isolator");
+        node_attr
+            .create_property(
+                "isolation",
+                "attenuation of backward propagating light in dB",
+                30.0.into(),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "insertion loss",
+                "attenuation of forward propagating light in dB",
+                0.0.into(),
+            )
+            .unwrap();
+        let mut isolator = Self { node_attr };
+        isolator.update_surfaces().unwrap();
+        isolator
+    }
+}
+impl FaradayIsolator {
+    /// Creates a new [`FaradayIsolator`] with the given isolation and insertion loss (both in dB).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if one of the given values is negative or not finite.
+    pub fn new(name: &str, isolation: f64, insertion_loss: f64) -> OpmResult<Self> {
+        let mut isolator = Self::default();
+        isolator.set_isolation(isolation)?;
+        isolator.set_insertion_loss(insertion_loss)?;
+        isolator.node_attr.set_name(name);
+        Ok(isolator)
+    }
+    /// Returns the isolation (attenuation of backward propagating light) of this [`FaradayIsolator`] in dB.
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the isolation property.
+    #[must_use]
+    pub fn isolation(&self) -> f64 {
+        if let Ok(Proptype::F64(isolation)) = self.node_attr.get_property("isolation") {
+            *isolation
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the isolation (attenuation of backward propagating light) of this [`FaradayIsolator`] in dB.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given value is negative or not finite.
+    pub fn set_isolation(&mut self, isolation: f64) -> OpmResult<()> {
+        if !isolation.is_finite() || isolation.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "isolation must be positive and finite".into(),
+            ));
+        }
+        self.node_attr.set_property("isolation", isolation.into())
+    }
+    /// Returns the insertion loss (attenuation of forward propagating light) of this [`FaradayIsolator`] in dB.
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the insertion-loss property.
+    #[must_use]
+    pub fn insertion_loss(&self) -> f64 {
+        if let Ok(Proptype::F64(insertion_loss)) = self.node_attr.get_property("insertion loss") {
+            *insertion_loss
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the insertion loss (attenuation of forward propagating light) of this [`FaradayIsolator`] in dB.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given value is negative or not finite.
+    pub fn set_insertion_loss(&mut self, insertion_loss: f64) -> OpmResult<()> {
+        if !insertion_loss.is_finite() || insertion_loss.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "insertion loss must be positive and finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("insertion loss", insertion_loss.into())
+    }
+    /// Returns the energy transmission for forward propagating light.
+    #[must_use]
+    pub fn forward_transmission(&self) -> f64 {
+        db_to_transmission(self.insertion_loss())
+    }
+    /// Returns the energy transmission for backward propagating light.
+    #[must_use]
+    pub fn backward_transmission(&self) -> f64 {
+        db_to_transmission(self.isolation())
+    }
+    /// Returns the forward direction of this [`FaradayIsolator`] in global coordinates.
+    fn forward_direction(&self, iso: &Isometry) -> Vector3<f64> {
+        let local_z = if self.inverted() {
+            -Vector3::z()
+        } else {
+            Vector3::z()
+        };
+        iso.transform_vector_f64(&local_z)
+    }
+    /// Attenuates all valid rays of the given bundle depending on their propagation direction.
+    fn attenuate(&self, rays: &mut Rays, iso: &Isometry) -> OpmResult<()> {
+        let forward = self.forward_direction(iso);
+        let forward_filter = FilterType::Constant(self.forward_transmission());
+        let backward_filter = FilterType::Constant(self.backward_transmission());
+        for ray in rays.iter_mut().filter(|r| r.valid()) {
+            if ray.direction().dot(&forward) >= 0.0 {
+                ray.filter_energy(&forward_filter)?;
+            } else {
+                ray.filter_energy(&backward_filter)?;
+            }
+        }
+        Ok(())
+    }
+}
+/// Converts an attenuation given in dB into an energy transmission factor.
+fn db_to_transmission(attenuation: f64) -> f64 {
+    f64::powf(10.0, -attenuation / 10.0)
+}
+
+impl OpticNode for FaradayIsolator {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        self.update_flat_single_surfaces()
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+    fn set_apodization_warning(&mut self, _apodized: bool) {}
+    fn reset_data(&mut self) {
+        self.reset_optic_surfaces();
+    }
+}
+impl AnalysisGhostFocus for FaradayIsolator {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let iso = self.effective_surface_iso(in_port)?;
+        let mut output =
+            AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)?;
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        if let Some(rays_bundles) = output.get_mut(out_port) {
+            for rays in rays_bundles {
+                self.attenuate(rays, &iso)?;
+            }
+            Ok(output)
+        } else {
+            Err(OpossumError::Analysis("attenuation of rays failed".into()))
+        }
+    }
+}
+impl AnalysisGaussianBeam for FaradayIsolator {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
+impl AnalysisEnergy for FaradayIsolator {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        if let LightData::Energy(s) = input {
+            let transmission = if self.inverted() {
+                self.backward_transmission()
+            } else {
+                self.forward_transmission()
+            };
+            let mut new_spectrum = s.clone();
+            new_spectrum.filter_with_type(&FilterType::Constant(transmission))?;
+            let light_data = LightData::Energy(new_spectrum);
+            Ok(LightResult::from([(out_port.into(), light_data)]))
+        } else {
+            Err(OpossumError::Analysis("expected energy light data".into()))
+        }
+    }
+}
+impl AnalysisRayTrace for FaradayIsolator {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        let LightData::Geometric(r) = input else {
+            return Err(OpossumError::Analysis(
+                "expected geometric light data".into(),
+            ));
+        };
+        let mut rays = r.clone();
+        let iso = self.effective_surface_iso(in_port)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis("no surface found. Aborting".into()));
+        };
+        let refraction_intended = true;
+        rays.refract_on_surface(
+            surf,
+            None,
+            refraction_intended,
+            config.missed_surface_strategy(),
+        )?;
+        self.attenuate(&mut rays, &iso)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let Some(surf) = self.get_optic_surface_mut(out_port) else {
+            return Err(OpossumError::OpticPort("output aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let light_data = LightData::Geometric(rays);
+        Ok(LightResult::from([(out_port.into(), light_data)]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, millimeter, nanometer, nodes::test_helper::test_helper::*, optic_ports::PortType,
+        ray::Ray, spectrum_helper::create_he_ne_spec,
+    };
+    use approx::assert_abs_diff_eq;
+    use nalgebra::vector;
+    use uom::si::energy::joule;
+    #[test]
+    fn default() {
+        let mut node = FaradayIsolator::default();
+        assert_eq!(node.isolation(), 30.0);
+        assert_eq!(node.insertion_loss(), 0.0);
+        assert_eq!(node.name(), "faraday isolator");
+        assert_eq!(node.node_type(), "faraday isolator");
+        assert!(!node.inverted());
+        assert_eq!(node.node_color(), "darkorange");
+        assert!(node.as_group_mut().is_err());
+    }
+    #[test]
+    fn new() {
+        assert!(FaradayIsolator::new("test", -1.0, 0.0).is_err());
+        assert!(FaradayIsolator::new("test", f64::NAN, 0.0).is_err());
+        assert!(FaradayIsolator::new("test", 30.0, -0.1).is_err());
+        assert!(FaradayIsolator::new("test", 30.0, f64::INFINITY).is_err());
+        let node = FaradayIsolator::new("test", 40.0, 0.5).unwrap();
+        assert_eq!(node.name(), "test");
+        assert_eq!(node.isolation(), 40.0);
+        assert_eq!(node.insertion_loss(), 0.5);
+    }
+    #[test]
+    fn transmissions() {
+        let node = FaradayIsolator::new("test", 30.0, 3.0).unwrap();
+        assert_abs_diff_eq!(node.backward_transmission(), 1e-3);
+        assert_abs_diff_eq!(node.forward_transmission(), 0.501_187, epsilon = 1e-6);
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<FaradayIsolator>()
+    }
+    #[test]
+    fn ports() {
+        let node = FaradayIsolator::default();
+        assert_eq!(node.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(node.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<FaradayIsolator>()
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<FaradayIsolator>("input_1");
+    }
+    #[test]
+    fn analyze_energy() {
+        let mut node = FaradayIsolator::new("test", 20.0, 10.0).unwrap();
+        let mut input = LightResult::default();
+        input.insert(
+            "input_1".into(),
+            LightData::Energy(create_he_ne_spec(1.0).unwrap()),
+        );
+        let output = AnalysisEnergy::analyze(&mut node, input.clone()).unwrap();
+        let Some(LightData::Energy(s)) = output.get("output_1") else {
+            panic!("no energy light data found")
+        };
+        assert_abs_diff_eq!(s.total_energy(), 0.1, epsilon = 1e-12);
+        node.set_inverted(true).unwrap();
+        let mut input = LightResult::default();
+        input.insert(
+            "output_1".into(),
+            LightData::Energy(create_he_ne_spec(1.0).unwrap()),
+        );
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        let Some(LightData::Energy(s)) = output.get("input_1") else {
+            panic!("no energy light data found")
+        };
+        assert_abs_diff_eq!(s.total_energy(), 0.01, epsilon = 1e-12);
+    }
+    #[test]
+    fn analyze_geometric_directional() {
+        let mut node = FaradayIsolator::new("test", 30.0, 1.0).unwrap();
+        node.set_isometry(
+            Isometry::new(millimeter!(0.0, 0.0, 10.0), crate::degree!(0.0, 0.0, 0.0)).unwrap(),
+        )
+        .unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap(),
+        );
+        rays.add_ray(
+            Ray::new(
+                millimeter!(0., 0., 20.),
+                vector![0.0, 0.0, -1.0],
+                nanometer!(1054.0),
+                joule!(1.0),
+            )
+            .unwrap(),
+        );
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        let energies: Vec<f64> = rays.iter().map(|r| r.energy().get::<joule>()).collect();
+        assert_abs_diff_eq!(energies[0], 0.794_328, epsilon = 1e-6);
+        assert_abs_diff_eq!(energies[1], 1e-3, epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,50 @@
+use super::Lens;
+use crate::{
+    analyzers::{gaussian_beam::AnalysisGaussianBeam, raytrace::AnalysisRayTrace},
+    error::OpmResult,
+};
+use nalgebra::Matrix2;
+use uom::si::{f64::Length, length::meter};
+
+/// ABCD matrix of a refraction at a spherical surface (`None` = flat) from medium `n1` into medium `n2`.
+fn refraction_matrix(radius: Option<Length>, n1: f64, n2: f64) -> Matrix2<f64> {
+    let power = radius.map_or(0.0, |r| (n1 - n2) / (n2 * r.get::<meter>()));
+    Matrix2::new(1.0, 0.0, power, n1 / n2)
+}
+
+impl AnalysisGaussianBeam for Lens {
+    fn abcd_matrix(&self, wavelength: Length, n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        let (refri, center_thickness, _) = self.get_node_attributes_ray_trace(&self.node_attr)?;
+        let n_lens = refri.get_refractive_index(wavelength)?;
+        let front = refraction_matrix(self.front_curvature()?, n_ambient, n_lens);
+        let translation = Matrix2::new(1.0, center_thickness.get::<meter>(), 0.0, 1.0);
+        let rear = refraction_matrix(self.rear_curvature()?, n_lens, n_ambient);
+        Ok(Some(rear * translation * front))
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{millimeter, nodes::Lens, refractive_index::RefrIndexConst};
+    use approx::assert_relative_eq;
+    use uom::si::f64::Length;
+
+    #[test]
+    fn abcd_matrix() {
+        let lens = Lens::new(
+            "test",
+            millimeter!(100.0),
+            Length::new::<meter>(f64::INFINITY),
+            millimeter!(5.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        let m = lens.abcd_matrix(millimeter!(0.001), 1.0).unwrap().unwrap();
+        // plano-convex lens: f = R / (n - 1)
+        assert_relative_eq!(m[(1, 0)], -5.0);
+        assert_relative_eq!(m[(0, 0)], 1.0 - 0.005 / 1.5 * 5.0);
+        assert_relative_eq!(m[(0, 1)], 0.005 / 1.5);
+        assert_relative_eq!(m[(1, 1)], 1.0);
+        assert_relative_eq!(m.determinant(), 1.0, epsilon = 1e-12);
+    }
+}
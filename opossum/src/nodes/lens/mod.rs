@@ -1,8 +1,9 @@
 #![warn(missing_docs)]
-//! Lens with spherical or flat surfaces
+//! Lens with spherical, aspheric or flat surfaces
 
 use super::node_attr::NodeAttr;
 use crate::{
+    aperture::Aperture,
     error::{OpmResult, OpossumError},
     meter, millimeter,
     optic_node::OpticNode,
@@ -10,7 +11,7 @@ use crate::{
     properties::Proptype,
     radian,
     refractive_index::{RefrIndexConst, RefractiveIndex, RefractiveIndexType},
-    surface::{Plane, Sphere, geo_surface::GeoSurfaceRef},
+    surface::{Asphere, AsphericProfile, Plane, Sphere, geo_surface::GeoSurfaceRef},
     utils::geom_transformation::Isometry,
 };
 use log::warn;
@@ -20,6 +21,7 @@ use std::sync::{Arc, Mutex};
 use uom::si::f64::Length;
 
 mod analysis_energy;
+mod analysis_gaussian_beam;
 mod analysis_ghostfocus;
 mod analysis_raytrace;
 
@@ -27,6 +29,10 @@ mod analysis_raytrace;
 #[opm_node("aqua")]
 /// A real lens with spherical (or flat) surfaces.
 ///
+/// Each surface can optionally be turned into an even asphere by adding an [`AsphericProfile`] (see
+/// [`Lens::with_front_asphere`] and [`Lens::with_rear_asphere`]). In this case, the curvature of the surface denotes
+/// the radius of curvature of the base sphere of the asphere.
+///
 /// # Curvature convention:
 /// - negative curvature on the input will be a concave (defocusing) surface
 /// - positive curvature on the input will be a convex (focusing) surface
@@ -45,6 +51,8 @@ mod analysis_raytrace;
 ///   - `rear curvature`
 ///   - `center thickness`
 ///   - `refractive index`
+///   - `front aspheric profile`
+///   - `rear aspheric profile`
 pub struct Lens {
     node_attr: NodeAttr,
 }
@@ -81,6 +89,20 @@ impl Default for Lens {
                 RefractiveIndexType::Const(RefrIndexConst::new(1.5).unwrap()).into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "front aspheric profile",
+                "aspheric profile of the front surface (if any)",
+                Proptype::AsphericProfile(None),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "rear aspheric profile",
+                "aspheric profile of the rear surface (if any)",
+                Proptype::AsphericProfile(None),
+            )
+            .unwrap();
         let mut lens = Self { node_attr };
         lens.update_surfaces().unwrap();
         lens
@@ -91,11 +113,13 @@ impl Lens {
     ///
     /// This function creates a lens with spherical front and back surfaces, a given center thickness and refractive index.
     /// The radii of curvature must not be zero. The given refractive index must not be < 1.0. A radius of curvature of +/- infinity
-    /// corresponds to a flat surface.
+    /// corresponds to a flat surface. Both radii may have any sign (see the curvature convention of [`Lens`]), so that also negative
+    /// lenses as well as (thick) meniscus lenses can be modelled.
     ///
     /// # Errors
     ///
-    /// This function returns an error if the given parameters are not correct.
+    /// This function returns an error if the given parameters are not correct. This is also the case, if the combination of radii
+    /// and center thickness would lead to a negative lens thickness near the optical axis.
     pub fn new(
         name: &str,
         front_curvature: Length,
@@ -127,13 +151,151 @@ impl Lens {
         }
         lens.node_attr
             .set_property("center thickness", center_thickness.into())?;
+        Self::check_curvature_combination(
+            Self::surface_radius(front_curvature),
+            Self::surface_radius(rear_curvature),
+            center_thickness,
+        )?;
 
         lens.node_attr
             .set_property("refractive index", refractive_index.to_enum().into())?;
         lens.update_surfaces()?;
         Ok(lens)
     }
-
+    /// Modifies the clear aperture of a [`Lens`].
+    ///
+    /// The given [`Aperture`] (e.g. [`Aperture::BinaryRectangle`] or [`Aperture::BinaryEllipse`]) is applied to the front
+    /// and the rear surface of the lens. Rays outside the clear aperture are vignetted during analysis. This function can be
+    /// used with the "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ports of the lens cannot be found.
+    pub fn with_clear_aperture(mut self, aperture: &Aperture) -> OpmResult<Self> {
+        self.set_aperture(&PortType::Input, "input_1", aperture)?;
+        self.set_aperture(&PortType::Output, "output_1", aperture)?;
+        Ok(self)
+    }
+    /// Turns the front surface of this [`Lens`] into an even asphere with the given [`AsphericProfile`].
+    ///
+    /// The front curvature is used as radius of curvature of the base sphere. This function can be used with the
+    /// "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the surfaces of the lens cannot be updated.
+    pub fn with_front_asphere(mut self, profile: AsphericProfile) -> OpmResult<Self> {
+        self.node_attr
+            .set_property("front aspheric profile", Some(profile).into())?;
+        self.update_surfaces()?;
+        Ok(self)
+    }
+    /// Turns the rear surface of this [`Lens`] into an even asphere with the given [`AsphericProfile`].
+    ///
+    /// The rear curvature is used as radius of curvature of the base sphere. This function can be used with the
+    /// "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the surfaces of the lens cannot be updated.
+    pub fn with_rear_asphere(mut self, profile: AsphericProfile) -> OpmResult<Self> {
+        self.node_attr
+            .set_property("rear aspheric profile", Some(profile).into())?;
+        self.update_surfaces()?;
+        Ok(self)
+    }
+    /// Returns the aspheric profile of the front surface of this [`Lens`] (if any).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property `front aspheric profile` cannot be read.
+    pub fn front_aspheric_profile(&self) -> OpmResult<Option<AsphericProfile>> {
+        let Ok(Proptype::AsphericProfile(profile)) =
+            self.node_attr.get_property("front aspheric profile")
+        else {
+            return Err(OpossumError::Analysis(
+                "cannot read front aspheric profile".into(),
+            ));
+        };
+        Ok(profile.clone())
+    }
+    /// Returns the aspheric profile of the rear surface of this [`Lens`] (if any).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property `rear aspheric profile` cannot be read.
+    pub fn rear_aspheric_profile(&self) -> OpmResult<Option<AsphericProfile>> {
+        let Ok(Proptype::AsphericProfile(profile)) =
+            self.node_attr.get_property("rear aspheric profile")
+        else {
+            return Err(OpossumError::Analysis(
+                "cannot read rear aspheric profile".into(),
+            ));
+        };
+        Ok(profile.clone())
+    }
+    /// Returns the radius of curvature of the front surface of this [`Lens`].
+    ///
+    /// A flat surface is returned as `None`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property `front curvature` cannot be read.
+    pub fn front_curvature(&self) -> OpmResult<Option<Length>> {
+        let Ok(Proptype::Length(front_curvature)) = self.node_attr.get_property("front curvature")
+        else {
+            return Err(OpossumError::Analysis("cannot read front curvature".into()));
+        };
+        Ok(Self::surface_radius(*front_curvature))
+    }
+    /// Returns the radius of curvature of the rear surface of this [`Lens`].
+    ///
+    /// A flat surface is returned as `None`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property `rear curvature` cannot be read.
+    pub fn rear_curvature(&self) -> OpmResult<Option<Length>> {
+        let Ok(Proptype::Length(rear_curvature)) = self.node_attr.get_property("rear curvature")
+        else {
+            return Err(OpossumError::Analysis("cannot read rear curvature".into()));
+        };
+        Ok(Self::surface_radius(*rear_curvature))
+    }
+    /// Convert a (signed) curvature property value into a radius of curvature.
+    ///
+    /// A radius of +/- infinity denotes a flat surface and is mapped to `None`.
+    fn surface_radius(curvature: Length) -> Option<Length> {
+        if curvature.is_infinite() {
+            None
+        } else {
+            Some(curvature)
+        }
+    }
+    /// Check, if the given combination of surface radii and center thickness forms a valid lens.
+    ///
+    /// All signs of the radii are accepted (biconvex, biconcave, plano-convex, plano-concave as well as positive and
+    /// negative meniscus lenses). However, the lens must not have a negative thickness in the vicinity of the optical
+    /// axis. This can only happen for a center thickness of zero. In this case, the lens thickness must not decrease
+    /// off-axis, i.e. the lens must not be convex in total.
+    fn check_curvature_combination(
+        front_radius: Option<Length>,
+        rear_radius: Option<Length>,
+        center_thickness: Length,
+    ) -> OpmResult<()> {
+        if !center_thickness.is_zero() {
+            return Ok(());
+        }
+        let front_curv = front_radius.map_or(0.0, |r| 1.0 / r.value);
+        let rear_curv = rear_radius.map_or(0.0, |r| 1.0 / r.value);
+        // paraxial thickness at height h: t(h) = ct + h^2 / 2 * (1/R_rear - 1/R_front)
+        if rear_curv - front_curv < 0.0 {
+            return Err(OpossumError::Other(
+                "surfaces of a lens with zero center thickness must not intersect".into(),
+            ));
+        }
+        Ok(())
+    }
     /// create a default aperture: defined by
     ///  - intersection of two spheres
     ///  - intersection of sphere and plane
@@ -225,36 +387,41 @@ impl Lens {
 impl OpticNode for Lens {
     fn update_surfaces(&mut self) -> OpmResult<()> {
         let node_iso = self.effective_node_iso().unwrap_or_else(Isometry::identity);
-        let Ok(Proptype::Length(front_curvature)) = self.node_attr.get_property("front curvature")
-        else {
-            return Err(OpossumError::Analysis("cannot read front curvature".into()));
-        };
-        let (front_geosurface, anchor_point_iso_front) = if front_curvature.is_infinite() {
-            (
-                GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(node_iso.clone())))),
-                Isometry::identity(),
-            )
-        } else {
-            let anchor_point_iso_front =
-                Isometry::new(meter!(0., 0., front_curvature.value), radian!(0., 0., 0.))?;
-            (
-                GeoSurfaceRef(Arc::new(Mutex::new(Sphere::new(
-                    *front_curvature,
-                    node_iso.append(&anchor_point_iso_front),
-                )?))),
-                anchor_point_iso_front,
-            )
-        };
+        let (front_geosurface, anchor_point_iso_front) =
+            if let Some(profile) = self.front_aspheric_profile()? {
+                let radius = self
+                    .front_curvature()?
+                    .unwrap_or_else(|| meter!(f64::INFINITY));
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Asphere::new(
+                        radius,
+                        profile,
+                        node_iso.clone(),
+                    )?))),
+                    Isometry::identity(),
+                )
+            } else if let Some(front_curvature) = self.front_curvature()? {
+                let anchor_point_iso_front =
+                    Isometry::new(meter!(0., 0., front_curvature.value), radian!(0., 0., 0.))?;
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Sphere::new(
+                        front_curvature,
+                        node_iso.append(&anchor_point_iso_front),
+                    )?))),
+                    anchor_point_iso_front,
+                )
+            } else {
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(node_iso.clone())))),
+                    Isometry::identity(),
+                )
+            };
         self.update_surface(
             &"input_1".to_string(),
             front_geosurface,
             anchor_point_iso_front,
             &PortType::Input,
         )?;
-        let Ok(Proptype::Length(rear_curvature)) = self.node_attr.get_property("rear curvature")
-        else {
-            return Err(OpossumError::Analysis("cannot read rear curvature".into()));
-        };
         let Ok(Proptype::Length(center_thickness)) =
             self.node_attr.get_property("center thickness")
         else {
@@ -262,28 +429,43 @@ impl OpticNode for Lens {
                 "cannot read center thickness".into(),
             ));
         };
-        let (rear_geosurface, anchor_point_iso_rear) = if rear_curvature.is_infinite() {
-            let anchor_point_iso_rear =
-                Isometry::new(meter!(0., 0., center_thickness.value), radian!(0., 0., 0.))?;
-            (
-                GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(
-                    node_iso.append(&anchor_point_iso_rear),
-                )))),
-                anchor_point_iso_rear,
-            )
-        } else {
-            let anchor_point_iso_rear = Isometry::new(
-                meter!(0., 0., (*rear_curvature + *center_thickness).value),
-                radian!(0., 0., 0.),
-            )?;
-            (
-                GeoSurfaceRef(Arc::new(Mutex::new(Sphere::new(
-                    *rear_curvature,
-                    node_iso.append(&anchor_point_iso_rear),
-                )?))),
-                anchor_point_iso_rear,
-            )
-        };
+        let (rear_geosurface, anchor_point_iso_rear) =
+            if let Some(profile) = self.rear_aspheric_profile()? {
+                let radius = self
+                    .rear_curvature()?
+                    .unwrap_or_else(|| meter!(f64::INFINITY));
+                let anchor_point_iso_rear =
+                    Isometry::new(meter!(0., 0., center_thickness.value), radian!(0., 0., 0.))?;
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Asphere::new(
+                        radius,
+                        profile,
+                        node_iso.append(&anchor_point_iso_rear),
+                    )?))),
+                    anchor_point_iso_rear,
+                )
+            } else if let Some(rear_curvature) = self.rear_curvature()? {
+                let anchor_point_iso_rear = Isometry::new(
+                    meter!(0., 0., (rear_curvature + *center_thickness).value),
+                    radian!(0., 0., 0.),
+                )?;
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Sphere::new(
+                        rear_curvature,
+                        node_iso.append(&anchor_point_iso_rear),
+                    )?))),
+                    anchor_point_iso_rear,
+                )
+            } else {
+                let anchor_point_iso_rear =
+                    Isometry::new(meter!(0., 0., center_thickness.value), radian!(0., 0., 0.))?;
+                (
+                    GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(
+                        node_iso.append(&anchor_point_iso_rear),
+                    )))),
+                    anchor_point_iso_rear,
+                )
+            };
         self.update_surface(
             &"output_1".to_string(),
             rear_geosurface,
@@ -328,8 +510,9 @@ impl OpticNode for Lens {
 mod test {
     use super::*;
     use crate::{
+        SceneryResources,
         analyzers::{RayTraceConfig, energy::AnalysisEnergy, raytrace::AnalysisRayTrace},
-        aperture::Aperture,
+        aperture::{EllipseConfig, RectangleConfig},
         joule,
         light_result::LightResult,
         lightdata::LightData,
@@ -338,10 +521,12 @@ mod test {
         position_distributions::Hexapolar,
         properties::Proptype,
         rays::Rays,
+        refractive_index::RefrIndexTempCoeff,
     };
     use approx::assert_relative_eq;
     use core::f64;
     use nalgebra::Vector3;
+    use uom::si::{f64::ThermodynamicTemperature, thermodynamic_temperature::degree_celsius};
 
     #[test]
     fn default() {
@@ -368,6 +553,8 @@ mod test {
             panic!()
         };
         assert_eq!((*index).get_refractive_index(Length::zero()).unwrap(), 1.5);
+        assert!(node.front_aspheric_profile().unwrap().is_none());
+        assert!(node.rear_aspheric_profile().unwrap().is_none());
     }
     #[test]
     fn new() {
@@ -416,6 +603,38 @@ mod test {
         );
     }
     #[test]
+    fn refractive_index_ambient_temperature() {
+        let celsius = ThermodynamicTemperature::new::<degree_celsius>;
+        let roc = millimeter!(100.0);
+        let mut ref_index = RefrIndexTempCoeff::new(
+            RefrIndexConst::new(1.5).unwrap().into(),
+            1.0e-5,
+            0.0,
+            celsius(20.0),
+        )
+        .unwrap();
+        let mut node = Lens::new("test", roc, roc, millimeter!(10.0), &ref_index).unwrap();
+        let refr_index_at = |node: &Lens| {
+            node.get_node_attributes_ray_trace(&node.node_attr)
+                .unwrap()
+                .0
+                .get_refractive_index(nanometer!(1053.0))
+                .unwrap()
+        };
+        assert_relative_eq!(refr_index_at(&node), 1.5);
+        node.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources {
+            ambient_temperature: Some(celsius(30.0)),
+            ..Default::default()
+        }))));
+        assert_relative_eq!(refr_index_at(&node), 1.5001);
+        // an operating temperature of the node overrides the ambient temperature
+        ref_index.set_temperature(Some(celsius(40.0))).unwrap();
+        node.node_attr
+            .set_property("refractive index", ref_index.to_enum().into())
+            .unwrap();
+        assert_relative_eq!(refr_index_at(&node), 1.5002);
+    }
+    #[test]
     fn inverted() {
         test_inverted::<Lens>()
     }
@@ -469,6 +688,66 @@ mod test {
         }
     }
     #[test]
+    fn with_clear_aperture() {
+        let ap = Aperture::BinaryEllipse(
+            EllipseConfig::new((millimeter!(5.0), millimeter!(2.0)), millimeter!(0.0, 0.0))
+                .unwrap(),
+        );
+        let node = Lens::default().with_clear_aperture(&ap).unwrap();
+        assert!(matches!(
+            node.ports().aperture(&PortType::Input, "input_1"),
+            Some(Aperture::BinaryEllipse(_))
+        ));
+        assert!(matches!(
+            node.ports().aperture(&PortType::Output, "output_1"),
+            Some(Aperture::BinaryEllipse(_))
+        ));
+    }
+    #[test]
+    fn analyze_rectangular_clear_aperture() {
+        let ap = Aperture::BinaryRectangle(
+            RectangleConfig::new(millimeter!(10.0), millimeter!(30.0), millimeter!(0.0, 0.0))
+                .unwrap(),
+        );
+        let mut node = Lens::new(
+            "test",
+            millimeter!(f64::INFINITY),
+            millimeter!(f64::NEG_INFINITY),
+            millimeter!(10.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap()
+        .with_clear_aperture(&ap)
+        .unwrap();
+        node.set_isometry(Isometry::new_along_z(millimeter!(10.0)).unwrap())
+            .unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(10.0), 5).unwrap(),
+        )
+        .unwrap();
+        let nr_of_rays = rays.nr_of_rays(true);
+        let mut incoming_data = LightResult::default();
+        incoming_data.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, incoming_data, &RayTraceConfig::default())
+                .unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric output data");
+        };
+        assert!(rays.nr_of_rays(true) < nr_of_rays);
+        let mut max_y = millimeter!(0.0);
+        for ray in rays.iter().filter(|r| r.valid()) {
+            let pos = ray.position();
+            assert!(pos.x.abs() <= millimeter!(5.0));
+            max_y = max_y.max(pos.y.abs());
+        }
+        // footprint is a "rounded rectangle": clipped in x but still limited by the beam radius in y
+        assert!(max_y > millimeter!(5.0));
+        assert!(max_y <= millimeter!(10.0));
+    }
+    #[test]
     fn analyze_biconvex() {
         // biconvex lens with index of 1.0 (="neutral" lens)
         let mut node = Lens::new(
@@ -500,6 +779,209 @@ mod test {
         }
     }
     #[test]
+    fn with_asphere() {
+        let profile = AsphericProfile::new(-1.0, vec![1.0e-3]).unwrap();
+        let node = Lens::default().with_front_asphere(profile.clone()).unwrap();
+        assert_eq!(
+            node.front_aspheric_profile().unwrap(),
+            Some(profile.clone())
+        );
+        assert!(node.rear_aspheric_profile().unwrap().is_none());
+        let surface_name = |node: &Lens, name: &str| {
+            node.get_optic_surface(name)
+                .unwrap()
+                .geo_surface()
+                .0
+                .lock()
+                .unwrap()
+                .name()
+        };
+        assert_eq!(surface_name(&node, "input_1"), "asphere");
+        assert_eq!(surface_name(&node, "output_1"), "sphere");
+        let node = Lens::default().with_rear_asphere(profile.clone()).unwrap();
+        assert!(node.front_aspheric_profile().unwrap().is_none());
+        assert_eq!(node.rear_aspheric_profile().unwrap(), Some(profile));
+        assert_eq!(surface_name(&node, "input_1"), "sphere");
+        assert_eq!(surface_name(&node, "output_1"), "asphere");
+    }
+    /// Returns the minimum and maximum z positions at which the (off-axis) output rays cross the optical axis.
+    fn axis_crossings(node: &mut Lens) -> (f64, f64) {
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(10.0), 3).unwrap(),
+        )
+        .unwrap();
+        let mut incoming_data = LightResult::default();
+        incoming_data.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(node, incoming_data, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!()
+        };
+        let mut crossings = (f64::INFINITY, f64::NEG_INFINITY);
+        for ray in rays {
+            let (pos, dir) = (ray.position(), ray.direction());
+            let radial_dir = dir.x.mul_add(dir.x, dir.y * dir.y);
+            if radial_dir == 0.0 {
+                continue;
+            }
+            let t = -pos.x.value.mul_add(dir.x, pos.y.value * dir.y) / radial_dir;
+            let z = t.mul_add(dir.z, pos.z.value);
+            crossings = (crossings.0.min(z), crossings.1.max(z));
+        }
+        crossings
+    }
+    #[test]
+    fn analyze_aspheric_focus() {
+        // plano-convex lens with a hyperbolic rear surface (K = -n²) focuses a collimated beam without spherical aberration
+        let n = RefrIndexConst::new(1.5).unwrap();
+        let mut node = Lens::new(
+            "test",
+            millimeter!(f64::INFINITY),
+            millimeter!(-50.0),
+            millimeter!(10.0),
+            &n,
+        )
+        .unwrap()
+        .with_rear_asphere(AsphericProfile::new(-2.25, vec![]).unwrap())
+        .unwrap();
+        let (min, max) = axis_crossings(&mut node);
+        assert_relative_eq!(min, 0.11, epsilon = 1e-9);
+        assert_relative_eq!(max, 0.11, epsilon = 1e-9);
+        let mut node = Lens::new(
+            "test",
+            millimeter!(f64::INFINITY),
+            millimeter!(-50.0),
+            millimeter!(10.0),
+            &n,
+        )
+        .unwrap();
+        let (min, max) = axis_crossings(&mut node);
+        assert!(max - min > 1.0e-3);
+    }
+    #[test]
+    fn new_signed_radii() {
+        let n = RefrIndexConst::new(1.5).unwrap();
+        // plano-concave, concave-plano, bi-concave, positive & negative meniscus
+        for (front, rear) in [
+            (f64::INFINITY, 100.0),
+            (-100.0, f64::INFINITY),
+            (-100.0, 100.0),
+            (100.0, 200.0),
+            (200.0, 100.0),
+            (-100.0, -200.0),
+        ] {
+            assert!(
+                Lens::new(
+                    "test",
+                    millimeter!(front),
+                    millimeter!(rear),
+                    millimeter!(10.0),
+                    &n
+                )
+                .is_ok()
+            );
+        }
+        // zero center thickness is ok, if the lens gets thicker off-axis
+        assert!(
+            Lens::new(
+                "test",
+                millimeter!(-100.0),
+                millimeter!(100.0),
+                millimeter!(0.0),
+                &n
+            )
+            .is_ok()
+        );
+        assert!(
+            Lens::new(
+                "test",
+                millimeter!(100.0),
+                millimeter!(-100.0),
+                millimeter!(0.0),
+                &n
+            )
+            .is_err()
+        );
+        assert!(
+            Lens::new(
+                "test",
+                millimeter!(100.0),
+                millimeter!(200.0),
+                millimeter!(0.0),
+                &n
+            )
+            .is_err()
+        );
+    }
+    #[test]
+    fn curvature_getters() {
+        let node = Lens::new(
+            "test",
+            millimeter!(f64::INFINITY),
+            millimeter!(100.0),
+            millimeter!(10.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(node.front_curvature().unwrap(), None);
+        assert_eq!(node.rear_curvature().unwrap(), Some(millimeter!(100.0)));
+        let node = Lens::new(
+            "test",
+            millimeter!(-50.0),
+            millimeter!(f64::NEG_INFINITY),
+            millimeter!(10.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(node.front_curvature().unwrap(), Some(millimeter!(-50.0)));
+        assert_eq!(node.rear_curvature().unwrap(), None);
+    }
+    #[test]
+    fn analyze_plano_concave() {
+        let mut node = Lens::new(
+            "test",
+            millimeter!(f64::INFINITY),
+            millimeter!(100.0),
+            millimeter!(0.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        node.set_isometry(Isometry::new_along_z(millimeter!(10.0)).unwrap())
+            .unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(5.0), 3).unwrap(),
+        )
+        .unwrap();
+        let mut incoming_data = LightResult::default();
+        incoming_data.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, incoming_data, &RayTraceConfig::default())
+                .unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric output")
+        };
+        for ray in rays {
+            let pos = ray.position().map(|c| c.value);
+            let dir = ray.direction();
+            let r = pos.x.hypot(pos.y);
+            if r > 1.0e-6 {
+                // diverging: rays point away from the optical axis
+                assert!(pos.x * dir.x + pos.y * dir.y > 0.0);
+                // paraxial focal length f = R / (1 - n) = -200 mm
+                let slope = dir.x.hypot(dir.y) / dir.z;
+                assert_relative_eq!(-r / slope, -0.2, max_relative = 0.02);
+            } else {
+                assert_relative_eq!(dir.x, 0.0);
+                assert_relative_eq!(dir.y, 0.0);
+            }
+        }
+    }
+    #[test]
     fn get_minimum_logical_aperture_radius_bi_convex() {
         let node = Lens::new(
             "test",
@@ -17,50 +17,68 @@
 //!    node_attr: NodeAttr
 //! }
 //! ```
+pub mod beam_expander;
 mod beam_splitter;
 mod cylindric_lens;
 mod dummy;
 mod energy_meter;
 pub mod fluence_detector;
 mod ideal_filter;
+pub mod isolator;
 mod lens;
 mod node_attr;
 mod node_group;
 mod parabolic_mirror;
 mod paraxial_surface;
+pub mod polarizer;
+pub mod prism;
 pub mod ray_propagation_visualizer;
 mod reference;
 pub mod reflective_grating;
 mod source;
 mod source_helper;
+pub mod spatial_filter;
 mod spectrometer;
 mod spot_diagram;
 mod test_helper;
 mod thin_mirror;
+pub mod transmission_grating;
 mod wavefront;
+pub mod waveplate;
 mod wedge;
+pub use beam_expander::{ExpanderType, beam_expander};
 pub use beam_splitter::BeamSplitter;
 pub use cylindric_lens::CylindricLens;
 pub use dummy::Dummy;
 pub use energy_meter::{EnergyMeter, Metertype};
 pub use fluence_detector::FluenceDetector;
-pub use ideal_filter::{FilterType, IdealFilter};
+pub use ideal_filter::{FilterType, IdealFilter, TransmissionCurve};
+pub use isolator::FaradayIsolator;
 pub use lens::Lens;
+pub(crate) use node_attr::LegacyNodeAttr;
 pub use node_attr::NodeAttr;
-pub use node_group::{NodeGroup, OpticGraph};
+pub use node_group::{
+    ConnectionInfo, NodeGroup, OpticGraph, Prescription, PrescriptionSurface, Warning, WarningKind,
+};
+pub(crate) use node_group::{LegacyNodeGroup, LegacyOpticGraph};
 pub use parabolic_mirror::ParabolicMirror;
 pub use paraxial_surface::ParaxialSurface;
+pub use polarizer::Polarizer;
+pub use prism::Prism;
 pub use ray_propagation_visualizer::RayPropagationVisualizer;
 pub use reference::NodeReference;
 pub use reflective_grating::ReflectiveGrating;
 pub use spectrometer::{Spectrometer, SpectrometerType};
 pub use thin_mirror::ThinMirror;
+pub use transmission_grating::TransmissionGrating;
 pub use wavefront::{WaveFront, WaveFrontData, WaveFrontErrorMap};
+pub use waveplate::{Retardance, Waveplate};
 
 pub use source::Source;
 pub use source_helper::{
     collimated_line_ray_source, point_ray_source, round_collimated_ray_source,
 };
+pub use spatial_filter::{Pinhole, spatial_filter};
 pub use spot_diagram::SpotDiagram;
 use std::sync::{Arc, Mutex};
 pub use wedge::Wedge;
@@ -101,6 +119,10 @@ pub fn create_node_ref(node_type: &str) -> OpmResult<OpticRef> {
             Arc::new(Mutex::new(ReflectiveGrating::default())),
             None,
         )),
+        "transmission grating" => Ok(OpticRef::new(
+            Arc::new(Mutex::new(TransmissionGrating::default())),
+            None,
+        )),
         "reference" => Ok(OpticRef::new(
             Arc::new(Mutex::new(NodeReference::default())),
             None,
@@ -136,6 +158,7 @@ pub fn create_node_ref(node_type: &str) -> OpmResult<OpticRef> {
             None,
         )),
         "wedge" => Ok(OpticRef::new(Arc::new(Mutex::new(Wedge::default())), None)),
+        "prism" => Ok(OpticRef::new(Arc::new(Mutex::new(Prism::default())), None)),
         "mirror" => Ok(OpticRef::new(
             Arc::new(Mutex::new(ThinMirror::default())),
             None,
@@ -144,6 +167,22 @@ pub fn create_node_ref(node_type: &str) -> OpmResult<OpticRef> {
             Arc::new(Mutex::new(ParabolicMirror::default())),
             None,
         )),
+        "polarizer" => Ok(OpticRef::new(
+            Arc::new(Mutex::new(Polarizer::default())),
+            None,
+        )),
+        "waveplate" => Ok(OpticRef::new(
+            Arc::new(Mutex::new(Waveplate::default())),
+            None,
+        )),
+        "faraday isolator" => Ok(OpticRef::new(
+            Arc::new(Mutex::new(FaradayIsolator::default())),
+            None,
+        )),
+        "pinhole" => Ok(OpticRef::new(
+            Arc::new(Mutex::new(Pinhole::default())),
+            None,
+        )),
         _ => Err(OpossumError::Other(format!(
             "cannot create node type <{node_type}>"
         ))),
@@ -164,6 +203,7 @@ pub fn node_types() -> Vec<(&'static str, &'static str)> {
         ("group", "group node containing othe nodes or groups"),
         ("ideal filter", "ideal filter"),
         ("reflective grating", "reflective optical grating"),
+        ("transmission grating", "transmission optical grating"),
         ("lens", "spherical lens"),
         ("cylindric lens", "cylindric lens"),
         ("source", "light source"),
@@ -174,8 +214,13 @@ pub fn node_types() -> Vec<(&'static str, &'static str)> {
         ("ray propagation", "ray propagation plotter"),
         ("fluence detector", "fluence detector"),
         ("wedge", "wedged substrate (prism)"),
+        ("prism", "dispersing prism"),
         ("mirror", "ideal flat / spherical mirror"),
         ("parabolic mirror", "parabolic mirror"),
+        ("polarizer", "ideal linear polarizer"),
+        ("waveplate", "ideal waveplate (linear retarder)"),
+        ("faraday isolator", "ideal Faraday isolator"),
+        ("pinhole", "ideal pinhole (spatial filter aperture)"),
     ]
 }
 #[cfg(test)]
@@ -194,6 +239,7 @@ mod test {
             "group",
             "ideal filter",
             "reflective grating",
+            "transmission grating",
             "reference",
             "lens",
             "cylindric lens",
@@ -205,8 +251,13 @@ mod test {
             "ray propagation",
             "fluence detector",
             "wedge",
+            "prism",
             "mirror",
             "parabolic mirror",
+            "polarizer",
+            "waveplate",
+            "faraday isolator",
+            "pinhole",
         ];
         for node_type in node_types {
             assert!(create_node_ref(node_type).is_ok());
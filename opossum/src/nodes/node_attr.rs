@@ -14,23 +14,48 @@ use crate::{
     error::{OpmResult, OpossumError},
     optic_ports::OpticPorts,
     optic_scenery_rsc::SceneryResources,
-    properties::{Properties, Proptype},
+    properties::{LegacyProperties, Properties, Proptype},
     utils::geom_transformation::Isometry,
 };
 
+const fn enabled_default() -> bool {
+    true
+}
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+/// JSON schema of the `node_type` field listing all node types known to [`create_node_ref`](super::create_node_ref).
+#[cfg(feature = "json_schema")]
+fn node_type_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    let node_types = super::node_types()
+        .into_iter()
+        .chain(std::iter::once(("reference", "reference to another node")))
+        .map(|(node_type, description)| {
+            serde_json::json!({"const": node_type, "description": description})
+        })
+        .collect::<Vec<_>>();
+    schemars::json_schema!({
+        "type": "string",
+        "oneOf": node_types
+    })
+}
 /// Struct for storing common attributes of optical nodes.
 ///
 /// `NodeAttr` encapsulates metadata and configuration for an optical node, including its type, name, ports, unique identifier,
 /// laser-induced damage threshold (LIDT), geometric transformations, alignment, and frontend GUI position.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct NodeAttr {
     /// The type of the node (e.g., "lens", "mirror").
+    #[cfg_attr(feature = "json_schema", schemars(schema_with = "node_type_schema"))]
     node_type: String,
     /// The name of the node.
     name: String,
     ports: OpticPorts,
     /// Universally unique identifier for this node.
     uuid: Uuid,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     lidt: Fluence,
     #[serde(default)]
     props: Properties,
@@ -38,15 +63,57 @@ pub struct NodeAttr {
     isometry: Option<Isometry>,
     #[serde(default)]
     inverted: bool,
+    #[serde(default = "enabled_default", skip_serializing_if = "is_enabled")]
+    enabled: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     alignment: Option<Isometry>,
     #[serde(skip)]
     global_conf: Option<Arc<Mutex<SceneryResources>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<(Uuid, f64)>"))]
     align_like_node_at_distance: Option<(Uuid, Length)>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<[f64; 2]>"))]
     gui_position: Option<Point2<f64>>,
 }
+/// Layout of [`NodeAttr`] in `.opm` files of version `0` (see [`LegacyProperties`]).
+#[derive(Deserialize)]
+pub(crate) struct LegacyNodeAttr {
+    node_type: String,
+    name: String,
+    ports: OpticPorts,
+    uuid: Uuid,
+    lidt: Fluence,
+    #[serde(default)]
+    props: LegacyProperties,
+    isometry: Option<Isometry>,
+    #[serde(default)]
+    inverted: bool,
+    #[serde(default = "enabled_default")]
+    enabled: bool,
+    alignment: Option<Isometry>,
+    align_like_node_at_distance: Option<(Uuid, Length)>,
+    gui_position: Option<Point2<f64>>,
+}
+impl From<LegacyNodeAttr> for NodeAttr {
+    fn from(legacy: LegacyNodeAttr) -> Self {
+        Self {
+            node_type: legacy.node_type,
+            name: legacy.name,
+            ports: legacy.ports,
+            uuid: legacy.uuid,
+            lidt: legacy.lidt,
+            props: legacy.props.into(),
+            isometry: legacy.isometry,
+            inverted: legacy.inverted,
+            enabled: legacy.enabled,
+            alignment: legacy.alignment,
+            global_conf: None,
+            align_like_node_at_distance: legacy.align_like_node_at_distance,
+            gui_position: legacy.gui_position,
+        }
+    }
+}
 impl NodeAttr {
     /// Creates new node attributes ([`NodeAttr`]).
     ///
@@ -54,6 +121,7 @@ impl NodeAttr {
     /// - `name`: Set to the provided `node_type` string.
     /// - `node_type`: Set to the provided `node_type` string.
     /// - `inverted`: Set to `false`.
+    /// - `enabled`: Set to `true`.
     /// - `ports`: Set to default (empty) [`OpticPorts`] structure.
     /// - `alignment`: Set to `None`.
     /// - `uuid`: Randomly generated unique identifier.
@@ -78,6 +146,7 @@ impl NodeAttr {
             global_conf: None,
             isometry: None,
             inverted: false,
+            enabled: true,
             alignment: None,
             align_like_node_at_distance: None,
             uuid: Uuid::new_v4(),
@@ -203,6 +272,17 @@ impl NodeAttr {
     pub const fn set_inverted(&mut self, inverted: bool) {
         self.inverted = inverted;
     }
+    /// Returns `true` if the node is enabled.
+    ///
+    /// A disabled node is bypassed during analysis, i.e. light passes through unchanged.
+    #[must_use]
+    pub const fn enabled(&self) -> bool {
+        self.enabled
+    }
+    /// Enables or disables this [`NodeAttr`].
+    pub const fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
     /// Returns a reference to the optic ports of this [`NodeAttr`].
     #[must_use]
     pub const fn ports(&self) -> &OpticPorts {
@@ -0,0 +1,4 @@
+use super::NodeGroup;
+use crate::analyzers::gaussian_beam::AnalysisGaussianBeam;
+
+impl AnalysisGaussianBeam for NodeGroup {}
@@ -1,22 +1,38 @@
 #![warn(missing_docs)]
 use super::NodeGroup;
 use crate::{
-    analyzers::{GhostFocusConfig, ghostfocus::AnalysisGhostFocus},
+    analyzers::{
+        GhostFocusConfig,
+        ghostfocus::AnalysisGhostFocus,
+        progress::{AnalysisProgress, report_progress},
+    },
     error::{OpmResult, OpossumError},
-    light_result::{LightRays, light_rays_to_light_result, light_result_to_light_rays},
+    light_result::{LightRays, bypass, light_rays_to_light_result, light_result_to_light_rays},
     lightdata::LightData,
     optic_node::OpticNode,
     optic_ports::PortType,
     rays::Rays,
 };
-use log::warn;
+use log::{info, warn};
 
-fn filter_ray_limits(light_rays: &mut LightRays, config: &GhostFocusConfig) {
+fn filter_ray_limits(
+    light_rays: &mut LightRays,
+    config: &GhostFocusConfig,
+    node_info: &str,
+) -> OpmResult<()> {
+    let mut nr_of_pruned_rays = 0;
     for lr in light_rays {
         for rays in lr.1 {
             rays.filter_by_nr_of_bounces(config.max_bounces());
+            nr_of_pruned_rays += rays
+                .prune_by_threshold_energy(config.min_energy_per_ray())?
+                .nr_of_rays(false);
         }
     }
+    if nr_of_pruned_rays > 0 {
+        info!("{nr_of_pruned_rays} rays below energy threshold pruned after node {node_info}");
+    }
+    Ok(())
 }
 
 impl AnalysisGhostFocus for NodeGroup {
@@ -38,13 +54,17 @@ impl AnalysisGhostFocus for NodeGroup {
             warn!("group contains unconnected sub-trees. Analysis might not be complete.");
         }
         let sorted = self.graph.topologically_sorted()?;
-        for idx in sorted {
+        let nr_of_nodes = sorted.len();
+        for (node_index, idx) in sorted.into_iter().enumerate() {
             let node_ref = g_clone.graph.node_by_idx(idx)?.optical_ref;
             let node = node_ref
                 .lock()
                 .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
             let node_id = node.node_attr().uuid();
+            let node_name = node.name();
             let node_info = node.to_string();
+            let ports = node.ports();
+            let enabled = node.enabled();
             drop(node);
             if self.graph.is_stale_node(node_id) {
                 warn!("graph contains stale (completely unconnected) node {node_info}. Skipping.");
@@ -53,20 +73,35 @@ impl AnalysisGhostFocus for NodeGroup {
                     node_id,
                     &light_rays_to_light_result(current_bouncing_rays.clone()),
                 );
-
-                let mut outgoing_edges = AnalysisGhostFocus::analyze(
-                    &mut *node_ref
-                        .lock()
-                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
-                    light_result_to_light_rays(incoming_edges)?,
-                    config,
-                    ray_collection,
-                    bounce_lvl,
-                )
-                .map_err(|e| {
-                    OpossumError::Analysis(format!("analysis of node {node_info} failed: {e}"))
-                })?;
-                filter_ray_limits(&mut outgoing_edges, config);
+                let incoming_rays = light_result_to_light_rays(incoming_edges)?;
+                report_progress(|| AnalysisProgress {
+                    node: node_name,
+                    node_index,
+                    nr_of_nodes,
+                    bounce: bounce_lvl,
+                    nr_of_rays: incoming_rays
+                        .values()
+                        .flatten()
+                        .map(|rays| rays.nr_of_rays(true))
+                        .sum(),
+                });
+                let mut outgoing_edges = if enabled {
+                    AnalysisGhostFocus::analyze(
+                        &mut *node_ref
+                            .lock()
+                            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
+                        incoming_rays,
+                        config,
+                        ray_collection,
+                        bounce_lvl,
+                    )
+                    .map_err(|e| {
+                        OpossumError::Analysis(format!("analysis of node {node_info} failed: {e}"))
+                    })?
+                } else {
+                    bypass(&incoming_rays, &ports)
+                };
+                filter_ray_limits(&mut outgoing_edges, config, &node_info)?;
 
                 current_bouncing_rays.clone_from(&outgoing_edges);
 
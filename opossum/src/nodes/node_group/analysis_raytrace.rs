@@ -3,34 +3,144 @@ use log::{info, warn};
 use nalgebra::{Point3, Vector3};
 use num::Zero;
 use petgraph::graph::NodeIndex;
-use uom::si::f64::Length;
+use uom::si::{
+    energy::joule,
+    f64::{Energy, Length},
+};
 
 use super::{NodeGroup, OpticGraph};
 use crate::{
-    analyzers::{RayTraceConfig, raytrace::AnalysisRayTrace},
+    analyzers::{
+        BeamCaustic, RayTraceConfig, TraceStatistics,
+        progress::{AnalysisProgress, report_progress},
+        raytrace::AnalysisRayTrace,
+    },
     error::{OpmResult, OpossumError},
-    light_result::LightResult,
+    light_result::{LightResult, bypass},
     lightdata::LightData,
     optic_node::OpticNode,
     optic_ports::PortType,
+    properties::proptype::format_quantity,
     radian,
     utils::geom_transformation::Isometry,
 };
 
-fn filter_ray_limits(light_result: &mut LightResult, r_config: &RayTraceConfig) {
+fn filter_ray_limits(
+    light_result: &mut LightResult,
+    r_config: &RayTraceConfig,
+    node_info: &str,
+    trace_statistics: &mut TraceStatistics,
+) -> OpmResult<()> {
+    let mut nr_of_pruned_rays = 0;
     for lr in light_result {
         if let LightData::Geometric(rays) = lr.1 {
             rays.filter_by_nr_of_bounces(r_config.max_number_of_bounces());
             rays.filter_by_nr_of_refractions(r_config.max_number_of_refractions());
+            if r_config.prune_low_energy_rays() {
+                let pruned = rays.prune_by_threshold_energy(r_config.min_energy_per_ray())?;
+                nr_of_pruned_rays += pruned.nr_of_rays(false);
+                trace_statistics.add_pruned(&pruned);
+            }
         }
     }
+    if nr_of_pruned_rays > 0 {
+        info!("{nr_of_pruned_rays} rays below energy threshold pruned after node {node_info}");
+    }
+    Ok(())
 }
 
-impl AnalysisRayTrace for NodeGroup {
-    fn analyze(
+fn total_geometric_energy(light_data: Option<&LightData>) -> Energy {
+    if let Some(LightData::Geometric(rays)) = light_data {
+        rays.total_energy()
+    } else {
+        Energy::zero()
+    }
+}
+
+impl NodeGroup {
+    /// Repeatedly analyze this group while feeding the light of the round trip output port back into the round trip
+    /// input port.
+    ///
+    /// Sources within this group only emit light during the first round trip. The light leaving all other output
+    /// ports is accumulated over all round trips while the light of the round trip output port is returned as it is
+    /// after the last round trip. Detector nodes within this group accumulate the light of all round trips. The hit
+    /// points of each round trip are stored at the hit map level corresponding to the round trip index.
+    fn analyze_round_trips(
         &mut self,
         incoming_data: LightResult,
         config: &RayTraceConfig,
+        output_port: &str,
+        input_port: &str,
+    ) -> OpmResult<LightResult> {
+        let light_result =
+            self.analyze_round_trip_passes(incoming_data, config, output_port, input_port);
+        self.set_hit_map_level(None);
+        light_result
+    }
+    fn analyze_round_trip_passes(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+        output_port: &str,
+        input_port: &str,
+    ) -> OpmResult<LightResult> {
+        let mut trace_statistics = TraceStatistics::default();
+        let mut beam_caustic = config.caustic_step().map(BeamCaustic::new).transpose()?;
+        let mut light_result = LightResult::default();
+        let mut trip_input = incoming_data;
+        for round_trip in 0..config.round_trips() {
+            let energy_in = total_geometric_energy(trip_input.get(input_port));
+            self.set_hit_map_level(Some(round_trip));
+            let mut trip_output =
+                self.analyze_single_pass(std::mem::take(&mut trip_input), config, round_trip == 0)?;
+            trace_statistics.merge(&self.trace_statistics);
+            if let (Some(caustic), Some(trip_caustic)) = (&mut beam_caustic, &self.beam_caustic) {
+                caustic.merge(trip_caustic)?;
+            }
+            let feedback = trip_output.remove(output_port);
+            let energy_out = total_geometric_energy(feedback.as_ref());
+            if round_trip > 0 && energy_out > energy_in {
+                warn!(
+                    "energy increased from {} to {} during round trip {round_trip} of group '{}'. The result might grow unbounded.",
+                    format_quantity(joule, energy_in),
+                    format_quantity(joule, energy_out),
+                    self.name()
+                );
+            }
+            for (port, light_data) in trip_output {
+                match (light_result.get_mut(&port), light_data) {
+                    (Some(LightData::Geometric(rays)), LightData::Geometric(new_rays)) => {
+                        rays.merge(&new_rays);
+                    }
+                    (_, light_data) => {
+                        light_result.insert(port, light_data);
+                    }
+                }
+            }
+            let Some(feedback) = feedback else {
+                info!(
+                    "no light on round trip output port '{output_port}' after round trip {round_trip}. Stopping."
+                );
+                break;
+            };
+            if round_trip + 1 == config.round_trips() {
+                light_result.insert(output_port.to_string(), feedback);
+            } else {
+                trip_input = LightResult::from([(input_port.to_string(), feedback)]);
+            }
+        }
+        self.trace_statistics = trace_statistics;
+        self.beam_caustic = beam_caustic;
+        Ok(light_result)
+    }
+    /// Analyze all nodes of this group once in topological order.
+    ///
+    /// If `emit_sources` is false, all source nodes of this group are skipped (i.e. they do not emit any light).
+    fn analyze_single_pass(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+        emit_sources: bool,
     ) -> OpmResult<LightResult> {
         if self.graph.is_inverted() {
             self.graph.invert_graph()?;
@@ -41,30 +151,82 @@ impl AnalysisRayTrace for NodeGroup {
         }
         let sorted = self.graph.topologically_sorted()?;
         let mut light_result = incoming_data.clone();
-        for idx in sorted {
+        self.trace_statistics = TraceStatistics::default();
+        self.beam_caustic = config.caustic_step().map(BeamCaustic::new).transpose()?;
+        let nr_of_nodes = sorted.len();
+        for (node_index, idx) in sorted.into_iter().enumerate() {
             let node_ref = g_clone.graph.node_by_idx(idx)?.optical_ref;
             let node = node_ref
                 .lock()
                 .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            let node_name = node.name();
             let node_info = node.to_string();
             let node_id = node.node_attr().uuid();
+            let is_source = node.node_attr().node_type() == "source";
+            let ports = node.ports();
+            let output_ports = ports.names(&PortType::Output);
+            let enabled = node.enabled();
             drop(node);
             if self.graph.is_stale_node(node_id) {
                 warn!("graph contains stale (completely unconnected) node {node_info}. Skipping.");
             } else {
                 let incoming_edges = self.graph.get_incoming(node_id, &incoming_data);
-                let mut outgoing_edges = AnalysisRayTrace::analyze(
-                    &mut *node_ref
-                        .lock()
-                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
-                    incoming_edges,
+                report_progress(|| AnalysisProgress {
+                    node: node_name,
+                    node_index,
+                    nr_of_nodes,
+                    bounce: 0,
+                    nr_of_rays: incoming_edges
+                        .values()
+                        .map(|light_data| match light_data {
+                            LightData::Geometric(rays) => rays.nr_of_rays(true),
+                            _ => 0,
+                        })
+                        .sum(),
+                });
+                let mut outgoing_edges = if is_source && !emit_sources {
+                    LightResult::default()
+                } else if enabled {
+                    AnalysisRayTrace::analyze(
+                        &mut *node_ref
+                            .lock()
+                            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
+                        incoming_edges,
+                        config,
+                    )
+                    .map_err(|e| {
+                        OpossumError::Analysis(format!("analysis of node {node_info} failed: {e}"))
+                    })?
+                } else {
+                    bypass(&incoming_edges, &ports)
+                };
+                filter_ray_limits(
+                    &mut outgoing_edges,
                     config,
-                )
-                .map_err(|e| {
-                    OpossumError::Analysis(format!("analysis of node {node_info} failed: {e}"))
-                })?;
-                filter_ray_limits(&mut outgoing_edges, config);
+                    &node_info,
+                    &mut self.trace_statistics,
+                )?;
+                if is_source {
+                    for light_data in outgoing_edges.values() {
+                        if let LightData::Geometric(rays) = light_data {
+                            self.trace_statistics.add_launched(rays);
+                        }
+                    }
+                }
+                if let Ok(group) = node_ref
+                    .lock()
+                    .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
+                    .as_group_mut()
+                {
+                    self.trace_statistics.merge(group.trace_statistics());
+                    if let (Some(caustic), Some(group_caustic)) =
+                        (&mut self.beam_caustic, group.beam_caustic())
+                    {
+                        caustic.merge(group_caustic)?;
+                    }
+                }
                 // If node is sink node, rewrite port names according to output mapping
+                let mut mapped_ports = Vec::new();
                 if self.graph.is_output_node(idx) {
                     let portmap = if self.graph.is_inverted() {
                         self.graph.port_map(&PortType::Input).clone()
@@ -75,12 +237,23 @@ impl AnalysisRayTrace for NodeGroup {
                     for port in assigned_ports {
                         if let Some(light_data) = outgoing_edges.get(&port.1) {
                             light_result.insert(port.0, light_data.clone());
+                            mapped_ports.push(port.1);
                         }
                     }
                 }
                 for outgoing_edge in outgoing_edges {
-                    self.graph
-                        .set_outgoing_edge_data(idx, &outgoing_edge.0, &outgoing_edge.1);
+                    let connected =
+                        self.graph
+                            .set_outgoing_edge_data(idx, &outgoing_edge.0, &outgoing_edge.1);
+                    // light on unconnected (and unmapped) ports leaves the scenery
+                    if !connected
+                        && output_ports.contains(&outgoing_edge.0)
+                        && !mapped_ports.contains(&outgoing_edge.0)
+                        && let LightData::Geometric(rays) = &outgoing_edge.1
+                    {
+                        self.trace_statistics.add_terminated(rays);
+                        self.add_to_beam_caustic(rays);
+                    }
                 }
             }
         }
@@ -89,6 +262,21 @@ impl AnalysisRayTrace for NodeGroup {
         } // revert initial inversion (if necessary)
         Ok(light_result)
     }
+}
+
+impl AnalysisRayTrace for NodeGroup {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        match self.round_trip_ports() {
+            Some((output_port, input_port)) if config.round_trips() > 1 => {
+                self.analyze_round_trips(incoming_data, config, &output_port, &input_port)
+            }
+            _ => self.analyze_single_pass(incoming_data, config, true),
+        }
+    }
     fn calc_node_positions(
         &mut self,
         incoming_data: LightResult,
@@ -97,6 +285,11 @@ impl AnalysisRayTrace for NodeGroup {
         // set stored distances from predecessors
         self.graph
             .set_external_distances(self.input_port_distances.clone());
+        // an alignment wavelength defined for this group overrides the one of the analysis
+        let mut config = config.clone();
+        if let Some(wvl) = self.alignment_wavelength() {
+            config.set_alignment_wavelength(Some(wvl))?;
+        }
 
         let sorted = self.graph.topologically_sorted()?;
         let mut light_result = LightResult::default();
@@ -107,7 +300,7 @@ impl AnalysisRayTrace for NodeGroup {
                 idx,
                 &incoming_data,
                 &mut up_direction,
-                config,
+                &config,
                 &mut light_result,
             )?;
         }
@@ -1,13 +1,16 @@
 #![warn(missing_docs)]
 mod analysis_energy;
+mod analysis_gaussian_beam;
 mod analysis_ghostfocus;
 mod analysis_raytrace;
 mod optic_graph;
-use super::node_attr::NodeAttr;
+mod prescription;
+mod validation;
+use super::node_attr::{LegacyNodeAttr, NodeAttr};
 use crate::{
     SceneryResources,
-    analyzers::Analyzable,
-    dottable::Dottable,
+    analyzers::{Analyzable, caustic::BeamCaustic, trace_statistics::TraceStatistics},
+    dottable::{DotOptions, Dottable},
     error::{OpmResult, OpossumError},
     lightdata::{LightData, light_data_builder::LightDataBuilder},
     optic_node::OpticNode,
@@ -16,11 +19,13 @@ use crate::{
     properties::{Properties, Proptype},
     rays::Rays,
     reporting::{analysis_report::AnalysisReport, node_report::NodeReport},
-    surface::optic_surface::OpticSurface,
+    surface::optic_surface::{ApertureClipping, OpticSurface},
+    utils::geom_transformation::Isometry,
 };
 use num::Zero;
-use optic_graph::ConnectionInfo;
-pub use optic_graph::OpticGraph;
+pub(crate) use optic_graph::LegacyOpticGraph;
+pub use optic_graph::{ConnectionInfo, OpticGraph};
+pub use prescription::{Prescription, PrescriptionSurface};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
 use std::{
@@ -33,7 +38,9 @@ use std::{
 };
 use uom::si::f64::Length;
 use uuid::Uuid;
+pub use validation::{Warning, WarningKind};
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// The basic building block of an optical system. It represents a group of other optical
 /// nodes ([`OpticNode`]s) arranged in a (sub)graph.
 ///
@@ -66,6 +73,9 @@ use uuid::Uuid;
 ///   - `name`
 ///   - `inverted`
 ///   - `expand view`
+///   - `alignment wavelength`
+///   - `round trip output port`
+///   - `round trip input port`
 ///
 /// **Note**: The group node does currently ignore all [`Aperture`](crate::aperture::Aperture) definitions on its publicly
 /// mapped input and output ports.
@@ -76,6 +86,27 @@ pub struct NodeGroup {
     input_port_distances: BTreeMap<String, Length>,
     #[serde(skip)]
     accumulated_rays: Vec<HashMap<Uuid, Rays>>,
+    #[serde(skip)]
+    trace_statistics: TraceStatistics,
+    #[serde(skip)]
+    beam_caustic: Option<BeamCaustic>,
+}
+/// Layout of a [`NodeGroup`] in `.opm` files of version `0` (see [`LegacyOpticGraph`]).
+#[derive(Deserialize)]
+pub(crate) struct LegacyNodeGroup {
+    node_attr: LegacyNodeAttr,
+    graph: LegacyOpticGraph,
+}
+impl TryFrom<LegacyNodeGroup> for NodeGroup {
+    type Error = OpossumError;
+
+    fn try_from(legacy: LegacyNodeGroup) -> OpmResult<Self> {
+        Ok(Self {
+            node_attr: legacy.node_attr.into(),
+            graph: legacy.graph.try_into()?,
+            ..Default::default()
+        })
+    }
 }
 impl Default for NodeGroup {
     fn default() -> Self {
@@ -87,11 +118,34 @@ impl Default for NodeGroup {
                 false.into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "alignment wavelength",
+                "wavelength used for aligning all sources of this group (overrides the source settings)",
+                Proptype::LengthOption(None),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "round trip output port",
+                "mapped output port whose light is fed back for further round trips (empty if unused)",
+                "".into(),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "round trip input port",
+                "mapped input port into which the light of the round trip output port is re-injected (empty if unused)",
+                "".into(),
+            )
+            .unwrap();
         Self {
             graph: OpticGraph::default(),
             input_port_distances: BTreeMap::default(),
             node_attr,
             accumulated_rays: Vec::<HashMap<Uuid, Rays>>::new(),
+            trace_statistics: TraceStatistics::default(),
+            beam_caustic: None,
         }
     }
 }
@@ -165,6 +219,21 @@ impl NodeGroup {
     pub fn delete_node(&mut self, node_id: Uuid) -> OpmResult<Vec<Uuid>> {
         self.graph.delete_node(node_id)
     }
+    /// Duplicate a node of this [`NodeGroup`].
+    ///
+    /// This function creates an independent deep copy of the node with the given [`Uuid`] (including all sub-nodes if
+    /// the node is a [`NodeGroup`]) with new [`Uuid`]s and adds it to this group. The copy is not connected to other
+    /// nodes. See [`OpticGraph::duplicate_node`] for details. The function returns the [`Uuid`] of the new node.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// - the node does not exist.
+    /// - the group is inverted.
+    /// - the node could not be copied.
+    pub fn duplicate_node(&mut self, node_id: Uuid) -> OpmResult<Uuid> {
+        self.graph.duplicate_node(node_id)
+    }
     fn store_node_uuid_in_rays_bundle(&self, node_id: Uuid) -> OpmResult<()> {
         let node_ref = self.graph.node(node_id)?;
         let node = node_ref
@@ -212,6 +281,27 @@ impl NodeGroup {
     pub fn node_recursive(&self, node_id: Uuid) -> OpmResult<OpticRef> {
         self.graph.node_recursive(node_id)
     }
+    /// Return the global (world-frame) [`Isometry`] of the optical node specified by its [`Uuid`].
+    ///
+    /// The node is searched recursively in the subnodes of the group (see [`node_recursive`](NodeGroup::node_recursive())).
+    /// For nodes without an explicitly set isometry, the position and orientation is determined during the positioning
+    /// pass of a ray tracing analysis. The returned [`Isometry`] includes a local alignment (if any) of the node.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the node does not exist.
+    ///   - the node has not been positioned yet.
+    pub fn global_isometry(&self, node_id: Uuid) -> OpmResult<Isometry> {
+        let node_ref = self.graph.node_recursive(node_id)?;
+        let node = node_ref
+            .optical_ref
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+        node.effective_node_iso().ok_or_else(|| {
+            OpossumError::OpticScenery(format!("node {} has not been positioned yet", node.name()))
+        })
+    }
     /// Returns all nodes of this [`NodeGroup`].
     #[must_use]
     pub fn nodes(&self) -> Vec<&OpticRef> {
@@ -238,8 +328,11 @@ impl NodeGroup {
     /// This function returns an [`OpossumError::OpticScenery`] if
     ///   - the group is set as `inverted`. Connectiing subnodes of an inverted group node would result in strange behaviour.
     ///   - the source node / port or target node / port does not exist.
-    ///   - the source node / port or target node / port is already connected.
     ///   - the node connection would form a loop in the graph.
+    ///
+    /// An [`OpossumError::PortAlreadyConnected`] is returned if the target node / port is already connected or if the source
+    /// node / port is already connected and the source node does not allow output fan-out (see
+    /// [`OpticNode::allows_output_fan_out`]).
     pub fn connect_nodes(
         &mut self,
         src_id: Uuid,
@@ -359,6 +452,101 @@ impl NodeGroup {
         self.node_attr
             .set_property("expand view", expand_view.into())
     }
+    /// Returns the alignment wavelength of this [`NodeGroup`] (if defined).
+    ///
+    /// If set, all sources within this group (including nested groups, unless they define their own
+    /// alignment wavelength) are aligned using this wavelength instead of their own settings.
+    #[must_use]
+    pub fn alignment_wavelength(&self) -> Option<Length> {
+        if let Ok(Proptype::LengthOption(wvl)) = self.node_attr.get_property("alignment wavelength")
+        {
+            *wvl
+        } else {
+            None
+        }
+    }
+    /// Sets the alignment wavelength of this [`NodeGroup`].
+    ///
+    /// This forces the calculation of the optical axis (and hence the positioning of, e.g., dispersive elements) at the
+    /// given wavelength, independent of the spectrum of the sources within this group. Setting `None` restores the default
+    /// behaviour.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given wavelength is not positive and finite or the property
+    /// `alignment wavelength` cannot be set.
+    pub fn set_alignment_wavelength(&mut self, wavelength: Option<Length>) -> OpmResult<()> {
+        if let Some(wvl) = wavelength
+            && (wvl.is_sign_negative() || !wvl.is_normal())
+        {
+            return Err(OpossumError::Other(
+                "wavelength must be positive and finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("alignment wavelength", Proptype::LengthOption(wavelength))
+    }
+    /// Returns the feedback path (output port, input port) of this [`NodeGroup`] (if defined).
+    ///
+    /// See [`set_round_trip_ports`](NodeGroup::set_round_trip_ports) for details.
+    #[must_use]
+    pub fn round_trip_ports(&self) -> Option<(String, String)> {
+        let Ok(Proptype::String(output_port)) =
+            self.node_attr.get_property("round trip output port")
+        else {
+            return None;
+        };
+        let Ok(Proptype::String(input_port)) = self.node_attr.get_property("round trip input port")
+        else {
+            return None;
+        };
+        if output_port.is_empty() || input_port.is_empty() {
+            None
+        } else {
+            Some((output_port.clone(), input_port.clone()))
+        }
+    }
+    /// Defines a feedback path of this [`NodeGroup`] for round-trip (e.g. resonator) analyses.
+    ///
+    /// During a ray tracing analysis the light leaving the given (mapped) output port is re-injected into the given (mapped)
+    /// input port until the group has been traversed [`RayTraceConfig::round_trips`](crate::analyzers::RayTraceConfig::round_trips)
+    /// times. Setting `None` removes the feedback path. Detector nodes within this group accumulate the light of all round
+    /// trips. Their hit maps store the hit points of each round trip at the bounce level given by the round trip index.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the given output port or input port is not mapped in this group.
+    ///   - the corresponding properties cannot be set.
+    pub fn set_round_trip_ports(&mut self, ports: Option<(&str, &str)>) -> OpmResult<()> {
+        let (output_port, input_port) = ports.unwrap_or_default();
+        if ports.is_some() {
+            if !self
+                .graph
+                .port_map(&PortType::Output)
+                .port_names()
+                .contains(&output_port.to_string())
+            {
+                return Err(OpossumError::OpticGroup(format!(
+                    "output port {output_port} is not mapped"
+                )));
+            }
+            if !self
+                .graph
+                .port_map(&PortType::Input)
+                .port_names()
+                .contains(&input_port.to_string())
+            {
+                return Err(OpossumError::OpticGroup(format!(
+                    "input port {input_port} is not mapped"
+                )));
+            }
+        }
+        self.node_attr
+            .set_property("round trip output port", output_port.into())?;
+        self.node_attr
+            .set_property("round trip input port", input_port.into())
+    }
     /// Creates the dot format of the [`NodeGroup`] in its expanded view
     /// # Parameters:
     ///   - `node_index`: [`NodeIndex`] of the group
@@ -371,13 +559,13 @@ impl NodeGroup {
         node_index: &str,
         name: &str,
         inverted: bool,
-        rankdir: &str,
+        options: &DotOptions,
     ) -> OpmResult<String> {
         let inv_string = if inverted { "(inv)" } else { "" };
         let mut dot_string = format!(
             "  subgraph i{node_index} {{\n\tlabel=\"{name}{inv_string}\"\n\tfontsize=8\n\tcluster=true\n\t"
         );
-        dot_string += &self.graph.create_dot_string(rankdir)?;
+        dot_string += &self.graph.create_dot_string(options)?;
         Ok(dot_string)
     }
     /// Creates the dot format of the [`NodeGroup`] in its collapsed view
@@ -393,13 +581,18 @@ impl NodeGroup {
         name: &str,
         inverted: bool,
         ports: &OpticPorts,
-        rankdir: &str,
+        options: &DotOptions,
     ) -> String {
         let inv_string = if inverted { " (inv)" } else { "" };
         let node_name = format!("{name}{inv_string}");
         let mut dot_str = format!("\ti{node_index} [\n\t\tshape=plaintext\n");
         let mut indent_level = 2;
-        dot_str.push_str(&self.add_html_like_labels(&node_name, &mut indent_level, ports, rankdir));
+        dot_str.push_str(&self.add_html_like_labels(
+            &node_name,
+            &mut indent_level,
+            ports,
+            options.rankdir.as_str(),
+        ));
         dot_str
     }
     /// A helper function for the distances handover between to two `OpticGraph`s.
@@ -447,30 +640,34 @@ impl NodeGroup {
         Ok(analysis_report)
     }
     /// Returns the dot-file header of this [`NodeGroup`] graph.
-    fn add_dot_header(&self, rankdir: &str) -> String {
-        let mut dot_string = String::from("digraph {\n\tfontsize = 10;\n");
+    fn add_dot_header(&self, options: &DotOptions) -> String {
+        let fontname = &options.fontname;
+        let fontsize = options.fontsize;
+        let mut dot_string = format!("digraph {{\n\tfontsize = {fontsize};\n");
         let _ = writeln!(dot_string, "\tcompound = true;");
-        let _ = writeln!(dot_string, "\trankdir = \"{rankdir}\";");
+        let _ = writeln!(dot_string, "\trankdir = \"{}\";", options.rankdir);
         let _ = writeln!(dot_string, "\tlabel=\"{}\"", self.node_attr.name());
-        let _ = writeln!(dot_string, "\tfontname=\"Courier-monospace\"");
+        let _ = writeln!(dot_string, "\tfontname=\"{fontname}\"");
         let _ = writeln!(
             dot_string,
-            "\tnode [fontname=\"Courier-monospace\" fontsize = 10]"
+            "\tnode [fontname=\"{fontname}\" fontsize = {fontsize}]"
         );
         let _ = writeln!(
             dot_string,
-            "\tedge [fontname=\"Courier-monospace\" fontsize = 10]\n"
+            "\tedge [fontname=\"{fontname}\" fontsize = {fontsize}]\n"
         );
         dot_string
     }
     /// Export the optic graph, including ports, into the `dot` format to be used in combination with
     /// the [`graphviz`](https://graphviz.org/) software.
     ///
+    /// The layout direction and styling of the diagram is controlled by the given [`DotOptions`].
+    ///
     /// # Errors
     /// This function returns an error if nodes do not return a proper value for their `name` property.
-    pub fn toplevel_dot(&self, rankdir: &str) -> OpmResult<String> {
-        let mut dot_string = self.add_dot_header(rankdir);
-        dot_string += &self.graph.create_dot_string(rankdir)?;
+    pub fn toplevel_dot(&self, options: &DotOptions) -> OpmResult<String> {
+        let mut dot_string = self.add_dot_header(options);
+        dot_string += &self.graph.create_dot_string(options)?;
         Ok(dot_string)
     }
     /// Generate an SVG of the (top level) [`NodeGroup`] `dot` diagram.
@@ -534,6 +731,69 @@ impl NodeGroup {
         &self.accumulated_rays
     }
 
+    /// Returns the [`TraceStatistics`] of the last ray tracing analysis of this [`NodeGroup`].
+    ///
+    /// The statistics include the results of all subgroups.
+    #[must_use]
+    pub const fn trace_statistics(&self) -> &TraceStatistics {
+        &self.trace_statistics
+    }
+    /// Returns a mutable reference to the [`TraceStatistics`] of this [`NodeGroup`].
+    pub(crate) const fn trace_statistics_mut(&mut self) -> &mut TraceStatistics {
+        &mut self.trace_statistics
+    }
+    /// Returns the [`BeamCaustic`] of the last ray tracing analysis of this [`NodeGroup`].
+    ///
+    /// The caustic is only recorded if a sampling step is set in the
+    /// [`RayTraceConfig`](crate::analyzers::RayTraceConfig). It includes the rays leaving all subgroups.
+    #[must_use]
+    pub const fn beam_caustic(&self) -> Option<&BeamCaustic> {
+        self.beam_caustic.as_ref()
+    }
+    /// Returns the [`ApertureClipping`] of all surfaces of this [`NodeGroup`] (including subgroups) hit by light.
+    ///
+    /// Each entry contains the node name, the surface (port) name and the clipping accumulated during the last
+    /// analysis. Surfaces without an aperture are not listed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a node cannot be locked.
+    pub fn aperture_clipping(&self) -> OpmResult<Vec<(String, String, ApertureClipping)>> {
+        let mut clipping = Vec::new();
+        for node_ref in self.graph.nodes() {
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            if let Ok(group) = node.as_group_mut() {
+                clipping.append(&mut group.aperture_clipping()?);
+                continue;
+            }
+            // reference nodes share the surfaces of the referenced node
+            if node.node_type() == "reference" {
+                continue;
+            }
+            let name = node.name();
+            let ports = node.node_attr().ports();
+            for (port_name, surf) in ports
+                .ports(&PortType::Input)
+                .iter()
+                .chain(ports.ports(&PortType::Output))
+            {
+                let surf_clipping = surf.aperture_clipping();
+                if surf_clipping.clipped_fraction().is_some() {
+                    clipping.push((name.clone(), port_name.clone(), *surf_clipping));
+                }
+            }
+        }
+        Ok(clipping)
+    }
+    /// Add the [`Rays`] leaving this [`NodeGroup`] to its [`BeamCaustic`] (if recorded).
+    pub(crate) fn add_to_beam_caustic(&mut self, rays: &Rays) {
+        if let Some(beam_caustic) = &mut self.beam_caustic {
+            beam_caustic.add_rays(rays);
+        }
+    }
     /// add a ray bundle to the set of accumulated rays of this node group
     /// # Arguments
     /// - rays: pointer to ray bundle that should be included
@@ -633,6 +893,15 @@ impl OpticNode for NodeGroup {
             }
         }
         self.accumulated_rays = Vec::<HashMap<Uuid, Rays>>::new();
+        self.trace_statistics = TraceStatistics::default();
+        self.beam_caustic = None;
+    }
+    fn set_hit_map_level(&mut self, level: Option<usize>) {
+        for node in self.graph.nodes() {
+            if let Ok(mut node) = node.optical_ref.lock() {
+                node.set_hit_map_level(level);
+            }
+        }
     }
     fn get_optic_surface_mut(&mut self, _surf_name: &str) -> Option<&mut OpticSurface> {
         None
@@ -650,16 +919,16 @@ impl Dottable for NodeGroup {
         name: &str,
         inverted: bool,
         ports: &OpticPorts,
-        rankdir: &str,
+        options: &DotOptions,
     ) -> OpmResult<String> {
         let mut cloned_self = self.clone();
         if self.node_attr.inverted() {
             cloned_self.graph.invert_graph()?;
         }
         if self.expand_view()? {
-            cloned_self.to_dot_expanded_view(node_index, name, inverted, rankdir)
+            cloned_self.to_dot_expanded_view(node_index, name, inverted, options)
         } else {
-            Ok(cloned_self.to_dot_collapsed_view(node_index, name, inverted, ports, rankdir))
+            Ok(cloned_self.to_dot_collapsed_view(node_index, name, inverted, ports, options))
         }
     }
     fn node_color(&self) -> &'static str {
@@ -672,17 +941,25 @@ mod test {
     use super::*;
     use crate::{
         analyzers::{RayTraceConfig, energy::AnalysisEnergy, raytrace::AnalysisRayTrace},
-        joule,
+        degree, joule,
         light_result::LightResult,
-        lightdata::light_data_builder::LightDataBuilder,
+        lightdata::{energy_data_builder::EnergyDataBuilder, light_data_builder::LightDataBuilder},
         millimeter, nanometer,
-        nodes::{Dummy, EnergyMeter, Source, test_helper::test_helper::*},
+        nodes::{
+            Dummy, EnergyMeter, FilterType, IdealFilter, NodeReference, Source, Wedge,
+            test_helper::test_helper::*,
+        },
         optic_node::OpticNode,
+        position_distributions::Hexapolar,
         ray::Ray,
         rays::Rays,
+        refractive_index::RefrIndexSellmeier1,
         utils::geom_transformation::Isometry,
     };
+    use approx::assert_relative_eq;
+    use assert_matches::assert_matches;
     use num::Zero;
+    use uom::si::f64::Energy;
     #[test]
     fn default() {
         let mut node = NodeGroup::default();
@@ -704,6 +981,131 @@ mod test {
         assert_eq!(node.expand_view().unwrap(), false);
     }
     #[test]
+    fn alignment_wavelength_property() {
+        let mut node = NodeGroup::default();
+        assert_eq!(node.alignment_wavelength(), None);
+        assert!(
+            node.set_alignment_wavelength(Some(nanometer!(0.0)))
+                .is_err()
+        );
+        assert!(
+            node.set_alignment_wavelength(Some(nanometer!(f64::NAN)))
+                .is_err()
+        );
+        assert!(
+            node.set_alignment_wavelength(Some(nanometer!(-600.0)))
+                .is_err()
+        );
+        node.set_alignment_wavelength(Some(nanometer!(600.0)))
+            .unwrap();
+        assert_eq!(node.alignment_wavelength(), Some(nanometer!(600.0)));
+        node.set_alignment_wavelength(None).unwrap();
+        assert_eq!(node.alignment_wavelength(), None);
+    }
+    fn dispersive_scenery(alignment_wvl: Option<Length>) -> Isometry {
+        let mut scenery = NodeGroup::default();
+        scenery.set_alignment_wavelength(alignment_wvl).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap();
+        let mut src = Source::new("src", LightDataBuilder::Geometric(rays.into()));
+        src.set_isometry(Isometry::identity()).unwrap();
+        let i_src = scenery.add_node(src).unwrap();
+        let glass = RefrIndexSellmeier1::new(
+            6.14555251E-1,
+            6.56775017E-1,
+            1.02699346E+0,
+            1.45987884E-2,
+            2.87769588E-3,
+            1.07653051E+2,
+            nanometer!(300.0)..nanometer!(2000.0),
+        )
+        .unwrap();
+        let wedge = Wedge::new("wedge", millimeter!(10.0), degree!(20.0), &glass).unwrap();
+        let i_w = scenery.add_node(wedge).unwrap();
+        let i_d = scenery.add_node(Dummy::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_w, "input_1", millimeter!(10.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_w, "output_1", i_d, "input_1", millimeter!(100.0))
+            .unwrap();
+        AnalysisRayTrace::calc_node_positions(
+            &mut scenery,
+            LightResult::default(),
+            &RayTraceConfig::default(),
+        )
+        .unwrap();
+        scenery
+            .node(i_d)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .isometry()
+            .unwrap()
+    }
+    #[test]
+    fn alignment_wavelength_dispersive() {
+        let iso_src_wvl = dispersive_scenery(None);
+        let iso_same_wvl = dispersive_scenery(Some(nanometer!(1000.0)));
+        let iso_other_wvl = dispersive_scenery(Some(nanometer!(400.0)));
+        assert_eq!(iso_src_wvl.translation(), iso_same_wvl.translation());
+        assert!(iso_src_wvl.translation().y != iso_other_wvl.translation().y);
+    }
+    #[test]
+    fn global_isometry() {
+        let mut scenery = NodeGroup::default();
+        let mut src = Source::new(
+            "src",
+            LightDataBuilder::Geometric(
+                Rays::new_uniform_collimated(
+                    nanometer!(1000.0),
+                    joule!(1.0),
+                    &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+                )
+                .unwrap()
+                .into(),
+            ),
+        );
+        src.set_isometry(Isometry::identity()).unwrap();
+        let i_src = scenery.add_node(src).unwrap();
+        let mut group = NodeGroup::new("sub group");
+        let i_d1 = group.add_node(Dummy::default()).unwrap();
+        let i_d2 = group.add_node(Dummy::default()).unwrap();
+        group
+            .connect_nodes(i_d1, "output_1", i_d2, "input_1", millimeter!(20.0))
+            .unwrap();
+        group.map_input_port(i_d1, "input_1", "input_1").unwrap();
+        let i_g = scenery.add_node(group).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_g, "input_1", millimeter!(10.0))
+            .unwrap();
+        assert!(scenery.global_isometry(Uuid::new_v4()).is_err());
+        assert!(scenery.global_isometry(i_d2).is_err());
+        assert_eq!(
+            scenery.global_isometry(i_src).unwrap().translation(),
+            millimeter!(0.0, 0.0, 0.0)
+        );
+        AnalysisRayTrace::calc_node_positions(
+            &mut scenery,
+            LightResult::default(),
+            &RayTraceConfig::default(),
+        )
+        .unwrap();
+        assert_relative_eq!(
+            scenery.global_isometry(i_d1).unwrap().translation().z.value,
+            0.01
+        );
+        assert_relative_eq!(
+            scenery.global_isometry(i_d2).unwrap().translation().z.value,
+            0.03
+        );
+    }
+    #[test]
     fn new() {
         let node = NodeGroup::new("test");
         assert_eq!(node.name(), "test");
@@ -826,4 +1228,311 @@ mod test {
             assert!(false)
         }
     }
+    fn energy_behind_filter(filter_enabled: bool, ray_trace: bool) -> Energy {
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(1.0)).unwrap(),
+        );
+        let mut scenery = NodeGroup::default();
+        let light_data_builder = if ray_trace {
+            LightDataBuilder::Geometric(rays.into())
+        } else {
+            LightDataBuilder::Energy(EnergyDataBuilder::LaserLines(
+                vec![(nanometer!(1053.0), joule!(1.0))],
+                nanometer!(1.0),
+            ))
+        };
+        let mut source = Source::new("src", light_data_builder);
+        source.set_isometry(Isometry::identity()).unwrap();
+        let i_s = scenery.add_node(source).unwrap();
+        let mut filter = IdealFilter::new("filter", &FilterType::Constant(0.5)).unwrap();
+        filter.set_enabled(filter_enabled);
+        let i_f = scenery.add_node(filter).unwrap();
+        let i_e = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(i_s, "output_1", i_f, "input_1", millimeter!(10.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_f, "output_1", i_e, "input_1", millimeter!(10.0))
+            .unwrap();
+        if ray_trace {
+            let config = RayTraceConfig::default();
+            AnalysisRayTrace::calc_node_positions(&mut scenery, LightResult::default(), &config)
+                .unwrap();
+            AnalysisRayTrace::analyze(&mut scenery, LightResult::default(), &config).unwrap();
+        } else {
+            AnalysisEnergy::analyze(&mut scenery, LightResult::default()).unwrap();
+        }
+        let report = scenery
+            .node(i_e)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .node_report("")
+            .unwrap();
+        let Ok(Proptype::Energy(energy)) = report.properties().get("Energy") else {
+            panic!("energy not found");
+        };
+        *energy
+    }
+    #[test]
+    fn analyze_disabled_node() {
+        assert_relative_eq!(energy_behind_filter(true, false).value, 0.5);
+        assert_relative_eq!(energy_behind_filter(false, false).value, 1.0);
+        assert_relative_eq!(energy_behind_filter(true, true).value, 0.5);
+        assert_relative_eq!(energy_behind_filter(false, true).value, 1.0);
+    }
+    #[test]
+    fn round_trip_ports_property() {
+        let mut group = NodeGroup::default();
+        assert_eq!(group.round_trip_ports(), None);
+        let i_d = group.add_node(Dummy::default()).unwrap();
+        assert!(
+            group
+                .set_round_trip_ports(Some(("output_1", "input_1")))
+                .is_err()
+        );
+        group.map_input_port(i_d, "input_1", "input_1").unwrap();
+        assert!(
+            group
+                .set_round_trip_ports(Some(("output_1", "input_1")))
+                .is_err()
+        );
+        group.map_output_port(i_d, "output_1", "output_1").unwrap();
+        group
+            .set_round_trip_ports(Some(("output_1", "input_1")))
+            .unwrap();
+        assert_eq!(
+            group.round_trip_ports(),
+            Some(("output_1".to_string(), "input_1".to_string()))
+        );
+        group.set_round_trip_ports(None).unwrap();
+        assert_eq!(group.round_trip_ports(), None);
+    }
+    fn energy_after_round_trips(round_trips: usize, feedback: bool) -> Energy {
+        let mut group = NodeGroup::default();
+        let mut filter = IdealFilter::new("filter", &FilterType::Constant(0.5)).unwrap();
+        filter.set_isometry(Isometry::identity()).unwrap();
+        let i_f = group.add_node(filter).unwrap();
+        group.map_input_port(i_f, "input_1", "input_1").unwrap();
+        group.map_output_port(i_f, "output_1", "output_1").unwrap();
+        if feedback {
+            group
+                .set_round_trip_ports(Some(("output_1", "input_1")))
+                .unwrap();
+        }
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(nanometer!(1053.0), joule!(1.0)).unwrap());
+        let input = LightResult::from([("input_1".into(), LightData::Geometric(rays))]);
+        let mut config = RayTraceConfig::default();
+        config.set_round_trips(round_trips).unwrap();
+        let output = AnalysisRayTrace::analyze(&mut group, input, &config).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        rays.total_energy()
+    }
+    #[test]
+    fn analyze_round_trips() {
+        assert_relative_eq!(energy_after_round_trips(1, true).value, 0.5);
+        assert_relative_eq!(energy_after_round_trips(3, true).value, 0.125);
+        assert_relative_eq!(energy_after_round_trips(3, false).value, 0.5);
+    }
+    #[test]
+    fn analyze_round_trips_detector() {
+        let mut group = NodeGroup::default();
+        let mut filter = IdealFilter::new("filter", &FilterType::Constant(0.5)).unwrap();
+        filter.set_isometry(Isometry::identity()).unwrap();
+        let i_f = group.add_node(filter).unwrap();
+        let mut meter = EnergyMeter::default();
+        meter.set_isometry(Isometry::identity()).unwrap();
+        let i_m = group.add_node(meter).unwrap();
+        group
+            .connect_nodes(i_f, "output_1", i_m, "input_1", Length::zero())
+            .unwrap();
+        group.map_input_port(i_f, "input_1", "input_1").unwrap();
+        group.map_output_port(i_m, "output_1", "output_1").unwrap();
+        group
+            .set_round_trip_ports(Some(("output_1", "input_1")))
+            .unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(nanometer!(1053.0), joule!(1.0)).unwrap());
+        let input = LightResult::from([("input_1".into(), LightData::Geometric(rays))]);
+        let mut config = RayTraceConfig::default();
+        config.set_round_trips(3).unwrap();
+        let output = AnalysisRayTrace::analyze(&mut group, input, &config).unwrap();
+        // the detector only forwards the light of the current round trip
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        assert_relative_eq!(rays.total_energy().value, 0.125);
+        // the detector accumulates the light of all round trips
+        let meter = group.node(i_m).unwrap();
+        let meter = meter.optical_ref.lock().unwrap();
+        let report = meter.node_report("").unwrap();
+        let Ok(Proptype::Energy(energy)) = report.properties().get("Energy") else {
+            panic!("could not get energy");
+        };
+        assert_relative_eq!(energy.value, 0.875);
+        // each round trip is stored on its own hit map level
+        let hit_map = meter.hit_maps().remove("input_1").unwrap();
+        assert_eq!(hit_map.hit_map().len(), 3);
+        assert_relative_eq!(
+            hit_map
+                .get_merged_rays_hit_map()
+                .unwrap()
+                .total_energy()
+                .value,
+            0.875
+        );
+    }
+    #[test]
+    fn dot_disabled_node() {
+        let mut scenery = NodeGroup::default();
+        let i_d1 = scenery.add_node(Dummy::default()).unwrap();
+        let mut dummy = Dummy::default();
+        dummy.set_enabled(false);
+        let i_d2 = scenery.add_node(dummy).unwrap();
+        scenery
+            .connect_nodes(i_d1, "output_1", i_d2, "input_1", Length::zero())
+            .unwrap();
+        let dot_string = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        assert!(dot_string.contains("fontcolor=gray"));
+        assert!(dot_string.contains("STYLE=\"ROUNDED,DASHED\""));
+        assert!(dot_string.contains("style=dashed]"));
+    }
+    #[test]
+    fn dot_port_and_data_labels() {
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1000.0), joule!(1.0)).unwrap(),
+        );
+        let mut scenery = NodeGroup::default();
+        let i_s = scenery
+            .add_node(Source::new("src", LightDataBuilder::Geometric(rays.into())))
+            .unwrap();
+        let mut em = EnergyMeter::default();
+        em.set_isometry(Isometry::identity()).unwrap();
+        let i_e = scenery.add_node(em).unwrap();
+        scenery
+            .connect_nodes(i_s, "output_1", i_e, "input_1", Length::zero())
+            .unwrap();
+        let options = DotOptions {
+            port_labels: true,
+            data_labels: true,
+            ..DotOptions::default()
+        };
+        let dot_string = scenery.toplevel_dot(&DotOptions::default()).unwrap();
+        assert!(!dot_string.contains("taillabel"));
+        // no analysis performed yet
+        let dot_string = scenery.toplevel_dot(&options).unwrap();
+        assert!(dot_string.contains("taillabel=\"output_1\", headlabel=\"input_1\""));
+        assert!(!dot_string.contains("rays"));
+        AnalysisRayTrace::analyze(
+            &mut scenery,
+            LightResult::default(),
+            &RayTraceConfig::default(),
+        )
+        .unwrap();
+        let dot_string = scenery.toplevel_dot(&options).unwrap();
+        assert!(dot_string.contains("\\n1 rays, E = 1.000 J, λ = 1.000 μm"));
+    }
+    #[test]
+    fn duplicate_node() {
+        let mut scenery = NodeGroup::default();
+        assert!(scenery.duplicate_node(Uuid::new_v4()).is_err());
+        let i_d = scenery.add_node(Dummy::new("dummy")).unwrap();
+        let i_r = scenery
+            .add_node(NodeReference::from_node(&scenery.node(i_d).unwrap()))
+            .unwrap();
+        let i_copy = scenery.duplicate_node(i_d).unwrap();
+        assert_ne!(i_copy, i_d);
+        assert_eq!(scenery.nr_of_nodes(), 3);
+        assert!(scenery.connections().is_empty());
+        let original = scenery.node(i_d).unwrap();
+        let copy = scenery.node(i_copy).unwrap();
+        assert!(!Arc::ptr_eq(&original.optical_ref, &copy.optical_ref));
+        assert_eq!(copy.optical_ref.lock().unwrap().name(), "dummy");
+        copy.optical_ref
+            .lock()
+            .unwrap()
+            .node_attr_mut()
+            .set_name("copy");
+        assert_eq!(original.optical_ref.lock().unwrap().name(), "dummy");
+        // a copied reference node still refers to the original node
+        let i_ref_copy = scenery.duplicate_node(i_r).unwrap();
+        let ref_copy = scenery.node(i_ref_copy).unwrap();
+        let ref_copy = ref_copy.optical_ref.lock().unwrap();
+        assert_matches!(
+            ref_copy.properties().get("reference id").unwrap(),
+            Proptype::Uuid(id) if *id == i_d
+        );
+        assert_eq!(ref_copy.name(), "ref (dummy)");
+    }
+    #[test]
+    fn duplicate_group() {
+        let mut group = NodeGroup::new("group");
+        let i_d1 = group.add_node(Dummy::default()).unwrap();
+        let i_d2 = group.add_node(Dummy::default()).unwrap();
+        let i_r = group
+            .add_node(NodeReference::from_node(&group.node(i_d1).unwrap()))
+            .unwrap();
+        group
+            .connect_nodes(i_d1, "output_1", i_d2, "input_1", millimeter!(10.0))
+            .unwrap();
+        group.map_input_port(i_d1, "input_1", "input_1").unwrap();
+        let mut scenery = NodeGroup::default();
+        let i_g = scenery.add_node(group).unwrap();
+        let i_copy = scenery.duplicate_node(i_g).unwrap();
+        assert_ne!(i_copy, i_g);
+        let copy = scenery.node(i_copy).unwrap();
+        let mut copy = copy.optical_ref.lock().unwrap();
+        let group_copy = copy.as_group_mut().unwrap();
+        let sub_node_ids: Vec<Uuid> = group_copy.nodes().iter().map(|n| n.uuid()).collect();
+        assert_eq!(sub_node_ids.len(), 3);
+        for id in [i_d1, i_d2, i_r] {
+            assert!(!sub_node_ids.contains(&id));
+        }
+        let connections = group_copy.connections();
+        assert_eq!(connections.len(), 1);
+        let new_d1 = connections[0].0;
+        assert!(sub_node_ids.contains(&new_d1));
+        assert_eq!(connections[0].4, millimeter!(10.0));
+        assert_eq!(
+            group_copy
+                .graph()
+                .port_map(&PortType::Input)
+                .get("input_1")
+                .unwrap()
+                .0,
+            new_d1
+        );
+        let ref_copy = group_copy
+            .nodes()
+            .into_iter()
+            .find(|n| n.optical_ref.lock().unwrap().node_type() == "reference")
+            .unwrap()
+            .clone();
+        assert_matches!(
+            ref_copy
+                .optical_ref
+                .lock()
+                .unwrap()
+                .properties()
+                .get("reference id")
+                .unwrap(),
+            Proptype::Uuid(id) if *id == new_d1
+        );
+        // reference is resolved
+        assert!(
+            !ref_copy
+                .optical_ref
+                .lock()
+                .unwrap()
+                .ports()
+                .names(&PortType::Input)
+                .is_empty()
+        );
+    }
 }
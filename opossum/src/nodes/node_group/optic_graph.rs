@@ -1,13 +1,14 @@
 #![warn(missing_docs)]
 use crate::{
     analyzers::{Analyzable, energy::AnalysisEnergy},
+    dottable::{DotOptions, EdgeStyle, disabled_node_dot},
     error::{OpmResult, OpossumError},
     light_flow::LightFlow,
-    light_result::LightResult,
+    light_result::{LightResult, bypass},
     lightdata::LightData,
     optic_node::OpticNode,
     optic_ports::PortType,
-    optic_ref::OpticRef,
+    optic_ref::{LegacyOpticRef, OpticRef},
     optic_scenery_rsc::SceneryResources,
     port_map::PortMap,
     properties::{Proptype, proptype::format_quantity},
@@ -18,7 +19,7 @@ use petgraph::{
     Directed, Direction,
     algo::{connected_components, is_cyclic_directed, toposort},
     graph::{DiGraph, EdgeIndex, Edges, NodeIndex},
-    visit::EdgeRef,
+    visit::{Dfs, EdgeRef},
 };
 use serde::{
     Deserialize, Serialize,
@@ -27,15 +28,21 @@ use serde::{
 };
 use std::fmt::Write as _;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     sync::{Arc, Mutex},
 };
 use uom::si::{f64::Length, length::meter};
 use uuid::Uuid;
+/// Connection between two nodes: (source node, source port, target node, target port, distance)
 pub type ConnectionInfo = (Uuid, String, Uuid, String, Length);
 
 /// Data structure representing an optical graph
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(
+    feature = "json_schema",
+    derive(schemars::JsonSchema),
+    schemars(with = "OpticGraphLayout")
+)]
 pub struct OpticGraph {
     g: DiGraph<OpticRef, LightFlow>,
     input_port_map: PortMap,
@@ -132,6 +139,44 @@ impl OpticGraph {
         }
         Ok(nodes_deleted)
     }
+    /// Duplicate the node with the given [`Uuid`] within this [`OpticGraph`].
+    ///
+    /// The copy is completely independent of the original node. For a [`NodeGroup`](crate::nodes::NodeGroup), all
+    /// sub-nodes are copied recursively. All copied nodes get new [`Uuid`]s. References to nodes within the copied
+    /// subtree (e.g. reference nodes or port mappings of a group) are redirected to the respective copies. The new node
+    /// is not connected to any other node. This function returns the [`Uuid`] of the new node.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// - the node with the given [`Uuid`] does not exist.
+    /// - the graph is set as `inverted`.
+    /// - the node could not be copied.
+    pub fn duplicate_node(&mut self, node_id: Uuid) -> OpmResult<Uuid> {
+        let new_node = self.node(node_id)?.deep_copy(|_| Uuid::new_v4())?;
+        let new_node_id = new_node.uuid();
+        new_node.update_global_config(self.global_confg.clone());
+        self.add_node_ref(new_node.clone())?;
+        // a copied reference node still refers to the original node
+        assign_reference_to_ref_node(&new_node, self)?;
+        Ok(new_node_id)
+    }
+    /// Returns a copy of this [`OpticGraph`] without the given (toplevel) nodes.
+    ///
+    /// All connections and port mappings of the removed nodes are removed as well. The remaining nodes are shared
+    /// with this graph.
+    #[must_use]
+    pub fn without_nodes(&self, node_ids: &[Uuid]) -> Self {
+        let mut graph = self.clone();
+        graph
+            .g
+            .retain_nodes(|g, idx| !node_ids.contains(&g[idx].uuid()));
+        for node_id in node_ids {
+            graph.input_port_map.remove_all_from_uuid(*node_id);
+            graph.output_port_map.remove_all_from_uuid(*node_id);
+        }
+        graph
+    }
     /// Return the first [`NodeId`] with the given [`Uuid`] in this [`OpticGraph`].
     ///
     /// This also includes reference nodes referring to the given [`Uuid`]. This function returns
@@ -169,7 +214,9 @@ impl OpticGraph {
     /// This function will return an error if
     ///   - the [`NodeIndex`] of source or target node does not exist in the [`OpticGraph`]
     ///   - a port name of the source or target node does not exist
-    ///   - if a node/port combination was already connected earlier
+    ///   - if a node/port combination was already connected earlier ([`OpossumError::PortAlreadyConnected`]). An input port accepts
+    ///     only a single connection. An output port may only be connected to more than one input port (fan-out) if the source node
+    ///     type allows it (see [`OpticNode::allows_output_fan_out`]).
     ///   - the connection of the nodes would form a loop in the network.
     ///   - the given geometric distance between the nodes is not finite.
     ///
@@ -244,18 +291,28 @@ impl OpticGraph {
                 target_ports
             )));
         }
-        if self.src_node_port_exists(src_node, src_port) {
-            return Err(OpossumError::OpticScenery(format!(
-                "src node <{}> with port <{}> is already connected",
+        let fan_out = source.optical_ref.lock().unwrap().allows_output_fan_out();
+        if !fan_out
+            && let Some((connected_node, connected_port)) =
+                self.connected_target_of_src_port(src_node, src_port)
+        {
+            return Err(OpossumError::PortAlreadyConnected(format!(
+                "output port <{}> of node {} is already connected to input port <{}> of node {}. An output port of this node type can only be connected once",
+                src_port,
                 source.optical_ref.lock().unwrap(),
-                src_port
+                connected_port,
+                connected_node
             )));
         }
-        if self.target_node_port_exists(target_node, target_port) {
-            return Err(OpossumError::OpticScenery(format!(
-                "target node {} with port <{}> is already connected",
+        if let Some((connected_node, connected_port)) =
+            self.connected_src_of_target_port(target_node, target_port)
+        {
+            return Err(OpossumError::PortAlreadyConnected(format!(
+                "input port <{}> of node {} is already connected to output port <{}> of node {}. An input port accepts only one connection",
+                target_port,
                 target.optical_ref.lock().unwrap(),
-                target_port
+                connected_port,
+                connected_node
             )));
         }
         let src_name = source.optical_ref.lock().unwrap().name();
@@ -278,7 +335,8 @@ impl OpticGraph {
     ///
     /// This function deletes the connection between two nodes, referenced by the [`Uuid`] of the
     /// source node and the name of the source port. **Note**: It's not necessary to specify the target node,
-    /// as the connection is uniquely identified by the source node and the source port.
+    /// as the connection is uniquely identified by the source node and the source port. For nodes allowing
+    /// output fan-out, all connections of the given source port are removed.
     ///
     /// # Errors
     ///
@@ -289,27 +347,33 @@ impl OpticGraph {
         let src_idx = self.node_idx_by_uuid(src_id).ok_or_else(|| {
             OpossumError::OpticScenery("node with given index does not exist".into())
         })?;
-        let edges = self.g.edges_directed(src_idx, Direction::Outgoing);
-        let edge_ref = edges
-            .into_iter()
+        let mut edge_idxs: Vec<EdgeIndex> = self
+            .g
+            .edges_directed(src_idx, Direction::Outgoing)
             .filter(|idx| idx.weight().src_port() == src_port)
-            .last();
-        if let Some(edge_ref) = edge_ref {
-            self.g.remove_edge(edge_ref.id());
-            Ok(())
-        } else {
+            .map(|edge_ref| edge_ref.id())
+            .collect();
+        if edge_idxs.is_empty() {
             let node_ref = self.node(src_id)?;
             let node_info = node_ref
                 .optical_ref
                 .lock()
                 .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
-            Err(OpossumError::OpticScenery(format!(
+            return Err(OpossumError::OpticScenery(format!(
                 "source node {node_info} with port <{src_port}> is not connected"
-            )))
+            )));
         }
+        // removing an edge invalidates the index of the last edge, hence remove the highest indices first
+        edge_idxs.sort_unstable_by(|a, b| b.cmp(a));
+        for edge_idx in edge_idxs {
+            self.g.remove_edge(edge_idx);
+        }
+        Ok(())
     }
     /// Update the distance of an already existing connection.
     ///
+    /// For nodes allowing output fan-out, the distance of all connections of the given source port is updated.
+    ///
     /// # Errors
     ///
     /// This function will return an error if the connection does not exist.
@@ -322,28 +386,28 @@ impl OpticGraph {
         let src_idx = self.node_idx_by_uuid(src_id).ok_or_else(|| {
             OpossumError::OpticScenery("node with given index does not exist".into())
         })?;
-        let edges = self.g.edges_directed(src_idx, Direction::Outgoing);
-        let edge_ref = edges
-            .into_iter()
+        let edge_idxs: Vec<EdgeIndex> = self
+            .g
+            .edges_directed(src_idx, Direction::Outgoing)
             .filter(|idx| idx.weight().src_port() == src_port)
-            .last();
-        if let Some(edge_ref) = edge_ref {
-            let edge_id = edge_ref.id();
-            let edge = self.g.edge_weight_mut(edge_id);
-            if let Some(edge) = edge {
-                edge.set_distance(distance);
-            }
-            Ok(())
-        } else {
+            .map(|edge_ref| edge_ref.id())
+            .collect();
+        if edge_idxs.is_empty() {
             let node_ref = self.node(src_id)?;
             let node_info = node_ref
                 .optical_ref
                 .lock()
                 .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
-            Err(OpossumError::OpticScenery(format!(
+            return Err(OpossumError::OpticScenery(format!(
                 "source node {node_info} with port <{src_port}> is not connected"
-            )))
+            )));
+        }
+        for edge_idx in edge_idxs {
+            if let Some(edge) = self.g.edge_weight_mut(edge_idx) {
+                edge.set_distance(distance);
+            }
         }
+        Ok(())
     }
     /// Returns a reference to the input port map of this [`OpticGraph`].
     #[must_use]
@@ -514,22 +578,72 @@ impl OpticGraph {
         let neighbors = self.g.neighbors_undirected(idx);
         neighbors.count() == 0 && !self.input_port_map.contains_node(node_id)
     }
+    /// Returns the [`Uuid`]s of all nodes, which cannot be reached from any of the given start nodes.
+    ///
+    /// The connections are only followed in their (forward) direction. The start nodes themselves are considered
+    /// reachable. Unknown start node [`Uuid`]s are ignored.
+    #[must_use]
+    pub fn unreachable_nodes(&self, start_nodes: &[Uuid]) -> Vec<Uuid> {
+        let mut reachable = HashSet::new();
+        for start in start_nodes
+            .iter()
+            .filter_map(|node_id| self.node_idx_by_uuid(*node_id))
+        {
+            let mut dfs = Dfs::new(&self.g, start);
+            while let Some(idx) = dfs.next(&self.g) {
+                reachable.insert(idx);
+            }
+        }
+        self.g
+            .node_indices()
+            .filter(|idx| !reachable.contains(idx))
+            .filter_map(|idx| self.g.node_weight(idx).map(OpticRef::uuid))
+            .collect()
+    }
     /// Update reference to global config for each node in this [`OpticGraph`].
-    /// This function is needed after deserialization.
+    /// This function is needed after deserialization. Nodes added later on also receive this global config.
     pub fn update_global_config(&mut self, global_conf: &Option<Arc<Mutex<SceneryResources>>>) {
+        self.global_confg.clone_from(global_conf);
         for node in self.g.node_weights_mut() {
             node.update_global_config(global_conf.clone());
         }
     }
-    fn src_node_port_exists(&self, src_node: NodeIndex, src_port: &str) -> bool {
+    /// Returns the node description and the input port name connected to the given output port (if any).
+    fn connected_target_of_src_port(
+        &self,
+        src_node: NodeIndex,
+        src_port: &str,
+    ) -> Option<(String, String)> {
         self.g
             .edges_directed(src_node, petgraph::Direction::Outgoing)
-            .any(|e| e.weight().src_port() == src_port)
+            .find(|e| e.weight().src_port() == src_port)
+            .map(|e| {
+                (
+                    self.node_description(e.target()),
+                    e.weight().target_port().to_string(),
+                )
+            })
     }
-    fn target_node_port_exists(&self, target_node: NodeIndex, target_port: &str) -> bool {
+    /// Returns the node description and the output port name connected to the given input port (if any).
+    fn connected_src_of_target_port(
+        &self,
+        target_node: NodeIndex,
+        target_port: &str,
+    ) -> Option<(String, String)> {
         self.g
             .edges_directed(target_node, petgraph::Direction::Incoming)
-            .any(|e| e.weight().target_port() == target_port)
+            .find(|e| e.weight().target_port() == target_port)
+            .map(|e| {
+                (
+                    self.node_description(e.source()),
+                    e.weight().src_port().to_string(),
+                )
+            })
+    }
+    fn node_description(&self, idx: NodeIndex) -> String {
+        self.g.node_weight(idx).map_or_else(String::new, |node| {
+            format!("{}", node.optical_ref.lock().expect("Mutex lock failed"))
+        })
     }
     /// Returns [`OpticRef`] with the given [`Uuid`].
     ///
@@ -701,20 +815,25 @@ impl OpticGraph {
                 );
             } else {
                 let incoming_edges = self.get_incoming(node_id, incoming_data);
-                let node_name = format!(
-                    "{}",
-                    node.lock()
-                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
-                );
-                let outgoing_edges = AnalysisEnergy::analyze(
-                    &mut *node
+                let (node_name, ports, enabled) = {
+                    let node = node
                         .lock()
-                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
-                    incoming_edges,
-                )
-                .map_err(|e| {
-                    OpossumError::Analysis(format!("analysis of node {node_name} failed: {e}"))
-                })?;
+                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+                    (format!("{node}"), node.ports(), node.enabled())
+                };
+                let outgoing_edges = if enabled {
+                    AnalysisEnergy::analyze(
+                        &mut *node
+                            .lock()
+                            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?,
+                        incoming_edges,
+                    )
+                    .map_err(|e| {
+                        OpossumError::Analysis(format!("analysis of node {node_name} failed: {e}"))
+                    })?
+                } else {
+                    bypass(&incoming_edges, &ports)
+                };
                 // If node is sink node, rewrite port names according to output mapping
                 if self.is_output_node(idx) {
                     let portmap = if self.is_inverted {
@@ -814,23 +933,20 @@ impl OpticGraph {
     /// Sets the outgoing edge data of this [`OpticGraph`].
     /// Returns true if data has been passed on, false otherwise
     pub fn set_outgoing_edge_data(&mut self, idx: NodeIndex, port: &str, data: &LightData) -> bool {
-        let edges = self.g.edges_directed(idx, Direction::Outgoing);
-        let edge_ref = edges
-            .into_iter()
+        // an output port of a node allowing fan-out might be connected to several input ports
+        let edge_idxs: Vec<EdgeIndex> = self
+            .g
+            .edges_directed(idx, Direction::Outgoing)
             .filter(|idx| idx.weight().src_port() == port)
-            .last();
-        if let Some(edge_ref) = edge_ref {
-            let edge_idx = edge_ref.id();
-            let light = self.g.edge_weight_mut(edge_idx);
-            if let Some(light) = light {
+            .map(|edge_ref| edge_ref.id())
+            .collect();
+        for edge_idx in &edge_idxs {
+            if let Some(light) = self.g.edge_weight_mut(*edge_idx) {
                 light.set_data(Some(data.clone()));
             }
-            true
-        }
-        // else outgoing edge not connected -> data dropped
-        else {
-            false
         }
+        // if outgoing edge not connected -> data dropped
+        !edge_idxs.is_empty()
     }
     fn edges_directed(&self, idx: NodeIndex, dir: Direction) -> Edges<'_, LightFlow, Directed> {
         self.g.edges_directed(idx, dir)
@@ -840,8 +956,24 @@ impl OpticGraph {
     /// This functions changes all directions of node connections and inverts the nodes itself.
     /// # Errors
     ///
-    /// This function will return an error if one tries to invert a graph containing a non-invertable node (eg. source).
+    /// This function will return an error if
+    ///   - one tries to invert a graph containing a non-invertable node (eg. source).
+    ///   - an output port is connected to more than one input port (fan-out), since the inverted connections would
+    ///     feed a single input port.
     pub fn invert_graph(&mut self) -> OpmResult<()> {
+        for idx in self.g.node_indices() {
+            let mut src_ports = Vec::new();
+            for edge in self.g.edges_directed(idx, Direction::Outgoing) {
+                let src_port = edge.weight().src_port();
+                if src_ports.contains(&src_port) {
+                    return Err(OpossumError::OpticGroup(format!(
+                        "group cannot be inverted because output port <{src_port}> of node {} is connected to more than one input port",
+                        self.node_description(idx)
+                    )));
+                }
+                src_ports.push(src_port);
+            }
+        }
         for node in self.g.node_weights_mut() {
             let node_to_be_inverted = !node
                 .optical_ref
@@ -949,9 +1081,7 @@ impl OpticGraph {
     /// # Errors
     ///
     /// This function will return an error if .
-    pub fn create_dot_string(&self, rankdir: &str) -> OpmResult<String> {
-        //check direction
-        let rankdir = if rankdir == "LR" { "LR" } else { "TB" };
+    pub fn create_dot_string(&self, options: &DotOptions) -> OpmResult<String> {
         let mut dot_string = String::default();
         let sorted = self.topologically_sorted()?;
         for idx in &sorted {
@@ -964,7 +1094,12 @@ impl OpticGraph {
             let inverted = node.inverted();
             let ports = node.ports();
             let uuid = node.node_attr().uuid().as_simple().to_string();
-            dot_string += &node.to_dot(&uuid, &node_name, inverted, &ports, rankdir)?;
+            let node_dot = node.to_dot(&uuid, &node_name, inverted, &ports, options)?;
+            if node.enabled() {
+                dot_string += &node_dot;
+            } else {
+                dot_string += &disabled_node_dot(&node_dot, node.node_color());
+            }
         }
         for edge_idx in self.g.edge_indices() {
             let light: &LightFlow = self.edge_by_idx(edge_idx)?;
@@ -977,11 +1112,37 @@ impl OpticGraph {
 
             let src_edge_str = self.create_node_edge_str(end_nodes.0, light.src_port())?;
             let target_edge_str = self.create_node_edge_str(end_nodes.1, light.target_port())?;
-
+            let mut disabled_end_node = false;
+            for end_node in [end_nodes.0, end_nodes.1] {
+                let node = self.node_by_idx(end_node)?.optical_ref;
+                disabled_end_node |= !node
+                    .lock()
+                    .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
+                    .enabled();
+            }
+            let edge_style = if disabled_end_node {
+                EdgeStyle::Dashed.attribute()
+            } else {
+                options.edge_style.attribute()
+            };
+            let mut label = format_quantity(meter, dist);
+            if options.data_labels
+                && let Some(summary) = light.data().and_then(LightData::summary)
+            {
+                label = format!("{}\\n{summary}", label.trim());
+            }
+            let port_labels = if options.port_labels {
+                format!(
+                    ", taillabel=\"{}\", headlabel=\"{}\"",
+                    light.src_port(),
+                    light.target_port()
+                )
+            } else {
+                String::new()
+            };
             let _ = writeln!(
                 dot_string,
-                "  {src_edge_str} -> {target_edge_str} [label=\"{}\"]",
-                format_quantity(meter, dist)
+                "  {src_edge_str} -> {target_edge_str} [label=\"{label}\"{port_labels}{edge_style}]"
             );
         }
         dot_string.push_str("}\n");
@@ -1071,6 +1232,18 @@ impl Serialize for OpticGraph {
     }
 }
 
+/// Serialized layout of an [`OpticGraph`] as written by its [`Serialize`] implementation.
+#[cfg(feature = "json_schema")]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "OpticGraph")]
+#[allow(dead_code)]
+struct OpticGraphLayout {
+    nodes: Vec<OpticRef>,
+    /// connections: (source node, source port, target node, target port, distance in meter)
+    edges: Vec<(Uuid, String, Uuid, String, f64)>,
+    input_map: Option<PortMap>,
+    output_map: Option<PortMap>,
+}
 impl<'de> Deserialize<'de> for OpticGraph {
     #[allow(clippy::too_many_lines)]
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
@@ -1130,7 +1303,6 @@ impl<'de> Deserialize<'de> for OpticGraph {
             where
                 A: MapAccess<'de>,
             {
-                let mut g = OpticGraph::default();
                 let mut nodes: Option<Vec<OpticRef>> = None;
                 let mut edges: Option<Vec<ConnectionInfo>> = None;
                 let mut input_map: Option<PortMap> = None;
@@ -1165,35 +1337,70 @@ impl<'de> Deserialize<'de> for OpticGraph {
                 }
                 let nodes = nodes.ok_or_else(|| de::Error::missing_field("nodes"))?;
                 let edges = edges.ok_or_else(|| de::Error::missing_field("edges"))?;
-                for node in &nodes {
-                    g.g.add_node(node.clone());
-                }
-                for node_ref in &nodes {
-                    // assign references to ref nodes (if any)
-                    assign_reference_to_ref_node(node_ref, &g)
-                        .map_err(|e| de::Error::custom(e.to_string()))?;
-                }
-                for edge in &edges {
-                    g.connect_nodes(edge.0, &edge.1, edge.2, &edge.3, edge.4)
-                        .map_err(|e| {
-                            de::Error::custom(format!("connecting OpticGraph nodes failed: {e}"))
-                        })?;
-                }
-                if let Some(input_map) = input_map {
-                    // todo: do sanity check
-                    g.input_port_map = input_map;
-                }
-                if let Some(output_map) = output_map {
-                    // todo: do sanity check
-                    g.output_port_map = output_map;
-                }
-                Ok(g)
+                OpticGraph::from_parts(
+                    nodes,
+                    &edges,
+                    input_map.unwrap_or_default(),
+                    output_map.unwrap_or_default(),
+                )
+                .map_err(|e| de::Error::custom(e.to_string()))
             }
         }
         deserializer.deserialize_struct("OpticGraph", FIELDS, OpticGraphVisitor)
     }
 }
 
+impl OpticGraph {
+    /// Create an [`OpticGraph`] from its (deserialized) nodes, connections and port mappings.
+    fn from_parts(
+        nodes: Vec<OpticRef>,
+        edges: &[ConnectionInfo],
+        input_map: PortMap,
+        output_map: PortMap,
+    ) -> OpmResult<Self> {
+        let mut g = Self::default();
+        for node in &nodes {
+            g.g.add_node(node.clone());
+        }
+        for node_ref in &nodes {
+            // assign references to ref nodes (if any)
+            assign_reference_to_ref_node(node_ref, &g)?;
+        }
+        for edge in edges {
+            g.connect_nodes(edge.0, &edge.1, edge.2, &edge.3, edge.4)
+                .map_err(|e| {
+                    OpossumError::OpticGroup(format!("connecting OpticGraph nodes failed: {e}"))
+                })?;
+        }
+        // todo: do sanity check
+        g.input_port_map = input_map;
+        g.output_port_map = output_map;
+        Ok(g)
+    }
+}
+/// Layout of an [`OpticGraph`] in `.opm` files of version `0` (see [`LegacyOpticRef`]).
+#[derive(Deserialize)]
+pub(crate) struct LegacyOpticGraph {
+    nodes: Vec<LegacyOpticRef>,
+    edges: Vec<ConnectionInfo>,
+    #[serde(default)]
+    input_map: PortMap,
+    #[serde(default)]
+    output_map: PortMap,
+}
+impl TryFrom<LegacyOpticGraph> for OpticGraph {
+    type Error = OpossumError;
+
+    fn try_from(legacy: LegacyOpticGraph) -> OpmResult<Self> {
+        let nodes = legacy
+            .nodes
+            .into_iter()
+            .map(OpticRef::try_from)
+            .collect::<OpmResult<Vec<_>>>()?;
+        Self::from_parts(nodes, &legacy.edges, legacy.input_map, legacy.output_map)
+    }
+}
+
 fn assign_reference_to_ref_node(node_ref: &OpticRef, graph: &OpticGraph) -> OpmResult<()> {
     if let Ok(ref_node) = node_ref
         .optical_ref
@@ -1237,6 +1444,7 @@ mod test {
         utils::{geom_transformation::Isometry, test_helper::test_helper::check_logs},
     };
     use approx::assert_abs_diff_eq;
+    use assert_matches::assert_matches;
     use num::Zero;
     #[test]
     fn default() {
@@ -1357,6 +1565,50 @@ mod test {
         );
     }
     #[test]
+    fn connect_nodes_already_connected_error() {
+        let mut graph = OpticGraph::default();
+        let n1 = graph.add_node(Dummy::new("first")).unwrap();
+        let n2 = graph.add_node(Dummy::new("second")).unwrap();
+        let n3 = graph.add_node(Dummy::new("third")).unwrap();
+        graph
+            .connect_nodes(n1, "output_1", n2, "input_1", Length::zero())
+            .unwrap();
+        let err = graph
+            .connect_nodes(n3, "output_1", n2, "input_1", Length::zero())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OpossumError::PortAlreadyConnected(
+                "input port <input_1> of node 'second' (dummy) is already connected to output port <output_1> of node 'first' (dummy). An input port accepts only one connection".into()
+            )
+        );
+        let err = graph
+            .connect_nodes(n1, "output_1", n3, "input_1", Length::zero())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            OpossumError::PortAlreadyConnected(
+                "output port <output_1> of node 'first' (dummy) is already connected to input port <input_1> of node 'second' (dummy). An output port of this node type can only be connected once".into()
+            )
+        );
+        assert_eq!(graph.g.edge_count(), 1);
+    }
+    #[test]
+    fn connect_nodes_source_no_fan_out() {
+        let mut graph = OpticGraph::default();
+        let src = graph.add_node(Source::default()).unwrap();
+        let n1 = graph.add_node(Dummy::new("first")).unwrap();
+        let n2 = graph.add_node(Dummy::new("second")).unwrap();
+        graph
+            .connect_nodes(src, "output_1", n1, "input_1", Length::zero())
+            .unwrap();
+        assert_matches!(
+            graph.connect_nodes(src, "output_1", n2, "input_1", Length::zero()),
+            Err(OpossumError::PortAlreadyConnected(_))
+        );
+        assert_eq!(graph.g.edge_count(), 1);
+    }
+    #[test]
     fn connect_nodes_loop_error() {
         let mut graph = OpticGraph::default();
         let n1 = graph.add_node(Dummy::default()).unwrap();
@@ -1595,6 +1847,29 @@ mod test {
         assert_eq!(graph.is_single_tree(), true);
     }
     #[test]
+    fn unreachable_nodes() {
+        let mut graph = OpticGraph::default();
+        let n1 = graph.add_node(Dummy::default()).unwrap();
+        let n2 = graph.add_node(Dummy::default()).unwrap();
+        let n3 = graph.add_node(Dummy::default()).unwrap();
+        let n4 = graph.add_node(Dummy::default()).unwrap();
+        graph
+            .connect_nodes(n1, "output_1", n2, "input_1", Length::zero())
+            .unwrap();
+        graph
+            .connect_nodes(n3, "output_1", n4, "input_1", Length::zero())
+            .unwrap();
+        assert_eq!(graph.unreachable_nodes(&[]).len(), 4);
+        let mut unreachable = graph.unreachable_nodes(&[n1]);
+        unreachable.sort();
+        let mut expected = vec![n3, n4];
+        expected.sort();
+        assert_eq!(unreachable, expected);
+        assert_eq!(graph.unreachable_nodes(&[n2]).len(), 3);
+        assert!(graph.unreachable_nodes(&[n1, n3]).is_empty());
+        assert_eq!(graph.unreachable_nodes(&[Uuid::new_v4()]).len(), 4);
+    }
+    #[test]
     fn analyze_empty() {
         let mut node = OpticGraph::default();
         let output = node.analyze_energy(&LightResult::default()).unwrap();
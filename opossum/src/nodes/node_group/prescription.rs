@@ -0,0 +1,746 @@
+#![warn(missing_docs)]
+//! Export of a sequential [`NodeGroup`] as optical prescription (surface table) and import from a CSV prescription.
+use super::NodeGroup;
+use crate::{
+    aperture::Aperture,
+    error::{OpmResult, OpossumError},
+    nodes::{Dummy, Lens, ThinMirror},
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    refractive_index::{RefrIndexCatalog, RefractiveIndexType},
+};
+use csv::{ReaderBuilder, Trim};
+use log::warn;
+use std::{collections::HashSet, fmt::Write as _, fs, fs::File, path::Path};
+use uom::si::{
+    f64::Length,
+    length::{millimeter, nanometer},
+};
+use uuid::Uuid;
+
+/// Node types, which are exported as plane (dummy) surfaces.
+const PLANE_NODE_TYPES: [&str; 8] = [
+    "source",
+    "dummy",
+    "energy meter",
+    "spot diagram",
+    "wavefront monitor",
+    "fluence detector",
+    "ray propagation",
+    "spectrometer",
+];
+
+/// A single surface of a [`Prescription`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrescriptionSurface {
+    /// surface type (e.g. `STANDARD` or `PARAXIAL`)
+    pub surface_type: String,
+    /// comment (usually the name of the corresponding node)
+    pub comment: String,
+    /// radius of curvature (infinite for a flat surface)
+    pub radius: Length,
+    /// distance to the next surface
+    pub thickness: Length,
+    /// material behind the surface (`None` for the ambient medium)
+    pub glass: Option<String>,
+    /// semi-diameter of the surface (if defined by a circular aperture)
+    pub semi_diameter: Option<Length>,
+}
+impl PrescriptionSurface {
+    fn plane(comment: String) -> Self {
+        Self {
+            surface_type: "STANDARD".into(),
+            comment,
+            radius: Length::new::<millimeter>(f64::INFINITY),
+            thickness: Length::default(),
+            glass: None,
+            semi_diameter: None,
+        }
+    }
+}
+/// Surface-by-surface prescription of a sequential optical system.
+///
+/// The prescription is created by [`NodeGroup::prescription`]. It can be exported as a text table similar to the
+/// prescription listings of commercial optical design software.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prescription {
+    name: String,
+    wavelength: Length,
+    surfaces: Vec<PrescriptionSurface>,
+    issues: Vec<String>,
+}
+impl Prescription {
+    /// Returns the surfaces of this [`Prescription`] (starting with the source).
+    #[must_use]
+    pub fn surfaces(&self) -> &[PrescriptionSurface] {
+        &self.surfaces
+    }
+    /// Returns the wavelength used for determining the refractive indices of this [`Prescription`].
+    #[must_use]
+    pub const fn wavelength(&self) -> Length {
+        self.wavelength
+    }
+    /// Returns the issues (e.g. unsupported nodes or branching beam paths) found while creating this [`Prescription`].
+    #[must_use]
+    pub fn issues(&self) -> &[String] {
+        &self.issues
+    }
+    /// Returns this [`Prescription`] as text table.
+    ///
+    /// All lengths are given in millimeters. The first surface (`OBJ`) corresponds to the source, the last surface
+    /// (`IMA`) to the last node of the beam path. The thicknesses are given along the (unfolded) beam path. Issues are
+    /// listed as comments (lines starting with `!`) at the end of the table.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let format_length = |length: Length| {
+            if length.is_infinite() {
+                "Infinity".to_string()
+            } else {
+                format!("{:.6}", length.get::<millimeter>())
+            }
+        };
+        let mut text = format!(
+            "! OPOSSUM prescription: {}\n! wavelength: {:.3} nm\n! units: mm\n",
+            self.name,
+            self.wavelength.get::<nanometer>()
+        );
+        let _ = writeln!(
+            text,
+            "{:<6}{:<10}{:<16}{:<16}{:<16}{:<16}COMMENT",
+            "SURF", "TYPE", "RADIUS", "THICKNESS", "GLASS", "SEMI-DIA"
+        );
+        for (i, surface) in self.surfaces.iter().enumerate() {
+            let label = if i == 0 {
+                "OBJ".to_string()
+            } else if i + 1 == self.surfaces.len() {
+                "IMA".to_string()
+            } else {
+                i.to_string()
+            };
+            let _ = writeln!(
+                text,
+                "{label:<6}{:<10}{:<16}{:<16}{:<16}{:<16}{}",
+                surface.surface_type,
+                format_length(surface.radius),
+                format_length(surface.thickness),
+                surface.glass.as_deref().unwrap_or("-"),
+                surface
+                    .semi_diameter
+                    .map_or_else(|| "-".into(), format_length),
+                surface.comment
+            );
+        }
+        for issue in &self.issues {
+            let _ = writeln!(text, "! {issue}");
+        }
+        text
+    }
+    /// Write this [`Prescription`] as text table (see [`to_text`](Self::to_text)) to a file.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written.
+    pub fn to_file(&self, path: &Path) -> OpmResult<()> {
+        fs::write(path, self.to_text())
+            .map_err(|e| OpossumError::Other(format!("could not write prescription file: {e}")))
+    }
+}
+
+/// Returns the radius of a circular aperture on the first port of the given type (if any).
+fn semi_diameter<N: OpticNode + ?Sized>(node: &N, port_type: &PortType) -> Option<Length> {
+    let port_name = node.ports().names(port_type).into_iter().next()?;
+    if let Some(Aperture::BinaryCircle(circle)) = node.ports().aperture(port_type, &port_name) {
+        Some(*circle.radius())
+    } else {
+        None
+    }
+}
+/// Returns the glass name of a refractive index (catalog name or model glass given by its refractive index).
+fn glass_name(index: &RefractiveIndexType, wavelength: Length) -> OpmResult<String> {
+    if let RefractiveIndexType::Catalog(catalog) = index {
+        Ok(catalog.name().to_string())
+    } else {
+        Ok(format!("{:.6}", index.get_refractive_index(wavelength)?))
+    }
+}
+fn length_property<N: OpticNode + ?Sized>(node: &N, name: &str) -> OpmResult<Length> {
+    if let Ok(Proptype::Length(length)) = node.node_attr().get_property(name) {
+        Ok(*length)
+    } else {
+        Err(OpossumError::Other(format!(
+            "property '{name}' of node '{}' not found",
+            node.name()
+        )))
+    }
+}
+/// Convert a single node into its prescription surfaces.
+fn node_surfaces<N: OpticNode + ?Sized>(
+    node: &N,
+    wavelength: Length,
+    issues: &mut Vec<String>,
+) -> OpmResult<Vec<PrescriptionSurface>> {
+    let name = node.name();
+    let node_type = node.node_type();
+    if !node.enabled() {
+        return Ok(vec![PrescriptionSurface::plane(format!(
+            "{name} (disabled)"
+        ))]);
+    }
+    match node_type.as_str() {
+        "lens" => {
+            let mut front = length_property(node, "front curvature")?;
+            let mut rear = length_property(node, "rear curvature")?;
+            let Ok(Proptype::RefractiveIndex(index)) =
+                node.node_attr().get_property("refractive index")
+            else {
+                return Err(OpossumError::Other(format!(
+                    "property 'refractive index' of node '{name}' not found"
+                )));
+            };
+            for profile in ["front aspheric profile", "rear aspheric profile"] {
+                if let Ok(Proptype::AsphericProfile(Some(_))) =
+                    node.node_attr().get_property(profile)
+                {
+                    issues.push(format!(
+                        "{profile} of node '{name}' is not exported (spherical base surface only)"
+                    ));
+                }
+            }
+            let mut front_semi_diameter = semi_diameter(node, &PortType::Input);
+            let mut rear_semi_diameter = semi_diameter(node, &PortType::Output);
+            if node.inverted() {
+                (front, rear) = (-rear, -front);
+                (front_semi_diameter, rear_semi_diameter) =
+                    (rear_semi_diameter, front_semi_diameter);
+            }
+            Ok(vec![
+                PrescriptionSurface {
+                    surface_type: "STANDARD".into(),
+                    comment: name,
+                    radius: front,
+                    thickness: length_property(node, "center thickness")?,
+                    glass: Some(glass_name(index, wavelength)?),
+                    semi_diameter: front_semi_diameter,
+                },
+                PrescriptionSurface {
+                    radius: rear,
+                    semi_diameter: rear_semi_diameter,
+                    ..PrescriptionSurface::plane(String::new())
+                },
+            ])
+        }
+        "mirror" => Ok(vec![PrescriptionSurface {
+            radius: length_property(node, "curvature")?,
+            glass: Some("MIRROR".into()),
+            semi_diameter: semi_diameter(node, &PortType::Input),
+            ..PrescriptionSurface::plane(name)
+        }]),
+        "paraxial surface" => {
+            let focal_length = length_property(node, "focal length")?;
+            Ok(vec![PrescriptionSurface {
+                surface_type: "PARAXIAL".into(),
+                semi_diameter: semi_diameter(node, &PortType::Input),
+                ..PrescriptionSurface::plane(format!(
+                    "{name} (f = {:.6} mm)",
+                    focal_length.get::<millimeter>()
+                ))
+            }])
+        }
+        node_type if PLANE_NODE_TYPES.contains(&node_type) => Ok(vec![PrescriptionSurface {
+            semi_diameter: semi_diameter(node, &PortType::Input),
+            ..PrescriptionSurface::plane(name)
+        }]),
+        _ => {
+            issues.push(format!(
+                "node '{name}' ({node_type}) is not supported and exported as plane surface"
+            ));
+            Ok(vec![PrescriptionSurface::plane(format!(
+                "{name} (unsupported: {node_type})"
+            ))])
+        }
+    }
+}
+
+impl NodeGroup {
+    /// Create a surface-by-surface [`Prescription`] of this (sequential) [`NodeGroup`].
+    ///
+    /// Starting at the (single) source, the beam path follows the first connected output port of each node. Lenses,
+    /// mirrors and paraxial surfaces are converted into their respective surfaces while detectors and dummy nodes are
+    /// exported as plane surfaces. The refractive indices of non-catalog glasses are evaluated at the given wavelength.
+    ///
+    /// Unsupported nodes (e.g. beam splitters, prisms or nested groups) are exported as plane surfaces. These as well as
+    /// other losses of information (e.g. branching beam paths) are listed in the [`Prescription::issues`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the group does not contain exactly one source.
+    ///   - a refractive index cannot be evaluated at the given wavelength.
+    pub fn prescription(&self, wavelength: Length) -> OpmResult<Prescription> {
+        let mut sources = Vec::new();
+        for node_ref in self.nodes() {
+            let node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            if node.node_type() == "source" {
+                sources.push(node.node_attr().uuid());
+            }
+        }
+        if sources.len() != 1 {
+            return Err(OpossumError::Other(
+                "prescription export requires exactly one source in the scenery".into(),
+            ));
+        }
+        let connections = self.connections();
+        let mut surfaces = Vec::new();
+        let mut issues = Vec::new();
+        let mut visited = HashSet::<Uuid>::new();
+        let mut node_id = sources[0];
+        loop {
+            visited.insert(node_id);
+            let node_ref = self.node(node_id)?;
+            let node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+            surfaces.append(&mut node_surfaces(&*node, wavelength, &mut issues)?);
+            let outgoing: Vec<_> = node
+                .ports()
+                .names(&PortType::Output)
+                .iter()
+                .filter_map(|port| {
+                    connections
+                        .iter()
+                        .find(|c| c.0 == node_id && c.1 == *port)
+                        .cloned()
+                })
+                .collect();
+            let Some((_, port, target, _, distance)) = outgoing.first().cloned() else {
+                break;
+            };
+            if outgoing.len() > 1 {
+                issues.push(format!(
+                    "beam path branches at node '{}'. Only output port '{port}' is followed",
+                    node.name()
+                ));
+            }
+            drop(node);
+            if let Some(last) = surfaces.last_mut() {
+                last.thickness = distance;
+            }
+            if visited.contains(&target) {
+                issues.push("beam path contains a loop. Export stopped".into());
+                break;
+            }
+            node_id = target;
+        }
+        for issue in &issues {
+            warn!("{issue}");
+        }
+        Ok(Prescription {
+            name: self.node_attr().name(),
+            wavelength,
+            surfaces,
+            issues,
+        })
+    }
+    /// Create a sequential [`NodeGroup`] from a CSV prescription table.
+    ///
+    /// The file must contain a header with the columns `radius`, `thickness` (both in mm) and `material`. Each following
+    /// row describes a single surface. The radius may be given as `inf` (or left empty) for a flat surface. The thickness
+    /// denotes the distance to the next surface. The surfaces are converted into nodes as follows:
+    ///   - a surface with a glass followed by a surface without material forms a [`Lens`] (the glass is resolved using
+    ///     the glass catalog, see [`RefrIndexCatalog`]).
+    ///   - a surface with the material `MIRROR` forms a [`ThinMirror`].
+    ///   - a flat surface without material forms a [`Dummy`] node.
+    ///
+    /// The nodes are connected in order. The input port of the first node and the output port of the last node are
+    /// mapped to `input_1` and `output_1` of the group.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the file cannot be read or does not contain the required columns.
+    ///   - the file does not contain any surface.
+    ///   - a row contains invalid values or an unknown glass. The error message names the offending row.
+    ///   - a row cannot be converted into a node (e.g. cemented surfaces or curved surfaces without material).
+    pub fn from_prescription_csv(name: &str, path: &Path) -> OpmResult<Self> {
+        let rows = read_prescription_csv(path)?;
+        if rows.is_empty() {
+            return Err(OpossumError::Other(
+                "no surfaces found in prescription file".into(),
+            ));
+        }
+        let mut group = Self::new(name);
+        let mut previous: Option<(Uuid, Length)> = None;
+        let mut nr_of_lenses = 0;
+        let mut nr_of_mirrors = 0;
+        let mut nr_of_surfaces = 0;
+        let mut rows = rows.into_iter().peekable();
+        while let Some(row) = rows.next() {
+            let (node_id, distance) = match row.material {
+                CsvMaterial::Glass(glass) => {
+                    let Some(rear) = rows.next() else {
+                        return Err(OpossumError::Other(format!(
+                            "row {}: lens surface without rear surface",
+                            row.row
+                        )));
+                    };
+                    if !matches!(rear.material, CsvMaterial::Ambient) {
+                        return Err(OpossumError::Other(format!(
+                            "row {}: cemented surfaces are not supported",
+                            rear.row
+                        )));
+                    }
+                    nr_of_lenses += 1;
+                    let lens = Lens::new(
+                        &format!("lens {nr_of_lenses}"),
+                        row.radius,
+                        rear.radius,
+                        row.thickness,
+                        &glass,
+                    )
+                    .map_err(|e| OpossumError::Other(format!("row {}: {e}", row.row)))?;
+                    (group.add_node(lens)?, rear.thickness)
+                }
+                CsvMaterial::Mirror => {
+                    nr_of_mirrors += 1;
+                    let mirror = ThinMirror::new(&format!("mirror {nr_of_mirrors}"))
+                        .with_curvature(row.radius)
+                        .map_err(|e| OpossumError::Other(format!("row {}: {e}", row.row)))?;
+                    (group.add_node(mirror)?, row.thickness)
+                }
+                CsvMaterial::Ambient => {
+                    if row.radius.is_finite() {
+                        return Err(OpossumError::Other(format!(
+                            "row {}: curved surface without material is not supported",
+                            row.row
+                        )));
+                    }
+                    nr_of_surfaces += 1;
+                    let dummy = Dummy::new(&format!("surface {nr_of_surfaces}"));
+                    (group.add_node(dummy)?, row.thickness)
+                }
+            };
+            if let Some((previous_id, previous_distance)) = previous {
+                group.connect_nodes(
+                    previous_id,
+                    "output_1",
+                    node_id,
+                    "input_1",
+                    previous_distance,
+                )?;
+            } else {
+                group.map_input_port(node_id, "input_1", "input_1")?;
+            }
+            previous = Some((node_id, distance));
+        }
+        if let Some((last_id, _)) = previous {
+            group.map_output_port(last_id, "output_1", "output_1")?;
+        }
+        Ok(group)
+    }
+}
+
+/// Material of a surface row of a CSV prescription.
+enum CsvMaterial {
+    Ambient,
+    Mirror,
+    Glass(RefrIndexCatalog),
+}
+/// A single surface row of a CSV prescription.
+struct CsvSurface {
+    /// row number (1-based, without header)
+    row: usize,
+    radius: Length,
+    thickness: Length,
+    material: CsvMaterial,
+}
+/// Read all surface rows of a CSV prescription.
+fn read_prescription_csv(path: &Path) -> OpmResult<Vec<CsvSurface>> {
+    let file = File::open(path)
+        .map_err(|e| OpossumError::Other(format!("could not open prescription file: {e}")))?;
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(file);
+    let headers = reader
+        .headers()
+        .map_err(|e| OpossumError::Other(format!("could not read prescription file: {e}")))?
+        .clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                OpossumError::Other(format!("column '{name}' not found in prescription file"))
+            })
+    };
+    let (radius_col, thickness_col, material_col) =
+        (column("radius")?, column("thickness")?, column("material")?);
+    let mut surfaces = Vec::new();
+    for (idx, record) in reader.records().enumerate() {
+        let row = idx + 1;
+        let record = record.map_err(|e| OpossumError::Other(format!("row {row}: {e}")))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let radius = record.get(radius_col).unwrap_or_default();
+        let radius = if radius.is_empty()
+            || radius.eq_ignore_ascii_case("inf")
+            || radius.eq_ignore_ascii_case("infinity")
+        {
+            f64::INFINITY
+        } else {
+            radius
+                .parse::<f64>()
+                .map_err(|_| OpossumError::Other(format!("row {row}: invalid radius '{radius}'")))?
+        };
+        let thickness = record.get(thickness_col).unwrap_or_default();
+        let thickness = thickness
+            .parse::<f64>()
+            .ok()
+            .filter(|t| t.is_finite() && t.is_sign_positive())
+            .ok_or_else(|| {
+                OpossumError::Other(format!("row {row}: invalid thickness '{thickness}'"))
+            })?;
+        let material = record.get(material_col).unwrap_or_default();
+        let material = if material.is_empty() || material.eq_ignore_ascii_case("air") {
+            CsvMaterial::Ambient
+        } else if material.eq_ignore_ascii_case("mirror") {
+            CsvMaterial::Mirror
+        } else {
+            CsvMaterial::Glass(RefrIndexCatalog::new(material).map_err(|_| {
+                OpossumError::Other(format!("row {row}: unknown glass '{material}'"))
+            })?)
+        };
+        surfaces.push(CsvSurface {
+            row,
+            radius: Length::new::<millimeter>(radius),
+            thickness: Length::new::<millimeter>(thickness),
+            material,
+        });
+    }
+    Ok(surfaces)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        joule, millimeter, nanometer,
+        nodes::{
+            BeamSplitter, EnergyMeter, Lens, NodeGroup, ParaxialSurface, ThinMirror,
+            round_collimated_ray_source,
+        },
+        optic_node::OpticNode,
+        refractive_index::{RefrIndexCatalog, RefrIndexConst},
+    };
+    use std::{io::Write, path::Path};
+    use tempfile::NamedTempFile;
+    use uom::si::f64::Length;
+
+    #[test]
+    fn from_prescription_csv() {
+        let group = NodeGroup::from_prescription_csv(
+            "doublet",
+            Path::new("files_for_testing/prescription/two_lenses.csv"),
+        )
+        .unwrap();
+        assert_eq!(group.name(), "doublet");
+        // two lenses separated by a dummy surface
+        assert_eq!(group.nr_of_nodes(), 3);
+        assert_eq!(group.connections().len(), 2);
+        let distances: Vec<Length> = group.connections().iter().map(|c| c.4).collect();
+        assert!(distances.contains(&millimeter!(50.0)));
+        assert!(distances.contains(&millimeter!(20.0)));
+        let prescription = group
+            .prescription(nanometer!(1053.0))
+            .unwrap_err()
+            .to_string();
+        // no source in imported group
+        assert!(prescription.contains("exactly one source"));
+    }
+    fn csv_file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+    #[test]
+    fn from_prescription_csv_errors() {
+        assert!(NodeGroup::from_prescription_csv("", Path::new("wrong_path.csv")).is_err());
+        let file = csv_file("radius,thickness\n100,5\n");
+        assert!(NodeGroup::from_prescription_csv("", file.path()).is_err());
+        let file = csv_file("radius,thickness,material\n");
+        assert!(NodeGroup::from_prescription_csv("", file.path()).is_err());
+        let file = csv_file("radius,thickness,material\n100,5,N-BK7\n-100,10,\n50,5,UNOBTAINIUM\n");
+        let error = NodeGroup::from_prescription_csv("", file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("row 3: unknown glass 'UNOBTAINIUM'"));
+        let file = csv_file("radius,thickness,material\n100,abc,N-BK7\n");
+        let error = NodeGroup::from_prescription_csv("", file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("row 1: invalid thickness"));
+        let file = csv_file("radius,thickness,material\n100,5,N-BK7\n");
+        let error = NodeGroup::from_prescription_csv("", file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("row 1: lens surface without rear surface"));
+        let file = csv_file("radius,thickness,material\n100,5,N-BK7\n-100,2,N-SF6\n");
+        let error = NodeGroup::from_prescription_csv("", file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("row 2: cemented surfaces"));
+        let file = csv_file("radius,thickness,material\n100,5,\n");
+        let error = NodeGroup::from_prescription_csv("", file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("row 1: curved surface"));
+    }
+    #[test]
+    fn from_prescription_csv_mirror() {
+        let file = csv_file("Radius,Thickness,Material\ninf,10,\n-200,100,MIRROR\n");
+        let group = NodeGroup::from_prescription_csv("", file.path()).unwrap();
+        assert_eq!(group.nr_of_nodes(), 2);
+        assert_eq!(group.connections()[0].4, millimeter!(10.0));
+    }
+    #[test]
+    fn no_source() {
+        let scenery = NodeGroup::default();
+        assert!(scenery.prescription(nanometer!(1000.0)).is_err());
+    }
+    #[test]
+    fn lens_and_mirror() {
+        let mut scenery = NodeGroup::new("test system");
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(5.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let i_l = scenery
+            .add_node(
+                Lens::new(
+                    "lens",
+                    millimeter!(100.0),
+                    millimeter!(f64::INFINITY),
+                    millimeter!(5.0),
+                    &RefrIndexCatalog::new("N-BK7").unwrap(),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let i_m = scenery
+            .add_node(
+                ThinMirror::new("mirror")
+                    .with_curvature(millimeter!(-200.0))
+                    .unwrap(),
+            )
+            .unwrap();
+        let i_p = scenery
+            .add_node(ParaxialSurface::new("paraxial", millimeter!(50.0)).unwrap())
+            .unwrap();
+        let i_em = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_l, "input_1", millimeter!(20.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_l, "output_1", i_m, "input_1", millimeter!(100.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_m, "output_1", i_p, "input_1", millimeter!(30.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_p, "output_1", i_em, "input_1", millimeter!(50.0))
+            .unwrap();
+        let prescription = scenery.prescription(nanometer!(1053.0)).unwrap();
+        assert!(prescription.issues().is_empty());
+        let surfaces = prescription.surfaces();
+        assert_eq!(surfaces.len(), 6);
+        assert_eq!(surfaces[0].thickness, millimeter!(20.0));
+        assert_eq!(surfaces[1].radius, millimeter!(100.0));
+        assert_eq!(surfaces[1].thickness, millimeter!(5.0));
+        assert_eq!(surfaces[1].glass.as_deref(), Some("N-BK7"));
+        assert!(surfaces[2].radius.is_infinite());
+        assert_eq!(surfaces[2].thickness, millimeter!(100.0));
+        assert!(surfaces[2].glass.is_none());
+        assert_eq!(surfaces[3].radius, millimeter!(-200.0));
+        assert_eq!(surfaces[3].glass.as_deref(), Some("MIRROR"));
+        assert_eq!(surfaces[4].surface_type, "PARAXIAL");
+        assert_eq!(surfaces[5].thickness, millimeter!(0.0));
+        let text = prescription.to_text();
+        assert!(text.starts_with("! OPOSSUM prescription: test system\n"));
+        assert_eq!(text.lines().count(), 4 + 6);
+        assert!(text.contains("\nOBJ "));
+        assert!(text.contains("\nIMA "));
+        assert!(text.contains("N-BK7"));
+        assert!(text.contains("Infinity"));
+        let file = NamedTempFile::new().unwrap();
+        prescription.to_file(file.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), text);
+    }
+    #[test]
+    fn inverted_lens() {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(5.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let mut lens = Lens::new(
+            "lens",
+            millimeter!(100.0),
+            millimeter!(-50.0),
+            millimeter!(5.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        lens.set_inverted(true).unwrap();
+        let i_l = scenery.add_node(lens).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_l, "output_1", millimeter!(20.0))
+            .unwrap();
+        let prescription = scenery.prescription(nanometer!(1053.0)).unwrap();
+        let surfaces = prescription.surfaces();
+        assert_eq!(surfaces[1].radius, millimeter!(50.0));
+        assert_eq!(surfaces[2].radius, millimeter!(-100.0));
+        assert_eq!(surfaces[1].glass.as_deref(), Some("1.500000"));
+    }
+    #[test]
+    fn unsupported_nodes() {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(5.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let i_bs = scenery.add_node(BeamSplitter::default()).unwrap();
+        let i_em1 = scenery.add_node(EnergyMeter::default()).unwrap();
+        let i_em2 = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_bs, "input_1", millimeter!(20.0))
+            .unwrap();
+        scenery
+            .connect_nodes(
+                i_bs,
+                "out1_trans1_refl2",
+                i_em1,
+                "input_1",
+                millimeter!(10.0),
+            )
+            .unwrap();
+        scenery
+            .connect_nodes(
+                i_bs,
+                "out2_trans2_refl1",
+                i_em2,
+                "input_1",
+                millimeter!(10.0),
+            )
+            .unwrap();
+        let prescription = scenery.prescription(nanometer!(1053.0)).unwrap();
+        assert_eq!(prescription.surfaces().len(), 3);
+        assert_eq!(prescription.issues().len(), 2);
+        assert!(
+            prescription
+                .to_text()
+                .contains("! node 'beam splitter' (beam splitter)")
+        );
+    }
+}
@@ -0,0 +1,365 @@
+#![warn(missing_docs)]
+//! Validation ("dry run") of a [`NodeGroup`] without performing an analysis.
+use super::NodeGroup;
+use crate::{
+    error::{OpmResult, OpossumError},
+    lightdata::light_data_builder::LightDataBuilder,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uuid::Uuid;
+
+/// Category of a [`Warning`] found during the validation of a scenery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarningKind {
+    /// The graph of a group consists of several unconnected sub-graphs.
+    UnconnectedGraph,
+    /// The graph of a group contains a cycle.
+    Cycle,
+    /// A node is not connected to any other node.
+    StaleNode,
+    /// A node has input ports but does not receive any light (none of its inputs is connected).
+    UnconnectedInput,
+    /// A connection or port mapping refers to a non-existing node or port.
+    InvalidPort,
+    /// A source does not define any light data.
+    MissingSourceData,
+    /// The light data of a source is not compatible with an analyzer.
+    LightDataMismatch,
+    /// The scenery does not contain any source.
+    NoSource,
+    /// A node cannot be reached by light from any source.
+    UnreachableNode,
+    /// The document does not define any analyzer.
+    NoAnalyzer,
+}
+/// A diagnostic message found during the validation of a scenery.
+///
+/// See [`OpmDocument::validate`](crate::OpmDocument::validate) for details.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warning {
+    kind: WarningKind,
+    node_id: Option<Uuid>,
+    message: String,
+}
+impl Warning {
+    /// Creates a new [`Warning`] of the given kind, optionally referring to the node with the given [`Uuid`].
+    #[must_use]
+    pub fn new(kind: WarningKind, node_id: Option<Uuid>, message: &str) -> Self {
+        Self {
+            kind,
+            node_id,
+            message: message.to_owned(),
+        }
+    }
+    /// Returns the kind of this [`Warning`].
+    #[must_use]
+    pub const fn kind(&self) -> WarningKind {
+        self.kind
+    }
+    /// Returns the [`Uuid`] of the node this [`Warning`] refers to (if any).
+    #[must_use]
+    pub const fn node_id(&self) -> Option<Uuid> {
+        self.node_id
+    }
+    /// Returns the message of this [`Warning`].
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+impl NodeGroup {
+    /// Validate this [`NodeGroup`] without performing an analysis.
+    ///
+    /// This function walks through the graph of this group (and all subgroups) and collects all findings, which would
+    /// lead to an incomplete or failing analysis:
+    ///   - unconnected sub-graphs and cycles
+    ///   - stale (completely unconnected) nodes
+    ///   - nodes with input ports not receiving any light
+    ///   - connections or port mappings referring to non-existing ports
+    ///   - sources without light data
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the internal node mutexes could not be locked.
+    pub fn validate(&self) -> OpmResult<Vec<Warning>> {
+        let mut warnings = Vec::new();
+        let group_info = format!("'{}' ({})", self.name(), self.node_type());
+        if self.graph.node_count() > 1 && !self.graph.is_single_tree() {
+            warnings.push(Warning::new(
+                WarningKind::UnconnectedGraph,
+                Some(self.node_attr.uuid()),
+                &format!("group {group_info} contains unconnected sub-graphs"),
+            ));
+        }
+        if self.graph.topologically_sorted().is_err() {
+            warnings.push(Warning::new(
+                WarningKind::Cycle,
+                Some(self.node_attr.uuid()),
+                &format!("group {group_info} contains a cycle"),
+            ));
+        }
+        let connections = self.graph.connections();
+        let input_map = self.graph.port_map(&PortType::Input);
+        for node_ref in self.graph.nodes() {
+            let node_id = node_ref.uuid();
+            // must be determined before locking the node since it accesses all nodes of the graph
+            let is_stale = self.graph.is_stale_node(node_id);
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            let node_info = format!("{node}");
+            let ports = node.ports();
+            for (src_id, src_port, target_id, target_port, _) in &connections {
+                if *src_id == node_id && !ports.names(&PortType::Output).contains(src_port) {
+                    warnings.push(Warning::new(
+                        WarningKind::InvalidPort,
+                        Some(node_id),
+                        &format!(
+                            "connection from non-existing output port <{src_port}> of node {node_info}"
+                        ),
+                    ));
+                }
+                if *target_id == node_id && !ports.names(&PortType::Input).contains(target_port) {
+                    warnings.push(Warning::new(
+                        WarningKind::InvalidPort,
+                        Some(node_id),
+                        &format!(
+                            "connection to non-existing input port <{target_port}> of node {node_info}"
+                        ),
+                    ));
+                }
+            }
+            if is_stale {
+                if self.graph.node_count() > 1 {
+                    warnings.push(Warning::new(
+                        WarningKind::StaleNode,
+                        Some(node_id),
+                        &format!("node {node_info} is not connected to any other node"),
+                    ));
+                }
+            } else if node.node_type() != "source"
+                && !ports.names(&PortType::Input).is_empty()
+                && !connections.iter().any(|c| c.2 == node_id)
+                && !input_map.contains_node(node_id)
+            {
+                warnings.push(Warning::new(
+                    WarningKind::UnconnectedInput,
+                    Some(node_id),
+                    &format!(
+                        "node {node_info} does not receive any light (no input port connected)"
+                    ),
+                ));
+            }
+            if node.node_type() == "source"
+                && !matches!(
+                    node.node_attr().get_property("light data"),
+                    Ok(Proptype::LightDataBuilder(Some(_)))
+                )
+            {
+                warnings.push(Warning::new(
+                    WarningKind::MissingSourceData,
+                    Some(node_id),
+                    &format!("source {node_info} does not define any light data"),
+                ));
+            }
+            if let Ok(group) = node.as_group_mut() {
+                warnings.append(&mut group.validate()?);
+            }
+        }
+        for port_type in [PortType::Input, PortType::Output] {
+            let port_map = self.graph.port_map(&port_type);
+            for external_port in port_map.port_names() {
+                let Some((node_id, internal_port)) = port_map.get(&external_port) else {
+                    continue;
+                };
+                let port_exists = if let Ok(node_ref) = self.graph.node(*node_id) {
+                    node_ref
+                        .optical_ref
+                        .lock()
+                        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
+                        .ports()
+                        .names(&port_type)
+                        .contains(internal_port)
+                } else {
+                    false
+                };
+                if !port_exists {
+                    warnings.push(Warning::new(
+                        WarningKind::InvalidPort,
+                        Some(self.node_attr.uuid()),
+                        &format!(
+                            "port <{external_port}> of group {group_info} is mapped to non-existing port <{internal_port}>"
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(warnings)
+    }
+    /// Returns the light data definitions of all sources of this [`NodeGroup`] (including all subgroups).
+    ///
+    /// Each entry contains the [`Uuid`] and the description of the source as well as its light data definition.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the internal node mutexes could not be locked.
+    pub(crate) fn source_light_data(
+        &self,
+    ) -> OpmResult<Vec<(Uuid, String, Option<LightDataBuilder>)>> {
+        let mut sources = Vec::new();
+        for node_ref in self.graph.nodes() {
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            if let Ok(group) = node.as_group_mut() {
+                sources.append(&mut group.source_light_data()?);
+            } else if node.node_type() == "source" {
+                let light_data = if let Ok(Proptype::LightDataBuilder(light_data)) =
+                    node.node_attr().get_property("light data")
+                {
+                    light_data.clone()
+                } else {
+                    None
+                };
+                sources.push((node.node_attr().uuid(), format!("{node}"), light_data));
+            }
+        }
+        Ok(sources)
+    }
+    /// Returns all nodes of this [`NodeGroup`] (including subgroups), which cannot be reached by light from any source.
+    ///
+    /// Light is assumed to originate from all sources (or subgroups containing sources) and to propagate along the
+    /// connections. Inside a subgroup, light additionally enters through its mapped input ports. Nodes not
+    /// reachable this way would silently be ignored by an analysis, which is usually an authoring mistake. If an
+    /// unreachable node is a group, its internal nodes are not listed separately.
+    ///
+    /// Each entry contains the [`Uuid`] and the name of the unreachable node.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the internal node mutexes could not be locked.
+    pub fn unreachable_nodes(&self) -> OpmResult<Vec<(Uuid, String)>> {
+        self.unreachable_nodes_from_sources(false)
+    }
+    fn unreachable_nodes_from_sources(&self, is_subgroup: bool) -> OpmResult<Vec<(Uuid, String)>> {
+        let mut start_nodes = Vec::new();
+        for node_ref in self.graph.nodes() {
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            let is_source = if let Ok(group) = node.as_group_mut() {
+                !group.source_light_data()?.is_empty()
+            } else {
+                node.node_type() == "source"
+            };
+            if is_source {
+                start_nodes.push(node.node_attr().uuid());
+            }
+        }
+        if is_subgroup {
+            let input_map = self.graph.port_map(&PortType::Input);
+            start_nodes.extend(
+                input_map
+                    .port_names()
+                    .iter()
+                    .filter_map(|port_name| input_map.get(port_name).map(|(node_id, _)| *node_id)),
+            );
+        }
+        let unreachable = self.graph.unreachable_nodes(&start_nodes);
+        let mut nodes = Vec::new();
+        for node_ref in self.graph.nodes() {
+            let node_id = node_ref.uuid();
+            let mut node = node_ref
+                .optical_ref
+                .lock()
+                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+            if unreachable.contains(&node_id) {
+                nodes.push((node_id, node.name()));
+            } else if let Ok(group) = node.as_group_mut() {
+                nodes.append(&mut group.unreachable_nodes_from_sources(true)?);
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{joule, millimeter, nodes::Dummy, nodes::round_collimated_ray_source};
+    #[test]
+    fn validate() {
+        let mut group = NodeGroup::default();
+        assert!(group.validate().unwrap().is_empty());
+        let d1 = group.add_node(Dummy::default()).unwrap();
+        assert!(group.validate().unwrap().is_empty());
+        let d2 = group.add_node(Dummy::default()).unwrap();
+        let warnings = group.validate().unwrap();
+        let kinds: Vec<WarningKind> = warnings.iter().map(Warning::kind).collect();
+        assert!(kinds.contains(&WarningKind::UnconnectedGraph));
+        assert_eq!(
+            kinds
+                .iter()
+                .filter(|k| **k == WarningKind::StaleNode)
+                .count(),
+            2
+        );
+        group
+            .connect_nodes(d1, "output_1", d2, "input_1", millimeter!(10.0))
+            .unwrap();
+        let warnings = group.validate().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind(), WarningKind::UnconnectedInput);
+        assert_eq!(warnings[0].node_id(), Some(d1));
+        group.map_input_port(d1, "input_1", "input_1").unwrap();
+        assert!(group.validate().unwrap().is_empty());
+    }
+    #[test]
+    fn unreachable_nodes() {
+        let mut scenery = NodeGroup::default();
+        assert!(scenery.unreachable_nodes().unwrap().is_empty());
+        let src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(1.0), joule!(1.0), 1).unwrap())
+            .unwrap();
+        let mut group = NodeGroup::new("group");
+        let g1 = group.add_node(Dummy::new("g1")).unwrap();
+        let g2 = group.add_node(Dummy::new("g2")).unwrap();
+        let g3 = group.add_node(Dummy::new("g3")).unwrap();
+        group
+            .connect_nodes(g1, "output_1", g2, "input_1", millimeter!(10.0))
+            .unwrap();
+        group.map_input_port(g1, "input_1", "input_1").unwrap();
+        group.map_output_port(g2, "output_1", "output_1").unwrap();
+        let group = scenery.add_node(group).unwrap();
+        scenery
+            .connect_nodes(src, "output_1", group, "input_1", millimeter!(10.0))
+            .unwrap();
+        let d1 = scenery.add_node(Dummy::new("d1")).unwrap();
+        let d2 = scenery.add_node(Dummy::new("d2")).unwrap();
+        scenery
+            .connect_nodes(d1, "output_1", d2, "input_1", millimeter!(10.0))
+            .unwrap();
+        let mut unreachable = scenery.unreachable_nodes().unwrap();
+        unreachable.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            unreachable,
+            vec![
+                (d1, "d1".to_owned()),
+                (d2, "d2".to_owned()),
+                (g3, "g3".to_owned())
+            ]
+        );
+    }
+}
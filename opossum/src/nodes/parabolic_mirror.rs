@@ -2,15 +2,19 @@ use core::f64;
 
 use std::sync::{Arc, Mutex};
 
-use nalgebra::{Isometry3, Point3, Vector2, Vector3, vector};
+use nalgebra::{Isometry3, Matrix2, Point3, Vector2, Vector3, vector};
 use opm_macros_lib::OpmNode;
-use uom::si::f64::{Angle, Length};
+use uom::si::{
+    f64::{Angle, Length},
+    length::meter,
+};
 
 use super::NodeAttr;
 use crate::{
     analyzers::{
         GhostFocusConfig, RayTraceConfig,
         energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
         ghostfocus::AnalysisGhostFocus,
         raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
     },
@@ -31,7 +35,12 @@ use crate::{
 
 #[derive(OpmNode, Debug, Clone)]
 #[opm_node("chocolate2")]
-/// An infinitely thin mirror with a spherical (or flat) surface.
+/// An infinitely thin mirror with a parabolic surface.
+///
+/// The mirror can be used on-axis or as off-axis parabola (OAP). An off-axis parabola is defined either by its
+/// (effective) focal length and off-axis angle or by the focal length of the parent parabola and the off-axis
+/// distance (see [`ParabolicMirror::new_with_off_axis_distance`]). The surface of an off-axis parabola is a decentered
+/// section of the parent parabola, so that the reflected beam is folded by the off-axis angle.
 ///
 /// # Focal length convention:
 /// - positive focal length will be a common focusing parabola
@@ -45,7 +54,10 @@ use crate::{
 /// ## Properties
 ///   - `name`
 ///   - `inverted`
-///   - `curvature`
+///   - `focal length`
+///   - `oa angle`
+///   - `collimating`
+///   - `oa direction`
 pub struct ParabolicMirror {
     node_attr: NodeAttr,
 }
@@ -227,6 +239,70 @@ impl ParabolicMirror {
         parabola.update_surfaces()?;
         Ok(parabola)
     }
+    /// Creates a new off-axis [`ParabolicMirror`] node from its parent parabola.
+    ///
+    /// This function creates an infinitely thin, off-axis parabolic mirror which is a section of a parent parabola
+    /// with the given (parent) focal length. The center of the section is decentered from the vertex of the parent
+    /// parabola by the given off-axis distance. This corresponds to the usual specification of commercial OAPs.
+    /// The (effective) focal length and the off-axis angle are derived by
+    ///
+    /// `f_eff = f_parent + d^2 / (4 * f_parent)` and `tan(oa_angle / 2) = d / (2 * f_parent)`.
+    /// # Attributes
+    /// - `name`: name of the node
+    /// - `parent_focal_length`: focal length of the parent parabola
+    /// - `off_axis_distance`: distance of the center of the mirror from the axis of the parent parabola
+    /// - `collimating`: flag that defines if the parabola should collimate a beam (true) or should focus the beam (false)
+    /// - `oa_dir`: projected direction of the reflected ray in the x-y plane of the parabola
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if
+    /// - the parent focal length is zero or not finite.
+    /// - the off-axis distance is not finite
+    /// - the off-axis direction is not finite or if its norm is zero
+    pub fn new_with_off_axis_distance(
+        name: &str,
+        parent_focal_length: Length,
+        off_axis_distance: Length,
+        collimating: bool,
+        oa_dir: Vector2<f64>,
+    ) -> OpmResult<Self> {
+        if !parent_focal_length.is_normal() {
+            return Err(OpossumError::Other(
+                "parent focal length must not be 0.0 and finite".into(),
+            ));
+        }
+        if !off_axis_distance.is_finite() {
+            return Err(OpossumError::Other(
+                "off-axis distance must be finite".into(),
+            ));
+        }
+        let tan_val = (off_axis_distance / (2. * parent_focal_length)).value;
+        let focal_length = parent_focal_length * tan_val.mul_add(tan_val, 1.);
+        let oa_angle = radian!(2. * tan_val.atan());
+        Self::new_with_off_axis(name, focal_length, collimating, oa_angle, oa_dir)
+    }
+    /// Returns the focal length of the parent parabola of this [`ParabolicMirror`].
+    ///
+    /// For an on-axis parabola, this is identical to its focal length.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the node properties cannot be read.
+    pub fn parent_focal_length(&self) -> OpmResult<Length> {
+        self.calc_parent_focal_length()
+    }
+    /// Returns the off-axis distance of this [`ParabolicMirror`].
+    ///
+    /// This is the distance of the center of the mirror from the axis of its parent parabola.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the node properties cannot be read.
+    pub fn off_axis_distance(&self) -> OpmResult<Length> {
+        let (focal_length, oa_angle, _, _) = self.get_parabola_attributes()?;
+        Ok(oa_angle.sin() * focal_length)
+    }
     /// checks the validity of the provided node attributes of thie parabola
     fn check_attributes(
         focal_length: Length,
@@ -461,6 +537,19 @@ impl AnalysisGhostFocus for ParabolicMirror {
         Ok(out_light_rays)
     }
 }
+impl AnalysisGaussianBeam for ParabolicMirror {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        let Ok(Proptype::Length(focal_length)) = self.node_attr.get_property("focal length") else {
+            return Err(OpossumError::Analysis("cannot read focal length".into()));
+        };
+        Ok(Some(Matrix2::new(
+            1.0,
+            0.0,
+            -focal_length.get::<meter>().recip(),
+            1.0,
+        )))
+    }
+}
 impl AnalysisEnergy for ParabolicMirror {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -491,6 +580,7 @@ impl AnalysisRayTrace for ParabolicMirror {
         if rays.is_empty() {
             return Ok(LightResult::default());
         }
+        let iso = self.effective_surface_iso(in_port)?;
         let Some(surf) = self.get_optic_surface_mut(in_port) else {
             return Err(OpossumError::Analysis("no surface found. Aborting".into()));
         };
@@ -502,15 +592,8 @@ impl AnalysisRayTrace for ParabolicMirror {
             refraction_intended,
             config.missed_surface_strategy(),
         )?;
-        match self.ports().aperture(&PortType::Input, in_port) {
-            Some(aperture) => {
-                reflected_rays.apodize(aperture, &self.effective_surface_iso(in_port)?)?;
-                reflected_rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-            }
-            _ => {
-                return Err(OpossumError::OpticPort("input aperture not found".into()));
-            }
-        }
+        surf.apodize_rays(&mut reflected_rays, &iso)?;
+        reflected_rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
         let light_data = LightData::Geometric(reflected_rays);
         let light_result = LightResult::from([(out_port.into(), light_data)]);
         Ok(light_result)
@@ -540,6 +623,7 @@ mod test {
         optic_node::OpticNode,
         position_distributions::Hexapolar,
         properties::Proptype,
+        ray::Ray,
         rays::Rays,
         spectrum_helper::create_he_ne_spec,
         utils::geom_transformation::Isometry,
@@ -950,6 +1034,111 @@ mod test {
         assert_relative_eq!(parabola.calc_parent_focal_length().unwrap().value, 0.5);
     }
 
+    #[test]
+    fn new_with_off_axis_distance() {
+        assert!(
+            ParabolicMirror::new_with_off_axis_distance(
+                "Parabola",
+                meter!(0.),
+                meter!(1.),
+                false,
+                Vector2::new(1., 0.)
+            )
+            .is_err()
+        );
+        assert!(
+            ParabolicMirror::new_with_off_axis_distance(
+                "Parabola",
+                meter!(0.5),
+                meter!(f64::NAN),
+                false,
+                Vector2::new(1., 0.)
+            )
+            .is_err()
+        );
+        assert!(
+            ParabolicMirror::new_with_off_axis_distance(
+                "Parabola",
+                meter!(0.5),
+                meter!(1.),
+                false,
+                Vector2::new(0., 0.)
+            )
+            .is_err()
+        );
+        let parabola = ParabolicMirror::new_with_off_axis_distance(
+            "Parabola",
+            meter!(0.5),
+            meter!(1.),
+            true,
+            Vector2::new(0., 1.),
+        )
+        .unwrap();
+        let (focal_length, oa_angle, oa_dir, collimating) =
+            parabola.get_parabola_attributes().unwrap();
+        assert_relative_eq!(focal_length.value, 1.);
+        assert_relative_eq!(oa_angle.value, degree!(90.).value);
+        assert_relative_eq!(oa_dir, Vector2::new(0., 1.));
+        assert!(collimating);
+        assert_relative_eq!(parabola.parent_focal_length().unwrap().value, 0.5);
+        assert_relative_eq!(parabola.off_axis_distance().unwrap().value, 1.);
+        let reference = ParabolicMirror::new_with_off_axis(
+            "Parabola",
+            meter!(1.),
+            true,
+            degree!(90.),
+            Vector2::new(0., 1.),
+        )
+        .unwrap();
+        assert_relative_eq!(
+            parabola
+                .calc_off_axis_isometry()
+                .unwrap()
+                .get_transform()
+                .to_matrix(),
+            reference
+                .calc_off_axis_isometry()
+                .unwrap()
+                .get_transform()
+                .to_matrix(),
+            epsilon = 10. * f64::EPSILON
+        );
+    }
+    #[test]
+    fn off_axis_distance() {
+        let parabola = ParabolicMirror::default();
+        assert_relative_eq!(parabola.off_axis_distance().unwrap().value, 0.);
+        assert_relative_eq!(parabola.parent_focal_length().unwrap().value, 1.);
+        let parabola =
+            ParabolicMirror::new_with_off_axis_x("Parabola", meter!(1.), false, degree!(90.))
+                .unwrap();
+        assert_relative_eq!(parabola.off_axis_distance().unwrap().value, 1.);
+        assert_relative_eq!(parabola.parent_focal_length().unwrap().value, 0.5);
+    }
+    #[test]
+    fn analysis_raytrace_off_axis_folding() {
+        let mut node = ParabolicMirror::new_with_off_axis_distance(
+            "Parabola",
+            meter!(0.5),
+            meter!(1.),
+            false,
+            Vector2::new(1., 0.),
+        )
+        .unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(nanometer!(1000.), joule!(1.)).unwrap());
+        let input = LightResult::from([("input_1".into(), LightData::Geometric(rays))]);
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!()
+        };
+        let ray = rays.iter().next().unwrap();
+        // the central ray is folded by the off-axis angle of 90°
+        assert_relative_eq!(ray.direction().normalize().x.abs(), 1., epsilon = 1e-6);
+        assert_relative_eq!(ray.direction().z, 0., epsilon = 1e-6);
+    }
     #[test]
     fn with_oap_angle() {
         let parabola = ParabolicMirror::default()
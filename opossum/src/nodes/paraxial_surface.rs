@@ -4,6 +4,7 @@ use crate::{
     analyzers::{
         GhostFocusConfig, RayTraceConfig,
         energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
         ghostfocus::AnalysisGhostFocus,
         raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
     },
@@ -17,8 +18,12 @@ use crate::{
     rays::Rays,
 };
 use log::warn;
+use nalgebra::Matrix2;
 use opm_macros_lib::OpmNode;
-use uom::{num_traits::Zero, si::f64::Length};
+use uom::{
+    num_traits::Zero,
+    si::{f64::Length, length::meter},
+};
 
 use super::node_attr::NodeAttr;
 
@@ -127,7 +132,7 @@ impl AnalysisGhostFocus for ParaxialSurface {
 
             rays.refract_paraxial(focal_length, &iso)?;
 
-            apodized |= rays.apodize(surf.aperture(), &iso)?;
+            apodized |= surf.apodize_rays(rays, &iso)?;
             if apodized {
                 warn!(
                     "Rays have been apodized at input aperture of {optic_name}. Results might not be accurate."
@@ -156,6 +161,19 @@ impl AnalysisGhostFocus for ParaxialSurface {
         Ok(out_light_rays)
     }
 }
+impl AnalysisGaussianBeam for ParaxialSurface {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        let Ok(Proptype::Length(focal_length)) = self.node_attr.get_property("focal length") else {
+            return Err(OpossumError::Analysis("cannot read focal length".into()));
+        };
+        Ok(Some(Matrix2::new(
+            1.0,
+            0.0,
+            -focal_length.get::<meter>().recip(),
+            1.0,
+        )))
+    }
+}
 impl AnalysisEnergy for ParaxialSurface {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -194,24 +212,16 @@ impl AnalysisRayTrace for ParaxialSurface {
                     config.missed_surface_strategy(),
                 )?;
                 rays.refract_paraxial(focal_length, &iso)?;
-                match self.ports().aperture(&PortType::Input, in_port) {
-                    Some(aperture) => {
-                        rays.apodize(aperture, &iso)?;
-                        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("input aperture not found".into()));
-                    }
-                }
-                match self.ports().aperture(&PortType::Output, out_port) {
-                    Some(aperture) => {
-                        rays.apodize(aperture, &iso)?;
-                        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("output aperture not found".into()));
-                    }
-                }
+                let Some(surf) = self.get_optic_surface_mut(in_port) else {
+                    return Err(OpossumError::OpticPort("input aperture not found".into()));
+                };
+                surf.apodize_rays(&mut rays, &iso)?;
+                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+                let Some(surf) = self.get_optic_surface_mut(out_port) else {
+                    return Err(OpossumError::OpticPort("output aperture not found".into()));
+                };
+                surf.apodize_rays(&mut rays, &iso)?;
+                rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
                 let mut light_result = LightResult::default();
                 light_result.insert(out_port.into(), LightData::Geometric(rays));
                 Ok(light_result)
@@ -0,0 +1,347 @@
+#![warn(missing_docs)]
+//! Ideal linear polarizer
+use super::node_attr::NodeAttr;
+use crate::{
+    analyzers::{
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
+        raytrace::AnalysisRayTrace,
+    },
+    degree,
+    error::{OpmResult, OpossumError},
+    light_result::{LightRays, LightResult},
+    lightdata::LightData,
+    nodes::FilterType,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    rays::Rays,
+    utils::geom_transformation::Isometry,
+};
+use nalgebra::{Matrix2, Vector3};
+use num::Complex;
+use opm_macros_lib::OpmNode;
+use uom::si::f64::{Angle, Length};
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("mediumpurple")]
+/// An ideal linear polarizer.
+///
+/// The polarizer applies the Jones matrix of an ideal linear polarizer to all rays passing through. The transmission
+/// axis is given as angle with respect to the local x axis of the node. Unpolarized rays are attenuated by 50% and leave
+/// the polarizer linearly polarized along the transmission axis. For polarized rays, the transmitted energy follows
+/// Malus's law.
+///
+/// During an energy analysis, the light is assumed to be unpolarized and hence attenuated by 50%.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `transmission axis`
+pub struct Polarizer {
+    node_attr: NodeAttr,
+}
+unsafe impl Send for Polarizer {}
+
+impl Default for Polarizer {
+    /// Create a linear polarizer with its transmission axis along the local x axis.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("polarizer");
+        node_attr
+            .create_property(
+                "transmission axis",
+                "angle of the transmission axis with respect to the local x axis",
+                degree!(0.0).into(),
+            )
+            .unwrap();
+        let mut polarizer = Self { node_attr };
+        polarizer.update_surfaces().unwrap();
+        polarizer
+    }
+}
+impl Polarizer {
+    /// Creates a new [`Polarizer`] with the given angle of its transmission axis.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given angle is not finite.
+    pub fn new(name: &str, transmission_axis: Angle) -> OpmResult<Self> {
+        let mut polarizer = Self::default();
+        polarizer.set_transmission_axis(transmission_axis)?;
+        polarizer.node_attr.set_name(name);
+        Ok(polarizer)
+    }
+    /// Returns the angle of the transmission axis of this [`Polarizer`] with respect to its local x axis.
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the transmission-axis property.
+    #[must_use]
+    pub fn transmission_axis(&self) -> Angle {
+        if let Ok(Proptype::Angle(angle)) = self.node_attr.get_property("transmission axis") {
+            *angle
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the angle of the transmission axis of this [`Polarizer`] with respect to its local x axis.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given angle is not finite.
+    pub fn set_transmission_axis(&mut self, transmission_axis: Angle) -> OpmResult<()> {
+        if !transmission_axis.is_finite() {
+            return Err(OpossumError::Other(
+                "transmission axis angle must be finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("transmission axis", transmission_axis.into())
+    }
+    /// Returns the Jones matrix of this [`Polarizer`] with respect to the global x and y axes.
+    ///
+    /// The transmission axis is rotated into the global coordinate system using the given isometry of the node.
+    fn jones_matrix(&self, iso: &Isometry) -> Matrix2<Complex<f64>> {
+        linear_polarizer_matrix(global_axis_angle(self.transmission_axis(), iso))
+    }
+}
+/// Returns the angle (in radians) of an axis, given in the local x-y plane of a node, with respect to the global x axis.
+pub(crate) fn global_axis_angle(local_angle: Angle, iso: &Isometry) -> f64 {
+    let local_axis = Vector3::new(local_angle.value.cos(), local_angle.value.sin(), 0.0);
+    let global_axis = iso.transform_vector_f64(&local_axis);
+    global_axis.y.atan2(global_axis.x)
+}
+/// Returns the Jones matrix of an ideal linear polarizer with its transmission axis at the given angle (in radians).
+fn linear_polarizer_matrix(angle: f64) -> Matrix2<Complex<f64>> {
+    let (sin, cos) = angle.sin_cos();
+    Matrix2::new(cos * cos, cos * sin, cos * sin, sin * sin).map(|v| Complex::new(v, 0.0))
+}
+
+impl OpticNode for Polarizer {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        self.update_flat_single_surfaces()
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+    fn set_apodization_warning(&mut self, _apodized: bool) {}
+    fn reset_data(&mut self) {
+        self.reset_optic_surfaces();
+    }
+}
+impl AnalysisGhostFocus for Polarizer {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let jones_matrix = self.jones_matrix(&self.effective_surface_iso(in_port)?);
+        let mut output =
+            AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)?;
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        if let Some(rays_bundles) = output.get_mut(out_port) {
+            for rays in rays_bundles {
+                rays.apply_jones_matrix(&jones_matrix)?;
+            }
+            Ok(output)
+        } else {
+            Err(OpossumError::Analysis("polarizing of rays failed".into()))
+        }
+    }
+}
+impl AnalysisGaussianBeam for Polarizer {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
+impl AnalysisEnergy for Polarizer {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        if let LightData::Energy(s) = input {
+            let mut new_spectrum = s.clone();
+            new_spectrum.filter_with_type(&FilterType::Constant(0.5))?;
+            let light_data = LightData::Energy(new_spectrum);
+            Ok(LightResult::from([(out_port.into(), light_data)]))
+        } else {
+            Err(OpossumError::Analysis("expected energy light data".into()))
+        }
+    }
+}
+impl AnalysisRayTrace for Polarizer {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        let LightData::Geometric(r) = input else {
+            return Err(OpossumError::Analysis(
+                "expected geometric light data".into(),
+            ));
+        };
+        let mut rays = r.clone();
+        let iso = self.effective_surface_iso(in_port)?;
+        let jones_matrix = self.jones_matrix(&iso);
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis("no surface found. Aborting".into()));
+        };
+        let refraction_intended = true;
+        rays.refract_on_surface(
+            surf,
+            None,
+            refraction_intended,
+            config.missed_surface_strategy(),
+        )?;
+        rays.apply_jones_matrix(&jones_matrix)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let Some(surf) = self.get_optic_surface_mut(out_port) else {
+            return Err(OpossumError::OpticPort("output aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let light_data = LightData::Geometric(rays);
+        Ok(LightResult::from([(out_port.into(), light_data)]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, millimeter, nanometer, nodes::test_helper::test_helper::*, optic_ports::PortType,
+        position_distributions::Hexapolar, spectrum_helper::create_he_ne_spec,
+    };
+    use approx::assert_abs_diff_eq;
+    use nalgebra::Vector2;
+    use uom::si::energy::joule;
+    #[test]
+    fn default() {
+        let mut node = Polarizer::default();
+        assert_eq!(node.transmission_axis(), degree!(0.0));
+        assert_eq!(node.name(), "polarizer");
+        assert_eq!(node.node_type(), "polarizer");
+        assert!(!node.inverted());
+        assert_eq!(node.node_color(), "mediumpurple");
+        assert!(node.as_group_mut().is_err());
+    }
+    #[test]
+    fn new() {
+        assert!(Polarizer::new("test", degree!(f64::NAN)).is_err());
+        assert!(Polarizer::new("test", degree!(f64::INFINITY)).is_err());
+        let node = Polarizer::new("test", degree!(45.0)).unwrap();
+        assert_eq!(node.name(), "test");
+        assert_eq!(node.transmission_axis(), degree!(45.0));
+    }
+    #[test]
+    fn set_transmission_axis() {
+        let mut node = Polarizer::default();
+        assert!(node.set_transmission_axis(degree!(f64::NAN)).is_err());
+        node.set_transmission_axis(degree!(90.0)).unwrap();
+        assert_eq!(node.transmission_axis(), degree!(90.0));
+    }
+    #[test]
+    fn jones_matrix() {
+        let node = Polarizer::new("test", degree!(90.0)).unwrap();
+        let m = node.jones_matrix(&Isometry::identity());
+        assert_abs_diff_eq!(m[(0, 0)].re, 0.0);
+        assert_abs_diff_eq!(m[(1, 1)].re, 1.0);
+        assert_abs_diff_eq!(m[(0, 1)].re, 0.0);
+        let iso = Isometry::new(millimeter!(0.0, 0.0, 0.0), degree!(0.0, 0.0, -90.0)).unwrap();
+        let m = node.jones_matrix(&iso);
+        assert_abs_diff_eq!(m[(0, 0)].re, 1.0);
+        assert_abs_diff_eq!(m[(1, 1)].re, 0.0);
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<Polarizer>()
+    }
+    #[test]
+    fn ports() {
+        let node = Polarizer::default();
+        assert_eq!(node.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(node.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<Polarizer>()
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<Polarizer>("input_1");
+    }
+    #[test]
+    fn analyze_energy_ok() {
+        let mut node = Polarizer::default();
+        let mut input = LightResult::default();
+        input.insert(
+            "input_1".into(),
+            LightData::Energy(create_he_ne_spec(1.0).unwrap()),
+        );
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        let expected_output_light = LightData::Energy(create_he_ne_spec(0.5).unwrap());
+        assert_eq!(*output.get("output_1").unwrap(), expected_output_light);
+    }
+    #[test]
+    fn analyze_geometric() {
+        let mut node = Polarizer::new("test", degree!(60.0)).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1054.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(5.0), 1).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        // unpolarized light
+        assert_abs_diff_eq!(rays.total_energy().get::<joule>(), 0.5, epsilon = 1e-12);
+        let pol = rays.iter().next().unwrap().polarization().unwrap();
+        assert_abs_diff_eq!(pol[0].norm(), 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(pol[1].norm(), f64::sqrt(0.75), epsilon = 1e-12);
+        // Malus's law for a second polarizer
+        let mut node = Polarizer::new("test", degree!(0.0)).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays.clone()));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_abs_diff_eq!(rays.total_energy().get::<joule>(), 0.125, epsilon = 1e-12);
+        let pol = rays.iter().next().unwrap().polarization().unwrap();
+        assert_abs_diff_eq!(
+            (pol - Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 0.0))).norm(),
+            0.0,
+            epsilon = 1e-12
+        );
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{
+    analyzers::energy::AnalysisEnergy, error::OpmResult, light_result::LightResult,
+    optic_node::OpticNode, optic_ports::PortType,
+};
+
+use super::Prism;
+
+impl AnalysisEnergy for Prism {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(data) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        Ok(LightResult::from([(out_port.into(), data.clone())]))
+    }
+}
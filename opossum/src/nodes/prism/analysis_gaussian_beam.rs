@@ -0,0 +1,18 @@
+use super::Prism;
+use crate::{analyzers::gaussian_beam::AnalysisGaussianBeam, error::OpmResult};
+use nalgebra::Matrix2;
+use uom::si::{f64::Length, length::meter};
+
+impl AnalysisGaussianBeam for Prism {
+    fn abcd_matrix(&self, wavelength: Length, n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        // the deflection and the anamorphic magnification of the beam are neglected here
+        let (refri, center_thickness, _) = self.get_attributes()?;
+        let n_prism = refri.get_refractive_index(wavelength)?;
+        Ok(Some(Matrix2::new(
+            1.0,
+            center_thickness.get::<meter>() * n_ambient / n_prism,
+            0.0,
+            1.0,
+        )))
+    }
+}
@@ -0,0 +1,31 @@
+use super::Prism;
+use crate::{
+    analyzers::{AnalyzerType, GhostFocusConfig, ghostfocus::AnalysisGhostFocus},
+    error::OpmResult,
+    light_result::LightRays,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    rays::Rays,
+};
+
+impl AnalysisGhostFocus for Prism {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+
+        let mut rays_bundle = incoming_data
+            .get(in_port)
+            .map_or_else(Vec::<Rays>::new, std::clone::Clone::clone);
+        self.pass_through_prism(&mut rays_bundle, &AnalyzerType::GhostFocus(config.clone()))?;
+
+        let mut out_light_rays = LightRays::default();
+        out_light_rays.insert(out_port.to_string(), rays_bundle);
+        Ok(out_light_rays)
+    }
+}
@@ -0,0 +1,386 @@
+#![warn(missing_docs)]
+//! Dispersing prism
+use std::sync::{Arc, Mutex};
+
+use super::NodeAttr;
+use crate::{
+    analyzers::{AnalyzerType, raytrace::AnalysisRayTrace},
+    degree,
+    error::{OpmResult, OpossumError},
+    millimeter,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    ray::{Ray, TerminationReason},
+    rays::Rays,
+    refractive_index::{RefrIndexConst, RefractiveIndex, RefractiveIndexType},
+    surface::{Plane, geo_surface::GeoSurfaceRef},
+    utils::geom_transformation::Isometry,
+};
+use nalgebra::Point3;
+use num::Zero;
+use opm_macros_lib::OpmNode;
+use uom::si::{
+    angle::degree,
+    f64::{Angle, Length},
+};
+
+mod analysis_energy;
+mod analysis_gaussian_beam;
+mod analysis_ghostfocus;
+mod analysis_raytrace;
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("lightskyblue")]
+/// A prism with two flat faces enclosing a given apex angle.
+///
+/// Both faces are tilted symmetrically (around the local x axis) by half of the apex angle with respect to the
+/// optical axis. The rays are refracted at both faces using the refractive index of the prism material at their
+/// wavelength. Hence, rays of different wavelengths leave the prism in different directions. Rays, which are totally
+/// reflected at the exit face, are terminated.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `center thickness`
+///   - `refractive index`
+///   - `apex angle`
+pub struct Prism {
+    node_attr: NodeAttr,
+}
+unsafe impl Send for Prism {}
+
+impl Default for Prism {
+    /// Create a prism with a center thickness of 10.0 mm, refractive index of 1.5 and an apex angle of 60°.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("prism");
+        node_attr
+            .create_property(
+                "center thickness",
+                "thickness of the prism on the optical axis",
+                millimeter!(10.0).into(),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "refractive index",
+                "refractive index of the prism material",
+                RefractiveIndexType::Const(RefrIndexConst::new(1.5).unwrap()).into(),
+            )
+            .unwrap();
+        node_attr
+            .create_property("apex angle", "apex angle", degree!(60.0).into())
+            .unwrap();
+        let mut prism = Self { node_attr };
+        prism.update_surfaces().unwrap();
+        prism
+    }
+}
+impl Prism {
+    /// Create a new prism.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the center thickness is negative or not finite
+    ///   - the apex angle is outside [0°; 180°[ or not finite
+    pub fn new(
+        name: &str,
+        center_thickness: Length,
+        apex_angle: Angle,
+        refractive_index: &dyn RefractiveIndex,
+    ) -> OpmResult<Self> {
+        if center_thickness.is_sign_negative() || !center_thickness.is_finite() {
+            return Err(OpossumError::Other(
+                "center thickness must be positive and finite".into(),
+            ));
+        }
+        if !apex_angle.is_finite()
+            || apex_angle.is_sign_negative()
+            || apex_angle.get::<degree>() >= 180.0
+        {
+            return Err(OpossumError::Other(
+                "apex angle must be within the interval [0 deg; 180 deg[ and finite".into(),
+            ));
+        }
+        let mut prism = Self::default();
+        prism.node_attr.set_name(name);
+        prism
+            .node_attr
+            .set_property("center thickness", center_thickness.into())?;
+        prism
+            .node_attr
+            .set_property("refractive index", refractive_index.to_enum().into())?;
+        prism
+            .node_attr
+            .set_property("apex angle", apex_angle.into())?;
+        prism.update_surfaces()?;
+        Ok(prism)
+    }
+    /// Returns the refractive index (at ambient temperature), the center thickness and the apex angle of this prism.
+    fn get_attributes(&self) -> OpmResult<(RefractiveIndexType, Length, Angle)> {
+        let (refri, center_thickness, _) = self.get_node_attributes_ray_trace(&self.node_attr)?;
+        let Ok(Proptype::Angle(apex_angle)) = self.node_attr.get_property("apex angle") else {
+            return Err(OpossumError::Analysis("cannot read apex angle".into()));
+        };
+        Ok((refri, center_thickness, *apex_angle))
+    }
+    /// Refract the given rays at the entrance and the exit face of this prism.
+    ///
+    /// Rays, which are totally reflected at the exit face, are terminated.
+    fn pass_through_prism(
+        &mut self,
+        rays_bundle: &mut Vec<Rays>,
+        analyzer_type: &AnalyzerType,
+    ) -> OpmResult<()> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let (refri, _, _) = self.get_attributes()?;
+        let refraction_intended = true;
+        self.pass_through_surface(
+            in_port,
+            &refri,
+            rays_bundle,
+            analyzer_type,
+            self.inverted(),
+            refraction_intended,
+        )?;
+        let bounces: Vec<Vec<usize>> = rays_bundle
+            .iter()
+            .map(|rays| rays.iter().map(Ray::number_of_bounces).collect())
+            .collect();
+        self.pass_through_surface(
+            out_port,
+            &self.ambient_idx(),
+            rays_bundle,
+            analyzer_type,
+            self.inverted(),
+            refraction_intended,
+        )?;
+        for (rays, bounces) in rays_bundle.iter_mut().zip(bounces) {
+            for (ray, bounces) in rays.iter_mut().zip(bounces) {
+                if ray.number_of_bounces() > bounces {
+                    ray.terminate(TerminationReason::TotalReflection);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OpticNode for Prism {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        let node_iso = self.effective_node_iso().unwrap_or_else(Isometry::identity);
+        let (_, center_thickness, apex_angle) = self.get_attributes()?;
+
+        let front_iso = Isometry::new(
+            Point3::origin(),
+            Point3::new(-apex_angle / 2.0, Angle::zero(), Angle::zero()),
+        )?;
+        let front_geosurface = GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(
+            node_iso.append(&front_iso),
+        ))));
+        self.update_surface(
+            &"input_1".to_string(),
+            front_geosurface,
+            front_iso,
+            &PortType::Input,
+        )?;
+
+        let thickness_iso = Isometry::new_along_z(center_thickness)?;
+        let rear_iso = Isometry::new(
+            Point3::origin(),
+            Point3::new(apex_angle / 2.0, Angle::zero(), Angle::zero()),
+        )?;
+        let anchor_point_iso = thickness_iso.append(&rear_iso);
+        let rear_geosurface = GeoSurfaceRef(Arc::new(Mutex::new(Plane::new(
+            node_iso.append(&anchor_point_iso),
+        ))));
+        self.update_surface(
+            &"output_1".to_string(),
+            rear_geosurface,
+            anchor_point_iso,
+            &PortType::Output,
+        )?;
+        Ok(())
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        SceneryResources,
+        analyzers::{RayTraceConfig, energy::AnalysisEnergy, raytrace::AnalysisRayTrace},
+        joule,
+        light_result::LightResult,
+        lightdata::LightData,
+        nanometer,
+        nodes::test_helper::test_helper::*,
+        refractive_index::{RefrIndexCatalog, RefrIndexTempCoeff},
+        spectrum_helper::create_he_ne_spec,
+    };
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+    use uom::si::{f64::ThermodynamicTemperature, thermodynamic_temperature::degree_celsius};
+
+    fn trace(node: &mut Prism, wavelength: Length) -> Rays {
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(wavelength, joule!(1.0)).unwrap());
+        let input = LightResult::from([("input_1".into(), LightData::Geometric(rays))]);
+        let output = AnalysisRayTrace::analyze(node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        rays.clone()
+    }
+    /// deviation angle (in the y-z plane) of a ray propagating along the z axis
+    fn deviation(direction: Vector3<f64>) -> f64 {
+        direction.y.atan2(direction.z)
+    }
+    #[test]
+    fn default() {
+        let node = Prism::default();
+        assert_eq!(node.name(), "prism");
+        assert_eq!(node.node_type(), "prism");
+        assert_eq!(node.node_color(), "lightskyblue");
+        assert!(!node.inverted());
+        let (refri, center_thickness, apex_angle) = node.get_attributes().unwrap();
+        assert_eq!(center_thickness, millimeter!(10.0));
+        assert_eq!(apex_angle, degree!(60.0));
+        assert_eq!(refri.get_refractive_index(nanometer!(1000.0)).unwrap(), 1.5);
+    }
+    #[test]
+    fn refractive_index_ambient_temperature() {
+        let celsius = ThermodynamicTemperature::new::<degree_celsius>;
+        let ref_index = RefrIndexTempCoeff::new(
+            RefrIndexConst::new(1.5).unwrap().into(),
+            1.0e-5,
+            0.0,
+            celsius(20.0),
+        )
+        .unwrap();
+        let mut node = Prism::new("test", millimeter!(10.0), degree!(60.0), &ref_index).unwrap();
+        let refr_index_at = |node: &Prism| {
+            node.get_attributes()
+                .unwrap()
+                .0
+                .get_refractive_index(nanometer!(1053.0))
+                .unwrap()
+        };
+        assert_relative_eq!(refr_index_at(&node), 1.5);
+        node.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources {
+            ambient_temperature: Some(celsius(30.0)),
+            ..Default::default()
+        }))));
+        assert_relative_eq!(refr_index_at(&node), 1.5001);
+    }
+    #[test]
+    fn new() {
+        let n = RefrIndexConst::new(1.5).unwrap();
+        assert!(Prism::new("test", millimeter!(-0.1), degree!(60.0), &n).is_err());
+        assert!(Prism::new("test", millimeter!(f64::INFINITY), degree!(60.0), &n).is_err());
+        assert!(Prism::new("test", millimeter!(f64::NAN), degree!(60.0), &n).is_err());
+        assert!(Prism::new("test", millimeter!(1.0), degree!(-1.0), &n).is_err());
+        assert!(Prism::new("test", millimeter!(1.0), degree!(180.0), &n).is_err());
+        assert!(Prism::new("test", millimeter!(1.0), degree!(f64::NAN), &n).is_err());
+        assert!(Prism::new("test", millimeter!(1.0), degree!(0.0), &n).is_ok());
+        let node = Prism::new("test", millimeter!(5.0), degree!(45.0), &n).unwrap();
+        assert_eq!(node.name(), "test");
+        let (_, center_thickness, apex_angle) = node.get_attributes().unwrap();
+        assert_eq!(center_thickness, millimeter!(5.0));
+        assert_eq!(apex_angle, degree!(45.0));
+    }
+    #[test]
+    fn ports() {
+        let node = Prism::default();
+        assert_eq!(node.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(node.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+    #[test]
+    fn set_aperture() {
+        test_set_aperture::<Prism>("input_1", "output_1");
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<Prism>()
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<Prism>()
+    }
+    #[test]
+    fn analyze_energy_ok() {
+        let mut node = Prism::default();
+        let input_light = LightData::Energy(create_he_ne_spec(1.0).unwrap());
+        let input = LightResult::from([("input_1".into(), input_light.clone())]);
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(*output.get("output_1").unwrap(), input_light);
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<Prism>("input_1");
+    }
+    #[test]
+    fn analyze_geometric_deviation() {
+        let mut node = Prism::default();
+        let rays = trace(&mut node, nanometer!(1000.0));
+        assert_eq!(rays.nr_of_rays(true), 1);
+        let ray = rays.iter().next().unwrap();
+        assert_eq!(ray.number_of_refractions(), 2);
+        // analytical deviation for an incidence angle of apex / 2 on the first face
+        let apex = 60_f64.to_radians();
+        let theta_1 = apex / 2.0;
+        let theta_2 = (theta_1.sin() / 1.5).asin();
+        let theta_3 = apex - theta_2;
+        let theta_4 = (1.5 * theta_3.sin()).asin();
+        let expected = theta_1 + theta_4 - apex;
+        assert_relative_eq!(deviation(ray.direction()).abs(), expected, epsilon = 1e-12);
+    }
+    #[test]
+    fn analyze_geometric_dispersion() {
+        let mut node = Prism::new(
+            "N-BK7",
+            millimeter!(10.0),
+            degree!(60.0),
+            &RefrIndexCatalog::new("N-BK7").unwrap(),
+        )
+        .unwrap();
+        let blue = trace(&mut node, nanometer!(400.0));
+        let red = trace(&mut node, nanometer!(800.0));
+        let blue_dev = deviation(blue.iter().next().unwrap().direction()).abs();
+        let red_dev = deviation(red.iter().next().unwrap().direction()).abs();
+        // normal dispersion: shorter wavelengths are deviated more
+        assert!(blue_dev > red_dev);
+    }
+    #[test]
+    fn analyze_geometric_total_reflection() {
+        let mut node = Prism::new(
+            "test",
+            millimeter!(10.0),
+            degree!(100.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        let rays = trace(&mut node, nanometer!(1000.0));
+        assert_eq!(rays.nr_of_rays(true), 0);
+        assert_eq!(
+            rays.iter().next().unwrap().termination_reason(),
+            Some(TerminationReason::TotalReflection)
+        );
+    }
+}
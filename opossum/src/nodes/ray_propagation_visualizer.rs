@@ -1,19 +1,22 @@
 //! Ray propagation monitor
 #![warn(missing_docs)]
 use log::warn;
-use nalgebra::{MatrixXx2, MatrixXx3, Vector3};
+use nalgebra::{Matrix2, MatrixXx2, MatrixXx3, Point3, Vector3};
 use opm_macros_lib::OpmNode;
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
+use std::{fs::File, path::Path};
 use uom::si::{
-    f64::Length,
+    energy::joule,
+    f64::{Energy, Length},
     length::{millimeter, nanometer},
 };
 
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
         raytrace::AnalysisRayTrace,
     },
     error::{OpmResult, OpossumError},
@@ -24,8 +27,10 @@ use crate::{
     optic_ports::PortType,
     plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
     properties::{Properties, Proptype},
+    ray::Ray,
     rays::Rays,
     reporting::node_report::NodeReport,
+    utils::gltf::{rays_mesh, write_glb},
 };
 /// A ray-propagation monitor
 ///
@@ -40,6 +45,7 @@ use crate::{
 /// ## Properties
 ///   - `view direction`
 ///   - `ray transperency`
+///   - `max polyline rays`
 ///
 /// During analysis, the output port contains a replica of the input port similar to a [`Dummy`](crate::nodes::Dummy) node. This way,
 /// different dectector nodes can be "stacked" or used somewhere within the optical setup.
@@ -61,6 +67,9 @@ impl Default for RayPropagationVisualizer {
         node_attr.create_property("ray transparency", 
         "transparency (alpha) value of the ray colors to be plotted. Must be in the interval [0.0,1.0]", 
                 0.4.into()).unwrap();
+        node_attr.create_property("max polyline rays",
+        "maximum number of rays whose paths are exported as polylines (CSV / JSON / glTF). 0 disables the export",
+                0.into()).unwrap();
         let mut rpv = Self {
             light_data: None,
             node_attr,
@@ -88,6 +97,30 @@ impl RayPropagationVisualizer {
         }
         Ok(rpv)
     }
+    /// Enable the export of the ray paths as polylines.
+    ///
+    /// The paths of at most `max_rays` rays are added to the node report as [`RayPolylines`]. These are exported as CSV,
+    /// JSON and glTF files together with the report data (e.g. for an external animation). A value of 0 disables the export.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `max_rays` is too large or the property `max polyline rays` could not be set.
+    pub fn with_polyline_export(mut self, max_rays: usize) -> OpmResult<Self> {
+        let max_rays = i32::try_from(max_rays)
+            .map_err(|_| OpossumError::Other("maximum number of rays too large".into()))?;
+        self.node_attr
+            .set_property("max polyline rays", max_rays.into())?;
+        Ok(self)
+    }
+    /// Returns the maximum number of rays exported as polylines. 0 means that the export is disabled.
+    #[must_use]
+    pub fn max_polyline_rays(&self) -> usize {
+        if let Ok(Proptype::I32(max_rays)) = self.node_attr.get_property("max polyline rays") {
+            usize::try_from(*max_rays).unwrap_or_default()
+        } else {
+            0
+        }
+    }
 }
 impl OpticNode for RayPropagationVisualizer {
     fn set_apodization_warning(&mut self, apodized: bool) {
@@ -125,6 +158,16 @@ impl OpticNode for RayPropagationVisualizer {
                     .unwrap();
                 }
             }
+            let max_rays = self.max_polyline_rays();
+            if max_rays > 0 && rays.nr_of_rays(false) > 0 {
+                props
+                    .create(
+                        "Ray polylines",
+                        "paths of the individual rays",
+                        rays.polylines(max_rays).into(),
+                    )
+                    .unwrap();
+            }
         }
         Some(NodeReport::new(
             &self.node_type(),
@@ -155,6 +198,11 @@ impl AnalysisGhostFocus for RayPropagationVisualizer {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for RayPropagationVisualizer {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for RayPropagationVisualizer {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -404,14 +452,182 @@ impl Plottable for RayPositionHistories {
     }
 }
 
+/// Path of a single ray through the scenery.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RayPolyline {
+    positions: Vec<Point3<Length>>,
+    wavelength: Length,
+    energy: Energy,
+    valid: bool,
+    #[serde(default)]
+    number_of_bounces: usize,
+}
+impl RayPolyline {
+    /// Returns the positions (vertices) of this [`RayPolyline`].
+    #[must_use]
+    pub fn positions(&self) -> &[Point3<Length>] {
+        &self.positions
+    }
+    /// Returns the wavelength of the ray of this [`RayPolyline`].
+    #[must_use]
+    pub const fn wavelength(&self) -> Length {
+        self.wavelength
+    }
+    /// Returns the (final) energy of the ray of this [`RayPolyline`].
+    #[must_use]
+    pub const fn energy(&self) -> Energy {
+        self.energy
+    }
+    /// Returns `true` if the ray of this [`RayPolyline`] is still valid (i.e. was not terminated).
+    #[must_use]
+    pub const fn valid(&self) -> bool {
+        self.valid
+    }
+    /// Returns the number of bounces (reflections) of the ray of this [`RayPolyline`].
+    #[must_use]
+    pub const fn number_of_bounces(&self) -> usize {
+        self.number_of_bounces
+    }
+}
+impl From<&Ray> for RayPolyline {
+    fn from(ray: &Ray) -> Self {
+        let positions = ray
+            .position_history_with_current()
+            .row_iter()
+            .map(|pos| Point3::new(pos[0], pos[1], pos[2]))
+            .collect();
+        Self {
+            positions,
+            wavelength: ray.wavelength(),
+            energy: ray.energy(),
+            valid: ray.valid(),
+            number_of_bounces: ray.number_of_bounces(),
+        }
+    }
+}
+/// A set of [`RayPolyline`]s, e.g. for exporting the ray paths to external visualization tools.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RayPolylines {
+    polylines: Vec<RayPolyline>,
+}
+impl RayPolylines {
+    /// Creates a new [`RayPolylines`] structure.
+    #[must_use]
+    pub const fn new(polylines: Vec<RayPolyline>) -> Self {
+        Self { polylines }
+    }
+    /// Returns the polylines of this [`RayPolylines`].
+    #[must_use]
+    pub fn polylines(&self) -> &[RayPolyline] {
+        &self.polylines
+    }
+    /// Returns the number of polylines of this [`RayPolylines`].
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.polylines.len()
+    }
+    /// Returns `true` if this [`RayPolylines`] does not contain any polyline.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.polylines.is_empty()
+    }
+    /// Write this [`RayPolylines`] to a CSV file.
+    ///
+    /// The file contains one line per vertex with the columns `ray`, `point`, `x`, `y`, `z` (in mm), `wavelength`
+    /// (in nm), `energy` (in J) and `valid`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written.
+    pub fn to_csv(&self, path: &Path) -> OpmResult<()> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        writer
+            .write_record([
+                "ray",
+                "point",
+                "x in mm",
+                "y in mm",
+                "z in mm",
+                "wavelength in nm",
+                "energy in J",
+                "valid",
+            ])
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        for (ray_idx, polyline) in self.polylines.iter().enumerate() {
+            for (point_idx, pos) in polyline.positions.iter().enumerate() {
+                writer
+                    .write_record([
+                        ray_idx.to_string(),
+                        point_idx.to_string(),
+                        pos.x.get::<millimeter>().to_string(),
+                        pos.y.get::<millimeter>().to_string(),
+                        pos.z.get::<millimeter>().to_string(),
+                        polyline.wavelength.get::<nanometer>().to_string(),
+                        polyline.energy.get::<joule>().to_string(),
+                        polyline.valid.to_string(),
+                    ])
+                    .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+            }
+        }
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
+    /// Write this [`RayPolylines`] to a JSON file.
+    ///
+    /// All values are given in SI base units.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written.
+    pub fn to_json(&self, path: &Path) -> OpmResult<()> {
+        let file = File::create(path)
+            .map_err(|e| OpossumError::Other(format!("could not create json file: {e}")))?;
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|e| OpossumError::Other(format!("could not write json file: {e}")))
+    }
+    /// Write this [`RayPolylines`] to a binary glTF (`.glb`) file.
+    ///
+    /// All ray paths are stored as a single mesh of line primitives (in meters) which can be imported into external 3D
+    /// tools such as Blender or web viewers. The vertices are colored by the number of bounces of the respective ray.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the number of vertices is too large.
+    ///   - the file could not be written.
+    pub fn to_gltf(&self, path: &Path) -> OpmResult<()> {
+        let mesh = rays_mesh(self)?;
+        let file = File::create(path)
+            .map_err(|e| OpossumError::Other(format!("could not create glTF file: {e}")))?;
+        write_glb(file, &[mesh])
+            .map_err(|e| OpossumError::Other(format!("could not write glTF file: {e}")))
+    }
+}
+impl From<RayPolylines> for Proptype {
+    fn from(value: RayPolylines) -> Self {
+        Self::RayPolylines(value)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{
+        analyzers::{Analyzer, raytrace::RayTracingAnalyzer},
+        degree,
+        nodes::{NodeGroup, ThinMirror, round_collimated_ray_source},
+        optic_node::Alignable,
+    };
     use crate::{
         joule, millimeter, nanometer, nodes::test_helper::test_helper::*, optic_ports::PortType,
         position_distributions::Hexapolar, rays::Rays, spectrum_helper::create_he_ne_spec,
     };
     use approx::assert_relative_eq;
+    use std::fs;
+    use tempfile::TempDir;
     use uom::si::length::{millimeter, nanometer};
     #[test]
     fn default() {
@@ -428,6 +644,19 @@ mod test {
         let meter = RayPropagationVisualizer::new("test", None).unwrap();
         assert_eq!(meter.name(), "test");
         assert!(meter.light_data.is_none());
+        assert_eq!(meter.max_polyline_rays(), 0);
+    }
+    #[test]
+    fn with_polyline_export() {
+        let meter = RayPropagationVisualizer::default()
+            .with_polyline_export(100)
+            .unwrap();
+        assert_eq!(meter.max_polyline_rays(), 100);
+        assert!(
+            RayPropagationVisualizer::default()
+                .with_polyline_export(usize::MAX)
+                .is_err()
+        );
     }
     #[test]
     fn ports() {
@@ -520,6 +749,119 @@ mod test {
         assert_eq!(nr_of_props, 1);
     }
     #[test]
+    fn report_polylines() {
+        let mut fd = RayPropagationVisualizer::default()
+            .with_polyline_export(3)
+            .unwrap();
+        fd.light_data = Some(LightData::Geometric(Rays::default()));
+        let node_report = fd.node_report("").unwrap();
+        assert!(!node_report.properties().contains("Ray polylines"));
+        fd.light_data = Some(LightData::Geometric(
+            Rays::new_uniform_collimated(
+                nanometer!(1053.0),
+                joule!(1.0),
+                &Hexapolar::new(millimeter!(1.), 1).unwrap(),
+            )
+            .unwrap(),
+        ));
+        let node_report = fd.node_report("").unwrap();
+        let Ok(Proptype::RayPolylines(polylines)) = node_report.properties().get("Ray polylines")
+        else {
+            panic!("property `Ray polylines` not found");
+        };
+        assert_eq!(polylines.len(), 3);
+    }
+    #[test]
+    fn polylines_folded_system() {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 6).unwrap())
+            .unwrap();
+        let i_m = scenery
+            .add_node(
+                ThinMirror::new("mirror")
+                    .with_tilt(degree!(45.0, 0.0, 0.0))
+                    .unwrap(),
+            )
+            .unwrap();
+        let i_rpv = scenery
+            .add_node(
+                RayPropagationVisualizer::default()
+                    .with_polyline_export(100)
+                    .unwrap(),
+            )
+            .unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_m, "input_1", millimeter!(100.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_m, "output_1", i_rpv, "input_1", millimeter!(50.0))
+            .unwrap();
+        RayTracingAnalyzer::default().analyze(&mut scenery).unwrap();
+        let node_report = scenery
+            .node(i_rpv)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .node_report("")
+            .unwrap();
+        let Ok(Proptype::RayPolylines(polylines)) = node_report.properties().get("Ray polylines")
+        else {
+            panic!("property `Ray polylines` not found");
+        };
+        assert_eq!(polylines.len(), 100);
+        for polyline in polylines.polylines() {
+            assert!(polyline.valid());
+            let positions = polyline.positions();
+            // source, mirror and visualizer
+            assert_eq!(positions.len(), 3);
+            // the beam is folded at the mirror
+            assert_relative_eq!(positions[1].z.value, positions[2].z.value, epsilon = 1e-9);
+            assert!((positions[2].y - positions[1].y).abs() > millimeter!(30.0));
+        }
+        let dir = TempDir::new().unwrap();
+        let csv_path = dir.path().join("polylines.csv");
+        let json_path = dir.path().join("polylines.json");
+        polylines.to_csv(&csv_path).unwrap();
+        polylines.to_json(&json_path).unwrap();
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        assert!(
+            csv.starts_with(
+                "ray,point,x in mm,y in mm,z in mm,wavelength in nm,energy in J,valid\n"
+            )
+        );
+        assert_eq!(csv.lines().count(), 1 + 100 * 3);
+        let glb_path = dir.path().join("polylines.glb");
+        polylines.to_gltf(&glb_path).unwrap();
+        let glb = fs::read(&glb_path).unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(u32::from_le_bytes(glb[4..8].try_into().unwrap()), 2);
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+        let json_length = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        assert_eq!(&glb[16..20], b"JSON");
+        let gltf: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        // 3 vertices and 2 line segments per ray
+        assert_eq!(gltf["accessors"][0]["count"], 100 * 3);
+        assert_eq!(gltf["accessors"][2]["count"], 100 * 2 * 2);
+        assert_eq!(gltf["meshes"][0]["primitives"][0]["mode"], 1);
+        let bin_length =
+            u32::from_le_bytes(glb[20 + json_length..24 + json_length].try_into().unwrap())
+                as usize;
+        assert_eq!(gltf["buffers"][0]["byteLength"], bin_length);
+        assert_eq!(&glb[24 + json_length..28 + json_length], b"BIN\0");
+        let json: RayPolylines =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json.len(), 100);
+        for (read, written) in json.polylines().iter().zip(polylines.polylines()) {
+            assert_eq!(read.positions().len(), written.positions().len());
+            assert_relative_eq!(read.positions()[2].y.value, written.positions()[2].y.value);
+        }
+    }
+    #[test]
     fn new_ray_pos_hist_spec() {
         let h = vec![
             MatrixXx3::from_vec(vec![millimeter!(1.), millimeter!(0.), millimeter!(0.)]),
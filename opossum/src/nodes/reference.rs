@@ -1,8 +1,8 @@
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
-        raytrace::AnalysisRayTrace,
+        RayTraceConfig, energy::AnalysisEnergy, gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
     },
     error::{OpmResult, OpossumError},
     light_result::LightResult,
@@ -143,6 +143,7 @@ impl OpticNode for NodeReference {
     }
 }
 impl AnalysisGhostFocus for NodeReference {}
+impl AnalysisGaussianBeam for NodeReference {}
 impl AnalysisEnergy for NodeReference {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let rf = &self
@@ -7,6 +7,7 @@ use crate::{
     analyzers::{
         GhostFocusConfig, RayTraceConfig,
         energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
         ghostfocus::AnalysisGhostFocus,
         raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
     },
@@ -194,6 +195,7 @@ impl AnalysisGhostFocus for ReflectiveGrating {
         Ok(out_light_rays)
     }
 }
+impl AnalysisGaussianBeam for ReflectiveGrating {}
 impl AnalysisEnergy for ReflectiveGrating {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -241,16 +243,8 @@ impl AnalysisRayTrace for ReflectiveGrating {
                     &diffraction_order,
                     refraction_intended,
                 )?;
-                match self.ports().aperture(&PortType::Input, in_port) {
-                    Some(aperture) => {
-                        diffracted_rays.apodize(aperture, &iso)?;
-                        diffracted_rays
-                            .invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("input aperture not found".into()));
-                    }
-                }
+                surf.apodize_rays(&mut diffracted_rays, &iso)?;
+                diffracted_rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
 
                 let light_result =
                     LightResult::from([(out_port.into(), LightData::Geometric(diffracted_rays))]);
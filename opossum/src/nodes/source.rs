@@ -1,5 +1,6 @@
 #![warn(missing_docs)]
 use log::{info, warn};
+use nalgebra::Matrix2;
 use opm_macros_lib::OpmNode;
 use uom::si::f64::Length;
 
@@ -8,6 +9,7 @@ use crate::{
     analyzers::{
         GhostFocusConfig, RayTraceConfig,
         energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
         ghostfocus::AnalysisGhostFocus,
         raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
     },
@@ -18,11 +20,13 @@ use crate::{
     millimeter,
     optic_node::OpticNode,
     optic_ports::PortType,
-    properties::Proptype,
+    properties::{Properties, Proptype},
     ray::Ray,
     rays::Rays,
+    reporting::node_report::NodeReport,
     utils::geom_transformation::Isometry,
 };
+use num::Zero;
 use std::fmt::Debug;
 
 /// A general light source
@@ -165,6 +169,117 @@ impl OpticNode for Source {
     fn update_surfaces(&mut self) -> OpmResult<()> {
         self.update_flat_single_surfaces()
     }
+    fn node_report(&self, uuid: &str) -> Option<NodeReport> {
+        let Ok(Proptype::LightDataBuilder(Some(light_data_builder))) =
+            self.node_attr.get_property("light data")
+        else {
+            return None;
+        };
+        let mut props = Properties::default();
+        props
+            .create(
+                "Definition",
+                "definition of the emitted light",
+                light_data_builder.to_string().into(),
+            )
+            .unwrap();
+        match light_data_builder.clone().build() {
+            Ok(LightData::Energy(spectrum)) => {
+                props
+                    .create("Light type", "type of the emitted light", "energy".into())
+                    .unwrap();
+                props
+                    .create(
+                        "Total energy",
+                        "total energy of the emitted light",
+                        joule!(spectrum.total_energy()).into(),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "Central wavelength",
+                        "energy-weighted central wavelength",
+                        spectrum.center_wavelength().into(),
+                    )
+                    .unwrap();
+                if let Some(fwhm) = spectrum.fwhm() {
+                    props
+                        .create("FWHM", "spectral width (FWHM)", fwhm.into())
+                        .unwrap();
+                }
+            }
+            Ok(LightData::Geometric(rays)) => {
+                props
+                    .create("Light type", "type of the emitted light", "rays".into())
+                    .unwrap();
+                props
+                    .create(
+                        "Number of rays",
+                        "number of emitted rays",
+                        i32::try_from(rays.nr_of_rays(true))
+                            .unwrap_or(i32::MAX)
+                            .into(),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "Total energy",
+                        "total energy of the emitted light",
+                        rays.total_energy().into(),
+                    )
+                    .unwrap();
+                if let Some(wvl) = rays.central_wavelength() {
+                    props
+                        .create(
+                            "Central wavelength",
+                            "energy-weighted central wavelength",
+                            wvl.into(),
+                        )
+                        .unwrap();
+                }
+                if let Some(range) = rays.wavelength_range() {
+                    let width = range.end - range.start;
+                    let fwhm = if width > Length::zero() {
+                        rays.to_spectrum(&(width / 100.0))
+                            .ok()
+                            .and_then(|spectrum| spectrum.fwhm())
+                    } else {
+                        Some(Length::zero())
+                    };
+                    if let Some(fwhm) = fwhm {
+                        props
+                            .create("FWHM", "spectral width (FWHM)", fwhm.into())
+                            .unwrap();
+                    }
+                }
+            }
+            Ok(_) => {
+                props
+                    .create("Light type", "type of the emitted light", "fourier".into())
+                    .unwrap();
+            }
+            Err(e) => {
+                props
+                    .create(
+                        "Warning",
+                        "warning during report generation",
+                        format!("light data could not be generated: {e}").into(),
+                    )
+                    .unwrap();
+            }
+        }
+        Some(NodeReport::new(
+            &self.node_type(),
+            &self.name(),
+            uuid,
+            props,
+        ))
+    }
+}
+impl AnalysisGaussianBeam for Source {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
 }
 impl AnalysisEnergy for Source {
     fn analyze(&mut self, _incoming_data: LightResult) -> OpmResult<LightResult> {
@@ -186,17 +301,14 @@ impl AnalysisEnergy for Source {
         }
     }
 }
-impl AnalysisRayTrace for Source {
-    fn analyze(
-        &mut self,
-        _incoming_edges: LightResult,
-        config: &RayTraceConfig,
-    ) -> OpmResult<LightResult> {
+impl Source {
+    /// Generate the light emitted by this [`Source`] during a ray tracing analysis.
+    fn emitted_light(&self, config: &RayTraceConfig) -> OpmResult<LightResult> {
         if let Ok(Proptype::LightDataBuilder(light_data_builder)) =
             self.node_attr.get_property("light data")
         {
             let mut data = if let Some(lightdata_builder) = light_data_builder.clone() {
-                lightdata_builder.build()?
+                lightdata_builder.build_seeded(config.seed())?
             } else {
                 return Err(OpossumError::Analysis(
                     "source has empty light data builder".into(),
@@ -225,6 +337,7 @@ impl AnalysisRayTrace for Source {
                         }
                     }
                 }
+                self.set_ambient_medium(rays)?;
             }
             Ok(LightResult::from([("output_1".into(), data)]))
         } else {
@@ -233,20 +346,35 @@ impl AnalysisRayTrace for Source {
             ))
         }
     }
-    fn calc_node_positions(
-        &mut self,
-        incoming_data: LightResult,
-        config: &RayTraceConfig,
-    ) -> OpmResult<LightResult> {
-        let outgoing_edges = AnalysisRayTrace::analyze(self, incoming_data, config)?;
+    /// Generate the chief ray (= optical axis) emitted by this [`Source`].
+    ///
+    /// The wavelength of this ray is given by the alignment wavelength of the [`RayTraceConfig`], the alignment
+    /// wavelength of this [`Source`], the default wavelength of the scenery resources or the energy-weighted central
+    /// wavelength of the emitted light (in this order).
+    fn chief_ray_light(&self, config: &RayTraceConfig) -> OpmResult<LightResult> {
+        let outgoing_edges = self.emitted_light(config)?;
         // generate a single beam (= optical axis) from source
         let mut new_outgoing_edges = LightResult::new();
         for outgoing_edge in &outgoing_edges {
             if let LightData::Geometric(rays) = outgoing_edge.1 {
-                let mut axis_ray = if let Ok(Proptype::LengthOption(Some(alignment_wvl))) =
-                    self.node_attr.get_property("alignment wavelength")
-                {
-                    Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), *alignment_wvl, joule!(1.0))
+                let alignment_wvl = config
+                    .alignment_wavelength()
+                    .or_else(|| {
+                        if let Ok(Proptype::LengthOption(wvl)) =
+                            self.node_attr.get_property("alignment wavelength")
+                        {
+                            *wvl
+                        } else {
+                            None
+                        }
+                    })
+                    .or_else(|| {
+                        self.global_conf().as_ref().and_then(|conf| {
+                            conf.lock().expect("Mutex lock failed").default_wavelength
+                        })
+                    });
+                let mut axis_ray = if let Some(alignment_wvl) = alignment_wvl {
+                    Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), alignment_wvl, joule!(1.0))
                 } else {
                     info!(
                         "No alignment wavelength defined, using energy-weighted central wavelength for alignment"
@@ -258,6 +386,7 @@ impl AnalysisRayTrace for Source {
 
                 let mut new_rays = Rays::default();
                 new_rays.add_ray(axis_ray);
+                self.set_ambient_medium(&mut new_rays)?;
                 new_outgoing_edges
                     .insert(outgoing_edge.0.to_string(), LightData::Geometric(new_rays));
             } else {
@@ -269,6 +398,35 @@ impl AnalysisRayTrace for Source {
         }
         Ok(new_outgoing_edges)
     }
+    /// Set the refractive index of the emitted rays to the ambient medium of the scenery.
+    ///
+    /// Rays are left untouched (vacuum) if this [`Source`] has no global configuration.
+    fn set_ambient_medium(&self, rays: &mut Rays) -> OpmResult<()> {
+        if self.global_conf().is_some() {
+            rays.set_refractive_index(&self.ambient_idx())?;
+        }
+        Ok(())
+    }
+}
+impl AnalysisRayTrace for Source {
+    fn analyze(
+        &mut self,
+        _incoming_edges: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        if config.chief_ray_only() {
+            self.chief_ray_light(config)
+        } else {
+            self.emitted_light(config)
+        }
+    }
+    fn calc_node_positions(
+        &mut self,
+        _incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        self.chief_ray_light(config)
+    }
 }
 impl AnalysisGhostFocus for Source {
     fn analyze(
@@ -302,6 +460,7 @@ impl AnalysisGhostFocus for Source {
                     }
                     let iso = self.effective_surface_iso("output_1")?;
                     *rays = rays.transformed_by_iso(&iso);
+                    self.set_ambient_medium(rays)?;
 
                     vec![rays.clone()]
                 } else {
@@ -341,11 +500,22 @@ impl AnalysisGhostFocus for Source {
 mod test {
     use super::*;
     use crate::{
-        nanometer, optic_ports::PortType, position_distributions::Hexapolar,
-        spectrum_helper::create_he_ne_spec, utils::geom_transformation::Isometry,
+        energy_distributions::UniformDist,
+        lightdata::ray_data_builder::RayDataBuilder,
+        meter, nanometer,
+        optic_ports::PortType,
+        optic_scenery_rsc::SceneryResources,
+        position_distributions::{Hexapolar, Random},
+        refractive_index::{RefrIndexAir, RefractiveIndexType, refr_index_vaccuum},
+        spectral_distribution::LaserLines,
+        spectrum_helper::create_he_ne_spec,
+        utils::geom_transformation::Isometry,
     };
+    use approx::assert_relative_eq;
     use assert_matches::assert_matches;
     use core::f64;
+    use std::sync::{Arc, Mutex};
+    use uom::si::length::meter;
 
     #[test]
     fn default() {
@@ -503,6 +673,128 @@ mod test {
         }
     }
     #[test]
+    fn analyze_raytrace_chief_ray_only() {
+        let mut node = Source::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 3).unwrap(),
+        )
+        .unwrap();
+        node.set_light_data(LightDataBuilder::Geometric(rays.into()))
+            .unwrap();
+        let mut config = RayTraceConfig::default();
+        config.set_chief_ray_only(true);
+        let output = AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_eq!(rays.nr_of_rays(true), 1);
+        let ray = rays.iter().next().unwrap();
+        assert_eq!(ray.position(), millimeter!(0.0, 0.0, 0.0));
+        assert_eq!(ray.wavelength(), nanometer!(1000.0));
+    }
+    #[test]
+    fn analyze_raytrace_seeded() {
+        let mut node = Source::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        node.set_light_data(LightDataBuilder::Geometric(RayDataBuilder::Collimated {
+            pos_dist: Random::new(millimeter!(1.0), millimeter!(1.0), 10)
+                .unwrap()
+                .into(),
+            energy_dist: UniformDist::new(joule!(1.0)).unwrap().into(),
+            spect_dist: LaserLines::new(vec![(nanometer!(1000.0), 1.0)])
+                .unwrap()
+                .into(),
+        }))
+        .unwrap();
+        let mut config = RayTraceConfig::default();
+        config.set_seed(Some(1234));
+        let output1 =
+            AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        let output2 =
+            AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        assert_eq!(output1.get("output_1"), output2.get("output_1"));
+        config.set_seed(Some(4321));
+        let output3 =
+            AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        assert_ne!(output1.get("output_1"), output3.get("output_1"));
+    }
+    #[test]
+    fn analyze_raytrace_chief_ray_default_wavelength() {
+        let mut node = Source::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        node.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources {
+            default_wavelength: Some(nanometer!(532.0)),
+            ..Default::default()
+        }))));
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 3).unwrap(),
+        )
+        .unwrap();
+        node.set_light_data(LightDataBuilder::Geometric(rays.into()))
+            .unwrap();
+        let mut config = RayTraceConfig::default();
+        config.set_chief_ray_only(true);
+        let output = AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_eq!(rays.iter().next().unwrap().wavelength(), nanometer!(532.0));
+        node.set_property(
+            "alignment wavelength",
+            Proptype::LengthOption(Some(nanometer!(633.0))),
+        )
+        .unwrap();
+        let output = AnalysisRayTrace::analyze(&mut node, LightResult::default(), &config).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_eq!(rays.iter().next().unwrap().wavelength(), nanometer!(633.0));
+    }
+    #[test]
+    fn analyze_raytrace_ambient_air() {
+        let path_length_in = |ambient: RefractiveIndexType| {
+            let mut node = Source::default();
+            node.set_isometry(Isometry::identity()).unwrap();
+            node.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources {
+                ambient_refr_index: ambient,
+                ..Default::default()
+            }))));
+            let rays = Rays::new_uniform_collimated(
+                nanometer!(632.8),
+                joule!(1.0),
+                &Hexapolar::new(millimeter!(1.0), 0).unwrap(),
+            )
+            .unwrap();
+            node.set_light_data(LightDataBuilder::Geometric(rays.into()))
+                .unwrap();
+            let output = AnalysisRayTrace::analyze(
+                &mut node,
+                LightResult::default(),
+                &RayTraceConfig::default(),
+            )
+            .unwrap();
+            let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+                panic!("no geometric light data found")
+            };
+            let mut ray = rays.iter().next().unwrap().clone();
+            ray.propagate(meter!(1.0)).unwrap();
+            ray.path_length()
+        };
+        let opl_vacuum = path_length_in(refr_index_vaccuum());
+        let opl_air = path_length_in(RefrIndexAir::standard().into());
+        assert_relative_eq!(opl_vacuum.get::<meter>(), 1.0);
+        assert_relative_eq!(
+            (opl_air - opl_vacuum).get::<meter>() * 1.0e6,
+            276.5,
+            epsilon = 0.1
+        );
+    }
+    #[test]
     fn analyze_raytrace_light_data_iso() {
         let mut node = Source::default();
         node.set_isometry(Isometry::identity()).unwrap();
@@ -558,6 +850,26 @@ mod test {
         }
     }
     #[test]
+    fn calc_node_position_alignment_wavelength_from_config() {
+        let mut node = Source::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        node.set_alignment_wavelength(nanometer!(630.0)).unwrap();
+        let light_data_builder = LightDataBuilder::Geometric(Rays::default().into());
+        node.set_light_data(light_data_builder).unwrap();
+        let mut config = RayTraceConfig::default();
+        config
+            .set_alignment_wavelength(Some(nanometer!(1053.0)))
+            .unwrap();
+        let output =
+            AnalysisRayTrace::calc_node_positions(&mut node, LightResult::default(), &config)
+                .unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_eq!(rays.nr_of_rays(true), 1);
+        assert_eq!(rays.iter().next().unwrap().wavelength(), nanometer!(1053.0));
+    }
+    #[test]
     fn analyze_ghost_focus_no_light_defined() {
         let mut node = Source::default();
         node.set_isometry(Isometry::identity()).unwrap();
@@ -597,6 +909,50 @@ mod test {
         assert!(output.is_ok());
     }
     #[test]
+    fn report_no_light_defined() {
+        assert!(Source::default().node_report("").is_none());
+    }
+    #[test]
+    fn report_energy() {
+        let light_builder = LightDataBuilder::Energy(create_he_ne_spec(1.0).unwrap().into());
+        let node = Source::new("test", light_builder);
+        let report = node.node_report("123").unwrap();
+        assert_eq!(report.name(), "test");
+        assert_eq!(report.node_type(), "source");
+        let props = report.properties();
+        assert_matches!(props.get("Light type").unwrap(), Proptype::String(s) if s == "energy");
+        assert!(props.contains("Definition"));
+        assert!(props.contains("Total energy"));
+        assert!(props.contains("Central wavelength"));
+        assert!(props.contains("FWHM"));
+    }
+    #[test]
+    fn report_geometric() {
+        let rays = Rays::new_uniform_collimated(
+            nanometer!(1000.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap();
+        let node = Source::new("test", LightDataBuilder::Geometric(rays.into()));
+        let report = node.node_report("123").unwrap();
+        let props = report.properties();
+        assert_matches!(props.get("Light type").unwrap(), Proptype::String(s) if s == "rays");
+        assert_matches!(props.get("Number of rays").unwrap(), Proptype::I32(7));
+        let Proptype::Energy(energy) = props.get("Total energy").unwrap() else {
+            panic!("wrong proptype")
+        };
+        assert_eq!(*energy, joule!(1.0));
+        let Proptype::Length(wvl) = props.get("Central wavelength").unwrap() else {
+            panic!("wrong proptype")
+        };
+        assert_relative_eq!(wvl.value, nanometer!(1000.0).value);
+        let Proptype::Length(fwhm) = props.get("FWHM").unwrap() else {
+            panic!("wrong proptype")
+        };
+        assert_eq!(*fwhm, Length::zero());
+    }
+    #[test]
     fn debug() {
         assert_eq!(format!("{:?}", Source::default()), "Source: no data");
         assert_eq!(
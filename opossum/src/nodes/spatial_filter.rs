@@ -0,0 +1,443 @@
+#![warn(missing_docs)]
+//! Vacuum spatial filter consisting of two lenses with a pinhole in the common focal plane
+use super::{NodeGroup, ParaxialSurface, node_attr::NodeAttr};
+use crate::{
+    analyzers::{
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
+        raytrace::AnalysisRayTrace,
+    },
+    aperture::{Aperture, CircleConfig},
+    error::{OpmResult, OpossumError},
+    light_result::{LightRays, LightResult},
+    lightdata::LightData,
+    millimeter,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::{Properties, Proptype},
+    rays::Rays,
+    reporting::node_report::NodeReport,
+    utils::geom_transformation::Isometry,
+};
+use nalgebra::Matrix2;
+use num::Zero;
+use opm_macros_lib::OpmNode;
+use uom::si::f64::{Energy, Length};
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("slategray")]
+/// An ideal (infinitely thin) pinhole.
+///
+/// The pinhole removes all rays hitting its surface outside of the given diameter. It is normally placed in the common
+/// focal plane of two lenses forming a spatial filter (see [`spatial_filter`]), where it clips high spatial-frequency
+/// content of the beam. The node keeps track of the incoming and the clipped energy during a ray tracing or ghost focus
+/// analysis and reports the clipped energy fraction.
+///
+/// During an energy analysis, which does not contain spatial information, the light is transmitted unmodified.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `diameter`
+pub struct Pinhole {
+    node_attr: NodeAttr,
+    incoming_energy: Energy,
+    clipped_energy: Energy,
+}
+unsafe impl Send for Pinhole {}
+
+impl Default for Pinhole {
+    /// Create a pinhole with a diameter of 1 mm.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("pinhole");
+        node_attr
+            .create_property(
+                "diameter",
+                "diameter of the pinhole",
+                millimeter!(1.0).into(),
+            )
+            .unwrap();
+        let mut pinhole = Self {
+            node_attr,
+            incoming_energy: Energy::zero(),
+            clipped_energy: Energy::zero(),
+        };
+        pinhole.update_surfaces().unwrap();
+        pinhole
+    }
+}
+impl Pinhole {
+    /// Creates a new [`Pinhole`] with the given diameter.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given diameter is not positive and finite.
+    pub fn new(name: &str, diameter: Length) -> OpmResult<Self> {
+        let mut pinhole = Self::default();
+        pinhole.set_diameter(diameter)?;
+        pinhole.node_attr.set_name(name);
+        Ok(pinhole)
+    }
+    /// Returns the diameter of this [`Pinhole`].
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the diameter property.
+    #[must_use]
+    pub fn diameter(&self) -> Length {
+        if let Ok(Proptype::Length(diameter)) = self.node_attr.get_property("diameter") {
+            *diameter
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the diameter of this [`Pinhole`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given diameter is not positive and finite.
+    pub fn set_diameter(&mut self, diameter: Length) -> OpmResult<()> {
+        if !diameter.is_normal() || diameter.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "pinhole diameter must be positive and finite".into(),
+            ));
+        }
+        self.node_attr.set_property("diameter", diameter.into())
+    }
+    /// Returns the fraction of the incoming energy clipped by this [`Pinhole`] during the last analysis.
+    ///
+    /// This function returns `None` if no energy has hit the pinhole (e.g. before an analysis or after an energy analysis).
+    #[must_use]
+    pub fn clipped_energy_fraction(&self) -> Option<f64> {
+        if self.incoming_energy.is_zero() {
+            None
+        } else {
+            Some((self.clipped_energy / self.incoming_energy).value)
+        }
+    }
+    /// Removes all rays outside the pinhole from the given bundle and accumulates the incoming and clipped energies.
+    fn clip(&mut self, rays: &mut Rays, iso: &Isometry) -> OpmResult<()> {
+        let aperture = Aperture::BinaryCircle(CircleConfig::new(
+            self.diameter() / 2.0,
+            millimeter!(0.0, 0.0),
+        )?);
+        let energy_before = rays.total_energy();
+        rays.apodize(&aperture, iso)?;
+        self.incoming_energy += energy_before;
+        self.clipped_energy += energy_before - rays.total_energy();
+        Ok(())
+    }
+}
+
+impl OpticNode for Pinhole {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        self.update_flat_single_surfaces()
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+    fn set_apodization_warning(&mut self, _apodized: bool) {}
+    fn reset_data(&mut self) {
+        self.incoming_energy = Energy::zero();
+        self.clipped_energy = Energy::zero();
+        self.reset_optic_surfaces();
+    }
+    fn node_report(&self, uuid: &str) -> Option<NodeReport> {
+        let mut props = Properties::default();
+        props
+            .create(
+                "Diameter",
+                "diameter of the pinhole",
+                self.diameter().into(),
+            )
+            .unwrap();
+        if let Some(fraction) = self.clipped_energy_fraction() {
+            props
+                .create(
+                    "Clipped energy",
+                    "energy clipped by the pinhole",
+                    self.clipped_energy.into(),
+                )
+                .unwrap();
+            props
+                .create(
+                    "Clipped energy fraction",
+                    "fraction of the incoming energy clipped by the pinhole",
+                    fraction.into(),
+                )
+                .unwrap();
+        } else {
+            props
+                .create(
+                    "Clipped energy fraction",
+                    "fraction of the incoming energy clipped by the pinhole",
+                    "no data".into(),
+                )
+                .unwrap();
+        }
+        Some(NodeReport::new(
+            &self.node_type(),
+            &self.name(),
+            uuid,
+            props,
+        ))
+    }
+}
+impl AnalysisGhostFocus for Pinhole {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let iso = self.effective_surface_iso(in_port)?;
+        let mut output =
+            AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)?;
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        if let Some(rays_bundles) = output.get_mut(out_port) {
+            for rays in rays_bundles {
+                self.clip(rays, &iso)?;
+            }
+            Ok(output)
+        } else {
+            Err(OpossumError::Analysis("clipping of rays failed".into()))
+        }
+    }
+}
+impl AnalysisGaussianBeam for Pinhole {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
+impl AnalysisEnergy for Pinhole {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        Ok(LightResult::from([(out_port.into(), input.clone())]))
+    }
+}
+impl AnalysisRayTrace for Pinhole {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        let LightData::Geometric(r) = input else {
+            return Err(OpossumError::Analysis(
+                "expected geometric light data".into(),
+            ));
+        };
+        let mut rays = r.clone();
+        let iso = self.effective_surface_iso(in_port)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis("no surface found. Aborting".into()));
+        };
+        let refraction_intended = true;
+        rays.refract_on_surface(
+            surf,
+            None,
+            refraction_intended,
+            config.missed_surface_strategy(),
+        )?;
+        self.clip(&mut rays, &iso)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let Some(surf) = self.get_optic_surface_mut(out_port) else {
+            return Err(OpossumError::OpticPort("output aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let light_data = LightData::Geometric(rays);
+        Ok(LightResult::from([(out_port.into(), light_data)]))
+    }
+}
+
+/// Create a vacuum spatial filter.
+///
+/// This is a convenience function, which generates a [`NodeGroup`] consisting of two ideal lenses
+/// ([`ParaxialSurface`]s) with the given (positive) focal lengths and a [`Pinhole`] of the given diameter in their common
+/// focal plane. The magnification of the filter is given by `focal_length_out / focal_length_in`. The group has the
+/// mapped ports `input_1` and `output_1`.
+///
+/// # Errors
+///
+/// This function will return an error if
+///   - one of the focal lengths is not positive and finite.
+///   - the pinhole diameter is not positive and finite.
+pub fn spatial_filter(
+    name: &str,
+    focal_length_in: Length,
+    focal_length_out: Length,
+    pinhole_diameter: Length,
+) -> OpmResult<NodeGroup> {
+    if !focal_length_in.is_normal()
+        || focal_length_in.is_sign_negative()
+        || !focal_length_out.is_normal()
+        || focal_length_out.is_sign_negative()
+    {
+        return Err(OpossumError::Other(
+            "focal lengths of a spatial filter must be positive and finite".into(),
+        ));
+    }
+    let mut group = NodeGroup::new(name);
+    let lens_in = group.add_node(ParaxialSurface::new("input lens", focal_length_in)?)?;
+    let pinhole = group.add_node(Pinhole::new("pinhole", pinhole_diameter)?)?;
+    let lens_out = group.add_node(ParaxialSurface::new("output lens", focal_length_out)?)?;
+    group.connect_nodes(lens_in, "output_1", pinhole, "input_1", focal_length_in)?;
+    group.connect_nodes(pinhole, "output_1", lens_out, "input_1", focal_length_out)?;
+    group.map_input_port(lens_in, "input_1", "input_1")?;
+    group.map_output_port(lens_out, "output_1", "output_1")?;
+    Ok(group)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, nanometer, nodes::test_helper::test_helper::*, optic_ports::PortType, ray::Ray,
+        spectrum_helper::create_he_ne_spec,
+    };
+    use approx::assert_abs_diff_eq;
+    use uom::si::energy::joule;
+    #[test]
+    fn default() {
+        let mut node = Pinhole::default();
+        assert_eq!(node.diameter(), millimeter!(1.0));
+        assert_eq!(node.name(), "pinhole");
+        assert_eq!(node.node_type(), "pinhole");
+        assert!(!node.inverted());
+        assert_eq!(node.node_color(), "slategray");
+        assert!(node.as_group_mut().is_err());
+        assert!(node.clipped_energy_fraction().is_none());
+    }
+    #[test]
+    fn new() {
+        assert!(Pinhole::new("test", millimeter!(0.0)).is_err());
+        assert!(Pinhole::new("test", millimeter!(-1.0)).is_err());
+        assert!(Pinhole::new("test", millimeter!(f64::NAN)).is_err());
+        assert!(Pinhole::new("test", millimeter!(f64::INFINITY)).is_err());
+        let node = Pinhole::new("test", millimeter!(0.1)).unwrap();
+        assert_eq!(node.name(), "test");
+        assert_eq!(node.diameter(), millimeter!(0.1));
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<Pinhole>()
+    }
+    #[test]
+    fn ports() {
+        let node = Pinhole::default();
+        assert_eq!(node.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(node.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<Pinhole>()
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<Pinhole>("input_1");
+    }
+    #[test]
+    fn analyze_energy() {
+        let mut node = Pinhole::default();
+        let mut input = LightResult::default();
+        input.insert(
+            "input_1".into(),
+            LightData::Energy(create_he_ne_spec(1.0).unwrap()),
+        );
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        let Some(LightData::Energy(s)) = output.get("output_1") else {
+            panic!("no energy light data found")
+        };
+        assert_abs_diff_eq!(s.total_energy(), 1.0);
+        assert!(node.clipped_energy_fraction().is_none());
+    }
+    #[test]
+    fn analyze_geometric() {
+        let mut node = Pinhole::new("test", millimeter!(2.0)).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let mut rays = Rays::default();
+        for y in [0.0, 0.5, 2.0, -3.0] {
+            rays.add_ray(
+                Ray::new_collimated(millimeter!(0., y, 0.), nanometer!(1054.0), joule!(1.0))
+                    .unwrap(),
+            );
+        }
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        assert_eq!(rays.nr_of_rays(true), 2);
+        assert_abs_diff_eq!(rays.total_energy().get::<joule>(), 2.0);
+        assert_abs_diff_eq!(node.clipped_energy_fraction().unwrap(), 0.5);
+        assert!(node.node_report("").is_some());
+        node.reset_data();
+        assert!(node.clipped_energy_fraction().is_none());
+    }
+    #[test]
+    fn spatial_filter_group() {
+        assert!(
+            spatial_filter(
+                "test",
+                millimeter!(0.0),
+                millimeter!(100.0),
+                millimeter!(0.1)
+            )
+            .is_err()
+        );
+        assert!(
+            spatial_filter(
+                "test",
+                millimeter!(100.0),
+                millimeter!(-100.0),
+                millimeter!(0.1)
+            )
+            .is_err()
+        );
+        assert!(
+            spatial_filter(
+                "test",
+                millimeter!(100.0),
+                millimeter!(200.0),
+                millimeter!(0.0)
+            )
+            .is_err()
+        );
+        let group = spatial_filter(
+            "test",
+            millimeter!(100.0),
+            millimeter!(200.0),
+            millimeter!(0.1),
+        )
+        .unwrap();
+        assert_eq!(group.name(), "test");
+        assert_eq!(group.nr_of_nodes(), 3);
+        assert_eq!(group.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(group.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+}
@@ -1,16 +1,19 @@
 #![warn(missing_docs)]
 use log::warn;
+use nalgebra::Matrix2;
+use num::Zero;
 use opm_macros_lib::OpmNode;
 use serde::{Deserialize, Serialize};
-use uom::si::length::nanometer;
+use uom::si::{f64::Length, length::nanometer};
 
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
         raytrace::AnalysisRayTrace,
     },
-    error::OpmResult,
+    error::{OpmResult, OpossumError},
     light_result::{LightRays, LightResult},
     lightdata::LightData,
     nanometer,
@@ -20,11 +23,16 @@ use crate::{
     properties::{Properties, Proptype},
     rays::Rays,
     reporting::node_report::NodeReport,
+    spectrum::Spectrum,
+};
+use std::{
+    fmt::{Debug, Display},
+    path::Path,
 };
-use std::fmt::{Debug, Display};
 
 #[non_exhaustive]
 #[derive(Debug, Default, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Type of the [`Spectrometer`]. This is currently not used.
 pub enum SpectrometerType {
     /// an ideal energy meter
@@ -64,6 +72,13 @@ impl From<Spectrometer> for Proptype {
 /// ## Properties
 ///   - `name`
 ///   - `spectrometer type`
+///   - `resolution`
+///   - `bin width`
+///
+/// A real spectrometer has a finite resolution. If a `resolution` is set, the incident spectrum is convolved with a Gaussian
+/// instrument function with the given FWHM. If a `bin width` is set, the (convolved) spectrum is finally integrated into
+/// wavelength bins of the given width (e.g. corresponding to the pixels of the detector). This way, simulated spectra become
+/// comparable to measured ones.
 ///
 /// During analysis, the output port contains a replica of the input port similar to a [`Dummy`](crate::nodes::Dummy) node. This way,
 /// different dectector nodes can be "stacked" or used somewhere within the optical setup.
@@ -87,6 +102,20 @@ impl Default for Spectrometer {
                 SpectrometerType::Ideal.into(),
             )
             .unwrap();
+        node_attr
+            .create_property(
+                "resolution",
+                "FWHM of the (Gaussian) instrument function (if any)",
+                Proptype::LengthOption(None),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "bin width",
+                "width of the wavelength bins of the recorded spectrum (if any)",
+                Proptype::LengthOption(None),
+            )
+            .unwrap();
         let mut spect = Self {
             light_data: None,
             node_attr,
@@ -145,6 +174,161 @@ impl Spectrometer {
             .set_property("spectrometer type", meter_type.into())?;
         Ok(())
     }
+    /// Returns the resolution (FWHM of the instrument function) of this [`Spectrometer`].
+    ///
+    /// `None` means an ideal spectrometer with infinite resolution.
+    /// # Panics
+    /// This function panics if
+    /// - the property "resolution" does not exist.
+    /// - the data format is wrong.
+    #[must_use]
+    pub fn resolution(&self) -> Option<Length> {
+        if let Ok(Proptype::LengthOption(resolution)) = self.node_attr.get_property("resolution") {
+            *resolution
+        } else {
+            panic!("wrong data format")
+        }
+    }
+    /// Sets the resolution (FWHM of the instrument function) of this [`Spectrometer`].
+    ///
+    /// If set, the recorded spectrum is convolved with a Gaussian instrument function of the given FWHM.
+    /// # Errors
+    /// This function returns an error if the given resolution is not positive and finite.
+    pub fn set_resolution(&mut self, resolution: Option<Length>) -> OpmResult<()> {
+        if let Some(resolution) = resolution
+            && (!resolution.is_finite() || resolution <= Length::zero())
+        {
+            return Err(OpossumError::Properties(
+                "resolution must be positive and finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("resolution", Proptype::LengthOption(resolution))
+    }
+    /// Returns the width of the wavelength bins of this [`Spectrometer`].
+    ///
+    /// `None` means that the spectrum is not binned.
+    /// # Panics
+    /// This function panics if
+    /// - the property "bin width" does not exist.
+    /// - the data format is wrong.
+    #[must_use]
+    pub fn bin_width(&self) -> Option<Length> {
+        if let Ok(Proptype::LengthOption(bin_width)) = self.node_attr.get_property("bin width") {
+            *bin_width
+        } else {
+            panic!("wrong data format")
+        }
+    }
+    /// Sets the width of the wavelength bins of this [`Spectrometer`].
+    ///
+    /// If set, the recorded spectrum is integrated into wavelength bins of the given width.
+    /// # Errors
+    /// This function returns an error if the given bin width is not positive and finite.
+    pub fn set_bin_width(&mut self, bin_width: Option<Length>) -> OpmResult<()> {
+        if let Some(bin_width) = bin_width
+            && (!bin_width.is_finite() || bin_width <= Length::zero())
+        {
+            return Err(OpossumError::Properties(
+                "bin width must be positive and finite".into(),
+            ));
+        }
+        self.node_attr
+            .set_property("bin width", Proptype::LengthOption(bin_width))
+    }
+    /// Returns the instrument function of this [`Spectrometer`] (if a resolution is set).
+    ///
+    /// The instrument function is a Gaussian with the given resolution as FWHM and unit area. Since only the shape of
+    /// the instrument function is relevant, it is centered at a wavelength of four times its FWHM and covers +/- two
+    /// times its FWHM.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the spectrum of the instrument function cannot be created.
+    pub fn instrument_function(&self) -> OpmResult<Option<Spectrum>> {
+        let Some(resolution) = self.resolution() else {
+            return Ok(None);
+        };
+        let mut kernel = Spectrum::new(2.0 * resolution..6.0 * resolution, resolution / 20.0)?;
+        kernel.add_gaussian_peak(4.0 * resolution, resolution, 1.0)?;
+        Ok(Some(kernel))
+    }
+    /// Applies the instrument function and the binning of this [`Spectrometer`] to the given (incident) spectrum.
+    fn apply_instrument(&self, spectrum: &Spectrum) -> OpmResult<Spectrum> {
+        let mut measured = spectrum.clone();
+        if let Some(kernel) = self.instrument_function()? {
+            measured.convolve(&kernel)?;
+        }
+        if let Some(bin_width) = self.bin_width() {
+            let range = measured.range();
+            let mut binned = Spectrum::new(range.start..range.end + bin_width, bin_width)?;
+            binned.resample(&measured);
+            measured = binned;
+        }
+        Ok(measured)
+    }
+    /// Returns the spectrum measured by this [`Spectrometer`] (if any).
+    ///
+    /// This is the spectrum of the recorded light data convolved with the instrument function and integrated into
+    /// the wavelength bins of this [`Spectrometer`] (if configured). If the instrument function cannot be applied, the
+    /// incident spectrum is returned and a warning is emitted.
+    #[must_use]
+    pub fn spectrum(&self) -> Option<Spectrum> {
+        let spectrum = self.incident_spectrum()?;
+        match self.apply_instrument(&spectrum) {
+            Ok(measured) => Some(measured),
+            Err(e) => {
+                warn!("could not apply instrument function of spectrometer: {e}");
+                Some(spectrum)
+            }
+        }
+    }
+    /// Returns the spectrum of the light data recorded by this [`Spectrometer`] (if any).
+    fn incident_spectrum(&self) -> Option<Spectrum> {
+        match self.light_data.as_ref()? {
+            LightData::Energy(s) => Some(s.clone()),
+            LightData::Geometric(r) => r.to_spectrum(&nanometer!(0.2)).ok(),
+            LightData::Fourier => None,
+            LightData::GhostFocus(r) => {
+                let mut all_rays = Rays::default();
+                for rays in r {
+                    all_rays.merge(rays);
+                }
+                all_rays.to_spectrum(&nanometer!(0.2)).ok()
+            }
+        }
+    }
+    /// Write the recorded spectrum of this [`Spectrometer`] to a CSV file.
+    ///
+    /// The file contains one line per wavelength slot with the columns `wavelength in nm` and
+    /// `spectral energy density in J/µm`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - no spectrum has been recorded.
+    ///   - the file could not be written.
+    pub fn to_csv(&self, path: &Path) -> OpmResult<()> {
+        let Some(spectrum) = self.spectrum() else {
+            return Err(OpossumError::Other(
+                "no spectrum available for csv export".into(),
+            ));
+        };
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        writer
+            .write_record(["wavelength in nm", "spectral energy density in J/µm"])
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        for (wavelength, value) in spectrum.iter() {
+            writer
+                .write_record([(wavelength * 1000.0).to_string(), value.to_string()])
+                .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
 }
 impl OpticNode for Spectrometer {
     fn set_apodization_warning(&mut self, apodized: bool) {
@@ -155,43 +339,47 @@ impl OpticNode for Spectrometer {
     }
     fn node_report(&self, uuid: &str) -> Option<NodeReport> {
         let mut props = Properties::default();
-        let data = &self.light_data;
-        if let Some(light_data) = data {
-            let spectrum = match light_data {
-                LightData::Energy(s) => Some(s.clone()),
-                LightData::Geometric(r) => r.to_spectrum(&nanometer!(0.2)).ok(),
-                LightData::Fourier => None,
-                LightData::GhostFocus(r) => {
-                    let mut all_rays = Rays::default();
-                    for rays in r {
-                        all_rays.merge(rays);
-                    }
-                    all_rays.to_spectrum(&nanometer!(0.2)).ok()
-                }
-            };
-            if spectrum.is_some() {
+        if self.spectrum().is_some() {
+            props
+                .create("Spectrum", "Output spectrum", self.clone().into())
+                .unwrap();
+            props
+                .create(
+                    "Model",
+                    "Spectrometer model",
+                    self.node_attr
+                        .get_property("spectrometer type")
+                        .unwrap()
+                        .clone(),
+                )
+                .unwrap();
+            if let Some(resolution) = self.resolution() {
                 props
-                    .create("Spectrum", "Output spectrum", self.clone().into())
+                    .create(
+                        "Instrument function",
+                        "instrument function of the spectrometer",
+                        format!("Gaussian (FWHM = {:.3} nm)", resolution.get::<nanometer>()).into(),
+                    )
                     .unwrap();
+            }
+            if let Some(bin_width) = self.bin_width() {
                 props
                     .create(
-                        "Model",
-                        "Spectrometer model",
-                        self.node_attr
-                            .get_property("spectrometer type")
-                            .unwrap()
-                            .clone(),
+                        "Bin width",
+                        "width of the wavelength bins",
+                        bin_width.into(),
                     )
                     .unwrap();
-                if self.apodization_warning {
-                    props
+            }
+            if self.apodization_warning {
+                props
                     .create(
                         "Warning",
                         "warning during analysis",
-                        "Rays have been apodized at input aperture. Results might not be accurate.".into(),
+                        "Rays have been apodized at input aperture. Results might not be accurate."
+                            .into(),
                     )
                     .unwrap();
-                }
             }
         }
         Some(NodeReport::new(
@@ -243,6 +431,11 @@ impl AnalysisGhostFocus for Spectrometer {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for Spectrometer {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for Spectrometer {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -291,12 +484,10 @@ impl Plottable for Spectrometer {
         plt_type: &mut PlotType,
         legend: bool,
     ) -> OpmResult<Option<Vec<PlotSeries>>> {
-        let data = &self.light_data;
-        match data {
-            Some(LightData::Geometric(rays)) => rays
-                .to_spectrum(&nanometer!(0.2))?
-                .get_plot_series(plt_type, legend),
-            Some(LightData::Energy(s)) => s.get_plot_series(plt_type, legend),
+        match &self.light_data {
+            Some(LightData::Geometric(_) | LightData::Energy(_)) => self
+                .spectrum()
+                .map_or(Ok(None), |s| s.get_plot_series(plt_type, legend)),
             _ => Ok(None),
         }
     }
@@ -313,6 +504,7 @@ mod test {
         rays::Rays,
         spectrum_helper::{create_he_ne_spec, create_visible_spec},
     };
+    use approx::assert_relative_eq;
     use num::Zero;
     use uom::si::f64::Length;
 
@@ -447,4 +639,116 @@ mod test {
         assert!(node_props.contains("Spectrum"));
         assert!(node_props.contains("Model"));
     }
+    #[test]
+    fn to_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("spectrum.csv");
+        let mut node = Spectrometer::default();
+        assert!(node.to_csv(&path).is_err());
+        node.light_data = Some(LightData::Energy(create_he_ne_spec(1.0).unwrap()));
+        node.to_csv(&path).unwrap();
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["wavelength in nm", "spectral energy density in J/µm"]
+        );
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        let spectrum = create_he_ne_spec(1.0).unwrap();
+        assert_eq!(records.len(), spectrum.iter().count());
+        let first_wavelength: f64 = records[0][0].parse().unwrap();
+        assert_relative_eq!(first_wavelength, spectrum.range().start.get::<nanometer>());
+    }
+    #[test]
+    fn set_resolution() {
+        let mut meter = Spectrometer::default();
+        assert!(meter.resolution().is_none());
+        assert!(meter.set_resolution(Some(nanometer!(0.0))).is_err());
+        assert!(meter.set_resolution(Some(nanometer!(-1.0))).is_err());
+        assert!(meter.set_resolution(Some(nanometer!(f64::NAN))).is_err());
+        meter.set_resolution(Some(nanometer!(2.0))).unwrap();
+        assert_eq!(meter.resolution(), Some(nanometer!(2.0)));
+        meter.set_resolution(None).unwrap();
+        assert!(meter.resolution().is_none());
+    }
+    #[test]
+    fn set_bin_width() {
+        let mut meter = Spectrometer::default();
+        assert!(meter.bin_width().is_none());
+        assert!(meter.set_bin_width(Some(nanometer!(0.0))).is_err());
+        assert!(meter.set_bin_width(Some(nanometer!(-1.0))).is_err());
+        assert!(
+            meter
+                .set_bin_width(Some(nanometer!(f64::INFINITY)))
+                .is_err()
+        );
+        meter.set_bin_width(Some(nanometer!(1.0))).unwrap();
+        assert_eq!(meter.bin_width(), Some(nanometer!(1.0)));
+        meter.set_bin_width(None).unwrap();
+        assert!(meter.bin_width().is_none());
+    }
+    #[test]
+    fn instrument_function() {
+        let mut meter = Spectrometer::default();
+        assert!(meter.instrument_function().unwrap().is_none());
+        meter.set_resolution(Some(nanometer!(2.0))).unwrap();
+        let kernel = meter.instrument_function().unwrap().unwrap();
+        assert_relative_eq!(
+            kernel.center_wavelength().get::<nanometer>(),
+            8.0,
+            max_relative = 0.01
+        );
+        assert_relative_eq!(
+            kernel.fwhm().unwrap().get::<nanometer>(),
+            2.0,
+            max_relative = 0.05
+        );
+    }
+    #[test]
+    fn spectrum_with_resolution() {
+        let mut meter = Spectrometer::default();
+        let incident = create_he_ne_spec(1.0).unwrap();
+        meter.light_data = Some(LightData::Energy(incident.clone()));
+        assert_eq!(meter.spectrum().unwrap(), incident);
+        meter.set_resolution(Some(nanometer!(2.0))).unwrap();
+        let measured = meter.spectrum().unwrap();
+        assert_relative_eq!(
+            measured.total_energy(),
+            incident.total_energy(),
+            max_relative = 1e-6
+        );
+        let fwhm = measured.fwhm().unwrap().get::<nanometer>();
+        assert!(fwhm > 1.5 && fwhm < 2.5);
+    }
+    #[test]
+    fn spectrum_with_bin_width() {
+        let mut meter = Spectrometer::default();
+        let mut incident = create_visible_spec();
+        incident.add_single_peak(nanometer!(500.0), 1.0).unwrap();
+        meter.light_data = Some(LightData::Energy(incident.clone()));
+        meter.set_bin_width(Some(nanometer!(1.0))).unwrap();
+        let measured = meter.spectrum().unwrap();
+        assert!(measured.iter().count() < incident.iter().count());
+        assert_relative_eq!(
+            measured.average_resolution().get::<nanometer>(),
+            1.0,
+            max_relative = 0.01
+        );
+        assert_relative_eq!(
+            measured.total_energy(),
+            incident.total_energy(),
+            max_relative = 1e-3
+        );
+    }
+    #[test]
+    fn report_instrument() {
+        let mut meter = Spectrometer::default();
+        meter.set_resolution(Some(nanometer!(2.0))).unwrap();
+        meter.set_bin_width(Some(nanometer!(1.0))).unwrap();
+        meter.light_data = Some(LightData::Energy(create_he_ne_spec(1.0).unwrap()));
+        let node_report = meter.node_report("").unwrap();
+        let node_props = node_report.properties();
+        assert!(node_props.contains("Spectrum"));
+        assert!(node_props.contains("Instrument function"));
+        assert!(node_props.contains("Bin width"));
+    }
 }
@@ -1,21 +1,24 @@
 #![warn(missing_docs)]
 use log::warn;
-use nalgebra::{DVector, MatrixXx2};
+use nalgebra::{DVector, Matrix2, MatrixXx2, Point2};
 use opm_macros_lib::OpmNode;
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use uom::si::{
+    energy::joule,
     f64::Length,
-    length::{meter, nanometer},
+    length::{meter, millimeter, nanometer},
 };
 
 use super::node_attr::NodeAttr;
 use crate::{
     analyzers::{
-        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
         raytrace::AnalysisRayTrace,
     },
-    error::OpmResult,
+    error::{OpmResult, OpossumError},
     light_result::{LightRays, LightResult},
     lightdata::LightData,
     nanometer,
@@ -80,6 +83,45 @@ impl Default for SpotDiagram {
         sd
     }
 }
+/// Summary metrics of a spot diagram.
+///
+/// All metrics are calculated from the positions of the `valid` rays in the local coordinate system of the detector
+/// surface. As common in optical design, the rays are not weighted by their energy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotMetrics {
+    centroid: Point2<Length>,
+    rms_radius: Length,
+    geometric_radius: Length,
+}
+impl SpotMetrics {
+    /// Calculate the [`SpotMetrics`] of the given ray bundle.
+    ///
+    /// This function returns `None` if the ray bundle does not contain any valid rays.
+    #[must_use]
+    pub fn from_rays(rays: &Rays) -> Option<Self> {
+        let centroid = rays.centroid()?;
+        Some(Self {
+            centroid: Point2::new(centroid.x, centroid.y),
+            rms_radius: rays.beam_radius_rms()?,
+            geometric_radius: rays.beam_radius_geo()?,
+        })
+    }
+    /// Returns the centroid of the spots.
+    #[must_use]
+    pub const fn centroid(&self) -> Point2<Length> {
+        self.centroid
+    }
+    /// Returns the rms spot radius (with respect to the centroid).
+    #[must_use]
+    pub const fn rms_radius(&self) -> Length {
+        self.rms_radius
+    }
+    /// Returns the geometric spot radius, i.e. the maximum distance of a spot from the centroid.
+    #[must_use]
+    pub const fn geometric_radius(&self) -> Length {
+        self.geometric_radius
+    }
+}
 impl SpotDiagram {
     /// Creates a new [`SpotDiagram`].
     /// # Attributes
@@ -93,6 +135,94 @@ impl SpotDiagram {
         sd.update_surfaces().unwrap();
         sd
     }
+    /// Returns the recorded rays transformed into the local coordinate system of the detector surface.
+    fn transformed_rays(&self) -> Option<Rays> {
+        let Some(LightData::Geometric(rays)) = &self.light_data else {
+            return None;
+        };
+        let iso = self
+            .effective_surface_iso("input_1")
+            .unwrap_or_else(|_| Isometry::identity());
+        let mut transformed_rays = Rays::default();
+        for ray in rays {
+            transformed_rays.add_ray(ray.inverse_transformed_ray(&iso));
+        }
+        Some(transformed_rays)
+    }
+    /// Returns the [`SpotMetrics`] (centroid, rms and geometric spot radius) of all recorded rays.
+    ///
+    /// This function returns `None` if no valid rays have been recorded.
+    #[must_use]
+    pub fn spot_metrics(&self) -> Option<SpotMetrics> {
+        self.transformed_rays()
+            .and_then(|rays| SpotMetrics::from_rays(&rays))
+    }
+    /// Returns the [`SpotMetrics`] of the recorded rays separately for each wavelength.
+    ///
+    /// The rays are grouped by wavelength using the same binning (0.2 nm) as the spot diagram plot. The result
+    /// contains the (center) wavelength of each group together with its metrics. It is empty if no valid rays have been
+    /// recorded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the rays cannot be grouped by wavelength.
+    pub fn spot_metrics_per_wavelength(&self) -> OpmResult<Vec<(Length, SpotMetrics)>> {
+        let Some(rays) = self.transformed_rays() else {
+            return Ok(Vec::new());
+        };
+        if rays.nr_of_rays(true) == 0 {
+            return Ok(Vec::new());
+        }
+        let (split_rays_bundles, wavelengths) =
+            rays.split_ray_bundle_by_wavelength(nanometer!(0.2), true)?;
+        Ok(wavelengths
+            .into_iter()
+            .zip(split_rays_bundles.iter())
+            .filter_map(|(wavelength, rays)| {
+                SpotMetrics::from_rays(rays).map(|metrics| (wavelength, metrics))
+            })
+            .collect())
+    }
+    /// Write the recorded spots of this [`SpotDiagram`] to a CSV file.
+    ///
+    /// The file contains one line per valid ray with the columns `x in mm`, `y in mm` (in the local coordinate
+    /// system of the detector surface), `wavelength in nm` and `energy in J`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - no ray data has been recorded.
+    ///   - the file could not be written.
+    pub fn to_csv(&self, path: &Path) -> OpmResult<()> {
+        let Some(LightData::Geometric(rays)) = &self.light_data else {
+            return Err(OpossumError::Other(
+                "no ray data available for csv export".into(),
+            ));
+        };
+        let iso = self
+            .effective_surface_iso("input_1")
+            .unwrap_or_else(|_| Isometry::identity());
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        writer
+            .write_record(["x in mm", "y in mm", "wavelength in nm", "energy in J"])
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        for ray in rays.iter().filter(|r| r.valid()) {
+            let position = ray.inverse_transformed_ray(&iso).position();
+            writer
+                .write_record([
+                    position.x.get::<millimeter>().to_string(),
+                    position.y.get::<millimeter>().to_string(),
+                    ray.wavelength().get::<nanometer>().to_string(),
+                    ray.energy().get::<joule>().to_string(),
+                ])
+                .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
 }
 impl OpticNode for SpotDiagram {
     fn set_apodization_warning(&mut self, apodized: bool) {
@@ -100,15 +230,7 @@ impl OpticNode for SpotDiagram {
     }
     fn node_report(&self, uuid: &str) -> Option<NodeReport> {
         let mut props = Properties::default();
-        let data = &self.light_data;
-        if let Some(LightData::Geometric(rays)) = data {
-            let mut transformed_rays = Rays::default();
-            let iso = self
-                .effective_surface_iso("input_1")
-                .unwrap_or_else(|_| Isometry::identity());
-            for ray in rays {
-                transformed_rays.add_ray(ray.inverse_transformed_ray(&iso));
-            }
+        if let Some(transformed_rays) = self.transformed_rays() {
             props
                 .create("Spot diagram", "2D spot diagram", self.clone().into())
                 .unwrap();
@@ -143,6 +265,77 @@ impl OpticNode for SpotDiagram {
                     )
                     .unwrap();
             }
+            if let Some(focus) = transformed_rays.focus_parameters()
+                && focus.geometric_rayleigh_range().is_finite()
+            {
+                props
+                    .create(
+                        "waist position",
+                        "position of the best focus relative to the detector plane",
+                        focus.waist_position().into(),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "waist radius",
+                        "rms beam radius at the best focus",
+                        focus.rms_waist_radius().into(),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "depth of focus",
+                        "geometric depth of focus at the best focus",
+                        focus.depth_of_focus().into(),
+                    )
+                    .unwrap();
+                if let Some(wavelength) = transformed_rays.central_wavelength()
+                    && let Ok(rayleigh_range) = focus.rayleigh_range(wavelength)
+                {
+                    props
+                        .create(
+                            "Rayleigh range",
+                            "Rayleigh range of a Gaussian beam with the same waist",
+                            rayleigh_range.into(),
+                        )
+                        .unwrap();
+                }
+            }
+            if let Ok(metrics) = self.spot_metrics_per_wavelength()
+                && metrics.len() > 1
+            {
+                for (wavelength, metrics) in metrics {
+                    let wvl = format!("{:.1} nm", wavelength.get::<nanometer>());
+                    props
+                        .create(
+                            &format!("centroid x ({wvl})"),
+                            "x position of the spot centroid at the given wavelength",
+                            metrics.centroid().x.into(),
+                        )
+                        .unwrap();
+                    props
+                        .create(
+                            &format!("centroid y ({wvl})"),
+                            "y position of the spot centroid at the given wavelength",
+                            metrics.centroid().y.into(),
+                        )
+                        .unwrap();
+                    props
+                        .create(
+                            &format!("rms spot radius ({wvl})"),
+                            "rms spot radius at the given wavelength",
+                            metrics.rms_radius().into(),
+                        )
+                        .unwrap();
+                    props
+                        .create(
+                            &format!("geo spot radius ({wvl})"),
+                            "geometric spot radius at the given wavelength",
+                            metrics.geometric_radius().into(),
+                        )
+                        .unwrap();
+                }
+            }
             if self.apodization_warning {
                 props
                     .create(
@@ -176,6 +369,11 @@ impl OpticNode for SpotDiagram {
         self.update_flat_single_surfaces()
     }
 }
+impl AnalysisGaussianBeam for SpotDiagram {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for SpotDiagram {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -369,9 +567,11 @@ mod test {
     use super::*;
     use crate::optic_ports::PortType;
     use crate::{
-        joule, nodes::test_helper::test_helper::*, position_distributions::Hexapolar, rays::Rays,
-        spectrum_helper::create_he_ne_spec,
+        joule, millimeter, nodes::test_helper::test_helper::*, position_distributions::Hexapolar,
+        ray::Ray, rays::Rays, spectrum_helper::create_he_ne_spec,
     };
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
     use uom::num_traits::Zero;
 
     #[test]
@@ -508,4 +708,99 @@ mod test {
         let nr_of_props = node_props.iter().fold(0, |c, _p| c + 1);
         assert_eq!(nr_of_props, 5);
     }
+    #[test]
+    fn report_focus() {
+        let mut sd = SpotDiagram::default();
+        let mut rays = Rays::default();
+        for (x, y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            let dir = Vector3::new(-x / 100.0 - 0.01 * y, -y / 100.0 + 0.01 * x, 1.0);
+            rays.add_ray(
+                Ray::new(millimeter!(x, y, 0.0), dir, nanometer!(1000.0), joule!(1.0)).unwrap(),
+            );
+        }
+        sd.light_data = Some(LightData::Geometric(rays));
+        let node_report = sd.node_report("").unwrap();
+        let node_props = node_report.properties();
+        let Ok(Proptype::Length(waist_position)) = node_props.get("waist position") else {
+            panic!("waist position not found");
+        };
+        assert_relative_eq!(waist_position.value, 0.05);
+        assert!(node_props.contains("waist radius"));
+        assert!(node_props.contains("depth of focus"));
+        assert!(node_props.contains("Rayleigh range"));
+    }
+    #[test]
+    fn spot_metrics() {
+        let mut sd = SpotDiagram::default();
+        assert!(sd.spot_metrics().is_none());
+        assert!(sd.spot_metrics_per_wavelength().unwrap().is_empty());
+        let mut rays = Rays::default();
+        for (x, y) in [(2.0, 1.0), (0.0, 1.0), (1.0, 2.0), (1.0, 0.0)] {
+            rays.add_ray(
+                Ray::new_collimated(millimeter!(x, y, 0.0), nanometer!(1000.0), joule!(1.0))
+                    .unwrap(),
+            );
+        }
+        sd.light_data = Some(LightData::Geometric(rays.clone()));
+        let metrics = sd.spot_metrics().unwrap();
+        assert_relative_eq!(metrics.centroid().x.value, 0.001);
+        assert_relative_eq!(metrics.centroid().y.value, 0.001);
+        assert_relative_eq!(metrics.rms_radius().value, 0.001);
+        assert_relative_eq!(metrics.geometric_radius().value, 0.001);
+        assert_eq!(sd.spot_metrics_per_wavelength().unwrap().len(), 1);
+        // polychromatic
+        for (x, y) in [(3.0, 0.0), (-3.0, 0.0)] {
+            rays.add_ray(
+                Ray::new_collimated(millimeter!(x, y, 0.0), nanometer!(500.0), joule!(1.0))
+                    .unwrap(),
+            );
+        }
+        sd.light_data = Some(LightData::Geometric(rays));
+        let per_wavelength = sd.spot_metrics_per_wavelength().unwrap();
+        assert_eq!(per_wavelength.len(), 2);
+        let (wvl, metrics) = per_wavelength[0];
+        assert_relative_eq!(wvl.get::<nanometer>(), 500.0, max_relative = 1e-3);
+        assert!(metrics.centroid().x.value.abs() < 1e-12);
+        assert_relative_eq!(metrics.rms_radius().value, 0.003);
+        assert_relative_eq!(metrics.geometric_radius().value, 0.003);
+        let (wvl, metrics) = per_wavelength[1];
+        assert_relative_eq!(wvl.get::<nanometer>(), 1000.0, max_relative = 1e-3);
+        assert_relative_eq!(metrics.rms_radius().value, 0.001);
+        let node_report = sd.node_report("").unwrap();
+        let node_props = node_report.properties();
+        assert!(node_props.contains("rms spot radius (500.0 nm)"));
+        assert!(node_props.contains("geo spot radius (1000.0 nm)"));
+        assert!(node_props.contains("centroid x (1000.0 nm)"));
+        assert!(node_props.contains("centroid y (500.0 nm)"));
+    }
+    #[test]
+    fn to_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("spots.csv");
+        let mut sd = SpotDiagram::default();
+        assert!(sd.to_csv(&path).is_err());
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new(
+                millimeter!(1.0, 2.0, 0.0),
+                Vector3::z(),
+                nanometer!(1000.0),
+                joule!(0.5),
+            )
+            .unwrap(),
+        );
+        let mut invalid_ray = Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap();
+        invalid_ray.set_invalid();
+        rays.add_ray(invalid_ray);
+        sd.light_data = Some(LightData::Geometric(rays));
+        sd.to_csv(&path).unwrap();
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["x in mm", "y in mm", "wavelength in nm", "energy in J"]
+        );
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0], vec!["1", "2", "1000", "0.5"]);
+    }
 }
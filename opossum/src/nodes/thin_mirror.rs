@@ -7,25 +7,34 @@ use crate::{
     analyzers::{
         GhostFocusConfig, RayTraceConfig,
         energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
         ghostfocus::AnalysisGhostFocus,
         raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
     },
+    aperture::Aperture,
     coatings::CoatingType,
     error::{OpmResult, OpossumError},
+    joule,
     light_result::{LightRays, LightResult},
     lightdata::LightData,
-    meter, millimeter,
+    meter, micrometer, millimeter,
     optic_node::OpticNode,
     optic_ports::PortType,
-    properties::Proptype,
+    properties::{Properties, Proptype},
     radian,
+    ray::Ray,
     rays::Rays,
+    reporting::node_report::NodeReport,
     surface::{Plane, Sphere, geo_surface::GeoSurfaceRef},
     utils::geom_transformation::Isometry,
 };
+use nalgebra::{Matrix2, Vector3};
 use num::Zero;
 use opm_macros_lib::OpmNode;
-use uom::si::f64::Length;
+use uom::si::{
+    f64::{Energy, Length},
+    length::meter,
+};
 
 #[derive(OpmNode, Debug, Clone)]
 #[opm_node("aliceblue")]
@@ -34,6 +43,10 @@ use uom::si::f64::Length;
 /// Curvature convention:
 /// - negative curvature will be a concave (focusing) mirror
 /// - positive curvature will be a convex (defocusing) mirror
+///
+/// The reflectivity of the mirror is given by the coating of its surface (by default a perfect mirror with R = 1.0). The
+/// energy which is not reflected is considered to be absorbed by the mirror. The absorbed energy is summed up during an
+/// analysis and given in the node report (e.g. for estimating the thermal load).
 /// ## Optical Ports
 ///   - Inputs
 ///     - `in1`
@@ -46,6 +59,7 @@ use uom::si::f64::Length;
 ///   - `curvature`
 pub struct ThinMirror {
     node_attr: NodeAttr,
+    absorbed_energy: Option<Energy>,
 }
 unsafe impl Send for ThinMirror {}
 
@@ -61,7 +75,10 @@ impl Default for ThinMirror {
             )
             .unwrap();
 
-        let mut m = Self { node_attr };
+        let mut m = Self {
+            node_attr,
+            absorbed_energy: None,
+        };
         m.update_surfaces().unwrap();
         m.ports_mut()
             .set_coating(
@@ -110,6 +127,85 @@ impl ThinMirror {
         self.update_surfaces()?;
         Ok(self)
     }
+    /// Modifies the clear aperture of a [`ThinMirror`].
+    ///
+    /// The given [`Aperture`] (e.g. [`Aperture::BinaryRectangle`] or [`Aperture::BinaryEllipse`]) is applied to the mirror
+    /// surface. Rays outside the clear aperture are vignetted during analysis. This function can be used with the "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ports of the mirror cannot be found.
+    pub fn with_clear_aperture(mut self, aperture: &Aperture) -> OpmResult<Self> {
+        self.set_aperture(&PortType::Input, "input_1", aperture)?;
+        self.set_aperture(&PortType::Output, "output_1", aperture)?;
+        Ok(self)
+    }
+    /// Modifies the (constant) reflectivity of a [`ThinMirror`].
+    ///
+    /// This function sets a [`CoatingType::ConstantR`] coating with the given reflectivity on the mirror surface. The
+    /// remaining energy is absorbed by the mirror. This function can be used with the "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given reflectivity is outside the interval [0.0, 1.0] or not finite.
+    pub fn with_reflectivity(self, reflectivity: f64) -> OpmResult<Self> {
+        if !(0.0..=1.0).contains(&reflectivity) {
+            return Err(OpossumError::Other(
+                "reflectivity must be within the interval [0.0, 1.0]".into(),
+            ));
+        }
+        self.with_coating(&CoatingType::ConstantR { reflectivity })
+    }
+    /// Modifies the coating of a [`ThinMirror`].
+    ///
+    /// The given coating determines the (possibly wavelength- and angle-dependent) reflectivity of the mirror. This
+    /// function can be used with the "builder pattern".
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the ports of the mirror cannot be found.
+    pub fn with_coating(mut self, coating: &CoatingType) -> OpmResult<Self> {
+        self.ports_mut()
+            .set_coating(&PortType::Input, "input_1", coating)?;
+        self.ports_mut()
+            .set_coating(&PortType::Output, "output_1", coating)?;
+        Ok(self)
+    }
+    /// Returns the energy absorbed by this [`ThinMirror`] during the last analysis.
+    ///
+    /// `None` is returned if no analysis has been performed yet.
+    #[must_use]
+    pub const fn absorbed_energy(&self) -> Option<Energy> {
+        self.absorbed_energy
+    }
+    /// Returns the reflectivity of the mirror coating at normal incidence for the given wavelength.
+    fn reflectivity(&self, port_name: &str, wavelength: Length) -> OpmResult<f64> {
+        let ports = self.ports();
+        let Some(coating) = ports.coating(&PortType::Input, port_name) else {
+            return Ok(1.0);
+        };
+        let ray = Ray::origin_along_z(wavelength, joule!(1.0))?;
+        coating.calc_reflectivity(&ray, Vector3::new(0.0, 0.0, -1.0), ray.refractive_index())
+    }
+    fn add_absorbed_energy(&mut self, energy: Energy) {
+        self.absorbed_energy = Some(self.absorbed_energy.unwrap_or_else(Energy::zero) + energy);
+    }
+}
+/// Returns the energy of all rays which hit the mirror surface but were not reflected.
+///
+/// Rays missing the surface are either invalidated or left unmodified and hence not taken into account.
+fn non_reflected_energy(incoming: &Rays, transmitted: &Rays) -> Energy {
+    incoming
+        .iter()
+        .zip(transmitted.iter())
+        .filter(|(incoming, transmitted)| {
+            transmitted.valid()
+                && (transmitted.position() != incoming.position()
+                    || transmitted.energy() != incoming.energy())
+        })
+        .fold(Energy::zero(), |sum, (_, transmitted)| {
+            sum + transmitted.energy()
+        })
 }
 impl OpticNode for ThinMirror {
     fn node_attr(&self) -> &NodeAttr {
@@ -118,6 +214,27 @@ impl OpticNode for ThinMirror {
     fn node_attr_mut(&mut self) -> &mut NodeAttr {
         &mut self.node_attr
     }
+    fn reset_data(&mut self) {
+        self.absorbed_energy = None;
+        self.reset_optic_surfaces();
+    }
+    fn node_report(&self, uuid: &str) -> Option<NodeReport> {
+        let absorbed_energy = self.absorbed_energy?;
+        let mut props = Properties::default();
+        props
+            .create(
+                "Absorbed energy",
+                "energy absorbed by the mirror (not reflected)",
+                absorbed_energy.into(),
+            )
+            .unwrap();
+        Some(NodeReport::new(
+            &self.node_type(),
+            &self.name(),
+            uuid,
+            props,
+        ))
+    }
     fn update_surfaces(&mut self) -> OpmResult<()> {
         let node_iso = self.effective_node_iso().unwrap_or_else(Isometry::identity);
         let Ok(Proptype::Length(curvature)) = self.node_attr.get_property("curvature") else {
@@ -196,6 +313,20 @@ impl AnalysisGhostFocus for ThinMirror {
         Ok(out_light_rays)
     }
 }
+impl AnalysisGaussianBeam for ThinMirror {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        let Ok(Proptype::Length(curvature)) = self.node_attr.get_property("curvature") else {
+            return Err(OpossumError::Analysis("cannot read curvature".into()));
+        };
+        // a concave (focusing) mirror has a negative curvature
+        Ok(Some(Matrix2::new(
+            1.0,
+            0.0,
+            2.0 / curvature.get::<meter>(),
+            1.0,
+        )))
+    }
+}
 impl AnalysisEnergy for ThinMirror {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -203,7 +334,23 @@ impl AnalysisEnergy for ThinMirror {
         let Some(data) = incoming_data.get(in_port) else {
             return Ok(LightResult::default());
         };
-        Ok(LightResult::from([(out_port.into(), data.clone())]))
+        let LightData::Energy(spectrum) = data else {
+            return Ok(LightResult::from([(out_port.into(), data.clone())]));
+        };
+        let reflectivities = spectrum
+            .iter()
+            .map(|(wavelength, _)| self.reflectivity(in_port, micrometer!(*wavelength)))
+            .collect::<OpmResult<Vec<f64>>>()?;
+        let mut reflectivities = reflectivities.into_iter();
+        let mut reflected = spectrum.clone();
+        reflected.map_mut(|(wavelength, value)| {
+            (*wavelength, *value * reflectivities.next().unwrap_or(1.0))
+        });
+        self.add_absorbed_energy(joule!(spectrum.total_energy() - reflected.total_energy()));
+        Ok(LightResult::from([(
+            out_port.into(),
+            LightData::Energy(reflected),
+        )]))
     }
 }
 impl AnalysisRayTrace for ThinMirror {
@@ -218,6 +365,8 @@ impl AnalysisRayTrace for ThinMirror {
             return Ok(LightResult::default());
         };
         if let LightData::Geometric(mut rays) = data.clone() {
+            let iso = self.effective_surface_iso(in_port)?;
+            let incoming_rays = rays.clone();
             let reflected = if let Some(surf) = self.get_optic_surface_mut(in_port) {
                 let refraction_intended = false;
                 let mut reflected_rays = rays.refract_on_surface(
@@ -226,20 +375,13 @@ impl AnalysisRayTrace for ThinMirror {
                     refraction_intended,
                     config.missed_surface_strategy(),
                 )?;
-                match self.ports().aperture(&PortType::Input, in_port) {
-                    Some(aperture) => {
-                        reflected_rays.apodize(aperture, &self.effective_surface_iso(in_port)?)?;
-                        reflected_rays
-                            .invalidate_by_threshold_energy(config.min_energy_per_ray())?;
-                        reflected_rays
-                    }
-                    _ => {
-                        return Err(OpossumError::OpticPort("input aperture not found".into()));
-                    }
-                }
+                surf.apodize_rays(&mut reflected_rays, &iso)?;
+                reflected_rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+                reflected_rays
             } else {
                 return Err(OpossumError::Analysis("no surface found. Aborting".into()));
             };
+            self.add_absorbed_energy(non_reflected_energy(&incoming_rays, &rays));
             let light_data = LightData::Geometric(reflected);
             let light_result = LightResult::from([(out_port.into(), light_data)]);
             Ok(light_result)
@@ -263,11 +405,12 @@ impl AnalysisRayTrace for ThinMirror {
 mod test {
     use super::*;
     use crate::{
-        analyzers::RayTraceConfig, degree, joule, nanometer, nodes::test_helper::test_helper::*,
-        optic_ports::PortType, ray::Ray, rays::Rays, spectrum_helper::create_he_ne_spec,
-        utils::geom_transformation::Isometry,
+        analyzers::RayTraceConfig, aperture::RectangleConfig, degree, joule, nanometer,
+        nodes::test_helper::test_helper::*, optic_ports::PortType, ray::Ray, rays::Rays,
+        spectrum_helper::create_he_ne_spec, utils::geom_transformation::Isometry,
     };
     use nalgebra::vector;
+    use uom::si::energy::joule;
     #[test]
     fn default() {
         let node = ThinMirror::default();
@@ -303,6 +446,22 @@ mod test {
         test_set_aperture::<ThinMirror>("input_1", "output_1");
     }
     #[test]
+    fn with_clear_aperture() {
+        let ap = Aperture::BinaryRectangle(
+            RectangleConfig::new(millimeter!(10.0), millimeter!(5.0), millimeter!(0.0, 0.0))
+                .unwrap(),
+        );
+        let node = ThinMirror::default().with_clear_aperture(&ap).unwrap();
+        assert!(matches!(
+            node.ports().aperture(&PortType::Input, "input_1"),
+            Some(Aperture::BinaryRectangle(_))
+        ));
+        assert!(matches!(
+            node.ports().aperture(&PortType::Output, "output_1"),
+            Some(Aperture::BinaryRectangle(_))
+        ));
+    }
+    #[test]
     fn inverted() {
         test_inverted::<ThinMirror>()
     }
@@ -397,4 +556,60 @@ mod test {
             assert!(false, "could not get LightData");
         }
     }
+    #[test]
+    fn with_reflectivity() {
+        assert!(ThinMirror::default().with_reflectivity(-0.1).is_err());
+        assert!(ThinMirror::default().with_reflectivity(1.1).is_err());
+        assert!(ThinMirror::default().with_reflectivity(f64::NAN).is_err());
+        let node = ThinMirror::default().with_reflectivity(0.9).unwrap();
+        assert!(matches!(
+            node.ports().coating(&PortType::Input, "input_1"),
+            Some(CoatingType::ConstantR { reflectivity }) if *reflectivity == 0.9
+        ));
+        assert!(matches!(
+            node.ports().coating(&PortType::Output, "output_1"),
+            Some(CoatingType::ConstantR { reflectivity }) if *reflectivity == 0.9
+        ));
+    }
+    #[test]
+    fn analyze_energy_absorbed() {
+        let mut node = ThinMirror::default().with_reflectivity(0.9).unwrap();
+        assert!(node.absorbed_energy().is_none());
+        assert!(node.node_report("").is_none());
+        let mut input = LightResult::default();
+        let spectrum = create_he_ne_spec(1.0).unwrap();
+        input.insert("input_1".into(), LightData::Energy(spectrum.clone()));
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        let Some(LightData::Energy(reflected)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        assert!((reflected.total_energy() - 0.9 * spectrum.total_energy()).abs() < 1.0e-12);
+        let absorbed = node.absorbed_energy().unwrap();
+        assert!((absorbed.get::<joule>() - 0.1 * spectrum.total_energy()).abs() < 1.0e-12);
+        let report = node.node_report("").unwrap();
+        assert!(report.properties().contains("Absorbed energy"));
+        node.reset_data();
+        assert!(node.absorbed_energy().is_none());
+    }
+    #[test]
+    fn analyze_geometric_absorbed() {
+        let mut node = ThinMirror::default().with_reflectivity(0.8).unwrap();
+        node.set_isometry(
+            Isometry::new(millimeter!(0.0, 0.0, 10.0), degree!(0.0, 0.0, 0.0)).unwrap(),
+        )
+        .unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap());
+        rays.add_ray(Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap());
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output =
+            AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        assert!((rays.total_energy().get::<joule>() - 1.6).abs() < 1.0e-12);
+        let absorbed = node.absorbed_energy().unwrap();
+        assert!((absorbed.get::<joule>() - 0.4).abs() < 1.0e-12);
+    }
 }
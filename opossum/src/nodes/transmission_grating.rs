@@ -0,0 +1,389 @@
+#![warn(missing_docs)]
+//! Infinitely thin transmission grating
+use std::f64::consts::PI;
+
+use super::{NodeAttr, reflective_grating::LinearDensity};
+use crate::{
+    analyzers::{
+        GhostFocusConfig, RayTraceConfig,
+        energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus,
+        raytrace::{AnalysisRayTrace, MissedSurfaceStrategy},
+    },
+    error::{OpmResult, OpossumError},
+    light_result::{LightRays, LightResult},
+    lightdata::LightData,
+    num_per_mm,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    radian,
+    rays::Rays,
+};
+use approx::relative_eq;
+use nalgebra::Vector3;
+use num::ToPrimitive;
+use opm_macros_lib::OpmNode;
+use uom::si::{
+    angle::radian,
+    f64::{Angle, Length},
+};
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("lemonchiffon")]
+/// An infinitely thin transmission grating.
+///
+/// The rays are diffracted according to the grating equation into the given diffraction order. Since the diffraction
+/// angle depends on the wavelength of each ray, rays of different wavelengths (e.g. generated from a source spectrum)
+/// leave the grating in different directions. Rays, for which the given diffraction order cannot propagate, are
+/// terminated.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `line density`
+///   - `diffraction order`
+pub struct TransmissionGrating {
+    node_attr: NodeAttr,
+}
+unsafe impl Send for TransmissionGrating {}
+
+impl Default for TransmissionGrating {
+    /// Create a transmission grating with a line density of 1740 lines/mm used in the first order.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("transmission grating");
+        node_attr
+            .create_property(
+                "line density",
+                "line density in 1/mm of this grating",
+                Proptype::LinearDensity(num_per_mm!(1740.)),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "diffraction order",
+                "order of diffraction that should be used to propagate the rays",
+                1.into(),
+            )
+            .unwrap();
+        let mut g = Self { node_attr };
+        g.update_surfaces().unwrap();
+        g
+    }
+}
+impl TransmissionGrating {
+    /// Creates a new [`TransmissionGrating`].
+    ///
+    /// This function creates a transmission grating with a specified line-density on a flat surface.
+    /// The grating vector (direction along the periodicty) is allways applied in x direction in the origin.
+    /// # Errors
+    /// This function errors if the properties `line_density` or `diffraction_order` can not be set or if the line density is negative or non finite
+    pub fn new(name: &str, line_density: LinearDensity, diffraction_order: i32) -> OpmResult<Self> {
+        let mut grating = Self::default();
+        grating.node_attr.set_name(name);
+        if !(line_density.value.is_finite()
+            && line_density.value.is_sign_positive()
+            && !relative_eq!(line_density.value, 0.))
+        {
+            return Err(OpossumError::Other(
+                "Only positive finite values are allowed for a grating line density".into(),
+            ));
+        }
+        grating
+            .node_attr
+            .set_property("line density", Proptype::LinearDensity(line_density))?;
+        grating
+            .node_attr
+            .set_property("diffraction order", diffraction_order.into())?;
+        Ok(grating)
+    }
+    fn grating_parameters(&self) -> OpmResult<(LinearDensity, i32)> {
+        let Ok(Proptype::I32(diffraction_order)) = self.node_attr.get_property("diffraction order")
+        else {
+            return Err(OpossumError::Analysis(
+                "cannot read diffraction order".into(),
+            ));
+        };
+        let Ok(Proptype::LinearDensity(line_density)) = self.node_attr.get_property("line density")
+        else {
+            return Err(OpossumError::Analysis("cannot read line density".into()));
+        };
+        Ok((*line_density, *diffraction_order))
+    }
+    /// Set the angle of the grating such that the incoming ray of the given wavelength is symmetrically diffracted.
+    ///
+    /// In this (minimum deviation) configuration, the incoming and the diffracted ray enclose the same angle with
+    /// the grating normal.
+    /// # Errors
+    /// This function errors if
+    /// - the diffraction order cannot be read from te properties
+    /// - the line density cannot be read from te properties
+    /// # Panics
+    /// This function panics if the diffraction order canno be converted to f64.
+    pub fn with_symmetric_rot(self, wavelength: Length) -> OpmResult<Self> {
+        let (line_density, diffraction_order) = self.grating_parameters()?;
+        let angle = (diffraction_order.to_f64().unwrap() * wavelength.value * line_density.value
+            / 2.)
+            .asin();
+        self.with_tilt(radian!(0., angle, 0.0))
+    }
+    /// Set the angle of the grating such that the incoming ray hits the grating with the given angle of incidence.
+    /// # Errors
+    /// This function errors if the grating cannot be tilted.
+    pub fn with_incidence_angle(self, angle: Angle) -> OpmResult<Self> {
+        let angle_in_rad = angle.get::<radian>();
+        self.with_tilt(radian!(0., angle_in_rad, 0.0))
+    }
+}
+impl AnalysisGhostFocus for TransmissionGrating {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+
+        let mut rays_bundle = incoming_data
+            .get(in_port)
+            .map_or_else(Vec::<Rays>::new, std::clone::Clone::clone);
+        let mut ray_trace_config = RayTraceConfig::default();
+        ray_trace_config.set_missed_surface_strategy(MissedSurfaceStrategy::Ignore);
+        for rays in &mut rays_bundle {
+            let mut input = LightResult::default();
+            input.insert(in_port.clone(), LightData::Geometric(rays.clone()));
+            let out = AnalysisRayTrace::analyze(self, input, &ray_trace_config)?;
+
+            if let Some(LightData::Geometric(r)) = out.get(out_port) {
+                *rays = r.clone();
+            }
+        }
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis(format!(
+                "Cannot find surface: \"{in_port}\" of node: \"{}\"",
+                self.node_attr().name()
+            )));
+        };
+        for rays in &mut rays_bundle {
+            surf.evaluate_fluence_of_ray_bundle(rays, config.fluence_estimator())?;
+        }
+        let mut out_light_rays = LightRays::default();
+        out_light_rays.insert(out_port.to_string(), rays_bundle.clone());
+        Ok(out_light_rays)
+    }
+}
+impl AnalysisGaussianBeam for TransmissionGrating {}
+impl AnalysisEnergy for TransmissionGrating {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(data) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        Ok(LightResult::from([(out_port.into(), data.clone())]))
+    }
+}
+impl AnalysisRayTrace for TransmissionGrating {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(data) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        let LightData::Geometric(mut rays) = data.clone() else {
+            return Err(OpossumError::Analysis(
+                "expected ray data at input port".into(),
+            ));
+        };
+        let (line_density, diffraction_order) = self.grating_parameters()?;
+        let iso = self.effective_surface_iso(in_port)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis("no surface found. Aborting".into()));
+        };
+        let grating_vector = 2. * PI * line_density.value * iso.transform_vector_f64(&Vector3::x());
+        rays.diffract_through_periodic_surface(
+            surf,
+            grating_vector,
+            &diffraction_order,
+            config.missed_surface_strategy(),
+        )?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        Ok(LightResult::from([(
+            out_port.into(),
+            LightData::Geometric(rays),
+        )]))
+    }
+}
+
+impl OpticNode for TransmissionGrating {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        self.update_flat_single_surfaces()
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        analyzers::RayTraceConfig, degree, joule, millimeter, nanometer,
+        nodes::test_helper::test_helper::*, optic_ports::PortType, ray::Ray, rays::Rays,
+        spectrum_helper::create_he_ne_spec, utils::geom_transformation::Isometry,
+    };
+    use approx::assert_relative_eq;
+    use nalgebra::vector;
+
+    fn diffract(node: &mut TransmissionGrating, wavelength: Length) -> Rays {
+        node.set_isometry(Isometry::new(millimeter!(0., 0., 0.), degree!(0., 0., 0.)).unwrap())
+            .unwrap();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(wavelength, joule!(1.0)).unwrap());
+        let input = LightResult::from([("input_1".into(), LightData::Geometric(rays))]);
+        let output = AnalysisRayTrace::analyze(node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("could not get LightData");
+        };
+        rays.clone()
+    }
+    #[test]
+    fn default() {
+        let node = TransmissionGrating::default();
+        assert_eq!(node.name(), "transmission grating");
+        assert_eq!(node.node_type(), "transmission grating");
+        assert_eq!(node.node_color(), "lemonchiffon");
+        assert!(!node.inverted());
+        assert_eq!(node.grating_parameters().unwrap(), (num_per_mm!(1740.), 1));
+    }
+    #[test]
+    fn new() {
+        let node = TransmissionGrating::new("test", num_per_mm!(200.), -2).unwrap();
+        assert_eq!(node.name(), "test");
+        assert_eq!(node.node_type(), "transmission grating");
+        assert_eq!(node.grating_parameters().unwrap(), (num_per_mm!(200.), -2));
+    }
+    #[test]
+    fn invalid_line_density() {
+        assert!(TransmissionGrating::new("test", num_per_mm!(200.), 1).is_ok());
+        assert!(TransmissionGrating::new("test", num_per_mm!(-200.), 1).is_err());
+        assert!(TransmissionGrating::new("test", num_per_mm!(0.), 1).is_err());
+        assert!(TransmissionGrating::new("test", num_per_mm!(f64::INFINITY), 1).is_err());
+        assert!(TransmissionGrating::new("test", num_per_mm!(f64::NAN), 1).is_err());
+    }
+    #[test]
+    fn ports() {
+        let node = TransmissionGrating::default();
+        assert_eq!(node.ports().names(&PortType::Input), vec!["input_1"]);
+        assert_eq!(node.ports().names(&PortType::Output), vec!["output_1"]);
+    }
+    #[test]
+    fn set_aperture() {
+        test_set_aperture::<TransmissionGrating>("input_1", "output_1");
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<TransmissionGrating>()
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<TransmissionGrating>()
+    }
+    #[test]
+    fn analyze_energy_ok() {
+        let mut node = TransmissionGrating::default();
+        let input_light = LightData::Energy(create_he_ne_spec(1.0).unwrap());
+        let input = LightResult::from([("input_1".into(), input_light.clone())]);
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        assert_eq!(output.len(), 1);
+        assert_eq!(*output.get("output_1").unwrap(), input_light);
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<TransmissionGrating>("input_1");
+    }
+    #[test]
+    fn analyze_geometric_no_isometery() {
+        test_analyze_geometric_no_isometry::<TransmissionGrating>("input_1");
+    }
+    #[test]
+    fn analyze_geometric_normal_incidence() {
+        let mut node = TransmissionGrating::new("test", num_per_mm!(1000.), 1).unwrap();
+        let rays = diffract(&mut node, nanometer!(500.0));
+        assert_eq!(rays.nr_of_rays(true), 1);
+        let ray = rays.iter().next().unwrap();
+        assert_eq!(ray.position(), millimeter!(0.0, 0.0, 0.0));
+        // sin(theta) = m * lambda * N = 0.5
+        let dir = vector![0.5, 0.0, 0.75_f64.sqrt()];
+        assert_relative_eq!(ray.direction(), dir, epsilon = 1e-15);
+        assert_eq!(ray.number_of_refractions(), 1);
+    }
+    #[test]
+    fn analyze_geometric_wavelengths_diverge() {
+        let mut node = TransmissionGrating::new("test", num_per_mm!(1000.), -1).unwrap();
+        let rays_short = diffract(&mut node, nanometer!(400.0));
+        let rays_long = diffract(&mut node, nanometer!(800.0));
+        let dir_short = rays_short.iter().next().unwrap().direction();
+        let dir_long = rays_long.iter().next().unwrap().direction();
+        assert_relative_eq!(dir_short.x, -0.4, epsilon = 1e-15);
+        assert_relative_eq!(dir_long.x, -0.8, epsilon = 1e-15);
+    }
+    #[test]
+    fn analyze_geometric_evanescent_order() {
+        let mut node = TransmissionGrating::new("test", num_per_mm!(1000.), 2).unwrap();
+        let rays = diffract(&mut node, nanometer!(600.0));
+        assert_eq!(rays.nr_of_rays(true), 0);
+    }
+    #[test]
+    fn analyze_geometric_incidence_angle() {
+        let mut node = TransmissionGrating::new("test", num_per_mm!(1000.), 1)
+            .unwrap()
+            .with_incidence_angle(degree!(30.))
+            .unwrap();
+        let rays = diffract(&mut node, nanometer!(500.0));
+        assert_eq!(rays.nr_of_rays(true), 1);
+        let ray = rays.iter().next().unwrap();
+        // sin(theta_in) = 0.5 is compensated by m * lambda * N = 0.5: the ray leaves along the grating normal
+        let dir = vector![30_f64.to_radians().sin(), 0.0, 30_f64.to_radians().cos()];
+        assert_relative_eq!(ray.direction(), dir, epsilon = 1e-14);
+        // the opposite order is evanescent
+        let mut node = TransmissionGrating::new("test", num_per_mm!(1000.), -1)
+            .unwrap()
+            .with_incidence_angle(degree!(30.))
+            .unwrap();
+        assert_eq!(diffract(&mut node, nanometer!(1000.0)).nr_of_rays(true), 0);
+    }
+    #[test]
+    fn analyze_geometric_symmetric() {
+        let wvl = nanometer!(1000.);
+        let mut node = TransmissionGrating::default()
+            .with_symmetric_rot(wvl)
+            .unwrap();
+        let rays = diffract(&mut node, wvl);
+        let ray = rays.iter().next().unwrap();
+        // symmetric diffraction: the deviation is twice the angle of incidence
+        let incidence = (1740000. * wvl.value / 2.).asin();
+        let dir = vector![(2. * incidence).sin(), 0.0, (2. * incidence).cos()];
+        assert_relative_eq!(ray.direction(), dir, epsilon = 1e-14);
+    }
+}
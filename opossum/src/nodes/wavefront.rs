@@ -1,27 +1,28 @@
 #![warn(missing_docs)]
 //! Wavefront measurment node
 use log::warn;
-use nalgebra::{DVector, DVectorView, MatrixXx3};
+use nalgebra::{DMatrix, DVector, DVectorView, Matrix2, MatrixXx3};
 use opm_macros_lib::OpmNode;
 use plotters::style::RGBAColor;
 use serde::{Deserialize, Serialize};
-use uom::si::f64::Length;
+use uom::si::{f64::Length, length::millimeter};
 
 use crate::{
     analyzers::{
-        RayTraceConfig, energy::AnalysisEnergy, ghostfocus::AnalysisGhostFocus,
-        raytrace::AnalysisRayTrace,
+        RayTraceConfig, energy::AnalysisEnergy, gaussian_beam::AnalysisGaussianBeam,
+        ghostfocus::AnalysisGhostFocus, raytrace::AnalysisRayTrace,
     },
     error::{OpmResult, OpossumError},
     light_result::{LightRays, LightResult},
     lightdata::LightData,
-    nanometer,
+    millimeter, nanometer,
     optic_node::OpticNode,
     optic_ports::PortType,
     plottable::{AxLims, PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
     properties::{Properties, Proptype},
     rays::Rays,
     reporting::node_report::NodeReport,
+    surface::{noll_to_nm, zernike_polynomial},
     utils::{
         geom_transformation::Isometry,
         griddata::{create_linspace_axes, interpolate_3d_scatter_data},
@@ -163,6 +164,135 @@ impl WaveFrontErrorMap {
         }
     }
 }
+/// Number of Zernike terms (Noll indices 1 to 11, i.e. up to primary spherical aberration) used for the wavefront fit.
+const NR_OF_ZERNIKE_TERMS: usize = 11;
+/// Names of the fitted Zernike terms in Noll order.
+const ZERNIKE_TERM_NAMES: [&str; NR_OF_ZERNIKE_TERMS] = [
+    "piston",
+    "tilt x",
+    "tilt y",
+    "defocus",
+    "astigmatism 45°",
+    "astigmatism 0°",
+    "coma y",
+    "coma x",
+    "trefoil y",
+    "trefoil x",
+    "spherical",
+];
+
+/// Result of a Zernike decomposition of a [`WaveFrontErrorMap`].
+///
+/// The wavefront is fitted by the Zernike polynomials with Noll indices 1 to 11 (piston, tilt, defocus, astigmatism,
+/// coma, trefoil and primary spherical aberration) over a circular aperture centered at the optical axis of the monitor.
+/// The polynomials follow the convention of [`ZernikeSurface`](crate::surface::ZernikeSurface) (not normalized, i.e.
+/// each coefficient denotes the amplitude of its term at the edge of the aperture). All values are given in units of the
+/// wavelength of the fitted [`WaveFrontErrorMap`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ZernikeFit {
+    aperture_radius: Length,
+    coefficients: Vec<f64>,
+    residual_rms: f64,
+}
+impl ZernikeFit {
+    /// Returns the radius of the circular aperture used for the fit.
+    #[must_use]
+    pub const fn aperture_radius(&self) -> Length {
+        self.aperture_radius
+    }
+    /// Returns the fitted Zernike coefficients (in units of the wavelength) in Noll order starting with piston (j = 1).
+    #[must_use]
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+    /// Returns the fitted coefficient of the term with the given Noll index `j` (if fitted).
+    #[must_use]
+    pub fn coefficient(&self, j: usize) -> Option<f64> {
+        j.checked_sub(1)
+            .and_then(|idx| self.coefficients.get(idx))
+            .copied()
+    }
+    /// Returns the fitted coefficients together with their names (e.g. "defocus") in Noll order.
+    pub fn named_coefficients(&self) -> impl Iterator<Item = (&'static str, f64)> + '_ {
+        ZERNIKE_TERM_NAMES
+            .iter()
+            .copied()
+            .zip(self.coefficients.iter().copied())
+    }
+    /// Returns the rms value of the wavefront after removing all fitted terms (in units of the wavelength).
+    #[must_use]
+    pub const fn residual_rms(&self) -> f64 {
+        self.residual_rms
+    }
+}
+impl WaveFrontErrorMap {
+    /// Returns the wavelength of this [`WaveFrontErrorMap`].
+    #[must_use]
+    pub const fn wavelength(&self) -> Length {
+        self.wavelength
+    }
+    /// Fit Zernike polynomials to this [`WaveFrontErrorMap`] (see [`ZernikeFit`]).
+    ///
+    /// The fit is performed over a circular aperture with the given radius centered at the optical axis of the monitor.
+    /// Points outside this aperture are ignored. If no radius is given, the smallest circle containing all points is used.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the given aperture radius is not positive and finite.
+    ///   - the aperture contains less points than fitted terms.
+    pub fn zernike_fit(&self, aperture_radius: Option<Length>) -> OpmResult<ZernikeFit> {
+        let radius = if let Some(radius) = aperture_radius {
+            if !radius.is_normal() || radius.is_sign_negative() {
+                return Err(OpossumError::Other(
+                    "aperture radius must be positive and finite".into(),
+                ));
+            }
+            radius.get::<millimeter>()
+        } else {
+            self.x
+                .iter()
+                .zip(self.y.iter())
+                .map(|(x, y)| x.hypot(*y))
+                .fold(0.0, f64::max)
+        };
+        let points = self
+            .x
+            .iter()
+            .zip(self.y.iter())
+            .zip(self.wf_map.iter())
+            .map(|((x, y), w)| (x / radius, y / radius, *w))
+            .filter(|(x, y, _)| x.mul_add(*x, y * y) <= 1.0 + f64::EPSILON)
+            .collect::<Vec<_>>();
+        if points.len() < NR_OF_ZERNIKE_TERMS || radius <= 0.0 {
+            return Err(OpossumError::Other(
+                "not enough wavefront points within the aperture for Zernike fit".into(),
+            ));
+        }
+        let terms = (1..=NR_OF_ZERNIKE_TERMS)
+            .map(noll_to_nm)
+            .collect::<OpmResult<Vec<_>>>()?;
+        let mut design_matrix = DMatrix::<f64>::zeros(points.len(), NR_OF_ZERNIKE_TERMS);
+        for (row, (x, y, _)) in points.iter().enumerate() {
+            for (col, (n, m)) in terms.iter().enumerate() {
+                design_matrix[(row, col)] = zernike_polynomial(*n, *m, *x, *y)?;
+            }
+        }
+        let wavefront = DVector::from_iterator(points.len(), points.iter().map(|p| p.2));
+        let coefficients = design_matrix
+            .clone()
+            .svd(true, true)
+            .solve(&wavefront, 1.0e-12)
+            .map_err(|e| OpossumError::Other(format!("Zernike fit failed: {e}")))?;
+        let residuals = wavefront - design_matrix * &coefficients;
+        let residual_rms = (residuals.norm_squared() / usize_to_f64(points.len())).sqrt();
+        Ok(ZernikeFit {
+            aperture_radius: millimeter!(radius),
+            coefficients: coefficients.iter().copied().collect(),
+            residual_rms,
+        })
+    }
+}
 impl OpticNode for WaveFront {
     fn set_apodization_warning(&mut self, apodized: bool) {
         self.apodization_warning = apodized;
@@ -213,6 +343,25 @@ impl OpticNode for WaveFront {
                     Proptype::WfLambda(wf_data.wavefront_error_maps[0].rms, wf_data.wavefront_error_maps[0].wavelength),
                 )
                 .unwrap();
+                let wf_map = &wf_data.wavefront_error_maps[0];
+                if let Ok(fit) = wf_map.zernike_fit(None) {
+                    for (name, coefficient) in fit.named_coefficients().skip(1) {
+                        props
+                            .create(
+                                &format!("Zernike {name}"),
+                                "Zernike coefficient of the wavefront fitted over the full aperture (edge amplitude)",
+                                Proptype::WfLambda(coefficient, wf_map.wavelength),
+                            )
+                            .unwrap();
+                    }
+                    props
+                        .create(
+                            "Zernike residual RMS",
+                            "Wavefront root mean square value after removing the fitted Zernike terms",
+                            Proptype::WfLambda(fit.residual_rms(), wf_map.wavelength),
+                        )
+                        .unwrap();
+                }
                 if self.apodization_warning {
                     props
                 .create(
@@ -261,6 +410,11 @@ impl AnalysisGhostFocus for WaveFront {
         AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)
     }
 }
+impl AnalysisGaussianBeam for WaveFront {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
 impl AnalysisEnergy for WaveFront {
     fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
         let in_port = &self.ports().names(&PortType::Input)[0];
@@ -346,7 +500,7 @@ impl Plottable for WaveFrontErrorMap {
 #[cfg(test)]
 mod test_wavefront_error_map {
     use super::*;
-    use crate::{joule, nanometer, ray::Ray, rays::Rays};
+    use crate::{joule, millimeter, nanometer, ray::Ray, rays::Rays};
     use approx::assert_abs_diff_eq;
     use nalgebra::Point3;
     #[test]
@@ -366,6 +520,62 @@ mod test_wavefront_error_map {
         assert_eq!(wvf_map.ptv, 1.0);
         assert_abs_diff_eq!(wvf_map.rms, 0.5);
     }
+    fn synthetic_map(wavefront: impl Fn(f64, f64) -> f64) -> WaveFrontErrorMap {
+        // grid of points (in mm) within a circle of 1 mm radius
+        let points = (-10..=10)
+            .flat_map(|i| (-10..=10).map(move |j| (f64::from(i) / 10.0, f64::from(j) / 10.0)))
+            .filter(|(x, y)| x.hypot(*y) <= 1.0)
+            .collect::<Vec<_>>();
+        let wf_dat = MatrixXx3::from_fn(points.len(), |row, col| {
+            let (x, y) = points[row];
+            match col {
+                0 => x,
+                1 => y,
+                _ => wavefront(x, y),
+            }
+        });
+        WaveFrontErrorMap::new(&wf_dat, nanometer!(1000.0)).unwrap()
+    }
+    #[test]
+    fn zernike_fit() {
+        let map = synthetic_map(|x, y| {
+            0.1 * zernike_polynomial(2, 0, x, y).unwrap()
+                + 0.05 * zernike_polynomial(3, 1, x, y).unwrap()
+                - 0.02 * zernike_polynomial(4, 0, x, y).unwrap()
+        });
+        assert!(map.zernike_fit(Some(millimeter!(0.0))).is_err());
+        assert!(map.zernike_fit(Some(millimeter!(f64::NAN))).is_err());
+        assert!(map.zernike_fit(Some(millimeter!(0.01))).is_err());
+        let fit = map.zernike_fit(None).unwrap();
+        assert_abs_diff_eq!(fit.aperture_radius().get::<millimeter>(), 1.0);
+        assert_eq!(fit.coefficients().len(), 11);
+        assert_abs_diff_eq!(fit.coefficient(4).unwrap(), 0.1, epsilon = 1e-9);
+        assert_abs_diff_eq!(fit.coefficient(8).unwrap(), 0.05, epsilon = 1e-9);
+        assert_abs_diff_eq!(fit.coefficient(11).unwrap(), -0.02, epsilon = 1e-9);
+        assert_abs_diff_eq!(fit.coefficient(2).unwrap(), 0.0, epsilon = 1e-9);
+        assert!(fit.coefficient(0).is_none());
+        assert!(fit.coefficient(12).is_none());
+        assert_abs_diff_eq!(fit.residual_rms(), 0.0, epsilon = 1e-9);
+        let names = fit.named_coefficients().map(|c| c.0).collect::<Vec<_>>();
+        assert_eq!(names[3], "defocus");
+        assert_eq!(names[10], "spherical");
+    }
+    #[test]
+    fn zernike_fit_sub_aperture() {
+        // defocus over 1 mm radius corresponds to a quarter of the defocus over a 0.5 mm sub-aperture
+        let map = synthetic_map(|x, y| 0.4 * zernike_polynomial(2, 0, x, y).unwrap());
+        let fit = map.zernike_fit(Some(millimeter!(0.5))).unwrap();
+        assert_abs_diff_eq!(fit.coefficient(1).unwrap(), -0.3, epsilon = 1e-9);
+        assert_abs_diff_eq!(fit.coefficient(4).unwrap(), 0.1, epsilon = 1e-9);
+        assert_abs_diff_eq!(fit.residual_rms(), 0.0, epsilon = 1e-9);
+    }
+    #[test]
+    fn zernike_fit_residual() {
+        // a higher-order term cannot be represented by the fitted terms
+        let map = synthetic_map(|x, y| zernike_polynomial(6, 0, x, y).unwrap());
+        let fit = map.zernike_fit(None).unwrap();
+        assert!(fit.residual_rms() > 0.1);
+    }
     #[test]
     fn new_empty_wf_error_map() {
         let wf_dat = MatrixXx3::from_vec(Vec::<f64>::new());
@@ -519,5 +729,21 @@ mod test {
         let node_props = node_report.properties();
         let nr_of_props = node_props.iter().fold(0, |c, _p| c + 1);
         assert_eq!(nr_of_props, 3);
+        wf.light_data = Some(LightData::Geometric(
+            Rays::new_uniform_collimated(
+                nanometer!(1053.0),
+                joule!(1.0),
+                &Hexapolar::new(millimeter!(1.), 2).unwrap(),
+            )
+            .unwrap(),
+        ));
+        let node_report = wf.node_report("").unwrap();
+        let node_props = node_report.properties();
+        assert!(node_props.contains("Zernike defocus"));
+        assert!(node_props.contains("Zernike spherical"));
+        assert!(node_props.contains("Zernike residual RMS"));
+        assert!(!node_props.contains("Zernike piston"));
+        let nr_of_props = node_props.iter().fold(0, |c, _p| c + 1);
+        assert_eq!(nr_of_props, 14);
     }
 }
@@ -0,0 +1,477 @@
+#![warn(missing_docs)]
+//! Ideal waveplate (linear retarder)
+use super::{node_attr::NodeAttr, polarizer::global_axis_angle};
+use crate::{
+    analyzers::{
+        GhostFocusConfig, RayTraceConfig, energy::AnalysisEnergy,
+        gaussian_beam::AnalysisGaussianBeam, ghostfocus::AnalysisGhostFocus,
+        raytrace::AnalysisRayTrace,
+    },
+    degree,
+    error::{OpmResult, OpossumError},
+    light_result::{LightRays, LightResult},
+    lightdata::LightData,
+    nanometer,
+    optic_node::OpticNode,
+    optic_ports::PortType,
+    properties::Proptype,
+    rays::Rays,
+    utils::geom_transformation::Isometry,
+};
+use core::f64::consts::PI;
+use nalgebra::Matrix2;
+use num::Complex;
+use opm_macros_lib::OpmNode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uom::si::{
+    f64::{Angle, Length},
+    length::nanometer,
+};
+
+/// Retardance of a [`Waveplate`].
+///
+/// The retardance is given in waves at a design wavelength. For other wavelengths, the retardance is scaled
+/// assuming a wavelength-independent birefringence of the waveplate material.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct Retardance {
+    waves: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    design_wavelength: Length,
+}
+impl Retardance {
+    /// Creates a new [`Retardance`] of the given number of `waves` at the given design wavelength.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the number of waves is not finite.
+    ///   - the design wavelength is not positive and finite.
+    pub fn new(waves: f64, design_wavelength: Length) -> OpmResult<Self> {
+        if !waves.is_finite() {
+            return Err(OpossumError::Other("retardance must be finite".into()));
+        }
+        if !design_wavelength.is_normal() || design_wavelength.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "design wavelength must be positive and finite".into(),
+            ));
+        }
+        Ok(Self {
+            waves,
+            design_wavelength,
+        })
+    }
+    /// Creates a quarter-wave [`Retardance`] at the given design wavelength.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the design wavelength is not positive and finite.
+    pub fn quarter_wave(design_wavelength: Length) -> OpmResult<Self> {
+        Self::new(0.25, design_wavelength)
+    }
+    /// Creates a half-wave [`Retardance`] at the given design wavelength.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the design wavelength is not positive and finite.
+    pub fn half_wave(design_wavelength: Length) -> OpmResult<Self> {
+        Self::new(0.5, design_wavelength)
+    }
+    /// Returns the retardance in waves at the design wavelength.
+    #[must_use]
+    pub const fn waves(&self) -> f64 {
+        self.waves
+    }
+    /// Returns the design wavelength of this [`Retardance`].
+    #[must_use]
+    pub const fn design_wavelength(&self) -> Length {
+        self.design_wavelength
+    }
+    /// Returns the phase retardance (in radians) at the given wavelength.
+    #[must_use]
+    pub fn phase_at(&self, wavelength: Length) -> f64 {
+        2.0 * PI * self.waves * (self.design_wavelength / wavelength).value
+    }
+}
+impl Display for Retardance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}λ @ {:.1} nm",
+            self.waves,
+            self.design_wavelength.get::<nanometer>()
+        )
+    }
+}
+impl From<Retardance> for Proptype {
+    fn from(retardance: Retardance) -> Self {
+        Self::Retardance(retardance)
+    }
+}
+
+#[derive(OpmNode, Debug, Clone)]
+#[opm_node("plum")]
+/// An ideal waveplate (linear retarder).
+///
+/// The waveplate retards the polarization component along its slow axis with respect to the component along its fast
+/// axis. The fast axis is given as angle with respect to the local x axis of the node. The retardance is defined at a
+/// design wavelength (see [`Retardance`]) and scaled for each ray according to its wavelength. The waveplate is lossless,
+/// thus unpolarized light passes unaltered.
+///
+/// ## Optical Ports
+///   - Inputs
+///     - `input_1`
+///   - Outputs
+///     - `output_1`
+///
+/// ## Properties
+///   - `name`
+///   - `inverted`
+///   - `retardance`
+///   - `fast axis`
+pub struct Waveplate {
+    node_attr: NodeAttr,
+}
+unsafe impl Send for Waveplate {}
+
+impl Default for Waveplate {
+    /// Create a quarter-wave plate for 1053 nm with its fast axis along the local x axis.
+    fn default() -> Self {
+        let mut node_attr = NodeAttr::new("waveplate");
+        node_attr
+            .create_property(
+                "retardance",
+                "retardance in waves at the design wavelength",
+                Retardance::quarter_wave(nanometer!(1053.0)).unwrap().into(),
+            )
+            .unwrap();
+        node_attr
+            .create_property(
+                "fast axis",
+                "angle of the fast axis with respect to the local x axis",
+                degree!(0.0).into(),
+            )
+            .unwrap();
+        let mut waveplate = Self { node_attr };
+        waveplate.update_surfaces().unwrap();
+        waveplate
+    }
+}
+impl Waveplate {
+    /// Creates a new [`Waveplate`] with the given [`Retardance`] and fast-axis angle.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given angle is not finite.
+    pub fn new(name: &str, retardance: Retardance, fast_axis: Angle) -> OpmResult<Self> {
+        let mut waveplate = Self::default();
+        waveplate.set_retardance(retardance)?;
+        waveplate.set_fast_axis(fast_axis)?;
+        waveplate.node_attr.set_name(name);
+        Ok(waveplate)
+    }
+    /// Returns the [`Retardance`] of this [`Waveplate`].
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the retardance property.
+    #[must_use]
+    pub fn retardance(&self) -> Retardance {
+        if let Ok(Proptype::Retardance(retardance)) = self.node_attr.get_property("retardance") {
+            *retardance
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the [`Retardance`] of this [`Waveplate`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property cannot be set.
+    pub fn set_retardance(&mut self, retardance: Retardance) -> OpmResult<()> {
+        self.node_attr.set_property("retardance", retardance.into())
+    }
+    /// Returns the angle of the fast axis of this [`Waveplate`] with respect to its local x axis.
+    ///
+    /// # Panics
+    /// Panics if the wrong data type is stored in the fast-axis property.
+    #[must_use]
+    pub fn fast_axis(&self) -> Angle {
+        if let Ok(Proptype::Angle(angle)) = self.node_attr.get_property("fast axis") {
+            *angle
+        } else {
+            panic!("wrong data type")
+        }
+    }
+    /// Sets the angle of the fast axis of this [`Waveplate`] with respect to its local x axis.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given angle is not finite.
+    pub fn set_fast_axis(&mut self, fast_axis: Angle) -> OpmResult<()> {
+        if !fast_axis.is_finite() {
+            return Err(OpossumError::Other("fast axis angle must be finite".into()));
+        }
+        self.node_attr.set_property("fast axis", fast_axis.into())
+    }
+    /// Applies the (wavelength-dependent) Jones matrix of this [`Waveplate`] to all valid rays of the given bundle.
+    fn retard_rays(&self, rays: &mut Rays, iso: &Isometry) -> OpmResult<()> {
+        let retardance = self.retardance();
+        let axis_angle = global_axis_angle(self.fast_axis(), iso);
+        for ray in rays.iter_mut() {
+            if ray.valid() {
+                let phase = retardance.phase_at(ray.wavelength());
+                ray.apply_jones_matrix(&retarder_matrix(axis_angle, phase))?;
+            }
+        }
+        Ok(())
+    }
+}
+/// Returns the Jones matrix of a linear retarder with its fast axis at the given angle and the given phase retardance
+/// (both in radians).
+fn retarder_matrix(angle: f64, phase: f64) -> Matrix2<Complex<f64>> {
+    let (sin, cos) = angle.sin_cos();
+    let retardation = Complex::from_polar(1.0, phase);
+    let one = Complex::new(1.0, 0.0);
+    let off_diagonal = (one - retardation) * cos * sin;
+    Matrix2::new(
+        retardation * sin * sin + cos * cos,
+        off_diagonal,
+        off_diagonal,
+        retardation * cos * cos + sin * sin,
+    )
+}
+
+impl OpticNode for Waveplate {
+    fn update_surfaces(&mut self) -> OpmResult<()> {
+        self.update_flat_single_surfaces()
+    }
+    fn node_attr(&self) -> &NodeAttr {
+        &self.node_attr
+    }
+    fn node_attr_mut(&mut self) -> &mut NodeAttr {
+        &mut self.node_attr
+    }
+    fn set_apodization_warning(&mut self, _apodized: bool) {}
+    fn reset_data(&mut self) {
+        self.reset_optic_surfaces();
+    }
+}
+impl AnalysisGhostFocus for Waveplate {
+    fn analyze(
+        &mut self,
+        incoming_data: LightRays,
+        config: &GhostFocusConfig,
+        _ray_collection: &mut Vec<Rays>,
+        _bounce_lvl: usize,
+    ) -> OpmResult<LightRays> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let iso = self.effective_surface_iso(in_port)?;
+        let mut output =
+            AnalysisGhostFocus::analyze_single_surface_node(self, incoming_data, config)?;
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        if let Some(rays_bundles) = output.get_mut(out_port) {
+            for rays in rays_bundles {
+                self.retard_rays(rays, &iso)?;
+            }
+            Ok(output)
+        } else {
+            Err(OpossumError::Analysis("retarding of rays failed".into()))
+        }
+    }
+}
+impl AnalysisGaussianBeam for Waveplate {
+    fn abcd_matrix(&self, _wavelength: Length, _n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        Ok(Some(Matrix2::identity()))
+    }
+}
+impl AnalysisEnergy for Waveplate {
+    fn analyze(&mut self, incoming_data: LightResult) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(data) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        Ok(LightResult::from([(out_port.into(), data.clone())]))
+    }
+}
+impl AnalysisRayTrace for Waveplate {
+    fn analyze(
+        &mut self,
+        incoming_data: LightResult,
+        config: &RayTraceConfig,
+    ) -> OpmResult<LightResult> {
+        let in_port = &self.ports().names(&PortType::Input)[0];
+        let out_port = &self.ports().names(&PortType::Output)[0];
+        let Some(input) = incoming_data.get(in_port) else {
+            return Ok(LightResult::default());
+        };
+        let LightData::Geometric(r) = input else {
+            return Err(OpossumError::Analysis(
+                "expected geometric light data".into(),
+            ));
+        };
+        let mut rays = r.clone();
+        let iso = self.effective_surface_iso(in_port)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::Analysis("no surface found. Aborting".into()));
+        };
+        let refraction_intended = true;
+        rays.refract_on_surface(
+            surf,
+            None,
+            refraction_intended,
+            config.missed_surface_strategy(),
+        )?;
+        self.retard_rays(&mut rays, &iso)?;
+        let Some(surf) = self.get_optic_surface_mut(in_port) else {
+            return Err(OpossumError::OpticPort("input aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let Some(surf) = self.get_optic_surface_mut(out_port) else {
+            return Err(OpossumError::OpticPort("output aperture not found".into()));
+        };
+        surf.apodize_rays(&mut rays, &iso)?;
+        rays.invalidate_by_threshold_energy(config.min_energy_per_ray())?;
+        let light_data = LightData::Geometric(rays);
+        Ok(LightResult::from([(out_port.into(), light_data)]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, millimeter, nodes::test_helper::test_helper::*, ray::Ray,
+        spectrum_helper::create_he_ne_spec,
+    };
+    use approx::assert_abs_diff_eq;
+    use nalgebra::Vector2;
+    use num::Zero;
+    use uom::si::energy::joule;
+
+    fn x_polarized_ray(wavelength: Length) -> Rays {
+        let mut ray =
+            Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), wavelength, joule!(1.0)).unwrap();
+        ray.set_polarization(Some(Vector2::new(Complex::new(1.0, 0.0), Complex::zero())))
+            .unwrap();
+        Rays::from(vec![ray])
+    }
+    fn trace(node: &mut Waveplate, rays: Rays) -> Rays {
+        let mut input = LightResult::default();
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        let output = AnalysisRayTrace::analyze(node, input, &RayTraceConfig::default()).unwrap();
+        let Some(LightData::Geometric(rays)) = output.get("output_1") else {
+            panic!("no geometric light data found")
+        };
+        rays.clone()
+    }
+    #[test]
+    fn retardance() {
+        assert!(Retardance::new(f64::NAN, nanometer!(1053.0)).is_err());
+        assert!(Retardance::new(0.25, nanometer!(0.0)).is_err());
+        assert!(Retardance::new(0.25, nanometer!(-1053.0)).is_err());
+        assert!(Retardance::new(0.25, nanometer!(f64::INFINITY)).is_err());
+        let retardance = Retardance::quarter_wave(nanometer!(1053.0)).unwrap();
+        assert_eq!(retardance.waves(), 0.25);
+        assert_eq!(retardance.design_wavelength(), nanometer!(1053.0));
+        assert_abs_diff_eq!(retardance.phase_at(nanometer!(1053.0)), PI / 2.0);
+        assert_abs_diff_eq!(retardance.phase_at(nanometer!(526.5)), PI);
+        assert_eq!(
+            Retardance::half_wave(nanometer!(1053.0)).unwrap().waves(),
+            0.5
+        );
+        assert_eq!(format!("{retardance}"), "0.25λ @ 1053.0 nm");
+        assert!(matches!(
+            Proptype::from(retardance),
+            Proptype::Retardance(_)
+        ));
+    }
+    #[test]
+    fn default() {
+        let mut node = Waveplate::default();
+        assert_eq!(
+            node.retardance(),
+            Retardance::quarter_wave(nanometer!(1053.0)).unwrap()
+        );
+        assert_eq!(node.fast_axis(), degree!(0.0));
+        assert_eq!(node.name(), "waveplate");
+        assert_eq!(node.node_type(), "waveplate");
+        assert!(!node.inverted());
+        assert_eq!(node.node_color(), "plum");
+        assert!(node.as_group_mut().is_err());
+    }
+    #[test]
+    fn new() {
+        let retardance = Retardance::half_wave(nanometer!(1053.0)).unwrap();
+        assert!(Waveplate::new("test", retardance, degree!(f64::NAN)).is_err());
+        let node = Waveplate::new("test", retardance, degree!(45.0)).unwrap();
+        assert_eq!(node.name(), "test");
+        assert_eq!(node.retardance(), retardance);
+        assert_eq!(node.fast_axis(), degree!(45.0));
+    }
+    #[test]
+    fn inverted() {
+        test_inverted::<Waveplate>()
+    }
+    #[test]
+    fn analyze_empty() {
+        test_analyze_empty::<Waveplate>()
+    }
+    #[test]
+    fn analyze_geometric_wrong_data_type() {
+        test_analyze_wrong_data_type::<Waveplate>("input_1");
+    }
+    #[test]
+    fn analyze_energy_ok() {
+        let mut node = Waveplate::default();
+        let mut input = LightResult::default();
+        let input_light = LightData::Energy(create_he_ne_spec(1.0).unwrap());
+        input.insert("input_1".into(), input_light.clone());
+        let output = AnalysisEnergy::analyze(&mut node, input).unwrap();
+        assert_eq!(*output.get("output_1").unwrap(), input_light);
+    }
+    #[test]
+    fn analyze_half_wave() {
+        let retardance = Retardance::half_wave(nanometer!(1053.0)).unwrap();
+        let mut node = Waveplate::new("test", retardance, degree!(45.0)).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = trace(&mut node, x_polarized_ray(nanometer!(1053.0)));
+        let ray = rays.iter().next().unwrap();
+        assert_abs_diff_eq!(ray.energy().get::<joule>(), 1.0, epsilon = 1e-12);
+        let pol = ray.polarization().unwrap();
+        assert_abs_diff_eq!(pol[0].norm(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(pol[1].norm(), 1.0, epsilon = 1e-12);
+    }
+    #[test]
+    fn analyze_quarter_wave_wavelength_dependent() {
+        let retardance = Retardance::quarter_wave(nanometer!(1053.0)).unwrap();
+        let mut node = Waveplate::new("test", retardance, degree!(45.0)).unwrap();
+        node.set_isometry(Isometry::identity()).unwrap();
+        // circular polarization at the design wavelength
+        let rays = trace(&mut node, x_polarized_ray(nanometer!(1053.0)));
+        let pol = rays.iter().next().unwrap().polarization().unwrap();
+        assert_abs_diff_eq!(pol[0].norm(), f64::sqrt(0.5), epsilon = 1e-12);
+        assert_abs_diff_eq!(pol[1].norm(), f64::sqrt(0.5), epsilon = 1e-12);
+        assert_abs_diff_eq!((pol[1] / pol[0]).arg().abs(), PI / 2.0, epsilon = 1e-12);
+        // the plate acts as half-wave plate at half the design wavelength
+        let rays = trace(&mut node, x_polarized_ray(nanometer!(526.5)));
+        let pol = rays.iter().next().unwrap().polarization().unwrap();
+        assert_abs_diff_eq!(pol[0].norm(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(pol[1].norm(), 1.0, epsilon = 1e-12);
+    }
+    #[test]
+    fn analyze_unpolarized() {
+        let mut node = Waveplate::default();
+        node.set_isometry(Isometry::identity()).unwrap();
+        let rays = Rays::from(vec![
+            Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), nanometer!(1053.0), joule!(1.0))
+                .unwrap(),
+        ]);
+        let rays = trace(&mut node, rays);
+        let ray = rays.iter().next().unwrap();
+        assert_eq!(ray.polarization(), None);
+        assert_abs_diff_eq!(ray.energy().get::<joule>(), 1.0, epsilon = 1e-12);
+    }
+}
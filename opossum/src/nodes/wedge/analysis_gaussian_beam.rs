@@ -0,0 +1,21 @@
+use super::Wedge;
+use crate::{
+    analyzers::{gaussian_beam::AnalysisGaussianBeam, raytrace::AnalysisRayTrace},
+    error::OpmResult,
+};
+use nalgebra::Matrix2;
+use uom::si::{f64::Length, length::meter};
+
+impl AnalysisGaussianBeam for Wedge {
+    fn abcd_matrix(&self, wavelength: Length, n_ambient: f64) -> OpmResult<Option<Matrix2<f64>>> {
+        // the (small) wedge angle only deflects the beam and is neglected here
+        let (refri, center_thickness, _) = self.get_node_attributes_ray_trace(&self.node_attr)?;
+        let n_wedge = refri.get_refractive_index(wavelength)?;
+        Ok(Some(Matrix2::new(
+            1.0,
+            center_thickness.get::<meter>() * n_ambient / n_wedge,
+            0.0,
+            1.0,
+        )))
+    }
+}
@@ -26,6 +26,7 @@ impl AnalysisRayTrace for Wedge {
             ));
         };
 
+        self.record_incident_beam(&rays);
         let (refri, _, _) = self.get_node_attributes_ray_trace(&self.node_attr)?;
 
         let mut rays_bundle = vec![rays];
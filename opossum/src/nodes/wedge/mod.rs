@@ -1,25 +1,32 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
 
 use super::NodeAttr;
 use crate::{
+    analyzers::raytrace::AnalysisRayTrace,
     error::{OpmResult, OpossumError},
     millimeter,
     optic_node::OpticNode,
     optic_ports::PortType,
-    properties::Proptype,
+    properties::{Properties, Proptype},
+    rays::Rays,
     refractive_index::{RefrIndexConst, RefractiveIndex, RefractiveIndexType},
+    reporting::node_report::NodeReport,
     surface::{Plane, geo_surface::GeoSurfaceRef},
     utils::geom_transformation::Isometry,
 };
-use nalgebra::Point3;
+use nalgebra::{Point3, Vector3};
 use num::Zero;
 use opm_macros_lib::OpmNode;
 use uom::si::{
-    angle::degree,
+    angle::{degree, radian},
     f64::{Angle, Length},
 };
 
 mod analysis_energy;
+mod analysis_gaussian_beam;
 mod analysis_ghostfocus;
 mod analysis_raytrace;
 
@@ -40,8 +47,14 @@ mod analysis_raytrace;
 ///   - `center thickness`
 ///   - `refractive index`
 ///   - `wedge`
+///
+/// The wedge angle tilts the rear surface around the local x axis. The resulting beam deviation and lateral shift can
+/// be calculated using [`Wedge::beam_deviation`]. After a ray tracing analysis, the node report contains these values
+/// for the incoming beam (at its central wavelength as well as at the limits of its spectrum).
 pub struct Wedge {
     node_attr: NodeAttr,
+    /// incidence angle, central wavelength and wavelength range of the beam recorded during ray tracing
+    incident_beam: Option<(Angle, Length, Range<Length>)>,
 }
 unsafe impl Send for Wedge {}
 
@@ -67,7 +80,10 @@ impl Default for Wedge {
             .create_property("wedge", "wedge angle", Angle::zero().into())
             .unwrap();
 
-        let mut wedge = Self { node_attr };
+        let mut wedge = Self {
+            node_attr,
+            incident_beam: None,
+        };
         wedge.update_surfaces().unwrap();
         wedge
     }
@@ -110,6 +126,85 @@ impl Wedge {
         wedge.node_attr.set_property("wedge", wedge_angle.into())?;
         Ok(wedge)
     }
+    /// Calculate the beam deviation and the lateral shift caused by this [`Wedge`].
+    ///
+    /// The calculation is performed for a ray with the given `wavelength` hitting the center of the front surface
+    /// under the given `incidence_angle`. This angle is measured in the plane of the wedge (the local y-z plane)
+    /// from the optical axis (local z axis) towards the local y axis. The refractive index of the wedge material
+    /// and of the ambient medium are evaluated at the given wavelength, so that dispersion is respected.
+    ///
+    /// This function returns a tuple containing
+    ///   - the (signed) deviation angle between the outgoing and the incoming ray.
+    ///   - the (signed) lateral shift of the outgoing ray with respect to the undeviated incoming ray, measured
+    ///     perpendicular to the incoming ray at the exit point on the rear surface.
+    ///
+    /// `None` is returned if the ray is totally internally reflected at the rear surface.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the properties of the wedge cannot be read.
+    ///   - the refractive indices cannot be determined for the given wavelength.
+    ///   - the incidence angle is not finite or outside ]-90°; 90°[.
+    pub fn beam_deviation(
+        &self,
+        wavelength: Length,
+        incidence_angle: Angle,
+    ) -> OpmResult<Option<(Angle, Length)>> {
+        if !incidence_angle.is_finite() || incidence_angle.get::<degree>().abs() >= 90.0 {
+            return Err(OpossumError::Other(
+                "incidence angle must be within the interval ]-90 deg; 90 deg[ and finite".into(),
+            ));
+        }
+        let (refr_index, center_thickness, wedge_angle) =
+            self.get_node_attributes_ray_trace(&self.node_attr)?;
+        let n = refr_index.get_refractive_index(wavelength)?;
+        let n_ambient = self.ambient_idx().get_refractive_index(wavelength)?;
+        let theta = incidence_angle.get::<radian>();
+        let alpha = wedge_angle.get::<radian>();
+        // refraction at the front surface
+        let theta_inside = (n_ambient * theta.sin() / n).asin();
+        // refraction at the rear surface (tilted by the wedge angle)
+        let incidence_rear = theta_inside + alpha;
+        let sin_exit = n * incidence_rear.sin() / n_ambient;
+        if sin_exit.abs() > 1.0 {
+            return Ok(None);
+        }
+        let theta_out = sin_exit.asin() - alpha;
+        let deviation = Angle::new::<radian>(theta_out - theta);
+        // exit point on the rear surface (y, z)
+        let path_length = center_thickness * alpha.cos() / incidence_rear.cos();
+        let exit_y = path_length * theta_inside.sin();
+        let exit_z = path_length * theta_inside.cos();
+        let lateral_shift = exit_y * theta.cos() - exit_z * theta.sin();
+        Ok(Some((deviation, lateral_shift)))
+    }
+    /// Record the incidence angle and the spectral range of the given (incoming) [`Rays`] for the node report.
+    fn record_incident_beam(&mut self, rays: &Rays) {
+        self.incident_beam = None;
+        if self.inverted() {
+            return;
+        }
+        let Some(iso) = self.effective_node_iso() else {
+            return;
+        };
+        let direction = rays
+            .iter()
+            .filter(|ray| ray.valid())
+            .fold(Vector3::zeros(), |sum, ray| {
+                sum + ray.direction().normalize() * ray.energy().value
+            });
+        let direction = iso.inverse_transform_vector_f64(&direction);
+        if direction.norm().is_zero() {
+            return;
+        }
+        let incidence_angle = Angle::new::<radian>(direction.y.atan2(direction.z));
+        if let (Some(central_wavelength), Some(range)) =
+            (rays.central_wavelength(), rays.wavelength_range())
+        {
+            self.incident_beam = Some((incidence_angle, central_wavelength, range));
+        }
+    }
 }
 
 impl OpticNode for Wedge {
@@ -158,6 +253,83 @@ impl OpticNode for Wedge {
         Ok(())
     }
 
+    fn reset_data(&mut self) {
+        self.incident_beam = None;
+        self.reset_optic_surfaces();
+    }
+    fn node_report(&self, uuid: &str) -> Option<NodeReport> {
+        let (incidence_angle, central_wavelength, range) = self.incident_beam.clone()?;
+        let mut props = Properties::default();
+        props
+            .create(
+                "Incidence angle",
+                "incidence angle of the beam in the plane of the wedge",
+                incidence_angle.into(),
+            )
+            .unwrap();
+        match self.beam_deviation(central_wavelength, incidence_angle) {
+            Ok(Some((deviation, lateral_shift))) => {
+                props
+                    .create(
+                        "Deviation",
+                        "beam deviation at the central wavelength",
+                        deviation.into(),
+                    )
+                    .unwrap();
+                props
+                    .create(
+                        "Lateral shift",
+                        "lateral shift of the beam at the central wavelength",
+                        lateral_shift.into(),
+                    )
+                    .unwrap();
+            }
+            Ok(None) => {
+                props
+                    .create(
+                        "Deviation",
+                        "beam deviation at the central wavelength",
+                        "total internal reflection".into(),
+                    )
+                    .unwrap();
+            }
+            Err(_) => return None,
+        }
+        if range.start < range.end
+            && let (Ok(Some((deviation_min, _))), Ok(Some((deviation_max, _)))) = (
+                self.beam_deviation(range.start, incidence_angle),
+                self.beam_deviation(range.end, incidence_angle),
+            )
+        {
+            props
+                .create(
+                    "Deviation (min. wavelength)",
+                    "beam deviation at the minimum wavelength of the beam",
+                    deviation_min.into(),
+                )
+                .unwrap();
+            props
+                .create(
+                    "Deviation (max. wavelength)",
+                    "beam deviation at the maximum wavelength of the beam",
+                    deviation_max.into(),
+                )
+                .unwrap();
+            props
+                .create(
+                    "Angular dispersion",
+                    "difference of the beam deviation between minimum and maximum wavelength",
+                    (deviation_min - deviation_max).into(),
+                )
+                .unwrap();
+        }
+        Some(NodeReport::new(
+            &self.node_type(),
+            &self.name(),
+            uuid,
+            props,
+        ))
+    }
     fn node_attr(&self) -> &NodeAttr {
         &self.node_attr
     }
@@ -183,6 +355,7 @@ mod test {
         spectrum_helper::create_he_ne_spec,
     };
     use nalgebra::Vector3;
+    use uom::si::length::millimeter;
 
     #[test]
     fn default() {
@@ -413,4 +586,90 @@ mod test {
             assert!(false, "could not get LightData");
         }
     }
+    #[test]
+    fn beam_deviation() {
+        let node = Wedge::default();
+        assert!(
+            node.beam_deviation(nanometer!(1000.0), degree!(90.0))
+                .is_err()
+        );
+        assert!(
+            node.beam_deviation(nanometer!(1000.0), degree!(f64::NAN))
+                .is_err()
+        );
+        let (deviation, shift) = node
+            .beam_deviation(nanometer!(1000.0), degree!(0.0))
+            .unwrap()
+            .unwrap();
+        assert!(deviation.get::<degree>().abs() < 1.0e-12);
+        assert!(shift.get::<millimeter>().abs() < 1.0e-12);
+    }
+    #[test]
+    fn beam_deviation_tilted_window() {
+        let node = Wedge::default();
+        let theta = degree!(30.0).get::<radian>();
+        let theta_inside = (theta.sin() / 1.5).asin();
+        let (deviation, shift) = node
+            .beam_deviation(nanometer!(1000.0), degree!(30.0))
+            .unwrap()
+            .unwrap();
+        assert!(deviation.get::<degree>().abs() < 1.0e-12);
+        let expected = 10.0 * (theta - theta_inside).sin() / theta_inside.cos();
+        assert!((shift.get::<millimeter>().abs() - expected).abs() < 1.0e-12);
+    }
+    #[test]
+    fn beam_deviation_wedge() {
+        let node = Wedge::new(
+            "test",
+            millimeter!(10.0),
+            degree!(0.1),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        let (deviation, _) = node
+            .beam_deviation(nanometer!(1000.0), degree!(0.0))
+            .unwrap()
+            .unwrap();
+        assert!((deviation.get::<degree>() - 0.05).abs() < 1.0e-5);
+        let node = Wedge::new(
+            "test",
+            millimeter!(10.0),
+            degree!(45.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        assert!(
+            node.beam_deviation(nanometer!(1000.0), degree!(0.0))
+                .unwrap()
+                .is_none()
+        );
+    }
+    #[test]
+    fn report() {
+        let mut node = Wedge::new(
+            "test",
+            millimeter!(10.0),
+            degree!(1.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap();
+        assert!(node.node_report("").is_none());
+        node.set_isometry(
+            Isometry::new(millimeter!(0.0, 0.0, 10.0), degree!(0.0, 0.0, 0.0)).unwrap(),
+        )
+        .unwrap();
+        let mut input = LightResult::default();
+        let mut rays = Rays::default();
+        rays.add_ray(Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap());
+        input.insert("input_1".into(), LightData::Geometric(rays));
+        AnalysisRayTrace::analyze(&mut node, input, &RayTraceConfig::default()).unwrap();
+        let report = node.node_report("").unwrap();
+        let props = report.properties();
+        assert!(props.contains("Incidence angle"));
+        assert!(props.contains("Deviation"));
+        assert!(props.contains("Lateral shift"));
+        assert!(!props.contains("Angular dispersion"));
+        node.reset_data();
+        assert!(node.node_report("").is_none());
+    }
 }
@@ -8,13 +8,19 @@
 use crate::{
     SceneryResources,
     analyzers::{
-        Analyzer, AnalyzerType, energy::EnergyAnalyzer, ghostfocus::GhostFocusAnalyzer,
-        raytrace::RayTracingAnalyzer,
+        AnalysisProgress, Analyzer, AnalyzerType, energy::EnergyAnalyzer,
+        gaussian_beam::GaussianBeamAnalyzer, ghostfocus::GhostFocusAnalyzer,
+        progress::with_progress_hook, raytrace::RayTracingAnalyzer,
+        system_transmission::SystemTransmissionAnalyzer, wavelength_sweep::WavelengthSweep,
     },
     error::{OpmResult, OpossumError},
-    nodes::NodeGroup,
+    lightdata::light_data_builder::LightDataBuilder,
+    nodes::{ConnectionInfo, LegacyNodeGroup, NodeGroup, Warning, WarningKind},
     optic_node::OpticNode,
+    optic_ref::OpticRef,
+    refractive_index::RefractiveIndexType,
     reporting::analysis_report::AnalysisReport,
+    utils::deserialize_some,
 };
 use log::{info, warn};
 use nalgebra::Point2;
@@ -23,17 +29,22 @@ use std::{
     collections::HashMap,
     fs::{self, File},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
+use uom::si::f64::{Length, ThermodynamicTemperature};
 use utoipa::ToSchema;
 use uuid::Uuid;
 /// A structu containing the [`AnalyzerType`] together with its position on a frontend GUI.
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct AnalyzerInfo {
     analyzer_type: AnalyzerType,
     id: Uuid,
     gui_position: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    wavelength_sweep: Option<WavelengthSweep>,
 }
 impl AnalyzerInfo {
     /// Creates a new [`AnalyzerInfo`].
@@ -44,8 +55,20 @@ impl AnalyzerInfo {
             analyzer_type,
             id,
             gui_position: Some((gui_position.x, gui_position.y)),
+            wavelength_sweep: None,
         }
     }
+    /// Repeat the analysis of this [`AnalyzerInfo`] for the wavelengths of the given [`WavelengthSweep`].
+    #[must_use]
+    pub fn with_wavelength_sweep(mut self, wavelength_sweep: WavelengthSweep) -> Self {
+        self.wavelength_sweep = Some(wavelength_sweep);
+        self
+    }
+    /// Returns the [`WavelengthSweep`] (if any) of this [`AnalyzerInfo`].
+    #[must_use]
+    pub const fn wavelength_sweep(&self) -> Option<&WavelengthSweep> {
+        self.wavelength_sweep.as_ref()
+    }
     /// Returns the gui position of this [`AnalyzerInfo`].
     #[must_use]
     pub fn gui_position(&self) -> Option<Point2<f64>> {
@@ -67,7 +90,62 @@ impl AnalyzerInfo {
     }
 }
 
+/// A sub-document included into an [`OpmDocument`].
+///
+/// The scenery of the included `.opm` file is inserted as a [`NodeGroup`] into the (toplevel) scenery of the including
+/// document. Its analyzers and global configuration are ignored. The group gets the [`Uuid`] of this [`Include`], while
+/// the [`Uuid`]s of all nodes inside the group are derived from the original [`Uuid`]s and the [`Uuid`] of the
+/// [`Include`]. This way, the same file can be included several times without collisions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct Include {
+    path: PathBuf,
+    id: Uuid,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(
+        feature = "json_schema",
+        schemars(with = "Vec<(Uuid, String, Uuid, String, f64)>")
+    )]
+    connections: Vec<ConnectionInfo>,
+}
+impl Include {
+    /// Creates a new [`Include`] of the `.opm` file with the given path.
+    ///
+    /// A relative path is resolved relative to the directory of the including file.
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            id: Uuid::new_v4(),
+            connections: Vec::new(),
+        }
+    }
+    /// Returns the path of the included file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Returns the [`Uuid`] of the [`NodeGroup`] containing the included scenery.
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        self.id
+    }
+    /// Returns the [`Uuid`] of a node of the included file after insertion into the including document.
+    #[must_use]
+    pub fn namespaced_id(&self, node_id: Uuid) -> Uuid {
+        uuid::Builder::from_random_bytes((self.id.as_u128() ^ node_id.as_u128()).to_be_bytes())
+            .into_uuid()
+    }
+}
+
+/// Minimal view of an `.opm` file used for checking the file version before the actual parsing.
+#[derive(Deserialize)]
+struct OpmHeader {
+    opm_file_version: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// The main structure of an OPOSSUM model.
 /// It contains the [`NodeGroup`] representing the optical model, a list of analyzers and a global configuration.
 pub struct OpmDocument {
@@ -78,6 +156,40 @@ pub struct OpmDocument {
     global_conf: Arc<Mutex<SceneryResources>>,
     #[serde(default)]
     analyzers: HashMap<Uuid, AnalyzerInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    includes: Vec<Include>,
+}
+/// Layout of an [`OpmDocument`] in `.opm` files of version `0`.
+///
+/// Properties of these files might still be stored together with their description and validator (see
+/// [`LegacyProperty`](crate::properties::property::LegacyProperty)).
+#[derive(Deserialize)]
+struct LegacyOpmDocument {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    scenery: Option<LegacyNodeGroup>,
+    #[serde(default, rename = "global")]
+    global_conf: Arc<Mutex<SceneryResources>>,
+    #[serde(default)]
+    analyzers: HashMap<Uuid, AnalyzerInfo>,
+    #[serde(default)]
+    includes: Vec<Include>,
+}
+impl TryFrom<LegacyOpmDocument> for OpmDocument {
+    type Error = OpossumError;
+
+    fn try_from(legacy: LegacyOpmDocument) -> OpmResult<Self> {
+        Ok(Self {
+            opm_file_version: env!("OPM_FILE_VERSION").to_string(),
+            scenery: legacy
+                .scenery
+                .map(NodeGroup::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            global_conf: legacy.global_conf,
+            analyzers: legacy.analyzers,
+            includes: legacy.includes,
+        })
+    }
 }
 impl Default for OpmDocument {
     fn default() -> Self {
@@ -86,6 +198,7 @@ impl Default for OpmDocument {
             scenery: NodeGroup::default(),
             global_conf: Arc::new(Mutex::new(SceneryResources::default())),
             analyzers: HashMap::default(),
+            includes: Vec::new(),
         }
     }
 }
@@ -93,33 +206,102 @@ impl OpmDocument {
     /// Creates a new [`OpmDocument`].
     #[must_use]
     pub fn new(mut scenery: NodeGroup) -> Self {
-        scenery.set_global_conf(Some(Arc::new(Mutex::new(SceneryResources::default()))));
+        let global_conf = Arc::new(Mutex::new(SceneryResources::default()));
+        scenery.set_global_conf(Some(global_conf.clone()));
         Self {
             scenery,
+            global_conf,
             ..Default::default()
         }
     }
+    /// Returns the JSON schema (draft 2020-12) of an [`OpmDocument`].
+    ///
+    /// The schema is derived from the serialized data structures and lists all available node types as well as the
+    /// shapes of all property values (see [`Proptype`](crate::properties::Proptype)). Physical quantities are given as
+    /// plain numbers in SI base units. Analysis results stored in properties (e.g. spot diagrams) are not described in
+    /// detail. The schema can be used for validating `.opm` files (after conversion to JSON) or for generating input
+    /// forms in a GUI.
+    ///
+    /// This function is only available with the `json_schema` feature enabled.
+    #[cfg(feature = "json_schema")]
+    #[must_use]
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(Self).to_value()
+    }
     /// Create a new [`OpmDocument`] from an `.opm` file at the given path.
     ///
+    /// Included files (see [`Include`]) are resolved relative to the directory of the given file. Files of version `0`
+    /// written by older versions of OPOSSUM, which still store properties including their description and validator,
+    /// are migrated to the current layout.
+    ///
     /// # Errors
     ///
     /// This function will return an error if
     ///   - the given path is not found or readable.
+    ///   - the file was written by a newer version of OPOSSUM (i.e. with a newer `opm_file_version`).
     ///   - the parsing / deserialization of the file failed.
+    ///   - an included file could not be read or includes itself (directly or indirectly).
     pub fn from_file(path: &Path) -> OpmResult<Self> {
+        let path = path.canonicalize().map_err(|e| {
+            OpossumError::OpmDocument(format!("cannot read file {} : {}", path.display(), e))
+        })?;
+        Self::from_file_with_include_stack(&path, &mut Vec::new())
+    }
+    fn from_file_with_include_stack(
+        path: &Path,
+        include_stack: &mut Vec<PathBuf>,
+    ) -> OpmResult<Self> {
+        if include_stack.iter().any(|p| p == path) {
+            return Err(OpossumError::OpmDocument(format!(
+                "cyclic include of file {}",
+                path.display()
+            )));
+        }
         let contents = fs::read_to_string(path).map_err(|e| {
             OpossumError::OpmDocument(format!("cannot read file {} : {}", path.display(), e))
         })?;
-        Self::from_string(&contents)
+        include_stack.push(path.to_path_buf());
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let document = Self::from_string_with_include_stack(&contents, base_dir, include_stack);
+        include_stack.pop();
+        document
     }
     /// Create a new [`OpmDocument`] from the given `.opm` file string.
     ///
+    /// Included files (see [`Include`]) are resolved relative to the current working directory. Files of version `0`
+    /// (which might still store properties including their description and validator) are migrated to the current
+    /// layout. Saving the document afterwards writes the file in the current layout and version.
+    ///
     /// # Errors
     ///
-    /// This function will return an error if the parsing of the `.opm` file failed.
+    /// This function will return an error if
+    ///   - the file was written by a newer version of OPOSSUM (i.e. with a newer `opm_file_version`).
+    ///   - the parsing of the `.opm` file failed.
+    ///   - an included file could not be read or includes itself (directly or indirectly).
     pub fn from_string(file_string: &str) -> OpmResult<Self> {
-        let mut document: Self = ron::from_str(file_string)
-            .map_err(|e| OpossumError::OpmDocument(format!("parsing of model failed: {e}")))?;
+        Self::from_string_with_include_stack(file_string, Path::new("."), &mut Vec::new())
+    }
+    fn from_string_with_include_stack(
+        file_string: &str,
+        base_dir: &Path,
+        include_stack: &mut Vec<PathBuf>,
+    ) -> OpmResult<Self> {
+        let header = ron::from_str::<OpmHeader>(file_string).ok();
+        if let Some(header) = &header {
+            check_file_version(&header.opm_file_version)?;
+        }
+        let mut document: Self = if header
+            .as_ref()
+            .is_some_and(|header| is_legacy_file_version(&header.opm_file_version))
+        {
+            info!("migrating file of version 0 to the current layout");
+            ron::from_str::<LegacyOpmDocument>(file_string)
+                .map_err(|e| OpossumError::OpmDocument(format!("parsing of model failed: {e}")))?
+                .try_into()?
+        } else {
+            ron::from_str(file_string)
+                .map_err(|e| OpossumError::OpmDocument(format!("parsing of model failed: {e}")))?
+        };
         if document.opm_file_version != env!("OPM_FILE_VERSION") {
             warn!("OPM file version does not match the used OPOSSUM version.");
             warn!(
@@ -136,8 +318,79 @@ impl OpmDocument {
             .scenery
             .graph_mut()
             .update_global_config(&Some(document.global_conf.clone()));
+        for include in document.includes.clone() {
+            document.insert_include(&include, base_dir, include_stack)?;
+        }
+        for include in &document.includes {
+            for connection in &include.connections {
+                document.scenery.connect_nodes(
+                    connection.0,
+                    &connection.1,
+                    connection.2,
+                    &connection.3,
+                    connection.4,
+                )?;
+            }
+        }
         Ok(document)
     }
+    /// Load the scenery of the given [`Include`] and insert it as a [`NodeGroup`] into the scenery.
+    fn insert_include(
+        &mut self,
+        include: &Include,
+        base_dir: &Path,
+        include_stack: &mut Vec<PathBuf>,
+    ) -> OpmResult<()> {
+        let path = base_dir.join(&include.path).canonicalize().map_err(|e| {
+            OpossumError::OpmDocument(format!(
+                "cannot read included file {} : {}",
+                include.path.display(),
+                e
+            ))
+        })?;
+        let sub_document = Self::from_file_with_include_stack(&path, include_stack)?;
+        let group = OpticRef::new(Arc::new(Mutex::new(sub_document.scenery)), None);
+        let group_id = group.uuid();
+        let group = group.deep_copy(|node_id| {
+            if node_id == group_id {
+                include.id
+            } else {
+                include.namespaced_id(node_id)
+            }
+        })?;
+        group.update_global_config(Some(self.global_conf.clone()));
+        self.scenery.add_node_ref(group)?;
+        Ok(())
+    }
+    /// Include the scenery of another `.opm` file into this [`OpmDocument`].
+    ///
+    /// The scenery of the file is inserted as a (not yet connected) [`NodeGroup`] into the scenery of this document. A
+    /// relative path of the [`Include`] is resolved relative to the given `base_dir`. Connections with the inserted
+    /// group are stored together with the [`Include`] when saving this document. The function returns the [`Uuid`] of
+    /// the inserted group.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the included file could not be read or parsed.
+    ///   - the included file includes itself (directly or indirectly).
+    ///   - a node with the [`Uuid`] of the [`Include`] already exists.
+    pub fn add_include(&mut self, include: Include, base_dir: &Path) -> OpmResult<Uuid> {
+        if self.scenery.node(include.id).is_ok() {
+            return Err(OpossumError::OpmDocument(
+                "node with the uuid of the include already exists".into(),
+            ));
+        }
+        self.insert_include(&include, base_dir, &mut Vec::new())?;
+        let id = include.id;
+        self.includes.push(include);
+        Ok(id)
+    }
+    /// Returns the included sub-documents of this [`OpmDocument`].
+    #[must_use]
+    pub fn includes(&self) -> &[Include] {
+        &self.includes
+    }
     /// Save this [`OpmDocument`] to an `.opm` file with the given path
     ///
     /// # Errors
@@ -170,9 +423,35 @@ impl OpmDocument {
     ///
     /// This function will return an error if the serialization of the internal structures fail.
     pub fn to_opm_file_string(&self) -> OpmResult<String> {
-        ron::ser::to_string_pretty(&self, ron::ser::PrettyConfig::new().new_line("\n")).map_err(
-            |e| OpossumError::OpticScenery(format!("serialization of OpmDocument failed: {e}")),
-        )
+        let serialize = |document: &Self| {
+            ron::ser::to_string_pretty(document, ron::ser::PrettyConfig::new().new_line("\n"))
+                .map_err(|e| {
+                    OpossumError::OpticScenery(format!("serialization of OpmDocument failed: {e}"))
+                })
+        };
+        if self.includes.is_empty() {
+            return serialize(self);
+        }
+        // included groups are not stored inline but only their connections (each one stored with the first
+        // include involved)
+        let mut document = self.clone();
+        let include_ids: Vec<Uuid> = self.includes.iter().map(Include::id).collect();
+        for include in &mut document.includes {
+            include.connections.clear();
+        }
+        for connection in self.scenery.connections() {
+            if let Some(include) = document
+                .includes
+                .iter_mut()
+                .find(|i| i.id == connection.0 || i.id == connection.2)
+            {
+                include.connections.push(connection);
+            }
+        }
+        document
+            .scenery
+            .set_graph(self.scenery.graph().without_nodes(&include_ids));
+        serialize(&document)
     }
     /// Returns the list of analyzers of this [`OpmDocument`].
     #[must_use]
@@ -200,6 +479,7 @@ impl OpmDocument {
             analyzer_type,
             id: Uuid::new_v4(),
             gui_position: None,
+            wavelength_sweep: None,
         };
         self.add_analyzer_info(&analyzer_info)
     }
@@ -213,6 +493,7 @@ impl OpmDocument {
             analyzer_type,
             id: Uuid::new_v4(),
             gui_position,
+            wavelength_sweep: None,
         };
         self.add_analyzer_info(&analyzer_info)
     }
@@ -259,6 +540,218 @@ impl OpmDocument {
             .graph_mut()
             .update_global_config(&Some(self.global_conf.clone()));
     }
+    /// Returns the refractive index of the ambient medium of this [`OpmDocument`].
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    #[must_use]
+    pub fn ambient_refr_index(&self) -> RefractiveIndexType {
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .ambient_refr_index
+            .clone()
+    }
+    /// Sets the refractive index of the ambient medium of this [`OpmDocument`].
+    ///
+    /// The ambient medium is shared by all nodes of the scenery (including nodes added later on).
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    pub fn set_ambient_refr_index(&mut self, refr_index: RefractiveIndexType) {
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .ambient_refr_index = refr_index;
+    }
+    /// Returns the default wavelength (if any) of this [`OpmDocument`].
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    #[must_use]
+    pub fn default_wavelength(&self) -> Option<Length> {
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .default_wavelength
+    }
+    /// Sets the default wavelength of this [`OpmDocument`].
+    ///
+    /// This wavelength is used for aligning the nodes of the scenery if neither the analyzer nor the source
+    /// defines an alignment wavelength. Use `None` for falling back to the central wavelength of the source.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given wavelength is not positive and finite.
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    pub fn set_default_wavelength(&mut self, wavelength: Option<Length>) -> OpmResult<()> {
+        if let Some(wvl) = wavelength
+            && (!wvl.is_normal() || wvl.is_sign_negative())
+        {
+            return Err(OpossumError::OpmDocument(
+                "default wavelength must be positive and finite".into(),
+            ));
+        }
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .default_wavelength = wavelength;
+        Ok(())
+    }
+    /// Returns the ambient temperature (if any) of this [`OpmDocument`].
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    #[must_use]
+    pub fn ambient_temperature(&self) -> Option<ThermodynamicTemperature> {
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .ambient_temperature
+    }
+    /// Sets the ambient temperature of this [`OpmDocument`].
+    ///
+    /// This temperature is used for evaluating temperature-dependent refractive index models of all nodes (and the
+    /// ambient medium) which do not define an explicit operating temperature. Use `None` for evaluating these models
+    /// at their reference temperature.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given temperature is not finite or below absolute zero.
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    pub fn set_ambient_temperature(
+        &mut self,
+        temperature: Option<ThermodynamicTemperature>,
+    ) -> OpmResult<()> {
+        if let Some(temperature) = temperature
+            && (!temperature.is_finite() || temperature.is_sign_negative())
+        {
+            return Err(OpossumError::OpmDocument(
+                "ambient temperature must be finite and above absolute zero".into(),
+            ));
+        }
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .ambient_temperature = temperature;
+        Ok(())
+    }
+    /// Returns the resolution of fluence maps (if any) of this [`OpmDocument`].
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    #[must_use]
+    pub fn fluence_grid(&self) -> Option<(usize, usize)> {
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .fluence_grid
+    }
+    /// Sets the resolution (number of grid points in x and y) of fluence maps of this [`OpmDocument`].
+    ///
+    /// This resolution is used by all [`FluenceDetector`](crate::nodes::FluenceDetector)s of the scenery. Use `None`
+    /// for the default resolution ([`DEFAULT_FLUENCE_GRID`](crate::DEFAULT_FLUENCE_GRID)).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if one of the given dimensions is zero.
+    ///
+    /// # Panics
+    ///
+    /// This function might theoretically panic if locking of an internal mutex fails.
+    pub fn set_fluence_grid(&mut self, fluence_grid: Option<(usize, usize)>) -> OpmResult<()> {
+        if let Some((nr_x, nr_y)) = fluence_grid
+            && (nr_x == 0 || nr_y == 0)
+        {
+            return Err(OpossumError::OpmDocument(
+                "fluence grid must have at least one point in each direction".into(),
+            ));
+        }
+        self.global_conf
+            .lock()
+            .expect("Mutex lock failed")
+            .fluence_grid = fluence_grid;
+        Ok(())
+    }
+    /// Validate this [`OpmDocument`] without performing an analysis ("dry run").
+    ///
+    /// This function checks the scenery (see [`NodeGroup::validate`]) and additionally reports
+    ///   - a missing analyzer definition
+    ///   - a scenery without any source
+    ///   - nodes which cannot be reached by light from any source (see [`NodeGroup::unreachable_nodes`])
+    ///   - sources whose light data is not compatible with the defined analyzers (e.g. energy data for a ray
+    ///     tracing analysis)
+    ///
+    /// All findings are returned as a list of [`Warning`]s. An empty list indicates that no problems were found.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the internal node mutexes could not be locked.
+    pub fn validate(&self) -> OpmResult<Vec<Warning>> {
+        let mut warnings = self.scenery.validate()?;
+        if self.analyzers.is_empty() {
+            warnings.push(Warning::new(
+                WarningKind::NoAnalyzer,
+                None,
+                "document does not define any analyzer",
+            ));
+        }
+        let sources = self.scenery.source_light_data()?;
+        if sources.is_empty() {
+            warnings.push(Warning::new(
+                WarningKind::NoSource,
+                None,
+                "scenery does not contain any source",
+            ));
+        } else {
+            for (node_id, node_name) in self.scenery.unreachable_nodes()? {
+                let is_stale = warnings
+                    .iter()
+                    .any(|w| w.kind() == WarningKind::StaleNode && w.node_id() == Some(node_id));
+                if !is_stale {
+                    warnings.push(Warning::new(
+                        WarningKind::UnreachableNode,
+                        Some(node_id),
+                        &format!("node '{node_name}' cannot be reached by light from any source"),
+                    ));
+                }
+            }
+        }
+        let needs_rays = self.analyzers.values().any(|analyzer_info| {
+            matches!(
+                analyzer_info.analyzer_type,
+                AnalyzerType::RayTrace(_) | AnalyzerType::GhostFocus(_)
+            )
+        });
+        if needs_rays {
+            for (node_id, node_info, light_data) in sources {
+                if matches!(
+                    light_data,
+                    Some(LightDataBuilder::Energy(_) | LightDataBuilder::Fourier)
+                ) {
+                    warnings.push(Warning::new(
+                        WarningKind::LightDataMismatch,
+                        Some(node_id),
+                        &format!(
+                            "source {node_info} does not emit rays as needed for ray tracing analyses"
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(warnings)
+    }
     /// Perform an analysis run of this [`OpmDocument`].
     ///
     /// This function will perform the analysis of the defined analyzers in the order they were added.
@@ -278,21 +771,68 @@ impl OpmDocument {
                 AnalyzerType::Energy => &EnergyAnalyzer::default(),
                 AnalyzerType::RayTrace(config) => &RayTracingAnalyzer::new(config.clone()),
                 AnalyzerType::GhostFocus(config) => &GhostFocusAnalyzer::new(config.clone()),
+                AnalyzerType::SystemTransmission(config) => {
+                    &SystemTransmissionAnalyzer::new(config.clone())
+                }
+                AnalyzerType::GaussianBeam(config) => &GaussianBeamAnalyzer::new(config.clone()),
             };
             info!("Analysis #{}", ana.0);
-            analyzer.analyze(&mut self.scenery)?;
-            reports.push(analyzer.report(&self.scenery)?);
+            if let Some(wavelength_sweep) = ana.1.1.wavelength_sweep() {
+                let (_, report) = wavelength_sweep.run(analyzer, &mut self.scenery)?;
+                reports.push(report);
+            } else {
+                analyzer.analyze(&mut self.scenery)?;
+                reports.push(analyzer.report(&self.scenery)?);
+            }
             self.scenery.clear_edges();
             self.scenery.reset_data();
         }
         Ok(reports)
     }
+    /// Perform an analysis run of this [`OpmDocument`] while reporting its progress.
+    ///
+    /// This function behaves like [`analyze`](OpmDocument::analyze) but calls the given closure with an
+    /// [`AnalysisProgress`] before each node is processed by a ray tracing or ghost focus analysis. This can be used
+    /// for displaying a progress bar.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the individual analyzers fail to perform the analysis.
+    pub fn analyze_with_progress<F: Fn(&AnalysisProgress) + 'static>(
+        &mut self,
+        progress: F,
+    ) -> OpmResult<Vec<AnalysisReport>> {
+        with_progress_hook(Box::new(progress), || self.analyze())
+    }
     /// Returns a mutable reference to the analyzers of this [`OpmDocument`].
     pub const fn analyzers_mut(&mut self) -> &mut HashMap<Uuid, AnalyzerInfo> {
         &mut self.analyzers
     }
 }
 
+/// Returns `true` if the given `opm_file_version` denotes a file that has to be migrated from its legacy layout (see
+/// [`LegacyOpmDocument`]).
+fn is_legacy_file_version(opm_file_version: &str) -> bool {
+    opm_file_version
+        .parse::<u32>()
+        .is_ok_and(|file_version| file_version < 1)
+}
+/// Check that the given `opm_file_version` is not newer than the version written by this version of OPOSSUM.
+fn check_file_version(opm_file_version: &str) -> OpmResult<()> {
+    let supported_version = env!("OPM_FILE_VERSION");
+    let is_newer = opm_file_version
+        .parse::<u32>()
+        .ok()
+        .zip(supported_version.parse::<u32>().ok())
+        .is_some_and(|(file_version, supported_version)| file_version > supported_version);
+    if is_newer {
+        return Err(OpossumError::OpmDocument(format!(
+            "file version {opm_file_version} is newer than the supported version {supported_version}. Please update OPOSSUM to read this file"
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -301,22 +841,30 @@ mod test {
             Analyzer, GhostFocusConfig, RayTraceConfig, ghostfocus::GhostFocusAnalyzer,
             raytrace::RayTracingAnalyzer,
         },
-        degree, joule, millimeter, nanometer,
+        degree, joule,
+        lightdata::energy_data_builder::EnergyDataBuilder,
+        millimeter, nanometer,
         nodes::{
             BeamSplitter, CylindricLens, Dummy, EnergyMeter, FluenceDetector, IdealFilter, Lens,
-            ParabolicMirror, ParaxialSurface, RayPropagationVisualizer, ReflectiveGrating,
+            ParabolicMirror, ParaxialSurface, RayPropagationVisualizer, ReflectiveGrating, Source,
             Spectrometer, SpotDiagram, ThinMirror, WaveFront, Wedge, collimated_line_ray_source,
             round_collimated_ray_source,
         },
         optic_node::{Alignable, OpticNode},
-        refractive_index::RefrIndexConst,
+        optic_ports::PortType,
+        refractive_index::{RefrIndexAir, RefrIndexConst},
+        spectrum_helper::create_he_ne_spec,
         utils::test_helper::test_helper::check_logs,
     };
+    use approx::assert_relative_eq;
     use std::{
+        cell::RefCell,
         path::PathBuf,
+        rc::Rc,
         sync::{Arc, Mutex},
     };
     use tempfile::NamedTempFile;
+    use uom::si::thermodynamic_temperature::degree_celsius;
 
     #[test]
     fn new() {
@@ -333,6 +881,180 @@ mod test {
         assert!(document.analyzers.is_empty());
     }
 
+    #[test]
+    fn ambient_refr_index() {
+        let mut scenery = NodeGroup::default();
+        let before = scenery.add_node(Dummy::default()).unwrap();
+        let mut document = OpmDocument::new(scenery);
+        let after = document.scenery_mut().add_node(Dummy::default()).unwrap();
+        assert!(matches!(
+            document.ambient_refr_index(),
+            RefractiveIndexType::Const(_)
+        ));
+        document.set_ambient_refr_index(RefrIndexAir::standard().into());
+        assert!(matches!(
+            document.ambient_refr_index(),
+            RefractiveIndexType::Air(_)
+        ));
+        for uuid in [before, after] {
+            let node = document.scenery().node(uuid).unwrap();
+            assert!(matches!(
+                node.optical_ref.lock().unwrap().ambient_idx(),
+                RefractiveIndexType::Air(_)
+            ));
+        }
+    }
+    #[test]
+    fn default_wavelength() {
+        let mut document = OpmDocument::default();
+        assert!(document.default_wavelength().is_none());
+        assert!(
+            document
+                .set_default_wavelength(Some(nanometer!(0.0)))
+                .is_err()
+        );
+        assert!(
+            document
+                .set_default_wavelength(Some(nanometer!(-1.0)))
+                .is_err()
+        );
+        assert!(
+            document
+                .set_default_wavelength(Some(nanometer!(f64::NAN)))
+                .is_err()
+        );
+        document
+            .set_default_wavelength(Some(nanometer!(1053.0)))
+            .unwrap();
+        assert_eq!(document.default_wavelength(), Some(nanometer!(1053.0)));
+        let serialized = document.to_opm_file_string().unwrap();
+        let document = OpmDocument::from_string(&serialized).unwrap();
+        assert_eq!(document.default_wavelength(), Some(nanometer!(1053.0)));
+        let mut document = document;
+        document.set_default_wavelength(None).unwrap();
+        assert!(document.default_wavelength().is_none());
+    }
+    #[test]
+    fn ambient_temperature() {
+        let celsius = ThermodynamicTemperature::new::<degree_celsius>;
+        let mut scenery = NodeGroup::default();
+        let uuid = scenery.add_node(Dummy::default()).unwrap();
+        let mut document = OpmDocument::new(scenery);
+        assert!(document.ambient_temperature().is_none());
+        assert!(
+            document
+                .set_ambient_temperature(Some(celsius(f64::NAN)))
+                .is_err()
+        );
+        assert!(
+            document
+                .set_ambient_temperature(Some(celsius(-300.0)))
+                .is_err()
+        );
+        document.set_ambient_refr_index(
+            RefractiveIndexType::from(RefrIndexConst::new(1.0003).unwrap())
+                .with_thermal(-1.0e-6, 0.0, celsius(20.0))
+                .unwrap(),
+        );
+        document
+            .set_ambient_temperature(Some(celsius(30.0)))
+            .unwrap();
+        assert_eq!(document.ambient_temperature(), Some(celsius(30.0)));
+        let serialized = document.to_opm_file_string().unwrap();
+        let mut document = OpmDocument::from_string(&serialized).unwrap();
+        assert_eq!(document.ambient_temperature(), Some(celsius(30.0)));
+        let node = document.scenery().node(uuid).unwrap();
+        let node = node.optical_ref.lock().unwrap();
+        assert_eq!(node.ambient_temperature(), Some(celsius(30.0)));
+        assert_relative_eq!(
+            node.ambient_idx()
+                .get_refractive_index(nanometer!(1053.0))
+                .unwrap(),
+            1.000_29
+        );
+        drop(node);
+        document.set_ambient_temperature(None).unwrap();
+        assert!(document.ambient_temperature().is_none());
+    }
+    #[test]
+    fn validate() {
+        let document = OpmDocument::default();
+        let warnings = document.validate().unwrap();
+        let kinds: Vec<WarningKind> = warnings.iter().map(Warning::kind).collect();
+        assert_eq!(kinds, vec![WarningKind::NoAnalyzer, WarningKind::NoSource]);
+
+        let mut scenery = NodeGroup::default();
+        let src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(1.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let dummy = scenery.add_node(Dummy::default()).unwrap();
+        scenery
+            .connect_nodes(src, "output_1", dummy, "input_1", millimeter!(10.0))
+            .unwrap();
+        let mut document = OpmDocument::new(scenery);
+        document.add_analyzer(AnalyzerType::RayTrace(RayTraceConfig::default()));
+        assert!(document.validate().unwrap().is_empty());
+
+        let unconnected = document.scenery_mut().add_node(Dummy::default()).unwrap();
+        let empty_src = document
+            .scenery_mut()
+            .add_node(Source::new(
+                "empty",
+                LightDataBuilder::Energy(EnergyDataBuilder::Raw(create_he_ne_spec(1.0).unwrap())),
+            ))
+            .unwrap();
+        let warnings = document.validate().unwrap();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.kind() == WarningKind::UnconnectedGraph)
+        );
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.kind() == WarningKind::StaleNode && w.node_id() == Some(unconnected))
+        );
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.kind() == WarningKind::LightDataMismatch
+                    && w.node_id() == Some(empty_src))
+        );
+        assert!(
+            !warnings
+                .iter()
+                .any(|w| w.kind() == WarningKind::UnreachableNode)
+        );
+    }
+    #[test]
+    fn fluence_grid() {
+        let mut document = OpmDocument::default();
+        assert!(document.fluence_grid().is_none());
+        assert!(document.set_fluence_grid(Some((0, 10))).is_err());
+        assert!(document.set_fluence_grid(Some((10, 0))).is_err());
+        document.set_fluence_grid(Some((200, 150))).unwrap();
+        assert_eq!(document.fluence_grid(), Some((200, 150)));
+        let serialized = document.to_opm_file_string().unwrap();
+        let mut document = OpmDocument::from_string(&serialized).unwrap();
+        assert_eq!(document.fluence_grid(), Some((200, 150)));
+        document.set_fluence_grid(None).unwrap();
+        assert!(document.fluence_grid().is_none());
+    }
+    #[test]
+    fn serialized_without_validators() {
+        let mut scenery = NodeGroup::default();
+        for (node_type, _) in crate::nodes::node_types() {
+            scenery
+                .add_node_ref(crate::nodes::create_node_ref(node_type).unwrap())
+                .unwrap();
+        }
+        let document = OpmDocument::new(scenery);
+        let serialized = document.to_opm_file_string().unwrap();
+        assert!(!serialized.contains("_marker"));
+        assert!(!serialized.contains("validator"));
+        let document = OpmDocument::from_string(&serialized).unwrap();
+        assert_eq!(document.to_opm_file_string().unwrap(), serialized);
+    }
     #[test]
     fn from_file() {
         let result =
@@ -352,6 +1074,85 @@ mod test {
         );
     }
     #[test]
+    fn migrate_legacy_file() {
+        let document =
+            OpmDocument::from_file(&PathBuf::from("./files_for_testing/opm/legacy_v0.opm"))
+                .unwrap();
+        assert!(document.scenery.expand_view().unwrap());
+        assert_eq!(document.scenery.nr_of_nodes(), 2);
+        let serialized = document.to_opm_file_string().unwrap();
+        assert!(!serialized.contains("validator"));
+        assert!(!serialized.contains("prop:"));
+        assert_eq!(document.opm_file_version, env!("OPM_FILE_VERSION"));
+        // the legacy layout is only accepted for files of version 0
+        let legacy = fs::read_to_string("./files_for_testing/opm/legacy_v0.opm").unwrap();
+        let current_version = legacy.replacen(
+            "opm_file_version: \"0\"",
+            &format!("opm_file_version: \"{}\"", env!("OPM_FILE_VERSION")),
+            1,
+        );
+        assert!(OpmDocument::from_string(&current_version).is_err());
+    }
+    #[test]
+    fn legacy_file_version() {
+        assert!(is_legacy_file_version("0"));
+        assert!(!is_legacy_file_version("1"));
+        assert!(!is_legacy_file_version("unknown"));
+    }
+    #[cfg(feature = "json_schema")]
+    #[test]
+    fn json_schema() {
+        let schema = OpmDocument::json_schema();
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        for key in [
+            "opm_file_version",
+            "scenery",
+            "global",
+            "analyzers",
+            "includes",
+        ] {
+            assert!(schema["properties"].get(key).is_some());
+        }
+        let defs = &schema["$defs"];
+        let node_types = defs["NodeAttr"]["properties"]["node_type"]["oneOf"]
+            .as_array()
+            .unwrap();
+        assert_eq!(node_types.len(), crate::nodes::node_types().len() + 1);
+        assert!(node_types.iter().any(|t| t["const"] == "lens"));
+        assert!(node_types.iter().any(|t| t["const"] == "reference"));
+        let prop_types = defs["Proptype"]["oneOf"].as_array().unwrap();
+        for variant in [
+            "String",
+            "Length",
+            "Aperture",
+            "LightDataBuilder",
+            "Isometry",
+        ] {
+            assert!(
+                prop_types
+                    .iter()
+                    .any(|p| p["properties"].get(variant).is_some())
+            );
+        }
+        assert!(defs.get("RefractiveIndexType").is_some());
+        assert!(defs.get("AnalyzerType").is_some());
+    }
+    #[test]
+    fn newer_file_version() {
+        let version = env!("OPM_FILE_VERSION").parse::<u32>().unwrap() + 1;
+        let result =
+            OpmDocument::from_string(&format!("(opm_file_version: \"{version}\", new_field: 1)"));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Please update OPOSSUM")
+        );
+    }
+    #[test]
     fn save_to_file() {
         let file = NamedTempFile::new().unwrap();
         let path = file.into_temp_path();
@@ -396,6 +1197,73 @@ mod test {
         assert!(document.analyzers.is_empty());
     }
     #[test]
+    fn analyze_wavelength_sweep() {
+        let mut scenery = NodeGroup::default();
+        let src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 1).unwrap())
+            .unwrap();
+        let det = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(src, "output_1", det, "input_1", millimeter!(50.0))
+            .unwrap();
+        let mut document = OpmDocument::new(scenery);
+        let sweep = WavelengthSweep::new(nanometer!(500.0), nanometer!(1000.0), 3, det).unwrap();
+        let info = AnalyzerInfo::new(
+            AnalyzerType::RayTrace(RayTraceConfig::default()),
+            Uuid::new_v4(),
+            Point2::new(0.0, 0.0),
+        )
+        .with_wavelength_sweep(sweep.clone());
+        assert_eq!(info.wavelength_sweep(), Some(&sweep));
+        let uuid = document.add_analyzer_info(&info);
+        let temp_model_file = NamedTempFile::new().unwrap();
+        document.save_to_file(temp_model_file.path()).unwrap();
+        let mut document = OpmDocument::from_file(temp_model_file.path()).unwrap();
+        assert_eq!(
+            document.analyzer(uuid).unwrap().wavelength_sweep(),
+            Some(&sweep)
+        );
+        assert_eq!(document.analyze().unwrap().len(), 1);
+        document.remove_analyzer(uuid).unwrap();
+        let info = AnalyzerInfo::new(
+            AnalyzerType::RayTrace(RayTraceConfig::default()),
+            Uuid::new_v4(),
+            Point2::new(0.0, 0.0),
+        )
+        .with_wavelength_sweep(
+            WavelengthSweep::new(nanometer!(500.0), nanometer!(1000.0), 3, Uuid::new_v4()).unwrap(),
+        );
+        document.add_analyzer_info(&info);
+        assert!(document.analyze().is_err());
+    }
+    #[test]
+    fn analyze_with_progress() {
+        let mut scenery = NodeGroup::default();
+        let src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 1).unwrap())
+            .unwrap();
+        let det = scenery.add_node(EnergyMeter::default()).unwrap();
+        scenery
+            .connect_nodes(src, "output_1", det, "input_1", millimeter!(50.0))
+            .unwrap();
+        let mut document = OpmDocument::new(scenery);
+        document.add_analyzer(AnalyzerType::RayTrace(RayTraceConfig::default()));
+        let progress = Rc::new(RefCell::new(Vec::new()));
+        let progress_clone = progress.clone();
+        document
+            .analyze_with_progress(move |p| progress_clone.borrow_mut().push(p.clone()))
+            .unwrap();
+        let progress = progress.borrow();
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].node, "collimated line ray source");
+        assert_eq!(progress[0].nr_of_rays, 0);
+        assert_eq!(progress[1].node, "energy meter");
+        assert_eq!(progress[1].node_index, 1);
+        assert_eq!(progress[1].nr_of_nodes, 2);
+        assert_eq!(progress[1].nr_of_rays, 7);
+        assert!(progress.iter().all(|p| p.bounce == 0));
+    }
+    #[test]
     fn all_nodes_integration_test() {
         let mut scenery = NodeGroup::default();
         let src = round_collimated_ray_source(millimeter!(10.0), joule!(1.0), 1).unwrap();
@@ -537,4 +1405,85 @@ mod test {
         let _ = doc.analyze().unwrap();
         check_logs(log::Level::Warn, vec![]);
     }
+    fn save_module(dir: &Path) -> Uuid {
+        let mut module = NodeGroup::default();
+        let d = module.add_node(Dummy::default()).unwrap();
+        module.map_input_port(d, "input_1", "input_1").unwrap();
+        module.map_output_port(d, "output_1", "output_1").unwrap();
+        OpmDocument::new(module)
+            .save_to_file(&dir.join("module.opm"))
+            .unwrap();
+        d
+    }
+    #[test]
+    fn include() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let module_dummy = save_module(dir.path());
+        let mut scenery = NodeGroup::default();
+        let d = scenery.add_node(Dummy::default()).unwrap();
+        let mut doc = OpmDocument::new(scenery);
+        let include1 = Include::new(Path::new("module.opm"));
+        let include2 = Include::new(Path::new("module.opm"));
+        let i1 = doc.add_include(include1.clone(), dir.path()).unwrap();
+        let i2 = doc.add_include(include2.clone(), dir.path()).unwrap();
+        assert!(doc.add_include(include1.clone(), dir.path()).is_err());
+        assert_eq!(doc.includes().len(), 2);
+        doc.scenery_mut()
+            .connect_nodes(d, "output_1", i1, "input_1", millimeter!(10.0))
+            .unwrap();
+        doc.scenery_mut()
+            .connect_nodes(i1, "output_1", i2, "input_1", millimeter!(20.0))
+            .unwrap();
+        let main_path = dir.path().join("main.opm");
+        doc.save_to_file(&main_path).unwrap();
+        // included nodes are not stored inline
+        let file_string = doc.to_opm_file_string().unwrap();
+        assert!(!file_string.contains(&include1.namespaced_id(module_dummy).to_string()));
+        let doc = OpmDocument::from_file(&main_path).unwrap();
+        assert_eq!(doc.includes().len(), 2);
+        assert_eq!(doc.scenery().nr_of_nodes(), 3);
+        assert_eq!(doc.scenery().connections().len(), 2);
+        for include in [&include1, &include2] {
+            let group = doc.scenery().node(include.id()).unwrap();
+            let mut group = group.optical_ref.lock().unwrap();
+            let group = group.as_group_mut().unwrap();
+            assert_eq!(group.nodes()[0].uuid(), include.namespaced_id(module_dummy));
+            assert!(
+                group
+                    .ports()
+                    .names(&PortType::Input)
+                    .contains(&"input_1".into())
+            );
+        }
+        // saving the loaded document again yields the same file
+        assert_eq!(doc.to_opm_file_string().unwrap(), file_string);
+    }
+    #[test]
+    fn include_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut doc = OpmDocument::default();
+        assert!(
+            doc.add_include(Include::new(Path::new("missing.opm")), dir.path())
+                .is_err()
+        );
+        assert!(doc.includes().is_empty());
+        let version = env!("OPM_FILE_VERSION");
+        let a = format!(
+            "(opm_file_version: \"{version}\", includes: [(path: \"b.opm\", id: \"{}\")])",
+            Uuid::new_v4()
+        );
+        let b = format!(
+            "(opm_file_version: \"{version}\", includes: [(path: \"a.opm\", id: \"{}\")])",
+            Uuid::new_v4()
+        );
+        fs::write(dir.path().join("a.opm"), a).unwrap();
+        fs::write(dir.path().join("b.opm"), b).unwrap();
+        let result = OpmDocument::from_file(&dir.path().join("a.opm"));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .starts_with("OpmDocument:cyclic include of file")
+        );
+    }
 }
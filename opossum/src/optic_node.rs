@@ -2,7 +2,7 @@
 //! Contains the basic trait representing an optical element
 use log::warn;
 use nalgebra::{Point2, Point3, Vector3};
-use uom::si::f64::{Angle, Length};
+use uom::si::f64::{Angle, Length, ThermodynamicTemperature};
 use uuid::Uuid;
 
 use crate::{
@@ -19,7 +19,10 @@ use crate::{
     rays::Rays,
     refractive_index::RefractiveIndexType,
     reporting::node_report::NodeReport,
-    surface::{Plane, geo_surface::GeoSurfaceRef, hit_map::HitMap, optic_surface::OpticSurface},
+    surface::{
+        Plane, geo_surface::GeoSurfaceRef, hit_map::HitMap, optic_surface::OpticSurface,
+        roughness::SurfaceRoughness,
+    },
     utils::geom_transformation::Isometry,
 };
 use std::collections::HashMap;
@@ -86,11 +89,24 @@ pub trait OpticNode: Dottable {
             optic_surf.set_backwards_rays_cache(Vec::<Rays>::new());
             optic_surf.set_forward_rays_cache(Vec::<Rays>::new());
             optic_surf.reset_hit_map();
+            optic_surf.reset_aperture_clipping();
         }
         for optic_surf in self.ports_mut().ports_mut(&PortType::Output).values_mut() {
             optic_surf.set_backwards_rays_cache(Vec::<Rays>::new());
             optic_surf.set_forward_rays_cache(Vec::<Rays>::new());
             optic_surf.reset_hit_map();
+            optic_surf.reset_aperture_clipping();
+        }
+    }
+    /// Store all following hit points of the [`OpticSurface`]s of this node at the given hit map level.
+    ///
+    /// See [`OpticSurface::set_hit_map_level`] for details.
+    fn set_hit_map_level(&mut self, level: Option<usize>) {
+        for optic_surf in self.ports_mut().ports_mut(&PortType::Input).values_mut() {
+            optic_surf.set_hit_map_level(level);
+        }
+        for optic_surf in self.ports_mut().ports_mut(&PortType::Output).values_mut() {
+            optic_surf.set_hit_map_level(level);
         }
     }
     /// Return the available (input & output) ports of this [`OpticNode`].
@@ -142,6 +158,24 @@ pub trait OpticNode: Dottable {
         self.node_attr_mut().set_ports(ports);
         Ok(())
     }
+    /// Set the surface micro-roughness for a given port name.
+    ///
+    /// By default, surfaces are ideally smooth (no scattering).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the port name does not exist.
+    fn set_roughness(
+        &mut self,
+        port_type: &PortType,
+        port_name: &str,
+        roughness: Option<&SurfaceRoughness>,
+    ) -> OpmResult<()> {
+        let mut ports = self.ports();
+        ports.set_roughness(port_type, port_name, roughness)?;
+        self.node_attr_mut().set_ports(ports);
+        Ok(())
+    }
     /// define the up-direction of this lightdata's first ray which is needed to create an isometry from this ray.
     /// This function should only be used during the node positioning process, and only for source nodes.
     ///
@@ -289,6 +323,26 @@ pub trait OpticNode: Dottable {
     fn inverted(&self) -> bool {
         self.node_attr().inverted()
     }
+    /// Enable or disable this [`OpticNode`].
+    ///
+    /// A disabled node stays part of the optical setup (including its position) but is bypassed during analysis.
+    /// Light arriving at its n-th input port is passed unchanged to its n-th output port. This is useful for
+    /// temporarily removing a component without rewiring the [`NodeGroup`].
+    fn set_enabled(&mut self, enabled: bool) {
+        self.node_attr_mut().set_enabled(enabled);
+    }
+    /// Returns `true` if the node is enabled (the default).
+    fn enabled(&self) -> bool {
+        self.node_attr().enabled()
+    }
+    /// Returns `true` if an output port of this node may be connected to more than one input port.
+    ///
+    /// The light of an output port is forwarded unchanged to all connected input ports. Since this would duplicate the
+    /// energy, none of the built-in node types allows a fan-out. Node types overriding this function must only emit
+    /// light data which can be safely shared between several input ports.
+    fn allows_output_fan_out(&self) -> bool {
+        false
+    }
     /// Return [`NodeReport`] of the current state of this [`OpticNode`].
     ///
     /// This function must be overridden for generating output in the analysis report. Mainly
@@ -311,6 +365,7 @@ pub trait OpticNode: Dottable {
         }
         node_attr_mut.set_name(&node_attributes.name());
         node_attr_mut.set_inverted(node_attributes.inverted());
+        node_attr_mut.set_enabled(node_attributes.enabled());
         if let Some((node_idx, distance)) = node_attributes.get_align_like_node_at_distance() {
             node_attr_mut.set_align_like_node_at_distance(*node_idx, *distance);
         }
@@ -403,7 +458,7 @@ pub trait OpticNode: Dottable {
     /// Get the ambient refractive index.
     ///
     /// This value is determined by the global configuration. A warning is issued and a default value is returned
-    /// if the global config could not be found.
+    /// if the global config could not be found. Temperature-dependent models are evaluated at the ambient temperature.
     fn ambient_idx(&self) -> RefractiveIndexType {
         self.global_conf().as_ref().map_or_else(
             || {
@@ -413,13 +468,21 @@ pub trait OpticNode: Dottable {
                 SceneryResources::default().ambient_refr_index
             },
             |conf| {
-                conf.lock()
-                    .expect("Mutex lock failed")
-                    .ambient_refr_index
-                    .clone()
+                let conf = conf.lock().expect("Mutex lock failed");
+                conf.ambient_refr_index
+                    .at_ambient_temperature(conf.ambient_temperature)
             },
         )
     }
+    /// Get the ambient temperature (if any).
+    ///
+    /// This value is determined by the global configuration. `None` is returned if the global config could not be
+    /// found or does not define an ambient temperature.
+    fn ambient_temperature(&self) -> Option<ThermodynamicTemperature> {
+        self.global_conf()
+            .as_ref()
+            .and_then(|conf| conf.lock().expect("Mutex lock failed").ambient_temperature)
+    }
 
     /// Returns a mutable reference to an [`OpticSurface`] of this [`OpticNode`] with the key `surf_name`
     /// # Attributes
@@ -572,4 +635,21 @@ mod tests {
         assert_abs_diff_eq!(iso.translation().y.value, decenter.y.value);
         assert_abs_diff_eq!(iso.translation().z.value, decenter.z.value);
     }
+    #[test]
+    fn enabled() {
+        let mut node = Dummy::default();
+        assert!(node.enabled());
+        let serialized = ron::to_string(node.node_attr()).unwrap();
+        assert!(!serialized.contains("enabled"));
+        let node_attr: NodeAttr = ron::from_str(&serialized).unwrap();
+        assert!(node_attr.enabled());
+        node.set_enabled(false);
+        assert!(!node.enabled());
+        let serialized = ron::to_string(node.node_attr()).unwrap();
+        let node_attr: NodeAttr = ron::from_str(&serialized).unwrap();
+        assert!(!node_attr.enabled());
+        let mut other_node = Dummy::default();
+        other_node.set_node_attr(node_attr);
+        assert!(!other_node.enabled());
+    }
 }
@@ -20,7 +20,7 @@ use crate::{
     aperture::Aperture,
     coatings::CoatingType,
     error::{OpmResult, OpossumError},
-    surface::optic_surface::OpticSurface,
+    surface::{optic_surface::OpticSurface, roughness::SurfaceRoughness},
 };
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Display};
@@ -34,6 +34,7 @@ pub enum PortType {
 }
 /// Structure defining the optical ports (input / output terminals) of an [`OpticNode`](crate::optic_node::OpticNode).
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct OpticPorts {
     inputs: BTreeMap<String, OpticSurface>,
     outputs: BTreeMap<String, OpticSurface>,
@@ -217,6 +218,39 @@ impl OpticPorts {
             },
         )
     }
+    /// Sets the surface micro-roughness of a port with the given name.
+    ///
+    /// The port must have already been created before. `None` disables surface scattering.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the port name does not exist.
+    pub fn set_roughness(
+        &mut self,
+        port_type: &PortType,
+        port_name: &str,
+        roughness: Option<&SurfaceRoughness>,
+    ) -> OpmResult<()> {
+        let (mut input_ports, mut output_ports) = (&mut self.inputs, &mut self.outputs);
+        if self.inverted {
+            (input_ports, output_ports) = (output_ports, input_ports);
+        }
+        let ports: &mut BTreeMap<String, OpticSurface> = match port_type {
+            PortType::Input => input_ports,
+            PortType::Output => output_ports,
+        };
+        ports.get_mut(port_name).map_or_else(
+            || {
+                Err(OpossumError::OpticPort(format!(
+                    "port <{port_name}> does not exist",
+                )))
+            },
+            |optic_surf| {
+                optic_surf.set_roughness(roughness.cloned());
+                Ok(())
+            },
+        )
+    }
     /// Sets the (input & ouput port) apertures of this [`OpticPorts`] from another [`OpticPorts`].
     ///
     /// This is a convenience function during deserialization of an optical element.
@@ -11,11 +11,18 @@ use uuid::Uuid;
 
 use crate::{
     analyzers::Analyzable,
-    nodes::{NodeAttr, OpticGraph, create_node_ref},
+    error::{OpmResult, OpossumError},
+    nodes::{LegacyNodeAttr, LegacyOpticGraph, NodeAttr, OpticGraph, create_node_ref},
     optic_scenery_rsc::SceneryResources,
+    utils::deserialize_some,
 };
 
 #[derive(Clone)]
+#[cfg_attr(
+    feature = "json_schema",
+    derive(schemars::JsonSchema),
+    schemars(with = "OpticRefLayout")
+)]
 /// Structure for storing an optical node.
 ///
 /// This structure stores a reference to an optical node (a structure implementing the
@@ -53,6 +60,46 @@ impl OpticRef {
             .node_attr()
             .uuid()
     }
+    /// Create an independent deep copy of the referenced node.
+    ///
+    /// For a [`NodeGroup`](crate::nodes::NodeGroup), all sub-nodes are copied recursively. The [`Uuid`] of each
+    /// copied node is replaced by `new_id(old_id)`. All references to copied nodes within the copy (e.g. port
+    /// mappings, connections or reference nodes) are updated accordingly, while references to nodes outside the copy
+    /// remain untouched. The copy has no global configuration assigned.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the node could not be copied.
+    pub fn deep_copy(&self, new_id: impl Fn(Uuid) -> Uuid) -> OpmResult<Self> {
+        let mut node_ids = Vec::new();
+        collect_node_ids(self, &mut node_ids)?;
+        let mut serialized = ron::to_string(self)
+            .map_err(|e| OpossumError::Other(format!("could not copy node: {e}")))?;
+        for id in node_ids {
+            serialized = serialized.replace(&id.to_string(), &new_id(id).to_string());
+        }
+        ron::from_str(&serialized)
+            .map_err(|e| OpossumError::Other(format!("could not copy node: {e}")))
+    }
+    /// Create the node of the given [`NodeAttr`] (and the given graph in case of a group node) as done during
+    /// deserialization.
+    fn from_parts(node_attributes: NodeAttr, node_graph: Option<OpticGraph>) -> OpmResult<Self> {
+        let node = create_node_ref(&node_attributes.node_type())?;
+        let mut optic_node = node
+            .optical_ref
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+        optic_node.set_node_attr(node_attributes);
+        if let Ok(group_node) = optic_node.as_group_mut() {
+            group_node.set_graph(node_graph.ok_or_else(|| {
+                OpossumError::OpticGroup("missing field `graph` of group node".into())
+            })?);
+        }
+        // group node: assign props to graph
+        optic_node.after_deserialization_hook()?;
+        drop(optic_node);
+        Ok(node)
+    }
     /// Update the reference to the global configuration.
     /// **Note**: This functions is normally only called from `OpticGraph`.
     ///
@@ -66,6 +113,35 @@ impl OpticRef {
             .set_global_conf(global_conf);
     }
 }
+/// Collect the [`Uuid`]s of the given node and (recursively) all of its sub-nodes.
+fn collect_node_ids(node_ref: &OpticRef, node_ids: &mut Vec<Uuid>) -> OpmResult<()> {
+    let mut node = node_ref
+        .optical_ref
+        .lock()
+        .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?;
+    node_ids.push(node.node_attr().uuid());
+    if let Ok(group) = node.as_group_mut() {
+        for sub_node in group.nodes() {
+            collect_node_ids(sub_node, node_ids)?;
+        }
+    }
+    Ok(())
+}
+/// Layout of an [`OpticRef`] in `.opm` files of version `0` (see [`LegacyNodeAttr`]).
+#[derive(Deserialize)]
+pub(crate) struct LegacyOpticRef {
+    attributes: LegacyNodeAttr,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    graph: Option<LegacyOpticGraph>,
+}
+impl TryFrom<LegacyOpticRef> for OpticRef {
+    type Error = OpossumError;
+
+    fn try_from(legacy: LegacyOpticRef) -> OpmResult<Self> {
+        let node_graph = legacy.graph.map(OpticGraph::try_from).transpose()?;
+        Self::from_parts(legacy.attributes.into(), node_graph)
+    }
+}
 impl Debug for OpticRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("OpticRef")
@@ -99,6 +175,16 @@ impl Serialize for OpticRef {
     }
 }
 
+/// Serialized layout of an [`OpticRef`] as written by its [`Serialize`] implementation.
+#[cfg(feature = "json_schema")]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "OpticRef")]
+#[allow(dead_code)]
+struct OpticRefLayout {
+    attributes: NodeAttr,
+    /// the sub graph (only present for group nodes)
+    graph: Option<OpticGraph>,
+}
 impl<'de> Deserialize<'de> for OpticRef {
     #[allow(clippy::too_many_lines)]
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
@@ -177,29 +263,8 @@ impl<'de> Deserialize<'de> for OpticRef {
 
                 let node_attributes =
                     node_attributes.ok_or_else(|| de::Error::missing_field("attributes"))?;
-                let node_type = &node_attributes.node_type();
-                let node =
-                    create_node_ref(node_type).map_err(|e| de::Error::custom(e.to_string()))?;
-                node.optical_ref
-                    .lock()
-                    .expect("Mutex lock failed")
-                    .set_node_attr(node_attributes);
-                if let Ok(group_node) = node
-                    .optical_ref
-                    .lock()
-                    .expect("Mutex lock failed")
-                    .as_group_mut()
-                {
-                    group_node
-                        .set_graph(node_graph.ok_or_else(|| de::Error::missing_field("graph"))?);
-                }
-                // group node: assign props to graph
-                node.optical_ref
-                    .lock()
-                    .expect("Mutex lock failed")
-                    .after_deserialization_hook()
-                    .map_err(|e| de::Error::custom(e.to_string()))?;
-                Ok(node)
+                OpticRef::from_parts(node_attributes, node_graph)
+                    .map_err(|e| de::Error::custom(e.to_string()))
             }
         }
         deserializer.deserialize_struct("OpticRef", FIELDS, OpticRefVisitor)
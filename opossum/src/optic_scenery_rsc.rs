@@ -1,20 +1,49 @@
 #![warn(missing_docs)]
 use crate::refractive_index::{RefractiveIndexType, refr_index_vaccuum};
 use serde::{Deserialize, Serialize};
+use uom::si::f64::{Length, ThermodynamicTemperature};
 use utoipa::ToSchema;
 
 /// Structure handling scenery wide resources (e.g. ambient medium)
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct SceneryResources {
     /// Refractive index of the ambient medium
     #[schema(value_type=())]
     pub ambient_refr_index: RefractiveIndexType,
+    /// Default wavelength of the model
+    ///
+    /// This wavelength is used for aligning the nodes if neither the analyzer nor the source defines an alignment wavelength.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type=())]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
+    pub default_wavelength: Option<Length>,
+    /// Ambient temperature of the model
+    ///
+    /// This temperature is used for evaluating temperature-dependent refractive index models
+    /// (see [`RefrIndexTempCoeff`](crate::refractive_index::RefrIndexTempCoeff)) without an explicitly set operating temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type=())]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
+    pub ambient_temperature: Option<ThermodynamicTemperature>,
+    /// Resolution (number of grid points in x and y) of the fluence maps
+    ///
+    /// This grid size is used by all [`FluenceDetector`](crate::nodes::FluenceDetector)s of the model. If not set,
+    /// [`DEFAULT_FLUENCE_GRID`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type=())]
+    pub fluence_grid: Option<(usize, usize)>,
 }
+/// Default resolution (number of grid points in x and y) of fluence maps
+pub const DEFAULT_FLUENCE_GRID: (usize, usize) = (100, 83);
 
 impl Default for SceneryResources {
     fn default() -> Self {
         Self {
             ambient_refr_index: refr_index_vaccuum(),
+            default_wavelength: None,
+            ambient_temperature: None,
+            fluence_grid: None,
         }
     }
 }
@@ -37,6 +37,12 @@ impl TryFrom<Option<(f64, f64)>> for AxLims {
 }
 
 impl AxLims {
+    /// Default minimum range of automatically determined axis limits relative to the largest absolute limit.
+    ///
+    /// This value is close to the machine precision such that only degenerate ranges (e.g. of constant data) or ranges
+    /// caused by numerical noise are widened. A larger minimum range can be chosen using
+    /// [`PlotArgs::MinRelativeAxisRange`](crate::plottable::PlotArgs::MinRelativeAxisRange).
+    pub const DEFAULT_MIN_RELATIVE_RANGE: f64 = 1.0e-9;
     ///Creates a new [`AxLims`] struct
     /// # Attributes
     /// -`min`: minimum value of the ax limit
@@ -108,7 +114,47 @@ impl AxLims {
         }
     }
 
+    /// Expands the ax limits of a logarithmic axis by the provided expansion factor
+    ///
+    /// In contrast to [`expand_lim_range_by_factor`](Self::expand_lim_range_by_factor), the range is expanded symmetrically in
+    /// log space (i.e. in decades), such that the minimum stays positive.
+    /// # Attributes
+    /// - `expansion_factor`: factor to expand the (logarithmic) range of the ax limits
+    pub fn expand_log_lim_range_by_factor(&mut self, expansion_factor: f64) {
+        if !(expansion_factor.is_normal() && expansion_factor.is_sign_positive()) {
+            warn!("Cannot expand ax limits! Expansion factor must be normal and positive!");
+            return;
+        }
+        if self.min <= 0. || self.max <= 0. {
+            warn!("Cannot expand logarithmic ax limits! Limits must be positive!");
+            return;
+        }
+        let (log_min, log_max) = (self.min.log10(), self.max.log10());
+        let range = log_max - log_min;
+        self.max = 10f64.powf(log_max + range * (expansion_factor - 1.) / 2.);
+        self.min = 10f64.powf(log_min - range * (expansion_factor - 1.) / 2.);
+    }
+    /// This function creates an [`AxLims`] struct for a logarithmic axis from the provided `min` and `max` values
+    ///
+    /// The limits are determined like in [`create_useful_axlims`](Self::create_useful_axlims) but in log space. Hence, (nearly)
+    /// constant data is padded in decades instead of absolute values.
+    /// # Attributes
+    /// - `min`: minimum value for the ax limit
+    /// - `max`: maximum value for the ax limit
+    /// # Returns
+    /// Some([`AxLims`]) with positive limits or None if any of the values is not positive or not finite.
+    #[must_use]
+    pub fn create_useful_log_axlims(min_in: f64, max_in: f64) -> Option<Self> {
+        if !(min_in.is_finite() && max_in.is_finite() && min_in > 0. && max_in > 0.) {
+            return None;
+        }
+        let log_lims = Self::create_useful_axlims(min_in.log10(), max_in.log10())?;
+        Self::new(10f64.powf(log_lims.min), 10f64.powf(log_lims.max))
+    }
     /// This function creates an [`AxLims`] struct from the provided `min` and `max` values
+    ///
+    /// This is a shortcut for [`create_useful_axlims_with_min_range`](Self::create_useful_axlims_with_min_range) using
+    /// [`DEFAULT_MIN_RELATIVE_RANGE`](Self::DEFAULT_MIN_RELATIVE_RANGE).
     /// # Attributes
     /// - `min`: minimum value for the ax limit
     /// - `max`: maximum value for the ax limit
@@ -117,6 +163,29 @@ impl AxLims {
     /// If these criteria are not fulfilled, the values are changed accordingly to provide valid axlims. If for some reason, these values are still no okay, teh function returns None
     #[must_use]
     pub fn create_useful_axlims(min_in: f64, max_in: f64) -> Option<Self> {
+        Self::create_useful_axlims_with_min_range(min_in, max_in, Self::DEFAULT_MIN_RELATIVE_RANGE)
+    }
+    /// This function creates an [`AxLims`] struct from the provided `min` and `max` values with a guaranteed minimum range.
+    ///
+    /// In addition to the corrections done by [`create_useful_axlims`](Self::create_useful_axlims), the limits are symmetrically
+    /// padded around their center if their range is smaller than `min_rel_range` times the largest absolute limit value. This avoids
+    /// (almost) degenerate plots of (nearly) constant data.
+    /// # Attributes
+    /// - `min`: minimum value for the ax limit
+    /// - `max`: maximum value for the ax limit
+    /// - `min_rel_range`: minimum range relative to the largest absolute limit value. Must be positive and finite.
+    /// # Returns
+    /// Some([`AxLims`]) with a non-degenerate range or None if `min_rel_range` is invalid or no valid limits could be found.
+    #[must_use]
+    pub fn create_useful_axlims_with_min_range(
+        min_in: f64,
+        max_in: f64,
+        min_rel_range: f64,
+    ) -> Option<Self> {
+        if !min_rel_range.is_finite() || min_rel_range.is_sign_negative() {
+            warn!("Minimum relative axis range must be positive and finite!");
+            return None;
+        }
         if !min_in.is_finite() && !max_in.is_finite() {
             return Self::new(-0.5, 0.5);
         }
@@ -145,6 +214,14 @@ impl AxLims {
             max = 0.5;
             min = -0.5;
         }
+
+        //pad nearly constant data such that the axis covers at least the minimum range
+        let min_range = max.abs().max(min.abs()) * min_rel_range;
+        if max - min < min_range {
+            let center = min.midpoint(max);
+            min = center - min_range / 2.;
+            max = center + min_range / 2.;
+        }
         Self::new(min, max)
     }
 
@@ -293,6 +370,38 @@ mod tests {
         );
     }
     #[test]
+    fn axlim_expand_log() {
+        let mut axlim = AxLims::new(1., 100.).unwrap();
+        axlim.expand_log_lim_range_by_factor(2.);
+        assert_relative_eq!(axlim.min, 0.1, max_relative = 1e-12);
+        assert_relative_eq!(axlim.max, 1000., max_relative = 1e-12);
+
+        testing_logger::setup();
+        let mut axlim = AxLims::new(-1., 100.).unwrap();
+        axlim.expand_log_lim_range_by_factor(2.);
+        assert_relative_eq!(axlim.min, -1.);
+        assert_relative_eq!(axlim.max, 100.);
+        check_logs(
+            log::Level::Warn,
+            vec!["Cannot expand logarithmic ax limits! Limits must be positive!"],
+        );
+    }
+    #[test]
+    fn create_useful_log_axlims_test() {
+        let axlim = AxLims::create_useful_log_axlims(1e-3, 10.).unwrap();
+        assert_relative_eq!(axlim.min, 1e-3, max_relative = 1e-12);
+        assert_relative_eq!(axlim.max, 10., max_relative = 1e-12);
+        let axlim = AxLims::create_useful_log_axlims(10., 1e-3).unwrap();
+        assert_relative_eq!(axlim.min, 1e-3, max_relative = 1e-12);
+        assert_relative_eq!(axlim.max, 10., max_relative = 1e-12);
+        let axlim = AxLims::create_useful_log_axlims(100., 100.).unwrap();
+        assert_relative_eq!(axlim.min, 10., max_relative = 1e-12);
+        assert_relative_eq!(axlim.max, 1000., max_relative = 1e-12);
+        assert!(AxLims::create_useful_log_axlims(0., 10.).is_none());
+        assert!(AxLims::create_useful_log_axlims(-1., 10.).is_none());
+        assert!(AxLims::create_useful_log_axlims(1., f64::INFINITY).is_none());
+    }
+    #[test]
     fn create_useful_axlims_test() {
         let axlim = AxLims::create_useful_axlims(0., 10.).unwrap();
         assert_relative_eq!(axlim.min, 0.);
@@ -330,4 +439,36 @@ mod tests {
         assert_relative_eq!(axlim.min, 5.);
         assert_relative_eq!(axlim.max, 15.);
     }
+    #[test]
+    fn create_useful_axlims_with_min_range_test() {
+        assert!(AxLims::create_useful_axlims_with_min_range(0., 1., -1.).is_none());
+        assert!(AxLims::create_useful_axlims_with_min_range(0., 1., f64::NAN).is_none());
+        assert!(AxLims::create_useful_axlims_with_min_range(0., 1., f64::INFINITY).is_none());
+
+        let axlim = AxLims::create_useful_axlims_with_min_range(0., 10., 0.5).unwrap();
+        assert_relative_eq!(axlim.min, 0.);
+        assert_relative_eq!(axlim.max, 10.);
+
+        let axlim = AxLims::create_useful_axlims_with_min_range(9., 10., 0.5).unwrap();
+        assert_relative_eq!(axlim.min, 7.0);
+        assert_relative_eq!(axlim.max, 12.0);
+    }
+    #[test]
+    fn create_useful_axlims_nearly_constant() {
+        let value = 1.0;
+        let axlim = AxLims::create_useful_axlims(value, value + 1.0e-14).unwrap();
+        assert!(axlim.check_validity());
+        assert!(axlim.min <= value);
+        assert!(axlim.max >= value + 1.0e-14);
+        assert_relative_eq!(
+            axlim.max - axlim.min,
+            AxLims::DEFAULT_MIN_RELATIVE_RANGE,
+            max_relative = 1.0e-3
+        );
+        for value in [-1.0e6, -1.0, 0.0, 1.0e-12, 3.7, 1.0e9] {
+            let axlim = AxLims::create_useful_axlims(value, value).unwrap();
+            assert!(axlim.check_validity());
+            assert!(axlim.min <= value && axlim.max >= value);
+        }
+    }
 }
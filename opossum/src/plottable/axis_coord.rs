@@ -0,0 +1,130 @@
+//! This module contains the [`AxisCoord`] enum, a plot axis coordinate which is either linear or logarithmic.
+use std::ops::Range;
+
+use log::warn;
+use plotters::coord::{
+    combinators::{IntoLogRange, LogCoord},
+    ranged1d::{DefaultFormatting, KeyPointHint, Ranged},
+    types::RangedCoordf64,
+};
+
+use super::AxLims;
+
+/// One-dimensional coordinate of a plot axis
+///
+/// This enum allows to use the same chart type for linear as well as logarithmic axes.
+pub enum AxisCoord {
+    /// linearly scaled axis
+    Linear(RangedCoordf64),
+    /// logarithmically scaled axis
+    Log(LogCoord<f64>),
+}
+
+impl AxisCoord {
+    /// Creates a new [`AxisCoord`] from the given [`AxLims`]
+    ///
+    /// If `log_scale` is requested but the ax limits are not strictly positive, a warning is emitted and a linear axis
+    /// is used instead.
+    /// # Attributes
+    /// - `lims`: ax limits of the axis
+    /// - `log_scale`: true for a logarithmic axis, false for a linear axis
+    #[must_use]
+    pub fn new(lims: AxLims, log_scale: bool) -> Self {
+        if log_scale {
+            if lims.min > 0. && lims.max > 0. {
+                return Self::Log((lims.min..lims.max).log_scale().into());
+            }
+            warn!("Logarithmic axis requires positive ax limits! Use linear axis instead");
+        }
+        Self::Linear((lims.min..lims.max).into())
+    }
+    /// Returns true if this [`AxisCoord`] is logarithmically scaled.
+    #[must_use]
+    pub const fn is_log(&self) -> bool {
+        matches!(self, Self::Log(_))
+    }
+}
+
+impl Ranged for AxisCoord {
+    type FormatOption = DefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        match self {
+            Self::Linear(coord) => coord.map(value, limit),
+            Self::Log(coord) => {
+                // non-positive values have no position on a logarithmic axis: pin them to the lower axis limit
+                if *value > 0. {
+                    coord.map(value, limit)
+                } else {
+                    coord.map(&coord.range().start, limit)
+                }
+            }
+        }
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        match self {
+            Self::Linear(coord) => coord.key_points(hint),
+            Self::Log(coord) => {
+                let max_points = hint.max_num_points();
+                let points = coord.key_points(hint);
+                // ranges spanning less than a decade might not contain any power of ten
+                if points.len() < 2 {
+                    RangedCoordf64::from(coord.range()).key_points(max_points)
+                } else {
+                    points
+                }
+            }
+        }
+    }
+
+    fn range(&self) -> Range<f64> {
+        match self {
+            Self::Linear(coord) => coord.range(),
+            Self::Log(coord) => coord.range(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_helper::test_helper::check_logs;
+    #[test]
+    fn new() {
+        let lims = AxLims::new(1e-3, 10.).unwrap();
+        assert!(!AxisCoord::new(lims, false).is_log());
+        assert!(AxisCoord::new(lims, true).is_log());
+        testing_logger::setup();
+        let coord = AxisCoord::new(AxLims::new(-1., 10.).unwrap(), true);
+        assert!(!coord.is_log());
+        check_logs(
+            log::Level::Warn,
+            vec!["Logarithmic axis requires positive ax limits! Use linear axis instead"],
+        );
+    }
+    #[test]
+    fn map() {
+        let lims = AxLims::new(1., 100.).unwrap();
+        let linear = AxisCoord::new(lims, false);
+        assert_eq!(linear.map(&1., (0, 100)), 0);
+        assert_eq!(linear.map(&100., (0, 100)), 100);
+        let log = AxisCoord::new(lims, true);
+        assert_eq!(log.map(&1., (0, 100)), 0);
+        assert_eq!(log.map(&10., (0, 100)), 50);
+        assert_eq!(log.map(&100., (0, 100)), 100);
+        assert_eq!(log.map(&0., (0, 100)), 0);
+        assert_eq!(log.map(&-10., (0, 100)), 0);
+    }
+    #[test]
+    fn key_points() {
+        let log = AxisCoord::new(AxLims::new(1e-3, 10.).unwrap(), true);
+        let points = log.key_points(5);
+        assert!(!points.is_empty());
+        assert!(points.iter().all(|p| *p > 0.));
+        let log = AxisCoord::new(AxLims::new(2., 8.).unwrap(), true);
+        assert!(log.key_points(5).len() >= 2);
+        assert_eq!(log.range(), 2.0..8.0);
+    }
+}
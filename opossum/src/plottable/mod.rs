@@ -3,8 +3,11 @@
 
 pub mod ax_lims;
 pub use ax_lims::AxLims;
+mod axis_coord;
+use axis_coord::AxisCoord;
 
 use crate::error::{OpmResult, OpossumError};
+use crate::utils::contour::{auto_contour_levels, contour_lines};
 use crate::utils::griddata::create_valued_voronoi_cells;
 use crate::utils::{filter_data::get_min_max_filter_nonfinite, griddata::linspace};
 use approx::relative_ne;
@@ -22,10 +25,13 @@ use plotters::{
     backend::PixelFormat,
     chart::{ChartBuilder, ChartContext, LabelAreaPosition, MeshStyle, SeriesLabelPosition},
     coord::{Shift, cartesian::Cartesian2d, ranged3d::Cartesian3d, types::RangedCoordf64},
-    element::{Circle, PathElement, Polygon, Rectangle},
+    element::{Circle, PathElement, Polygon, Rectangle, Text},
     prelude::{BitMapBackend, DrawingArea, IntoDrawingArea, SVGBackend},
     series::LineSeries,
-    style::{BLACK, Color, IntoFont, RGBAColor, ShapeStyle, WHITE},
+    style::{
+        BLACK, Color, IntoFont, RGBAColor, ShapeStyle, WHITE,
+        text_anchor::{HPos, Pos, VPos},
+    },
 };
 use std::{collections::HashMap, env::current_dir, f64::consts::PI, path::Path, path::PathBuf};
 use strum::IntoEnumIterator;
@@ -50,6 +56,8 @@ pub enum PlotType {
     MultiLine3D(PlotParameters),
     ///2D color plot of gridded data with color representing the amplitude over an x-y grid
     ColorMesh(PlotParameters),
+    ///2D contour plot of gridded data with isolines at given amplitude levels over an x-y grid
+    Contour(PlotParameters),
     /// 3D surface plot of ungridded data
     TriangulatedSurface(PlotParameters),
 }
@@ -57,6 +65,7 @@ impl PlotType {
     const fn get_plot_params(&self) -> &PlotParameters {
         match self {
             Self::ColorMesh(p)
+            | Self::Contour(p)
             | Self::Scatter2D(p)
             | Self::Line2D(p)
             | Self::Histogram2D(p)
@@ -68,6 +77,7 @@ impl PlotType {
     const fn get_plot_params_mut(&mut self) -> &mut PlotParameters {
         match self {
             Self::ColorMesh(p)
+            | Self::Contour(p)
             | Self::Scatter2D(p)
             | Self::Line2D(p)
             | Self::Histogram2D(p)
@@ -81,6 +91,7 @@ impl PlotType {
         let _ = backend.fill(&WHITE);
         match self {
             Self::ColorMesh(_) => Self::plot_color_mesh(plot, backend),
+            Self::Contour(_) => Self::plot_contour(plot, backend),
             Self::TriangulatedSurface(_) => Self::plot_triangulated_surface(plot, backend),
             Self::Scatter2D(_) => Self::plot_2d_scatter(plot, backend),
             Self::Line2D(_) => Self::plot_2d_line(plot, backend),
@@ -115,6 +126,7 @@ impl PlotType {
     /// - the plotting backend can not be extracted
     /// - the plot can not be created inside the `create_plot()` method
     /// - the image buffer is too small
+    /// - the plot data should be exported but the `.csv` file(s) could not be written
     pub fn plot(&self, plt_series: &Vec<PlotSeries>) -> OpmResult<Option<RgbImage>> {
         let params = self.get_plot_params();
         params.check_backend_file_ext_compatibility()?;
@@ -125,7 +137,11 @@ impl PlotType {
         }
         plot.add_margin_to_figure_size(self);
 
-        match params.get_backend()? {
+        let backend = params.get_backend()?;
+        if backend != PltBackEnd::Buf && params.get_export_data_flag()? {
+            Self::export_plot_data(plt_series, &path)?;
+        }
+        match backend {
             PltBackEnd::Bitmap => {
                 let backend = BitMapBackend::new(&path, plot.fig_size).into_drawing_area();
                 self.create_plot(&backend, &mut plot);
@@ -154,8 +170,30 @@ impl PlotType {
         }
     }
 
+    /// Writes the data of the given [`PlotSeries`] next to the image file at `img_path`.
+    ///
+    /// The data of a single series is written to a `.csv` file with the same stem as the image. For multiple series, the
+    /// series index is appended to the stem (e.g. `plot_0.csv`, `plot_1.csv`). See [`PlotData::to_csv`] for the layout.
+    fn export_plot_data(plt_series: &[PlotSeries], img_path: &Path) -> OpmResult<()> {
+        if plt_series.len() == 1 {
+            return plt_series[0]
+                .get_plot_series_data()
+                .to_csv(&img_path.with_extension("csv"));
+        }
+        let stem = img_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        for (i, series) in plt_series.iter().enumerate() {
+            series
+                .get_plot_series_data()
+                .to_csv(&img_path.with_file_name(format!("{stem}_{i}.csv")))?;
+        }
+        Ok(())
+    }
+
     fn draw_line_2d<'a, 'b, T: DrawingBackend + 'a + 'b>(
-        chart: &'a mut ChartContext<'b, T, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+        chart: &'a mut ChartContext<'b, T, Cartesian2d<AxisCoord, AxisCoord>>,
         x: &DVectorView<'_, f64>,
         y: &DVectorView<'_, f64>,
         line_color: RGBAColor,
@@ -177,7 +215,7 @@ impl PlotType {
     }
 
     fn draw_histogram_2d<'a, 'b, T: DrawingBackend + 'a + 'b>(
-        chart: &'a mut ChartContext<'b, T, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+        chart: &'a mut ChartContext<'b, T, Cartesian2d<AxisCoord, AxisCoord>>,
         x: &DVectorView<'_, f64>,
         y: &DVectorView<'_, f64>,
         line_color: RGBAColor,
@@ -240,7 +278,7 @@ impl PlotType {
     }
 
     fn draw_points<'a, 'b, T: DrawingBackend + 'a + 'b>(
-        chart: &'a mut ChartContext<'b, T, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+        chart: &'a mut ChartContext<'b, T, Cartesian2d<AxisCoord, AxisCoord>>,
         x: &DVectorView<'_, f64>,
         y: &DVectorView<'_, f64>,
         marker_color: RGBAColor,
@@ -347,7 +385,7 @@ impl PlotType {
     }
 
     fn draw_2d_colormesh<T: DrawingBackend>(
-        chart: &mut ChartContext<'_, T, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+        chart: &mut ChartContext<'_, T, Cartesian2d<AxisCoord, AxisCoord>>,
         x_ax: &MatrixXx1<f64>,
         y_ax: &MatrixXx1<f64>,
         z_dat: &DMatrix<f64>,
@@ -398,7 +436,7 @@ impl PlotType {
     }
 
     fn config_series_label_2d<'a, 'b, T: DrawingBackend + 'a + 'b>(
-        chart: &'a mut ChartContext<'b, T, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+        chart: &'a mut ChartContext<'b, T, Cartesian2d<AxisCoord, AxisCoord>>,
     ) {
         chart
             .configure_series_labels()
@@ -436,8 +474,9 @@ impl PlotType {
                 plt.bounds.x.unwrap(),
                 plt.bounds.y.unwrap(),
                 &plt.label,
-                true,
-                true,
+                [true, true],
+                plt.log_scale,
+                &plt.title,
             );
 
             let mut label_flag = false;
@@ -473,8 +512,9 @@ impl PlotType {
                 plt.bounds.x.unwrap(),
                 plt.bounds.y.unwrap(),
                 &plt.label,
-                true,
-                true,
+                [true, true],
+                plt.log_scale,
+                &plt.title,
             );
 
             let mut label_flag = false;
@@ -518,8 +558,9 @@ impl PlotType {
                         LabelDescription::new("", plt.label[0].label_pos),
                         plt.cbar.label.clone(),
                     ],
-                    true,
-                    false,
+                    [false, true],
+                    [false, false],
+                    "",
                 );
 
                 let c_dat = linspace(
@@ -552,8 +593,9 @@ impl PlotType {
                 plt.bounds.x.unwrap(),
                 plt.bounds.y.unwrap(),
                 &plt.label,
-                true,
-                true,
+                [true, true],
+                plt.log_scale,
+                &plt.title,
             );
 
             let mut label_flag = false;
@@ -592,8 +634,9 @@ impl PlotType {
                 plt.bounds.x.unwrap(),
                 plt.bounds.y.unwrap(),
                 &plt.label,
-                true,
-                true,
+                [true, true],
+                plt.log_scale,
+                &plt.title,
             );
             for plt_series in plt_series_vec {
                 if let PlotData::MultiDim2 { vec_of_xy_data } = plt_series.get_plot_series_data() {
@@ -732,8 +775,9 @@ impl PlotType {
                         LabelDescription::new("", plt.label[0].label_pos),
                         plt.cbar.label.clone(),
                     ],
-                    true,
-                    false,
+                    [false, true],
+                    [false, false],
+                    "",
                 );
 
                 let c_dat =
@@ -771,8 +815,9 @@ impl PlotType {
                     x_bounds.unwrap(),
                     y_bounds.unwrap(),
                     &plt.label,
-                    true,
-                    true,
+                    [true, true],
+                    [false, false],
+                    &plt.title,
                 );
 
                 Self::draw_2d_colormesh(
@@ -794,6 +839,97 @@ impl PlotType {
         root.present().unwrap();
     }
 
+    fn plot_contour<B: DrawingBackend>(plt: &Plot, root: &DrawingArea<B, Shift>) {
+        if let Some(plt_series_vec) = plt.get_plot_series_vec() {
+            if plt_series_vec.len() > 1 {
+                warn!(
+                    "For this type of plot only one series can be plotted at a time. Only the first series will be used!"
+                );
+            }
+            if let PlotData::ColorMesh {
+                x_dat_n,
+                y_dat_m,
+                z_dat_nxm,
+            } = plt_series_vec[0].get_plot_series_data()
+            {
+                let mut chart = Self::create_2d_plot_chart(
+                    root,
+                    plt.bounds.x.unwrap(),
+                    plt.bounds.y.unwrap(),
+                    &plt.label,
+                    [true, true],
+                    [false, false],
+                    &plt.title,
+                );
+                let Some((z_min, z_max)) = get_min_max_filter_nonfinite(z_dat_nxm.as_slice())
+                else {
+                    warn!("No finite z data! Cannot draw contour lines!");
+                    root.present().unwrap();
+                    return;
+                };
+                let levels = if plt.contour_levels.is_empty() {
+                    auto_contour_levels(z_min, z_max, Plot::DEFAULT_NR_OF_CONTOUR_LEVELS)
+                } else {
+                    plt.contour_levels.clone()
+                };
+                let mut label_flag = false;
+                for level in levels
+                    .into_iter()
+                    .filter(|level| (z_min..=z_max).contains(level))
+                {
+                    let Ok(lines) = contour_lines(x_dat_n, y_dat_m, z_dat_nxm, level) else {
+                        continue;
+                    };
+                    let c = plt
+                        .cbar
+                        .cmap
+                        .eval_continuous((level - z_min) / (z_max - z_min));
+                    // round to four significant digits for the legend
+                    let rounded_level = format!("{level:.3e}").parse::<f64>().unwrap_or(level);
+                    for (i, line) in lines.iter().enumerate() {
+                        let label = (i == 0).then(|| format!("{rounded_level}"));
+                        label_flag |= label.is_some();
+                        Self::draw_line_2d(
+                            &mut chart,
+                            &line.column(0),
+                            &line.column(1),
+                            RGBAColor(c.r, c.g, c.b, 1.),
+                            label,
+                        );
+                    }
+                }
+                if label_flag {
+                    Self::config_series_label_2d(&mut chart);
+                }
+            } else {
+                warn!(
+                    "Wrong PlotData stored for this plot type! Must use ColorMesh! Not all series will be plotted!"
+                );
+            }
+        } else {
+            warn!("No plot series defined! Cannot create plot!");
+        }
+        root.present().unwrap();
+    }
+
+    /// Draws the title centered into the top margin of the drawing area. Nothing is drawn for an empty title.
+    fn draw_title<T: DrawingBackend>(root: &DrawingArea<T, Shift>, title: &str) {
+        if title.is_empty() {
+            return;
+        }
+        let (width, _) = root.dim_in_pixel();
+        let style = ("sans-serif", 30)
+            .into_font()
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Center, VPos::Center));
+        root.draw(&Text::new(
+            title.to_owned(),
+            (width.to_i32().unwrap_or_default() / 2, 20),
+            style,
+        ))
+        .unwrap();
+    }
+
     fn create_3d_plot_chart<'a, T: DrawingBackend>(
         root: &'a DrawingArea<T, Shift>,
         plot: &Plot,
@@ -803,6 +939,7 @@ impl PlotType {
         let y_bounds = plot.bounds.y.unwrap();
         let z_bounds = plot.bounds.z.unwrap();
 
+        Self::draw_title(root, &plot.title);
         let mut chart = ChartBuilder::on(root)
             .margin(20)
             .set_all_label_area_size(100)
@@ -829,7 +966,8 @@ impl PlotType {
         chart
     }
 
-    fn tick_formatter(range: core::ops::Range<f64>) -> impl Fn(&f64) -> String {
+    fn tick_formatter(range: core::ops::Range<f64>, log_scale: bool) -> impl Fn(&f64) -> String {
+        // an all-zero range has no magnitude: fall back to the default formatting
         let log_val = range
             .end
             .abs()
@@ -837,14 +975,22 @@ impl PlotType {
             .log10()
             .floor()
             .to_i32()
-            .unwrap();
-
-        move |v: &_| match log_val {
-            -3 | -2 => format!("{v:.3}"),
-            -1 | 0 => format!("{v:.2}"),
-            1 => format!("{v:.1}"),
-            2 => format!("{v:.0}"),
-            _ => format!("{v}"),
+            .unwrap_or(i32::MAX);
+
+        move |v: &_| {
+            if log_scale {
+                // round to three significant digits to avoid floating point artifacts of the logarithmic key points
+                return format!("{v:.2e}")
+                    .parse::<f64>()
+                    .map_or_else(|_| format!("{v}"), |rounded| format!("{rounded:e}"));
+            }
+            match log_val {
+                -3 | -2 => format!("{v:.3}"),
+                -1 | 0 => format!("{v:.2}"),
+                1 => format!("{v:.1}"),
+                2 => format!("{v:.0}"),
+                _ => format!("{v}"),
+            }
         }
     }
 
@@ -853,13 +999,15 @@ impl PlotType {
         x_bounds: AxLims,
         y_bounds: AxLims,
         label_desc: &[LabelDescription; 2],
-        y_ax: bool,
-        x_ax: bool,
-    ) -> ChartContext<'a, T, Cartesian2d<RangedCoordf64, RangedCoordf64>> {
+        ax: [bool; 2],
+        log_scale: [bool; 2],
+        title: &str,
+    ) -> ChartContext<'a, T, Cartesian2d<AxisCoord, AxisCoord>> {
+        Self::draw_title(root, title);
         let mut chart_builder = ChartBuilder::on(root);
         chart_builder.margin(30).margin_top(40).margin_left(10);
 
-        if y_ax {
+        if ax[1] {
             let pixel_margin = Self::calc_pixel_margin(y_bounds);
 
             chart_builder.set_label_area_size(label_desc[1].label_pos.into(), 21 + pixel_margin);
@@ -873,18 +1021,23 @@ impl PlotType {
         chart_builder.set_label_area_size(label_desc[0].label_pos.into(), 65);
 
         let mut chart = chart_builder
-            .build_cartesian_2d(x_bounds.min..x_bounds.max, y_bounds.min..y_bounds.max)
+            .build_cartesian_2d(
+                AxisCoord::new(x_bounds, log_scale[0]),
+                AxisCoord::new(y_bounds, log_scale[1]),
+            )
             .unwrap();
 
-        let x_format = Self::tick_formatter(chart.x_range());
-        let y_format = Self::tick_formatter(chart.y_range());
+        let x_format =
+            Self::tick_formatter(chart.x_range(), chart.as_coord_spec().x_spec().is_log());
+        let y_format =
+            Self::tick_formatter(chart.y_range(), chart.as_coord_spec().y_spec().is_log());
         let mut mesh = chart.configure_mesh();
         mesh.x_labels(5).y_labels(5);
 
         mesh.x_label_formatter(&x_format)
             .y_label_formatter(&y_format);
 
-        Self::set_or_disable_axis_desc([x_ax, y_ax], label_desc, &mut mesh);
+        Self::set_or_disable_axis_desc(ax, label_desc, &mut mesh);
 
         mesh.label_style(("sans-serif", 30).into_font())
             .draw()
@@ -896,7 +1049,7 @@ impl PlotType {
     fn set_or_disable_axis_desc<T: DrawingBackend>(
         ax: [bool; 2],
         label_desc: &[LabelDescription; 2],
-        mesh: &mut MeshStyle<'_, '_, RangedCoordf64, RangedCoordf64, T>,
+        mesh: &mut MeshStyle<'_, '_, AxisCoord, AxisCoord, T>,
     ) {
         if ax[1] {
             mesh.y_desc(&label_desc[1].label);
@@ -1288,12 +1441,29 @@ impl PlotData {
     /// This function panics if the `expand_lims` function fails. As this only happens for a non-normal number this cannnot happen here.
     #[must_use]
     fn define_data_based_axes_bounds(&self, expand_flag: bool) -> PlotBounds {
+        self.define_data_based_axes_bounds_with_min_range(
+            expand_flag,
+            AxLims::DEFAULT_MIN_RELATIVE_RANGE,
+        )
+    }
+    /// Defines the plot-axes bounds of this [`PlotData`] with a guaranteed minimum range of each axis.
+    ///
+    /// See [`AxLims::create_useful_axlims_with_min_range`] for the meaning of `min_rel_range`.
+    fn define_data_based_axes_bounds_with_min_range(
+        &self,
+        expand_flag: bool,
+        min_rel_range: f64,
+    ) -> PlotBounds {
         let ax_min_max_vals = self.get_axes_min_max_values();
         let mut axlims = Vec::<Option<AxLims>>::with_capacity(ax_min_max_vals.len());
         //check if the limits are useful for visualization
         for min_max_vals_opt in &ax_min_max_vals {
             if let Some((min, max)) = min_max_vals_opt {
-                axlims.push(AxLims::create_useful_axlims(*min, *max));
+                axlims.push(AxLims::create_useful_axlims_with_min_range(
+                    *min,
+                    *max,
+                    min_rel_range,
+                ));
             } else {
                 axlims.push(AxLims::new(0., 1.));
             }
@@ -1311,6 +1481,147 @@ impl PlotData {
 
         PlotBounds::new(axlim_opt[0], axlim_opt[1], axlim_opt[2])
     }
+
+    fn get_xy_axis_values(&self, axis: usize) -> Option<Vec<f64>> {
+        match self {
+            Self::Dim2 { xy_data } => Some(xy_data.column(axis).iter().copied().collect()),
+            Self::MultiDim2 { vec_of_xy_data } => Some(
+                vec_of_xy_data
+                    .iter()
+                    .flat_map(|xy_data| xy_data.column(axis).iter().copied().collect_vec())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Defines the plot-axes bounds of this [`PlotData`] for (possibly) logarithmically scaled x and y axes.
+    ///
+    /// Linearly scaled axes are handled like in [`define_data_based_axes_bounds`](Self::define_data_based_axes_bounds).
+    /// For logarithmically scaled axes, only positive finite values are taken into account. Logarithmic scaling is only
+    /// supported for two-dimensional data ([`PlotData::Dim2`] and [`PlotData::MultiDim2`]) and ignored otherwise.
+    /// # Attributes
+    /// - `expand_flag`: true if the ax bounds should expand such that the data is not on the edge of the plot. false for no expansion
+    /// - `log_scale`: flags for logarithmic scaling of the x and the y axis
+    /// - `min_rel_range`: minimum range of linearly scaled axes relative to their largest absolute limit (see
+    ///   [`AxLims::create_useful_axlims_with_min_range`])
+    /// # Returns
+    /// This function returns the [`PlotBounds`] and a flag whether the data contains zero or negative values on a logarithmic axis
+    #[must_use]
+    pub fn define_data_based_log_axes_bounds(
+        &self,
+        expand_flag: bool,
+        log_scale: [bool; 2],
+        min_rel_range: f64,
+    ) -> (PlotBounds, bool) {
+        let mut bounds =
+            self.define_data_based_axes_bounds_with_min_range(expand_flag, min_rel_range);
+        let mut non_positive = false;
+        for (axis, _) in log_scale.iter().enumerate().filter(|(_, log)| **log) {
+            let Some(values) = self.get_xy_axis_values(axis) else {
+                continue;
+            };
+            non_positive |= values.iter().any(|v| *v <= 0.);
+            let positive_values = values.into_iter().filter(|v| *v > 0.).collect_vec();
+            let mut axlim = get_min_max_filter_nonfinite(&positive_values)
+                .and_then(|(min, max)| AxLims::create_useful_log_axlims(min, max))
+                .unwrap_or(AxLims { min: 0.1, max: 1. });
+            if expand_flag {
+                axlim.expand_log_lim_range_by_factor(1.1);
+            }
+            if axis == 0 {
+                bounds.x = Some(axlim);
+            } else {
+                bounds.y = Some(axlim);
+            }
+        }
+        (bounds, non_positive)
+    }
+
+    /// Writes this [`PlotData`] to a `.csv` file.
+    ///
+    /// The layout of the file depends on the variant:
+    /// - [`PlotData::Dim2`]: columns `x`, `y`
+    /// - [`PlotData::Dim3`] and [`PlotData::TriangulatedSurface`]: columns `x`, `y`, `z` (for triangulated surfaces only the
+    ///   vertices are written)
+    /// - [`PlotData::MultiDim2`]: columns `line`, `x`, `y` with `line` being the index of the line
+    /// - [`PlotData::MultiDim3`]: columns `line`, `x`, `y`, `z` with `line` being the index of the line
+    /// - [`PlotData::ColorMesh`]: grid layout. The first row holds the `N` x values (preceded by an empty cell), each
+    ///   of the following `M` rows holds the y value followed by the `N` z values of this row.
+    /// # Attributes
+    /// - `path`: path of the `.csv` file
+    /// # Errors
+    /// This function errors if the file could not be written
+    pub fn to_csv(&self, path: &Path) -> OpmResult<()> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        let mut records: Vec<Vec<String>> = Vec::new();
+        match self {
+            Self::Dim2 { xy_data } => {
+                records.push(vec!["x".into(), "y".into()]);
+                records.extend(
+                    xy_data
+                        .row_iter()
+                        .map(|row| row.iter().map(ToString::to_string).collect()),
+                );
+            }
+            Self::Dim3 { xyz_data: xyz_dat } | Self::TriangulatedSurface { xyz_dat, .. } => {
+                records.push(vec!["x".into(), "y".into(), "z".into()]);
+                records.extend(
+                    xyz_dat
+                        .row_iter()
+                        .map(|row| row.iter().map(ToString::to_string).collect()),
+                );
+            }
+            Self::MultiDim2 { vec_of_xy_data } => {
+                records.push(vec!["line".into(), "x".into(), "y".into()]);
+                for (i, xy_data) in vec_of_xy_data.iter().enumerate() {
+                    records.extend(xy_data.row_iter().map(|row| {
+                        std::iter::once(i.to_string())
+                            .chain(row.iter().map(ToString::to_string))
+                            .collect()
+                    }));
+                }
+            }
+            Self::MultiDim3 { vec_of_xyz_data } => {
+                records.push(vec!["line".into(), "x".into(), "y".into(), "z".into()]);
+                for (i, xyz_data) in vec_of_xyz_data.iter().enumerate() {
+                    records.extend(xyz_data.row_iter().map(|row| {
+                        std::iter::once(i.to_string())
+                            .chain(row.iter().map(ToString::to_string))
+                            .collect()
+                    }));
+                }
+            }
+            Self::ColorMesh {
+                x_dat_n,
+                y_dat_m,
+                z_dat_nxm,
+            } => {
+                records.push(
+                    std::iter::once(String::new())
+                        .chain(x_dat_n.iter().map(ToString::to_string))
+                        .collect(),
+                );
+                for (y, z_row) in y_dat_m.iter().zip(z_dat_nxm.row_iter()) {
+                    records.push(
+                        std::iter::once(y.to_string())
+                            .chain(z_row.iter().map(ToString::to_string))
+                            .collect(),
+                    );
+                }
+            }
+        }
+        for record in records {
+            writer
+                .write_record(record)
+                .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
 }
 
 /// Trait for adding the possibility to generate a (x/y) plot of an element.
@@ -1394,10 +1705,84 @@ pub enum PltBackEnd {
 ///Struct to hold the color gradient information of a [`ColorBar`]
 #[derive(Debug, Clone, Copy)]
 pub struct CGradient {
+    name: &'static str,
     gradient: Gradient,
 }
 
+/// Available color gradients and their names
+const GRADIENTS: [(&str, Gradient); 38] = [
+    ("turbo", colorous::TURBO),
+    ("viridis", colorous::VIRIDIS),
+    ("inferno", colorous::INFERNO),
+    ("magma", colorous::MAGMA),
+    ("plasma", colorous::PLASMA),
+    ("cividis", colorous::CIVIDIS),
+    ("warm", colorous::WARM),
+    ("cool", colorous::COOL),
+    ("cubehelix", colorous::CUBEHELIX),
+    ("blue_green", colorous::BLUE_GREEN),
+    ("blue_purple", colorous::BLUE_PURPLE),
+    ("green_blue", colorous::GREEN_BLUE),
+    ("orange_red", colorous::ORANGE_RED),
+    ("purple_blue_green", colorous::PURPLE_BLUE_GREEN),
+    ("purple_blue", colorous::PURPLE_BLUE),
+    ("purple_red", colorous::PURPLE_RED),
+    ("red_purple", colorous::RED_PURPLE),
+    ("yellow_green_blue", colorous::YELLOW_GREEN_BLUE),
+    ("yellow_green", colorous::YELLOW_GREEN),
+    ("yellow_orange_brown", colorous::YELLOW_ORANGE_BROWN),
+    ("yellow_orange_red", colorous::YELLOW_ORANGE_RED),
+    ("brown_green", colorous::BROWN_GREEN),
+    ("purple_green", colorous::PURPLE_GREEN),
+    ("pink_green", colorous::PINK_GREEN),
+    ("purple_orange", colorous::PURPLE_ORANGE),
+    ("red_blue", colorous::RED_BLUE),
+    ("red_grey", colorous::RED_GREY),
+    ("red_yellow_blue", colorous::RED_YELLOW_BLUE),
+    ("red_yellow_green", colorous::RED_YELLOW_GREEN),
+    ("spectral", colorous::SPECTRAL),
+    ("blues", colorous::BLUES),
+    ("greens", colorous::GREENS),
+    ("greys", colorous::GREYS),
+    ("oranges", colorous::ORANGES),
+    ("purples", colorous::PURPLES),
+    ("reds", colorous::REDS),
+    ("rainbow", colorous::RAINBOW),
+    ("sinebow", colorous::SINEBOW),
+];
+
 impl CGradient {
+    /// Creates a new [`CGradient`] from the name of a `colorous` gradient.
+    ///
+    /// The name is case-insensitive and corresponds to the name of the gradient constant in `colorous`,
+    /// e.g. `viridis`, `greys` or `red_blue`.
+    /// # Errors
+    /// This function errors if no gradient with the given name exists. The error message lists all valid names.
+    pub fn from_name(name: &str) -> OpmResult<Self> {
+        GRADIENTS
+            .iter()
+            .find(|(gradient_name, _)| gradient_name.eq_ignore_ascii_case(name.trim()))
+            .map(|(name, gradient)| Self {
+                name,
+                gradient: *gradient,
+            })
+            .ok_or_else(|| {
+                OpossumError::Other(format!(
+                    "unknown colormap \"{name}\"! Valid options are: {}",
+                    Self::names().join(", ")
+                ))
+            })
+    }
+    /// Returns the names of all available color gradients.
+    #[must_use]
+    pub fn names() -> Vec<&'static str> {
+        GRADIENTS.iter().map(|(name, _)| *name).collect()
+    }
+    /// Returns the name of this [`CGradient`].
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
     /// Returns the gradient of this [`CGradient`].
     #[must_use]
     pub const fn get_gradient(&self) -> Gradient {
@@ -1407,6 +1792,7 @@ impl CGradient {
 impl Default for CGradient {
     fn default() -> Self {
         Self {
+            name: "turbo",
             gradient: colorous::TURBO,
         }
     }
@@ -1620,6 +2006,12 @@ impl Default for PlotParameters {
     /// - `PlotArgs::FDir`: `current directory`
     /// - `PlotArgs::FName`: `opossum_default_plot_{i}.png`. Here, i is chosen such that no file is overwritten, but a new file is generated
     /// - `PlotArgs::PlotSize`: `(800, 800)`
+    /// - `PlotArgs::XLogScale`: `false`
+    /// - `PlotArgs::YLogScale`: `false`
+    /// - `PlotArgs::ExportData`: `true`
+    /// - `PlotArgs::ContourLevels`: `[]` (automatically chosen levels)
+    /// - `PlotArgs::Title`: `""` (no title)
+    /// - `PlotArgs::MinRelativeAxisRange`: [`AxLims::DEFAULT_MIN_RELATIVE_RANGE`]
     /// # Returns
     /// This method returns a new [`PlotParameters`] struct
     /// # Panics
@@ -1675,6 +2067,18 @@ impl Default for PlotParameters {
                     .set(&PlotArgs::ViewDirection3D(Vector3::new(-1., -1., -1.)))
                     .unwrap(),
                 PlotArgs::Legend(_) => plt_params.set(&PlotArgs::Legend(true)).unwrap(),
+                PlotArgs::XLogScale(_) => plt_params.set(&PlotArgs::XLogScale(false)).unwrap(),
+                PlotArgs::YLogScale(_) => plt_params.set(&PlotArgs::YLogScale(false)).unwrap(),
+                PlotArgs::ExportData(_) => plt_params.set(&PlotArgs::ExportData(true)).unwrap(),
+                PlotArgs::ContourLevels(_) => plt_params
+                    .set(&PlotArgs::ContourLevels(Vec::new()))
+                    .unwrap(),
+                PlotArgs::Title(_) => plt_params.set(&PlotArgs::Title(String::new())).unwrap(),
+                PlotArgs::MinRelativeAxisRange(_) => plt_params
+                    .set(&PlotArgs::MinRelativeAxisRange(
+                        AxLims::DEFAULT_MIN_RELATIVE_RANGE,
+                    ))
+                    .unwrap(),
             };
         }
 
@@ -1995,6 +2399,91 @@ impl PlotParameters {
         }
     }
 
+    ///This method gets the logarithmic x-axis flag which is stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<bool>`] with the flag that decides if the x axis is scaled logarithmically
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_x_log_scale_flag(&self) -> OpmResult<bool> {
+        if let Some(PlotArgs::XLogScale(log_scale)) = self.params.get("xlogscale") {
+            Ok(*log_scale)
+        } else {
+            Err(OpossumError::Other("xlogscale argument not found!".into()))
+        }
+    }
+
+    ///This method gets the logarithmic y-axis flag which is stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<bool>`] with the flag that decides if the y axis is scaled logarithmically
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_y_log_scale_flag(&self) -> OpmResult<bool> {
+        if let Some(PlotArgs::YLogScale(log_scale)) = self.params.get("ylogscale") {
+            Ok(*log_scale)
+        } else {
+            Err(OpossumError::Other("ylogscale argument not found!".into()))
+        }
+    }
+
+    ///This method gets the data export flag which is stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<bool>`] with the flag that decides if the plot data is exported to a `.csv` file
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_export_data_flag(&self) -> OpmResult<bool> {
+        if let Some(PlotArgs::ExportData(export_data)) = self.params.get("exportdata") {
+            Ok(*export_data)
+        } else {
+            Err(OpossumError::Other("exportdata argument not found!".into()))
+        }
+    }
+
+    ///This method gets the contour levels which are stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<Vec<f64>>`] with the z levels of the isolines of a contour plot
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_contour_levels(&self) -> OpmResult<Vec<f64>> {
+        if let Some(PlotArgs::ContourLevels(levels)) = self.params.get("contourlevels") {
+            Ok(levels.clone())
+        } else {
+            Err(OpossumError::Other(
+                "contourlevels argument not found!".into(),
+            ))
+        }
+    }
+
+    ///This method gets the minimum relative axis range which is stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<f64>`] with the minimum range of automatically determined axis limits relative to
+    /// their largest absolute value
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_min_relative_axis_range(&self) -> OpmResult<f64> {
+        if let Some(PlotArgs::MinRelativeAxisRange(min_rel_range)) =
+            self.params.get("minrelativeaxisrange")
+        {
+            Ok(*min_rel_range)
+        } else {
+            Err(OpossumError::Other(
+                "minrelativeaxisrange argument not found!".into(),
+            ))
+        }
+    }
+
+    ///This method gets the plot title which is stored in the [`PlotParameters`]
+    /// # Returns
+    /// This method returns an [`OpmResult<String>`] containing the title of the plot
+    /// # Errors
+    /// This method throws an error if the argument is not found
+    pub fn get_title(&self) -> OpmResult<String> {
+        if let Some(PlotArgs::Title(title)) = self.params.get("title") {
+            Ok(title.clone())
+        } else {
+            Err(OpossumError::Other("title argument not found!".into()))
+        }
+    }
+
     fn check_if_set(&self, plt_arg: &PlotArgs) -> bool {
         let mut found = false;
         for param_val in self.params.values() {
@@ -2026,6 +2515,10 @@ impl PlotParameters {
             PlotArgs::FName(fname) => {
                 Self::check_file_ext_validity(fname, vec!["jpg", "png", "bmp", "svg"])
             }
+            PlotArgs::ContourLevels(levels) => levels.iter().all(|level| level.is_finite()),
+            PlotArgs::MinRelativeAxisRange(min_rel_range) => {
+                min_rel_range.is_finite() && min_rel_range.is_sign_positive()
+            }
             // labels, color and gradient are irrelevant to check.
             //cross check of backend and full file path is done later, as a change would otherwise always result in an error.
             _ => true,
@@ -2067,6 +2560,12 @@ impl PlotParameters {
             PlotArgs::Backend(_) => "backend".to_owned(),
             PlotArgs::ViewDirection3D(_) => "view3d".to_owned(),
             PlotArgs::Legend(_) => "legend".to_owned(),
+            PlotArgs::XLogScale(_) => "xlogscale".to_owned(),
+            PlotArgs::YLogScale(_) => "ylogscale".to_owned(),
+            PlotArgs::ExportData(_) => "exportdata".to_owned(),
+            PlotArgs::ContourLevels(_) => "contourlevels".to_owned(),
+            PlotArgs::Title(_) => "title".to_owned(),
+            PlotArgs::MinRelativeAxisRange(_) => "minrelativeaxisrange".to_owned(),
         }
     }
 
@@ -2163,6 +2662,16 @@ impl PlotParameters {
                 self.params.insert("view3d".to_owned(), plt_arg.clone())
             }
             PlotArgs::Legend(_) => self.params.insert("legend".to_owned(), plt_arg.clone()),
+            PlotArgs::XLogScale(_) => self.params.insert("xlogscale".to_owned(), plt_arg.clone()),
+            PlotArgs::YLogScale(_) => self.params.insert("ylogscale".to_owned(), plt_arg.clone()),
+            PlotArgs::ExportData(_) => self.params.insert("exportdata".to_owned(), plt_arg.clone()),
+            PlotArgs::ContourLevels(_) => self
+                .params
+                .insert("contourlevels".to_owned(), plt_arg.clone()),
+            PlotArgs::Title(_) => self.params.insert("title".to_owned(), plt_arg.clone()),
+            PlotArgs::MinRelativeAxisRange(_) => self
+                .params
+                .insert("minrelativeaxisrange".to_owned(), plt_arg.clone()),
         };
     }
 }
@@ -2244,9 +2753,16 @@ pub struct Plot {
     fig_size: (u32, u32),
     pl_series: Option<Vec<PlotSeries>>,
     _view_3d: Vector3<f64>,
+    log_scale: [bool; 2],
+    log_warning_issued: bool,
+    contour_levels: Vec<f64>,
+    title: String,
+    min_rel_range: f64,
 }
 
 impl Plot {
+    /// Number of automatically chosen contour levels if no levels are given for a [`PlotType::Contour`] plot
+    pub const DEFAULT_NR_OF_CONTOUR_LEVELS: usize = 5;
     /// creates a new [`Plot`]
     /// # Attributes
     /// - `plt_series`: reference to a [`PlotSeries`]
@@ -2310,10 +2826,8 @@ impl Plot {
             self.pl_series = Some(plt_series_vec.clone());
         }
 
-        let mut bounds = PlotBounds::default();
-        for plt_series in plt_series_vec {
-            bounds.join(&plt_series.define_data_based_axes_bounds(self.expand_bounds));
-        }
+        let (bounds, non_positive) = self.data_based_axes_bounds(plt_series_vec);
+        self.warn_non_positive_log_data(non_positive);
         if join_bounds {
             self.bounds = bounds;
         } else {
@@ -2393,6 +2907,35 @@ impl Plot {
         }
     }
 
+    /// Determines the data-based axes bounds of the given [`PlotSeries`] with respect to the axis scaling of this [`Plot`].
+    ///
+    /// Returns the joined [`PlotBounds`] and a flag whether non-positive values had to be ignored on a logarithmic axis.
+    fn data_based_axes_bounds(&self, plt_series_vec: &[PlotSeries]) -> (PlotBounds, bool) {
+        let mut bounds = PlotBounds::default();
+        let mut non_positive = false;
+        for plt_series in plt_series_vec {
+            let (series_bounds, series_non_positive) = plt_series
+                .get_plot_series_data()
+                .define_data_based_log_axes_bounds(
+                    self.expand_bounds,
+                    self.log_scale,
+                    self.min_rel_range,
+                );
+            bounds.join(&series_bounds);
+            non_positive |= series_non_positive;
+        }
+        (bounds, non_positive)
+    }
+
+    fn warn_non_positive_log_data(&mut self, non_positive: bool) {
+        if non_positive && !self.log_warning_issued {
+            warn!(
+                "Logarithmic axis scaling requested for data containing zero or negative values! These values are ignored for the axis limits"
+            );
+            self.log_warning_issued = true;
+        }
+    }
+
     /// Defines the axes bounds of this [`Plot`] if the limit is not already defined by the initial [`PlotParameters`].
     ///
     /// # Errors
@@ -2406,11 +2949,8 @@ impl Plot {
             if plot_series.is_empty() {
                 warn!("No plot series defined! Cannot define axes bounds!");
             } else {
-                let mut plt_bounds_series = PlotBounds::default();
-                for plt_series in plot_series {
-                    plt_bounds_series
-                        .join(&plt_series.define_data_based_axes_bounds(self.expand_bounds));
-                }
+                let (plt_bounds_series, non_positive) = self.data_based_axes_bounds(plot_series);
+                self.warn_non_positive_log_data(non_positive);
                 if self.bounds.get_x_bounds().is_none() {
                     self.bounds.x = plt_bounds_series.get_x_bounds();
                 }
@@ -2420,7 +2960,8 @@ impl Plot {
                 if self.bounds.get_z_bounds().is_none() {
                     self.bounds.z = plt_bounds_series.get_z_bounds();
                 }
-                if self.ax_equal {
+                // equal axes are meaningless if any of them is scaled logarithmically
+                if self.ax_equal && !self.log_scale.contains(&true) {
                     self.set_xy_axes_ranges_equal();
                 }
             }
@@ -2448,6 +2989,13 @@ impl TryFrom<&PlotParameters> for Plot {
         let x_label_pos = plt_params.get_x_label_pos()?;
         let y_label_pos = plt_params.get_y_label_pos()?;
         let view_3d = plt_params.get_3d_view()?;
+        let log_scale = [
+            plt_params.get_x_log_scale_flag()?,
+            plt_params.get_y_log_scale_flag()?,
+        ];
+        let contour_levels = plt_params.get_contour_levels()?;
+        let title = plt_params.get_title()?;
+        let min_rel_range = plt_params.get_min_relative_axis_range()?;
 
         let x_label = LabelDescription::new(&x_label_str, x_label_pos);
         let y_label = LabelDescription::new(&y_label_str, y_label_pos);
@@ -2469,6 +3017,11 @@ impl TryFrom<&PlotParameters> for Plot {
             fig_size: plot_size,
             pl_series: None,
             _view_3d: view_3d,
+            log_scale,
+            log_warning_issued: false,
+            contour_levels,
+            title,
+            min_rel_range,
         })
     }
 }
@@ -2484,7 +3037,7 @@ pub enum PlotArgs {
     XLabelPos(LabelPos),
     ///Position of the y label. Holds a [`LabelPos`] enum
     YLabelPos(LabelPos),
-    ///Colormap of the Data Points. Holds a [`CGradient`] struct
+    ///Colormap of the Data Points. Holds a [`CGradient`] struct, which can be created by name using [`CGradient::from_name`]
     CMap(CGradient),
     ///Label of the colorbar. Holds a String
     CBarLabel(String),
@@ -2514,6 +3067,19 @@ pub enum PlotArgs {
     ViewDirection3D(Vector3<f64>),
     ///Define to show the legend or not. default true
     Legend(bool),
+    ///defines whether the x axis is scaled logarithmically. default false
+    XLogScale(bool),
+    ///defines whether the y axis is scaled logarithmically. default false
+    YLogScale(bool),
+    ///defines whether the plot data is additionally exported to a `.csv` file next to the image file. default true
+    ExportData(bool),
+    ///z levels of the isolines of a [`PlotType::Contour`] plot. Levels outside the data range are skipped. If empty, the levels are chosen automatically. default empty
+    ContourLevels(Vec<f64>),
+    ///Title of the plot, shown centered above the plot. Holds a String. default empty (no title)
+    Title(String),
+    ///Minimum range of automatically determined (linear) axis limits relative to their largest absolute value. Avoids
+    ///the visual amplification of numerical noise in (nearly) constant data. Must be positive and finite. default [`AxLims::DEFAULT_MIN_RELATIVE_RANGE`]
+    MinRelativeAxisRange(f64),
 }
 
 #[cfg(test)]
@@ -2899,6 +3465,7 @@ mod test {
         let mut plt_params = PlotParameters::default();
         plt_params
             .set(&PlotArgs::CMap(CGradient {
+                name: "turbo",
                 gradient: colorous::TURBO,
             }))
             .unwrap();
@@ -2906,6 +3473,25 @@ mod test {
             format!("{:?}", plt_params.get_cmap().unwrap().get_gradient()),
             "Gradient(Turbo)".to_owned()
         );
+        plt_params
+            .set(&PlotArgs::CMap(CGradient::from_name("greys").unwrap()))
+            .unwrap();
+        assert_eq!(plt_params.get_cmap().unwrap().name(), "greys");
+    }
+    #[test]
+    fn cgradient_from_name() {
+        assert_eq!(CGradient::default().name(), "turbo");
+        let cmap = CGradient::from_name("Viridis").unwrap();
+        assert_eq!(cmap.name(), "viridis");
+        assert_eq!(format!("{:?}", cmap.get_gradient()), "Gradient(Viridis)");
+        let cmap = CGradient::from_name(" red_blue ").unwrap();
+        assert_eq!(format!("{:?}", cmap.get_gradient()), "Gradient(RdBu)");
+        for name in CGradient::names() {
+            assert_eq!(CGradient::from_name(name).unwrap().name(), name);
+        }
+        let err = CGradient::from_name("not_a_colormap").unwrap_err();
+        assert!(err.to_string().contains("not_a_colormap"));
+        assert!(err.to_string().contains("viridis, inferno"));
     }
     #[test]
     fn plot_params_ax_lims() {
@@ -2958,6 +3544,79 @@ mod test {
         assert_eq!(plt_params.get_fname().unwrap(), "test_name.png".to_owned());
     }
     #[test]
+    fn plot_params_log_scale() {
+        let mut plt_params = PlotParameters::default();
+        assert!(!plt_params.get_x_log_scale_flag().unwrap());
+        assert!(!plt_params.get_y_log_scale_flag().unwrap());
+        plt_params
+            .set(&PlotArgs::XLogScale(true))
+            .unwrap()
+            .set(&PlotArgs::YLogScale(true))
+            .unwrap();
+        assert!(plt_params.get_x_log_scale_flag().unwrap());
+        assert!(plt_params.get_y_log_scale_flag().unwrap());
+        let plt = Plot::try_from(&plt_params).unwrap();
+        assert_eq!(plt.log_scale, [true, true]);
+    }
+    #[test]
+    fn plot_params_export_data() {
+        let mut plt_params = PlotParameters::default();
+        assert!(plt_params.get_export_data_flag().unwrap());
+        plt_params.set(&PlotArgs::ExportData(false)).unwrap();
+        assert!(!plt_params.get_export_data_flag().unwrap());
+    }
+    #[test]
+    fn plot_params_contour_levels() {
+        let mut plt_params = PlotParameters::default();
+        assert!(plt_params.get_contour_levels().unwrap().is_empty());
+        plt_params
+            .set(&PlotArgs::ContourLevels(vec![1., 2.]))
+            .unwrap();
+        assert_eq!(plt_params.get_contour_levels().unwrap(), vec![1., 2.]);
+        assert!(
+            plt_params
+                .set(&PlotArgs::ContourLevels(vec![1., f64::NAN]))
+                .is_err()
+        );
+        let plt = Plot::try_from(&plt_params).unwrap();
+        assert_eq!(plt.contour_levels, vec![1., 2.]);
+    }
+    #[test]
+    fn plot_params_min_relative_axis_range() {
+        let mut plt_params = PlotParameters::default();
+        assert_eq!(
+            plt_params.get_min_relative_axis_range().unwrap(),
+            AxLims::DEFAULT_MIN_RELATIVE_RANGE
+        );
+        plt_params
+            .set(&PlotArgs::MinRelativeAxisRange(0.1))
+            .unwrap();
+        assert_eq!(plt_params.get_min_relative_axis_range().unwrap(), 0.1);
+        assert!(
+            plt_params
+                .set(&PlotArgs::MinRelativeAxisRange(-0.1))
+                .is_err()
+        );
+        assert!(
+            plt_params
+                .set(&PlotArgs::MinRelativeAxisRange(f64::NAN))
+                .is_err()
+        );
+        let plt = Plot::try_from(&plt_params).unwrap();
+        assert_eq!(plt.min_rel_range, 0.1);
+    }
+    #[test]
+    fn plot_params_title() {
+        let mut plt_params = PlotParameters::default();
+        assert!(plt_params.get_title().unwrap().is_empty());
+        plt_params
+            .set(&PlotArgs::Title("detector 1".into()))
+            .unwrap();
+        assert_eq!(plt_params.get_title().unwrap(), "detector 1");
+        let plt = Plot::try_from(&plt_params).unwrap();
+        assert_eq!(plt.title, "detector 1");
+    }
+    #[test]
     fn plot_params_fpath() {
         let mut plt_params = PlotParameters::default();
         plt_params
@@ -3313,6 +3972,16 @@ mod test {
         assert_relative_eq!(axlims.z.unwrap().max, 4.1);
     }
     #[test]
+    fn define_data_based_axes_bounds_constant_data() {
+        let x = linspace(0., 2., 3).unwrap();
+        let y = DVector::from_vec(vec![1.0e-3, 1.0e-3, 1.0e-3]);
+        let plt_dat = PlotData::new_dim2(MatrixXx2::from_columns(&[x, y])).unwrap();
+        let axlims = plt_dat.define_data_based_axes_bounds(false);
+        let y_lims = axlims.y.unwrap();
+        assert!(y_lims.check_validity());
+        assert!(y_lims.min < 1.0e-3 && y_lims.max > 1.0e-3);
+    }
+    #[test]
     fn define_plot_axes_bounds() {
         //define test data
         let x = linspace(0., 2., 3).unwrap();
@@ -3639,4 +4308,250 @@ mod test {
         let _ =
             PlotType::TriangulatedSurface(plt_params.clone()).plot(&vec![plt_series_surf_triangle]);
     }
+    #[test]
+    fn tick_formatter_test() {
+        let format = PlotType::tick_formatter(0.0..0.0, false);
+        assert_eq!(format(&0.0), "0");
+        let format = PlotType::tick_formatter(0.0..10.0, false);
+        assert_eq!(format(&2.5), "2.5");
+        let format = PlotType::tick_formatter(1e-3..10.0, true);
+        assert_eq!(format(&0.001_000_000_000_000_2), "1e-3");
+        assert_eq!(format(&2.0), "2e0");
+    }
+    #[test]
+    fn log_scale_bounds() {
+        let data = PlotData::new_dim2(MatrixXx2::from_vec(vec![
+            -1., 0., 1., 10., 0., 1e-3, 1e-2, 1.,
+        ]))
+        .unwrap();
+        let plt_series = PlotSeries::new(&data, RGBAColor(255, 0, 0, 1.), None);
+        let (bounds, non_positive) = data.define_data_based_log_axes_bounds(
+            false,
+            [false, true],
+            AxLims::DEFAULT_MIN_RELATIVE_RANGE,
+        );
+        assert!(non_positive);
+        assert_relative_eq!(bounds.x.unwrap().min, -1.);
+        assert_relative_eq!(bounds.x.unwrap().max, 10.);
+        assert_relative_eq!(bounds.y.unwrap().min, 1e-3, max_relative = 1e-12);
+        assert_relative_eq!(bounds.y.unwrap().max, 1., max_relative = 1e-12);
+        let (_, non_positive) = data.define_data_based_log_axes_bounds(
+            false,
+            [false, false],
+            AxLims::DEFAULT_MIN_RELATIVE_RANGE,
+        );
+        assert!(!non_positive);
+
+        let mut plt_params = PlotParameters::default();
+        plt_params
+            .set(&PlotArgs::XLogScale(true))
+            .unwrap()
+            .set(&PlotArgs::YLogScale(true))
+            .unwrap();
+        testing_logger::setup();
+        let mut plt = Plot::new(&vec![plt_series.clone()], &plt_params);
+        plt.define_axes_bounds();
+        plt.add_plot_series(&vec![plt_series], true);
+        check_logs(
+            log::Level::Warn,
+            vec![
+                "Logarithmic axis scaling requested for data containing zero or negative values! These values are ignored for the axis limits",
+            ],
+        );
+        let x_bounds = plt.bounds.get_x_bounds().unwrap();
+        let y_bounds = plt.bounds.get_y_bounds().unwrap();
+        assert!(x_bounds.min > 0. && x_bounds.min < 1.);
+        assert!(x_bounds.max > 10.);
+        assert!(y_bounds.min > 0. && y_bounds.min < 1e-3);
+        assert!(y_bounds.max > 1.);
+    }
+    #[test]
+    fn create_log_plots_buffer_test() {
+        let x = DVector::from_vec(vec![0., 1., 10., 100.]);
+        let y = DVector::from_vec(vec![1e-4, 0., 1e-2, 5.]);
+        let plt_series = PlotSeries::new(
+            &PlotData::new_dim2(MatrixXx2::from_columns(&[x, y])).unwrap(),
+            RGBAColor(0, 0, 0, 1.),
+            None,
+        );
+        let mut plt_params = PlotParameters::default();
+        plt_params
+            .set(&PlotArgs::Backend(PltBackEnd::Buf))
+            .unwrap()
+            .set(&PlotArgs::XLogScale(true))
+            .unwrap()
+            .set(&PlotArgs::YLogScale(true))
+            .unwrap();
+        for plt_type in [
+            PlotType::Line2D(plt_params.clone()),
+            PlotType::Histogram2D(plt_params.clone()),
+            PlotType::Scatter2D(plt_params.clone()),
+        ] {
+            let img = plt_type.plot(&vec![plt_series.clone()]).unwrap();
+            assert!(img.is_some());
+        }
+    }
+    #[test]
+    fn plot_data_to_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("dim2.csv");
+        PlotData::new_dim2(MatrixXx2::from_vec(vec![0., 1., 2., 3.]))
+            .unwrap()
+            .to_csv(&path)
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x,y\n0,2\n1,3\n");
+
+        let path = dir.path().join("multidim2.csv");
+        PlotData::MultiDim2 {
+            vec_of_xy_data: vec![
+                MatrixXx2::from_vec(vec![0., 1.]),
+                MatrixXx2::from_vec(vec![2., 3.]),
+            ],
+        }
+        .to_csv(&path)
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "line,x,y\n0,0,1\n1,2,3\n"
+        );
+
+        let path = dir.path().join("colormesh.csv");
+        PlotData::new_colormesh(
+            DVector::from_vec(vec![0., 1., 2.]),
+            DVector::from_vec(vec![5., 6.]),
+            DMatrix::from_row_slice(2, 3, &[1., 2., 3., 4., 5., 6.]),
+        )
+        .unwrap()
+        .to_csv(&path)
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            ",0,1,2\n5,1,2,3\n6,4,5,6\n"
+        );
+        assert!(
+            PlotData::new_dim2(MatrixXx2::from_vec(vec![0., 1.]))
+                .unwrap()
+                .to_csv(&dir.path().join("not_existing").join("file.csv"))
+                .is_err()
+        );
+    }
+    #[test]
+    fn plot_exports_data() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let plt_series = PlotSeries::new(
+            &PlotData::new_dim2(MatrixXx2::from_vec(vec![0., 1., 2., 3.])).unwrap(),
+            RGBAColor(0, 0, 0, 1.),
+            None,
+        );
+        let mut plt_params = PlotParameters::default();
+        plt_params
+            .set(&PlotArgs::FDir(dir.path().into()))
+            .unwrap()
+            .set(&PlotArgs::Backend(PltBackEnd::SVG))
+            .unwrap()
+            .set(&PlotArgs::FName("single.svg".into()))
+            .unwrap();
+        PlotType::Line2D(plt_params.clone())
+            .plot(&vec![plt_series.clone()])
+            .unwrap();
+        assert!(dir.path().join("single.svg").exists());
+        assert!(dir.path().join("single.csv").exists());
+
+        plt_params
+            .set(&PlotArgs::FName("multi.svg".into()))
+            .unwrap();
+        PlotType::Line2D(plt_params.clone())
+            .plot(&vec![plt_series.clone(), plt_series.clone()])
+            .unwrap();
+        assert!(dir.path().join("multi_0.csv").exists());
+        assert!(dir.path().join("multi_1.csv").exists());
+
+        plt_params
+            .set(&PlotArgs::FName("no_export.svg".into()))
+            .unwrap()
+            .set(&PlotArgs::ExportData(false))
+            .unwrap();
+        PlotType::Line2D(plt_params)
+            .plot(&vec![plt_series])
+            .unwrap();
+        assert!(dir.path().join("no_export.svg").exists());
+        assert!(!dir.path().join("no_export.csv").exists());
+    }
+    #[test]
+    fn create_contour_plot() {
+        let x = linspace(-1., 1., 21).unwrap();
+        let y = linspace(-1., 1., 11).unwrap();
+        let z = DMatrix::from_fn(11, 21, |row, col| x[col] * x[col] + y[row] * y[row]);
+        let plt_series = PlotSeries::new(
+            &PlotData::new_colormesh(x, y, z).unwrap(),
+            RGBAColor(0, 0, 0, 1.),
+            None,
+        );
+        let mut plt_params = PlotParameters::default();
+        plt_params
+            .set(&PlotArgs::Backend(PltBackEnd::Buf))
+            .unwrap()
+            .set(&PlotArgs::ExportData(false))
+            .unwrap();
+        // automatically chosen levels
+        let img = PlotType::Contour(plt_params.clone())
+            .plot(&vec![plt_series.clone()])
+            .unwrap();
+        assert!(img.is_some());
+        // levels outside the z range are skipped silently
+        plt_params
+            .set(&PlotArgs::ContourLevels(vec![-1., 0.5, 10.]))
+            .unwrap();
+        testing_logger::setup();
+        let img = PlotType::Contour(plt_params.clone())
+            .plot(&vec![plt_series])
+            .unwrap();
+        assert!(img.is_some());
+        check_logs(log::Level::Warn, vec![]);
+        // wrong data type
+        let plt_series = PlotSeries::new(
+            &PlotData::new_dim2(MatrixXx2::from_vec(vec![0., 1., 2., 3.])).unwrap(),
+            RGBAColor(0, 0, 0, 1.),
+            None,
+        );
+        testing_logger::setup();
+        let _ = PlotType::Contour(plt_params).plot(&vec![plt_series]);
+        check_logs(
+            log::Level::Warn,
+            vec![
+                "Wrong PlotData stored for this plot type! Must use ColorMesh! Not all series will be plotted!",
+            ],
+        );
+    }
+    #[test]
+    fn plot_title() {
+        let plt_series = PlotSeries::new(
+            &PlotData::new_dim2(MatrixXx2::from_vec(vec![0., 1., 2., 3.])).unwrap(),
+            RGBAColor(0, 0, 0, 1.),
+            None,
+        );
+        let mut plt_params = PlotParameters::default();
+        plt_params
+            .set(&PlotArgs::Backend(PltBackEnd::Buf))
+            .unwrap()
+            .set(&PlotArgs::ExportData(false))
+            .unwrap();
+        let img_default = PlotType::Line2D(plt_params.clone())
+            .plot(&vec![plt_series.clone()])
+            .unwrap()
+            .unwrap();
+        plt_params.set(&PlotArgs::Title(String::new())).unwrap();
+        let img_empty_title = PlotType::Line2D(plt_params.clone())
+            .plot(&vec![plt_series.clone()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(img_default, img_empty_title);
+        plt_params.set(&PlotArgs::Title("title".into())).unwrap();
+        let img_title = PlotType::Line2D(plt_params)
+            .plot(&vec![plt_series])
+            .unwrap()
+            .unwrap();
+        assert_eq!(img_default.dimensions(), img_title.dimensions());
+        assert_ne!(img_default, img_title);
+    }
 }
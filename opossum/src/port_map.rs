@@ -9,6 +9,7 @@ use uuid::Uuid;
 /// maps to a specific internal port name on a specific node (identified by a [`Uuid`])
 /// within a the optical graph.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct PortMap(HashMap<String, (Uuid, String)>);
 
 impl PortMap {
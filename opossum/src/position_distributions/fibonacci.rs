@@ -14,9 +14,12 @@ use uom::si::f64::Length;
 ///
 /// For further details see [here](https://en.wikipedia.org/wiki/Fibonacci_sequence)
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct FibonacciRectangle {
     nr_of_rays: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_x: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_y: Length,
 }
 impl FibonacciRectangle {
@@ -73,9 +76,12 @@ impl From<FibonacciRectangle> for super::PosDistType {
 ///
 /// For further details see [here](https://en.wikipedia.org/wiki/Fibonacci_sequence)
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct FibonacciEllipse {
     nr_of_rays: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     radius_x: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     radius_y: Length,
 }
 impl FibonacciEllipse {
@@ -12,8 +12,10 @@ use uom::si::f64::Length;
 
 /// Rectangular, evenly-sized grid distribution
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct Grid {
     nr_of_points: (usize, usize),
+    #[cfg_attr(feature = "json_schema", schemars(with = "(f64, f64)"))]
     side_length: (Length, Length),
 }
 
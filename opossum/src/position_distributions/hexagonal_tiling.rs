@@ -14,9 +14,12 @@ use uom::si::f64::Length;
 
 /// Circular, hexapolar distribution
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct HexagonalTiling {
     nr_of_hex_along_radius: u8,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     radius: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     center: Point2<Length>,
 }
 impl HexagonalTiling {
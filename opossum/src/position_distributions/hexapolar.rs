@@ -9,8 +9,10 @@ use uom::si::f64::Length;
 
 /// Circular, hexapolar distribution
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct Hexapolar {
     nr_of_rings: u8,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     radius: Length,
 }
 impl Hexapolar {
@@ -24,15 +24,19 @@ mod fibonacci;
 mod grid;
 mod hexagonal_tiling;
 mod hexapolar;
+mod poisson_disk;
 mod random;
+mod ring;
 mod sobol;
 
 pub use fibonacci::{FibonacciEllipse, FibonacciRectangle};
 pub use grid::Grid;
 pub use hexagonal_tiling::HexagonalTiling;
 pub use hexapolar::Hexapolar;
+pub use poisson_disk::{PoissonDisk, PoissonDiskShape};
 pub use random::Random;
-pub use sobol::SobolDist;
+pub use ring::{MultiRing, Ring};
+pub use sobol::{SobolDist, SobolEllipse};
 
 /// Trait for the generation of point distributions
 pub trait PositionDistribution {
@@ -40,10 +44,19 @@ pub trait PositionDistribution {
     ///
     /// This function generates a vector of 3D points (of dimension [`Length`]) with the given parameters defined earlier.
     fn generate(&self) -> Vec<Point3<Length>>;
+    /// Generate the point distribution using a random number generator initialized with the given `seed`.
+    ///
+    /// For identical seeds, this function always returns the same points. The default implementation simply calls
+    /// [`generate`](PositionDistribution::generate), which is appropriate for all deterministic distributions.
+    fn generate_seeded(&self, seed: u64) -> Vec<Point3<Length>> {
+        let _ = seed;
+        self.generate()
+    }
 }
 
 /// Enum for the different types of position distributions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum PosDistType {
     /// Rectangular, uniform random distribution
     Random(random::Random),
@@ -59,6 +72,14 @@ pub enum PosDistType {
     FibonacciEllipse(fibonacci::FibonacciEllipse),
     /// Pseudo random Sobol distribution
     Sobol(sobol::SobolDist),
+    /// Pseudo random elliptical Sobol distribution
+    SobolEllipse(sobol::SobolEllipse),
+    /// Poisson-disk (blue noise) distribution with a minimum distance between points
+    PoissonDisk(poisson_disk::PoissonDisk),
+    /// Points evenly distributed on a circle
+    Ring(ring::Ring),
+    /// Points evenly distributed on several concentric circles
+    MultiRing(ring::MultiRing),
 }
 impl PosDistType {
     /// Generate the point distribution.
@@ -72,6 +93,10 @@ impl PosDistType {
             Self::FibonacciRectangle(dist) => dist,
             Self::FibonacciEllipse(dist) => dist,
             Self::Sobol(dist) => dist,
+            Self::SobolEllipse(dist) => dist,
+            Self::PoissonDisk(dist) => dist,
+            Self::Ring(dist) => dist,
+            Self::MultiRing(dist) => dist,
         }
     }
 }
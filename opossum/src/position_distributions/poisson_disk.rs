@@ -0,0 +1,335 @@
+#![warn(missing_docs)]
+//! Poisson-disk (blue noise) distribution
+use super::PositionDistribution;
+use crate::{
+    error::{OpmResult, OpossumError},
+    utils::{f64_to_usize, usize_to_f64},
+};
+use nalgebra::{Point3, point};
+use num::Zero;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, SQRT_2};
+use uom::si::f64::Length;
+
+/// Number of candidate points tested around an active point before it is retired.
+const NR_OF_CANDIDATES: usize = 30;
+
+/// Ratio between the area covered by a maximal Poisson-disk distribution and the number of points times the squared
+/// minimum distance (determined empirically).
+const AREA_PER_POINT: f64 = 1.45;
+
+/// Bounding shape of a [`PoissonDisk`] distribution.
+///
+/// Both shapes are centered at the origin.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub enum PoissonDiskShape {
+    /// circular disk with the given radius
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    Disk(Length),
+    /// rectangle with the given (full) side lengths in x and y direction
+    Rectangle(
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))] Length,
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))] Length,
+    ),
+}
+impl PoissonDiskShape {
+    /// Half extent (in meters) of the bounding box of this shape.
+    fn half_extent(&self) -> (f64, f64) {
+        match self {
+            Self::Disk(radius) => (radius.value, radius.value),
+            Self::Rectangle(x, y) => (x.value / 2.0, y.value / 2.0),
+        }
+    }
+    /// Area (in m²) of this shape.
+    fn area(&self) -> f64 {
+        match self {
+            Self::Disk(radius) => PI * radius.value * radius.value,
+            Self::Rectangle(x, y) => x.value * y.value,
+        }
+    }
+    fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Self::Disk(radius) => x.mul_add(x, y * y) <= radius.value * radius.value,
+            Self::Rectangle(..) => {
+                let (half_x, half_y) = self.half_extent();
+                x.abs() <= half_x && y.abs() <= half_y
+            }
+        }
+    }
+}
+/// Poisson-disk (blue noise) distribution
+///
+/// Points are randomly distributed within the given [`PoissonDiskShape`] such that no two points are closer than a given
+/// minimum distance (Bridson's algorithm). In contrast to a uniform random distribution, there are no clusters of
+/// near-coincident points, which leads to rather uniform Voronoi cells and thus to a fluence estimation without spikes.
+/// On the other hand, there are no regular patterns as for grid-like distributions.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct PoissonDisk {
+    shape: PoissonDiskShape,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    min_distance: Length,
+}
+impl PoissonDisk {
+    /// Create a new [`PoissonDisk`] distribution generator with the given minimum distance between two points.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the dimensions of the shape are not positive and finite.
+    ///   - the minimum distance is not positive and finite.
+    pub fn new(shape: PoissonDiskShape, min_distance: Length) -> OpmResult<Self> {
+        let (half_x, half_y) = shape.half_extent();
+        if !half_x.is_normal() || half_x.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "x dimension of shape must be > zero and finite".into(),
+            ));
+        }
+        if !half_y.is_normal() || half_y.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "y dimension of shape must be > zero and finite".into(),
+            ));
+        }
+        if !min_distance.is_normal() || min_distance.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "min_distance must be > zero and finite".into(),
+            ));
+        }
+        Ok(Self {
+            shape,
+            min_distance,
+        })
+    }
+    /// Create a new [`PoissonDisk`] distribution generator with approximately the given number of points.
+    ///
+    /// The minimum distance between two points is chosen such that the generated distribution contains roughly
+    /// `nr_of_points` points.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the dimensions of the shape are not positive and finite.
+    ///   - `nr_of_points` is zero.
+    pub fn with_nr_of_points(shape: PoissonDiskShape, nr_of_points: usize) -> OpmResult<Self> {
+        if nr_of_points.is_zero() {
+            return Err(OpossumError::Other("nr_of_points must be >= 1.".into()));
+        }
+        let min_distance = (shape.area() / (AREA_PER_POINT * usize_to_f64(nr_of_points))).sqrt();
+        Self::new(shape, Length::new::<uom::si::length::meter>(min_distance))
+    }
+    /// Returns the bounding shape of this [`PoissonDisk`].
+    #[must_use]
+    pub const fn shape(&self) -> &PoissonDiskShape {
+        &self.shape
+    }
+    /// Returns the minimum distance between two points of this [`PoissonDisk`].
+    #[must_use]
+    pub const fn min_distance(&self) -> Length {
+        self.min_distance
+    }
+    fn generate_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<Point3<Length>> {
+        let r = self.min_distance.value;
+        let (half_x, half_y) = self.shape.half_extent();
+        // each grid cell contains at most one point
+        let cell_size = r / SQRT_2;
+        let nx = f64_to_usize((2.0 * half_x / cell_size).ceil()).max(1);
+        let ny = f64_to_usize((2.0 * half_y / cell_size).ceil()).max(1);
+        let cell_index = |x: f64, y: f64| {
+            (
+                f64_to_usize((x + half_x) / cell_size).min(nx - 1),
+                f64_to_usize((y + half_y) / cell_size).min(ny - 1),
+            )
+        };
+        let mut grid: Vec<Option<usize>> = vec![None; nx * ny];
+        let mut samples: Vec<(f64, f64)> = Vec::new();
+        let mut active: Vec<usize> = Vec::new();
+        let first = loop {
+            let x = rng.random_range(-half_x..=half_x);
+            let y = rng.random_range(-half_y..=half_y);
+            if self.shape.contains(x, y) {
+                break (x, y);
+            }
+        };
+        let (i, j) = cell_index(first.0, first.1);
+        grid[j * nx + i] = Some(0);
+        samples.push(first);
+        active.push(0);
+        while !active.is_empty() {
+            let active_idx = rng.random_range(0..active.len());
+            let (px, py) = samples[active[active_idx]];
+            let mut found = false;
+            for _ in 0..NR_OF_CANDIDATES {
+                // uniform sampling of the annulus r..2r
+                let radius = r * rng.random_range(1.0_f64..4.0).sqrt();
+                let (sin, cos) = rng.random_range(0.0..2.0 * PI).sin_cos();
+                let (x, y) = (radius.mul_add(cos, px), radius.mul_add(sin, py));
+                if !self.shape.contains(x, y) {
+                    continue;
+                }
+                let (i, j) = cell_index(x, y);
+                let mut too_close = false;
+                'neighbors: for nj in j.saturating_sub(2)..=(j + 2).min(ny - 1) {
+                    for ni in i.saturating_sub(2)..=(i + 2).min(nx - 1) {
+                        if let Some(idx) = grid[nj * nx + ni] {
+                            let (qx, qy) = samples[idx];
+                            if (x - qx).hypot(y - qy) < r {
+                                too_close = true;
+                                break 'neighbors;
+                            }
+                        }
+                    }
+                }
+                if !too_close {
+                    grid[j * nx + i] = Some(samples.len());
+                    active.push(samples.len());
+                    samples.push((x, y));
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                active.swap_remove(active_idx);
+            }
+        }
+        samples
+            .into_iter()
+            .map(|(x, y)| {
+                point![
+                    Length::new::<uom::si::length::meter>(x),
+                    Length::new::<uom::si::length::meter>(y),
+                    Length::zero()
+                ]
+            })
+            .collect()
+    }
+}
+impl PositionDistribution for PoissonDisk {
+    fn generate(&self) -> Vec<Point3<Length>> {
+        self.generate_with_rng(&mut rand::rng())
+    }
+    fn generate_seeded(&self, seed: u64) -> Vec<Point3<Length>> {
+        self.generate_with_rng(&mut StdRng::seed_from_u64(seed))
+    }
+}
+impl From<PoissonDisk> for super::PosDistType {
+    fn from(dist: PoissonDisk) -> Self {
+        Self::PoissonDisk(dist)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::millimeter;
+    use uom::si::length::millimeter;
+
+    fn min_distance(points: &[Point3<Length>]) -> Length {
+        let mut min = Length::new::<millimeter>(f64::INFINITY);
+        for (i, p) in points.iter().enumerate() {
+            for q in &points[i + 1..] {
+                let d = ((p.x - q.x) * (p.x - q.x) + (p.y - q.y) * (p.y - q.y)).sqrt();
+                if d < min {
+                    min = d;
+                }
+            }
+        }
+        min
+    }
+    #[test]
+    fn new_wrong() {
+        let disk = PoissonDiskShape::Disk(millimeter!(1.0));
+        assert!(
+            PoissonDisk::new(PoissonDiskShape::Disk(millimeter!(0.0)), millimeter!(0.1)).is_err()
+        );
+        assert!(
+            PoissonDisk::new(PoissonDiskShape::Disk(millimeter!(-1.0)), millimeter!(0.1)).is_err()
+        );
+        assert!(
+            PoissonDisk::new(
+                PoissonDiskShape::Rectangle(millimeter!(1.0), millimeter!(f64::INFINITY)),
+                millimeter!(0.1)
+            )
+            .is_err()
+        );
+        assert!(
+            PoissonDisk::new(
+                PoissonDiskShape::Rectangle(millimeter!(0.0), millimeter!(1.0)),
+                millimeter!(0.1)
+            )
+            .is_err()
+        );
+        assert!(PoissonDisk::new(disk.clone(), millimeter!(0.0)).is_err());
+        assert!(PoissonDisk::new(disk.clone(), millimeter!(f64::NAN)).is_err());
+        assert!(PoissonDisk::with_nr_of_points(disk, 0).is_err());
+    }
+    #[test]
+    fn new() {
+        let dist =
+            PoissonDisk::new(PoissonDiskShape::Disk(millimeter!(1.0)), millimeter!(0.1)).unwrap();
+        assert_eq!(dist.shape(), &PoissonDiskShape::Disk(millimeter!(1.0)));
+        assert_eq!(dist.min_distance(), millimeter!(0.1));
+    }
+    #[test]
+    fn generate_disk() {
+        let dist =
+            PoissonDisk::new(PoissonDiskShape::Disk(millimeter!(1.0)), millimeter!(0.1)).unwrap();
+        let points = dist.generate_seeded(42);
+        assert!(points.len() > 100);
+        assert!(
+            points
+                .iter()
+                .all(|p| (p.x * p.x + p.y * p.y).sqrt() <= millimeter!(1.0))
+        );
+        assert!(min_distance(&points) >= millimeter!(0.1));
+        assert!(points.iter().all(|p| p.z.is_zero()));
+    }
+    #[test]
+    fn generate_rectangle() {
+        let dist = PoissonDisk::new(
+            PoissonDiskShape::Rectangle(millimeter!(2.0), millimeter!(1.0)),
+            millimeter!(0.1),
+        )
+        .unwrap();
+        let points = dist.generate();
+        assert!(
+            points
+                .iter()
+                .all(|p| p.x.abs() <= millimeter!(1.0) && p.y.abs() <= millimeter!(0.5))
+        );
+        assert!(min_distance(&points) >= millimeter!(0.1));
+        // the rectangle is covered completely
+        assert!(
+            points
+                .iter()
+                .any(|p| p.x > millimeter!(0.8) && p.y > millimeter!(0.3))
+        );
+        assert!(
+            points
+                .iter()
+                .any(|p| p.x < millimeter!(-0.8) && p.y < millimeter!(-0.3))
+        );
+    }
+    #[test]
+    fn generate_seeded() {
+        let dist =
+            PoissonDisk::new(PoissonDiskShape::Disk(millimeter!(1.0)), millimeter!(0.2)).unwrap();
+        let points = dist.generate_seeded(42);
+        assert_eq!(points, dist.generate_seeded(42));
+        assert_ne!(points, dist.generate_seeded(43));
+    }
+    #[test]
+    fn with_nr_of_points() {
+        for shape in [
+            PoissonDiskShape::Disk(millimeter!(1.0)),
+            PoissonDiskShape::Rectangle(millimeter!(3.0), millimeter!(1.0)),
+        ] {
+            for nr_of_points in [100, 1000] {
+                let dist = PoissonDisk::with_nr_of_points(shape.clone(), nr_of_points).unwrap();
+                let nr_of_generated = usize_to_f64(dist.generate_seeded(1).len());
+                let ratio = nr_of_generated / usize_to_f64(nr_of_points);
+                assert!((0.8..1.2).contains(&ratio), "ratio {ratio}");
+            }
+        }
+    }
+}
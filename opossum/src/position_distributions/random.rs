@@ -4,15 +4,18 @@ use super::PositionDistribution;
 use crate::error::{OpmResult, OpossumError};
 use nalgebra::{Point3, point};
 use num::Zero;
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
 use uom::si::f64::Length;
 
 /// Rectangular, uniform random distribution
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct Random {
     nr_of_points: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_x: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_y: Length,
 }
 impl Random {
@@ -53,11 +56,8 @@ impl Random {
             side_length_y,
         })
     }
-}
-impl PositionDistribution for Random {
-    fn generate(&self) -> Vec<nalgebra::Point3<Length>> {
+    fn generate_with_rng<R: Rng>(&self, rng: &mut R) -> Vec<Point3<Length>> {
         let mut points: Vec<Point3<Length>> = Vec::with_capacity(self.nr_of_points);
-        let mut rng = rand::rng();
         for _ in 0..self.nr_of_points {
             let point_x = self.side_length_x * rng.random_range(-1.0..1.0);
             let point_y = self.side_length_y * rng.random_range(-1.0..1.0);
@@ -66,6 +66,14 @@ impl PositionDistribution for Random {
         points
     }
 }
+impl PositionDistribution for Random {
+    fn generate(&self) -> Vec<nalgebra::Point3<Length>> {
+        self.generate_with_rng(&mut rand::rng())
+    }
+    fn generate_seeded(&self, seed: u64) -> Vec<Point3<Length>> {
+        self.generate_with_rng(&mut StdRng::seed_from_u64(seed))
+    }
+}
 impl From<Random> for super::PosDistType {
     fn from(random: Random) -> Self {
         Self::Random(random)
@@ -92,4 +100,12 @@ mod test {
         let strategy = Random::new(millimeter!(1.0), millimeter!(1.0), 10).unwrap();
         assert_eq!(strategy.generate().len(), 10);
     }
+    #[test]
+    fn generate_seeded() {
+        let strategy = Random::new(millimeter!(1.0), millimeter!(1.0), 10).unwrap();
+        let points = strategy.generate_seeded(42);
+        assert_eq!(points.len(), 10);
+        assert_eq!(points, strategy.generate_seeded(42));
+        assert_ne!(points, strategy.generate_seeded(43));
+    }
 }
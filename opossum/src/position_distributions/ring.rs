@@ -0,0 +1,175 @@
+//! Ring (annulus) distributions
+use crate::error::{OpmResult, OpossumError};
+
+use super::PositionDistribution;
+use crate::utils::usize_to_f64;
+use nalgebra::{Point3, point};
+use num::Zero;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use uom::si::f64::Length;
+
+fn check_radius(radius: Length) -> OpmResult<()> {
+    if !radius.is_normal() || radius.is_sign_negative() {
+        return Err(OpossumError::Other(
+            "radius must be > zero and finite".into(),
+        ));
+    }
+    Ok(())
+}
+fn check_nr_of_points(nr_of_points: usize) -> OpmResult<()> {
+    if nr_of_points.is_zero() {
+        return Err(OpossumError::Other("nr_of_points must be >= 1".into()));
+    }
+    Ok(())
+}
+fn ring_points(radius: Length, nr_of_points: usize) -> impl Iterator<Item = Point3<Length>> {
+    let angle_step = 2.0 * PI / usize_to_f64(nr_of_points);
+    (0..nr_of_points).map(move |point_nr| {
+        let (sin, cos) = (usize_to_f64(point_nr) * angle_step).sin_cos();
+        point![radius * cos, radius * sin, Length::zero()]
+    })
+}
+
+/// Points evenly distributed on a circle
+///
+/// The first point is located on the positive x axis. In contrast to [`Hexapolar`](super::Hexapolar), no points are
+/// generated inside the circle. Hence this distribution is useful if only edge rays matter (e.g. for checking
+/// clipping at an aperture).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct Ring {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius: Length,
+    nr_of_points: usize,
+}
+impl Ring {
+    /// Create a new [`Ring`] distribution generator.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///  - the given `radius` is not positive or not finite.
+    ///  - `nr_of_points` is zero.
+    pub fn new(radius: Length, nr_of_points: usize) -> OpmResult<Self> {
+        check_radius(radius)?;
+        check_nr_of_points(nr_of_points)?;
+        Ok(Self {
+            radius,
+            nr_of_points,
+        })
+    }
+}
+impl PositionDistribution for Ring {
+    fn generate(&self) -> Vec<Point3<Length>> {
+        ring_points(self.radius, self.nr_of_points).collect()
+    }
+}
+impl From<Ring> for super::PosDistType {
+    fn from(dist: Ring) -> Self {
+        Self::Ring(dist)
+    }
+}
+
+/// Points evenly distributed on several concentric circles
+///
+/// Each ring contains the same number of points.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct MultiRing {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<f64>"))]
+    radii: Vec<Length>,
+    nr_of_points: usize,
+}
+impl MultiRing {
+    /// Create a new [`MultiRing`] distribution generator with `nr_of_points` points on each ring.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///  - `radii` is empty.
+    ///  - one of the given radii is not positive or not finite.
+    ///  - `nr_of_points` is zero.
+    pub fn new(radii: Vec<Length>, nr_of_points: usize) -> OpmResult<Self> {
+        if radii.is_empty() {
+            return Err(OpossumError::Other("at least one radius required".into()));
+        }
+        for radius in &radii {
+            check_radius(*radius)?;
+        }
+        check_nr_of_points(nr_of_points)?;
+        Ok(Self {
+            radii,
+            nr_of_points,
+        })
+    }
+}
+impl PositionDistribution for MultiRing {
+    fn generate(&self) -> Vec<Point3<Length>> {
+        self.radii
+            .iter()
+            .flat_map(|radius| ring_points(*radius, self.nr_of_points))
+            .collect()
+    }
+}
+impl From<MultiRing> for super::PosDistType {
+    fn from(dist: MultiRing) -> Self {
+        Self::MultiRing(dist)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{millimeter, position_distributions::PosDistType};
+    use approx::assert_abs_diff_eq;
+    #[test]
+    fn new_wrong() {
+        assert!(Ring::new(millimeter!(0.0), 1).is_err());
+        assert!(Ring::new(millimeter!(-0.1), 1).is_err());
+        assert!(Ring::new(millimeter!(f64::NAN), 1).is_err());
+        assert!(Ring::new(millimeter!(f64::INFINITY), 1).is_err());
+        assert!(Ring::new(millimeter!(1.0), 0).is_err());
+        assert!(MultiRing::new(vec![], 1).is_err());
+        assert!(MultiRing::new(vec![millimeter!(1.0), millimeter!(-1.0)], 1).is_err());
+        assert!(MultiRing::new(vec![millimeter!(1.0)], 0).is_err());
+    }
+    #[test]
+    fn generate() {
+        let points = Ring::new(millimeter!(2.0), 4).unwrap().generate();
+        assert_eq!(points.len(), 4);
+        for (point, (x, y)) in points
+            .iter()
+            .zip([(2.0, 0.0), (0.0, 2.0), (-2.0, 0.0), (0.0, -2.0)])
+        {
+            assert_abs_diff_eq!(point.x.value, millimeter!(x).value, epsilon = 1e-15);
+            assert_abs_diff_eq!(point.y.value, millimeter!(y).value, epsilon = 1e-15);
+            assert!(point.z.is_zero());
+        }
+    }
+    #[test]
+    fn generate_multi_ring() {
+        let points = MultiRing::new(vec![millimeter!(1.0), millimeter!(2.0)], 6)
+            .unwrap()
+            .generate();
+        assert_eq!(points.len(), 12);
+        for point in &points[..6] {
+            assert_abs_diff_eq!(point.x.hypot(point.y).value, millimeter!(1.0).value);
+        }
+        for point in &points[6..] {
+            assert_abs_diff_eq!(point.x.hypot(point.y).value, millimeter!(2.0).value);
+        }
+    }
+    #[test]
+    fn serialize() {
+        let dist: PosDistType = MultiRing::new(vec![millimeter!(1.0), millimeter!(2.0)], 6)
+            .unwrap()
+            .into();
+        let serialized = ron::to_string(&dist).unwrap();
+        let deserialized: PosDistType = ron::from_str(&serialized).unwrap();
+        assert_eq!(dist, deserialized);
+        let dist: PosDistType = Ring::new(millimeter!(1.0), 8).unwrap().into();
+        let serialized = ron::to_string(&dist).unwrap();
+        let deserialized: PosDistType = ron::from_str(&serialized).unwrap();
+        assert_eq!(dist, deserialized);
+    }
+}
@@ -1,4 +1,6 @@
-//! Rectangluar, low-discrepancy quasirandom distribution
+//! Rectangluar and elliptical, low-discrepancy quasirandom distributions
+use std::f64::consts::PI;
+
 use super::PositionDistribution;
 use crate::error::{OpmResult, OpossumError};
 use nalgebra::{Point3, point};
@@ -11,9 +13,12 @@ use uom::si::f64::Length;
 ///
 /// For further details see [here](https://en.wikipedia.org/wiki/Sobol_sequence)
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct SobolDist {
     nr_of_points: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_x: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     side_length_y: Length,
 }
 
@@ -75,6 +80,79 @@ impl From<SobolDist> for super::PosDistType {
         Self::Sobol(f)
     }
 }
+/// Elliptical, low-discrepancy quasirandom distribution
+///
+/// The points of a two-dimensional Sobol sequence are mapped onto an ellipse using an area-preserving transformation.
+/// Compared to a uniform random distribution, this leads to a faster convergence of fluence estimations while, in
+/// contrast to regular grids, avoiding sampling artifacts.
+///
+/// For further details see [here](https://en.wikipedia.org/wiki/Sobol_sequence)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct SobolEllipse {
+    nr_of_points: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius_x: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    radius_y: Length,
+}
+impl SobolEllipse {
+    /// Create a new [`SobolEllipse`] distribution generator.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - both radii are zero.
+    ///   - one radius is negative or not finite
+    ///   - `nr_of_points` is zero.
+    pub fn new(radius_x: Length, radius_y: Length, nr_of_points: usize) -> OpmResult<Self> {
+        if radius_x.is_zero() && radius_y.is_zero() {
+            return Err(OpossumError::Other(
+                "At least one radius must be != zero".into(),
+            ));
+        }
+        if radius_x.is_sign_negative() || !radius_x.is_finite() {
+            return Err(OpossumError::Other(
+                "radius_x must be >= zero and finite".into(),
+            ));
+        }
+        if radius_y.is_sign_negative() || !radius_y.is_finite() {
+            return Err(OpossumError::Other(
+                "radius_y must be >= zero and finite".into(),
+            ));
+        }
+        if nr_of_points.is_zero() {
+            return Err(OpossumError::Other("nr_of_points must be >= 1.".into()));
+        }
+        Ok(Self {
+            nr_of_points,
+            radius_x,
+            radius_y,
+        })
+    }
+}
+impl PositionDistribution for SobolEllipse {
+    fn generate(&self) -> Vec<nalgebra::Point3<Length>> {
+        let mut points: Vec<Point3<Length>> = Vec::with_capacity(self.nr_of_points);
+        let params = JoeKuoD6::minimal();
+        let seq = Sobol::<f64>::new(2, &params);
+        for point in seq.take(self.nr_of_points) {
+            let radius = point[0].sqrt();
+            let (sin, cos) = (2.0 * PI * point[1]).sin_cos();
+            points.push(point!(
+                self.radius_x * radius * cos,
+                self.radius_y * radius * sin,
+                Length::zero()
+            ));
+        }
+        points
+    }
+}
+impl From<SobolEllipse> for super::PosDistType {
+    fn from(f: SobolEllipse) -> Self {
+        Self::SobolEllipse(f)
+    }
+}
 #[cfg(test)]
 mod test {
     use super::*;
@@ -96,4 +174,36 @@ mod test {
         let strategy = SobolDist::new(millimeter!(1.0), millimeter!(1.0), 10).unwrap();
         assert_eq!(strategy.generate().len(), 10);
     }
+    #[test]
+    fn new_ellipse_wrong() {
+        assert!(SobolEllipse::new(Length::zero(), Length::zero(), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(-0.1), millimeter!(1.0), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(f64::NAN), millimeter!(1.0), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(f64::INFINITY), millimeter!(1.0), 1).is_err());
+
+        assert!(SobolEllipse::new(millimeter!(1.0), millimeter!(-0.1), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(1.0), millimeter!(f64::NAN), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(1.0), millimeter!(f64::INFINITY), 1).is_err());
+        assert!(SobolEllipse::new(millimeter!(1.0), millimeter!(1.0), 0).is_err());
+    }
+    #[test]
+    fn generate_ellipse() {
+        let strategy = SobolEllipse::new(millimeter!(1.0), millimeter!(2.0), 256).unwrap();
+        let points = strategy.generate();
+        assert_eq!(points.len(), 256);
+        for p in &points {
+            let r_sq =
+                (p.x / millimeter!(1.0)).value.powi(2) + (p.y / millimeter!(2.0)).value.powi(2);
+            assert!(r_sq <= 1.0 + f64::EPSILON);
+        }
+        // area-preserving mapping: half of the points lie within the inner ellipse of half the area
+        let inner = points
+            .iter()
+            .filter(|p| {
+                (p.x / millimeter!(1.0)).value.powi(2) + (p.y / millimeter!(2.0)).value.powi(2)
+                    < 0.5
+            })
+            .count();
+        assert_eq!(inner, 128);
+    }
 }
@@ -7,6 +7,7 @@ pub use property::Property;
 pub use proptype::Proptype;
 
 use crate::error::{OpmResult, OpossumError};
+use property::LegacyProperty;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Debug;
@@ -27,10 +28,26 @@ use crate::reporting::html_report::HtmlProperty;
 /// props.set("my float", 2.71.into()).unwrap();
 /// ```
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct Properties {
     props: BTreeMap<String, Property>,
 }
+/// Layout of [`Properties`] in `.opm` files of version `0` (see [`LegacyProperty`]).
+#[derive(Default, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct LegacyProperties(BTreeMap<String, LegacyProperty>);
+impl From<LegacyProperties> for Properties {
+    fn from(legacy: LegacyProperties) -> Self {
+        Self {
+            props: legacy
+                .0
+                .into_iter()
+                .map(|(name, prop)| (name, prop.into()))
+                .collect(),
+        }
+    }
+}
 impl Properties {
     /// Create a new property with the given name.
     ///
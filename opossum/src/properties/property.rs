@@ -4,7 +4,8 @@ use crate::{
     plottable::Plottable,
 };
 use nalgebra::vector;
-use serde::{Deserialize, Serialize};
+use ron::value::RawValue;
+use serde::{Deserialize, Deserializer, Serialize, de};
 use std::{mem, path::Path};
 
 /// (optical) Property
@@ -12,12 +13,39 @@ use std::{mem, path::Path};
 /// A property consists of the actual value (stored as [`Proptype`]), a description and optionally a list of value conditions
 /// (such as `GreaterThan`, `NonEmptyString`, etc.)
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct Property {
     prop: Proptype,
     #[serde(skip)]
     description: String,
 }
+/// Layout of a [`Property`] in `.opm` files of version `0`.
+///
+/// Depending on the version of OPOSSUM that wrote the file, a property was either stored as its plain value (as it is
+/// today) or as a struct containing the value (`prop`) as well as the (now ignored) `description` and `validator`
+/// fields. The layout is determined from the raw value.
+pub(crate) struct LegacyProperty(Proptype);
+impl<'de> Deserialize<'de> for LegacyProperty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct PropertyStruct {
+            prop: Proptype,
+        }
+        let raw = Box::<RawValue>::deserialize(deserializer)?.trim_boxed();
+        let prop = if raw.get_ron().starts_with('(') {
+            raw.into_rust::<PropertyStruct>().map(|p| p.prop)
+        } else {
+            raw.into_rust::<Proptype>()
+        };
+        prop.map(Self).map_err(de::Error::custom)
+    }
+}
+impl From<LegacyProperty> for Property {
+    fn from(legacy: LegacyProperty) -> Self {
+        Self::new(legacy.0, String::new())
+    }
+}
 impl Property {
     #[must_use]
     pub const fn new(prop: Proptype, description: String) -> Self {
@@ -57,19 +85,32 @@ impl Property {
             Proptype::SpotDiagram(spot_diagram) => {
                 let file_path = report_path.join(Path::new(&format!("{id}.svg")));
                 spot_diagram.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+                spot_diagram.to_csv(&report_path.join(Path::new(&format!("{id}.csv"))))?;
             }
             Proptype::FluenceData(fluence) => {
                 let file_path = report_path.join(Path::new(&format!("{id}.png")));
                 fluence.to_plot(&file_path, crate::plottable::PltBackEnd::Bitmap)?;
+                fluence.to_csv(&report_path.join(Path::new(&format!("{id}.csv"))))?;
             }
             Proptype::Spectrometer(spectrometer) => {
                 let file_path = report_path.join(Path::new(&format!("{id}.svg")));
                 spectrometer.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+                spectrometer.to_csv(&report_path.join(Path::new(&format!("{id}.csv"))))?;
+            }
+            Proptype::EnergySpectrum(spectrum) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                spectrum.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+                spectrum.to_energy_csv(&report_path.join(Path::new(&format!("{id}.csv"))))?;
             }
             Proptype::RayPositionHistory(ray_hist) => {
                 let file_path = report_path.join(Path::new(&format!("{id}.svg")));
                 ray_hist.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
             }
+            Proptype::RayPolylines(polylines) => {
+                polylines.to_csv(&report_path.join(Path::new(&format!("{id}.csv"))))?;
+                polylines.to_json(&report_path.join(Path::new(&format!("{id}.json"))))?;
+                polylines.to_gltf(&report_path.join(Path::new(&format!("{id}.glb"))))?;
+            }
             Proptype::GhostFocusHistory(ghost_hist) => {
                 let file_path = report_path.join(Path::new(&format!("{id}.svg")));
                 let mut ghost_hist = ghost_hist.clone();
@@ -85,6 +126,26 @@ impl Property {
                 let file_path = report_path.join(Path::new(&format!("{id}.svg")));
                 hit_map.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
             }
+            Proptype::BounceEnergyDecay(decay) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                decay.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+            }
+            Proptype::SystemTransmission(transmission) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                transmission.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+            }
+            Proptype::GaussianBeamProfile(profile) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                profile.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+            }
+            Proptype::BeamCaustic(caustic) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                caustic.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+            }
+            Proptype::WavelengthSweep(sweep) => {
+                let file_path = report_path.join(Path::new(&format!("{id}.svg")));
+                sweep.to_plot(&file_path, crate::plottable::PltBackEnd::SVG)?;
+            }
             Proptype::NodeReport(report) => {
                 for prop in report.properties() {
                     prop.1
@@ -125,4 +186,23 @@ mod test {
         assert!(prop.set_value(Proptype::Bool(false)).is_ok());
         assert!(prop.set_value(Proptype::F64(3.14)).is_err());
     }
+    #[test]
+    fn deserialize() {
+        let prop: Property = ron::from_str("Bool(true)").unwrap();
+        assert!(matches!(prop.prop(), Proptype::Bool(true)));
+        let legacy = "(prop: F64(1.5), description: \"legacy property\", validator: None)";
+        assert!(ron::from_str::<Property>(legacy).is_err());
+    }
+    #[test]
+    fn deserialize_legacy() {
+        let legacy = "(prop: F64(1.5), description: \"legacy property\", validator: None)";
+        let prop: Property = ron::from_str::<LegacyProperty>(legacy).unwrap().into();
+        assert!(matches!(prop.prop(), Proptype::F64(v) if *v == 1.5));
+        assert!(prop.description().is_empty());
+        let prop: Property = ron::from_str::<LegacyProperty>(" Bool(true)")
+            .unwrap()
+            .into();
+        assert!(matches!(prop.prop(), Proptype::Bool(true)));
+        assert!(ron::from_str::<LegacyProperty>("(description: \"no value\")").is_err());
+    }
 }
@@ -3,20 +3,31 @@
 use std::cell::RefCell;
 
 use crate::{
-    analyzers::ghostfocus::GhostFocusHistory,
+    analyzers::{
+        caustic::BeamCaustic, gaussian_beam::GaussianBeamProfile, ghostfocus::GhostFocusHistory,
+        system_transmission::SystemTransmission, wavelength_sweep::WavelengthSweepResult,
+    },
     aperture::Aperture,
     error::{OpmResult, OpossumError},
+    kde::KdeKernel,
     lightdata::{LightData, light_data_builder::LightDataBuilder},
     nodes::{
         FilterType, Metertype, Spectrometer, SpectrometerType, SpotDiagram, WaveFrontData,
         fluence_detector::{Fluence, fluence_data::FluenceData},
-        ray_propagation_visualizer::RayPositionHistories,
+        ray_propagation_visualizer::{RayPolylines, RayPositionHistories},
         reflective_grating::LinearDensity,
+        waveplate::Retardance,
     },
     ray::SplittingConfig,
     refractive_index::RefractiveIndexType,
     reporting::{html_report::HtmlNodeReport, node_report::NodeReport},
-    surface::hit_map::{HitMap, fluence_estimator::FluenceEstimator},
+    spectrum::Spectrum,
+    surface::{
+        AsphericProfile,
+        hit_map::{
+            HitMap, bounce_energy_decay::BounceEnergyDecay, fluence_estimator::FluenceEstimator,
+        },
+    },
     utils::{
         geom_transformation::Isometry,
         unit_format::{get_exponent_for_base_unit_in_e3_steps, get_prefix_for_base_unit},
@@ -55,6 +66,7 @@ thread_local! {
 
 #[non_exhaustive]
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// The type of the [`Property`](crate::properties::Property).
 pub enum Proptype {
     /// A string property
@@ -66,6 +78,7 @@ pub enum Proptype {
     /// A boolean property
     Bool(bool),
     /// An optional [`LightData`] property
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     LightData(Option<LightData>),
     /// Property for storing a [`FilterType`] of an [`IdealFilter`](crate::nodes::IdealFilter) node.
     FilterType(FilterType),
@@ -80,48 +93,94 @@ pub enum Proptype {
     /// A property for storing an optical [`Aperture`].
     Aperture(Aperture),
     /// A property for storing a [`Spectrum`](crate::spectrum::Spectrum).
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     Spectrometer(Spectrometer),
+    /// The energy spectrum recorded by an [`EnergyMeter`](crate::nodes::EnergyMeter)
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    EnergySpectrum(Spectrum),
     /// This property stores optical [`Rays`](crate::rays::Rays)
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     SpotDiagram(SpotDiagram),
     /// This property stores the fluence information [`FluenceData`]
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     FluenceData(FluenceData),
     /// This property stores the fluence estimator strategy [`FluenceEstimator`]
     FluenceEstimator(FluenceEstimator),
+    /// This property stores the kernel shape [`KdeKernel`] of the kernel density estimator
+    KdeKernel(KdeKernel),
     /// This property stores the wavefront Information [`WaveFrontData`]
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     WaveFrontData(WaveFrontData),
     /// This property stores the ray position history of all [`Rays`](crate::rays::Rays) during propagation through the optic scenery
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     RayPositionHistory(RayPositionHistories),
+    /// This property stores the paths of individual [`Rays`](crate::rays::Rays) as polylines for export
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    RayPolylines(RayPolylines),
     /// This property stores the ray position history of all [`Rays`](crate::rays::Rays), separated by their bounce level,
     /// during propagation through the optic scenery
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     GhostFocusHistory(GhostFocusHistory),
     /// A (nested set) of Properties
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     NodeReport(NodeReport),
     /// linear density in `1/length_unit`
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     LinearDensity(LinearDensity),
     /// Fluence in Units of J/cm²
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     Fluence(Fluence),
     /// Unit of Wavelength
-    WfLambda(f64, Length),
+    WfLambda(
+        f64,
+        #[cfg_attr(feature = "json_schema", schemars(with = "f64"))] Length,
+    ),
     /// a geometrical length
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     Length(Length),
     /// an optional length parameter. used, e.g., for the alignment wavelength of the source
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
     LengthOption(Option<Length>),
     /// an energy value
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     Energy(Energy),
     /// a (2D) geometric angle (e.g. component tilt)
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     Angle(Angle),
     /// an optical refractive index model
     RefractiveIndex(RefractiveIndexType),
     /// a (node) location / orientation
     Isometry(Option<Isometry>),
     /// Three dimensional Vector
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 3]"))]
     Vec3(Vector3<f64>),
     /// a hit map (position fo rays hitting a given surface)
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
     HitMap(HitMap),
     /// 2-dimenstional vector
+    #[cfg_attr(feature = "json_schema", schemars(with = "[f64; 2]"))]
     Vec2(Vector2<f64>),
     /// [`LightData`] build configuration
     LightDataBuilder(Option<LightDataBuilder>),
+    /// energy hitting a surface per bounce level
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    BounceEnergyDecay(BounceEnergyDecay),
+    /// spectral transmission of an optical system
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    SystemTransmission(SystemTransmission),
+    /// Gaussian beam radius along the optical axis
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    GaussianBeamProfile(GaussianBeamProfile),
+    /// beam caustic of a ray tracing analysis
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    BeamCaustic(BeamCaustic),
+    /// detected energy vs. source wavelength
+    #[cfg_attr(feature = "json_schema", schemars(with = "serde_json::Value"))]
+    WavelengthSweep(WavelengthSweepResult),
+    /// retardance of a [`Waveplate`](crate::nodes::Waveplate)
+    Retardance(Retardance),
+    /// aspheric profile (if any) of a lens surface
+    AsphericProfile(Option<AsphericProfile>),
 }
 impl Proptype {
     /// Generate a html representation of a Proptype.
@@ -134,68 +193,117 @@ impl Proptype {
     pub fn to_html(&self, id: &str, property_name: &str) -> OpmResult<String> {
         THREAD_TEMPLATES.with(|template_refcell| {
             let template_engine = template_refcell.borrow();
-            let string_value = match self {
-                Self::String(value) => template_engine.render("simple", value),
-                Self::I32(value) => template_engine.render("simple", &format!("{value}")),
-                Self::F64(value) => template_engine.render("simple", &format!("{value:.6}")),
-                Self::Bool(value) => template_engine.render("simple", &format!("{value}")),
-                Self::SpectrometerType(value) => {
-                    template_engine.render("simple", &value.to_string())
-                }
-                Self::Metertype(value) => template_engine.render("simple", &value.to_string()),
-                Self::Spectrometer(_)
-                | Self::SpotDiagram(_)
-                | Self::HitMap(_)
-                | Self::RayPositionHistory(_)
-                | Self::GhostFocusHistory(_) => {
-                    template_engine.render("image", &format!("data/{id}_{property_name}.svg"))
-                }
-                Self::WaveFrontData(_) | Self::FluenceData(_) => {
-                    template_engine.render("image", &format!("data/{id}_{property_name}.png"))
-                }
-                Self::NodeReport(report) => {
-                    let html_node_report = HtmlNodeReport {
-                        node_name: report.name().into(),
-                        node_type: report.node_type().into(),
-                        props: report.properties().html_props(&format!(
-                            "{id}_{}_{}",
-                            report.name(),
-                            report.uuid()
-                        )),
-                        uuid: report.uuid().to_string(),
-                        show_item: report.show_item(),
-                    };
-                    template_engine.render("group", &html_node_report)
-                }
-                Self::Fluence(value) => template_engine.render(
-                    "simple",
-                    &format!(
-                        "{}{}",
-                        format_value_with_prefix(value.get::<joule_per_square_centimeter>()),
-                        joule_per_square_centimeter::abbreviation()
-                    ),
-                ),
-                Self::WfLambda(value, wvl) => template_engine.render(
-                    "simple",
-                    &format!(
-                        "{}λ, (λ = {})",
-                        format_value_with_prefix(*value,),
-                        format_quantity(meter, *wvl)
+            let string_value = if let Some(value) = self.text_value() {
+                template_engine.render("simple", &value)
+            } else if let Some(file_name) = self.data_file_name(id, property_name) {
+                template_engine.render("image", &file_name)
+            } else {
+                match self {
+                    Self::RayPolylines(polylines) => template_engine.render(
+                        "simple",
+                        &format!(
+                            "{} rays (data/{id}_{property_name}.csv, data/{id}_{property_name}.json, data/{id}_{property_name}.glb)",
+                            polylines.len()
+                        ),
                     ),
-                ),
-                Self::Length(value) => {
-                    template_engine.render("simple", &format_quantity(meter, *value))
+                    Self::NodeReport(report) => {
+                        let html_node_report = HtmlNodeReport {
+                            node_name: report.name().into(),
+                            node_type: report.node_type().into(),
+                            props: report.properties().html_props(&format!(
+                                "{id}_{}_{}",
+                                report.name(),
+                                report.uuid()
+                            )),
+                            uuid: report.uuid().to_string(),
+                            show_item: report.show_item(),
+                        };
+                        template_engine.render("group", &html_node_report)
+                    }
+                    _ => Err(tinytemplate::error::Error::GenericError {
+                        msg: "proptype not supported".into(),
+                    }),
                 }
-                Self::Energy(value) => {
-                    template_engine.render("simple", &format_quantity(joule, *value))
-                }
-                _ => Err(tinytemplate::error::Error::GenericError {
-                    msg: "proptype not supported".into(),
-                }),
             };
             string_value.map_err(|e| OpossumError::Other(format!("Template rendering error: {e}")))
         })
     }
+    /// Generate a Markdown representation of a Proptype.
+    ///
+    /// Plots are represented by (relative) image links to the exported data files. Nested [`NodeReport`]s are not
+    /// supported by this function since they cannot be represented in a single table cell.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property value could not be converted to Markdown.
+    pub fn to_markdown(&self, id: &str, property_name: &str) -> OpmResult<String> {
+        if let Some(value) = self.text_value() {
+            Ok(value.trim().replace('|', "\\|"))
+        } else if let Some(file_name) = self.data_file_name(id, property_name) {
+            Ok(format!(
+                "![{property_name}]({})",
+                file_name.replace(' ', "%20")
+            ))
+        } else if let Self::RayPolylines(polylines) = self {
+            Ok(format!(
+                "{} rays (data/{id}_{property_name}.csv, data/{id}_{property_name}.json, data/{id}_{property_name}.glb)",
+                polylines.len()
+            ))
+        } else {
+            Err(OpossumError::Other("proptype not supported".into()))
+        }
+    }
+    /// Returns the textual representation of simple property values or `None` for all other property types.
+    fn text_value(&self) -> Option<String> {
+        match self {
+            Self::String(value) => Some(value.clone()),
+            Self::I32(value) => Some(format!("{value}")),
+            Self::F64(value) => Some(format!("{value:.6}")),
+            Self::Bool(value) => Some(format!("{value}")),
+            Self::SpectrometerType(value) => Some(value.to_string()),
+            Self::Metertype(value) => Some(value.to_string()),
+            Self::KdeKernel(value) => Some(value.to_string()),
+            Self::Retardance(value) => Some(value.to_string()),
+            Self::AsphericProfile(value) => Some(
+                value
+                    .as_ref()
+                    .map_or_else(|| "none".to_string(), ToString::to_string),
+            ),
+            Self::Fluence(value) => Some(format!(
+                "{}{}",
+                format_value_with_prefix(value.get::<joule_per_square_centimeter>()),
+                joule_per_square_centimeter::abbreviation()
+            )),
+            Self::WfLambda(value, wvl) => Some(format!(
+                "{}λ, (λ = {})",
+                format_value_with_prefix(*value,),
+                format_quantity(meter, *wvl)
+            )),
+            Self::Length(value) => Some(format_quantity(meter, *value)),
+            Self::Energy(value) => Some(format_quantity(joule, *value)),
+            _ => None,
+        }
+    }
+    /// Returns the (relative) file name of the exported plot of this property or `None` if no plot is exported.
+    fn data_file_name(&self, id: &str, property_name: &str) -> Option<String> {
+        match self {
+            Self::Spectrometer(_)
+            | Self::EnergySpectrum(_)
+            | Self::SpotDiagram(_)
+            | Self::HitMap(_)
+            | Self::BounceEnergyDecay(_)
+            | Self::SystemTransmission(_)
+            | Self::GaussianBeamProfile(_)
+            | Self::BeamCaustic(_)
+            | Self::WavelengthSweep(_)
+            | Self::RayPositionHistory(_)
+            | Self::GhostFocusHistory(_) => Some(format!("data/{id}_{property_name}.svg")),
+            Self::WaveFrontData(_) | Self::FluenceData(_) => {
+                Some(format!("data/{id}_{property_name}.png"))
+            }
+            _ => None,
+        }
+    }
 }
 impl From<bool> for Proptype {
     fn from(value: bool) -> Self {
@@ -227,6 +335,11 @@ impl From<Uuid> for Proptype {
         Self::Uuid(value)
     }
 }
+impl From<Option<AsphericProfile>> for Proptype {
+    fn from(value: Option<AsphericProfile>) -> Self {
+        Self::AsphericProfile(value)
+    }
+}
 impl From<Length> for Proptype {
     fn from(value: Length) -> Self {
         Self::Length(value)
@@ -4,8 +4,9 @@ use core::f64;
 use std::{f64::consts::PI, fmt::Display};
 
 use approx::relative_ne;
-use nalgebra::{MatrixXx3, Point3, Rotation3, Vector3, vector};
-use num::{ToPrimitive, Zero};
+use nalgebra::{Matrix2, MatrixXx3, Point3, Rotation3, Vector2, Vector3, vector};
+use num::{Complex, ToPrimitive, Zero};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uom::si::{
     energy::joule,
@@ -21,18 +22,22 @@ use crate::{
     nodes::{FilterType, fluence_detector::Fluence},
     properties::Proptype,
     rays::{FluenceRays, Rays},
+    refractive_index::GradientIndex,
     spectrum::Spectrum,
     surface::{
         hit_map::rays_hit_map::{EnergyHitPoint, FluenceHitPoint, HitPoint},
         optic_surface::OpticSurface,
+        roughness::{ScatterSampler, SurfaceRoughness},
     },
     utils::geom_transformation::Isometry,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Configuration for splitting a [`Ray`] into multiple parts.
 ///
-/// This enum defines how a ray is split, either by a fixed ratio or by a wavelength-dependent spectrum.
+/// This enum defines how a ray is split, either by a fixed ratio, by a wavelength-dependent spectrum or by the
+/// polarization state of the ray.
 pub enum SplittingConfig {
     /// Ideal beam splitter with a fixed splitting ratio.
     ///
@@ -43,6 +48,18 @@ pub enum SplittingConfig {
     ///
     /// The [`Spectrum`] must contain values in the range (0.0..=1.0).
     Spectrum(Spectrum),
+    /// Polarizing beam splitter with different energy transmissions for p- and s-polarized light.
+    ///
+    /// The p and s directions refer to the global x and y axes of the ray's Jones vector, respectively. Both values must
+    /// be in the range (0.0..=1.0). An ideal polarizing beam splitter transmits all p-polarized light
+    /// (`p_transmission = 1.0`) and reflects all s-polarized light (`s_transmission = 0.0`). The extinction ratio of the
+    /// transmitted beam is `p_transmission / s_transmission`.
+    Polarizing {
+        /// fraction of the p-polarized energy remaining in the initial beam
+        p_transmission: f64,
+        /// fraction of the s-polarized energy remaining in the initial beam
+        s_transmission: f64,
+    },
 }
 impl SplittingConfig {
     /// Checks the validity of the [`SplittingConfig`].
@@ -53,6 +70,10 @@ impl SplittingConfig {
         match self {
             Self::Ratio(r) => (0.0..=1.0).contains(r),
             Self::Spectrum(s) => s.is_transmission_spectrum(),
+            Self::Polarizing {
+                p_transmission,
+                s_transmission,
+            } => (0.0..=1.0).contains(p_transmission) && (0.0..=1.0).contains(s_transmission),
         }
     }
 }
@@ -61,6 +82,41 @@ impl From<SplittingConfig> for Proptype {
         Self::SplitterType(config)
     }
 }
+/// Reason, why a [`Ray`] has been invalidated (terminated).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TerminationReason {
+    /// The ray was blocked by an aperture.
+    Aperture,
+    /// The ray missed an optical surface.
+    MissedSurface,
+    /// The ray exceeded the maximum number of bounces (reflections).
+    MaxBounces,
+    /// The ray exceeded the maximum number of refractions.
+    MaxRefractions,
+    /// The energy of the ray fell below the energy threshold.
+    EnergyThreshold,
+    /// The ray was diffracted into an unsupported diffraction order.
+    DiffractionOrder,
+    /// The ray was totally reflected at a surface, where this is not intended (e.g. inside a prism).
+    TotalReflection,
+    /// The ray was invalidated for another (unspecified) reason.
+    Other,
+}
+impl Display for TerminationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Aperture => "aperture",
+            Self::MissedSurface => "missed surface",
+            Self::MaxBounces => "max. bounces",
+            Self::MaxRefractions => "max. refractions",
+            Self::EnergyThreshold => "energy threshold",
+            Self::DiffractionOrder => "unsupported diffraction order",
+            Self::TotalReflection => "total reflection",
+            Self::Other => "other",
+        };
+        write!(f, "{msg}")
+    }
+}
 ///Struct that contains all information about an optical ray
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Ray {
@@ -72,7 +128,11 @@ pub struct Ray {
     dir: Vector3<f64>,
     /// Previous propagation direction, used for node positioning.
     prev_dir: Option<Vector3<f64>>,
-    // pol: Vector2<Complex<f64>>, // Polarization vector (Jones vector), currently unused.
+    /// Polarization state of the ray as normalized Jones vector with respect to the global x and y axes.
+    ///
+    /// `None` denotes unpolarized light.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pol: Option<Vector2<Complex<f64>>>,
     /// Energy carried by the ray.
     e: Energy,
     /// Wavelength of the ray
@@ -83,6 +143,9 @@ pub struct Ray {
     number_of_refractions: usize,
     /// Indicates whether the ray is allowed to propagate further.
     valid: bool,
+    /// Reason, why the ray has been invalidated (if any).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    termination_reason: Option<TerminationReason>,
     /// Total optical path length traversed by the ray.
     path_length: Length,
     /// Refractive index of the medium in which the ray is propagating.
@@ -126,7 +189,7 @@ impl Ray {
             pos_hist: Vec::<Point3<Length>>::new(),
             dir: direction.normalize(),
             prev_dir: None,
-            //pol: Vector2::new(Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)), // horizontal polarization
+            pol: None,
             e: energy,
             wvl: wave_length,
             path_length: Length::zero(),
@@ -134,6 +197,7 @@ impl Ray {
             number_of_bounces: 0,
             number_of_refractions: 0,
             valid: true,
+            termination_reason: None,
             helper_rays: None,
             is_helper: false,
         })
@@ -283,6 +347,72 @@ impl Ray {
     pub fn wavelength(&self) -> Length {
         self.wvl
     }
+    /// Returns the polarization state (normalized Jones vector) of this [`Ray`].
+    ///
+    /// The Jones vector refers to the global x and y axes. `None` denotes unpolarized light, which is the default
+    /// for newly created rays.
+    #[must_use]
+    pub const fn polarization(&self) -> Option<Vector2<Complex<f64>>> {
+        self.pol
+    }
+    /// Sets the polarization state (Jones vector) of this [`Ray`].
+    ///
+    /// The given Jones vector is normalized before being stored, since the energy is carried separately by the ray.
+    /// Setting `None` marks the ray as unpolarized.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given Jones vector has zero length or contains non-finite values.
+    pub fn set_polarization(
+        &mut self,
+        jones_vector: Option<Vector2<Complex<f64>>>,
+    ) -> OpmResult<()> {
+        if let Some(jones_vector) = jones_vector {
+            let norm = jones_vector.norm();
+            if !norm.is_normal() {
+                return Err(OpossumError::Other(
+                    "Jones vector must have a finite, non-zero length".into(),
+                ));
+            }
+            self.pol = Some(jones_vector.unscale(norm));
+        } else {
+            self.pol = None;
+        }
+        Ok(())
+    }
+    /// Applies the given Jones matrix to the polarization state of this [`Ray`].
+    ///
+    /// The energy of the ray is attenuated by the transmitted fraction of the intensity. For a polarized ray with Jones
+    /// vector `J` this is `|M J|²`. An unpolarized ray transmits half of the squared Frobenius norm of the matrix `M`.
+    /// Its new polarization state is approximated by the principal eigenvector of `M M†`. If this eigenvector is not
+    /// unique (e.g. for retarders or an identity matrix), the ray stays unpolarized.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the Jones matrix contains non-finite values.
+    pub fn apply_jones_matrix(&mut self, jones_matrix: &Matrix2<Complex<f64>>) -> OpmResult<()> {
+        if jones_matrix
+            .iter()
+            .any(|c| !c.re.is_finite() || !c.im.is_finite())
+        {
+            return Err(OpossumError::Other(
+                "Jones matrix must only contain finite values".into(),
+            ));
+        }
+        let transmission = if let Some(pol) = self.pol {
+            let new_pol = jones_matrix * pol;
+            let norm = new_pol.norm();
+            if norm.is_normal() {
+                self.pol = Some(new_pol.unscale(norm));
+            }
+            norm * norm
+        } else {
+            self.pol = principal_polarization(jones_matrix);
+            jones_matrix.norm_squared() / 2.0
+        };
+        self.e *= transmission;
+        self.change_helper_fluence_by_factor(transmission)
+    }
     /// Adds a position to the position history of the ray.
     ///
     /// This is, for example, necessary for adding the position when the ray may be set invalid at an aperture.
@@ -371,9 +501,11 @@ impl Ray {
         Some(positions)
     }
 
-    /// Returns the path length of this [`Ray`].
+    /// Returns the optical path length of this [`Ray`].
     ///
-    /// Return the geometric path length of the ray.
+    /// The optical path length is accumulated along all propagation steps as the geometric distance multiplied by the
+    /// refractive index of the medium the ray propagates in. Rays without an explicitly set refractive index are assumed
+    /// to propagate in vacuum (n=1).
     #[must_use]
     pub fn path_length(&self) -> Length {
         self.path_length
@@ -425,6 +557,106 @@ impl Ray {
         self.path_length += length * self.refractive_index * self.dir.norm();
         Ok(())
     }
+    /// Propagate this [`Ray`] through a gradient-index (GRIN) medium.
+    ///
+    /// The medium is described by the given [`GradientIndex`] in the local coordinate system given by `isometry`. The
+    /// ray is assumed to be located inside the medium and is propagated until it reaches the plane `z = end_position`
+    /// (in local coordinates). The curved ray path is calculated by integrating the ray equation `d/ds (n dr/ds) = ∇n`
+    /// using the Runge-Kutta scheme of Sharma et al. (Appl. Opt. 21, 984 (1982)) with the given (geometric) step size.
+    /// All intermediate points are added to the position history of the ray. The optical path length is integrated along
+    /// the curved path and the refractive index of the ray is set to the index at the end point.
+    ///
+    /// Invalid rays are not modified.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the step size is not positive and finite or the end position is not finite.
+    ///   - the ray does not propagate towards the end plane or does not reach it.
+    ///   - the refractive index of the medium cannot be evaluated along the ray path (see [`GradientIndex::index_at`]).
+    pub fn propagate_in_gradient_index(
+        &mut self,
+        gradient_index: &GradientIndex,
+        isometry: &Isometry,
+        end_position: Length,
+        step_size: Length,
+    ) -> OpmResult<()> {
+        if !step_size.is_normal() || step_size.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "step size must be positive and finite".into(),
+            ));
+        }
+        if !end_position.is_finite() {
+            return Err(OpossumError::Other("end position must be finite".into()));
+        }
+        if !self.valid() {
+            return Ok(());
+        }
+        let wavelength = self.wavelength();
+        let start = isometry.inverse_transform_point(&self.pos);
+        let mut r = Vector3::new(start.x.value, start.y.value, start.z.value);
+        let z_end = end_position.value;
+        let dir = isometry.inverse_transform_vector_f64(&self.dir).normalize();
+        if (z_end - r.z) * dir.z <= 0.0 {
+            return Err(OpossumError::Other(
+                "ray does not propagate towards the end plane of the gradient-index medium".into(),
+            ));
+        }
+        let index_at =
+            |r: &Vector3<f64>| gradient_index.index_at(wavelength, &meter!(r.x, r.y, r.z));
+        // D(r) = n ∇n
+        let d = |r: &Vector3<f64>| -> OpmResult<Vector3<f64>> {
+            Ok(index_at(r)? * gradient_index.gradient_at(&meter!(r.x, r.y, r.z)))
+        };
+        let mut n = index_at(&r)?;
+        // optical direction vector T = n dr/ds
+        let mut t = n * dir;
+        // parameter step dt = ds / n
+        let dt = step_size.value / n;
+        let mut optical_path = 0.0;
+        // generous limit on the number of steps for rays being strongly bent by the medium
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let max_steps = (10.0 * (z_end - r.z).abs() / step_size.value).ceil() as usize + 1000;
+        let forward = z_end > r.z;
+        let mut history = vec![self.pos];
+        let mut end_reached = false;
+        for _ in 0..max_steps {
+            let a = dt * d(&r)?;
+            let b = dt * d(&(r + (dt / 2.0) * t + (dt / 8.0) * a))?;
+            let c = dt * d(&(r + dt * t + (dt / 2.0) * b))?;
+            let mut r_next = r + dt * (t + (a + 2.0 * b) / 6.0);
+            let mut t_next = t + (a + 4.0 * b + c) / 6.0;
+            let mut fraction = 1.0;
+            if (r_next.z >= z_end) == forward {
+                // interpolate onto the end plane
+                fraction = (z_end - r.z) / (r_next.z - r.z);
+                r_next = r + fraction * (r_next - r);
+                r_next.z = z_end;
+                t_next = t + fraction * (t_next - t);
+                end_reached = true;
+            }
+            let n_next = index_at(&r_next)?;
+            // OPL = ∫ n ds = ∫ n² dt
+            optical_path += 0.5 * n.mul_add(n, n_next * n_next) * fraction * dt;
+            (r, t, n) = (r_next, t_next, n_next);
+            if end_reached {
+                break;
+            }
+            history.push(isometry.transform_point(&meter!(r.x, r.y, r.z)));
+        }
+        if !end_reached {
+            return Err(OpossumError::Other(
+                "ray did not reach the end plane of the gradient-index medium".into(),
+            ));
+        }
+        self.pos_hist.append(&mut history);
+        self.pos = isometry.transform_point(&meter!(r.x, r.y, r.z));
+        self.prev_dir = Some(self.dir);
+        self.dir = isometry.transform_vector_f64(&t.normalize());
+        self.refractive_index = n;
+        self.path_length += meter!(optical_path);
+        Ok(())
+    }
     /// Create an [`Isometry`] from this [`Ray`].
     ///
     /// This function creates an [`Isometry`] with its position based on the ray position and the orientation (rotation) based on the ray direction.
@@ -491,67 +723,116 @@ impl Ray {
                 "the refractive index must be >=1.0 and finite".into(),
             ));
         }
+        if self.diffract(s, grating_vector, diffraction_order, true)? == Some(true) {
+            //currently only reflection
+            let reflected_ray = self.clone();
+            self.e = joule!(0.);
+            Ok(Some(reflected_ray))
+        } else {
+            Ok(None)
+        }
+    }
+    /// Diffract the [`Ray`] in transmission on a periodic surface, e.g., a transmission grating.
+    ///
+    /// In contrast to [`diffract_on_periodic_surface`](Ray::diffract_on_periodic_surface), the diffracted ray
+    /// continues behind the (infinitely thin) surface in the same medium. This [`Ray`] is modified in place. If the
+    /// given diffraction order cannot propagate for the wavelength of the ray, the ray is terminated. The behaviour
+    /// if the [`Ray`] does not intersect with the surface is determined by the `missed_surface_strategy` parameter.
+    /// # Errors
+    ///
+    /// This function only propagates errors of contained functions.
+    pub fn diffract_through_periodic_surface(
+        &mut self,
+        s: &OpticSurface,
+        grating_vector: Vector3<f64>,
+        diffraction_order: &i32,
+        missed_surface_strategy: &MissedSurfaceStrategy,
+    ) -> OpmResult<()> {
+        if self
+            .diffract(s, grating_vector, diffraction_order, false)?
+            .is_none()
+        {
+            match missed_surface_strategy {
+                MissedSurfaceStrategy::Stop => self.terminate(TerminationReason::MissedSurface),
+                MissedSurfaceStrategy::Ignore => {}
+            }
+        }
+        Ok(())
+    }
+    /// Propagate the [`Ray`] to the given periodic surface and diffract it (in reflection or transmission).
+    ///
+    /// Returns `None` if the surface was missed and `Some(false)` if the diffraction order cannot propagate. In the
+    /// latter case the ray is terminated.
+    fn diffract(
+        &mut self,
+        s: &OpticSurface,
+        grating_vector: Vector3<f64>,
+        diffraction_order: &i32,
+        reflection: bool,
+    ) -> OpmResult<Option<bool>> {
         let geo_surf = s.geo_surface();
         let surf_vectors = geo_surf
             .0
             .lock()
             .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
             .calc_intersect_and_normal(self);
-        if let Some((intersection_point, surface_normal)) = surf_vectors {
-            let surface_normal = surface_normal.normalize();
+        let Some((intersection_point, surface_normal)) = surf_vectors else {
+            return Ok(None);
+        };
+        let surface_normal = surface_normal.normalize();
 
-            // get correctly normalized k vector of ray
-            let ray_dir_norm = self.dir.norm();
-            let k0_n = 2. * PI * self.refractive_index / self.wavelength().value;
-            let k_vec = self.dir * k0_n / ray_dir_norm;
+        // get correctly normalized k vector of ray
+        let ray_dir_norm = self.dir.norm();
+        let k0_n = 2. * PI * self.refractive_index / self.wavelength().value;
+        let k_vec = self.dir * k0_n / ray_dir_norm;
 
-            //split k vetor into components parallel and perpendicular to the surface
-            let k_para = surface_normal.cross(&(k_vec.cross(&surface_normal)));
-            let k_perp = surface_normal * k_vec.dot(&surface_normal);
+        //split k vetor into components parallel and perpendicular to the surface
+        let k_para = surface_normal.cross(&(k_vec.cross(&surface_normal)));
+        let k_perp = surface_normal * k_vec.dot(&surface_normal);
 
-            //outgoing vector in-plane
-            let k_para_out = k_para + diffraction_order.to_f64().unwrap() * grating_vector;
+        //outgoing vector in-plane
+        let k_para_out = k_para + diffraction_order.to_f64().unwrap() * grating_vector;
 
-            //new ratio of the perpendicular part to the full k vector
-            let k_perp_norm_out = k0_n.mul_add(k0_n, -k_para_out.norm().powi(2)).sqrt();
+        //new ratio of the perpendicular part to the full k vector
+        let k_perp_norm_out = k0_n.mul_add(k0_n, -k_para_out.norm().powi(2)).sqrt();
 
-            let pos_in_m = self.pos.map(|c| c.value);
-            let intersection_in_m = intersection_point.map(|c| c.value);
-            //first add gemometrical path length
-            self.path_length +=
-                self.refractive_index * meter!((pos_in_m - intersection_in_m).norm());
-            //then add additional phase shift due to lateral displacement from the grating origin
-            let dist_from_origin = s
-                .geo_surface()
-                .0
-                .lock()
-                .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
-                .isometry()
-                .inverse_transform_point_f64(&intersection_in_m)
-                .x;
-            self.path_length +=
-                diffraction_order.to_f64().unwrap() * grating_vector.norm() / 2. / PI
-                    * dist_from_origin
-                    * self.wavelength();
+        let pos_in_m = self.pos.map(|c| c.value);
+        let intersection_in_m = intersection_point.map(|c| c.value);
+        //first add gemometrical path length
+        self.path_length += self.refractive_index * meter!((pos_in_m - intersection_in_m).norm());
+        //then add additional phase shift due to lateral displacement from the grating origin
+        let dist_from_origin = s
+            .geo_surface()
+            .0
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".to_string()))?
+            .isometry()
+            .inverse_transform_point_f64(&intersection_in_m)
+            .x;
+        self.path_length += diffraction_order.to_f64().unwrap() * grating_vector.norm() / 2. / PI
+            * dist_from_origin
+            * self.wavelength();
 
-            self.pos_hist.push(self.pos);
-            self.pos = intersection_point;
-            if k_perp_norm_out.is_finite() {
-                let k_perp_out = -k_perp.normalize() * k_perp_norm_out;
-                self.prev_dir = Some(self.dir);
-                self.dir = (k_perp_out + k_para_out).normalize();
+        self.pos_hist.push(self.pos);
+        self.pos = intersection_point;
+        if k_perp_norm_out.is_finite() {
+            let k_perp_out = if reflection {
+                -k_perp.normalize() * k_perp_norm_out
+            } else {
+                k_perp.normalize() * k_perp_norm_out
+            };
+            self.prev_dir = Some(self.dir);
+            self.dir = (k_perp_out + k_para_out).normalize();
+            if reflection {
                 self.number_of_bounces += 1;
-                //currently only reflection
-                let reflected_ray = self.clone();
-                self.e = joule!(0.);
-                Ok(Some(reflected_ray))
             } else {
-                // diffraction order is not supported
-                self.set_invalid();
-                Ok(None)
+                self.number_of_refractions += 1;
             }
+            Ok(Some(true))
         } else {
-            Ok(None)
+            // diffraction order is not supported
+            self.terminate(TerminationReason::DiffractionOrder);
+            Ok(Some(false))
         }
     }
     /// Refract the [`Ray`] on a given [`OpticSurface`] using Snellius' law.
@@ -680,13 +961,47 @@ impl Ray {
         } else {
             // no intersection
             match missed_surface_strategy {
-                MissedSurfaceStrategy::Stop => self.set_invalid(),
+                MissedSurfaceStrategy::Stop => self.terminate(TerminationReason::MissedSurface),
                 MissedSurfaceStrategy::Ignore => {}
             }
             Ok(None)
         }
     }
 
+    /// Scatter a fraction of the energy of this [`Ray`] on the micro-roughness of a surface.
+    ///
+    /// This function has to be called after the [`Ray`] was refracted or reflected on the surface. The `incoming_dir`
+    /// denotes the direction of the ray before hitting the surface. For a reflection, `index_contrast` is `None`, for a
+    /// transmission it denotes the difference of the refractive indices in front of and behind the surface.
+    ///
+    /// The fraction of the energy given by the total integrated scatter of the [`SurfaceRoughness`] is removed from this
+    /// [`Ray`] and returned as a new [`Ray`] propagating into a random direction drawn from the given [`ScatterSampler`].
+    /// This function returns `None` if nothing is scattered. Rays used for fluence estimation (helper rays) are not scattered.
+    pub fn scatter_on_rough_surface<R: Rng>(
+        &mut self,
+        roughness: &SurfaceRoughness,
+        sampler: &ScatterSampler,
+        incoming_dir: Vector3<f64>,
+        surface_normal: Vector3<f64>,
+        index_contrast: Option<f64>,
+        rng: &mut R,
+    ) -> Option<Self> {
+        if !self.valid() || self.is_helper || self.helper_rays.is_some() {
+            return None;
+        }
+        let normal = surface_normal.normalize();
+        let cos_incidence = incoming_dir.normalize().dot(&normal);
+        let tis =
+            roughness.total_integrated_scatter(self.wavelength(), cos_incidence, index_contrast);
+        if tis <= 0.0 || !tis.is_finite() {
+            return None;
+        }
+        let mut scattered_ray = self.clone();
+        scattered_ray.dir = sampler.sample_direction(self.dir, normal, rng);
+        scattered_ray.e = self.e * tis;
+        self.e *= 1.0 - tis;
+        Some(scattered_ray)
+    }
     fn change_helper_fluence_by_factor(&mut self, factor: f64) -> OpmResult<()> {
         self.helper_rays.as_mut().map_or(Ok(()), |helper_rays| {
             helper_rays.change_effective_energy_by_factor(factor)
@@ -695,7 +1010,8 @@ impl Ray {
     /// Attenuate a ray's energy by a given filter.
     ///
     /// This function attenuates the ray's energy by the given [`FilterType`]. For [`FilterType::Constant`] the energy is simply multiplied by the
-    /// given transmission factor.
+    /// given transmission factor. For [`FilterType::TransmissionCurve`] the (interpolated) transmission at the ray's
+    /// wavelength is used.
     /// # Errors
     ///
     /// This function will return an error if the transmission factor for the [`FilterType::Constant`] is not within the interval `(0.0..=1.0)`
@@ -719,6 +1035,7 @@ impl Ray {
                     ));
                 }
             }
+            FilterType::TransmissionCurve(curve) => curve.transmission(self.wavelength()),
         };
         self.e *= transmission;
         // let mut new_ray = self.clone();
@@ -742,6 +1059,12 @@ impl Ray {
     /// of 1.0 means that all energy remains in the initial beam and the split beam has an energy of zero. A spectrum value of 0.0 corresponds to
     /// a fully reflected beam.
     ///
+    /// ## [`SplittingConfig::Polarizing`]
+    ///
+    /// The p (x) and s (y) components of the ray's Jones vector are split according to their respective transmissions. The
+    /// polarization states of both resulting rays are updated accordingly. Unpolarized rays are split with the average
+    /// of both transmissions as scalar ratio and stay unpolarized.
+    ///
     /// **Note**: This function only copies the initial ray and modifies the energies. The split ray has the same position and direction as the
     /// original ray.
     ///
@@ -759,6 +1082,20 @@ impl Ray {
                     )
                 })?
             }
+            SplittingConfig::Polarizing {
+                p_transmission,
+                s_transmission,
+            } => {
+                if !config.is_valid() {
+                    return Err(OpossumError::Other(
+                        "p and s transmissions must be within [0.0;1.0]".into(),
+                    ));
+                }
+                if let Some(pol) = self.pol {
+                    return Ok(self.split_polarized(&pol, *p_transmission, *s_transmission));
+                }
+                f64::midpoint(*p_transmission, *s_transmission)
+            }
         };
         if !(0.0..=1.0).contains(&splitting_ratio) {
             return Err(OpossumError::Other(
@@ -770,6 +1107,31 @@ impl Ray {
         split_ray.e *= 1.0 - splitting_ratio;
         Ok(split_ray)
     }
+    /// Split a polarized ray into its transmitted and reflected part of a polarizing beam splitter.
+    fn split_polarized(
+        &mut self,
+        pol: &Vector2<Complex<f64>>,
+        p_transmission: f64,
+        s_transmission: f64,
+    ) -> Self {
+        let transmitted = Vector2::new(
+            pol[0] * p_transmission.sqrt(),
+            pol[1] * s_transmission.sqrt(),
+        );
+        let reflected = Vector2::new(
+            pol[0] * (1.0 - p_transmission).sqrt(),
+            pol[1] * (1.0 - s_transmission).sqrt(),
+        );
+        let mut split_ray = self.clone();
+        for (ray, jones_vector) in [(&mut *self, transmitted), (&mut split_ray, reflected)] {
+            let norm = jones_vector.norm();
+            ray.e *= norm * norm;
+            if norm.is_normal() {
+                ray.pol = Some(jones_vector.unscale(norm));
+            }
+        }
+        split_ray
+    }
     /// Returns the validity of this [`Ray`].
     ///
     /// The `valid` status denotes, if a [`Ray`] should be further propagated thorugh a system. A [`Ray`] is set to invalid if e.g.
@@ -779,8 +1141,26 @@ impl Ray {
         self.valid
     }
     /// Invalidates this [`Ray`].
+    ///
+    /// This is a shortcut for [`terminate`](Ray::terminate) with [`TerminationReason::Other`].
     pub const fn set_invalid(&mut self) {
-        self.valid = false;
+        self.terminate(TerminationReason::Other);
+    }
+    /// Invalidates this [`Ray`] for the given [`TerminationReason`].
+    ///
+    /// If the [`Ray`] is already invalid, its original [`TerminationReason`] is kept.
+    pub const fn terminate(&mut self, reason: TerminationReason) {
+        if self.valid {
+            self.valid = false;
+            self.termination_reason = Some(reason);
+        }
+    }
+    /// Returns the reason why this [`Ray`] has been invalidated.
+    ///
+    /// `None` is returned for a valid [`Ray`].
+    #[must_use]
+    pub const fn termination_reason(&self) -> Option<TerminationReason> {
+        self.termination_reason
     }
     /// Get [`Ray`] translated and rotated by given [`Isometry`]
     #[must_use]
@@ -859,6 +1239,28 @@ impl Ray {
         Ok(())
     }
 }
+/// Returns the principal eigenvector of `M M†` as output polarization of unpolarized light passing the Jones matrix `M`.
+///
+/// Returns `None`, if both eigenvalues are equal, i.e. the light stays unpolarized.
+fn principal_polarization(jones_matrix: &Matrix2<Complex<f64>>) -> Option<Vector2<Complex<f64>>> {
+    let coherency = jones_matrix * jones_matrix.adjoint();
+    let a = coherency[(0, 0)].re;
+    let d = coherency[(1, 1)].re;
+    let b = coherency[(0, 1)];
+    let half_split = f64::hypot((a - d) / 2.0, b.norm());
+    if half_split <= f64::EPSILON * (a + d) {
+        return None;
+    }
+    let lambda = (a + d) / 2.0 + half_split;
+    let eigenvector = if b.norm() > f64::EPSILON * (a + d) {
+        Vector2::new(b, Complex::new(lambda - a, 0.0))
+    } else if a > d {
+        Vector2::new(Complex::new(1.0, 0.0), Complex::zero())
+    } else {
+        Vector2::new(Complex::zero(), Complex::new(1.0, 0.0))
+    };
+    Some(eigenvector.normalize())
+}
 impl Display for Ray {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let m = Length::format_args(meter, uom::fmt::DisplayStyle::Abbreviation);
@@ -886,6 +1288,7 @@ mod test {
         J_per_cm2,
         coatings::CoatingType,
         degree, joule, millimeter, nanometer,
+        refractive_index::RefrIndexConst,
         spectrum_helper::{self, generate_filter_spectrum},
     };
     use approx::{abs_diff_eq, assert_abs_diff_eq, assert_relative_eq, relative_eq};
@@ -1201,6 +1604,81 @@ mod test {
         assert_eq!(ray.dir, Vector3::z());
     }
     #[test]
+    fn propagate_in_gradient_index() {
+        // parabolic radial profile n(r) = n0 (1 - g² r² / 2) => paraxial ray path r(z) = r0 cos(g z)
+        let (n0, g, r0) = (1.5, 100.0, 0.5e-3);
+        let grin = GradientIndex::radial(
+            RefrIndexConst::new(n0).unwrap().into(),
+            -n0 * g * g / 2.0,
+            0.0,
+        )
+        .unwrap();
+        let iso = Isometry::identity();
+        let mut ray =
+            Ray::new_collimated(meter!(r0, 0.0, 0.0), nanometer!(1000.0), joule!(1.0)).unwrap();
+        ray.set_refractive_index(n0 - n0 * g * g * r0 * r0 / 2.0)
+            .unwrap();
+        let half_pitch = PI / g;
+        ray.propagate_in_gradient_index(&grin, &iso, meter!(half_pitch), millimeter!(0.1))
+            .unwrap();
+        assert_abs_diff_eq!(ray.position().z.value, half_pitch);
+        assert_abs_diff_eq!(ray.position().x.value, -r0, epsilon = 1e-3 * r0);
+        assert_abs_diff_eq!(ray.position().y.value, 0.0);
+        assert_abs_diff_eq!(ray.direction().x, 0.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(
+            ray.refractive_index(),
+            n0 - n0 * g * g * r0 * r0 / 2.0,
+            epsilon = 1e-6
+        );
+        assert!(ray.pos_hist.len() > 100);
+        for pos in &ray.pos_hist {
+            assert_abs_diff_eq!(
+                pos.x.value,
+                r0 * (g * pos.z.value).cos(),
+                epsilon = 1e-2 * r0
+            );
+        }
+        // optical path length is close to the on-axis value
+        assert_relative_eq!(
+            ray.path_length().value,
+            n0 * half_pitch,
+            max_relative = 1e-3
+        );
+    }
+    #[test]
+    fn propagate_in_gradient_index_wrong() {
+        let grin =
+            GradientIndex::radial(RefrIndexConst::new(1.5).unwrap().into(), -1000.0, 0.0).unwrap();
+        let iso = Isometry::identity();
+        let mut ray =
+            Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), nanometer!(1000.0), joule!(1.0))
+                .unwrap();
+        assert!(
+            ray.propagate_in_gradient_index(&grin, &iso, millimeter!(10.0), millimeter!(0.0))
+                .is_err()
+        );
+        assert!(
+            ray.propagate_in_gradient_index(&grin, &iso, millimeter!(10.0), millimeter!(f64::NAN))
+                .is_err()
+        );
+        assert!(
+            ray.propagate_in_gradient_index(&grin, &iso, millimeter!(f64::NAN), millimeter!(1.0))
+                .is_err()
+        );
+        assert!(
+            ray.propagate_in_gradient_index(&grin, &iso, millimeter!(-10.0), millimeter!(1.0))
+                .is_err()
+        );
+        assert_eq!(ray.pos_hist.len(), 0);
+        // on-axis ray propagates along a straight line
+        ray.propagate_in_gradient_index(&grin, &iso, millimeter!(10.0), millimeter!(1.0))
+            .unwrap();
+        assert_abs_diff_eq!(ray.position().x.value, 0.0);
+        assert_abs_diff_eq!(ray.position().y.value, 0.0);
+        assert_abs_diff_eq!(ray.position().z.value, 0.01);
+        assert_relative_eq!(ray.path_length().value, 0.015);
+    }
+    #[test]
     fn refract_on_surface_collimated() {
         let position = Point3::origin();
         let wvl = nanometer!(1054.0);
@@ -1457,6 +1935,61 @@ mod test {
         assert_abs_diff_eq!(ray.dir[2], test_reflect[2]);
     }
     #[test]
+    fn set_polarization() {
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        assert_eq!(ray.polarization(), None);
+        let zero = Vector2::new(Complex::zero(), Complex::zero());
+        assert!(ray.set_polarization(Some(zero)).is_err());
+        let nan = Vector2::new(Complex::new(f64::NAN, 0.0), Complex::zero());
+        assert!(ray.set_polarization(Some(nan)).is_err());
+        let jones = Vector2::new(Complex::new(2.0, 0.0), Complex::new(0.0, 2.0));
+        ray.set_polarization(Some(jones)).unwrap();
+        let pol = ray.polarization().unwrap();
+        assert_relative_eq!(pol.norm(), 1.0);
+        assert_relative_eq!(pol[1].im, f64::sqrt(0.5));
+        ray.set_polarization(None).unwrap();
+        assert_eq!(ray.polarization(), None);
+    }
+    #[test]
+    fn apply_jones_matrix() {
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        let nan_matrix = Matrix2::from_element(Complex::new(f64::NAN, 0.0));
+        assert!(ray.apply_jones_matrix(&nan_matrix).is_err());
+        // a retarder keeps unpolarized light unpolarized
+        let retarder = Matrix2::new(
+            Complex::new(1.0, 0.0),
+            Complex::zero(),
+            Complex::zero(),
+            Complex::new(0.0, 1.0),
+        );
+        ray.apply_jones_matrix(&retarder).unwrap();
+        assert_eq!(ray.polarization(), None);
+        assert_relative_eq!(ray.energy().get::<joule>(), 1.0);
+        // x polarizer
+        let polarizer = Matrix2::new(
+            Complex::new(1.0, 0.0),
+            Complex::zero(),
+            Complex::zero(),
+            Complex::zero(),
+        );
+        ray.apply_jones_matrix(&polarizer).unwrap();
+        assert_relative_eq!(ray.energy().get::<joule>(), 0.5);
+        let pol = ray.polarization().unwrap();
+        assert_relative_eq!(pol[0].norm(), 1.0);
+        assert_relative_eq!(pol[1].norm(), 0.0);
+        // y polarizer blocks x-polarized light
+        let polarizer = Matrix2::new(
+            Complex::zero(),
+            Complex::zero(),
+            Complex::zero(),
+            Complex::new(1.0, 0.0),
+        );
+        ray.apply_jones_matrix(&polarizer).unwrap();
+        assert_relative_eq!(ray.energy().get::<joule>(), 0.0);
+    }
+    #[test]
     fn filter_energy() {
         let position = millimeter!(0., 1., 0.);
         let wvl = nanometer!(1054.0);
@@ -1514,6 +2047,66 @@ mod test {
         assert_eq!(ray.wavelength(), split_ray.wavelength());
     }
     #[test]
+    fn split_polarizing() {
+        let config = SplittingConfig::Polarizing {
+            p_transmission: 0.9,
+            s_transmission: 0.1,
+        };
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        assert!(
+            ray.split(&SplittingConfig::Polarizing {
+                p_transmission: 1.1,
+                s_transmission: 0.0
+            })
+            .is_err()
+        );
+        // unpolarized: average ratio
+        let split_ray = ray.split(&config).unwrap();
+        assert_abs_diff_eq!(ray.energy().value, 0.5);
+        assert_abs_diff_eq!(split_ray.energy().value, 0.5);
+        assert!(ray.polarization().is_none());
+        assert!(split_ray.polarization().is_none());
+        // p-polarized
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        let p_pol = Vector2::new(Complex::new(1.0, 0.0), Complex::zero());
+        ray.set_polarization(Some(p_pol)).unwrap();
+        let split_ray = ray.split(&config).unwrap();
+        assert_abs_diff_eq!(ray.energy().value, 0.9, epsilon = 1e-12);
+        assert_abs_diff_eq!(split_ray.energy().value, 0.1, epsilon = 1e-12);
+        assert_eq!(ray.polarization(), Some(p_pol));
+        assert_eq!(split_ray.polarization(), Some(p_pol));
+        // 45° linear polarization: transmitted part is mostly p, reflected part mostly s
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        ray.set_polarization(Some(Vector2::new(
+            Complex::new(1.0, 0.0),
+            Complex::new(1.0, 0.0),
+        )))
+        .unwrap();
+        let split_ray = ray.split(&config).unwrap();
+        assert_abs_diff_eq!(ray.energy().value, 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(split_ray.energy().value, 0.5, epsilon = 1e-12);
+        let pol = ray.polarization().unwrap();
+        assert_abs_diff_eq!(pol[0].norm_sqr(), 0.9, epsilon = 1e-12);
+        let pol = split_ray.polarization().unwrap();
+        assert_abs_diff_eq!(pol[1].norm_sqr(), 0.9, epsilon = 1e-12);
+        // ideal PBS with s-polarized input: everything is reflected
+        let ideal = SplittingConfig::Polarizing {
+            p_transmission: 1.0,
+            s_transmission: 0.0,
+        };
+        let mut ray =
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1054.0), joule!(1.0)).unwrap();
+        let s_pol = Vector2::new(Complex::zero(), Complex::new(1.0, 0.0));
+        ray.set_polarization(Some(s_pol)).unwrap();
+        let split_ray = ray.split(&ideal).unwrap();
+        assert_eq!(ray.energy(), Energy::zero());
+        assert_eq!(split_ray.energy(), joule!(1.0));
+        assert_eq!(split_ray.polarization(), Some(s_pol));
+    }
+    #[test]
     fn split_by_spectrum() {
         let mut ray =
             Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1000.0), joule!(1.0)).unwrap();
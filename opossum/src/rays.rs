@@ -14,18 +14,22 @@ use crate::{
     nodes::{
         FilterType, WaveFrontData, WaveFrontErrorMap,
         fluence_detector::{Fluence, fluence_data::FluenceData},
-        ray_propagation_visualizer::{RayPositionHistories, RayPositionHistorySpectrum},
+        ray_propagation_visualizer::{
+            RayPolyline, RayPolylines, RayPositionHistories, RayPositionHistorySpectrum,
+        },
     },
     plottable::AxLims,
     position_distributions::{Hexapolar, PositionDistribution},
     properties::Proptype,
-    ray::{Ray, SplittingConfig},
-    refractive_index::RefractiveIndexType,
+    radian,
+    ray::{Ray, SplittingConfig, TerminationReason},
+    refractive_index::{GradientIndex, RefractiveIndexType},
     spectral_distribution::SpectralDistribution,
     spectrum::Spectrum,
     surface::{hit_map::fluence_estimator::FluenceEstimator, optic_surface::OpticSurface},
     utils::{
         filter_data::get_unique_finite_values_sorted,
+        focus_parameters::FocusParameters,
         geom_transformation::Isometry,
         griddata::{
             VoronoiedData, calc_closed_poly_area, create_voronoi_cells,
@@ -41,12 +45,18 @@ use itertools::{Itertools, izip};
 use kahan::KahanSummator;
 use log::warn;
 use nalgebra::{
-    DMatrix, DVector, Matrix2xX, MatrixXx2, MatrixXx3, Point2, Point3, Vector2, Vector3, distance,
-    vector,
+    DMatrix, DVector, Matrix2, Matrix2xX, MatrixXx2, MatrixXx3, Point2, Point3, Vector2, Vector3,
+    distance, vector,
 };
-use num::ToPrimitive;
+use num::{Complex, ToPrimitive};
+use rand::{SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, ops::Range, path::Path};
+use std::{
+    fmt::Display,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    path::Path,
+};
 use uom::{
     num_traits::Zero,
     si::{
@@ -564,7 +574,7 @@ impl Rays {
                     ray.filter_energy(&FilterType::Constant(ap_factor))?;
                 } else {
                     ray.add_to_pos_hist(ray.position());
-                    ray.set_invalid();
+                    ray.terminate(TerminationReason::Aperture);
                     beams_invalided = true;
                 }
             }
@@ -591,6 +601,20 @@ impl Rays {
             .map(|w| nanometer!(*w))
             .collect::<Vec<Length>>()
     }
+    /// Returns the optical path lengths of all [`Ray`]s of this [`Rays`].
+    ///
+    /// The optical path length of each [`Ray`] is accumulated during propagation as the geometric distance multiplied
+    /// by the refractive index of the medium (see [`Ray::path_length`]). Differences between these values correspond to
+    /// relative phases of the rays. The `valid_only` switch determines if all [`Ray`]s or only `valid` [`Ray`]s are
+    /// considered.
+    #[must_use]
+    pub fn path_lengths(&self, valid_only: bool) -> Vec<Length> {
+        self.ray_bundle
+            .iter()
+            .filter(|r| r.valid() || !valid_only)
+            .map(Ray::path_length)
+            .collect()
+    }
     /// Returns the centroid of this [`Rays`].
     ///
     /// This functions returns the centroid of the positions (`valid` [`Ray`]s only) of this ray bundle. The
@@ -699,6 +723,74 @@ impl Rays {
             sum_dist_sq.sqrt()
         })
     }
+    /// Returns the [`FocusParameters`] (waist position, waist radius and divergence) of this [`Rays`].
+    ///
+    /// The parameters are determined from the energy-weighted second moments of the ray positions and ray slopes
+    /// (`valid` [`Ray`]s only) assuming free-space propagation along the z axis. The divergence is given as (paraxial) rms slope. Hence, the ray bundle should be given in the
+    /// local coordinate system of the evaluation plane (e.g. a detector surface). The waist position is relative to the
+    /// current ray positions. This function returns `None` if [`Rays`] contains no valid rays propagating in positive z direction
+    /// or if the bundle carries no energy.
+    #[must_use]
+    pub fn focus_parameters(&self) -> Option<FocusParameters> {
+        let rays = self
+            .ray_bundle
+            .iter()
+            .filter(|r| r.valid() && r.direction().z > 0.0)
+            .map(|r| {
+                let dir = r.direction();
+                (
+                    r.position().x.value,
+                    r.position().y.value,
+                    dir.x / dir.z,
+                    dir.y / dir.z,
+                    r.energy().get::<joule>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let total_energy: f64 = rays.iter().map(|r| r.4).sum();
+        if rays.is_empty() || total_energy <= 0.0 {
+            return None;
+        }
+        let mean = rays.iter().fold((0.0, 0.0, 0.0, 0.0), |m, r| {
+            (
+                r.4.mul_add(r.0, m.0),
+                r.4.mul_add(r.1, m.1),
+                r.4.mul_add(r.2, m.2),
+                r.4.mul_add(r.3, m.3),
+            )
+        });
+        let mean = (
+            mean.0 / total_energy,
+            mean.1 / total_energy,
+            mean.2 / total_energy,
+            mean.3 / total_energy,
+        );
+        // second moments: <r^2>, <r*u>, <u^2> with r the radial distance and u the slope (w.r.t. the centroid)
+        let (mut pos_sq, mut pos_slope, mut slope_sq) = (0.0, 0.0, 0.0);
+        for r in &rays {
+            let (dx, dy, du, dv) = (r.0 - mean.0, r.1 - mean.1, r.2 - mean.2, r.3 - mean.3);
+            pos_sq += r.4 * dx.mul_add(dx, dy * dy);
+            pos_slope += r.4 * dx.mul_add(du, dy * dv);
+            slope_sq += r.4 * du.mul_add(du, dv * dv);
+        }
+        pos_sq /= total_energy;
+        pos_slope /= total_energy;
+        slope_sq /= total_energy;
+        let (waist_position, waist_sq) = if slope_sq > 0.0 {
+            (
+                -pos_slope / slope_sq,
+                (pos_sq - pos_slope * pos_slope / slope_sq).max(0.0),
+            )
+        } else {
+            (0.0, pos_sq)
+        };
+        FocusParameters::new(
+            meter!(waist_position),
+            meter!(waist_sq.sqrt()),
+            radian!(slope_sq.sqrt()),
+        )
+        .ok()
+    }
     /// Returns the wavefront of the bundle of [`Rays`] at the center wavelength or at each band of the spectrum with a defined resolution.
     /// This function calculates the wavefront of a ray bundle as multiple of its wavelength with reference to the ray that is closest to the optical axis.
     /// # Attributes
@@ -907,17 +999,30 @@ impl Rays {
             interpolate_3d_triangulated_scatter_data(&voronoi_fluence_scatter, &co_ax1, &co_ax2)?;
 
         Ok(FluenceData::new(
-            DMatrix::from_iterator(
-                co_ax1.len(),
-                co_ax2.len(),
-                interp_fluence.iter().map(|val| J_per_cm2!(*val)),
-            ),
+            interp_fluence.map(|val| J_per_cm2!(val)),
             centimeter!(co_ax1_lim.min)..centimeter!(co_ax1_lim.max),
             centimeter!(co_ax2_lim.min)..centimeter!(co_ax2_lim.max),
             FluenceEstimator::Voronoi,
         ))
     }
 
+    /// Returns a seed for the random scattering of this ray bundle on rough surfaces.
+    ///
+    /// The seed is derived from the current positions and directions of all rays in the bundle. Hence, the scattering is
+    /// reproducible for reproducible sources (e.g. if the seed of the analyzer is set).
+    fn scatter_seed(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.ray_history_len().hash(&mut hasher);
+        for ray in &self.ray_bundle {
+            for coord in ray.position().iter() {
+                coord.value.to_bits().hash(&mut hasher);
+            }
+            for coord in ray.direction().iter() {
+                coord.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
     /// Add a single ray to the ray bundle.
     ///
     /// # Panics
@@ -956,6 +1061,35 @@ impl Rays {
         }
         Ok(())
     }
+    /// Propagate a ray bundle through a gradient-index (GRIN) medium up to the plane `z = end_position` (in local coordinates).
+    ///
+    /// This function propagates all valid [`Ray`]s (including their helper rays). See [`Ray::propagate_in_gradient_index`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the propagation of a single [`Ray`] fails.
+    pub fn propagate_in_gradient_index(
+        &mut self,
+        gradient_index: &GradientIndex,
+        isometry: &Isometry,
+        end_position: Length,
+        step_size: Length,
+    ) -> OpmResult<()> {
+        for ray in &mut self.ray_bundle {
+            if ray.valid() {
+                ray.propagate_in_gradient_index(gradient_index, isometry, end_position, step_size)?;
+            }
+            if let Some(helper_rays) = ray.helper_rays_mut() {
+                helper_rays.propagate_in_gradient_index(
+                    gradient_index,
+                    isometry,
+                    end_position,
+                    step_size,
+                )?;
+            }
+        }
+        Ok(())
+    }
     /// Refract a ray bundle on a [`GeoSurface`](crate::surface::geo_surface::GeoSurface) and returns a reflected [`Ray`] bundle.
     ///
     /// This function refracts all `valid` [`Ray`]s on a given surface.
@@ -983,6 +1117,12 @@ impl Rays {
         let mut valid_rays_found = false;
         let mut rays_missed = false;
         let mut reflected_rays = Self::default();
+        let mut scattered_rays = Vec::<Ray>::new();
+        let roughness = surface.roughness().cloned().map(|roughness| {
+            let sampler = roughness.sampler();
+            (roughness, sampler)
+        });
+        let mut rng = StdRng::seed_from_u64(self.scatter_seed());
         for ray in &mut self.ray_bundle {
             if ray.valid() {
                 let n2 = if let Some(refractive_index) = refractive_index {
@@ -990,6 +1130,7 @@ impl Rays {
                 } else {
                     None
                 };
+                let (incoming_dir, n1) = (ray.direction(), ray.refractive_index());
                 if let Some(mut reflected) =
                     ray.refract_on_surface(surface, n2, self.uuid, missed_surface_strategy)?
                 {
@@ -1016,6 +1157,33 @@ impl Rays {
                         reflected.reduce_bounce_counter();
                         ray.clear_pos_hist();
                     }
+                    if let Some((roughness, sampler)) = &roughness {
+                        // the surface normal is parallel to the difference of reflected and incoming direction
+                        let normal = reflected.direction() - incoming_dir.normalize();
+                        if normal.norm() > f64::EPSILON {
+                            let scattered = if refraction_intended {
+                                let index_contrast = n1 - ray.refractive_index();
+                                ray.scatter_on_rough_surface(
+                                    roughness,
+                                    sampler,
+                                    incoming_dir,
+                                    normal,
+                                    Some(index_contrast),
+                                    &mut rng,
+                                )
+                            } else {
+                                reflected.scatter_on_rough_surface(
+                                    roughness,
+                                    sampler,
+                                    incoming_dir,
+                                    normal,
+                                    None,
+                                    &mut rng,
+                                )
+                            };
+                            scattered_rays.extend(scattered);
+                        }
+                    }
                     reflected_rays.add_ray(reflected);
                 } else {
                     rays_missed = true;
@@ -1029,6 +1197,13 @@ impl Rays {
         if !valid_rays_found {
             warn!("ray bundle contains no valid rays - not propagating");
         }
+        for scattered in scattered_rays {
+            if refraction_intended {
+                self.add_ray(scattered);
+            } else {
+                reflected_rays.add_ray(scattered);
+            }
+        }
         //surface.set_backwards_rays_cache(reflected_rays.clone());
         if refraction_intended {
             reflected_rays.set_parent_uuid(self.uuid);
@@ -1109,6 +1284,41 @@ impl Rays {
         }
         Ok(reflected_rays)
     }
+    /// Diffract a bundle of [`Rays`] in transmission on a periodic surface, e.g., a transmission grating.
+    ///
+    /// All valid rays are diffracted in place according to the periodic structure, the diffraction order and their
+    /// wavelength. See [`Ray::diffract_through_periodic_surface`] for details.
+    /// # Warnings
+    ///
+    /// This functions emits a warning of no valid [`Ray`]s are found in the bundle.
+    ///
+    /// # Errors
+    ///
+    /// This function only propagates errors of contained functions.
+    pub fn diffract_through_periodic_surface(
+        &mut self,
+        surface: &OpticSurface,
+        grating_vector: Vector3<f64>,
+        diffraction_order: &i32,
+        missed_surface_strategy: &MissedSurfaceStrategy,
+    ) -> OpmResult<()> {
+        let mut valid_rays_found = false;
+        for ray in &mut self.ray_bundle {
+            if ray.valid() {
+                ray.diffract_through_periodic_surface(
+                    surface,
+                    grating_vector,
+                    diffraction_order,
+                    missed_surface_strategy,
+                )?;
+                valid_rays_found = true;
+            }
+        }
+        if !valid_rays_found {
+            warn!("ray bundle contains no valid rays - not propagating");
+        }
+        Ok(())
+    }
 
     /// Filter a ray bundle by a given filter.
     ///
@@ -1124,6 +1334,16 @@ impl Rays {
                 ));
             }
         }
+        if let FilterType::TransmissionCurve(curve) = filter
+            && self
+                .ray_bundle
+                .iter()
+                .any(|ray| ray.valid() && !curve.contains(ray.wavelength()))
+        {
+            warn!(
+                "ray wavelengths exceed the range of the transmission curve. Using transmission of nearest data point."
+            );
+        }
         for ray in &mut self.ray_bundle {
             if (*ray).valid() {
                 ray.filter_energy(filter)?;
@@ -1131,6 +1351,20 @@ impl Rays {
         }
         Ok(())
     }
+    /// Apply a Jones matrix to all `valid` rays of this ray bundle.
+    ///
+    /// See [`Ray::apply_jones_matrix`] for details.
+    /// # Errors
+    ///
+    /// This function will return an error if the Jones matrix contains non-finite values.
+    pub fn apply_jones_matrix(&mut self, jones_matrix: &Matrix2<Complex<f64>>) -> OpmResult<()> {
+        for ray in &mut self.ray_bundle {
+            if ray.valid() {
+                ray.apply_jones_matrix(jones_matrix)?;
+            }
+        }
+        Ok(())
+    }
     /// Invalidate all [`Ray`]s below a given energy threshold.
     ///
     /// Sets all rays with an energy (per ray) below the given threshold to the `invalid` state.
@@ -1156,10 +1390,42 @@ impl Rays {
             .ray_bundle
             .iter_mut()
             .filter(|r| r.energy() < min_energy_per_ray)
-            .map(Ray::set_invalid)
+            .map(|r| r.terminate(TerminationReason::EnergyThreshold))
             .count();
         Ok(())
     }
+    /// Remove all [`Ray`]s below a given energy threshold from this ray bundle.
+    ///
+    /// In contrast to [`invalidate_by_threshold_energy`](Rays::invalidate_by_threshold_energy) the affected rays are
+    /// not only set to the `invalid` state but completely removed from the bundle. The removed rays are returned in a
+    /// separate [`Rays`] bundle (for bookkeeping purposes). Rays, which were still valid, are terminated with
+    /// [`TerminationReason::EnergyThreshold`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given energy threshold is negative or not finite.
+    pub fn prune_by_threshold_energy(&mut self, min_energy_per_ray: Energy) -> OpmResult<Self> {
+        if min_energy_per_ray.is_sign_negative() || !min_energy_per_ray.is_finite() {
+            return Err(OpossumError::Other(
+                "threshold energy must be >=0.0 and finite".into(),
+            ));
+        }
+        let (mut pruned, kept): (Vec<Ray>, Vec<Ray>) = self
+            .ray_bundle
+            .drain(..)
+            .partition(|r| r.energy() < min_energy_per_ray);
+        self.ray_bundle = kept;
+        for ray in &mut pruned {
+            if ray.valid() {
+                ray.terminate(TerminationReason::EnergyThreshold);
+            }
+        }
+        let mut pruned_rays = Self::default();
+        for ray in pruned {
+            pruned_rays.add_ray(ray);
+        }
+        Ok(pruned_rays)
+    }
     /// Returns the central wavelength of this [`Rays`].
     /// If the ray bundle is emtpy, `None` is returned.
     #[must_use]
@@ -1382,6 +1648,20 @@ impl Rays {
             ray_transparency: 0.4,
         })
     }
+    /// Returns the position histories of (at most) `max_rays` rays of this ray bundle as [`RayPolylines`].
+    ///
+    /// Each polyline contains all intermediate positions of a ray as well as its current position. Invalid
+    /// (terminated) rays are included, so that the point where a ray was lost can be visualized.
+    #[must_use]
+    pub fn polylines(&self, max_rays: usize) -> RayPolylines {
+        RayPolylines::new(
+            self.ray_bundle
+                .iter()
+                .take(max_rays)
+                .map(RayPolyline::from)
+                .collect(),
+        )
+    }
     /// Invalide all rays that have a number of refractions higher or equal than the given upper limit.
     pub fn filter_by_nr_of_refractions(&mut self, max_refractions: usize) {
         for ray in self
@@ -1389,7 +1669,7 @@ impl Rays {
             .iter_mut()
             .filter(|r| r.number_of_refractions() >= max_refractions)
         {
-            ray.set_invalid();
+            ray.terminate(TerminationReason::MaxRefractions);
         }
     }
     /// Invalide all rays that have a number of bounces (reflections) higher than the given upper limit.
@@ -1399,7 +1679,7 @@ impl Rays {
             .iter_mut()
             .filter(|r| r.number_of_bounces() > max_bounces)
         {
-            ray.set_invalid();
+            ray.terminate(TerminationReason::MaxBounces);
         }
     }
     /// Returns a ray representing the optical axis of this [`Rays`].
@@ -1623,7 +1903,7 @@ mod test {
 
     use super::*;
     use crate::{
-        aperture::CircleConfig,
+        aperture::{AnnulusConfig, CircleConfig, GaussianApodizerConfig},
         centimeter,
         coatings::CoatingType,
         energy_distributions::General2DGaussian,
@@ -1632,7 +1912,7 @@ mod test {
         radian,
         ray::SplittingConfig,
         refractive_index::{RefrIndexConst, refr_index_vaccuum},
-        surface::optic_surface::OpticSurface,
+        surface::{optic_surface::OpticSurface, roughness::SurfaceRoughness},
         utils::test_helper::test_helper::check_logs,
     };
     use approx::{assert_abs_diff_eq, assert_relative_eq};
@@ -1799,6 +2079,24 @@ mod test {
         assert_eq!(split_bundles[1].ray_bundle.len(), 5);
     }
     #[test]
+    fn path_lengths() {
+        let mut rays = Rays::default();
+        assert!(rays.path_lengths(false).is_empty());
+        rays.add_ray(Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap());
+        let mut ray = Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap();
+        ray.set_refractive_index(1.5).unwrap();
+        rays.add_ray(ray);
+        let mut ray = Ray::origin_along_z(nanometer!(1000.0), joule!(1.0)).unwrap();
+        ray.terminate(TerminationReason::Other);
+        rays.add_ray(ray);
+        propagate(&mut rays, millimeter!(10.0)).unwrap();
+        let path_lengths = rays.path_lengths(true);
+        assert_eq!(path_lengths.len(), 2);
+        assert_relative_eq!(path_lengths[0].get::<millimeter>(), 10.0);
+        assert_relative_eq!(path_lengths[1].get::<millimeter>(), 15.0);
+        assert_eq!(rays.path_lengths(false).len(), 3);
+    }
+    #[test]
     fn get_unique_wavelengths() {
         let mut rays_1w = Rays::new_uniform_collimated(
             nanometer!(1053.),
@@ -2079,6 +2377,61 @@ mod test {
         assert_eq!(rays.beam_radius_geo().unwrap(), millimeter!(0.5_f64.sqrt()));
     }
     #[test]
+    fn focus_parameters() {
+        let mut rays = Rays::default();
+        assert!(rays.focus_parameters().is_none());
+        // skewed, converging rays: focal length f = 100 mm, skew c = 0.01 / mm
+        for (x, y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            let dir = Vector3::new(-x / 100.0 - 0.01 * y, -y / 100.0 + 0.01 * x, 1.0);
+            rays.add_ray(
+                Ray::new(millimeter!(x, y, 0.0), dir, nanometer!(1000.0), joule!(1.0)).unwrap(),
+            );
+        }
+        let focus = rays.focus_parameters().unwrap();
+        assert_relative_eq!(focus.waist_position().value, 0.05);
+        assert_relative_eq!(focus.rms_waist_radius().value, 0.001 / f64::sqrt(2.0));
+        assert_relative_eq!(focus.rms_divergence().value, f64::sqrt(2.0e-4));
+        propagate(&mut rays, focus.waist_position()).unwrap();
+        assert_relative_eq!(
+            rays.beam_radius_rms().unwrap().value,
+            focus.rms_waist_radius().value,
+            epsilon = 1.0e-9
+        );
+    }
+    #[test]
+    fn focus_parameters_tight_focus() {
+        let mut rays = Rays::default();
+        // tightly focused beam with a waist of 5 µm (rms) and a divergence of 0.1 rad (rms)
+        for (x, y) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            let dir = Vector3::new(0.1 * y, -0.1 * x, 1.0);
+            rays.add_ray(
+                Ray::new(
+                    micrometer!(5.0 * x, 5.0 * y, 0.0),
+                    dir,
+                    nanometer!(1000.0),
+                    joule!(1.0),
+                )
+                .unwrap(),
+            );
+        }
+        let focus = rays.focus_parameters().unwrap();
+        assert_abs_diff_eq!(focus.waist_position().value, 0.0);
+        assert_relative_eq!(focus.rms_waist_radius().value, 5.0e-6);
+        assert_relative_eq!(focus.geometric_rayleigh_range().value, 5.0e-5);
+        assert_relative_eq!(focus.depth_of_focus().value, 1.0e-4);
+        // 1/e^2 waist radius of ~7.07 µm at 1 µm wavelength
+        assert_relative_eq!(
+            focus.rayleigh_range(nanometer!(1000.0)).unwrap().value,
+            PI * 50.0e-12 / 1.0e-6
+        );
+        let mut invalid =
+            Ray::new_collimated(Point3::origin(), nanometer!(1000.0), joule!(1.0)).unwrap();
+        invalid.set_invalid();
+        let mut rays = Rays::default();
+        rays.add_ray(invalid);
+        assert!(rays.focus_parameters().is_none());
+    }
+    #[test]
     fn beam_radius_rms() {
         let mut rays = Rays::default();
         assert!(rays.beam_radius_rms().is_none());
@@ -2285,6 +2638,63 @@ mod test {
         assert_eq!(reflected.total_energy(), joule!(0.2));
     }
     #[test]
+    fn refract_on_surface_roughness() {
+        let mut rays = Rays::default();
+        for y in [-1.0, 0.0, 1.0] {
+            rays.add_ray(
+                Ray::new_collimated(millimeter!(0.0, y, -1.0), nanometer!(1000.0), joule!(1.0))
+                    .unwrap(),
+            );
+        }
+        let mut s = OpticSurface::default();
+        s.set_roughness(Some(
+            SurfaceRoughness::new(nanometer!(50.0), 0.01, 2.0).unwrap(),
+        ));
+        let refr_index = RefractiveIndexType::Const(RefrIndexConst::new(1.5).unwrap());
+        let mut rays2 = rays.clone();
+        let reflected = rays
+            .refract_on_surface(
+                &mut s,
+                Some(&refr_index),
+                true,
+                &MissedSurfaceStrategy::Stop,
+            )
+            .unwrap();
+        assert_eq!(reflected.nr_of_rays(true), 3);
+        assert_eq!(rays.nr_of_rays(true), 6);
+        assert_relative_eq!(rays.total_energy().value, 3.0);
+        let tis = (2.0 * PI * 0.05 * 0.5_f64).powi(2);
+        assert_relative_eq!(rays.ray_bundle[0].energy().value, 1.0 - tis);
+        assert_relative_eq!(rays.ray_bundle[3].energy().value, tis);
+        assert!(rays.ray_bundle[3].direction() != rays.ray_bundle[0].direction());
+        // deterministic for identical input
+        rays2
+            .refract_on_surface(
+                &mut s,
+                Some(&refr_index),
+                true,
+                &MissedSurfaceStrategy::Stop,
+            )
+            .unwrap();
+        for (ray, ray2) in izip!(rays.iter(), rays2.iter()) {
+            assert_eq!(ray.direction(), ray2.direction());
+        }
+        // reflection
+        let mut rays = Rays::default();
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0.0, 0.0, -1.0), nanometer!(1000.0), joule!(1.0))
+                .unwrap(),
+        );
+        s.set_coating(CoatingType::ConstantR { reflectivity: 1.0 });
+        let reflected = rays
+            .refract_on_surface(&mut s, None, false, &MissedSurfaceStrategy::Stop)
+            .unwrap();
+        assert_eq!(rays.nr_of_rays(true), 1);
+        assert_eq!(reflected.nr_of_rays(true), 2);
+        assert_relative_eq!(reflected.total_energy().value, 1.0);
+        assert!(reflected.ray_bundle[1].direction().z < 0.0);
+    }
+    #[test]
     fn filter_energy() {
         let mut rays = Rays::default();
         assert!(rays.filter_energy(&FilterType::Constant(0.5)).is_ok());
@@ -2335,6 +2745,35 @@ mod test {
         assert_eq!(rays.nr_of_rays(true), 0);
     }
     #[test]
+    fn prune_by_threshold() {
+        let mut rays = Rays::default();
+        assert!(rays.prune_by_threshold_energy(joule!(f64::NAN)).is_err());
+        assert!(
+            rays.prune_by_threshold_energy(joule!(f64::INFINITY))
+                .is_err()
+        );
+        assert!(rays.prune_by_threshold_energy(joule!(-0.1)).is_err());
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(1.0)).unwrap(),
+        );
+        rays.add_ray(
+            Ray::new_collimated(millimeter!(0., 0., 0.), nanometer!(1053.0), joule!(0.1)).unwrap(),
+        );
+        let pruned = rays.prune_by_threshold_energy(joule!(0.1)).unwrap();
+        assert_eq!(pruned.nr_of_rays(false), 0);
+        assert_eq!(rays.nr_of_rays(false), 2);
+        let pruned = rays.prune_by_threshold_energy(joule!(0.5)).unwrap();
+        assert_eq!(rays.nr_of_rays(false), 1);
+        assert_eq!(pruned.nr_of_rays(false), 1);
+        assert_eq!(pruned.nr_of_rays(true), 0);
+        let ray = pruned.iter().next().unwrap();
+        assert_eq!(ray.energy(), joule!(0.1));
+        assert_eq!(
+            ray.termination_reason(),
+            Some(TerminationReason::EnergyThreshold)
+        );
+    }
+    #[test]
     fn apodize() {
         let mut rays = Rays::default();
         let ray0 = Ray::new_collimated(Point3::origin(), nanometer!(1053.0), joule!(1.0)).unwrap();
@@ -2349,6 +2788,45 @@ mod test {
         assert_eq!(rays.total_energy(), joule!(1.0));
     }
     #[test]
+    fn apodize_annulus() {
+        let mut rays = Rays::default();
+        for x in [0.0, 0.5, 1.5, 2.5] {
+            let ray =
+                Ray::new_collimated(millimeter!(x, 0.0, 0.0), nanometer!(1053.0), joule!(1.0))
+                    .unwrap();
+            rays.add_ray(ray);
+        }
+        let annulus_config =
+            AnnulusConfig::new(millimeter!(1.0), millimeter!(2.0), millimeter!(0.0, 0.0)).unwrap();
+        let aperture = Aperture::BinaryAnnulus(annulus_config);
+        assert!(rays.apodize(&aperture, &Isometry::identity()).unwrap());
+        assert_eq!(rays.total_energy(), joule!(1.0));
+        let valid_rays = rays.iter().filter(|r| r.valid()).collect::<Vec<_>>();
+        assert_eq!(valid_rays.len(), 1);
+        assert_eq!(valid_rays[0].position().x, millimeter!(1.5));
+    }
+    #[test]
+    fn apodize_gaussian_apodizer() {
+        let mut rays = Rays::default();
+        for x in [0.0, 1.0, 3.0] {
+            let ray =
+                Ray::new_collimated(millimeter!(x, 0.0, 0.0), nanometer!(1053.0), joule!(1.0))
+                    .unwrap();
+            rays.add_ray(ray);
+        }
+        let config =
+            GaussianApodizerConfig::new(millimeter!(1.0), millimeter!(2.0), millimeter!(0.0, 0.0))
+                .unwrap();
+        let aperture = Aperture::GaussianApodizer(config);
+        assert!(rays.apodize(&aperture, &Isometry::identity()).unwrap());
+        assert_eq!(rays.nr_of_rays(true), 2);
+        assert_relative_eq!(
+            rays.total_energy().get::<joule>(),
+            1.0 + f64::exp(-2.0),
+            epsilon = 1e-12
+        );
+    }
+    #[test]
     fn wavelength_range() {
         let e = joule!(1.0);
         let mut rays = Rays::default();
@@ -2455,6 +2933,29 @@ mod test {
         );
     }
 
+    #[test]
+    fn polylines() {
+        let mut rays = Rays::new_uniform_collimated(
+            nanometer!(1053.0),
+            joule!(1.0),
+            &Hexapolar::new(millimeter!(1.0), 1).unwrap(),
+        )
+        .unwrap();
+        assert!(Rays::default().polylines(10).is_empty());
+        assert!(rays.polylines(0).is_empty());
+        let _ = propagate(&mut rays, millimeter!(1.0));
+        rays.iter_mut()
+            .next()
+            .unwrap()
+            .terminate(TerminationReason::Aperture);
+        let polylines = rays.polylines(3);
+        assert_eq!(polylines.len(), 3);
+        assert!(!polylines.polylines()[0].valid());
+        assert!(polylines.polylines()[1].valid());
+        assert_eq!(polylines.polylines()[0].positions().len(), 2);
+        assert_eq!(polylines.polylines()[0].wavelength(), nanometer!(1053.0));
+        assert_eq!(rays.polylines(100).len(), 7);
+    }
     #[test]
     #[ignore]
     fn get_rays_position_history_in_mm() {
@@ -0,0 +1,299 @@
+//! Catalog of common optical glasses
+//!
+//! This module contains the Sellmeier coefficients of frequently used optical materials (mainly taken from the Schott
+//! datasheets). Materials can be looked up by name using [`glass`]. Names are matched case-insensitively while
+//! ignoring spaces, hyphens and underscores, thus `N-BK7`, `n-bk7` and `NBK7` refer to the same glass.
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Length;
+use uom::si::length::micrometer;
+
+use crate::error::{OpmResult, OpossumError};
+
+use super::{RefrIndexSellmeier1, RefractiveIndex, RefractiveIndexType};
+
+/// Sellmeier coefficients of a catalog glass.
+struct GlassData {
+    names: &'static [&'static str],
+    b: [f64; 3],
+    c: [f64; 3],
+    wvl_range_um: (f64, f64),
+}
+
+const GLASSES: &[GlassData] = &[
+    GlassData {
+        names: &["N-BK7"],
+        b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+        c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+        wvl_range_um: (0.3, 2.5),
+    },
+    GlassData {
+        names: &["N-BAK1"],
+        b: [1.123_656_62, 0.309_276_848, 0.881_511_957],
+        c: [0.006_447_427_52, 0.022_228_440_2, 107.297_751],
+        wvl_range_um: (0.3, 2.5),
+    },
+    GlassData {
+        names: &["N-FK51A"],
+        b: [0.971_247_817, 0.216_901_417, 0.904_651_666],
+        c: [0.004_723_019_95, 0.015_357_561_2, 168.681_33],
+        wvl_range_um: (0.31, 2.5),
+    },
+    GlassData {
+        names: &["N-LAK22"],
+        b: [1.142_297_81, 0.535_138_441, 1.040_883_85],
+        c: [0.005_857_785_94, 0.019_854_614_7, 100.834_017],
+        wvl_range_um: (0.31, 2.5),
+    },
+    GlassData {
+        names: &["F2"],
+        b: [1.345_333_59, 0.209_073_176, 0.937_357_162],
+        c: [0.009_977_438_71, 0.047_045_076_7, 111.886_764],
+        wvl_range_um: (0.32, 2.5),
+    },
+    GlassData {
+        names: &["N-SF5"],
+        b: [1.524_818_89, 0.187_085_527, 1.427_290_15],
+        c: [0.011_254_756, 0.058_899_539_2, 129.141_675],
+        wvl_range_um: (0.37, 2.5),
+    },
+    GlassData {
+        names: &["N-SF6"],
+        b: [1.779_317_63, 0.338_149_866, 2.087_344_74],
+        c: [0.013_371_418_2, 0.061_753_362_1, 174.017_59],
+        wvl_range_um: (0.37, 2.5),
+    },
+    GlassData {
+        names: &["N-SF10"],
+        b: [1.621_539_02, 0.256_287_842, 1.644_475_52],
+        c: [0.012_224_145_7, 0.059_573_677_5, 147.468_793],
+        wvl_range_um: (0.38, 2.5),
+    },
+    GlassData {
+        names: &["N-SF11"],
+        b: [1.737_596_95, 0.313_747_346, 1.898_781_01],
+        c: [0.013_188_707, 0.062_306_814_2, 155.236_29],
+        wvl_range_um: (0.37, 2.5),
+    },
+    // Malitson, J. Opt. Soc. Am. 55, 1205-1209 (1965)
+    GlassData {
+        names: &["Fused Silica", "SiO2"],
+        b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+        c: [0.004_679_148, 0.013_512_063, 97.934_002_5],
+        wvl_range_um: (0.21, 3.71),
+    },
+    // Malitson, Appl. Opt. 2, 1103-1107 (1963)
+    GlassData {
+        names: &["CaF2"],
+        b: [0.567_588_8, 0.471_091_4, 3.848_472_3],
+        c: [0.002_526_43, 0.010_078_333, 1_200.556],
+        wvl_range_um: (0.23, 9.7),
+    },
+    // ordinary ray, Malitson & Dodge, J. Opt. Soc. Am. 62, 1405 (1972)
+    GlassData {
+        names: &["Sapphire"],
+        b: [1.431_349_3, 0.650_547_13, 5.341_402_1],
+        c: [0.005_279_926, 0.014_238_26, 325.017_8],
+        wvl_range_um: (0.2, 5.5),
+    },
+];
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+/// Levenshtein distance of two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+/// Returns the names of all glasses in the catalog.
+#[must_use]
+pub fn glass_names() -> Vec<&'static str> {
+    GLASSES.iter().map(|glass| glass.names[0]).collect()
+}
+/// Look up a glass of the catalog by its name and return its Sellmeier model.
+///
+/// # Errors
+///
+/// This function will return an error if the glass is not found in the catalog. The error message contains similar
+/// glass names (if any).
+pub fn glass(name: &str) -> OpmResult<RefrIndexSellmeier1> {
+    let normalized = normalize(name);
+    if let Some(glass) = GLASSES
+        .iter()
+        .find(|glass| glass.names.iter().any(|n| normalize(n) == normalized))
+    {
+        return RefrIndexSellmeier1::new(
+            glass.b[0],
+            glass.b[1],
+            glass.b[2],
+            glass.c[0],
+            glass.c[1],
+            glass.c[2],
+            Length::new::<micrometer>(glass.wvl_range_um.0)
+                ..Length::new::<micrometer>(glass.wvl_range_um.1),
+        );
+    }
+    let near_matches: Vec<&str> = GLASSES
+        .iter()
+        .filter(|glass| {
+            glass.names.iter().any(|n| {
+                let n = normalize(n);
+                edit_distance(&n, &normalized) <= 2
+                    || (!normalized.is_empty() && n.contains(&normalized))
+            })
+        })
+        .map(|glass| glass.names[0])
+        .collect();
+    let message = if near_matches.is_empty() {
+        format!(
+            "glass <{name}> not found in catalog. Available glasses: {}",
+            glass_names().join(", ")
+        )
+    } else {
+        format!(
+            "glass <{name}> not found in catalog. Did you mean: {}?",
+            near_matches.join(", ")
+        )
+    };
+    Err(OpossumError::Other(message))
+}
+
+/// Refractive index model of a catalog glass.
+///
+/// In contrast to a plain Sellmeier model, this model is serialized by its glass name only. This way, nodes such as
+/// lenses or wedges can be configured by glass name in an OPM file (e.g. `Catalog("N-BK7")`).
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "String", into = "String")]
+pub struct RefrIndexCatalog {
+    name: String,
+    model: RefrIndexSellmeier1,
+}
+impl RefrIndexCatalog {
+    /// Create a new refractive index model of the catalog glass with the given name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the glass is not found in the catalog.
+    pub fn new(name: &str) -> OpmResult<Self> {
+        Ok(Self {
+            name: name.to_owned(),
+            model: glass(name)?,
+        })
+    }
+    /// Returns the glass name of this [`RefrIndexCatalog`].
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+impl TryFrom<String> for RefrIndexCatalog {
+    type Error = OpossumError;
+
+    fn try_from(name: String) -> OpmResult<Self> {
+        Self::new(&name)
+    }
+}
+impl From<RefrIndexCatalog> for String {
+    fn from(refr: RefrIndexCatalog) -> Self {
+        refr.name
+    }
+}
+impl RefractiveIndex for RefrIndexCatalog {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        self.model.get_refractive_index(wavelength)
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::Catalog(self.clone())
+    }
+}
+impl From<RefrIndexCatalog> for RefractiveIndexType {
+    fn from(refr: RefrIndexCatalog) -> Self {
+        Self::Catalog(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nanometer;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn glass_lookup() {
+        for (name, n_d) in [
+            ("N-BK7", 1.5168),
+            ("nbk7", 1.5168),
+            ("N-SF11", 1.784_72),
+            ("fused silica", 1.458_46),
+            ("SiO2", 1.458_46),
+            ("CaF2", 1.433_85),
+        ] {
+            let model = glass(name).unwrap();
+            assert_abs_diff_eq!(
+                model.get_refractive_index(nanometer!(587.56)).unwrap(),
+                n_d,
+                epsilon = 1e-4
+            );
+        }
+    }
+    #[test]
+    fn all_glasses_valid() {
+        for name in glass_names() {
+            let model = glass(name).unwrap();
+            let n = model.get_refractive_index(nanometer!(1000.0)).unwrap();
+            assert!(n > 1.0 && n < 2.0, "{name}");
+        }
+    }
+    #[test]
+    fn glass_unknown() {
+        assert_eq!(
+            glass("N-BK8").unwrap_err().to_string(),
+            "Opossum Error:Other:glass <N-BK8> not found in catalog. Did you mean: N-BK7, N-BAK1?"
+        );
+        assert!(
+            glass("Unobtainium")
+                .unwrap_err()
+                .to_string()
+                .contains("Available glasses: N-BK7")
+        );
+    }
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("", ""), 0);
+        assert_eq!(super::edit_distance("nbk7", "nbk7"), 0);
+        assert_eq!(super::edit_distance("nbk7", "nbk8"), 1);
+        assert_eq!(super::edit_distance("nbk7", "nbak1"), 2);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+    }
+    #[test]
+    fn catalog_model() {
+        assert!(RefrIndexCatalog::new("N-BK8").is_err());
+        let model = RefrIndexCatalog::new("N-BK7").unwrap();
+        assert_eq!(model.name(), "N-BK7");
+        assert!(matches!(model.to_enum(), RefractiveIndexType::Catalog(_)));
+        let serialized = ron::to_string(&model.to_enum()).unwrap();
+        assert_eq!(serialized, "Catalog(\"N-BK7\")");
+        let deserialized: RefractiveIndexType = ron::from_str("Catalog(\"n-sf11\")").unwrap();
+        assert_abs_diff_eq!(
+            deserialized
+                .get_refractive_index(nanometer!(587.56))
+                .unwrap(),
+            1.784_72,
+            epsilon = 1e-4
+        );
+        assert!(ron::from_str::<RefractiveIndexType>("Catalog(\"N-BK8\")").is_err());
+    }
+}
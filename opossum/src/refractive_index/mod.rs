@@ -1,24 +1,38 @@
 //! Module for handling the refractive index of an optical material.
 #![warn(missing_docs)]
 use serde::{Deserialize, Serialize};
-use uom::si::f64::Length;
+use std::path::Path;
+use uom::si::f64::{Length, ThermodynamicTemperature};
 
+pub mod catalog;
+pub mod refr_index_air;
+pub mod refr_index_cauchy;
 pub mod refr_index_conrady;
 pub mod refr_index_const;
+pub mod refr_index_gradient;
 pub mod refr_index_schott;
 pub mod refr_index_sellmeier1;
+pub mod refr_index_tabulated;
+pub mod refr_index_temp_coeff;
 
 use self::refr_index_schott::RefrIndexSchott;
+pub use catalog::RefrIndexCatalog;
+pub use refr_index_air::RefrIndexAir;
+pub use refr_index_cauchy::RefrIndexCauchy;
 pub use refr_index_conrady::RefrIndexConrady;
 pub use refr_index_const::RefrIndexConst;
 pub use refr_index_const::refr_index_vaccuum;
+pub use refr_index_gradient::GradientIndex;
 pub use refr_index_sellmeier1::RefrIndexSellmeier1;
+pub use refr_index_tabulated::RefrIndexTabulated;
+pub use refr_index_temp_coeff::RefrIndexTempCoeff;
 
 use crate::error::{OpmResult, OpossumError};
 use crate::properties::Proptype;
 
 /// Available models for the calculation of refractive index
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub enum RefractiveIndexType {
     /// Trivial model returning a wavelength-independant constant
     Const(RefrIndexConst),
@@ -28,6 +42,18 @@ pub enum RefractiveIndexType {
     Schott(RefrIndexSchott),
     /// Conrady model
     Conrady(RefrIndexConrady),
+    /// Cauchy model
+    Cauchy(RefrIndexCauchy),
+    /// Dry air model (Ciddor / Edlén)
+    Air(RefrIndexAir),
+    /// Temperature-dependent model based on another model
+    TempCoeff(RefrIndexTempCoeff),
+    /// Linearly interpolated tabulated data
+    Tabulated(RefrIndexTabulated),
+    /// Glass of the built-in [`catalog`]
+    Catalog(RefrIndexCatalog),
+    /// Gradient-index (GRIN) medium
+    Gradient(GradientIndex),
 }
 
 impl RefractiveIndexType {
@@ -50,6 +76,20 @@ impl RefractiveIndexType {
             Self::Conrady(refr_index_conrady) => {
                 refr_index_conrady.get_refractive_index(wavelength)?
             }
+            Self::Cauchy(refr_index_cauchy) => {
+                refr_index_cauchy.get_refractive_index(wavelength)?
+            }
+            Self::Air(refr_index_air) => refr_index_air.get_refractive_index(wavelength)?,
+            Self::TempCoeff(refr_index_temp_coeff) => {
+                refr_index_temp_coeff.get_refractive_index(wavelength)?
+            }
+            Self::Tabulated(refr_index_tabulated) => {
+                refr_index_tabulated.get_refractive_index(wavelength)?
+            }
+            Self::Catalog(refr_index_catalog) => {
+                refr_index_catalog.get_refractive_index(wavelength)?
+            }
+            Self::Gradient(gradient_index) => gradient_index.get_refractive_index(wavelength)?,
         };
         if refr_index < 1.0 || !refr_index.is_finite() {
             return Err(OpossumError::Other(
@@ -58,6 +98,52 @@ impl RefractiveIndexType {
         }
         Ok(refr_index)
     }
+    /// Create a tabulated refractive index model from a CSV file in the format of
+    /// [refractiveindex.info](https://refractiveindex.info).
+    ///
+    /// See [`RefrIndexTabulated::from_csv`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be read or contains malformed or invalid data.
+    pub fn from_csv(path: &Path) -> OpmResult<Self> {
+        Ok(RefrIndexTabulated::from_csv(path)?.into())
+    }
+    /// Extend this [`RefractiveIndexType`] by the given thermo-optic coefficients.
+    ///
+    /// This model is regarded as valid at the given reference temperature. See [`RefrIndexTempCoeff`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the given coefficients are not finite.
+    ///   - the reference temperature is not finite or below absolute zero.
+    pub fn with_thermal(
+        self,
+        dn_dt: f64,
+        d2n_dt2: f64,
+        reference_temperature: ThermodynamicTemperature,
+    ) -> OpmResult<Self> {
+        Ok(RefrIndexTempCoeff::new(self, dn_dt, d2n_dt2, reference_temperature)?.into())
+    }
+    /// Returns this [`RefractiveIndexType`] evaluated at the given ambient temperature.
+    ///
+    /// The ambient temperature is only applied to temperature-dependent models without an explicitly set
+    /// operating temperature. All other models are returned unchanged.
+    #[must_use]
+    pub fn at_ambient_temperature(&self, ambient: Option<ThermodynamicTemperature>) -> Self {
+        match (self, ambient) {
+            (Self::TempCoeff(refr_index_temp_coeff), Some(temperature))
+                if refr_index_temp_coeff.temperature().is_none() =>
+            {
+                let mut refr_index = refr_index_temp_coeff.clone();
+                // an invalid ambient temperature is ignored, thus falling back to the reference temperature
+                refr_index.set_temperature(Some(temperature)).ok();
+                Self::TempCoeff(refr_index)
+            }
+            _ => self.clone(),
+        }
+    }
 }
 
 impl From<RefractiveIndexType> for Proptype {
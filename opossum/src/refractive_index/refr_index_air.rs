@@ -0,0 +1,176 @@
+//! Dispersion model of air
+use serde::Deserialize;
+use serde::Serialize;
+use uom::si::f64::{Length, Pressure, ThermodynamicTemperature};
+use uom::si::length::micrometer;
+use uom::si::pressure::pascal;
+use uom::si::thermodynamic_temperature::degree_celsius;
+
+use crate::error::OpmResult;
+use crate::error::OpossumError;
+
+use super::{RefractiveIndex, RefractiveIndexType};
+
+/// Refractive index model of dry air.
+///
+/// The dispersion of standard air (15 °C, 101 325 Pa, 0 % humidity, 450 ppm CO₂) is calculated using the
+/// formula given by Ciddor (Appl. Opt. 35, 1566-1573 (1996)). Deviating temperatures and pressures are
+/// considered using the density correction of Edlén as revised by Birch & Downs (Metrologia 30, 155 (1993)).
+///
+/// The model is valid for wavelengths between 230 nm and 1690 nm.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct RefrIndexAir {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    temperature: ThermodynamicTemperature,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    pressure: Pressure,
+}
+impl RefrIndexAir {
+    /// Create a new refractive index model of dry air at the given temperature and pressure.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the temperature is not finite or below absolute zero.
+    ///   - the pressure is negative or not finite.
+    pub fn new(temperature: ThermodynamicTemperature, pressure: Pressure) -> OpmResult<Self> {
+        if !temperature.is_finite() || temperature.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "temperature must be finite and above absolute zero".into(),
+            ));
+        }
+        if !pressure.is_finite() || pressure.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "pressure must be positive and finite".into(),
+            ));
+        }
+        Ok(Self {
+            temperature,
+            pressure,
+        })
+    }
+    /// Create a refractive index model of standard air (15 °C, 101 325 Pa).
+    #[must_use]
+    pub fn standard() -> Self {
+        Self {
+            temperature: ThermodynamicTemperature::new::<degree_celsius>(15.0),
+            pressure: Pressure::new::<pascal>(101_325.0),
+        }
+    }
+    /// Returns the temperature of this [`RefrIndexAir`].
+    #[must_use]
+    pub const fn temperature(&self) -> ThermodynamicTemperature {
+        self.temperature
+    }
+    /// Returns the pressure of this [`RefrIndexAir`].
+    #[must_use]
+    pub const fn pressure(&self) -> Pressure {
+        self.pressure
+    }
+}
+impl Default for RefrIndexAir {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+impl RefractiveIndex for RefrIndexAir {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        let lambda = wavelength.get::<micrometer>();
+        if !(0.23..=1.69).contains(&lambda) {
+            return Err(OpossumError::Other("wavelength outside valid range".into()));
+        }
+        let sigma_sq = 1.0 / (lambda * lambda);
+        // Ciddor: standard air with 450 ppm CO2
+        let n_s = 0.057_921_05 / (238.0185 - sigma_sq) + 0.001_679_17 / (57.362 - sigma_sq);
+        // Edlén / Birch & Downs: correction for temperature and pressure
+        let t = self.temperature.get::<degree_celsius>();
+        let p = self.pressure.get::<pascal>();
+        let density_factor = p * 1.0e-8f64.mul_add((0.00972f64).mul_add(-t, 0.601) * p, 1.0)
+            / (96_095.43 * 0.003_661f64.mul_add(t, 1.0));
+        Ok(n_s.mul_add(density_factor, 1.0))
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::Air(self.clone())
+    }
+}
+impl From<RefrIndexAir> for RefractiveIndexType {
+    fn from(refr: RefrIndexAir) -> Self {
+        Self::Air(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::nanometer;
+    use approx::assert_relative_eq;
+
+    use super::*;
+    #[test]
+    fn new() {
+        let t = ThermodynamicTemperature::new::<degree_celsius>(20.0);
+        let p = Pressure::new::<pascal>(100_000.0);
+        let r = RefrIndexAir::new(t, p).unwrap();
+        assert_eq!(r.temperature(), t);
+        assert_eq!(r.pressure(), p);
+        assert!(
+            RefrIndexAir::new(ThermodynamicTemperature::new::<degree_celsius>(f64::NAN), p)
+                .is_err()
+        );
+        assert!(
+            RefrIndexAir::new(ThermodynamicTemperature::new::<degree_celsius>(-300.0), p).is_err()
+        );
+        assert!(RefrIndexAir::new(t, Pressure::new::<pascal>(-1.0)).is_err());
+        assert!(RefrIndexAir::new(t, Pressure::new::<pascal>(f64::INFINITY)).is_err());
+    }
+    #[test]
+    fn default() {
+        let r = RefrIndexAir::default();
+        assert_relative_eq!(r.temperature().get::<degree_celsius>(), 15.0);
+        assert_relative_eq!(r.pressure().get::<pascal>(), 101_325.0);
+    }
+    #[test]
+    fn get_refractive_index() {
+        let r = RefrIndexAir::standard();
+        assert_relative_eq!(
+            r.get_refractive_index(nanometer!(632.8)).unwrap(),
+            1.000_276_5,
+            epsilon = 1.0e-7
+        );
+        assert_relative_eq!(
+            r.get_refractive_index(nanometer!(1064.0)).unwrap(),
+            1.000_274_0,
+            epsilon = 1.0e-7
+        );
+        assert!(r.get_refractive_index(nanometer!(229.0)).is_err());
+        assert!(r.get_refractive_index(nanometer!(1700.0)).is_err());
+    }
+    #[test]
+    fn get_refractive_index_temp_pressure() {
+        let standard = RefrIndexAir::standard()
+            .get_refractive_index(nanometer!(632.8))
+            .unwrap();
+        let warm = RefrIndexAir::new(
+            ThermodynamicTemperature::new::<degree_celsius>(25.0),
+            Pressure::new::<pascal>(101_325.0),
+        )
+        .unwrap()
+        .get_refractive_index(nanometer!(632.8))
+        .unwrap();
+        assert!(warm < standard);
+        let vacuum = RefrIndexAir::new(
+            ThermodynamicTemperature::new::<degree_celsius>(15.0),
+            Pressure::new::<pascal>(0.0),
+        )
+        .unwrap()
+        .get_refractive_index(nanometer!(632.8))
+        .unwrap();
+        assert_relative_eq!(vacuum, 1.0);
+    }
+    #[test]
+    fn get_enum() {
+        assert!(matches!(
+            RefrIndexAir::standard().to_enum(),
+            RefractiveIndexType::Air(_)
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+//! Cauchy model
+use std::ops::Range;
+
+use serde::Deserialize;
+use serde::Serialize;
+use uom::si::f64::Length;
+use uom::si::length::micrometer;
+
+use crate::error::OpmResult;
+use crate::error::OpossumError;
+
+use super::{RefractiveIndex, RefractiveIndexType};
+
+/// Refractive index model following Cauchy's equation.
+///
+/// The refractive index is calculated as `n(λ) = A + B / λ² + C / λ⁴` with the wavelength `λ` given in micrometers.
+/// Hence, the coefficients `B` and `C` have units of µm² and µm⁴ respectively.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct RefrIndexCauchy {
+    a: f64,
+    b: f64,
+    c: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "std::ops::Range<f64>"))]
+    wvl_range: Range<Length>,
+}
+impl RefrIndexCauchy {
+    /// Create a new refractive index model following Cauchy's equation.
+    ///
+    /// This model is commonly used for plastics and glasses in the visible range, where no Sellmeier coefficients are
+    /// available.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given coefficients are not finite or the wavelength range is invalid.
+    pub fn new(a: f64, b: f64, c: f64, wavelength_range: Range<Length>) -> OpmResult<Self> {
+        if !a.is_finite() || !b.is_finite() || !c.is_finite() {
+            return Err(OpossumError::Other(
+                "all coefficients must be finite.".into(),
+            ));
+        }
+        if !wavelength_range.start.is_normal() || wavelength_range.start.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "lower wavelength limit is invalid.".into(),
+            ));
+        }
+        if wavelength_range.end.is_sign_negative() || !wavelength_range.end.is_finite() {
+            return Err(OpossumError::Other(
+                "upper wavelength limit is invalid.".into(),
+            ));
+        }
+        Ok(Self {
+            a,
+            b,
+            c,
+            wvl_range: wavelength_range,
+        })
+    }
+}
+impl RefractiveIndex for RefrIndexCauchy {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        if !self.wvl_range.contains(&wavelength) {
+            return Err(OpossumError::Other("wavelength outside valid range".into()));
+        }
+        let lambda_sq = wavelength.get::<micrometer>().powi(2);
+        Ok(self.a + self.b / lambda_sq + self.c / (lambda_sq * lambda_sq))
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::Cauchy(self.clone())
+    }
+}
+impl From<RefrIndexCauchy> for RefractiveIndexType {
+    fn from(refr: RefrIndexCauchy) -> Self {
+        Self::Cauchy(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{nanometer, properties::Proptype, refractive_index::RefrIndexCatalog};
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+    #[test]
+    fn new_wrong() {
+        let valid_wvl = nanometer!(400.0)..nanometer!(700.0);
+        assert!(RefrIndexCauchy::new(1.5, 0.004, f64::NAN, valid_wvl.clone()).is_err());
+        assert!(RefrIndexCauchy::new(1.5, 0.004, f64::INFINITY, valid_wvl.clone()).is_err());
+        assert!(RefrIndexCauchy::new(1.5, f64::NAN, 0.0, valid_wvl.clone()).is_err());
+        assert!(RefrIndexCauchy::new(1.5, f64::INFINITY, 0.0, valid_wvl.clone()).is_err());
+        assert!(RefrIndexCauchy::new(f64::NAN, 0.004, 0.0, valid_wvl.clone()).is_err());
+        assert!(RefrIndexCauchy::new(f64::INFINITY, 0.004, 0.0, valid_wvl).is_err());
+
+        assert!(RefrIndexCauchy::new(1.5, 0.004, 0.0, nanometer!(0.0)..nanometer!(700.0)).is_err());
+        assert!(
+            RefrIndexCauchy::new(1.5, 0.004, 0.0, nanometer!(-1.0)..nanometer!(700.0)).is_err()
+        );
+        assert!(
+            RefrIndexCauchy::new(1.5, 0.004, 0.0, nanometer!(f64::NAN)..nanometer!(700.0)).is_err()
+        );
+        assert!(
+            RefrIndexCauchy::new(1.5, 0.004, 0.0, nanometer!(400.0)..nanometer!(-1.0)).is_err()
+        );
+        assert!(
+            RefrIndexCauchy::new(
+                1.5,
+                0.004,
+                0.0,
+                nanometer!(400.0)..nanometer!(f64::INFINITY)
+            )
+            .is_err()
+        );
+    }
+    #[test]
+    fn new() {
+        let r = RefrIndexCauchy::new(1.0, 2.0, 3.0, nanometer!(400.0)..nanometer!(700.0)).unwrap();
+        assert_eq!(r.a, 1.0);
+        assert_eq!(r.b, 2.0);
+        assert_eq!(r.c, 3.0);
+    }
+    #[test]
+    fn get_refractive_index() {
+        let i =
+            RefrIndexCauchy::new(1.5, 0.01, 0.001, nanometer!(400.0)..nanometer!(700.0)).unwrap();
+        assert_relative_eq!(
+            i.get_refractive_index(nanometer!(500.0)).unwrap(),
+            1.5 + 0.01 / 0.25 + 0.001 / 0.0625
+        );
+        assert!(i.get_refractive_index(nanometer!(399.0)).is_err());
+        assert!(i.get_refractive_index(nanometer!(700.0)).is_err());
+    }
+    #[test]
+    fn compare_with_bk7() {
+        // two-term Cauchy approximation of N-BK7 in the visible range
+        let cauchy =
+            RefrIndexCauchy::new(1.5046, 0.00420, 0.0, nanometer!(400.0)..nanometer!(701.0))
+                .unwrap();
+        let sellmeier = RefrIndexCatalog::new("N-BK7").unwrap();
+        for wvl in (400..=700).step_by(25) {
+            let wvl = nanometer!(f64::from(wvl));
+            assert_abs_diff_eq!(
+                cauchy.get_refractive_index(wvl).unwrap(),
+                sellmeier.get_refractive_index(wvl).unwrap(),
+                epsilon = 2e-4
+            );
+        }
+    }
+    #[test]
+    fn serialize_proptype() {
+        let i =
+            RefrIndexCauchy::new(1.5, 0.004, 0.0, nanometer!(400.0)..nanometer!(700.0)).unwrap();
+        let prop: Proptype = i.to_enum().into();
+        let serialized = ron::to_string(&prop).unwrap();
+        let deserialized: Proptype = ron::from_str(&serialized).unwrap();
+        let Proptype::RefractiveIndex(RefractiveIndexType::Cauchy(r)) = deserialized else {
+            panic!("wrong proptype");
+        };
+        assert_eq!(r.a, 1.5);
+        assert_eq!(r.b, 0.004);
+        assert_eq!(r.wvl_range, nanometer!(400.0)..nanometer!(700.0));
+    }
+    #[test]
+    fn get_enum() {
+        let i = RefrIndexCauchy::new(1.5, 0.0, 0.0, nanometer!(1.0)..nanometer!(2.0)).unwrap();
+        assert!(matches!(i.to_enum(), RefractiveIndexType::Cauchy(_)));
+    }
+}
@@ -14,10 +14,12 @@ use super::{RefractiveIndex, RefractiveIndexType};
 
 /// Refractive index model following the Conrady formula.
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct RefrIndexConrady {
     n0: f64,
     a: f64,
     b: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "std::ops::Range<f64>"))]
     wvl_range: Range<Length>,
 }
 impl RefrIndexConrady {
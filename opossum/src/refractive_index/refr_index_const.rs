@@ -19,6 +19,7 @@ pub fn refr_index_vaccuum() -> RefractiveIndexType {
 }
 /// Constant refractive index model
 #[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct RefrIndexConst {
     refractive_index: f64,
 }
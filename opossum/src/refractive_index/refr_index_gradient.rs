@@ -0,0 +1,195 @@
+//! Gradient-index (GRIN) model
+//!
+//! This model describes a medium with a spatially varying refractive index. Rays propagating through such a medium
+//! follow curved paths, which are calculated by [`Ray::propagate_in_gradient_index`](crate::ray::Ray::propagate_in_gradient_index).
+use nalgebra::{Point3, Vector3};
+use serde::Deserialize;
+use serde::Serialize;
+use uom::si::f64::Length;
+
+use crate::error::OpmResult;
+use crate::error::OpossumError;
+
+use super::{RefractiveIndex, RefractiveIndexType};
+
+/// Refractive index model of a gradient-index (GRIN) medium.
+///
+/// The refractive index of a base (dispersion) model is extended by a radial and an axial index profile:
+///
+/// `n(λ, r, z) = n₀(λ) + n_r2 · r² + n_r4 · r⁴ + n_z1 · z + n_z2 · z²`
+///
+/// with `r` being the distance from and `z` the position along the optical axis (local z axis) of the medium. All
+/// coordinates are given in meters. Hence, the coefficients have units of 1/m², 1/m⁴, 1/m and 1/m² respectively.
+///
+/// Used as an ordinary [`RefractiveIndexType`], this model returns the refractive index on the axis at `z = 0`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct GradientIndex {
+    base: Box<RefractiveIndexType>,
+    n_r2: f64,
+    n_r4: f64,
+    n_z1: f64,
+    n_z2: f64,
+}
+impl GradientIndex {
+    /// Create a new gradient-index model with radial and axial index profile.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given coefficients are not finite.
+    pub fn new(
+        base: RefractiveIndexType,
+        n_r2: f64,
+        n_r4: f64,
+        n_z1: f64,
+        n_z2: f64,
+    ) -> OpmResult<Self> {
+        if !n_r2.is_finite() || !n_r4.is_finite() || !n_z1.is_finite() || !n_z2.is_finite() {
+            return Err(OpossumError::Other(
+                "all coefficients must be finite.".into(),
+            ));
+        }
+        Ok(Self {
+            base: Box::new(base),
+            n_r2,
+            n_r4,
+            n_z1,
+            n_z2,
+        })
+    }
+    /// Create a new gradient-index model with a purely radial index profile.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given coefficients are not finite.
+    pub fn radial(base: RefractiveIndexType, n_r2: f64, n_r4: f64) -> OpmResult<Self> {
+        Self::new(base, n_r2, n_r4, 0.0, 0.0)
+    }
+    /// Create a new gradient-index model with a purely axial index profile.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given coefficients are not finite.
+    pub fn axial(base: RefractiveIndexType, n_z1: f64, n_z2: f64) -> OpmResult<Self> {
+        Self::new(base, 0.0, 0.0, n_z1, n_z2)
+    }
+    /// Returns the refractive index at the given position (in local coordinates of the medium) for the given wavelength.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the base model cannot be evaluated for the given wavelength.
+    ///   - the resulting refractive index is < 1.0 or not finite.
+    pub fn index_at(&self, wavelength: Length, position: &Point3<Length>) -> OpmResult<f64> {
+        let n = self.base.get_refractive_index(wavelength)? + self.profile(position);
+        if n < 1.0 || !n.is_finite() {
+            return Err(OpossumError::Other(
+                "refractive index of gradient-index medium is <1.0 or not finite".into(),
+            ));
+        }
+        Ok(n)
+    }
+    /// Returns the gradient of the refractive index (in 1/m) at the given position (in local coordinates of the medium).
+    ///
+    /// Since the index profile is independent of the base model, the gradient does not depend on the wavelength.
+    #[must_use]
+    pub fn gradient_at(&self, position: &Point3<Length>) -> Vector3<f64> {
+        let (x, y, z) = (position.x.value, position.y.value, position.z.value);
+        let r_sq = x.mul_add(x, y * y);
+        let radial = 2.0f64.mul_add(self.n_r2, 4.0 * self.n_r4 * r_sq);
+        Vector3::new(
+            radial * x,
+            radial * y,
+            (2.0 * self.n_z2).mul_add(z, self.n_z1),
+        )
+    }
+    fn profile(&self, position: &Point3<Length>) -> f64 {
+        let (x, y, z) = (position.x.value, position.y.value, position.z.value);
+        let r_sq = x.mul_add(x, y * y);
+        self.n_r4
+            .mul_add(r_sq, self.n_r2)
+            .mul_add(r_sq, self.n_z2.mul_add(z, self.n_z1) * z)
+    }
+}
+impl RefractiveIndex for GradientIndex {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        self.base.get_refractive_index(wavelength)
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::Gradient(self.clone())
+    }
+}
+impl From<GradientIndex> for RefractiveIndexType {
+    fn from(refr: GradientIndex) -> Self {
+        Self::Gradient(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{meter, millimeter, nanometer, refractive_index::RefrIndexConst};
+    use approx::assert_abs_diff_eq;
+
+    fn base() -> RefractiveIndexType {
+        RefrIndexConst::new(1.5).unwrap().into()
+    }
+    #[test]
+    fn new() {
+        assert!(GradientIndex::new(base(), f64::NAN, 0.0, 0.0, 0.0).is_err());
+        assert!(GradientIndex::new(base(), 0.0, f64::INFINITY, 0.0, 0.0).is_err());
+        assert!(GradientIndex::new(base(), 0.0, 0.0, f64::NAN, 0.0).is_err());
+        assert!(GradientIndex::new(base(), 0.0, 0.0, 0.0, f64::NEG_INFINITY).is_err());
+        let g = GradientIndex::new(base(), 1.0, 2.0, 3.0, 4.0).unwrap();
+        assert_eq!(g.n_r2, 1.0);
+        assert_eq!(g.n_r4, 2.0);
+        assert_eq!(g.n_z1, 3.0);
+        assert_eq!(g.n_z2, 4.0);
+    }
+    #[test]
+    fn index_at() {
+        let g = GradientIndex::radial(base(), -1000.0, 0.0).unwrap();
+        assert_eq!(
+            g.index_at(nanometer!(1000.0), &millimeter!(0.0, 0.0, 5.0))
+                .unwrap(),
+            1.5
+        );
+        assert_abs_diff_eq!(
+            g.index_at(nanometer!(1000.0), &millimeter!(3.0, 4.0, 0.0))
+                .unwrap(),
+            1.5 - 0.025
+        );
+        // index below 1.0
+        assert!(
+            g.index_at(nanometer!(1000.0), &meter!(1.0, 0.0, 0.0))
+                .is_err()
+        );
+        let g = GradientIndex::axial(base(), 2.0, 10.0).unwrap();
+        assert_abs_diff_eq!(
+            g.index_at(nanometer!(1000.0), &meter!(1.0, 0.0, 0.1))
+                .unwrap(),
+            1.5 + 0.2 + 0.1
+        );
+        assert_eq!(g.get_refractive_index(nanometer!(1000.0)).unwrap(), 1.5);
+    }
+    #[test]
+    fn gradient_at() {
+        let g = GradientIndex::new(base(), -1000.0, 500.0, 2.0, 10.0).unwrap();
+        let h = 1.0e-7;
+        let p = meter!(0.01, -0.02, 0.03);
+        let grad = g.gradient_at(&p);
+        let num = Vector3::new(
+            (g.profile(&meter!(0.01 + h, -0.02, 0.03)) - g.profile(&meter!(0.01 - h, -0.02, 0.03)))
+                / (2.0 * h),
+            (g.profile(&meter!(0.01, -0.02 + h, 0.03)) - g.profile(&meter!(0.01, -0.02 - h, 0.03)))
+                / (2.0 * h),
+            (g.profile(&meter!(0.01, -0.02, 0.03 + h)) - g.profile(&meter!(0.01, -0.02, 0.03 - h)))
+                / (2.0 * h),
+        );
+        assert_abs_diff_eq!(grad, num, epsilon = 1e-6);
+    }
+    #[test]
+    fn get_enum() {
+        let g = GradientIndex::radial(base(), -1000.0, 0.0).unwrap();
+        assert!(matches!(g.to_enum(), RefractiveIndexType::Gradient(_)));
+    }
+}
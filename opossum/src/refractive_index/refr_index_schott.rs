@@ -13,6 +13,7 @@ use super::{RefractiveIndex, RefractiveIndexType};
 
 /// Refractive index model following the Schott equation.
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct RefrIndexSchott {
     a0: f64,
     a1: f64,
@@ -20,6 +21,7 @@ pub struct RefrIndexSchott {
     a3: f64,
     a4: f64,
     a5: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "std::ops::Range<f64>"))]
     wvl_range: Range<Length>,
 }
 impl RefrIndexSchott {
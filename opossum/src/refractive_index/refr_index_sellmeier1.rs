@@ -13,6 +13,7 @@ use super::{RefractiveIndex, RefractiveIndexType};
 
 /// Sellmeier (1) model for calculation of a refractive index.
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct RefrIndexSellmeier1 {
     k1: f64,
     k2: f64,
@@ -20,6 +21,7 @@ pub struct RefrIndexSellmeier1 {
     l1: f64,
     l2: f64,
     l3: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "std::ops::Range<f64>"))]
     wvl_range: Range<Length>,
 }
 impl RefrIndexSellmeier1 {
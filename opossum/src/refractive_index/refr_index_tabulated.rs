@@ -0,0 +1,366 @@
+//! Tabulated refractive index model
+//!
+//! This model linearly interpolates measured refractive index data, e.g. exported from
+//! [refractiveindex.info](https://refractiveindex.info).
+use std::{fs::File, path::Path};
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+use log::warn;
+use serde::Deserialize;
+use serde::Serialize;
+use uom::si::f64::Length;
+use uom::si::length::micrometer;
+
+use crate::error::OpmResult;
+use crate::error::OpossumError;
+
+use super::{RefractiveIndex, RefractiveIndexType};
+
+/// Refractive index model based on tabulated data.
+///
+/// The model consists of a list of wavelengths with the corresponding refractive index `n` and (optionally) the
+/// extinction coefficient `k`. Values in between are linearly interpolated. Outside the tabulated wavelength range,
+/// the boundary values are used and a warning is issued.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct RefrIndexTabulated {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<f64>"))]
+    wavelengths: Vec<Length>,
+    n: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    k: Option<Vec<f64>>,
+}
+impl RefrIndexTabulated {
+    /// Create a new tabulated refractive index model.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the table is empty or the number of wavelengths and values does not match.
+    ///   - the wavelengths are not positive, finite and strictly increasing.
+    ///   - the refractive indices are not finite.
+    ///   - the extinction coefficients are negative or not finite.
+    pub fn new(wavelengths: Vec<Length>, n: Vec<f64>, k: Option<Vec<f64>>) -> OpmResult<Self> {
+        if wavelengths.is_empty() {
+            return Err(OpossumError::Other(
+                "tabulated data must not be empty".into(),
+            ));
+        }
+        if n.len() != wavelengths.len() || k.as_ref().is_some_and(|k| k.len() != wavelengths.len())
+        {
+            return Err(OpossumError::Other(
+                "number of wavelengths and tabulated values must match".into(),
+            ));
+        }
+        if wavelengths
+            .iter()
+            .any(|w| !w.is_normal() || w.is_sign_negative())
+        {
+            return Err(OpossumError::Other(
+                "wavelengths must be positive and finite".into(),
+            ));
+        }
+        if wavelengths.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(OpossumError::Other(
+                "wavelengths must be strictly increasing".into(),
+            ));
+        }
+        if n.iter().any(|n| !n.is_finite()) {
+            return Err(OpossumError::Other(
+                "refractive indices must be finite".into(),
+            ));
+        }
+        if k.as_ref()
+            .is_some_and(|k| k.iter().any(|k| !k.is_finite() || k.is_sign_negative()))
+        {
+            return Err(OpossumError::Other(
+                "extinction coefficients must be positive and finite".into(),
+            ));
+        }
+        Ok(Self { wavelengths, n, k })
+    }
+    /// Create a new tabulated refractive index model from a CSV file in the format of
+    /// [refractiveindex.info](https://refractiveindex.info).
+    ///
+    /// The wavelengths are given in micrometers. The file might contain
+    ///   - a single table with a `wl,n` or `wl,n,k` header, or
+    ///   - a `wl,n` table followed by a `wl,k` table. In this case, the extinction coefficients are interpolated to the
+    ///     wavelengths of the refractive index table.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the file could not be opened or read.
+    ///   - the file contains malformed lines or unknown columns.
+    ///   - the file does not contain refractive index data.
+    ///   - the data is not valid (see [`RefrIndexTabulated::new`]).
+    pub fn from_csv(path: &Path) -> OpmResult<Self> {
+        let file = File::open(path).map_err(|e| OpossumError::Other(e.to_string()))?;
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .from_reader(file);
+        let mut columns = vec!["wl".to_owned(), "n".to_owned()];
+        let mut n_table: Vec<(f64, f64)> = Vec::new();
+        let mut k_table: Vec<(f64, f64)> = Vec::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| OpossumError::Other(e.to_string()))?;
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            let Ok(values) = parse_record(&record) else {
+                columns = parse_header(&record, line)?;
+                continue;
+            };
+            if values.len() != columns.len() {
+                return Err(OpossumError::Other(format!(
+                    "wrong number of values in line {}",
+                    line + 1
+                )));
+            }
+            for (column, value) in columns.iter().zip(&values).skip(1) {
+                match column.as_str() {
+                    "n" => n_table.push((values[0], *value)),
+                    _ => k_table.push((values[0], *value)),
+                }
+            }
+        }
+        if n_table.is_empty() {
+            return Err(OpossumError::Other(
+                "no refractive index data found in file".into(),
+            ));
+        }
+        let wavelengths: Vec<Length> = n_table
+            .iter()
+            .map(|(wvl, _)| Length::new::<micrometer>(*wvl))
+            .collect();
+        let n: Vec<f64> = n_table.iter().map(|(_, n)| *n).collect();
+        let k = if k_table.is_empty() {
+            None
+        } else {
+            let k_wavelengths: Vec<Length> = k_table
+                .iter()
+                .map(|(wvl, _)| Length::new::<micrometer>(*wvl))
+                .collect();
+            let k_values: Vec<f64> = k_table.iter().map(|(_, k)| *k).collect();
+            let k_model = Self::new(k_wavelengths, k_values, None)?;
+            Some(
+                wavelengths
+                    .iter()
+                    .map(|wvl| k_model.interpolate_n(*wvl))
+                    .collect(),
+            )
+        };
+        Self::new(wavelengths, n, k)
+    }
+    /// Returns the tabulated wavelengths of this [`RefrIndexTabulated`].
+    #[must_use]
+    pub fn wavelengths(&self) -> &[Length] {
+        &self.wavelengths
+    }
+    /// Get the (interpolated) extinction coefficient `k` for the given wavelength.
+    ///
+    /// This function returns `None` if the model does not contain extinction data.
+    #[must_use]
+    pub fn get_extinction_coefficient(&self, wavelength: Length) -> Option<f64> {
+        self.k
+            .as_ref()
+            .map(|k| self.interpolate(k, self.clamped_wavelength(wavelength)))
+    }
+    fn interpolate_n(&self, wavelength: Length) -> f64 {
+        self.interpolate(&self.n, self.clamped_wavelength(wavelength))
+    }
+    fn clamped_wavelength(&self, wavelength: Length) -> Length {
+        let first = self.wavelengths[0];
+        let last = self.wavelengths[self.wavelengths.len() - 1];
+        if wavelength < first || wavelength > last {
+            warn!(
+                "wavelength {:.3} µm outside tabulated range ({:.3} µm - {:.3} µm) ... using boundary value",
+                wavelength.get::<micrometer>(),
+                first.get::<micrometer>(),
+                last.get::<micrometer>()
+            );
+        }
+        wavelength.max(first).min(last)
+    }
+    fn interpolate(&self, values: &[f64], wavelength: Length) -> f64 {
+        let idx = self.wavelengths.partition_point(|w| *w < wavelength);
+        if idx == 0 {
+            return values[0];
+        }
+        let t = ((wavelength - self.wavelengths[idx - 1])
+            / (self.wavelengths[idx] - self.wavelengths[idx - 1]))
+            .value;
+        t.mul_add(values[idx] - values[idx - 1], values[idx - 1])
+    }
+}
+fn parse_record(record: &StringRecord) -> OpmResult<Vec<f64>> {
+    record
+        .iter()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .map_err(|e| OpossumError::Other(e.to_string()))
+        })
+        .collect()
+}
+fn parse_header(record: &StringRecord, line: usize) -> OpmResult<Vec<String>> {
+    let columns: Vec<String> = record.iter().map(str::to_lowercase).collect();
+    let valid = columns.len() >= 2
+        && columns[0] == "wl"
+        && matches!(
+            columns[1..]
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .as_slice(),
+            ["n"] | ["k"] | ["n", "k"]
+        );
+    if valid {
+        Ok(columns)
+    } else {
+        Err(OpossumError::Other(format!(
+            "malformed line {} in refractive index file",
+            line + 1
+        )))
+    }
+}
+impl RefractiveIndex for RefrIndexTabulated {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        Ok(self.interpolate_n(wavelength))
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::Tabulated(self.clone())
+    }
+}
+impl From<RefrIndexTabulated> for RefractiveIndexType {
+    fn from(refr: RefrIndexTabulated) -> Self {
+        Self::Tabulated(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{nanometer, utils::test_helper::test_helper::check_logs};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn new() {
+        let wvls = vec![nanometer!(500.0), nanometer!(1000.0)];
+        assert!(RefrIndexTabulated::new(vec![], vec![], None).is_err());
+        assert!(RefrIndexTabulated::new(wvls.clone(), vec![1.5], None).is_err());
+        assert!(RefrIndexTabulated::new(wvls.clone(), vec![1.5, 1.4], Some(vec![0.0])).is_err());
+        assert!(
+            RefrIndexTabulated::new(
+                vec![nanometer!(1000.0), nanometer!(500.0)],
+                vec![1.5, 1.4],
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            RefrIndexTabulated::new(
+                vec![nanometer!(500.0), nanometer!(500.0)],
+                vec![1.5, 1.4],
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            RefrIndexTabulated::new(
+                vec![nanometer!(-500.0), nanometer!(500.0)],
+                vec![1.5, 1.4],
+                None
+            )
+            .is_err()
+        );
+        assert!(RefrIndexTabulated::new(wvls.clone(), vec![1.5, f64::NAN], None).is_err());
+        assert!(
+            RefrIndexTabulated::new(wvls.clone(), vec![1.5, 1.4], Some(vec![0.0, -1.0])).is_err()
+        );
+        let r = RefrIndexTabulated::new(wvls, vec![1.5, 1.4], Some(vec![0.0, 0.1])).unwrap();
+        assert_eq!(r.wavelengths(), [nanometer!(500.0), nanometer!(1000.0)]);
+    }
+    #[test]
+    fn get_refractive_index() {
+        let r = RefrIndexTabulated::new(
+            vec![nanometer!(500.0), nanometer!(1000.0), nanometer!(2000.0)],
+            vec![1.5, 1.4, 1.2],
+            Some(vec![0.0, 0.1, 0.3]),
+        )
+        .unwrap();
+        assert_relative_eq!(r.get_refractive_index(nanometer!(500.0)).unwrap(), 1.5);
+        assert_relative_eq!(r.get_refractive_index(nanometer!(750.0)).unwrap(), 1.45);
+        assert_relative_eq!(r.get_refractive_index(nanometer!(1500.0)).unwrap(), 1.3);
+        assert_relative_eq!(r.get_refractive_index(nanometer!(2000.0)).unwrap(), 1.2);
+        assert_relative_eq!(
+            r.get_extinction_coefficient(nanometer!(1500.0)).unwrap(),
+            0.2
+        );
+        testing_logger::setup();
+        assert_relative_eq!(r.get_refractive_index(nanometer!(3000.0)).unwrap(), 1.2);
+        check_logs(
+            log::Level::Warn,
+            vec![
+                "wavelength 3.000 µm outside tabulated range (0.500 µm - 2.000 µm) ... using boundary value",
+            ],
+        );
+        assert_relative_eq!(r.get_refractive_index(nanometer!(100.0)).unwrap(), 1.5);
+        let r = RefrIndexTabulated::new(vec![nanometer!(500.0)], vec![1.5], None).unwrap();
+        assert_relative_eq!(r.get_refractive_index(nanometer!(500.0)).unwrap(), 1.5);
+        assert!(r.get_extinction_coefficient(nanometer!(500.0)).is_none());
+    }
+    #[test]
+    fn from_csv() {
+        let r = RefrIndexTabulated::from_csv(Path::new(
+            "files_for_testing/refractive_index/n_only.csv",
+        ))
+        .unwrap();
+        assert_eq!(r.wavelengths().len(), 4);
+        assert!(r.get_extinction_coefficient(nanometer!(1000.0)).is_none());
+        assert_relative_eq!(r.get_refractive_index(nanometer!(750.0)).unwrap(), 1.51);
+
+        let r = RefrIndexTabulated::from_csv(Path::new(
+            "files_for_testing/refractive_index/n_k_sections.csv",
+        ))
+        .unwrap();
+        assert_eq!(r.wavelengths().len(), 4);
+        assert_relative_eq!(r.get_refractive_index(nanometer!(750.0)).unwrap(), 1.51);
+        assert_relative_eq!(
+            r.get_extinction_coefficient(nanometer!(1000.0)).unwrap(),
+            0.002
+        );
+
+        let r = RefrIndexTabulated::from_csv(Path::new(
+            "files_for_testing/refractive_index/n_k_columns.csv",
+        ))
+        .unwrap();
+        assert_relative_eq!(r.get_refractive_index(nanometer!(1000.0)).unwrap(), 1.5);
+        assert_relative_eq!(
+            r.get_extinction_coefficient(nanometer!(750.0)).unwrap(),
+            0.0015
+        );
+    }
+    #[test]
+    fn from_csv_wrong() {
+        assert!(RefrIndexTabulated::from_csv(Path::new("invalid_file_path/invalid.csv")).is_err());
+        for file in [
+            "not_monotonic.csv",
+            "malformed.csv",
+            "wrong_column_count.csv",
+            "k_only.csv",
+        ] {
+            let path = Path::new("files_for_testing/refractive_index").join(file);
+            assert!(
+                RefrIndexTabulated::from_csv(&path).is_err(),
+                "{file} should not be parsed"
+            );
+        }
+    }
+    #[test]
+    fn get_enum() {
+        let r = RefrIndexTabulated::new(vec![nanometer!(500.0)], vec![1.5], None).unwrap();
+        assert!(matches!(r.to_enum(), RefractiveIndexType::Tabulated(_)));
+    }
+}
@@ -0,0 +1,210 @@
+//! Temperature-dependent refractive index model
+//!
+//! This model extends an arbitrary (dispersion) model by its thermo-optic coefficients.
+use serde::Deserialize;
+use serde::Serialize;
+use uom::si::f64::{Length, ThermodynamicTemperature};
+use uom::si::thermodynamic_temperature::kelvin;
+
+use crate::error::OpmResult;
+use crate::error::OpossumError;
+
+use super::{RefractiveIndex, RefractiveIndexType};
+
+/// Refractive index model considering the thermo-optic effect.
+///
+/// The refractive index of a base model (given at a reference temperature `T₀`) is corrected by
+///
+/// `n(λ, T) = n₀(λ) + dn/dT · (T - T₀) + d²n/dT² · (T - T₀)²`
+///
+/// The model is evaluated at its operating temperature. If no operating temperature is set, the ambient
+/// temperature of the scenery (see [`SceneryResources`](crate::SceneryResources)) is used during an analysis.
+/// If neither is given, the model is evaluated at the reference temperature.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct RefrIndexTempCoeff {
+    base: Box<RefractiveIndexType>,
+    dn_dt: f64,
+    d2n_dt2: f64,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    reference_temperature: ThermodynamicTemperature,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "json_schema", schemars(with = "Option<f64>"))]
+    temperature: Option<ThermodynamicTemperature>,
+}
+impl RefrIndexTempCoeff {
+    /// Create a new temperature-dependent refractive index model.
+    ///
+    /// The thermo-optic coefficients `dn_dt` and `d2n_dt2` are given in 1/K and 1/K² respectively.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the given coefficients are not finite.
+    ///   - the reference temperature is not finite or below absolute zero.
+    pub fn new(
+        base: RefractiveIndexType,
+        dn_dt: f64,
+        d2n_dt2: f64,
+        reference_temperature: ThermodynamicTemperature,
+    ) -> OpmResult<Self> {
+        if !dn_dt.is_finite() || !d2n_dt2.is_finite() {
+            return Err(OpossumError::Other(
+                "all coefficients must be finite.".into(),
+            ));
+        }
+        check_temperature(reference_temperature)?;
+        Ok(Self {
+            base: Box::new(base),
+            dn_dt,
+            d2n_dt2,
+            reference_temperature,
+            temperature: None,
+        })
+    }
+    /// Returns the base model of this [`RefrIndexTempCoeff`].
+    #[must_use]
+    pub fn base(&self) -> &RefractiveIndexType {
+        &self.base
+    }
+    /// Returns the linear thermo-optic coefficient dn/dT (in 1/K) of this [`RefrIndexTempCoeff`].
+    #[must_use]
+    pub const fn dn_dt(&self) -> f64 {
+        self.dn_dt
+    }
+    /// Returns the quadratic thermo-optic coefficient d²n/dT² (in 1/K²) of this [`RefrIndexTempCoeff`].
+    #[must_use]
+    pub const fn d2n_dt2(&self) -> f64 {
+        self.d2n_dt2
+    }
+    /// Returns the reference temperature of the base model of this [`RefrIndexTempCoeff`].
+    #[must_use]
+    pub const fn reference_temperature(&self) -> ThermodynamicTemperature {
+        self.reference_temperature
+    }
+    /// Returns the operating temperature (if set) of this [`RefrIndexTempCoeff`].
+    #[must_use]
+    pub const fn temperature(&self) -> Option<ThermodynamicTemperature> {
+        self.temperature
+    }
+    /// Sets the operating temperature of this [`RefrIndexTempCoeff`].
+    ///
+    /// A given temperature overrides the ambient temperature of the scenery. Use `None` for falling back to the
+    /// ambient temperature.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the temperature is not finite or below absolute zero.
+    pub fn set_temperature(
+        &mut self,
+        temperature: Option<ThermodynamicTemperature>,
+    ) -> OpmResult<()> {
+        if let Some(temperature) = temperature {
+            check_temperature(temperature)?;
+        }
+        self.temperature = temperature;
+        Ok(())
+    }
+    /// Get the refractive index value for the given wavelength and temperature.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the refractive index of the base model could not be calculated.
+    pub fn get_refractive_index_at(
+        &self,
+        wavelength: Length,
+        temperature: ThermodynamicTemperature,
+    ) -> OpmResult<f64> {
+        let delta_t = temperature.get::<kelvin>() - self.reference_temperature.get::<kelvin>();
+        let base_index = self.base.get_refractive_index(wavelength)?;
+        Ok(self
+            .d2n_dt2
+            .mul_add(delta_t * delta_t, self.dn_dt.mul_add(delta_t, base_index)))
+    }
+}
+fn check_temperature(temperature: ThermodynamicTemperature) -> OpmResult<()> {
+    if !temperature.is_finite() || temperature.is_sign_negative() {
+        return Err(OpossumError::Other(
+            "temperature must be finite and above absolute zero".into(),
+        ));
+    }
+    Ok(())
+}
+impl RefractiveIndex for RefrIndexTempCoeff {
+    fn get_refractive_index(&self, wavelength: Length) -> OpmResult<f64> {
+        self.get_refractive_index_at(
+            wavelength,
+            self.temperature.unwrap_or(self.reference_temperature),
+        )
+    }
+    fn to_enum(&self) -> RefractiveIndexType {
+        RefractiveIndexType::TempCoeff(self.clone())
+    }
+}
+impl From<RefrIndexTempCoeff> for RefractiveIndexType {
+    fn from(refr: RefrIndexTempCoeff) -> Self {
+        Self::TempCoeff(refr)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{nanometer, refractive_index::RefrIndexConst};
+    use approx::assert_relative_eq;
+    use uom::si::thermodynamic_temperature::degree_celsius;
+
+    fn celsius(t: f64) -> ThermodynamicTemperature {
+        ThermodynamicTemperature::new::<degree_celsius>(t)
+    }
+    fn base() -> RefractiveIndexType {
+        RefrIndexConst::new(1.5).unwrap().into()
+    }
+    #[test]
+    fn new() {
+        assert!(RefrIndexTempCoeff::new(base(), f64::NAN, 0.0, celsius(20.0)).is_err());
+        assert!(RefrIndexTempCoeff::new(base(), 0.0, f64::INFINITY, celsius(20.0)).is_err());
+        assert!(RefrIndexTempCoeff::new(base(), 0.0, 0.0, celsius(f64::NAN)).is_err());
+        assert!(RefrIndexTempCoeff::new(base(), 0.0, 0.0, celsius(-300.0)).is_err());
+        let r = RefrIndexTempCoeff::new(base(), 1.0e-5, 1.0e-8, celsius(20.0)).unwrap();
+        assert_eq!(r.dn_dt(), 1.0e-5);
+        assert_eq!(r.d2n_dt2(), 1.0e-8);
+        assert_eq!(r.reference_temperature(), celsius(20.0));
+        assert!(r.temperature().is_none());
+        assert!(matches!(r.base(), RefractiveIndexType::Const(_)));
+    }
+    #[test]
+    fn set_temperature() {
+        let mut r = RefrIndexTempCoeff::new(base(), 1.0e-5, 0.0, celsius(20.0)).unwrap();
+        assert!(r.set_temperature(Some(celsius(f64::NAN))).is_err());
+        assert!(r.set_temperature(Some(celsius(-300.0))).is_err());
+        r.set_temperature(Some(celsius(30.0))).unwrap();
+        assert_eq!(r.temperature(), Some(celsius(30.0)));
+        r.set_temperature(None).unwrap();
+        assert!(r.temperature().is_none());
+    }
+    #[test]
+    fn get_refractive_index() {
+        let mut r = RefrIndexTempCoeff::new(base(), 1.0e-5, 1.0e-7, celsius(20.0)).unwrap();
+        assert_relative_eq!(r.get_refractive_index(nanometer!(1053.0)).unwrap(), 1.5);
+        assert_relative_eq!(
+            r.get_refractive_index_at(nanometer!(1053.0), celsius(30.0))
+                .unwrap(),
+            1.5 + 1.0e-4 + 1.0e-5
+        );
+        assert_relative_eq!(
+            r.get_refractive_index_at(nanometer!(1053.0), celsius(10.0))
+                .unwrap(),
+            1.5 - 1.0e-4 + 1.0e-5
+        );
+        r.set_temperature(Some(celsius(30.0))).unwrap();
+        assert_relative_eq!(
+            r.get_refractive_index(nanometer!(1053.0)).unwrap(),
+            1.5 + 1.0e-4 + 1.0e-5
+        );
+    }
+    #[test]
+    fn get_enum() {
+        let r = RefrIndexTempCoeff::new(base(), 1.0e-5, 0.0, celsius(20.0)).unwrap();
+        assert!(matches!(r.to_enum(), RefractiveIndexType::TempCoeff(_)));
+    }
+}
@@ -8,6 +8,7 @@ use super::{
     node_report::NodeReport,
 };
 use crate::{
+    analyzers::TraceStatistics,
     error::{OpmResult, OpossumError},
     get_version,
     nodes::NodeGroup,
@@ -24,6 +25,8 @@ pub struct AnalysisReport {
     analysis_type: String,
     scenery: Option<NodeGroup>,
     node_reports: Vec<NodeReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace_statistics: Option<TraceStatistics>,
 }
 impl Default for AnalysisReport {
     fn default() -> Self {
@@ -33,6 +36,7 @@ impl Default for AnalysisReport {
             analysis_type: String::default(),
             scenery: None,
             node_reports: Vec::default(),
+            trace_statistics: None,
         }
     }
 }
@@ -46,6 +50,7 @@ impl AnalysisReport {
             analysis_type: String::default(),
             scenery: None,
             node_reports: Vec::default(),
+            trace_statistics: None,
         }
     }
     /// Add an [`NodeGroup`] to this [`AnalysisReport`].
@@ -119,6 +124,39 @@ impl AnalysisReport {
             html_node_reports,
         ))
     }
+    /// Generate a Markdown representation of this [`AnalysisReport`].
+    ///
+    /// The analysis is listed as a section with the given `heading_level` followed by all [`NodeReport`]s. Plots are
+    /// linked relative to the report directory (see [`export_data`](Self::export_data)).
+    #[must_use]
+    pub fn to_markdown(&self, heading_level: usize) -> String {
+        let description = self
+            .scenery
+            .as_ref()
+            .map_or_else(String::new, |scenery| scenery.node_attr().name());
+        let mut markdown = format!(
+            "{} {} Report: {description}\n\nOPOSSUM version: {}  \nAnalysis date: {}\n\n![Optical scene diagram](scenery.svg)\n\n",
+            "#".repeat(heading_level.clamp(1, 6)),
+            self.analysis_type,
+            self.opossum_version,
+            self.analysis_timestamp.format("%Y/%m/%d %H:%M")
+        );
+        for node_report in &self.node_reports {
+            markdown.push_str(&node_report.to_markdown("", heading_level + 1));
+        }
+        markdown
+    }
+    /// Returns the [`TraceStatistics`] of this [`AnalysisReport`].
+    ///
+    /// Statistics are only available for ray tracing analyses.
+    #[must_use]
+    pub const fn trace_statistics(&self) -> Option<&TraceStatistics> {
+        self.trace_statistics.as_ref()
+    }
+    /// Sets the [`TraceStatistics`] of this [`AnalysisReport`].
+    pub fn set_trace_statistics(&mut self, trace_statistics: TraceStatistics) {
+        self.trace_statistics = Some(trace_statistics);
+    }
     /// Sets the analysis type of this [`AnalysisReport`].
     ///
     /// This information is used i.e. in the [`HtmlReport`].
@@ -184,6 +222,20 @@ mod test {
         // further details to be checked in html_reports module (private fields...)
     }
     #[test]
+    fn to_markdown() {
+        let mut report = AnalysisReport::default();
+        report.set_analysis_type("Energy");
+        report.add_node_report(NodeReport::new(
+            "detector",
+            "my detector",
+            "123",
+            Properties::default(),
+        ));
+        let markdown = report.to_markdown(2);
+        assert!(markdown.starts_with("## Energy Report: \n"));
+        assert!(markdown.contains("### my detector (detector)"));
+    }
+    #[test]
     fn export_data() {
         let report = AnalysisReport::default();
         assert!(report.export_data(Path::new("")).is_err());
@@ -1,4 +1,5 @@
 //! Module for generating html reports from analysis results.
+use base64::{Engine, prelude::BASE64_STANDARD};
 use serde::Serialize;
 use std::{fs, path::Path};
 use tinytemplate::TinyTemplate;
@@ -53,6 +54,56 @@ impl HtmlReport {
         fs::write(path, rendered).map_err(|e| OpossumError::Other(e.to_string()))?;
         Ok(())
     }
+    /// Generate a self-contained html report from this [`HtmlReport`].
+    ///
+    /// In contrast to [`generate_html`](Self::generate_html), all referenced images (`.svg` and `.png` files, relative
+    /// to the directory of the given `path`) are embedded as base64 encoded data. Hence, the resulting file can be viewed
+    /// without the exported data files. Images, which could not be found, are kept as references.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - underlying templates could not be compiled.
+    ///   - the file could not be written.
+    pub fn generate_self_contained_html(&self, path: &Path) -> OpmResult<()> {
+        self.generate_html(path)?;
+        let rendered = fs::read_to_string(path).map_err(|e| OpossumError::Other(e.to_string()))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        fs::write(path, embed_images(&rendered, base_dir))
+            .map_err(|e| OpossumError::Other(e.to_string()))?;
+        Ok(())
+    }
+}
+/// Replace the sources of all local images in the given html string by inline data URIs.
+fn embed_images(html: &str, base_dir: &Path) -> String {
+    let mut embedded = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("src=\"") {
+        let (head, tail) = rest.split_at(start + 5);
+        embedded.push_str(head);
+        let Some(end) = tail.find('"') else {
+            rest = tail;
+            break;
+        };
+        let src = &tail[..end];
+        embedded.push_str(&data_uri(src, base_dir).unwrap_or_else(|| src.to_owned()));
+        rest = &tail[end..];
+    }
+    embedded.push_str(rest);
+    embedded
+}
+/// Returns the content of the given image file as data URI or `None` if it is not a (readable) image file.
+fn data_uri(src: &str, base_dir: &Path) -> Option<String> {
+    let mime_type = match Path::new(src).extension()?.to_str()? {
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        _ => return None,
+    };
+    let content = fs::read(base_dir.join(src)).ok()?;
+    Some(format!(
+        "data:{mime_type};base64,{}",
+        BASE64_STANDARD.encode(content)
+    ))
 }
 /// Structure for storing a node report during html conversion.
 #[derive(Serialize)]
@@ -75,3 +126,39 @@ pub struct HtmlProperty {
     pub description: String,
     pub prop_value: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::TempDir;
+    #[test]
+    fn embed_images() {
+        let tmp_dir = TempDir::new().unwrap();
+        fs::create_dir(tmp_dir.path().join("data")).unwrap();
+        fs::write(tmp_dir.path().join("data/plot.svg"), "<svg/>").unwrap();
+        let html =
+            r#"<img src="data/plot.svg"/><img src="missing.png"/><script src="x.js"></script>"#;
+        let embedded = super::embed_images(html, tmp_dir.path());
+        assert_eq!(
+            embedded,
+            r#"<img src="data:image/svg+xml;base64,PHN2Zy8+"/><img src="missing.png"/><script src="x.js"></script>"#
+        );
+    }
+    #[test]
+    fn generate_self_contained_html() {
+        let tmp_dir = TempDir::new().unwrap();
+        fs::write(tmp_dir.path().join("scenery.svg"), "<svg/>").unwrap();
+        let report = HtmlReport::new(
+            "1.0".into(),
+            "today".into(),
+            "Test".into(),
+            "test scenery".into(),
+            vec![],
+        );
+        let path = tmp_dir.path().join("report.html");
+        report.generate_self_contained_html(&path).unwrap();
+        let html = fs::read_to_string(&path).unwrap();
+        assert!(html.contains("data:image/svg+xml;base64,PHN2Zy8+"));
+        assert!(!html.contains("src=\"scenery.svg\""));
+    }
+}
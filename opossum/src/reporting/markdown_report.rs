@@ -0,0 +1,29 @@
+//! Module for generating Markdown reports from analysis results.
+use super::analysis_report::AnalysisReport;
+
+/// Generate a Markdown document from the given [`AnalysisReport`]s.
+///
+/// Each analysis is listed as a separate section. Detector results are given as GitHub-flavored Markdown tables.
+/// Plots are linked relative to the report directory, hence the data files have to be exported (see
+/// [`AnalysisReport::export_data`]) into the same directory as the Markdown document.
+#[must_use]
+pub fn markdown_report(reports: &[AnalysisReport]) -> String {
+    let mut markdown = String::from("# OPOSSUM Analysis Report\n\n");
+    for report in reports {
+        markdown.push_str(&report.to_markdown(2));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn markdown_report() {
+        assert_eq!(super::markdown_report(&[]), "# OPOSSUM Analysis Report\n\n");
+        let mut report = AnalysisReport::default();
+        report.set_analysis_type("Energy");
+        let markdown = super::markdown_report(&[report.clone(), report]);
+        assert_eq!(markdown.matches("## Energy Report").count(), 2);
+    }
+}
@@ -1,4 +1,52 @@
 //! Module for reporting analysis results.
 pub mod analysis_report;
 pub mod html_report;
+pub mod markdown_report;
 pub mod node_report;
+
+use crate::error::{OpmResult, OpossumError};
+use std::str::FromStr;
+
+/// Output format of the analysis reports.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Report data file (`.ron`) together with an html report referencing the exported data files.
+    #[default]
+    Ron,
+    /// Self-contained html report with all plots embedded. No further files are needed for viewing the report.
+    Html,
+    /// Markdown document with tables of the detector results and links to the exported plots.
+    Markdown,
+}
+impl FromStr for ReportFormat {
+    type Err = OpossumError;
+
+    fn from_str(s: &str) -> OpmResult<Self> {
+        match s.to_lowercase().as_str() {
+            "ron" => Ok(Self::Ron),
+            "html" => Ok(Self::Html),
+            "md" | "markdown" => Ok(Self::Markdown),
+            _ => Err(OpossumError::Other(format!("unknown report format: {s}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReportFormat;
+    #[test]
+    fn from_str() {
+        assert_eq!("ron".parse::<ReportFormat>().unwrap(), ReportFormat::Ron);
+        assert_eq!("HTML".parse::<ReportFormat>().unwrap(), ReportFormat::Html);
+        assert_eq!(
+            "md".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Markdown
+        );
+        assert_eq!(
+            "Markdown".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Markdown
+        );
+        assert!("json".parse::<ReportFormat>().is_err());
+        assert_eq!(ReportFormat::default(), ReportFormat::Ron);
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
     error::OpmResult,
     properties::{Properties, Proptype},
 };
+use log::warn;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -76,6 +77,41 @@ impl NodeReport {
             show_item: self.show_item,
         }
     }
+    /// Return a Markdown representation of this [`NodeReport`].
+    ///
+    /// The properties are listed in a (GitHub-flavored) table starting with a heading of the given `heading_level`.
+    /// Plots are linked to the files exported by [`export_data`](Self::export_data). Nested [`NodeReport`]s follow the
+    /// table as sub sections.
+    #[must_use]
+    pub fn to_markdown(&self, id: &str, heading_level: usize) -> String {
+        let id = format!("{id}_{}_{}", self.name, self.uuid);
+        let mut markdown = format!(
+            "{} {} ({})\n\n",
+            "#".repeat(heading_level.clamp(1, 6)),
+            self.name,
+            self.node_type
+        );
+        let mut sub_reports = Vec::new();
+        let mut rows = String::new();
+        for (name, property) in &self.properties {
+            if let Proptype::NodeReport(report) = property.prop() {
+                sub_reports.push(report);
+            } else if let Ok(value) = property.prop().to_markdown(&id, name) {
+                rows.push_str(&format!("| {name} | {value} |\n"));
+            } else {
+                warn!("property {name} could not be converted to markdown. Skipping");
+            }
+        }
+        if !rows.is_empty() {
+            markdown.push_str("| Property | Value |\n| --- | --- |\n");
+            markdown.push_str(&rows);
+            markdown.push('\n');
+        }
+        for report in sub_reports {
+            markdown.push_str(&report.to_markdown(&id, heading_level + 1));
+        }
+        markdown
+    }
     /// Export data files for the properties of this [`NodeReport`].
     ///
     /// This function exports data (mostly as data files) for each property. This is necessary if a report is exported to HTML.
@@ -151,6 +187,33 @@ mod test {
         assert_eq!(html_props[0].prop_value, "1.000000");
     }
     #[test]
+    fn to_markdown() {
+        let mut sub_properties = Properties::default();
+        sub_properties.create("sub", "sub desc", 2.into()).unwrap();
+        let sub_report = NodeReport::new("sub type", "sub name", "789", sub_properties);
+        let mut properties = Properties::default();
+        properties.create("test1", "desc1", 1.0.into()).unwrap();
+        properties.create("test2", "desc2", "a|b".into()).unwrap();
+        properties
+            .create("group", "desc3", sub_report.into())
+            .unwrap();
+        let report = NodeReport::new("test detector", "detector name", "123", properties);
+        assert_eq!(
+            report.to_markdown("345", 3),
+            "### detector name (test detector)\n\n| Property | Value |\n| --- | --- |\n| test1 | 1.000000 |\n| test2 | a\\|b |\n\n#### sub name (sub type)\n\n| Property | Value |\n| --- | --- |\n| sub | 2 |\n\n"
+        );
+        let report = NodeReport::new(
+            "test detector",
+            "detector name",
+            "123",
+            Properties::default(),
+        );
+        assert_eq!(
+            report.to_markdown("345", 7),
+            "###### detector name (test detector)\n\n"
+        );
+    }
+    #[test]
     fn export_data() {
         let report = NodeReport::new(
             "test detector",
@@ -10,10 +10,18 @@ use itertools::Itertools;
 use kahan::KahanSummator;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "json_schema",
+    derive(schemars::JsonSchema),
+    schemars(rename = "SpectralGaussian")
+)]
 pub struct Gaussian {
+    #[cfg_attr(feature = "json_schema", schemars(with = "(f64, f64)"))]
     wvl_range: (Length, Length),
     num_points: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     mu: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     fwhm: Length,
     power: f64,
 }
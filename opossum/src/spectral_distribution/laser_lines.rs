@@ -6,8 +6,10 @@ use crate::error::{OpmResult, OpossumError};
 use super::SpectralDistribution;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// A struct representing a collection of laser lines with their respective wavelengths and relative intensities.
 pub struct LaserLines {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Vec<(f64, f64)>"))]
     lines: Vec<(Length, f64)>,
 }
 impl LaserLines {
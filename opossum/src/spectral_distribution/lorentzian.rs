@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use uom::si::f64::Length;
+
+use super::SpectralDistribution;
+use crate::error::{OpmResult, OpossumError};
+use crate::meter;
+use crate::utils::griddata::linspace;
+use crate::utils::math_distribution_functions::lorentzian;
+use itertools::Itertools;
+use kahan::KahanSummator;
+
+/// Lorentzian spectral line shape, e.g. for modelling (homogeneously broadened) emission lines.
+///
+/// The line is sampled at equidistant wavelengths within the given range. The resulting spectral weights are normalized
+/// to sum up to 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct Lorentzian {
+    #[cfg_attr(feature = "json_schema", schemars(with = "(f64, f64)"))]
+    wvl_range: (Length, Length),
+    num_points: usize,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    center: Length,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    fwhm: Length,
+}
+
+impl Lorentzian {
+    /// Create a new Lorentzian distribution generator
+    ///
+    /// # Attributes
+    ///
+    /// - `wvl_range`: the wavelength range in which the distribution is sampled
+    /// - `num_points`: the number of sampled wavelengths
+    /// - `center`: the center wavelength of the line
+    /// - `fwhm`: the full-with at half maximum of the line
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the range limits are non-finite, zero or below zero
+    ///   - the center wavelength is non-finite, zero or below zero
+    ///   - the fwhm is non-finite, zero or below zero
+    pub fn new(
+        wvl_range: (Length, Length),
+        num_points: usize,
+        center: Length,
+        fwhm: Length,
+    ) -> OpmResult<Self> {
+        if !wvl_range.0.is_normal() || wvl_range.0.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "range start must be positive and finite".into(),
+            ));
+        }
+        if !wvl_range.1.is_normal() || wvl_range.1.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "range end must be positive and finite".into(),
+            ));
+        }
+        if !center.is_normal() || center.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "center wavelength must be positive and finite!".into(),
+            ));
+        }
+        if !fwhm.is_normal() || fwhm.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "fwhm must be greater than zero and finite!".into(),
+            ));
+        }
+        Ok(Self {
+            wvl_range,
+            num_points,
+            center,
+            fwhm,
+        })
+    }
+}
+impl SpectralDistribution for Lorentzian {
+    fn generate(&self) -> OpmResult<Vec<(Length, f64)>> {
+        let wvls = linspace(
+            self.wvl_range.0.value,
+            self.wvl_range.1.value,
+            self.num_points,
+        )?;
+        let spectral_distribution =
+            lorentzian(wvls.data.as_slice(), self.center.value, self.fwhm.value);
+        let sum: f64 = spectral_distribution.iter().kahan_sum().sum();
+        Ok(spectral_distribution
+            .iter()
+            .zip(wvls.iter())
+            .map(|v| (meter!(*v.1), *v.0 / sum))
+            .collect_vec())
+    }
+}
+impl From<Lorentzian> for super::SpecDistType {
+    fn from(l: Lorentzian) -> Self {
+        Self::Lorentzian(l)
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{
+        nanometer,
+        spectral_distribution::{Lorentzian, SpecDistType, SpectralDistribution},
+    };
+    use approx::assert_abs_diff_eq;
+    use core::f64;
+    use uom::si::f64::Length;
+    #[test]
+    fn new() {
+        assert!(
+            Lorentzian::new(
+                (nanometer!(1000.0), nanometer!(2000.0)),
+                10,
+                nanometer!(1500.0),
+                nanometer!(100.0),
+            )
+            .is_ok()
+        );
+        let test_values = [0.0, -0.1, f64::INFINITY, f64::NAN, f64::NEG_INFINITY];
+        let wvl_values: Vec<Length> = test_values.iter().map(|v| nanometer!(*v)).collect();
+        for value in &wvl_values {
+            assert!(
+                Lorentzian::new(
+                    (*value, nanometer!(2000.0)),
+                    10,
+                    nanometer!(1500.0),
+                    nanometer!(100.0),
+                )
+                .is_err()
+            );
+            assert!(
+                Lorentzian::new(
+                    (nanometer!(1000.0), *value),
+                    10,
+                    nanometer!(1500.0),
+                    nanometer!(100.0),
+                )
+                .is_err()
+            );
+            assert!(
+                Lorentzian::new(
+                    (nanometer!(1000.0), nanometer!(2000.0)),
+                    10,
+                    *value,
+                    nanometer!(100.0),
+                )
+                .is_err()
+            );
+            assert!(
+                Lorentzian::new(
+                    (nanometer!(1000.0), nanometer!(2000.0)),
+                    10,
+                    nanometer!(1500.0),
+                    *value,
+                )
+                .is_err()
+            );
+        }
+    }
+    #[test]
+    fn generate() {
+        let lorentz = Lorentzian::new(
+            (nanometer!(1000.0), nanometer!(2000.0)),
+            11,
+            nanometer!(1500.0),
+            nanometer!(200.0),
+        )
+        .unwrap();
+        let values = lorentz.generate().unwrap();
+        assert_eq!(values.len(), 11);
+        assert_abs_diff_eq!(values[5].0.value, nanometer!(1500.0).value);
+        assert_abs_diff_eq!(values[4].1, values[5].1 / 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(values[4].1, values[6].1, epsilon = 1e-12);
+        let v_sum: f64 = values.iter().map(|v| v.1).sum();
+        assert_abs_diff_eq!(v_sum, 1.0, epsilon = 1e-12);
+    }
+    #[test]
+    fn serialize() {
+        let lorentz: SpecDistType = Lorentzian::new(
+            (nanometer!(1000.0), nanometer!(2000.0)),
+            11,
+            nanometer!(1500.0),
+            nanometer!(200.0),
+        )
+        .unwrap()
+        .into();
+        let serialized = ron::to_string(&lorentz).unwrap();
+        let deserialized: SpecDistType = ron::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, lorentz);
+    }
+}
@@ -5,8 +5,10 @@ use uom::si::f64::Length;
 
 pub mod gaussian;
 pub mod laser_lines;
+pub mod lorentzian;
 pub use gaussian::Gaussian;
 pub use laser_lines::LaserLines;
+pub use lorentzian::Lorentzian;
 
 pub trait SpectralDistribution {
     /// Creates a Gaussian spectral distribution
@@ -16,10 +18,12 @@ pub trait SpectralDistribution {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 /// Enum representing different types of spectral distributions
 pub enum SpecDistType {
     Gaussian(gaussian::Gaussian),
     LaserLines(laser_lines::LaserLines),
+    Lorentzian(lorentzian::Lorentzian),
 }
 impl SpecDistType {
     /// Generates the spectral distribution
@@ -30,6 +34,7 @@ impl SpecDistType {
         match self {
             Self::Gaussian(g) => g,
             Self::LaserLines(l) => l,
+            Self::Lorentzian(l) => l,
         }
     }
 }
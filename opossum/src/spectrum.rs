@@ -2,6 +2,7 @@
 //! Module for creation and handling of optical spectra
 use crate::{
     error::{OpmResult, OpossumError},
+    joule,
     lightdata::energy_data_builder::EnergyDataBuilder,
     micrometer,
     plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
@@ -32,6 +33,7 @@ use uom::{
 /// This structure handles an array of values over a given wavelength range. Although the interface
 /// is still limited, the structure is prepared for handling also non-equidistant wavelength slots.  
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct Spectrum {
     data: Vec<(f64, f64)>, // (wavelength in micrometers, data in 1/micrometers)
 }
@@ -281,6 +283,98 @@ impl Spectrum {
         self.data = spectrum;
         Ok(())
     }
+    /// Adds a Gaussian peak to this [`Spectrum`].
+    ///
+    /// This function adds a peak following a Gaussian function with a given center wavelength, full width at half maximum (FWHM) and
+    /// energy to the spectrum. This can be used e.g. for creating the slit function of a spectrometer. **Note**: Due to rounding errors
+    /// (discrete wavelength bins, upper/lower spectrum limits) the total energy is not exactly the given value.
+    /// # Errors
+    ///
+    /// This function will return an [`OpossumError::Spectrum`] if
+    ///   - the center wavelength in negative
+    ///   - the width is not positive
+    ///   - the energy is negative
+    pub fn add_gaussian_peak(
+        &mut self,
+        center: Length,
+        fwhm: Length,
+        energy: f64,
+    ) -> OpmResult<()> {
+        if center.is_sign_negative() {
+            return Err(OpossumError::Spectrum(
+                "center wavelength must be positive".into(),
+            ));
+        }
+        if !fwhm.is_normal() || fwhm.is_sign_negative() {
+            return Err(OpossumError::Spectrum("line width must be positive".into()));
+        }
+        if energy < 0.0 {
+            return Err(OpossumError::Spectrum("energy must be positive".into()));
+        }
+        let wavelength_in_micrometers = center.get::<micrometer>();
+        let fwhm_in_micrometers = fwhm.get::<micrometer>();
+        for data in &mut self.data {
+            data.1 = energy.mul_add(
+                gauss(wavelength_in_micrometers, fwhm_in_micrometers, data.0),
+                data.1,
+            );
+        }
+        Ok(())
+    }
+    /// Convolve this [`Spectrum`] with an instrument response function.
+    ///
+    /// The given `kernel` spectrum (e.g. a Gaussian slit function created by [`add_gaussian_peak`](Self::add_gaussian_peak)) is
+    /// normalized to unit area and centered at its center wavelength (see [`center_wavelength`](Self::center_wavelength)). Hence,
+    /// only the shape of the kernel is relevant, not its absolute wavelength position. The spectrum is zero-padded outside its
+    /// wavelength range. Finally, the result is renormalized such that the total energy of the spectrum is conserved.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`OpossumError::Spectrum`] if
+    ///   - the spectrum or the kernel contains less than two wavelength slots
+    ///   - the kernel does not contain any (positive) energy
+    pub fn convolve(&mut self, kernel: &Self) -> OpmResult<()> {
+        if self.data.len() < 2 || kernel.data.len() < 2 {
+            return Err(OpossumError::Spectrum("spectrum size is too small".into()));
+        }
+        let kernel_energy = kernel.total_energy();
+        if !kernel_energy.is_normal() || kernel_energy.is_sign_negative() {
+            return Err(OpossumError::Spectrum(
+                "kernel must contain positive energy".into(),
+            ));
+        }
+        let kernel_center = kernel.center_wavelength().get::<micrometer>();
+        let kernel_min = kernel.data[0].0 - kernel_center;
+        let kernel_max = kernel.data[kernel.data.len() - 1].0 - kernel_center;
+        // slot widths as used for the energy calculation
+        let widths: Vec<f64> = self
+            .data
+            .windows(2)
+            .map(|l| l[1].0 - l[0].0)
+            .chain(std::iter::once(0.0))
+            .collect();
+        let lambdas = self.lambda_vec();
+        let convolved: Vec<(f64, f64)> = lambdas
+            .iter()
+            .map(|lambda| {
+                // only source slots within the kernel support contribute
+                let first = lambdas.partition_point(|l| *l < lambda - kernel_max);
+                let last = lambdas.partition_point(|l| *l <= lambda - kernel_min);
+                let value = (first..last).fold(0.0, |sum, j| {
+                    let k = interpolate(&kernel.data, lambda - lambdas[j] + kernel_center);
+                    (self.data[j].1 * widths[j]).mul_add(k, sum)
+                });
+                (*lambda, value / kernel_energy)
+            })
+            .collect();
+        let energy_before = self.total_energy();
+        self.data = convolved;
+        let energy_after = self.total_energy();
+        if energy_after > 0.0 {
+            self.scale_vertical(&(energy_before / energy_after))?;
+        }
+        Ok(())
+    }
     /// Returns the total energy of this [`Spectrum`].
     ///
     /// This function sums the values over all wavelength slots weighted with the individual slot widths. This
@@ -296,6 +390,50 @@ impl Spectrum {
         kahan_sum.sum()
     }
 
+    /// Returns the energy contained in each wavelength slot of this [`Spectrum`].
+    ///
+    /// The energy of a slot is the spectral value weighted with the slot width (consistent with
+    /// [`total_energy`](Self::total_energy)). Hence, the last slot (which has no width) always contains zero energy.
+    #[must_use]
+    pub fn slot_energies(&self) -> Vec<(Length, Energy)> {
+        let mut slot_widths: Vec<f64> = self.data.windows(2).map(|l| l[1].0 - l[0].0).collect();
+        slot_widths.push(0.0);
+        self.data
+            .iter()
+            .zip(slot_widths)
+            .map(|(d, width)| (micrometer!(d.0), joule!(d.1 * width)))
+            .collect()
+    }
+    /// Write the energy per wavelength slot of this [`Spectrum`] to a CSV file.
+    ///
+    /// The file contains one line per wavelength slot with the columns `wavelength in nm` and `energy in J`. The last
+    /// line contains the total energy.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file could not be written.
+    pub fn to_energy_csv(&self, path: &Path) -> OpmResult<()> {
+        let mut writer = csv::Writer::from_path(path)
+            .map_err(|e| OpossumError::Other(format!("could not create csv file: {e}")))?;
+        writer
+            .write_record(["wavelength in nm", "energy in J"])
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        for (wavelength, energy) in self.slot_energies() {
+            writer
+                .write_record([
+                    wavelength.get::<nanometer>().to_string(),
+                    energy.get::<joule>().to_string(),
+                ])
+                .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        }
+        writer
+            .write_record(["total".into(), self.total_energy().to_string()])
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        writer
+            .flush()
+            .map_err(|e| OpossumError::Other(format!("could not write csv file: {e}")))?;
+        Ok(())
+    }
     /// Returns the center wavelength of this [`Spectrum`].
     ///
     /// This function calculates the first moment of the spectral distribution.
@@ -313,6 +451,43 @@ impl Spectrum {
         }
         micrometer!(weighted_sum / total_weight)
     }
+    /// Returns the centroid wavelength of this [`Spectrum`].
+    ///
+    /// The centroid is the energy-weighted mean wavelength of the spectrum and thus identical to the
+    /// [`center_wavelength`](Self::center_wavelength).
+    #[must_use]
+    pub fn centroid(&self) -> Length {
+        self.center_wavelength()
+    }
+    /// Returns the full width at half maximum (FWHM) of this [`Spectrum`].
+    ///
+    /// The width is determined between the outermost wavelengths at which the spectrum crosses half of its
+    /// maximum value. The crossing points are linearly interpolated between the wavelength slots. If the spectrum
+    /// is empty, contains no positive values or has no clear peak (i.e. does not drop below half of its maximum on
+    /// both sides of the peak, such as a monotonic spectrum), `None` is returned.
+    #[must_use]
+    pub fn fwhm(&self) -> Option<Length> {
+        let max = self
+            .data
+            .iter()
+            .map(|d| d.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if !max.is_finite() || max <= 0.0 {
+            return None;
+        }
+        let half = max / 2.0;
+        let first = self.data.iter().position(|d| d.1 >= half)?;
+        let last = self.data.iter().rposition(|d| d.1 >= half)?;
+        let interpolate = |below: (f64, f64), above: (f64, f64)| {
+            below.0 + (half - below.1) * (above.0 - below.0) / (above.1 - below.1)
+        };
+        if first == 0 || last + 1 == self.data.len() {
+            return None;
+        }
+        let left = interpolate(self.data[first - 1], self.data[first]);
+        let right = interpolate(self.data[last + 1], self.data[last]);
+        Some(micrometer!(right - left))
+    }
     /// Return the value at a given wavelength.
     ///
     /// This function returns the spectrum value (y value) for a given wavelength. The value will be linear interpolated if the wavelength does not correspond
@@ -426,6 +601,68 @@ impl Spectrum {
             break;
         }
     }
+    /// Resample this [`Spectrum`] onto the given wavelength grid.
+    ///
+    /// In contrast to [`Spectrum::resample`], a new spectrum with the given wavelength slots is returned. The energy of
+    /// each original slot is redistributed onto the new slots according to their spectral overlap, so that the total
+    /// energy is conserved as long as the new grid covers the range of this spectrum. New slots outside the original
+    /// range are set to zero.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the given grid
+    ///   - contains less than two wavelengths.
+    ///   - contains non-finite wavelengths.
+    ///   - is not strictly increasing.
+    pub fn resample_to_grid(&self, new_wavelengths: &[Length]) -> OpmResult<Self> {
+        if new_wavelengths.len() < 2 {
+            return Err(OpossumError::Spectrum(
+                "wavelength grid must contain at least two values".into(),
+            ));
+        }
+        let grid: Vec<f64> = new_wavelengths
+            .iter()
+            .map(Length::get::<micrometer>)
+            .collect();
+        if grid.iter().any(|l| !l.is_finite()) {
+            return Err(OpossumError::Spectrum(
+                "wavelength grid must only contain finite values".into(),
+            ));
+        }
+        if grid.windows(2).any(|l| l[1] <= l[0]) {
+            return Err(OpossumError::Spectrum(
+                "wavelength grid must be strictly increasing".into(),
+            ));
+        }
+        let mut data: Vec<(f64, f64)> = grid
+            .windows(2)
+            .map(|bucket| {
+                let energy: f64 = self
+                    .data
+                    .windows(2)
+                    .map(|src| {
+                        let overlap = bucket[1].min(src[1].0) - bucket[0].max(src[0].0);
+                        if overlap > 0.0 {
+                            src[0].1 * overlap
+                        } else {
+                            0.0
+                        }
+                    })
+                    .kahan_sum()
+                    .sum();
+                (bucket[0], energy / (bucket[1] - bucket[0]))
+            })
+            .collect();
+        // the last wavelength only closes the last slot and does not carry energy
+        let last = grid[grid.len() - 1];
+        let last_value = self
+            .data
+            .windows(2)
+            .find(|src| src[0].0 <= last && last < src[1].0)
+            .map_or(0.0, |src| src[0].1);
+        data.push((last, last_value));
+        Ok(Self { data })
+    }
     /// Filter the spectrum with another given spectrum by multiplying the data values. The given spectrum is resampled before the multiplication.
     pub fn filter(&mut self, filter_spectrum: &Self) {
         let mut resampled_spec = self.clone();
@@ -448,6 +685,23 @@ impl Spectrum {
             crate::nodes::FilterType::Spectrum(s2) => {
                 self.filter(s2);
             }
+            crate::nodes::FilterType::TransmissionCurve(curve) => {
+                if self
+                    .data
+                    .iter()
+                    .any(|(wavelength, _)| !curve.contains(micrometer!(*wavelength)))
+                {
+                    warn!(
+                        "spectrum exceeds the range of the transmission curve. Using transmission of nearest data point."
+                    );
+                }
+                self.map_mut(|(wavelength, value)| {
+                    (
+                        *wavelength,
+                        *value * curve.transmission(micrometer!(*wavelength)),
+                    )
+                });
+            }
         }
         Ok(())
     }
@@ -504,6 +758,30 @@ impl Spectrum {
             .map(|d| (d.0.0, (d.0.1 - d.1.1).clamp(0.0, f64::abs(d.0.1 - d.1.1))))
             .collect();
     }
+    /// Divide by a given spectrum.
+    ///
+    /// The given spectrum might be resampled in order to match self. This can be used for calculating a transmission spectrum
+    /// from an output and an input spectrum. **Note**: Values, where the divisor is zero (or negative), are set to 0.0.
+    pub fn div_or_zero(&mut self, divisor: &Self) {
+        let mut resampled_spec = self.clone();
+        resampled_spec.resample(divisor);
+        // the last slot is not covered by the resampling
+        if let Some(last) = resampled_spec.data.last_mut() {
+            last.1 = divisor.get_value(&micrometer!(last.0)).unwrap_or_default();
+        }
+        self.data = self
+            .data
+            .iter()
+            .zip(resampled_spec.data.iter())
+            .map(|d| {
+                if d.1.1 > 0.0 {
+                    (d.0.0, d.0.1 / d.1.1)
+                } else {
+                    (d.0.0, 0.0)
+                }
+            })
+            .collect();
+    }
 }
 
 impl Plottable for Spectrum {
@@ -542,6 +820,56 @@ impl Plottable for Spectrum {
     }
 }
 
+impl std::ops::Add for &Spectrum {
+    type Output = Spectrum;
+    /// Add two spectra.
+    ///
+    /// Both spectra are linearly interpolated onto the union of their wavelength slots. Outside the range
+    /// of a spectrum, its values are assumed to be zero.
+    fn add(self, rhs: Self) -> Spectrum {
+        combine_spectra(self, rhs, |a, b| a + b)
+    }
+}
+impl std::ops::Add for Spectrum {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        &self + &rhs
+    }
+}
+impl std::ops::Sub for &Spectrum {
+    type Output = Spectrum;
+    /// Subtract two spectra.
+    ///
+    /// Both spectra are linearly interpolated onto the union of their wavelength slots. Outside the range
+    /// of a spectrum, its values are assumed to be zero. In contrast to [`Spectrum::sub`], negative values are kept, so that
+    /// the result can be used as a residual between e.g. a measured and a simulated spectrum.
+    fn sub(self, rhs: Self) -> Spectrum {
+        combine_spectra(self, rhs, |a, b| a - b)
+    }
+}
+impl std::ops::Sub for Spectrum {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        &self - &rhs
+    }
+}
+impl std::ops::Div for &Spectrum {
+    type Output = Spectrum;
+    /// Divide two spectra element-wise.
+    ///
+    /// Both spectra are linearly interpolated onto the union of their wavelength slots. Outside the range
+    /// of a spectrum, its values are assumed to be zero. Wavelengths, where the divisor is zero, yield `f64::NAN` (see [`Spectrum::div_or_zero`] for
+    /// an in-place division yielding 0.0 instead).
+    fn div(self, rhs: Self) -> Spectrum {
+        combine_spectra(self, rhs, |a, b| if b == 0.0 { f64::NAN } else { a / b })
+    }
+}
+impl std::ops::Div for Spectrum {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        &self / &rhs
+    }
+}
 impl<'a> IntoIterator for &'a Spectrum {
     type IntoIter = std::slice::Iter<'a, (f64, f64)>;
     type Item = &'a (f64, f64);
@@ -608,7 +936,47 @@ fn calc_ratio(bucket_left: f64, bucket_right: f64, source_left: f64, source_righ
 fn lorentz(center: f64, width: f64, x: f64) -> f64 {
     0.5 / PI * width / (0.25 * width).mul_add(width, (x - center) * (x - center))
 }
-
+/// Normalized Gaussian function for a given center and full width at half maximum.
+fn gauss(center: f64, fwhm: f64, x: f64) -> f64 {
+    let sigma = fwhm / (2.0 * f64::sqrt(2.0 * f64::ln(2.0)));
+    (-0.5 * ((x - center) / sigma).powi(2)).exp() / (sigma * f64::sqrt(2.0 * PI))
+}
+/// Linear interpolation of (sorted) spectrum data. Outside the data range 0.0 is returned.
+fn interpolate(data: &[(f64, f64)], x: f64) -> f64 {
+    let idx = data.partition_point(|d| d.0 < x);
+    if idx == 0 {
+        #[allow(clippy::float_cmp)]
+        return if data.first().is_some_and(|d| d.0 == x) {
+            data[0].1
+        } else {
+            0.0
+        };
+    }
+    if idx == data.len() {
+        return 0.0;
+    }
+    let (left, right) = (data[idx - 1], data[idx]);
+    let ratio = (x - left.0) / (right.0 - left.0);
+    left.1.mul_add(1.0 - ratio, right.1 * ratio)
+}
+/// Combine two spectra value by value using the given function.
+///
+/// The resulting spectrum is defined on the union of the wavelength slots of both spectra. The values of both spectra are
+/// linearly interpolated onto this common grid (0.0 outside their respective range) before being combined.
+fn combine_spectra<F>(s1: &Spectrum, s2: &Spectrum, f: F) -> Spectrum
+where
+    F: Fn(f64, f64) -> f64,
+{
+    let mut lambdas: Vec<f64> = s1.lambda_vec();
+    lambdas.extend(s2.lambda_vec());
+    lambdas.sort_by(f64::total_cmp);
+    lambdas.dedup();
+    let data = lambdas
+        .into_iter()
+        .map(|l| (l, f(interpolate(&s1.data, l), interpolate(&s2.data, l))))
+        .collect();
+    Spectrum { data }
+}
 /// Helper function for adding two spectra.
 ///
 /// This function allows for adding two (maybe non-existing = None) spectra with different bandwidth.
@@ -872,18 +1240,170 @@ mod test {
         );
     }
     #[test]
+    fn add_gaussian() {
+        let mut s = Spectrum::new(nanometer!(990.0)..nanometer!(1010.0), nanometer!(0.01)).unwrap();
+        s.add_gaussian_peak(nanometer!(1000.0), nanometer!(1.0), 2.0)
+            .unwrap();
+        assert!(s.total_energy().abs_diff_eq(&2.0, 1.0e-6));
+        assert!(
+            s.fwhm()
+                .unwrap()
+                .get::<nanometer>()
+                .abs_diff_eq(&1.0, 1.0e-3)
+        );
+        assert!(
+            s.center_wavelength()
+                .get::<nanometer>()
+                .abs_diff_eq(&1000.0, 0.01)
+        );
+    }
+    #[test]
+    fn add_gaussian_wrong_params() {
+        let mut s = prep();
+        assert!(
+            s.add_gaussian_peak(micrometer!(-5.0), micrometer!(0.5), 2.0)
+                .is_err()
+        );
+        assert!(
+            s.add_gaussian_peak(micrometer!(2.0), micrometer!(0.0), 2.0)
+                .is_err()
+        );
+        assert!(
+            s.add_gaussian_peak(micrometer!(2.0), micrometer!(-0.5), 2.0)
+                .is_err()
+        );
+        assert!(
+            s.add_gaussian_peak(micrometer!(2.0), micrometer!(0.5), -2.0)
+                .is_err()
+        );
+    }
+    #[test]
+    fn convolve() {
+        let mut s =
+            Spectrum::new(nanometer!(1000.0)..nanometer!(1100.0), nanometer!(0.05)).unwrap();
+        s.add_single_peak(nanometer!(1050.0), 1.0).unwrap();
+        let mut kernel =
+            Spectrum::new(nanometer!(500.0)..nanometer!(510.0), nanometer!(0.05)).unwrap();
+        kernel
+            .add_gaussian_peak(nanometer!(505.0), nanometer!(1.0), 3.0)
+            .unwrap();
+        s.convolve(&kernel).unwrap();
+        assert!(s.total_energy().abs_diff_eq(&1.0, 1.0e-9));
+        assert!(s.fwhm().unwrap().get::<nanometer>().abs_diff_eq(&1.0, 0.01));
+        assert!(
+            s.center_wavelength()
+                .get::<nanometer>()
+                .abs_diff_eq(&1050.0, 0.01)
+        );
+    }
+    #[test]
+    fn convolve_edge() {
+        // line at the spectrum edge: energy leaking outside the range is renormalized
+        let mut s =
+            Spectrum::new(nanometer!(1000.0)..nanometer!(1010.0), nanometer!(0.05)).unwrap();
+        s.add_single_peak(nanometer!(1000.0), 1.0).unwrap();
+        let mut kernel =
+            Spectrum::new(nanometer!(995.0)..nanometer!(1005.0), nanometer!(0.05)).unwrap();
+        kernel
+            .add_gaussian_peak(nanometer!(1000.0), nanometer!(1.0), 1.0)
+            .unwrap();
+        s.convolve(&kernel).unwrap();
+        assert!(s.total_energy().abs_diff_eq(&1.0, 1.0e-9));
+        assert!(s.data_vec().iter().all(|v| *v >= 0.0));
+    }
+    #[test]
+    fn convolve_wrong_params() {
+        let mut s = prep();
+        assert!(
+            s.convolve(&Spectrum {
+                data: vec![(1.0, 1.0)]
+            })
+            .is_err()
+        );
+        let empty_kernel =
+            Spectrum::new(micrometer!(1.0)..micrometer!(2.0), micrometer!(0.1)).unwrap();
+        assert!(s.convolve(&empty_kernel).is_err());
+        let mut s = Spectrum {
+            data: vec![(1.0, 1.0)],
+        };
+        assert!(s.convolve(&prep()).is_err());
+    }
+    #[test]
     fn total_energy() {
         let mut s = prep();
         s.add_single_peak(micrometer!(2.0), 1.0).unwrap();
         assert_eq!(s.total_energy(), 1.0);
     }
     #[test]
+    fn slot_energies() {
+        let mut s = Spectrum::new(micrometer!(1.0)..micrometer!(4.0), micrometer!(1.0)).unwrap();
+        s.add_single_peak(micrometer!(1.5), 1.0).unwrap();
+        let energies = s.slot_energies();
+        assert_eq!(energies.len(), 3);
+        assert_eq!(energies[0], (micrometer!(1.0), joule!(0.5)));
+        assert_eq!(energies[1], (micrometer!(2.0), joule!(0.5)));
+        assert_eq!(energies[2], (micrometer!(3.0), joule!(0.0)));
+    }
+    #[test]
+    fn to_energy_csv() {
+        let mut s = Spectrum::new(micrometer!(1.0)..micrometer!(4.0), micrometer!(1.0)).unwrap();
+        s.add_single_peak(micrometer!(1.5), 1.0).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spectrum.csv");
+        s.to_energy_csv(&path).unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "wavelength in nm,energy in J");
+        assert!(lines[1].ends_with(",0.5"));
+        assert_eq!(lines[4], "total,1");
+    }
+    #[test]
     fn total_energy_interpolated_peak() {
         let mut s = Spectrum::new(micrometer!(1.0)..micrometer!(4.0), micrometer!(1.0)).unwrap();
         s.add_single_peak(micrometer!(1.5), 1.0).unwrap();
         assert_eq!(s.total_energy(), 1.0);
     }
     #[test]
+    fn fwhm() {
+        let s = Spectrum {
+            data: vec![(1.0, 0.0), (2.0, 2.0), (3.0, 4.0), (4.0, 2.0), (5.0, 0.0)],
+        };
+        assert_eq!(s.fwhm(), Some(micrometer!(2.0)));
+        let s = Spectrum {
+            data: vec![(1.0, 0.0), (2.0, 4.0), (3.0, 0.0)],
+        };
+        assert_eq!(s.fwhm(), Some(micrometer!(1.0)));
+        let s = Spectrum {
+            data: vec![(1.0, 0.0), (2.0, 0.0)],
+        };
+        assert_eq!(s.fwhm(), None);
+        assert_eq!(Spectrum { data: vec![] }.fwhm(), None);
+        let s = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (4.0, 4.0)],
+        };
+        assert_eq!(s.fwhm(), None);
+        let s = Spectrum {
+            data: vec![(1.0, 4.0), (2.0, 4.0), (3.0, 0.0)],
+        };
+        assert_eq!(s.fwhm(), None);
+    }
+    #[test]
+    fn fwhm_gaussian_line() {
+        let mut s = Spectrum::new(nanometer!(1000.0)..nanometer!(1100.0), nanometer!(0.1)).unwrap();
+        s.add_gaussian_peak(nanometer!(1053.0), nanometer!(5.0), 1.0)
+            .unwrap();
+        assert_abs_diff_eq!(s.fwhm().unwrap().get::<nanometer>(), 5.0, epsilon = 0.01);
+        assert_abs_diff_eq!(s.centroid().get::<nanometer>(), 1053.0, epsilon = 0.1);
+    }
+    #[test]
+    fn centroid() {
+        let s = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 1.0), (3.0, 3.0), (4.0, 0.0)],
+        };
+        assert_eq!(s.centroid(), micrometer!(2.4));
+    }
+    #[test]
     fn get_value() {
         let s = Spectrum {
             data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 4.0)],
@@ -1013,6 +1533,69 @@ mod test {
         assert_eq!(s1.total_energy(), 0.0);
     }
     #[test]
+    fn resample_to_grid_wrong_params() {
+        let s = prep();
+        assert!(s.resample_to_grid(&[]).is_err());
+        assert!(s.resample_to_grid(&[micrometer!(1.0)]).is_err());
+        assert!(
+            s.resample_to_grid(&[micrometer!(1.0), micrometer!(f64::NAN)])
+                .is_err()
+        );
+        assert!(
+            s.resample_to_grid(&[micrometer!(1.0), micrometer!(2.0), micrometer!(1.5)])
+                .is_err()
+        );
+        assert!(
+            s.resample_to_grid(&[micrometer!(1.0), micrometer!(1.0)])
+                .is_err()
+        );
+    }
+    #[test]
+    fn resample_to_grid() {
+        let s = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 4.0), (4.0, 0.0)],
+        };
+        let grid: Vec<Length> = [0.0, 1.5, 2.0, 2.25, 3.5, 5.0]
+            .iter()
+            .map(|l| micrometer!(*l))
+            .collect();
+        let r = s.resample_to_grid(&grid).unwrap();
+        assert_eq!(r.lambda_vec(), vec![0.0, 1.5, 2.0, 2.25, 3.5, 5.0]);
+        assert_abs_diff_eq!(r.total_energy(), s.total_energy(), epsilon = 1e-12);
+        let values = r.data_vec();
+        assert_abs_diff_eq!(values[0], 0.5 / 1.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(values[1], 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(values[2], 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(values[3], 3.5 / 1.25, epsilon = 1e-12);
+        assert_abs_diff_eq!(values[4], 2.0 / 1.5, epsilon = 1e-12);
+        assert_eq!(values[5], 0.0);
+    }
+    #[test]
+    fn resample_to_grid_outside() {
+        let s = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 0.0)],
+        };
+        let r = s
+            .resample_to_grid(&[micrometer!(4.0), micrometer!(5.0), micrometer!(6.0)])
+            .unwrap();
+        assert_eq!(r.data, vec![(4.0, 0.0), (5.0, 0.0), (6.0, 0.0)]);
+        let r = s
+            .resample_to_grid(&[micrometer!(1.5), micrometer!(2.5)])
+            .unwrap();
+        assert_eq!(r.data, vec![(1.5, 1.5), (2.5, 2.0)]);
+    }
+    #[test]
+    fn resample_to_grid_gaussian() {
+        let mut s = Spectrum::new(nanometer!(1000.0)..nanometer!(1100.0), nanometer!(0.1)).unwrap();
+        s.add_gaussian_peak(nanometer!(1053.0), nanometer!(5.0), 1.0)
+            .unwrap();
+        let grid: Vec<Length> = (0..=37)
+            .map(|i| nanometer!(f64::from(i).mul_add(3.7, 990.0)))
+            .collect();
+        let r = s.resample_to_grid(&grid).unwrap();
+        assert_abs_diff_eq!(r.total_energy(), s.total_energy(), epsilon = 1e-12);
+    }
+    #[test]
     fn add() {
         let mut s = prep();
         s.add_single_peak(micrometer!(1.75), 1.0).unwrap();
@@ -1031,6 +1614,74 @@ mod test {
         assert_eq!(s.data_vec(), vec![0.0, 1.0, 0.5, 0.0, 0.0, 0.0]);
     }
     #[test]
+    fn div_or_zero() {
+        let mut s = prep();
+        s.map_mut(|(w, _)| (*w, 1.0));
+        let mut s2 = prep();
+        s2.map_mut(|(w, _)| {
+            if (*w - 1.5).abs() < 0.1 {
+                (*w, 0.0)
+            } else {
+                (*w, 2.0)
+            }
+        });
+        s.div_or_zero(&s2);
+        assert_eq!(s.data_vec(), vec![0.5, 0.0, 0.5, 0.5, 0.5, 0.5]);
+    }
+    #[test]
+    fn add_operator() {
+        let s1 = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0)],
+        };
+        let s2 = Spectrum {
+            data: vec![(1.5, 1.0), (2.5, 1.0), (3.5, 1.0)],
+        };
+        let s = &s1 + &s2;
+        assert_eq!(
+            s.data,
+            vec![
+                (1.0, 1.0),
+                (1.5, 2.5),
+                (2.0, 3.0),
+                (2.5, 3.5),
+                (3.0, 4.0),
+                (3.5, 1.0)
+            ]
+        );
+        assert_eq!(s1.clone() + s2.clone(), s);
+    }
+    #[test]
+    fn sub_operator() {
+        let measured = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 1.0)],
+        };
+        let simulated = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 3.0), (3.0, 0.5)],
+        };
+        let residual = &measured - &simulated;
+        assert_eq!(residual.data_vec(), vec![0.0, -1.0, 0.5]);
+        assert_eq!(measured - simulated, residual);
+    }
+    #[test]
+    fn div_operator() {
+        let s1 = Spectrum {
+            data: vec![(1.0, 1.0), (2.0, 2.0), (3.0, 3.0)],
+        };
+        let s2 = Spectrum {
+            data: vec![(1.0, 2.0), (2.0, 0.0), (3.0, 2.0), (4.0, 2.0)],
+        };
+        let s = &s1 / &s2;
+        let values = s.data_vec();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0], 0.5);
+        assert!(values[1].is_nan());
+        assert_eq!(values[2], 1.5);
+        assert_eq!(values[3], 0.0);
+        let s = &s2 / &s1;
+        assert!(s.data_vec()[3].is_nan());
+        assert_eq!((s1 / s2).data_vec()[0], 0.5);
+    }
+    #[test]
     fn serialize() {
         let s = prep();
         let s_ron =
@@ -0,0 +1,392 @@
+//! Even aspheric surface
+//!
+//! This module implements a rotationally symmetric even asphere defined by a base radius of curvature, a conic constant
+//! and polynomial coefficients.
+use super::geo_surface::GeoSurface;
+use crate::{
+    error::{OpmResult, OpossumError},
+    meter,
+    ray::Ray,
+    utils::geom_transformation::Isometry,
+};
+use log::warn;
+use nalgebra::{Point3, Vector3, vector};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uom::si::f64::Length;
+
+/// Maximum number of Newton iterations for calculating a ray intersection.
+const MAX_ITERATIONS: usize = 50;
+/// Tolerance (in meters) of the sag equation for accepting an intersection point.
+const TOLERANCE: f64 = 1.0e-12;
+
+/// Aspheric deviation of a surface from its base sphere.
+///
+/// The profile consists of a conic constant `K` and the even polynomial coefficients `a4, a6, a8, ...`. Together with
+/// the (base) radius of curvature `R = 1/c` of a surface, the sag is given by
+///
+/// `z(r) = c r² / (1 + sqrt(1 - (1 + K) c² r²)) + a4 r⁴ + a6 r⁶ + a8 r⁸ + ...`
+///
+/// **Note**: The polynomial coefficients are given in SI base units (i.e. `a4` in 1/m³, `a6` in 1/m⁵, ...).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct AsphericProfile {
+    conic: f64,
+    coefficients: Vec<f64>,
+}
+impl AsphericProfile {
+    /// Create a new [`AsphericProfile`] with the given conic constant and polynomial coefficients `a4, a6, a8, ...`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the conic constant or any of the coefficients is not finite.
+    pub fn new(conic: f64, coefficients: Vec<f64>) -> OpmResult<Self> {
+        if !conic.is_finite() {
+            return Err(OpossumError::Other("conic constant must be finite".into()));
+        }
+        if coefficients.iter().any(|a| !a.is_finite()) {
+            return Err(OpossumError::Other(
+                "aspheric coefficients must be finite".into(),
+            ));
+        }
+        Ok(Self {
+            conic,
+            coefficients,
+        })
+    }
+    /// Returns the conic constant of this [`AsphericProfile`].
+    #[must_use]
+    pub const fn conic(&self) -> f64 {
+        self.conic
+    }
+    /// Returns the polynomial coefficients `a4, a6, a8, ...` of this [`AsphericProfile`].
+    #[must_use]
+    pub fn coefficients(&self) -> &[f64] {
+        &self.coefficients
+    }
+}
+impl Display for AsphericProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "K = {}", self.conic)?;
+        for (i, a) in self.coefficients.iter().enumerate() {
+            write!(f, ", a{} = {a:e}", 2 * i + 4)?;
+        }
+        Ok(())
+    }
+}
+#[derive(Debug, Clone)]
+/// An even aspheric surface with its vertex located at the origin of its [`Isometry`].
+pub struct Asphere {
+    radius: Length,
+    profile: AsphericProfile,
+    isometry: Isometry,
+}
+impl Asphere {
+    /// Create a new [`Asphere`] located and oriented by the given [`Isometry`].
+    ///
+    /// The radius denotes the radius of curvature of the base sphere. A radius of +/- infinity leads to a flat base
+    /// surface, such that only the polynomial part of the [`AsphericProfile`] remains.
+    ///
+    /// **Note**: In contrast to a [`Sphere`](super::Sphere), the anchor point is the vertex of the surface.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the radius is zero or NaN.
+    pub fn new(radius: Length, profile: AsphericProfile, isometry: Isometry) -> OpmResult<Self> {
+        if radius.value == 0.0 || radius.is_nan() {
+            return Err(OpossumError::Other(
+                "radius of curvature must be != 0.0 and not NaN".into(),
+            ));
+        }
+        Ok(Self {
+            radius,
+            profile,
+            isometry,
+        })
+    }
+    fn curvature(&self) -> f64 {
+        if self.radius.is_infinite() {
+            0.0
+        } else {
+            1.0 / self.radius.value
+        }
+    }
+    /// Returns the sag of this [`Asphere`] at the given radial distance from the optical axis.
+    ///
+    /// This function returns `None` if the radial distance is outside the domain of the conic section.
+    #[must_use]
+    pub fn sag(&self, radial_distance: Length) -> Option<Length> {
+        self.sag_and_derivative(radial_distance.value * radial_distance.value)
+            .map(|(sag, _)| meter!(sag))
+    }
+    /// Calculate the sag and its derivative with respect to `s = r²` at the given squared radial distance.
+    ///
+    /// Returns `None` if `s` is outside the domain of the conic section.
+    fn sag_and_derivative(&self, s: f64) -> Option<(f64, f64)> {
+        let c = self.curvature();
+        let root_arg = ((1.0 + self.profile.conic) * c * c).mul_add(-s, 1.0);
+        if root_arg < 0.0 {
+            return None;
+        }
+        let root = root_arg.sqrt();
+        let mut sag = c * s / (1.0 + root);
+        let mut derivative = if root > 0.0 {
+            c / (2.0 * root)
+        } else {
+            f64::INFINITY
+        };
+        // a4 s² + a6 s³ + ...
+        let mut s_power = s;
+        for (i, a) in self.profile.coefficients.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let exponent = (i + 2) as f64;
+            derivative += exponent * a * s_power;
+            s_power *= s;
+            sag += a * s_power;
+        }
+        Some((sag, derivative))
+    }
+}
+impl GeoSurface for Asphere {
+    fn calc_intersect_and_normal_do(&self, ray: &Ray) -> Option<(Point3<Length>, Vector3<f64>)> {
+        let dir = ray.direction();
+        let pos = vector![
+            ray.position().x.value,
+            ray.position().y.value,
+            ray.position().z.value
+        ];
+        let is_back_propagating = dir.z.is_sign_negative();
+        // Solve f(t) = p_z + t d_z - sag((p_x + t d_x)² + (p_y + t d_y)²) = 0 using Newton's method.
+        // The intersection with the vertex plane serves as starting point.
+        let mut t = if dir.z == 0.0 { 0.0 } else { -pos.z / dir.z };
+        let mut converged = false;
+        for _ in 0..MAX_ITERATIONS {
+            let point = pos + t * dir;
+            let s = point.x.mul_add(point.x, point.y * point.y);
+            let (sag, sag_derivative) = self.sag_and_derivative(s)?;
+            let f = point.z - sag;
+            if f.abs() < TOLERANCE {
+                converged = true;
+                break;
+            }
+            let df =
+                (2.0 * sag_derivative).mul_add(-point.x.mul_add(dir.x, point.y * dir.y), dir.z);
+            if df == 0.0 || !df.is_finite() {
+                break;
+            }
+            t -= f / df;
+        }
+        if !converged {
+            warn!(
+                "intersection of ray with aspheric surface did not converge after {MAX_ITERATIONS} iterations. Ray is treated as missing the surface."
+            );
+            return None;
+        }
+        if t.is_sign_negative() {
+            // surface behind beam
+            return None;
+        }
+        let intersection_point = pos + t * dir;
+        let s = intersection_point.x.mul_add(
+            intersection_point.x,
+            intersection_point.y * intersection_point.y,
+        );
+        let (_, sag_derivative) = self.sag_and_derivative(s)?;
+        // gradient of F(x,y,z) = sag(x² + y²) - z
+        let mut normal_vector = vector![
+            2.0 * sag_derivative * intersection_point.x,
+            2.0 * sag_derivative * intersection_point.y,
+            -1.0
+        ]
+        .normalize();
+        if is_back_propagating {
+            normal_vector *= -1.0;
+        }
+        Some((
+            meter!(
+                intersection_point.x,
+                intersection_point.y,
+                intersection_point.z
+            ),
+            normal_vector,
+        ))
+    }
+    fn isometry(&self) -> &Isometry {
+        &self.isometry
+    }
+    fn set_isometry(&mut self, isometry: &Isometry) {
+        self.isometry = isometry.clone();
+    }
+    fn name(&self) -> String {
+        "asphere".into()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        joule, millimeter, nanometer,
+        surface::{Parabola, Sphere},
+    };
+    use approx::assert_abs_diff_eq;
+    #[test]
+    fn profile_new() {
+        assert!(AsphericProfile::new(f64::NAN, vec![]).is_err());
+        assert!(AsphericProfile::new(f64::INFINITY, vec![]).is_err());
+        assert!(AsphericProfile::new(0.0, vec![1.0, f64::NAN]).is_err());
+        let p = AsphericProfile::new(-1.0, vec![1.0, 2.0]).unwrap();
+        assert_eq!(p.conic(), -1.0);
+        assert_eq!(p.coefficients(), &[1.0, 2.0]);
+        assert_eq!(p.to_string(), "K = -1, a4 = 1e0, a6 = 2e0");
+    }
+    #[test]
+    fn new() {
+        let iso = Isometry::identity();
+        let profile = AsphericProfile::default();
+        assert!(Asphere::new(millimeter!(0.0), profile.clone(), iso.clone()).is_err());
+        assert!(Asphere::new(millimeter!(f64::NAN), profile.clone(), iso.clone()).is_err());
+        assert!(Asphere::new(millimeter!(f64::INFINITY), profile.clone(), iso.clone()).is_ok());
+        assert!(Asphere::new(millimeter!(-10.0), profile, iso).is_ok());
+    }
+    #[test]
+    fn sag() {
+        let profile = AsphericProfile::new(0.0, vec![2.0]).unwrap();
+        let a = Asphere::new(millimeter!(f64::INFINITY), profile, Isometry::identity()).unwrap();
+        let (sag, derivative) = a.sag_and_derivative(4.0).unwrap();
+        assert_eq!(sag, 32.0);
+        assert_eq!(derivative, 16.0);
+        let a = Asphere::new(
+            millimeter!(1.0),
+            AsphericProfile::default(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        assert!(a.sag_and_derivative(2.0e-6).is_none());
+        assert!(a.sag(millimeter!(2.0)).is_none());
+        assert_eq!(a.sag(millimeter!(0.0)), Some(millimeter!(0.0)));
+    }
+    #[test]
+    fn intersect_on_axis() {
+        let a = Asphere::new(
+            millimeter!(10.0),
+            AsphericProfile::new(-0.5, vec![1.0e3]).unwrap(),
+            Isometry::new_along_z(millimeter!(5.0)).unwrap(),
+        )
+        .unwrap();
+        let ray = Ray::new_collimated(millimeter!(0.0, 0.0, 0.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        let (point, normal) = a.calc_intersect_and_normal(&ray).unwrap();
+        assert_eq!(point, millimeter!(0.0, 0.0, 5.0));
+        assert_eq!(normal, vector![0.0, 0.0, -1.0]);
+        let ray = Ray::new(
+            millimeter!(0.0, 0.0, 10.0),
+            vector![0.0, 0.0, -1.0],
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (point, normal) = a.calc_intersect_and_normal(&ray).unwrap();
+        assert_eq!(point, millimeter!(0.0, 0.0, 5.0));
+        assert_eq!(normal, vector![0.0, 0.0, 1.0]);
+        // surface behind ray
+        let ray = Ray::new_collimated(millimeter!(0.0, 0.0, 6.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        assert!(a.calc_intersect_and_normal(&ray).is_none());
+    }
+    #[test]
+    fn intersect_equals_sphere() {
+        for radius in [millimeter!(20.0), millimeter!(-20.0)] {
+            let asphere =
+                Asphere::new(radius, AsphericProfile::default(), Isometry::identity()).unwrap();
+            let sphere = Sphere::new_at_position(radius, millimeter!(0.0, 0.0, 0.0)).unwrap();
+            let ray = Ray::new(
+                millimeter!(1.0, 2.0, -5.0),
+                vector![0.05, -0.1, 1.0],
+                nanometer!(1053.0),
+                joule!(1.0),
+            )
+            .unwrap();
+            let (a_point, a_normal) = asphere.calc_intersect_and_normal(&ray).unwrap();
+            let (s_point, s_normal) = sphere.calc_intersect_and_normal(&ray).unwrap();
+            assert_abs_diff_eq!(a_point.x.value, s_point.x.value, epsilon = 1e-12);
+            assert_abs_diff_eq!(a_point.y.value, s_point.y.value, epsilon = 1e-12);
+            assert_abs_diff_eq!(a_point.z.value, s_point.z.value, epsilon = 1e-12);
+            assert_abs_diff_eq!(a_normal, s_normal, epsilon = 1e-9);
+        }
+    }
+    #[test]
+    fn intersect_equals_parabola() {
+        // a conic constant of -1 yields a paraboloid with a focal length of R/2
+        let asphere = Asphere::new(
+            millimeter!(20.0),
+            AsphericProfile::new(-1.0, vec![]).unwrap(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let parabola = Parabola::new(millimeter!(10.0), &Isometry::identity()).unwrap();
+        let ray = Ray::new_collimated(
+            millimeter!(3.0, -4.0, -5.0),
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (a_point, a_normal) = asphere.calc_intersect_and_normal(&ray).unwrap();
+        let (p_point, p_normal) = parabola.calc_intersect_and_normal(&ray).unwrap();
+        assert_abs_diff_eq!(a_point.x.value, p_point.x.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(a_point.y.value, p_point.y.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(a_point.z.value, p_point.z.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(a_normal, p_normal.normalize(), epsilon = 1e-9);
+    }
+    #[test]
+    fn intersect_polynomial() {
+        let asphere = Asphere::new(
+            millimeter!(f64::INFINITY),
+            AsphericProfile::new(0.0, vec![1.0e6]).unwrap(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let ray = Ray::new_collimated(
+            millimeter!(0.0, 10.0, -5.0),
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (point, normal) = asphere.calc_intersect_and_normal(&ray).unwrap();
+        // z = a4 * y^4 = 1e6 * 1e-8 m
+        assert_abs_diff_eq!(point.z.value, 0.01, epsilon = 1e-12);
+        // dz/dy = 4 a4 y^3
+        assert_abs_diff_eq!(normal, vector![0.0, 4.0, -1.0].normalize(), epsilon = 1e-9);
+    }
+    #[test]
+    fn intersect_outside_conic() {
+        let asphere = Asphere::new(
+            millimeter!(5.0),
+            AsphericProfile::default(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let ray = Ray::new_collimated(
+            millimeter!(0.0, 10.0, -5.0),
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        assert!(asphere.calc_intersect_and_normal(&ray).is_none());
+    }
+    #[test]
+    fn isometry() {
+        let mut asphere = Asphere::new(
+            millimeter!(5.0),
+            AsphericProfile::default(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        assert_eq!(asphere.isometry(), &Isometry::identity());
+        asphere.set_isometry(&Isometry::new_along_z(millimeter!(1.0)).unwrap());
+        assert_eq!(
+            asphere.isometry(),
+            &Isometry::new_along_z(millimeter!(1.0)).unwrap()
+        );
+        assert_eq!(asphere.name(), "asphere");
+    }
+}
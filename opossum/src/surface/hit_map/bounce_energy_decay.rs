@@ -0,0 +1,171 @@
+//! Energy decay over the number of ray bounces on a surface.
+//!
+//! This module provides [`BounceEnergyDecay`], which sums up the energies stored in a [`HitMap`] for each bounce level.
+//! For reflective cavities (e.g. etalons), this yields the energy remaining after each round trip.
+use super::HitMap;
+use crate::{
+    error::OpmResult,
+    joule,
+    plottable::{PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
+    properties::Proptype,
+    utils::usize_to_f64,
+};
+use nalgebra::MatrixXx2;
+use plotters::style::RGBAColor;
+use serde::{Deserialize, Serialize};
+use uom::si::{energy::joule, f64::Energy};
+
+/// Energy hitting a surface for each bounce level.
+///
+/// The vector index represents the number of bounces the rays have undergone before hitting the surface.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct BounceEnergyDecay {
+    energies: Vec<Energy>,
+}
+impl BounceEnergyDecay {
+    /// Returns the energies per bounce level of this [`BounceEnergyDecay`].
+    #[must_use]
+    pub fn energies(&self) -> &[Energy] {
+        &self.energies
+    }
+    /// Returns `true` if this [`BounceEnergyDecay`] does not contain any data.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.energies.is_empty()
+    }
+}
+impl From<&HitMap> for BounceEnergyDecay {
+    fn from(hit_map: &HitMap) -> Self {
+        let energies = hit_map
+            .hit_map()
+            .iter()
+            .map(|bounced_hit_map| {
+                bounced_hit_map
+                    .hit_map
+                    .values()
+                    .fold(joule!(0.0), |sum, rays_hit_map| {
+                        sum + rays_hit_map.total_energy()
+                    })
+            })
+            .collect();
+        Self { energies }
+    }
+}
+impl From<BounceEnergyDecay> for Proptype {
+    fn from(value: BounceEnergyDecay) -> Self {
+        Self::BounceEnergyDecay(value)
+    }
+}
+impl Plottable for BounceEnergyDecay {
+    fn get_plot_series(
+        &self,
+        plt_type: &mut PlotType,
+        _legend: bool,
+    ) -> OpmResult<Option<Vec<PlotSeries>>> {
+        if self.energies.is_empty() {
+            return Ok(None);
+        }
+        let mut xy_data = MatrixXx2::zeros(self.energies.len());
+        for (i, energy) in self.energies.iter().enumerate() {
+            xy_data[(i, 0)] = usize_to_f64(i);
+            xy_data[(i, 1)] = energy.get::<joule>();
+        }
+        match plt_type {
+            PlotType::Line2D(_) | PlotType::Scatter2D(_) => Ok(Some(vec![PlotSeries::new(
+                &PlotData::Dim2 { xy_data },
+                RGBAColor(255, 0, 0, 1.),
+                None,
+            )])),
+            _ => Ok(None),
+        }
+    }
+    fn add_plot_specific_params(&self, plt_params: &mut PlotParameters) -> OpmResult<()> {
+        plt_params
+            .set(&PlotArgs::XLabel("number of bounces".into()))?
+            .set(&PlotArgs::YLabel("energy in J".into()))?
+            .set(&PlotArgs::AxisEqual(false))?
+            .set(&PlotArgs::PlotSize((800, 600)))?;
+        Ok(())
+    }
+    fn get_plot_type(&self, plt_params: &PlotParameters) -> PlotType {
+        PlotType::Line2D(plt_params.clone())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        J_per_cm2, meter,
+        surface::hit_map::{
+            HitPoint,
+            rays_hit_map::{EnergyHitPoint, FluenceHitPoint},
+        },
+    };
+    use assert_matches::assert_matches;
+    use uuid::Uuid;
+
+    fn add_energy(hit_map: &mut HitMap, energy: f64, bounce: usize, uuid: Uuid) {
+        hit_map
+            .add_to_hitmap(
+                HitPoint::Energy(
+                    EnergyHitPoint::new(meter!(0.0, 0.0, 0.0), joule!(energy)).unwrap(),
+                ),
+                bounce,
+                uuid,
+            )
+            .unwrap();
+    }
+    #[test]
+    fn from_empty_hit_map() {
+        let decay = BounceEnergyDecay::from(&HitMap::default());
+        assert!(decay.is_empty());
+        assert!(
+            decay
+                .get_plot_series(&mut PlotType::Line2D(PlotParameters::default()), false)
+                .unwrap()
+                .is_none()
+        );
+    }
+    #[test]
+    fn from_hit_map() {
+        let mut hit_map = HitMap::default();
+        let uuid1 = Uuid::new_v4();
+        let uuid2 = Uuid::new_v4();
+        add_energy(&mut hit_map, 1.0, 0, uuid1);
+        add_energy(&mut hit_map, 0.5, 0, uuid1);
+        add_energy(&mut hit_map, 0.1, 2, uuid1);
+        add_energy(&mut hit_map, 0.05, 2, uuid2);
+        hit_map
+            .add_to_hitmap(
+                HitPoint::Fluence(
+                    FluenceHitPoint::new(meter!(0.0, 0.0, 0.0), J_per_cm2!(1.0)).unwrap(),
+                ),
+                2,
+                Uuid::new_v4(),
+            )
+            .unwrap();
+        let decay = BounceEnergyDecay::from(&hit_map);
+        assert_eq!(
+            decay.energies(),
+            &[joule!(1.5), joule!(0.0), joule!(0.1) + joule!(0.05)]
+        );
+        let series = decay
+            .get_plot_series(&mut PlotType::Line2D(PlotParameters::default()), false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(series.len(), 1);
+    }
+    #[test]
+    fn into_proptype() {
+        let prop: Proptype = BounceEnergyDecay::default().into();
+        assert_matches!(prop, Proptype::BounceEnergyDecay(_));
+    }
+    #[test]
+    fn plot_type() {
+        let decay = BounceEnergyDecay::default();
+        assert_matches!(
+            decay.get_plot_type(&PlotParameters::default()),
+            PlotType::Line2D(_)
+        );
+    }
+}
@@ -1,54 +1,101 @@
-//! Strategies for fluence estimation
-
-use crate::properties::Proptype;
-use serde::{Deserialize, Serialize};
-use std::fmt::Display;
-
-/// Strategy for fluence estimation
-#[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum FluenceEstimator {
-    /// Calculate Voronoi cells of the hit points and use the cell area for calculation of the fluence.
-    #[default]
-    Voronoi,
-    /// Calculate the fluence at given point using a Kernel Density Estimator
-    KDE,
-    /// Simply perform binning of the hit points on a given matrix
-    Binning,
-    /// Using additional "helper rays" for each ray to calculate the evolution of a small area element around the intial ray to calcuklate the fluence
-    HelperRays,
-}
-impl Display for FluenceEstimator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Voronoi => write!(f, "Voronoi"),
-            Self::KDE => write!(f, "KDE"),
-            Self::Binning => write!(f, "Binning"),
-            Self::HelperRays => write!(f, "Helper Rays"),
-        }
-    }
-}
-impl From<FluenceEstimator> for Proptype {
-    fn from(value: FluenceEstimator) -> Self {
-        Self::FluenceEstimator(value)
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use crate::{properties::Proptype, surface::hit_map::fluence_estimator::FluenceEstimator};
-
-    #[test]
-    fn fmt() {
-        assert_eq!(format!("{}", FluenceEstimator::Voronoi), "Voronoi");
-        assert_eq!(format!("{}", FluenceEstimator::KDE), "KDE");
-        assert_eq!(format!("{}", FluenceEstimator::Binning), "Binning");
-    }
-    #[test]
-    fn from() {
-        assert!(matches!(
-            FluenceEstimator::Voronoi.into(),
-            Proptype::FluenceEstimator(_)
-        ));
-    }
-}
+//! Strategies for fluence estimation
+
+use super::rays_hit_map::HitPoints;
+use crate::properties::Proptype;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Strategy for fluence estimation
+#[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+pub enum FluenceEstimator {
+    /// Calculate Voronoi cells of the hit points and use the cell area for calculation of the fluence.
+    #[default]
+    Voronoi,
+    /// Calculate the fluence at given point using a Kernel Density Estimator
+    KDE,
+    /// Simply perform binning of the hit points on a given matrix
+    Binning,
+    /// Using additional "helper rays" for each ray to calculate the evolution of a small area element around the intial ray to calcuklate the fluence
+    HelperRays,
+}
+impl FluenceEstimator {
+    /// Checks if this [`FluenceEstimator`] can be used with the given type of [`HitPoints`].
+    ///
+    /// The helper-ray estimator requires fluence hit points while all other estimators require energy hit points.
+    #[must_use]
+    pub const fn is_compatible(&self, hit_points: &HitPoints) -> bool {
+        matches!(
+            (self, hit_points),
+            (
+                Self::Voronoi | Self::KDE | Self::Binning,
+                HitPoints::Energy(_)
+            ) | (Self::HelperRays, HitPoints::Fluence(_))
+        )
+    }
+}
+/// Deposition scheme of the hit point energies for the [`FluenceEstimator::Binning`] estimator
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinningDeposition {
+    /// The energy of each hit point is assigned to the single cell containing the hit point.
+    #[default]
+    NearestCell,
+    /// The energy of each hit point is spread across the four nearest cells (cloud-in-cell).
+    ///
+    /// The weight of each cell is given by the overlap of the cell with a virtual cell centered at the hit point. This
+    /// smoothes the result on coarse grids without introducing a smoothing length as for the kernel density estimator.
+    Bilinear,
+}
+impl Display for FluenceEstimator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Voronoi => write!(f, "Voronoi"),
+            Self::KDE => write!(f, "KDE"),
+            Self::Binning => write!(f, "Binning"),
+            Self::HelperRays => write!(f, "Helper Rays"),
+        }
+    }
+}
+impl From<FluenceEstimator> for Proptype {
+    fn from(value: FluenceEstimator) -> Self {
+        Self::FluenceEstimator(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        properties::Proptype,
+        surface::hit_map::{fluence_estimator::FluenceEstimator, rays_hit_map::HitPoints},
+    };
+
+    #[test]
+    fn fmt() {
+        assert_eq!(format!("{}", FluenceEstimator::Voronoi), "Voronoi");
+        assert_eq!(format!("{}", FluenceEstimator::KDE), "KDE");
+        assert_eq!(format!("{}", FluenceEstimator::Binning), "Binning");
+    }
+    #[test]
+    fn is_compatible() {
+        let energy = HitPoints::Energy(vec![]);
+        let fluence = HitPoints::Fluence(vec![]);
+        for estimator in [
+            FluenceEstimator::Voronoi,
+            FluenceEstimator::KDE,
+            FluenceEstimator::Binning,
+        ] {
+            assert!(estimator.is_compatible(&energy));
+            assert!(!estimator.is_compatible(&fluence));
+        }
+        assert!(FluenceEstimator::HelperRays.is_compatible(&fluence));
+        assert!(!FluenceEstimator::HelperRays.is_compatible(&energy));
+    }
+    #[test]
+    fn from() {
+        assert!(matches!(
+            FluenceEstimator::Voronoi.into(),
+            Proptype::FluenceEstimator(_)
+        ));
+    }
+}
@@ -14,24 +14,32 @@
 //!  - A [`HitMap`] stores a vector of [`BouncedHitMap`]s. The vector index represents the number of ray bounces. So, the
 //!    first entry contains all [`BouncedHitMap`]s caused by rays wih zero bounces, the second entry all [`BouncedHitMap`]s
 //!    caused by rays wih one bounce, ...
+//!
+//! The energy hitting a surface per bounce level can be extracted as a
+//! [`BounceEnergyDecay`](bounce_energy_decay::BounceEnergyDecay).
 
+pub mod bounce_energy_decay;
 pub mod fluence_estimator;
 pub mod rays_hit_map;
 
 use crate::{
     J_per_cm2,
     error::{OpmResult, OpossumError},
+    kde::{BandwidthSelection, KdeKernel},
     meter,
     nodes::fluence_detector::{Fluence, fluence_data::FluenceData},
     plottable::{AxLims, PlotArgs, PlotData, PlotParameters, PlotSeries, PlotType, Plottable},
     properties::Proptype,
-    utils::unit_format::{
-        get_exponent_for_base_unit_in_e3_steps, get_prefix_for_base_unit,
-        get_unit_value_as_length_with_format_by_exponent,
+    utils::{
+        f64_to_usize,
+        unit_format::{
+            get_exponent_for_base_unit_in_e3_steps, get_prefix_for_base_unit,
+            get_unit_value_as_length_with_format_by_exponent,
+        },
     },
 };
-use fluence_estimator::FluenceEstimator;
-use log::warn;
+use fluence_estimator::{BinningDeposition, FluenceEstimator};
+use log::{info, warn};
 use nalgebra::{DMatrix, DVector, MatrixXx2, Point2};
 use plotters::style::RGBAColor;
 use rays_hit_map::{HitPoint, HitPoints, RaysHitMap};
@@ -40,6 +48,9 @@ use std::{collections::HashMap, ops::Range};
 use uom::si::f64::Length;
 use uuid::Uuid;
 
+/// Maximum number of grid points of a fluence map calculated by [`HitMap::calc_fluence_map_adaptive`].
+pub const MAX_ADAPTIVE_GRID_POINTS: usize = 4_000_000;
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 /// Storage struct for `RaysHitMap` on a surface from a single bounce
 pub struct BouncedHitMap {
@@ -237,7 +248,7 @@ impl HitMap {
         if let Some(HitPoints::Energy(_)) = hit_point_opt {
             let (ax_1_range, ax_2_range) = self.get_bounding_box();
             let mut fluence_matrix =
-                DMatrix::from_element(nr_of_points.0, nr_of_points.1, J_per_cm2!(0.));
+                DMatrix::from_element(nr_of_points.1, nr_of_points.0, J_per_cm2!(0.));
 
             for bounced_hit_map in &self.hit_map {
                 for rays_hit_map in bounced_hit_map.hit_map.values() {
@@ -288,7 +299,7 @@ impl HitMap {
         if let Some(HitPoints::Fluence(_)) = hit_point_opt {
             let (ax_1_range, ax_2_range) = self.get_bounding_box();
             let mut fluence_matrix =
-                DMatrix::from_element(nr_of_points.0, nr_of_points.1, J_per_cm2!(0.));
+                DMatrix::from_element(nr_of_points.1, nr_of_points.0, J_per_cm2!(0.));
 
             for bounced_hit_map in &self.hit_map {
                 for rays_hit_map in bounced_hit_map.hit_map.values() {
@@ -326,6 +337,7 @@ impl HitMap {
     ///
     /// # Attributes
     /// -`nr_of_points`: tuple containing the number of (columns, rows) of the matrix on which the data should be calculated
+    /// -`kernel`: shape of the kernel
     ///
     /// # Errors
     /// This function errors if
@@ -334,11 +346,84 @@ impl HitMap {
     pub fn calc_combined_fluence_with_kde(
         &self,
         nr_of_points: (usize, usize),
+        kernel: KdeKernel,
     ) -> OpmResult<FluenceData> {
         let hit_point_opt = &self.get_first_hitpoints();
         if let Some(HitPoints::Energy(_)) = hit_point_opt {
-            self.get_merged_rays_hit_map()?
-                .calc_fluence_with_kde(nr_of_points, None, None)
+            self.get_merged_rays_hit_map()?.calc_fluence_with_kde(
+                nr_of_points,
+                &BandwidthSelection::default(),
+                kernel,
+                None,
+                None,
+            )
+        } else if let Some(HitPoints::Fluence(_)) = hit_point_opt {
+            warn!(
+                "Unexpected type of HitPoints for kernel density estimator! Changing to helper-ray estimator!"
+            );
+            self.calc_combined_fluence_with_helper_rays(nr_of_points)
+        } else {
+            Err(OpossumError::Analysis("Wrong hit point type to calculate fluence with kernel density estimator! Must be an EnergyHitpoint!".into()))
+        }
+    }
+
+    /// Calculate a fluence map ([`FluenceData`]) of this [`HitMap`] using the "Kernel Density Estimator (KDE)" method
+    /// with a separate kernel size for each ray bundle.
+    ///
+    /// In contrast to [`calc_combined_fluence_with_kde`](Self::calc_combined_fluence_with_kde), the stored
+    /// [`RaysHitMap`]s are not merged. Instead, the fluence of each [`RaysHitMap`] is calculated on a common grid with
+    /// its own band width (determined by the given [`BandwidthSelection`] strategy) and the results are summed up. This
+    /// correctly combines overlapping beams of different sizes.
+    ///
+    /// # Attributes
+    /// -`nr_of_points`: tuple containing the number of (columns, rows) of the matrix on which the data should be calculated
+    /// -`bandwidth`: strategy for selecting the band width of each kernel
+    /// -`kernel`: shape of the kernels
+    ///
+    /// # Errors
+    /// This function errors if
+    /// - no band width can be determined for one of the [`RaysHitMap`]s
+    /// - The hit point type is neither energy nor fluence
+    pub fn calc_combined_fluence_with_kde_per_bundle(
+        &self,
+        nr_of_points: (usize, usize),
+        bandwidth: &BandwidthSelection,
+        kernel: KdeKernel,
+    ) -> OpmResult<FluenceData> {
+        let hit_point_opt = &self.get_first_hitpoints();
+        if let Some(HitPoints::Energy(_)) = hit_point_opt {
+            let mut band_widths = Vec::new();
+            for bounced_hit_map in &self.hit_map {
+                for rays_hit_map in bounced_hit_map.hit_map.values() {
+                    band_widths.push((rays_hit_map, rays_hit_map.kde_bandwidth(bandwidth)?));
+                }
+            }
+            let margin = 3.
+                * band_widths
+                    .iter()
+                    .map(|(_, band_width)| *band_width)
+                    .fold(meter!(0.), Length::max);
+            let (ax_1_range, ax_2_range) = self.get_bounding_box();
+            let ax_1_range = (ax_1_range.start - margin)..(ax_1_range.end + margin);
+            let ax_2_range = (ax_2_range.start - margin)..(ax_2_range.end + margin);
+            let mut fluence_matrix =
+                DMatrix::from_element(nr_of_points.1, nr_of_points.0, J_per_cm2!(0.));
+            for (rays_hit_map, band_width) in band_widths {
+                let fl_data = rays_hit_map.calc_fluence_with_kde(
+                    nr_of_points,
+                    &BandwidthSelection::Fixed(band_width),
+                    kernel,
+                    Some(&ax_1_range),
+                    Some(&ax_2_range),
+                )?;
+                fluence_matrix += fl_data.interp_distribution();
+            }
+            Ok(FluenceData::new(
+                fluence_matrix,
+                ax_1_range,
+                ax_2_range,
+                FluenceEstimator::KDE,
+            ))
         } else if let Some(HitPoints::Fluence(_)) = hit_point_opt {
             warn!(
                 "Unexpected type of HitPoints for kernel density estimator! Changing to helper-ray estimator!"
@@ -366,8 +451,12 @@ impl HitMap {
     ) -> OpmResult<FluenceData> {
         let hit_point_opt = &self.get_first_hitpoints();
         if let Some(HitPoints::Energy(_)) = hit_point_opt {
-            self.get_merged_rays_hit_map()?
-                .calc_fluence_with_binning(nr_of_points, None, None)
+            self.get_merged_rays_hit_map()?.calc_fluence_with_binning(
+                nr_of_points,
+                BinningDeposition::default(),
+                None,
+                None,
+            )
         } else if let Some(HitPoints::Fluence(_)) = hit_point_opt {
             warn!(
                 "Unexpected type of HitPoints for binning estimator! Changing to helper-ray estimator!"
@@ -380,7 +469,8 @@ impl HitMap {
 
     /// Calculate a fluence map ([`FluenceData`]) of this [`HitMap`].
     ///
-    /// Create a fluence map with the given number of points and the concrete estimator algorithm.
+    /// Create a fluence map with the given number of points and the concrete estimator algorithm. The `kernel` is only
+    /// used by the [`FluenceEstimator::KDE`] estimator.
     ///
     /// # Errors
     ///
@@ -389,16 +479,67 @@ impl HitMap {
         &self,
         nr_of_points: (usize, usize),
         estimator: &FluenceEstimator,
+        kernel: KdeKernel,
     ) -> OpmResult<FluenceData> {
         match estimator {
             FluenceEstimator::Voronoi => self.calc_combined_fluence_with_voronoi(nr_of_points),
-            FluenceEstimator::KDE => self.calc_combined_fluence_with_kde(nr_of_points),
+            FluenceEstimator::KDE => self.calc_combined_fluence_with_kde(nr_of_points, kernel),
             FluenceEstimator::Binning => self.calc_combined_fluence_with_binning(nr_of_points),
             FluenceEstimator::HelperRays => {
                 self.calc_combined_fluence_with_helper_rays(nr_of_points)
             }
         }
     }
+
+    /// Calculate a fluence map ([`FluenceData`]) of this [`HitMap`] on an adaptively chosen grid.
+    ///
+    /// In contrast to [`calc_fluence_map`](Self::calc_fluence_map), the number of grid points is derived from the
+    /// smallest distance between two hit points of any of the stored [`RaysHitMap`]s, such that the pixel size does
+    /// not exceed this distance. This way, small beams are not smeared across a single pixel if combined with large
+    /// beams. The given `nr_of_points` is used as a lower limit and as a fallback if the adaptive grid would exceed
+    /// [`MAX_ADAPTIVE_GRID_POINTS`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the underlying concrete estimator function returns an error.
+    pub fn calc_fluence_map_adaptive(
+        &self,
+        nr_of_points: (usize, usize),
+        estimator: &FluenceEstimator,
+        kernel: KdeKernel,
+    ) -> OpmResult<FluenceData> {
+        let grid = self.adaptive_nr_of_points(nr_of_points);
+        info!("Calculating fluence map on a {}x{} grid", grid.0, grid.1);
+        self.calc_fluence_map(grid, estimator, kernel)
+    }
+    /// Returns the number of (columns, rows) of an adaptive fluence grid (see [`Self::calc_fluence_map_adaptive`]).
+    fn adaptive_nr_of_points(&self, nr_of_points: (usize, usize)) -> (usize, usize) {
+        let min_distance = self
+            .hit_map
+            .iter()
+            .flat_map(|bounced_hit_map| bounced_hit_map.hit_map.values())
+            .filter_map(RaysHitMap::min_ray_distance)
+            .reduce(Length::min);
+        let Some(min_distance) = min_distance else {
+            return nr_of_points;
+        };
+        let (x_range, y_range) = self.get_bounding_box();
+        let nr_of_points_for = |range: &Range<Length>| {
+            f64_to_usize(((range.end - range.start) / min_distance).value.ceil()) + 1
+        };
+        let grid = (
+            nr_of_points_for(&x_range).max(nr_of_points.0),
+            nr_of_points_for(&y_range).max(nr_of_points.1),
+        );
+        if grid.0.saturating_mul(grid.1) > MAX_ADAPTIVE_GRID_POINTS {
+            warn!(
+                "Adaptive fluence grid ({}x{}) too large. Using {}x{} instead.",
+                grid.0, grid.1, nr_of_points.0, nr_of_points.1
+            );
+            return nr_of_points;
+        }
+        grid
+    }
 }
 impl From<HitMap> for Proptype {
     fn from(value: HitMap) -> Self {
@@ -575,7 +716,9 @@ mod test_hit_map {
     use uuid::Uuid;
 
     use crate::{
-        J_per_cm2, joule, meter,
+        J_per_cm2, joule,
+        kde::{BandwidthSelection, KdeKernel},
+        meter, millimeter,
         plottable::{PlotParameters, Plottable},
         properties::Proptype,
         surface::hit_map::{
@@ -586,6 +729,30 @@ mod test_hit_map {
         utils::test_helper::test_helper::check_logs,
     };
 
+    /// Returns a [`HitMap`] with four hit points (1 J each) at the corners of a square with a side length of 1 m and
+    /// an optional fifth hit point in its center.
+    fn square_of_points(with_center: bool) -> HitMap {
+        let mut hm = HitMap::default();
+        let uuid = Uuid::new_v4();
+        let mut positions = vec![
+            meter!(-0.5, -0.5, 0.0),
+            meter!(-0.5, 0.5, 0.0),
+            meter!(0.5, 0.5, 0.0),
+            meter!(0.5, -0.5, 0.0),
+        ];
+        if with_center {
+            positions.push(meter!(0., 0., 0.0));
+        }
+        for pos in positions {
+            hm.add_to_hitmap(
+                HitPoint::Energy(EnergyHitPoint::new(pos, joule!(1.0)).unwrap()),
+                1,
+                uuid,
+            )
+            .unwrap();
+        }
+        hm
+    }
     #[test]
     fn hit_map() {
         let mut hm = HitMap::default();
@@ -979,6 +1146,33 @@ mod test_hit_map {
         let fl_data = hm.calc_combined_fluence_with_voronoi((51, 51)).unwrap();
         assert_relative_eq!(fl_data.interp_distribution()[(25, 25)].value, 4.);
     }
+    #[test]
+    fn calc_combined_fluence_non_square() {
+        let mut hm = HitMap::default();
+        let pos = vec![
+            meter!(-0.5, -0.5, 0.0),
+            meter!(0., 0., 0.0),
+            meter!(-0.5, 0.5, 0.0),
+            meter!(0.5, 0.5, 0.0),
+            meter!(0.5, -0.5, 0.0),
+        ];
+        for _ in 0..2 {
+            let uuid = Uuid::new_v4();
+            for p in &pos {
+                hm.add_to_hitmap(
+                    HitPoint::Energy(EnergyHitPoint::new(*p, joule!(1.0)).unwrap()),
+                    1,
+                    uuid,
+                )
+                .unwrap();
+            }
+        }
+        let fl_data = hm.calc_combined_fluence_with_voronoi((51, 41)).unwrap();
+        assert_eq!(fl_data.interp_distribution().shape(), (41, 51));
+        assert_relative_eq!(fl_data.interp_distribution()[(20, 25)].value, 4.);
+        let fl_data = hm.calc_combined_fluence_with_binning((51, 41)).unwrap();
+        assert_eq!(fl_data.interp_distribution().shape(), (41, 51));
+    }
 
     #[test]
     fn calc_combined_fluence_with_voronoi_too_few_points() {
@@ -1139,7 +1333,9 @@ mod test_hit_map {
             )
             .unwrap();
         }
-        let fl_data = hm.calc_combined_fluence_with_kde((51, 51)).unwrap();
+        let fl_data = hm
+            .calc_combined_fluence_with_kde((51, 51), KdeKernel::Gaussian)
+            .unwrap();
         assert_relative_eq!(
             fl_data.interp_distribution()[(25, 25)].value,
             5.474418964842738
@@ -1161,7 +1357,9 @@ mod test_hit_map {
             )
             .unwrap();
         }
-        let fl_data = hm.calc_combined_fluence_with_kde((51, 51)).unwrap();
+        let fl_data = hm
+            .calc_combined_fluence_with_kde((51, 51), KdeKernel::Gaussian)
+            .unwrap();
         assert_relative_eq!(
             fl_data.interp_distribution()[(25, 25)].value,
             8.969644069111087
@@ -1187,7 +1385,9 @@ mod test_hit_map {
             )
             .unwrap();
         }
-        let fl_data = hm.calc_combined_fluence_with_kde((51, 51)).unwrap();
+        let fl_data = hm
+            .calc_combined_fluence_with_kde((51, 51), KdeKernel::Gaussian)
+            .unwrap();
         check_logs(
             log::Level::Warn,
             vec![
@@ -1288,23 +1488,130 @@ mod test_hit_map {
             .unwrap();
         }
         assert!(
-            hm.calc_fluence_map((51, 51), &FluenceEstimator::Voronoi)
+            hm.calc_fluence_map((51, 51), &FluenceEstimator::Voronoi, KdeKernel::Gaussian)
                 .is_ok()
         );
         assert!(
-            hm.calc_fluence_map((51, 51), &FluenceEstimator::KDE)
+            hm.calc_fluence_map((51, 51), &FluenceEstimator::KDE, KdeKernel::Gaussian)
                 .is_ok()
         );
         assert!(
-            hm.calc_fluence_map((51, 51), &FluenceEstimator::Binning)
+            hm.calc_fluence_map((51, 51), &FluenceEstimator::Binning, KdeKernel::Gaussian)
                 .is_ok()
         );
         assert!(
-            hm.calc_fluence_map((51, 51), &FluenceEstimator::HelperRays)
+            hm.calc_fluence_map((51, 51), &FluenceEstimator::HelperRays, KdeKernel::Gaussian)
                 .is_ok()
         );
     }
 
+    #[test]
+    fn calc_combined_fluence_with_kde_per_bundle() {
+        let mut hm = HitMap::default();
+        // two square beams (1 J each) differing 10x in size
+        for (uuid, size) in [(Uuid::new_v4(), 10.0), (Uuid::new_v4(), 1.0)] {
+            for i in -5..=5 {
+                for j in -5..=5 {
+                    let pos =
+                        millimeter!(size * f64::from(i) / 5.0, size * f64::from(j) / 5.0, 0.0);
+                    hm.add_to_hitmap(
+                        HitPoint::Energy(EnergyHitPoint::new(pos, joule!(1.0 / 121.0)).unwrap()),
+                        0,
+                        uuid,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        let per_bundle = hm
+            .calc_combined_fluence_with_kde_per_bundle(
+                (201, 201),
+                &BandwidthSelection::Silverman,
+                KdeKernel::Gaussian,
+            )
+            .unwrap();
+        assert_eq!(per_bundle.interp_distribution().shape(), (201, 201));
+        assert_relative_eq!(per_bundle.total_energy().value, 2.0, max_relative = 0.02);
+        // the small beam (~25 J/cm²) dominates the peak fluence
+        assert!(per_bundle.peak() > J_per_cm2!(15.0));
+        // a single band width smears out the small beam
+        let merged = hm
+            .calc_combined_fluence_with_kde((201, 201), KdeKernel::Gaussian)
+            .unwrap();
+        assert!(merged.peak() < 0.5 * per_bundle.peak());
+        assert!(
+            hm.calc_combined_fluence_with_kde_per_bundle(
+                (51, 51),
+                &BandwidthSelection::Fixed(millimeter!(0.0)),
+                KdeKernel::Gaussian,
+            )
+            .is_err()
+        );
+        // compact kernels conserve the energy as well
+        let compact = hm
+            .calc_combined_fluence_with_kde_per_bundle(
+                (201, 201),
+                &BandwidthSelection::Silverman,
+                KdeKernel::Epanechnikov,
+            )
+            .unwrap();
+        assert_relative_eq!(compact.total_energy().value, 2.0, max_relative = 0.05);
+        assert!(compact.peak() > J_per_cm2!(15.0));
+    }
+    #[test]
+    fn calc_fluence_map_adaptive() {
+        let mut hm = HitMap::default();
+        assert_eq!(hm.adaptive_nr_of_points((51, 41)), (51, 41));
+        // large beam
+        let uuid = Uuid::new_v4();
+        for pos in [
+            meter!(-0.5, -0.5, 0.0),
+            meter!(-0.5, 0.5, 0.0),
+            meter!(0.5, 0.5, 0.0),
+            meter!(0.5, -0.5, 0.0),
+        ] {
+            hm.add_to_hitmap(
+                HitPoint::Energy(EnergyHitPoint::new(pos, joule!(1.0)).unwrap()),
+                0,
+                uuid,
+            )
+            .unwrap();
+        }
+        assert_eq!(hm.adaptive_nr_of_points((51, 41)), (51, 41));
+        // small beam
+        let uuid = Uuid::new_v4();
+        for pos in [
+            meter!(0.0, 0.0, 0.0),
+            meter!(0.01, 0.0, 0.0),
+            meter!(0.0, 0.01, 0.0),
+            meter!(0.01, 0.01, 0.0),
+        ] {
+            hm.add_to_hitmap(
+                HitPoint::Energy(EnergyHitPoint::new(pos, joule!(1.0)).unwrap()),
+                0,
+                uuid,
+            )
+            .unwrap();
+        }
+        assert_eq!(hm.adaptive_nr_of_points((51, 41)), (101, 101));
+        assert_eq!(hm.adaptive_nr_of_points((51, 201)), (101, 201));
+        let fluence = hm
+            .calc_fluence_map_adaptive((51, 51), &FluenceEstimator::Voronoi, KdeKernel::Gaussian)
+            .unwrap();
+        assert_eq!(fluence.interp_distribution().shape(), (101, 101));
+        // tiny beam -> grid would be too large
+        let uuid = Uuid::new_v4();
+        for pos in [meter!(0.0, 0.0, 0.0), meter!(1e-6, 0.0, 0.0)] {
+            hm.add_to_hitmap(
+                HitPoint::Energy(EnergyHitPoint::new(pos, joule!(1.0)).unwrap()),
+                0,
+                uuid,
+            )
+            .unwrap();
+        }
+        assert_eq!(hm.adaptive_nr_of_points((51, 41)), (51, 41));
+    }
+
     #[test]
     fn get_plot_series() {
         let mut hm = HitMap::default();
@@ -1331,4 +1638,55 @@ mod test_hit_map {
         let plt_series = plt_series.unwrap().unwrap();
         assert!(plt_series.len() == 1);
     }
+    #[test]
+    fn fluence_data_arithmetic() {
+        let full = square_of_points(true)
+            .calc_fluence_map((51, 51), &FluenceEstimator::Binning, KdeKernel::Gaussian)
+            .unwrap();
+        let corners = square_of_points(false)
+            .calc_fluence_map((51, 51), &FluenceEstimator::Binning, KdeKernel::Gaussian)
+            .unwrap();
+        assert_relative_eq!(full.total_energy().value, 5.0, max_relative = 1e-10);
+        assert_relative_eq!(corners.total_energy().value, 4.0, max_relative = 1e-10);
+        // the difference only contains the center point
+        let difference = (&full - &corners).unwrap();
+        assert_relative_eq!(difference.total_energy().value, 1.0, max_relative = 1e-10);
+        let (peak, position) = difference.peak_fluence();
+        assert_relative_eq!(peak.value, 51.0 * 51.0, max_relative = 1e-10);
+        assert_relative_eq!(position.x.value, 0.0);
+        assert_relative_eq!(position.y.value, 0.0);
+        // grids do not match
+        let coarse = square_of_points(false)
+            .calc_fluence_map((41, 41), &FluenceEstimator::Binning, KdeKernel::Gaussian)
+            .unwrap();
+        assert!((&full - &coarse).is_err());
+    }
+    #[test]
+    fn fluence_data_peak_fluence() {
+        let mut hm = HitMap::default();
+        let uuid = Uuid::new_v4();
+        // the center point carries the highest energy
+        for (pos, energy) in [
+            (meter!(-0.5, -0.5, 0.0), joule!(1.0)),
+            (meter!(0.5, 0.5, 0.0), joule!(1.0)),
+            (meter!(0., 0., 0.0), joule!(2.0)),
+        ] {
+            hm.add_to_hitmap(
+                HitPoint::Energy(EnergyHitPoint::new(pos, energy).unwrap()),
+                1,
+                uuid,
+            )
+            .unwrap();
+        }
+        let fl_data = hm
+            .calc_fluence_map((51, 51), &FluenceEstimator::KDE, KdeKernel::Gaussian)
+            .unwrap();
+        let (peak, position) = fl_data.peak_fluence();
+        assert_eq!(peak, fl_data.peak());
+        // the peak is located (within one pixel) at the center of the square
+        let (x, _, _) = fl_data.get_fluence_distribution();
+        let pixel_size = x[1] - x[0];
+        assert!(position.x.abs() <= pixel_size);
+        assert!(position.y.abs() <= pixel_size);
+    }
 }
@@ -7,7 +7,7 @@ use std::ops::Range;
 use crate::{
     J_per_cm2, centimeter,
     error::{OpmResult, OpossumError},
-    kde::Kde,
+    kde::{BandwidthSelection, Kde, KdeKernel},
     meter,
     nodes::fluence_detector::{Fluence, fluence_data::FluenceData},
     plottable::AxLims,
@@ -21,7 +21,6 @@ use crate::{
     },
 };
 use itertools::Itertools;
-use libm::modf;
 use log::warn;
 use nalgebra::{DMatrix, DVector, MatrixXx2, Point2, Point3};
 use num::Zero;
@@ -33,7 +32,7 @@ use uom::si::{
     radiant_exposure::joule_per_square_centimeter,
 };
 
-use super::fluence_estimator::FluenceEstimator;
+use super::fluence_estimator::{BinningDeposition, FluenceEstimator};
 
 /// A hit point as part of a [`RaysHitMap`].
 ///
@@ -271,6 +270,18 @@ impl RaysHitMap {
         Ok(())
     }
 
+    /// Returns the total energy of the [`HitPoints`] stored in this [`RaysHitMap`].
+    ///
+    /// Only [`EnergyHitPoint`]s carry an energy, hence zero is returned for fluence hit points.
+    #[must_use]
+    pub fn total_energy(&self) -> Energy {
+        match &self.hit_points {
+            HitPoints::Energy(vec) => vec
+                .iter()
+                .fold(Energy::zero(), |sum, hit_point| sum + hit_point.value()),
+            HitPoints::Fluence(_) => Energy::zero(),
+        }
+    }
     /// Returns the x limit (min, max) of the [`HitPoints`] that are stored in this [`RaysHitMap`]
     #[must_use]
     pub fn x_lims(&self) -> &(Length, Length) {
@@ -282,6 +293,34 @@ impl RaysHitMap {
         &self.y_lims
     }
 
+    /// Returns the smallest distance (in the x-y plane) between two hit points of this [`RaysHitMap`].
+    ///
+    /// Coincident hit points are ignored. Returns `None` if there are less than two distinct hit points.
+    #[must_use]
+    pub fn min_ray_distance(&self) -> Option<Length> {
+        let mut positions = self
+            .hit_points
+            .positions()
+            .iter()
+            .map(|p| (p.x.value, p.y.value))
+            .collect_vec();
+        positions.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut min_dist = f64::INFINITY;
+        for (i, p) in positions.iter().enumerate() {
+            for q in &positions[i + 1..] {
+                // points are sorted by x, so no closer point can follow
+                if q.0 - p.0 >= min_dist {
+                    break;
+                }
+                let dist = (q.0 - p.0).hypot(q.1 - p.1);
+                if dist > 0.0 && dist < min_dist {
+                    min_dist = dist;
+                }
+            }
+        }
+        min_dist.is_finite().then(|| meter!(min_dist))
+    }
+
     /// Merge this [`RaysHitMap`] with another [`RaysHitMap`].
     ///
     /// # Errors
@@ -318,25 +357,37 @@ impl RaysHitMap {
 
     /// Calculate a fluence map ([`FluenceData`]) of this [`RaysHitMap`] using the "Binning" method
     ///
+    /// The energy of each hit point is distributed to the cells of the fluence matrix according to the given
+    /// [`BinningDeposition`] scheme. Hit points outside the given axes ranges are ignored. For all other hit points, the
+    /// total energy is conserved.
+    ///
     /// # Attributes
     /// -`nr_of_points`: tuple containing the number of (columns, rows) of the matrix on which the data should be calculated
+    /// -`deposition`: deposition scheme of the energy of a hit point
     /// -`ax_1_range_opt`: optional range of the axis 1 on which the data should be interpolated
-    /// -`ax_2_range_opt`: optional range of the axis 2 on which the data should be interpolated    
+    /// -`ax_2_range_opt`: optional range of the axis 2 on which the data should be interpolated
     ///
     /// # Errors
     /// This function errors if
     /// - the [`RaysHitMap`] is empty.
+    /// - the number of points is zero or the axes ranges span a zero area.
     /// - The hit point type is neither energy nor fluence
     pub fn calc_fluence_with_binning(
         &self,
         nr_of_points: (usize, usize),
+        deposition: BinningDeposition,
         ax_1_range: Option<&Range<Length>>,
         ax_2_range: Option<&Range<Length>>,
     ) -> OpmResult<FluenceData> {
         if let HitPoints::Energy(hit_points) = &self.hit_points {
+            if nr_of_points.0 == 0 || nr_of_points.1 == 0 {
+                return Err(OpossumError::Analysis(
+                    "number of points for binning must be > 0".into(),
+                ));
+            }
             let (left, right, top, bottom) =
                 if let (Some(range_1), Some(range_2)) = (ax_1_range, ax_2_range) {
-                    (range_1.start, range_1.end, range_2.start, range_2.end)
+                    (range_1.start, range_1.end, range_2.end, range_2.start)
                 } else {
                     self.calc_2d_bounding_box(Length::zero())?
                 };
@@ -344,34 +395,37 @@ impl RaysHitMap {
             let bin_height: Length = (top - bottom) / usize_to_f64(nr_of_points.1);
 
             let bin_area: Area = bin_width * bin_height;
-
-            let width_step = (right - left) / (usize_to_f64(nr_of_points.0 - 1));
-            let height_step = (top - bottom) / (usize_to_f64(nr_of_points.1 - 1));
-
+            if !bin_area.is_normal() || bin_area.is_sign_negative() {
+                return Err(OpossumError::Analysis(
+                    "binning area must be positive and finite".into(),
+                ));
+            }
             let mut fluence_matrix = DMatrix::<Fluence>::zeros(nr_of_points.1, nr_of_points.0);
             for hit_point in hit_points {
-                let (fract_index_x, int_index_x) =
-                    modf((hit_point.position.x - left).value / width_step.value);
-                let (fract_index_y, int_index_y) =
-                    modf((hit_point.position.y - bottom).value / height_step.value);
-                let x_index = f64_to_usize(int_index_x);
-                let y_index = f64_to_usize(int_index_y);
-
+                if hit_point.position.x < left
+                    || hit_point.position.x > right
+                    || hit_point.position.y < bottom
+                    || hit_point.position.y > top
+                {
+                    continue;
+                }
+                // position in units of cells
+                let pos_x = ((hit_point.position.x - left) / bin_width).value;
+                let pos_y = ((hit_point.position.y - bottom) / bin_height).value;
                 let fluence = hit_point.value / bin_area;
-                let fluence_x = (1.0 - fract_index_x) * (1.0 - fract_index_y) * fluence;
-                let fl_next_x = fract_index_x * (1.0 - fract_index_y) * fluence;
-                let fl_next_y = (1.0 - fract_index_x) * fract_index_y * fluence;
-                let fl_xy_next = fract_index_x * fract_index_y * fluence;
-
-                fluence_matrix[(y_index, x_index)] += fluence_x;
-                if x_index < nr_of_points.0 - 1 {
-                    fluence_matrix[(y_index, x_index + 1)] += fl_next_x;
-                    if y_index < nr_of_points.1 - 1 {
-                        fluence_matrix[(y_index + 1, x_index + 1)] += fl_xy_next;
+                match deposition {
+                    BinningDeposition::NearestCell => {
+                        let col = f64_to_usize(pos_x).min(nr_of_points.0 - 1);
+                        let row = f64_to_usize(pos_y).min(nr_of_points.1 - 1);
+                        fluence_matrix[(row, col)] += fluence;
+                    }
+                    BinningDeposition::Bilinear => {
+                        for (row, weight_y) in bilinear_weights(pos_y, nr_of_points.1) {
+                            for (col, weight_x) in bilinear_weights(pos_x, nr_of_points.0) {
+                                fluence_matrix[(row, col)] += weight_x * weight_y * fluence;
+                            }
+                        }
                     }
-                }
-                if y_index < nr_of_points.1 - 1 {
-                    fluence_matrix[(y_index + 1, x_index)] += fl_next_y;
                 }
             }
             Ok(FluenceData::new(
@@ -477,7 +531,7 @@ impl RaysHitMap {
                     linspace(
                         range.start.get::<length::centimeter>(),
                         range.end.get::<length::centimeter>(),
-                        nr_of_points.0,
+                        nr_of_points.1,
                     )?,
                     range.clone(),
                 )
@@ -489,7 +543,7 @@ impl RaysHitMap {
                         )
                     })?;
                 (
-                    linspace(proj_ax2_lim.min, proj_ax2_lim.max, nr_of_points.0)?,
+                    linspace(proj_ax2_lim.min, proj_ax2_lim.max, nr_of_points.1)?,
                     centimeter!(proj_ax2_lim.min)..centimeter!(proj_ax2_lim.max),
                 )
             };
@@ -498,13 +552,8 @@ impl RaysHitMap {
             //currently only interpolation. voronoid data for plotting must still be implemented
             let (interp_fluence, _) =
                 interpolate_3d_triangulated_scatter_data(&voronied_data, &co_ax1, &co_ax2)?;
-            let fluence_matrix = DMatrix::from_iterator(
-                co_ax1.len(),
-                co_ax2.len(),
-                interp_fluence.iter().map(|val| J_per_cm2!(*val)),
-            );
             let fluence_data = FluenceData::new(
-                fluence_matrix,
+                interp_fluence.map(|val| J_per_cm2!(val)),
                 ax_1_range,
                 ax_2_range,
                 FluenceEstimator::Voronoi,
@@ -561,20 +610,50 @@ impl RaysHitMap {
         }
     }
 
+    /// Returns the band width of the kernel density estimator for this [`RaysHitMap`] using the given
+    /// [`BandwidthSelection`] strategy.
+    ///
+    /// # Errors
+    /// This function errors if
+    /// - no bandwidth for the kernel can be determined
+    /// - The hit point type is not energy
+    pub fn kde_bandwidth(&self, bandwidth: &BandwidthSelection) -> OpmResult<Length> {
+        if let HitPoints::Energy(hit_points) = &self.hit_points {
+            let mut kde = Kde::default();
+            kde.set_hit_map(
+                hit_points
+                    .iter()
+                    .map(|p| (p.position.xy(), p.value))
+                    .collect(),
+            );
+            let band_width = kde.bandwidth(bandwidth);
+            kde.set_band_width(band_width)?;
+            Ok(band_width)
+        } else {
+            Err(OpossumError::Analysis(
+                "wrong hit point type for kde band width! Must be an Energyhitpoint!".into(),
+            ))
+        }
+    }
     /// Calculate a fluence map ([`FluenceData`]) of this [`RaysHitMap`] using the "Kernel Density Estimator (KDE)" method
+    /// with the given [`BandwidthSelection`] strategy.
     ///
     /// # Attributes
     /// -`nr_of_points`: tuple containing the number of (columns, rows) of the matrix on which the data should be calculated
+    /// -`bandwidth`: strategy for selecting the band width of the kernel
+    /// -`kernel`: shape of the kernel
     /// -`ax_1_range_opt`: optional range of the axis 1 on which the data should be interpolated
-    /// -`ax_2_range_opt`: optional range of the axis 2 on which the data should be interpolated    
+    /// -`ax_2_range_opt`: optional range of the axis 2 on which the data should be interpolated
     ///
     /// # Errors
     /// This function errors if
-    /// - no bandwidth for the kernel can be estimated
+    /// - no bandwidth for the kernel can be determined
     /// - The hit point type is neither energy nor fluence
     pub fn calc_fluence_with_kde(
         &self,
         nr_of_points: (usize, usize),
+        bandwidth: &BandwidthSelection,
+        kernel: KdeKernel,
         ax_1_range: Option<&Range<Length>>,
         ax_2_range: Option<&Range<Length>>,
     ) -> OpmResult<FluenceData> {
@@ -585,7 +664,8 @@ impl RaysHitMap {
                 .map(|p| (p.position.xy(), p.value))
                 .collect();
             kde.set_hit_map(hitmap_2d);
-            let est_bandwidth = kde.bandwidth_estimate();
+            kde.set_kernel(kernel);
+            let est_bandwidth = kde.bandwidth(bandwidth);
             kde.set_band_width(est_bandwidth)?;
             let (left, right, top, bottom) =
                 if let (Some(range_1), Some(range_2)) = (ax_1_range, ax_2_range) {
@@ -673,7 +753,7 @@ impl RaysHitMap {
                     linspace(
                         range.start.get::<length::centimeter>(),
                         range.end.get::<length::centimeter>(),
-                        nr_of_points.0,
+                        nr_of_points.1,
                     )?,
                     range.clone(),
                 )
@@ -686,7 +766,7 @@ impl RaysHitMap {
                         )
                     })?;
                 (
-                    linspace(proj_ax2_lim.min, proj_ax2_lim.max, nr_of_points.0)?,
+                    linspace(proj_ax2_lim.min, proj_ax2_lim.max, nr_of_points.1)?,
                     centimeter!(proj_ax2_lim.min)..centimeter!(proj_ax2_lim.max),
                 )
             };
@@ -711,11 +791,7 @@ impl RaysHitMap {
             )?;
 
             Ok(FluenceData::new(
-                DMatrix::from_iterator(
-                    co_ax1.len(),
-                    co_ax2.len(),
-                    interp_fluence.iter().map(|val| J_per_cm2!(*val)),
-                ),
+                interp_fluence.map(|val| J_per_cm2!(val)),
                 ax_1_range,
                 ax_2_range,
                 FluenceEstimator::HelperRays,
@@ -753,12 +829,19 @@ impl RaysHitMap {
             FluenceEstimator::Voronoi => {
                 self.calc_fluence_with_voronoi(nr_of_points, ax_1_range_opt, ax_2_range_opt)
             }
-            FluenceEstimator::KDE => {
-                self.calc_fluence_with_kde(nr_of_points, ax_1_range_opt, ax_2_range_opt)
-            }
-            FluenceEstimator::Binning => {
-                self.calc_fluence_with_binning(nr_of_points, ax_1_range_opt, ax_2_range_opt)
-            }
+            FluenceEstimator::KDE => self.calc_fluence_with_kde(
+                nr_of_points,
+                &BandwidthSelection::default(),
+                KdeKernel::default(),
+                ax_1_range_opt,
+                ax_2_range_opt,
+            ),
+            FluenceEstimator::Binning => self.calc_fluence_with_binning(
+                nr_of_points,
+                BinningDeposition::default(),
+                ax_1_range_opt,
+                ax_2_range_opt,
+            ),
             FluenceEstimator::HelperRays => {
                 self.calc_fluence_with_helper_rays(nr_of_points, ax_1_range_opt, ax_2_range_opt)
             }
@@ -803,6 +886,20 @@ impl RaysHitMap {
         &self.hit_points
     }
 }
+/// Returns the two cell indices and their weights for a bilinear deposition at the given position.
+///
+/// The position is given in units of cells (starting at the left edge of the first cell). The weights are determined
+/// by the distance of the position to the centers of the two neighboring cells. Positions between the outer cell centers
+/// and the edge of the matrix are fully deposited into the outer cell such that the energy is conserved.
+fn bilinear_weights(position: f64, nr_of_cells: usize) -> [(usize, f64); 2] {
+    let pos = (position - 0.5).clamp(0.0, usize_to_f64(nr_of_cells - 1));
+    let lower = f64_to_usize(pos.floor());
+    let fraction = pos - pos.floor();
+    [
+        (lower, 1.0 - fraction),
+        ((lower + 1).min(nr_of_cells - 1), fraction),
+    ]
+}
 #[cfg(test)]
 mod test_hitpoint {
     use crate::{
@@ -921,10 +1018,37 @@ mod test_hitpoints {
 mod test_rays_hit_map {
     use super::RaysHitMap;
     use crate::{
-        J_per_cm2, joule, meter,
-        surface::hit_map::rays_hit_map::{EnergyHitPoint, FluenceHitPoint, HitPoint, HitPoints},
+        J_per_cm2, joule,
+        kde::{BandwidthSelection, Kde, KdeKernel},
+        meter, millimeter,
+        surface::hit_map::{
+            fluence_estimator::BinningDeposition,
+            rays_hit_map::{EnergyHitPoint, FluenceHitPoint, HitPoint, HitPoints},
+        },
+        utils::usize_to_f64,
     };
     use core::f64;
+    use nalgebra::Point3;
+    use uom::si::f64::Length;
+
+    /// Returns a hit map of `nr_of_points` with a total energy of 1 J sampling a round Gaussian distribution.
+    ///
+    /// The points are placed on a (quasi-random) Fibonacci spiral with radii following the inverse cumulative
+    /// distribution of the Gaussian. This leads to a low-noise sampling of the distribution.
+    fn gaussian_hit_map(sigma: Length, nr_of_points: usize) -> RaysHitMap {
+        let golden_angle = f64::consts::PI * (3.0 - f64::sqrt(5.0));
+        let n = usize_to_f64(nr_of_points);
+        let hit_points = (0..nr_of_points)
+            .map(|i| {
+                let i = usize_to_f64(i);
+                let r = sigma * f64::sqrt(-2.0 * f64::ln(1.0 - (i + 0.5) / n));
+                let phi = i * golden_angle;
+                let pos = Point3::new(r * phi.cos(), r * phi.sin(), millimeter!(0.0));
+                EnergyHitPoint::new(pos, joule!(1.0 / n)).unwrap()
+            })
+            .collect();
+        RaysHitMap::new(HitPoints::Energy(hit_points))
+    }
     #[test]
     fn lims() {
         let hp = HitPoints::Fluence(vec![
@@ -938,6 +1062,22 @@ mod test_rays_hit_map {
         assert_eq!(rhm.y_lims.1.value, 3.0);
     }
     #[test]
+    fn min_ray_distance() {
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![]));
+        assert!(rhm.min_ray_distance().is_none());
+        let hp = |x: f64, y: f64| EnergyHitPoint::new(meter!(x, y, 0.0), joule!(1.0)).unwrap();
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![hp(0.0, 0.0), hp(0.0, 0.0)]));
+        assert!(rhm.min_ray_distance().is_none());
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![
+            hp(0.0, 0.0),
+            hp(0.0, 0.0),
+            hp(2.0, 0.0),
+            hp(-1.0, 1.0),
+            hp(0.3, 0.4),
+        ]));
+        approx::assert_relative_eq!(rhm.min_ray_distance().unwrap().value, 0.5);
+    }
+    #[test]
     fn new_energy_hit_point() {
         let rhm = RaysHitMap::new(HitPoints::Energy(vec![]));
         assert_eq!(rhm.hit_points.len(), 0);
@@ -1054,4 +1194,240 @@ mod test_rays_hit_map {
         assert!(rhm.calc_2d_bounding_box(meter!(f64::INFINITY)).is_err());
         assert!(rhm.calc_2d_bounding_box(meter!(f64::NEG_INFINITY)).is_err());
     }
+    #[test]
+    fn calc_fluence_with_kde_wrong_hit_points() {
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![]));
+        assert!(
+            rhm.calc_fluence_with_kde(
+                (10, 10),
+                &BandwidthSelection::Scott,
+                KdeKernel::Gaussian,
+                None,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            rhm.calc_fluence_with_kde(
+                (10, 10),
+                &BandwidthSelection::Silverman,
+                KdeKernel::Gaussian,
+                None,
+                None
+            )
+            .is_err()
+        );
+    }
+    #[test]
+    fn calc_fluence_with_kde_peak_recovery() {
+        let sigma = millimeter!(1.0);
+        let rhm = gaussian_hit_map(sigma, 2000);
+        let HitPoints::Energy(hit_points) = &rhm.hit_points else {
+            panic!("wrong hit point type")
+        };
+        let mut kde = Kde::default();
+        kde.set_hit_map(
+            hit_points
+                .iter()
+                .map(|p| (p.position.xy(), p.value))
+                .collect(),
+        );
+        // grid with a node exactly at the center of the distribution
+        let range = millimeter!(-1.0)..millimeter!(1.1);
+        for selection in [
+            BandwidthSelection::Scott,
+            BandwidthSelection::Silverman,
+            BandwidthSelection::Fixed(millimeter!(0.3)),
+        ] {
+            let band_width = kde.bandwidth(&selection);
+            assert!(band_width < sigma);
+            let fluence_data = rhm
+                .calc_fluence_with_kde(
+                    (21, 21),
+                    &selection,
+                    KdeKernel::Gaussian,
+                    Some(&range),
+                    Some(&range),
+                )
+                .unwrap();
+            // the KDE of a Gaussian is a Gaussian with variance sigma^2 + band_width^2
+            let expected =
+                joule!(1.0) / (2.0 * f64::consts::PI * (sigma * sigma + band_width * band_width));
+            let true_peak = joule!(1.0) / (2.0 * f64::consts::PI * sigma * sigma);
+            let peak = fluence_data.peak();
+            approx::assert_relative_eq!(peak.value, expected.value, max_relative = 0.03);
+            approx::assert_relative_eq!(peak.value, true_peak.value, max_relative = 0.1);
+        }
+        // strong over-smoothing by a large fixed band width
+        let fluence_data = rhm
+            .calc_fluence_with_kde(
+                (21, 21),
+                &BandwidthSelection::Fixed(millimeter!(2.0)),
+                KdeKernel::Gaussian,
+                Some(&range),
+                Some(&range),
+            )
+            .unwrap();
+        let true_peak = joule!(1.0) / (2.0 * f64::consts::PI * sigma * sigma);
+        assert!(fluence_data.peak().value < 0.25 * true_peak.value);
+    }
+    #[test]
+    fn calc_fluence_with_kde_kernels() {
+        let sigma = millimeter!(1.0);
+        let rhm = gaussian_hit_map(sigma, 2000);
+        let bandwidth = BandwidthSelection::Fixed(millimeter!(0.3));
+        let true_peak = joule!(1.0) / (2.0 * f64::consts::PI * sigma * sigma);
+        let center = millimeter!(-1.0)..millimeter!(1.1);
+        // region outside the hit points (max. radius ~4.3 mm) and the compact kernel support
+        let far_away = millimeter!(6.0)..millimeter!(7.0);
+        for kernel in [
+            KdeKernel::Gaussian,
+            KdeKernel::Epanechnikov,
+            KdeKernel::TopHat,
+        ] {
+            let fluence_data = rhm
+                .calc_fluence_with_kde((21, 21), &bandwidth, kernel, Some(&center), Some(&center))
+                .unwrap();
+            approx::assert_relative_eq!(
+                fluence_data.peak().value,
+                true_peak.value,
+                max_relative = 0.1
+            );
+            let fluence_data = rhm
+                .calc_fluence_with_kde((5, 5), &bandwidth, kernel, Some(&far_away), Some(&center))
+                .unwrap();
+            if kernel == KdeKernel::Gaussian {
+                assert!(fluence_data.peak().value > 0.0);
+            } else {
+                assert_eq!(fluence_data.peak().value, 0.0);
+            }
+        }
+    }
+    #[test]
+    fn kde_bandwidth_adapts_to_point_density() {
+        for selection in [BandwidthSelection::Scott, BandwidthSelection::Silverman] {
+            let bandwidth = |sigma: Length, nr_of_points: usize| {
+                let rhm = gaussian_hit_map(sigma, nr_of_points);
+                let HitPoints::Energy(hit_points) = &rhm.hit_points else {
+                    panic!("wrong hit point type")
+                };
+                let mut kde = Kde::default();
+                kde.set_hit_map(
+                    hit_points
+                        .iter()
+                        .map(|p| (p.position.xy(), p.value))
+                        .collect(),
+                );
+                kde.bandwidth(&selection)
+            };
+            let reference = bandwidth(millimeter!(1.0), 500);
+            // smaller beam -> smaller band width
+            approx::assert_relative_eq!(
+                bandwidth(millimeter!(0.1), 500).value,
+                0.1 * reference.value,
+                max_relative = 1e-6
+            );
+            // more points -> smaller band width
+            assert!(bandwidth(millimeter!(1.0), 1000) < reference);
+        }
+    }
+    #[test]
+    fn calc_fluence_with_binning_wrong_input() {
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![]));
+        assert!(
+            rhm.calc_fluence_with_binning((10, 10), BinningDeposition::NearestCell, None, None)
+                .is_err()
+        );
+        let rhm = gaussian_hit_map(millimeter!(1.0), 10);
+        assert!(
+            rhm.calc_fluence_with_binning((0, 10), BinningDeposition::NearestCell, None, None)
+                .is_err()
+        );
+        let range = millimeter!(1.0)..millimeter!(1.0);
+        assert!(
+            rhm.calc_fluence_with_binning(
+                (10, 10),
+                BinningDeposition::Bilinear,
+                Some(&range),
+                Some(&range)
+            )
+            .is_err()
+        );
+    }
+    #[test]
+    fn calc_fluence_with_binning_single_point() {
+        let rhm = RaysHitMap::new(HitPoints::Energy(vec![
+            EnergyHitPoint::new(millimeter!(1.0, 1.0, 0.0), joule!(1.0)).unwrap(),
+        ]));
+        let range = millimeter!(0.0)..millimeter!(2.0);
+        // the hit point is located at the common corner of the four central cells
+        let fluence_data = rhm
+            .calc_fluence_with_binning(
+                (4, 4),
+                BinningDeposition::NearestCell,
+                Some(&range),
+                Some(&range),
+            )
+            .unwrap();
+        let matrix = fluence_data.interp_distribution();
+        assert_eq!(matrix.iter().filter(|f| f.value > 0.0).count(), 1);
+        approx::assert_relative_eq!(matrix[(2, 2)].value, 1.0 / 0.25e-6);
+        let fluence_data = rhm
+            .calc_fluence_with_binning(
+                (4, 4),
+                BinningDeposition::Bilinear,
+                Some(&range),
+                Some(&range),
+            )
+            .unwrap();
+        let matrix = fluence_data.interp_distribution();
+        assert_eq!(matrix.iter().filter(|f| f.value > 0.0).count(), 4);
+        for (row, col) in [(1, 1), (1, 2), (2, 1), (2, 2)] {
+            approx::assert_relative_eq!(matrix[(row, col)].value, 0.25 / 0.25e-6);
+        }
+        // hit point at the center of a cell
+        let range = millimeter!(0.25)..millimeter!(2.25);
+        let fluence_data = rhm
+            .calc_fluence_with_binning(
+                (4, 4),
+                BinningDeposition::Bilinear,
+                Some(&range),
+                Some(&range),
+            )
+            .unwrap();
+        let matrix = fluence_data.interp_distribution();
+        assert_eq!(matrix.iter().filter(|f| f.value > 0.0).count(), 1);
+        approx::assert_relative_eq!(matrix[(1, 1)].value, 1.0 / 0.25e-6);
+    }
+    #[test]
+    fn calc_fluence_with_binning_energy_conservation() {
+        let rhm = gaussian_hit_map(millimeter!(1.0), 500);
+        for deposition in [BinningDeposition::NearestCell, BinningDeposition::Bilinear] {
+            // bounding box of the hit points
+            let fluence_data = rhm
+                .calc_fluence_with_binning((7, 9), deposition, None, None)
+                .unwrap();
+            approx::assert_relative_eq!(
+                fluence_data.total_energy().value,
+                1.0,
+                max_relative = 1e-10
+            );
+            // hit points within a larger range
+            let range = millimeter!(-5.0)..millimeter!(5.0);
+            let fluence_data = rhm
+                .calc_fluence_with_binning((20, 20), deposition, Some(&range), Some(&range))
+                .unwrap();
+            approx::assert_relative_eq!(
+                fluence_data.total_energy().value,
+                1.0,
+                max_relative = 1e-10
+            );
+            // hit points outside the range are ignored
+            let range = millimeter!(0.0)..millimeter!(5.0);
+            let fluence_data = rhm
+                .calc_fluence_with_binning((20, 20), deposition, Some(&range), Some(&range))
+                .unwrap();
+            assert!(fluence_data.total_energy().value < 0.3);
+        }
+    }
 }
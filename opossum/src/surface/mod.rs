@@ -7,16 +7,23 @@
 //! An [`OpticSurface`](crate::surface::optic_surface::OpticSurface) contains a [`GeoSurface`](crate::surface::geo_surface::GeoSurface) but also
 //! adds further attributes such as a [`Coating`](crate::coatings::Coating) or an [`Aperture`](crate::aperture::Aperture).
 
+mod asphere;
 mod cylinder;
 mod parabola;
 mod plane;
 mod sphere;
+mod triangulated;
+mod zernike;
 
 pub mod geo_surface;
 pub mod hit_map;
 pub mod optic_surface;
+pub mod roughness;
 
+pub use asphere::{Asphere, AsphericProfile};
 pub use cylinder::Cylinder;
 pub use parabola::Parabola;
 pub use plane::Plane;
 pub use sphere::Sphere;
+pub use triangulated::TriangulatedSurface;
+pub use zernike::{ZernikeSurface, noll_to_nm, osa_to_nm, zernike_polynomial};
@@ -1,6 +1,8 @@
 //! Module handling optical surfaces
 use log::warn;
+use num::Zero;
 use serde::{Deserialize, Serialize};
+use uom::si::f64::Energy;
 use uuid::Uuid;
 
 use crate::{
@@ -20,12 +22,57 @@ use super::{
         fluence_estimator::FluenceEstimator,
         rays_hit_map::{HitPoint, RaysHitMap},
     },
+    roughness::SurfaceRoughness,
 };
 use core::fmt::Debug;
 
+/// Energy balance of the [`Aperture`] of an [`OpticSurface`] accumulated during an analysis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApertureClipping {
+    incident: Energy,
+    transmitted: Energy,
+}
+impl Default for ApertureClipping {
+    fn default() -> Self {
+        Self {
+            incident: Energy::zero(),
+            transmitted: Energy::zero(),
+        }
+    }
+}
+impl ApertureClipping {
+    /// Returns the total energy incident on the aperture.
+    #[must_use]
+    pub const fn incident(&self) -> Energy {
+        self.incident
+    }
+    /// Returns the total energy transmitted by the aperture.
+    #[must_use]
+    pub const fn transmitted(&self) -> Energy {
+        self.transmitted
+    }
+    /// Returns the energy clipped (blocked or apodized) by the aperture.
+    #[must_use]
+    pub fn clipped(&self) -> Energy {
+        self.incident - self.transmitted
+    }
+    /// Returns the fraction of the incident energy clipped by the aperture.
+    ///
+    /// This function returns `None` if no energy has hit the aperture.
+    #[must_use]
+    pub fn clipped_fraction(&self) -> Option<f64> {
+        if self.incident.is_zero() {
+            None
+        } else {
+            Some((self.clipped() / self.incident).value)
+        }
+    }
+}
+
 /// This struct represents an optical surface, which consists of the geometric surface shape
 /// ([`GeoSurface`](super::geo_surface::GeoSurface)) and further properties such as the [`CoatingType`].
 #[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct OpticSurface {
     #[serde(skip)]
     geo_surface: GeoSurfaceRef,
@@ -33,19 +80,26 @@ pub struct OpticSurface {
     #[serde(skip_serializing_if = "Aperture::is_none", default)]
     aperture: Aperture,
     coating: CoatingType,
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
     lidt: Fluence,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    roughness: Option<SurfaceRoughness>,
     #[serde(skip)]
     backward_rays_cache: Vec<Rays>,
     #[serde(skip)]
     forward_rays_cache: Vec<Rays>,
     #[serde(skip)]
     hit_map: HitMap,
+    #[serde(skip)]
+    hit_map_level: Option<usize>,
+    #[serde(skip)]
+    aperture_clipping: ApertureClipping,
 }
 impl Default for OpticSurface {
     /// Returns a default [`OpticSurface`].
     ///
-    /// The default is a flat surface with an ideal antireflective caoting (=no reflection), no limiting aperture
-    /// and a lidt of 1 J/cm².
+    /// The default is a flat surface with an ideal antireflective caoting (=no reflection), no limiting aperture,
+    /// a lidt of 1 J/cm² and no surface roughness (= no scattering).
     fn default() -> Self {
         Self {
             geo_surface: GeoSurfaceRef::default(),
@@ -53,9 +107,12 @@ impl Default for OpticSurface {
             aperture: Aperture::default(),
             coating: CoatingType::IdealAR,
             lidt: J_per_cm2!(1.),
+            roughness: None,
             backward_rays_cache: Vec::<Rays>::new(),
             forward_rays_cache: Vec::<Rays>::new(),
             hit_map: HitMap::default(),
+            hit_map_level: None,
+            aperture_clipping: ApertureClipping::default(),
         }
     }
 }
@@ -106,9 +163,16 @@ impl OpticSurface {
         self.aperture = aperture;
     }
     /// Sets the coating of this [`OpticSurface`].
-    pub const fn set_coating(&mut self, coating: CoatingType) {
+    pub fn set_coating(&mut self, coating: CoatingType) {
         self.coating = coating;
     }
+    /// Sets the micro-roughness of this [`OpticSurface`].
+    ///
+    /// If set, a fraction of the energy of each ray hitting this surface is scattered (see [`SurfaceRoughness`]).
+    /// `None` disables scattering.
+    pub fn set_roughness(&mut self, roughness: Option<SurfaceRoughness>) {
+        self.roughness = roughness;
+    }
     /// Returns a reference to the geo surface of this [`OpticSurface`].
     #[must_use]
     pub fn geo_surface(&self) -> GeoSurfaceRef {
@@ -124,6 +188,11 @@ impl OpticSurface {
     pub const fn coating(&self) -> &CoatingType {
         &self.coating
     }
+    /// Returns a reference to the micro-roughness of this [`OpticSurface`] (if any).
+    #[must_use]
+    pub const fn roughness(&self) -> Option<&SurfaceRoughness> {
+        self.roughness.as_ref()
+    }
 
     /// Sets the backwards rays cache of this [`OpticSurface`].
     pub fn set_backwards_rays_cache(&mut self, backward_rays_cache: Vec<Rays>) {
@@ -179,6 +248,9 @@ impl OpticSurface {
     }
     /// Add intersection point (with energy) to hit map.
     ///
+    /// The hit point is stored at the given bounce level unless a fixed level has been set using
+    /// [`set_hit_map_level`](Self::set_hit_map_level).
+    ///
     /// # Errors
     /// This function errors if adding the hit point to the hit map fails
     pub fn add_to_hit_map(
@@ -187,7 +259,15 @@ impl OpticSurface {
         bounce: usize,
         rays_uuid: Uuid,
     ) -> OpmResult<()> {
-        self.hit_map.add_to_hitmap(hit_point, bounce, rays_uuid)
+        self.hit_map
+            .add_to_hitmap(hit_point, self.hit_map_level.unwrap_or(bounce), rays_uuid)
+    }
+    /// Store all following hit points at the given level of the hit map instead of the bounce level of the ray.
+    ///
+    /// This is used for separating the hit points of the individual passes of a round-trip analysis. `None` restores the
+    /// default behavior.
+    pub const fn set_hit_map_level(&mut self, level: Option<usize>) {
+        self.hit_map_level = level;
     }
     /// Reset hit map of this [`OpticSurface`].
     pub fn reset_hit_map(&mut self) {
@@ -242,6 +322,35 @@ impl OpticSurface {
         Ok(())
     }
 
+    /// Apodize the given [`Rays`] by the [`Aperture`] of this [`OpticSurface`].
+    ///
+    /// In addition to [`Rays::apodize`], the incident and transmitted energies are accumulated in the
+    /// [`ApertureClipping`] of this surface. If no aperture is set, the rays are left untouched.
+    ///
+    /// This function returns `true` if rays have been invalidated by the aperture.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the apodization of the rays fails.
+    pub fn apodize_rays(&mut self, rays: &mut Rays, iso: &Isometry) -> OpmResult<bool> {
+        if self.aperture.is_none() {
+            return Ok(false);
+        }
+        let incident = rays.total_energy();
+        let apodized = rays.apodize(&self.aperture, iso)?;
+        self.aperture_clipping.incident += incident;
+        self.aperture_clipping.transmitted += rays.total_energy();
+        Ok(apodized)
+    }
+    /// Returns the [`ApertureClipping`] accumulated by this [`OpticSurface`] since the last reset.
+    #[must_use]
+    pub const fn aperture_clipping(&self) -> &ApertureClipping {
+        &self.aperture_clipping
+    }
+    /// Reset the [`ApertureClipping`] of this [`OpticSurface`].
+    pub fn reset_aperture_clipping(&mut self) {
+        self.aperture_clipping = ApertureClipping::default();
+    }
     /// Sets the anchor point isometry of this [`OpticSurface`]
     pub const fn set_anchor_point_iso(&mut self, iso: Isometry) {
         self.anchor_point_iso = iso;
@@ -275,7 +384,7 @@ mod test {
         joule, meter, nanometer,
         ray::Ray,
         rays::Rays,
-        surface::{Sphere, geo_surface::GeoSurfaceRef},
+        surface::{Sphere, geo_surface::GeoSurfaceRef, roughness::SurfaceRoughness},
         utils::geom_transformation::Isometry,
     };
     use core::f64;
@@ -291,6 +400,16 @@ mod test {
         assert_eq!(os.forward_rays_cache.len(), 0);
         assert!(os.hit_map.is_empty());
         assert_eq!(os.lidt, J_per_cm2!(1.0));
+        assert!(os.roughness().is_none());
+    }
+    #[test]
+    fn set_roughness() {
+        let mut os = OpticSurface::default();
+        let roughness = SurfaceRoughness::new(nanometer!(2.0), 0.01, 2.0).unwrap();
+        os.set_roughness(Some(roughness.clone()));
+        assert_eq!(os.roughness(), Some(&roughness));
+        os.set_roughness(None);
+        assert!(os.roughness().is_none());
     }
     #[test]
     fn new() {
@@ -360,6 +479,30 @@ mod test {
         assert_eq!(*os.lidt(), J_per_cm2!(2.5));
     }
     #[test]
+    fn apodize_rays() {
+        let mut os = OpticSurface::default();
+        let mut rays = Rays::default();
+        for y in [0.0, 2.0] {
+            rays.add_ray(
+                Ray::new_collimated(meter!(0.0, y, 0.0), nanometer!(1000.0), joule!(1.0)).unwrap(),
+            );
+        }
+        assert!(!os.apodize_rays(&mut rays, &Isometry::identity()).unwrap());
+        assert!(os.aperture_clipping().clipped_fraction().is_none());
+        os.set_aperture(Aperture::BinaryCircle(
+            CircleConfig::new(meter!(1.0), meter!(0.0, 0.0)).unwrap(),
+        ));
+        assert!(os.apodize_rays(&mut rays, &Isometry::identity()).unwrap());
+        assert_eq!(rays.nr_of_rays(true), 1);
+        let clipping = os.aperture_clipping();
+        assert_eq!(clipping.incident(), joule!(2.0));
+        assert_eq!(clipping.transmitted(), joule!(1.0));
+        assert_eq!(clipping.clipped(), joule!(1.0));
+        assert_eq!(clipping.clipped_fraction(), Some(0.5));
+        os.reset_aperture_clipping();
+        assert!(os.aperture_clipping().clipped_fraction().is_none());
+    }
+    #[test]
     fn add_to_rays_cache() {
         let mut os = OpticSurface::default();
         let ray =
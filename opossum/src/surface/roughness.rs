@@ -0,0 +1,261 @@
+//! Surface micro-roughness
+//!
+//! This module models the scattering of light on the micro-roughness of an optical surface. The total fraction of the
+//! scattered energy is given by the total integrated scatter (TIS) of the surface while the angular distribution of the
+//! scattered light follows a Harvey / ABg-type BSDF.
+use crate::error::{OpmResult, OpossumError};
+use nalgebra::Vector3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use uom::si::f64::Length;
+
+/// Number of intervals used for tabulating the cumulative distribution of the scatter angles.
+const NR_OF_TABLE_INTERVALS: usize = 1024;
+/// Maximum distance of a scattered direction from the specular direction in direction cosine space.
+const MAX_DIRECTION_COSINE_DISTANCE: f64 = 2.0;
+/// Maximum number of attempts to sample a propagating (non-evanescent) scattering direction.
+const MAX_SAMPLING_ATTEMPTS: usize = 100;
+
+/// The micro-roughness of an [`OpticSurface`](crate::surface::optic_surface::OpticSurface).
+///
+/// The fraction of the energy scattered by the surface is given by the total integrated scatter (TIS) calculated from
+/// the RMS roughness `σ` of the surface:
+///   - reflection: `TIS = (4π σ cos(θ) / λ)²`
+///   - transmission: `TIS = (2π σ cos(θ) |n₁ - n₂| / λ)²`
+///
+/// with `θ` being the angle of incidence and `λ` the wavelength of the ray. This approximation is only valid for smooth
+/// surfaces (`σ << λ`). The TIS is limited to 1.0.
+///
+/// The scattered directions are distributed around the specular direction following the Harvey / ABg model
+/// `BSDF ∝ 1 / (B + |β - β₀|^g)` with `β` and `β₀` being the projections of the scattered and specular directions
+/// onto the surface plane (direction cosine space). The amplitude `A` of the model is implicitly given by the TIS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
+pub struct SurfaceRoughness {
+    #[cfg_attr(feature = "json_schema", schemars(with = "f64"))]
+    rms_roughness: Length,
+    b: f64,
+    g: f64,
+}
+impl SurfaceRoughness {
+    /// Creates a new [`SurfaceRoughness`].
+    ///
+    /// The parameters `b` and `g` denote the shoulder and the slope of the ABg scatter distribution.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the RMS roughness is negative or not finite.
+    ///   - `b` is not positive or not finite.
+    ///   - `g` is negative or not finite.
+    pub fn new(rms_roughness: Length, b: f64, g: f64) -> OpmResult<Self> {
+        if rms_roughness.is_sign_negative() || !rms_roughness.is_finite() {
+            return Err(OpossumError::Other(
+                "RMS roughness must be positive and finite".into(),
+            ));
+        }
+        if b <= 0.0 || !b.is_finite() {
+            return Err(OpossumError::Other(
+                "ABg parameter B must be positive and finite".into(),
+            ));
+        }
+        if g.is_sign_negative() || !g.is_finite() {
+            return Err(OpossumError::Other(
+                "ABg parameter g must be positive and finite".into(),
+            ));
+        }
+        Ok(Self {
+            rms_roughness,
+            b,
+            g,
+        })
+    }
+    /// Returns the RMS roughness of this [`SurfaceRoughness`].
+    #[must_use]
+    pub const fn rms_roughness(&self) -> Length {
+        self.rms_roughness
+    }
+    /// Returns the ABg parameter `B` of this [`SurfaceRoughness`].
+    #[must_use]
+    pub const fn b(&self) -> f64 {
+        self.b
+    }
+    /// Returns the ABg parameter `g` of this [`SurfaceRoughness`].
+    #[must_use]
+    pub const fn g(&self) -> f64 {
+        self.g
+    }
+    /// Returns the total integrated scatter (TIS) of this [`SurfaceRoughness`].
+    ///
+    /// The `cos_incidence` denotes the cosine of the angle of incidence. For a reflection, `index_contrast` is `None`.
+    /// For a transmission, it denotes the difference `|n₁ - n₂|` of the refractive indices in front of and behind the
+    /// surface.
+    #[must_use]
+    pub fn total_integrated_scatter(
+        &self,
+        wavelength: Length,
+        cos_incidence: f64,
+        index_contrast: Option<f64>,
+    ) -> f64 {
+        let factor = index_contrast.map_or(4.0 * PI, |delta_n| 2.0 * PI * delta_n.abs());
+        let phase = factor * (self.rms_roughness / wavelength).value * cos_incidence.abs();
+        (phase * phase).min(1.0)
+    }
+    /// Create a [`ScatterSampler`] for drawing random scattering directions from the ABg distribution of this [`SurfaceRoughness`].
+    #[must_use]
+    pub fn sampler(&self) -> ScatterSampler {
+        let radii: Vec<f64> = (0..=NR_OF_TABLE_INTERVALS)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let t = i as f64 / NR_OF_TABLE_INTERVALS as f64;
+                MAX_DIRECTION_COSINE_DISTANCE * t * t
+            })
+            .collect();
+        // radial probability density r * BSDF(r) (the factor r accounts for the area element in polar coordinates)
+        let pdf = |r: f64| r / (self.b + r.powf(self.g));
+        let mut cdf = Vec::with_capacity(radii.len());
+        let mut sum = 0.0;
+        cdf.push(sum);
+        for r in radii.windows(2) {
+            sum += 0.5 * (pdf(r[0]) + pdf(r[1])) * (r[1] - r[0]);
+            cdf.push(sum);
+        }
+        for value in &mut cdf {
+            *value /= sum;
+        }
+        ScatterSampler { radii, cdf }
+    }
+}
+
+/// Random sampler of scattering directions following a tabulated ABg distribution.
+///
+/// A [`ScatterSampler`] is created by [`SurfaceRoughness::sampler`].
+#[derive(Debug, Clone)]
+pub struct ScatterSampler {
+    radii: Vec<f64>,
+    cdf: Vec<f64>,
+}
+impl ScatterSampler {
+    /// Draw a radial distance from the specular direction (in direction cosine space) by inverting the tabulated cumulative distribution.
+    fn sample_radius<R: Rng>(&self, rng: &mut R) -> f64 {
+        let u: f64 = rng.random();
+        let idx = self
+            .cdf
+            .partition_point(|c| *c < u)
+            .clamp(1, self.cdf.len() - 1);
+        let (c0, c1) = (self.cdf[idx - 1], self.cdf[idx]);
+        let (r0, r1) = (self.radii[idx - 1], self.radii[idx]);
+        if c1 > c0 {
+            r0 + (r1 - r0) * (u - c0) / (c1 - c0)
+        } else {
+            r0
+        }
+    }
+    /// Draw a random scattering direction around the given specular direction.
+    ///
+    /// The surface normal may point to either side of the surface. The scattered direction always lies on the same side
+    /// of the surface as the specular direction. If no propagating direction could be found after a number of attempts,
+    /// the specular direction is returned.
+    pub fn sample_direction<R: Rng>(
+        &self,
+        specular_dir: Vector3<f64>,
+        surface_normal: Vector3<f64>,
+        rng: &mut R,
+    ) -> Vector3<f64> {
+        let specular_dir = specular_dir.normalize();
+        let mut normal = surface_normal.normalize();
+        if normal.dot(&specular_dir).is_sign_negative() {
+            normal = -normal;
+        }
+        let beta_0 = specular_dir - specular_dir.dot(&normal) * normal;
+        let helper = if normal.x.abs() < 0.9 {
+            Vector3::x()
+        } else {
+            Vector3::y()
+        };
+        let u = normal.cross(&helper).normalize();
+        let v = normal.cross(&u);
+        for _ in 0..MAX_SAMPLING_ATTEMPTS {
+            let r = self.sample_radius(rng);
+            let phi = 2.0 * PI * rng.random::<f64>();
+            let beta = beta_0 + r * (phi.cos() * u + phi.sin() * v);
+            let beta_sq = beta.norm_squared();
+            if beta_sq < 1.0 {
+                return beta + (1.0 - beta_sq).sqrt() * normal;
+            }
+        }
+        specular_dir
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{millimeter, nanometer};
+    use approx::assert_abs_diff_eq;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn new() {
+        assert!(SurfaceRoughness::new(nanometer!(-1.0), 0.01, 2.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(f64::NAN), 0.01, 2.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(f64::INFINITY), 0.01, 2.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(1.0), 0.0, 2.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(1.0), f64::NAN, 2.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(1.0), 0.01, -1.0).is_err());
+        assert!(SurfaceRoughness::new(nanometer!(1.0), 0.01, f64::INFINITY).is_err());
+        let r = SurfaceRoughness::new(nanometer!(1.0), 0.01, 2.0).unwrap();
+        assert_eq!(r.rms_roughness(), nanometer!(1.0));
+        assert_eq!(r.b(), 0.01);
+        assert_eq!(r.g(), 2.0);
+    }
+    #[test]
+    fn total_integrated_scatter() {
+        let r = SurfaceRoughness::new(nanometer!(1.0), 0.01, 2.0).unwrap();
+        let tis = r.total_integrated_scatter(nanometer!(1000.0), 1.0, None);
+        assert_abs_diff_eq!(tis, (4.0 * PI * 0.001_f64).powi(2));
+        let tis = r.total_integrated_scatter(nanometer!(1000.0), 0.5, Some(0.5));
+        assert_abs_diff_eq!(tis, (0.5 * PI * 0.001_f64).powi(2));
+        assert_eq!(
+            r.total_integrated_scatter(nanometer!(1000.0), 1.0, Some(0.0)),
+            0.0
+        );
+        let r = SurfaceRoughness::new(millimeter!(1.0), 0.01, 2.0).unwrap();
+        assert_eq!(
+            r.total_integrated_scatter(nanometer!(1000.0), 1.0, None),
+            1.0
+        );
+    }
+    #[test]
+    fn sampler_cdf() {
+        let s = SurfaceRoughness::new(nanometer!(1.0), 0.01, 2.0)
+            .unwrap()
+            .sampler();
+        assert_eq!(s.cdf[0], 0.0);
+        assert_abs_diff_eq!(*s.cdf.last().unwrap(), 1.0);
+        assert!(s.cdf.windows(2).all(|c| c[0] <= c[1]));
+    }
+    #[test]
+    fn sample_direction() {
+        let s = SurfaceRoughness::new(nanometer!(1.0), 0.001, 2.0)
+            .unwrap()
+            .sampler();
+        let specular = Vector3::new(0.0, 0.3, 1.0).normalize();
+        let normal = Vector3::new(0.0, 0.0, -1.0);
+        let mut rng = StdRng::seed_from_u64(42);
+        let dirs: Vec<Vector3<f64>> = (0..1000)
+            .map(|_| s.sample_direction(specular, normal, &mut rng))
+            .collect();
+        for dir in &dirs {
+            assert_abs_diff_eq!(dir.norm(), 1.0, epsilon = 1e-12);
+            assert!(dir.z > 0.0);
+        }
+        // the distribution is peaked around the specular direction
+        let mean = dirs.iter().sum::<Vector3<f64>>().normalize();
+        assert!(mean.dot(&specular) > 0.9);
+        // deterministic for identical seeds
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(dirs[0], s.sample_direction(specular, normal, &mut rng));
+    }
+}
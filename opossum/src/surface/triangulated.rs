@@ -0,0 +1,615 @@
+//! Triangulated (tessellated) surface
+//!
+//! This module implements a surface given by a mesh of triangles. This allows for modelling surfaces without an
+//! analytical description (e.g. measured or tessellated freeform surfaces). Since a brute-force intersection test of a
+//! ray with all triangles becomes slow for fine meshes, the triangles are (optionally) organized in a bounding volume
+//! hierarchy (BVH).
+use super::geo_surface::GeoSurface;
+use crate::{
+    error::{OpmResult, OpossumError},
+    meter,
+    ray::Ray,
+    utils::geom_transformation::Isometry,
+};
+use nalgebra::{Point3, Vector3};
+use uom::si::f64::Length;
+
+/// Maximum number of triangles stored in a single leaf of the bounding volume hierarchy.
+const MAX_TRIANGLES_PER_LEAF: usize = 4;
+/// Tolerance of the barycentric coordinates for accepting an intersection at the edge of a triangle.
+const EDGE_TOLERANCE: f64 = 1.0e-12;
+
+/// Axis-aligned bounding box (in meters).
+#[derive(Debug, Clone, Copy)]
+struct Aabb {
+    min: Vector3<f64>,
+    max: Vector3<f64>,
+}
+impl Aabb {
+    fn empty() -> Self {
+        Self {
+            min: Vector3::repeat(f64::INFINITY),
+            max: Vector3::repeat(f64::NEG_INFINITY),
+        }
+    }
+    fn grow(&mut self, point: &Vector3<f64>) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+    fn merge(&mut self, other: &Self) {
+        self.min = self.min.inf(&other.min);
+        self.max = self.max.sup(&other.max);
+    }
+    /// Returns the entry distance of a ray into this box (slab test) or `None` if the ray misses the box or the box
+    /// lies completely behind the ray.
+    fn entry_distance(&self, origin: &Vector3<f64>, inv_dir: &Vector3<f64>) -> Option<f64> {
+        let mut t_min = f64::NEG_INFINITY;
+        let mut t_max = f64::INFINITY;
+        for axis in 0..3 {
+            let t1 = (self.min[axis] - origin[axis]) * inv_dir[axis];
+            let t2 = (self.max[axis] - origin[axis]) * inv_dir[axis];
+            // NaN occurs for a ray parallel to a slab with its origin on the slab boundary. Treat it as a hit.
+            let (near, far) = if t1 <= t2 { (t1, t2) } else { (t2, t1) };
+            if !near.is_nan() {
+                t_min = t_min.max(near);
+            }
+            if !far.is_nan() {
+                t_max = t_max.min(far);
+            }
+        }
+        (t_max >= t_min.max(0.0)).then_some(t_min.max(0.0))
+    }
+}
+#[derive(Debug, Clone)]
+enum BvhNode {
+    Leaf {
+        bounds: Aabb,
+        first: usize,
+        count: usize,
+    },
+    Inner {
+        bounds: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+impl BvhNode {
+    const fn bounds(&self) -> &Aabb {
+        match self {
+            Self::Leaf { bounds, .. } | Self::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+/// Bounding volume hierarchy over the triangles of a [`TriangulatedSurface`].
+#[derive(Debug, Clone)]
+struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// triangle indices ordered such that each leaf refers to a contiguous range
+    triangle_order: Vec<usize>,
+}
+impl Bvh {
+    fn new(triangle_bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            triangle_order: (0..triangle_bounds.len()).collect(),
+        };
+        if !triangle_bounds.is_empty() {
+            bvh.build(triangle_bounds, 0, triangle_bounds.len());
+        }
+        bvh
+    }
+    /// Recursively build the hierarchy for the triangles `triangle_order[first..first + count]` by splitting at the
+    /// median centroid along the longest axis. Returns the index of the created node.
+    fn build(&mut self, triangle_bounds: &[Aabb], first: usize, count: usize) -> usize {
+        let range = first..first + count;
+        let mut bounds = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for &idx in &self.triangle_order[range.clone()] {
+            bounds.merge(&triangle_bounds[idx]);
+            centroid_bounds.grow(&centroid(&triangle_bounds[idx]));
+        }
+        let node_idx = self.nodes.len();
+        if count <= MAX_TRIANGLES_PER_LEAF {
+            self.nodes.push(BvhNode::Leaf {
+                bounds,
+                first,
+                count,
+            });
+            return node_idx;
+        }
+        let axis = (centroid_bounds.max - centroid_bounds.min).imax();
+        let half = count / 2;
+        self.triangle_order[range].select_nth_unstable_by(half, |a, b| {
+            centroid(&triangle_bounds[*a])[axis].total_cmp(&centroid(&triangle_bounds[*b])[axis])
+        });
+        // placeholder, replaced after the children have been built
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            first,
+            count,
+        });
+        let left = self.build(triangle_bounds, first, half);
+        let right = self.build(triangle_bounds, first + half, count - half);
+        self.nodes[node_idx] = BvhNode::Inner {
+            bounds,
+            left,
+            right,
+        };
+        node_idx
+    }
+}
+fn centroid(bounds: &Aabb) -> Vector3<f64> {
+    0.5 * (bounds.min + bounds.max)
+}
+/// Intersection of a ray with a single triangle.
+struct TriangleHit {
+    distance: f64,
+    triangle: usize,
+    /// barycentric coordinates (u, v) with respect to the second and third vertex
+    u: f64,
+    v: f64,
+}
+
+#[derive(Debug, Clone)]
+/// A surface consisting of a mesh of triangles.
+///
+/// The intersection point of a ray is calculated exactly on the (flat) triangles while the surface normal is
+/// interpolated from the vertex normals. The vertex normals are derived from the (area-weighted) normals of all
+/// adjacent triangles. This leads to a smooth behaviour of tessellated curved surfaces.
+///
+/// By default, the triangles are organized in a bounding volume hierarchy for fast intersection tests. This is
+/// transparent to the caller and can be switched off (see [`set_accelerated`](TriangulatedSurface::set_accelerated))
+/// for comparison purposes.
+pub struct TriangulatedSurface {
+    vertices: Vec<Vector3<f64>>,
+    vertex_normals: Vec<Vector3<f64>>,
+    triangles: Vec<[usize; 3]>,
+    bvh: Option<Bvh>,
+    isometry: Isometry,
+}
+impl TriangulatedSurface {
+    /// Create a new [`TriangulatedSurface`] from the given vertices and triangles (given as vertex indices).
+    ///
+    /// The vertices are given in the local coordinate system of the surface which is located and oriented by the given
+    /// [`Isometry`]. Similar to the other surfaces, the surface should face the negative z direction (i.e. the optical
+    /// axis is along the z axis).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - no triangles are given.
+    ///   - any vertex coordinate is not finite.
+    ///   - any triangle refers to a non-existing vertex or is degenerated (zero area).
+    pub fn new(
+        vertices: &[Point3<Length>],
+        triangles: Vec<[usize; 3]>,
+        isometry: Isometry,
+    ) -> OpmResult<Self> {
+        if triangles.is_empty() {
+            return Err(OpossumError::Other(
+                "triangulated surface must contain at least one triangle".into(),
+            ));
+        }
+        let vertices: Vec<Vector3<f64>> = vertices
+            .iter()
+            .map(|v| Vector3::new(v.x.value, v.y.value, v.z.value))
+            .collect();
+        if vertices.iter().any(|v| !v.iter().all(|c| c.is_finite())) {
+            return Err(OpossumError::Other("vertices must be finite".into()));
+        }
+        let mut vertex_normals = vec![Vector3::zeros(); vertices.len()];
+        for triangle in &triangles {
+            if triangle.iter().any(|idx| *idx >= vertices.len()) {
+                return Err(OpossumError::Other(
+                    "triangle refers to a non-existing vertex".into(),
+                ));
+            }
+            let [a, b, c] = triangle.map(|idx| vertices[idx]);
+            // cross product has the length of twice the triangle area => area weighting
+            let mut face_normal = (b - a).cross(&(c - a));
+            if face_normal.norm() == 0.0 {
+                return Err(OpossumError::Other(
+                    "triangle must not be degenerated".into(),
+                ));
+            }
+            if face_normal.z > 0.0 {
+                face_normal *= -1.0;
+            }
+            for idx in triangle {
+                vertex_normals[*idx] += face_normal;
+            }
+        }
+        for normal in &mut vertex_normals {
+            *normal = normal.try_normalize(0.0).unwrap_or_default();
+        }
+        let mut surface = Self {
+            vertices,
+            vertex_normals,
+            triangles,
+            bvh: None,
+            isometry,
+        };
+        surface.set_accelerated(true);
+        Ok(surface)
+    }
+    /// Create a [`TriangulatedSurface`] by sampling a sag function on a regular, square grid.
+    ///
+    /// The grid spans `[-half_width; half_width]` in x and y direction with the given number of points per axis. Each grid
+    /// cell is split into two triangles. The sag function returns the z coordinate for a given (x, y) position. Grid cells
+    /// with points for which the sag function returns `None` are omitted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the half width is not positive and finite.
+    ///   - the number of points per axis is < 2.
+    ///   - the resulting mesh is empty or invalid (see [`new`](TriangulatedSurface::new)).
+    pub fn from_sag<F>(
+        half_width: Length,
+        nr_of_points: usize,
+        sag: F,
+        isometry: Isometry,
+    ) -> OpmResult<Self>
+    where
+        F: Fn(Length, Length) -> Option<Length>,
+    {
+        if !half_width.is_normal() || half_width.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "half width must be positive and finite".into(),
+            ));
+        }
+        if nr_of_points < 2 {
+            return Err(OpossumError::Other(
+                "number of grid points per axis must be >= 2".into(),
+            ));
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let step = 2.0 * half_width / (nr_of_points - 1) as f64;
+        let mut vertices = Vec::with_capacity(nr_of_points * nr_of_points);
+        let mut index_map = vec![None; nr_of_points * nr_of_points];
+        for j in 0..nr_of_points {
+            for i in 0..nr_of_points {
+                #[allow(clippy::cast_precision_loss)]
+                let (x, y) = (-half_width + step * i as f64, -half_width + step * j as f64);
+                if let Some(z) = sag(x, y).filter(|z| z.is_finite()) {
+                    index_map[j * nr_of_points + i] = Some(vertices.len());
+                    vertices.push(Point3::new(x, y, z));
+                }
+            }
+        }
+        let mut triangles = Vec::new();
+        for j in 0..nr_of_points - 1 {
+            for i in 0..nr_of_points - 1 {
+                let corners = [
+                    index_map[j * nr_of_points + i],
+                    index_map[j * nr_of_points + i + 1],
+                    index_map[(j + 1) * nr_of_points + i + 1],
+                    index_map[(j + 1) * nr_of_points + i],
+                ];
+                if let [Some(a), Some(b), Some(c), Some(d)] = corners {
+                    triangles.push([a, b, c]);
+                    triangles.push([a, c, d]);
+                }
+            }
+        }
+        Self::new(&vertices, triangles, isometry)
+    }
+    /// Returns the number of triangles of this [`TriangulatedSurface`].
+    #[must_use]
+    pub fn nr_of_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+    /// Returns `true` if intersections are calculated using a bounding volume hierarchy.
+    #[must_use]
+    pub const fn accelerated(&self) -> bool {
+        self.bvh.is_some()
+    }
+    /// Switch the bounding volume hierarchy for intersection calculations on or off.
+    ///
+    /// Without acceleration, each ray is tested against all triangles (brute force). The results of both methods are
+    /// identical.
+    pub fn set_accelerated(&mut self, accelerated: bool) {
+        self.bvh = if accelerated {
+            let triangle_bounds: Vec<Aabb> = self
+                .triangles
+                .iter()
+                .map(|triangle| {
+                    let mut bounds = Aabb::empty();
+                    for idx in triangle {
+                        bounds.grow(&self.vertices[*idx]);
+                    }
+                    bounds
+                })
+                .collect();
+            Some(Bvh::new(&triangle_bounds))
+        } else {
+            None
+        };
+    }
+    /// Möller-Trumbore intersection of a ray with a single triangle.
+    fn intersect_triangle(
+        &self,
+        triangle: usize,
+        origin: &Vector3<f64>,
+        dir: &Vector3<f64>,
+    ) -> Option<TriangleHit> {
+        let [a, b, c] = self.triangles[triangle].map(|idx| self.vertices[idx]);
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = dir.cross(&edge2);
+        let det = edge1.dot(&p);
+        if det == 0.0 {
+            // ray parallel to triangle
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = origin - a;
+        let u = s.dot(&p) * inv_det;
+        if !(-EDGE_TOLERANCE..=1.0 + EDGE_TOLERANCE).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&edge1);
+        let v = dir.dot(&q) * inv_det;
+        if v < -EDGE_TOLERANCE || u + v > 1.0 + EDGE_TOLERANCE {
+            return None;
+        }
+        let distance = edge2.dot(&q) * inv_det;
+        (distance >= 0.0).then_some(TriangleHit {
+            distance,
+            triangle,
+            u,
+            v,
+        })
+    }
+    fn closest_hit(&self, origin: &Vector3<f64>, dir: &Vector3<f64>) -> Option<TriangleHit> {
+        let mut closest: Option<TriangleHit> = None;
+        let test_triangle = |triangle: usize, closest: &mut Option<TriangleHit>| {
+            if let Some(hit) = self.intersect_triangle(triangle, origin, dir)
+                && closest.as_ref().is_none_or(|c| hit.distance < c.distance)
+            {
+                *closest = Some(hit);
+            }
+        };
+        if let Some(bvh) = &self.bvh {
+            let inv_dir = dir.map(|c| 1.0 / c);
+            let mut stack = vec![0];
+            while let Some(node_idx) = stack.pop() {
+                let node = &bvh.nodes[node_idx];
+                let Some(entry) = node.bounds().entry_distance(origin, &inv_dir) else {
+                    continue;
+                };
+                if closest.as_ref().is_some_and(|c| c.distance < entry) {
+                    continue;
+                }
+                match node {
+                    BvhNode::Leaf { first, count, .. } => {
+                        for triangle in &bvh.triangle_order[*first..*first + *count] {
+                            test_triangle(*triangle, &mut closest);
+                        }
+                    }
+                    BvhNode::Inner { left, right, .. } => {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        } else {
+            for triangle in 0..self.triangles.len() {
+                test_triangle(triangle, &mut closest);
+            }
+        }
+        closest
+    }
+}
+impl GeoSurface for TriangulatedSurface {
+    fn calc_intersect_and_normal_do(&self, ray: &Ray) -> Option<(Point3<Length>, Vector3<f64>)> {
+        let origin = ray.position().map(|c| c.value).coords;
+        let dir = ray.direction();
+        let hit = self.closest_hit(&origin, &dir)?;
+        let intersection_point = origin + hit.distance * dir;
+        let [a, b, c] = self.triangles[hit.triangle].map(|idx| self.vertex_normals[idx]);
+        let mut normal_vector = ((1.0 - hit.u - hit.v) * a + hit.u * b + hit.v * c).normalize();
+        if dir.z.is_sign_negative() {
+            normal_vector *= -1.0;
+        }
+        Some((
+            meter!(
+                intersection_point.x,
+                intersection_point.y,
+                intersection_point.z
+            ),
+            normal_vector,
+        ))
+    }
+    fn isometry(&self) -> &Isometry {
+        &self.isometry
+    }
+    fn set_isometry(&mut self, isometry: &Isometry) {
+        self.isometry = isometry.clone();
+    }
+    fn name(&self) -> String {
+        "triangulated".into()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        degree, joule, millimeter, nanometer,
+        position_distributions::{PositionDistribution, Random},
+        surface::{Asphere, AsphericProfile, Plane},
+    };
+    use approx::assert_abs_diff_eq;
+    use nalgebra::vector;
+
+    fn square() -> TriangulatedSurface {
+        TriangulatedSurface::from_sag(
+            millimeter!(1.0),
+            2,
+            |_, _| Some(millimeter!(0.0)),
+            Isometry::identity(),
+        )
+        .unwrap()
+    }
+    #[test]
+    fn new() {
+        let vertices = [
+            millimeter!(0.0, 0.0, 0.0),
+            millimeter!(1.0, 0.0, 0.0),
+            millimeter!(0.0, 1.0, 0.0),
+        ];
+        let iso = Isometry::identity();
+        assert!(TriangulatedSurface::new(&vertices, vec![], iso.clone()).is_err());
+        assert!(TriangulatedSurface::new(&vertices, vec![[0, 1, 3]], iso.clone()).is_err());
+        assert!(TriangulatedSurface::new(&vertices, vec![[0, 1, 1]], iso.clone()).is_err());
+        assert!(
+            TriangulatedSurface::new(
+                &[
+                    millimeter!(0.0, 0.0, f64::NAN),
+                    millimeter!(1.0, 0.0, 0.0),
+                    millimeter!(0.0, 1.0, 0.0),
+                ],
+                vec![[0, 1, 2]],
+                iso.clone()
+            )
+            .is_err()
+        );
+        let s = TriangulatedSurface::new(&vertices, vec![[0, 1, 2]], iso).unwrap();
+        assert_eq!(s.nr_of_triangles(), 1);
+        assert!(s.accelerated());
+        assert_eq!(s.name(), "triangulated");
+    }
+    #[test]
+    fn from_sag() {
+        let iso = Isometry::identity();
+        let flat = |_: Length, _: Length| Some(millimeter!(0.0));
+        assert!(TriangulatedSurface::from_sag(millimeter!(0.0), 10, flat, iso.clone()).is_err());
+        assert!(TriangulatedSurface::from_sag(millimeter!(-1.0), 10, flat, iso.clone()).is_err());
+        assert!(
+            TriangulatedSurface::from_sag(millimeter!(f64::INFINITY), 10, flat, iso.clone())
+                .is_err()
+        );
+        assert!(TriangulatedSurface::from_sag(millimeter!(1.0), 1, flat, iso.clone()).is_err());
+        assert!(
+            TriangulatedSurface::from_sag(millimeter!(1.0), 10, |_, _| None, iso.clone()).is_err()
+        );
+        let s = TriangulatedSurface::from_sag(millimeter!(1.0), 11, flat, iso).unwrap();
+        assert_eq!(s.nr_of_triangles(), 200);
+    }
+    #[test]
+    fn intersect_flat() {
+        let s = square();
+        let ray = Ray::new_collimated(
+            millimeter!(0.5, -0.2, -1.0),
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (point, normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        assert_abs_diff_eq!(point.x.value, 0.5e-3);
+        assert_abs_diff_eq!(point.y.value, -0.2e-3);
+        assert_abs_diff_eq!(point.z.value, 0.0);
+        assert_eq!(normal, vector![0.0, 0.0, -1.0]);
+        let ray = Ray::new(
+            millimeter!(0.5, -0.2, 1.0),
+            vector![0.0, 0.0, -1.0],
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (_, normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        assert_eq!(normal, vector![0.0, 0.0, 1.0]);
+        // outside of mesh
+        let ray = Ray::new_collimated(millimeter!(1.5, 0.0, -1.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        assert!(s.calc_intersect_and_normal(&ray).is_none());
+        // surface behind ray
+        let ray = Ray::new_collimated(millimeter!(0.0, 0.0, 1.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        assert!(s.calc_intersect_and_normal(&ray).is_none());
+    }
+    #[test]
+    fn intersect_equals_plane() {
+        let iso = Isometry::new(millimeter!(1.0, 2.0, 3.0), degree!(10.0, -5.0, 0.0)).unwrap();
+        let mut s = square();
+        s.set_isometry(&iso);
+        let plane = Plane::new(iso);
+        let ray = Ray::new(
+            millimeter!(1.2, 2.1, 0.0),
+            vector![0.01, -0.02, 1.0],
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (s_point, s_normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        let (p_point, p_normal) = plane.calc_intersect_and_normal(&ray).unwrap();
+        assert_abs_diff_eq!(s_point.x.value, p_point.x.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(s_point.y.value, p_point.y.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(s_point.z.value, p_point.z.value, epsilon = 1e-12);
+        assert_abs_diff_eq!(s_normal, p_normal, epsilon = 1e-12);
+    }
+    #[test]
+    fn intersect_tessellated_asphere() {
+        let asphere = Asphere::new(
+            millimeter!(20.0),
+            AsphericProfile::new(-0.5, vec![1.0e3]).unwrap(),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let s = TriangulatedSurface::from_sag(
+            millimeter!(5.0),
+            201,
+            |x, y| asphere.sag((x * x + y * y).sqrt()),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let ray = Ray::new_collimated(
+            millimeter!(1.23, -2.34, -5.0),
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (s_point, s_normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        let (a_point, a_normal) = asphere.calc_intersect_and_normal(&ray).unwrap();
+        assert_abs_diff_eq!(s_point.z.value, a_point.z.value, epsilon = 1e-6);
+        assert_abs_diff_eq!(s_normal, a_normal, epsilon = 1e-3);
+    }
+    #[test]
+    fn accelerated_equals_brute_force() {
+        let mut s = TriangulatedSurface::from_sag(
+            millimeter!(5.0),
+            31,
+            |x, y| Some((x * x - y * y) / millimeter!(20.0)),
+            Isometry::identity(),
+        )
+        .unwrap();
+        let positions = Random::new(millimeter!(6.0), millimeter!(6.0), 200)
+            .unwrap()
+            .generate_seeded(42);
+        let rays: Vec<Ray> = positions
+            .iter()
+            .map(|p| {
+                Ray::new(
+                    Point3::new(p.x, p.y, millimeter!(-3.0)),
+                    vector![0.1, -0.05, 1.0],
+                    nanometer!(1053.0),
+                    joule!(1.0),
+                )
+                .unwrap()
+            })
+            .collect();
+        let accelerated: Vec<_> = rays
+            .iter()
+            .map(|r| s.calc_intersect_and_normal(r))
+            .collect();
+        s.set_accelerated(false);
+        assert!(!s.accelerated());
+        let brute_force: Vec<_> = rays
+            .iter()
+            .map(|r| s.calc_intersect_and_normal(r))
+            .collect();
+        assert_eq!(accelerated, brute_force);
+        assert!(accelerated.iter().any(Option::is_none));
+        assert!(accelerated.iter().any(Option::is_some));
+    }
+}
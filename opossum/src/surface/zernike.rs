@@ -0,0 +1,488 @@
+//! Zernike surface
+//!
+//! This module implements a flat base surface deformed by a sum of Zernike polynomials. This is useful for modelling
+//! deformable mirrors or measured surface / wavefront errors.
+use super::geo_surface::GeoSurface;
+use crate::{
+    error::{OpmResult, OpossumError},
+    meter,
+    ray::Ray,
+    utils::geom_transformation::Isometry,
+};
+use log::warn;
+use nalgebra::{Point3, Vector3, vector};
+use num::Complex;
+use uom::si::f64::Length;
+
+/// Maximum number of Newton iterations for calculating a ray intersection.
+const MAX_ITERATIONS: usize = 50;
+/// Tolerance (in meters) of the sag equation for accepting an intersection point.
+const TOLERANCE: f64 = 1.0e-12;
+
+/// Convert a single Zernike index following the Noll convention (starting at `j = 1`) into the double index `(n, m)`.
+///
+/// In the Noll convention, even indices `j` correspond to the cosine terms (`m > 0`) and odd indices to the sine terms
+/// (`m < 0`). E.g. `j = 4` is defocus `(2, 0)`.
+///
+/// # Errors
+///
+/// This function will return an error if `j` is zero.
+pub fn noll_to_nm(j: usize) -> OpmResult<(usize, i32)> {
+    if j == 0 {
+        return Err(OpossumError::Other("Noll index must be >= 1".into()));
+    }
+    let mut n = 0;
+    let mut j1 = j - 1;
+    while j1 > n {
+        n += 1;
+        j1 -= n;
+    }
+    let abs_m = (n % 2) + 2 * ((j1 + (n + 1) % 2) / 2);
+    let abs_m =
+        i32::try_from(abs_m).map_err(|_| OpossumError::Other("Noll index too large".into()))?;
+    Ok((n, if j.is_multiple_of(2) { abs_m } else { -abs_m }))
+}
+/// Convert a single Zernike index following the OSA / ANSI convention (starting at `j = 0`) into the double index `(n, m)`.
+///
+/// In the OSA / ANSI convention, the terms are ordered by radial order `n` and then by azimuthal frequency `m` from
+/// `-n` to `n`. E.g. `j = 4` is defocus `(2, 0)`.
+///
+/// # Errors
+///
+/// This function will return an error if `j` is too large to be represented.
+pub fn osa_to_nm(j: usize) -> OpmResult<(usize, i32)> {
+    let mut n = 0;
+    while (n + 1) * (n + 2) / 2 <= j {
+        n += 1;
+    }
+    let m = i64::try_from(2 * j)
+        .ok()
+        .zip(i64::try_from(n * (n + 2)).ok())
+        .and_then(|(a, b)| i32::try_from(a - b).ok())
+        .ok_or_else(|| OpossumError::Other("OSA index too large".into()))?;
+    Ok((n, m))
+}
+
+/// Evaluate the Zernike polynomial `Z_n^m` at the given normalized position `(x, y)`.
+///
+/// The same convention as for [`ZernikeSurface`] is used: `R_n^m(1) = 1`, cosine terms for `m >= 0` and sine terms
+/// for `m < 0`. The position is not restricted to the unit circle.
+///
+/// # Errors
+///
+/// This function will return an error if `|m| > n` or `n - |m|` is odd.
+pub fn zernike_polynomial(n: usize, m: i32, x: f64, y: f64) -> OpmResult<f64> {
+    let term = ZernikeTerm::new(n, m, meter!(1.0))?;
+    Ok(term.value_and_gradient(x, y).0)
+}
+/// A single Zernike term `coefficient * Z_n^m`.
+#[derive(Debug, Clone, PartialEq)]
+struct ZernikeTerm {
+    /// azimuthal frequency |m|
+    abs_m: usize,
+    /// true for the sine terms (m < 0)
+    is_sine: bool,
+    /// coefficient (in meters) and exponent of ρ of each term of the radial polynomial
+    radial: Vec<(f64, usize)>,
+}
+impl ZernikeTerm {
+    fn new(n: usize, m: i32, coefficient: Length) -> OpmResult<Self> {
+        let abs_m = m.unsigned_abs() as usize;
+        if abs_m > n || !(n - abs_m).is_multiple_of(2) {
+            return Err(OpossumError::Other(format!(
+                "invalid Zernike term (n={n}, m={m}): |m| must be <= n and n - |m| must be even"
+            )));
+        }
+        if !coefficient.is_finite() {
+            return Err(OpossumError::Other(
+                "Zernike coefficients must be finite".into(),
+            ));
+        }
+        let factorial = |k: usize| (1..=k).map(|i| i as f64).product::<f64>();
+        let radial = (0..=(n - abs_m) / 2)
+            .map(|k| {
+                let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                let c = sign * factorial(n - k)
+                    / (factorial(k)
+                        * factorial((n + abs_m) / 2 - k)
+                        * factorial((n - abs_m) / 2 - k));
+                (coefficient.value * c, n - 2 * k)
+            })
+            .collect();
+        Ok(Self {
+            abs_m,
+            is_sine: m < 0,
+            radial,
+        })
+    }
+    /// Returns the value and the gradient of this term at the given normalized position (x, y).
+    ///
+    /// Each monomial `ρ^p cos(|m| θ)` (or `sin`) is evaluated as `(x² + y²)^((p - |m|) / 2) Re((x + iy)^|m|)` (or `Im`).
+    /// This avoids the singularity of the polar representation at the origin.
+    fn value_and_gradient(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let w = Complex::new(x, y);
+        let s = x.mul_add(x, y * y);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let m = self.abs_m as i32;
+        let w_m = w.powi(m);
+        let dw_m = if m == 0 {
+            Complex::new(0.0, 0.0)
+        } else {
+            f64::from(m) * w.powi(m - 1)
+        };
+        // angular part A and its partial derivatives
+        let (a, da_dx, da_dy) = if self.is_sine {
+            (w_m.im, dw_m.im, dw_m.re)
+        } else {
+            (w_m.re, dw_m.re, -dw_m.im)
+        };
+        let (mut value, mut grad_x, mut grad_y) = (0.0, 0.0, 0.0);
+        for (c, p) in &self.radial {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let h = ((p - self.abs_m) / 2) as i32;
+            let s_h = s.powi(h);
+            let ds_h = if h == 0 {
+                0.0
+            } else {
+                f64::from(h) * s.powi(h - 1)
+            };
+            value += c * s_h * a;
+            grad_x += c * (2.0 * x * ds_h).mul_add(a, s_h * da_dx);
+            grad_y += c * (2.0 * y * ds_h).mul_add(a, s_h * da_dy);
+        }
+        (value, grad_x, grad_y)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A flat surface deformed by a sum of Zernike polynomials with its vertex plane located at the origin of its [`Isometry`].
+///
+/// The sag is given by `z(x, y) = Σ c_nm Z_n^m(ρ, θ)` with the normalized radial coordinate `ρ = r / r_0` using the
+/// normalization radius `r_0`. The polynomials are given as `Z_n^m = R_n^m(ρ) cos(m θ)` for `m >= 0` and
+/// `Z_n^m = R_n^|m|(ρ) sin(|m| θ)` for `m < 0`. The radial polynomials are not normalized (i.e. `R_n^m(1) = 1`), such that
+/// each coefficient denotes the amplitude of its term at the edge of the normalized aperture.
+///
+/// The terms are specified using the double index `(n, m)`. Hence, the result does not depend on any single-index
+/// ordering convention. For converting single indices use [`noll_to_nm`] (Noll convention) or [`osa_to_nm`]
+/// (OSA / ANSI convention).
+///
+/// **Note**: The Zernike polynomials are only defined within the normalization radius. Rays hitting the surface outside
+/// this radius are treated as missing the surface.
+pub struct ZernikeSurface {
+    normalization_radius: Length,
+    terms: Vec<ZernikeTerm>,
+    isometry: Isometry,
+}
+impl ZernikeSurface {
+    /// Create a new [`ZernikeSurface`] from the given normalization radius and a list of Zernike terms `(n, m, coefficient)`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the normalization radius is not positive and finite.
+    ///   - any term does not satisfy `|m| <= n` with `n - |m|` even.
+    ///   - any coefficient is not finite.
+    pub fn new(
+        normalization_radius: Length,
+        terms: &[(usize, i32, Length)],
+        isometry: Isometry,
+    ) -> OpmResult<Self> {
+        if !normalization_radius.is_normal() || normalization_radius.is_sign_negative() {
+            return Err(OpossumError::Other(
+                "normalization radius must be positive and finite".into(),
+            ));
+        }
+        let terms = terms
+            .iter()
+            .map(|(n, m, coefficient)| ZernikeTerm::new(*n, *m, *coefficient))
+            .collect::<OpmResult<Vec<_>>>()?;
+        Ok(Self {
+            normalization_radius,
+            terms,
+            isometry,
+        })
+    }
+    /// Returns the normalization radius of this [`ZernikeSurface`].
+    #[must_use]
+    pub const fn normalization_radius(&self) -> Length {
+        self.normalization_radius
+    }
+    /// Returns the sag of this [`ZernikeSurface`] at the given position.
+    ///
+    /// This function returns `None` if the position is outside the normalization radius.
+    #[must_use]
+    pub fn sag(&self, x: Length, y: Length) -> Option<Length> {
+        self.sag_and_gradient(x.value, y.value)
+            .map(|(sag, _, _)| meter!(sag))
+    }
+    /// Calculate the sag (in meters) and its gradient with respect to x and y (in meters) at the given position.
+    ///
+    /// Returns `None` if the position is outside the normalization radius.
+    fn sag_and_gradient(&self, x: f64, y: f64) -> Option<(f64, f64, f64)> {
+        let r_0 = self.normalization_radius.value;
+        let (x, y) = (x / r_0, y / r_0);
+        if x.mul_add(x, y * y) > 1.0 {
+            return None;
+        }
+        let (mut sag, mut grad_x, mut grad_y) = (0.0, 0.0, 0.0);
+        for term in &self.terms {
+            let (value, dx, dy) = term.value_and_gradient(x, y);
+            sag += value;
+            grad_x += dx;
+            grad_y += dy;
+        }
+        Some((sag, grad_x / r_0, grad_y / r_0))
+    }
+}
+impl GeoSurface for ZernikeSurface {
+    fn calc_intersect_and_normal_do(&self, ray: &Ray) -> Option<(Point3<Length>, Vector3<f64>)> {
+        let dir = ray.direction();
+        let pos = vector![
+            ray.position().x.value,
+            ray.position().y.value,
+            ray.position().z.value
+        ];
+        let is_back_propagating = dir.z.is_sign_negative();
+        // Solve f(t) = p_z + t d_z - sag(p_x + t d_x, p_y + t d_y) = 0 using Newton's method.
+        // The intersection with the vertex plane serves as starting point.
+        let mut t = if dir.z == 0.0 { 0.0 } else { -pos.z / dir.z };
+        let mut converged = false;
+        for _ in 0..MAX_ITERATIONS {
+            let point = pos + t * dir;
+            let (sag, grad_x, grad_y) = self.sag_and_gradient(point.x, point.y)?;
+            let f = point.z - sag;
+            if f.abs() < TOLERANCE {
+                converged = true;
+                break;
+            }
+            let df = grad_x.mul_add(-dir.x, grad_y.mul_add(-dir.y, dir.z));
+            if df == 0.0 || !df.is_finite() {
+                break;
+            }
+            t -= f / df;
+        }
+        if !converged {
+            warn!(
+                "intersection of ray with Zernike surface did not converge after {MAX_ITERATIONS} iterations. Ray is treated as missing the surface."
+            );
+            return None;
+        }
+        if t.is_sign_negative() {
+            // surface behind beam
+            return None;
+        }
+        let intersection_point = pos + t * dir;
+        let (_, grad_x, grad_y) =
+            self.sag_and_gradient(intersection_point.x, intersection_point.y)?;
+        // gradient of F(x,y,z) = sag(x, y) - z
+        let mut normal_vector = vector![grad_x, grad_y, -1.0].normalize();
+        if is_back_propagating {
+            normal_vector *= -1.0;
+        }
+        Some((
+            meter!(
+                intersection_point.x,
+                intersection_point.y,
+                intersection_point.z
+            ),
+            normal_vector,
+        ))
+    }
+    fn isometry(&self) -> &Isometry {
+        &self.isometry
+    }
+    fn set_isometry(&mut self, isometry: &Isometry) {
+        self.isometry = isometry.clone();
+    }
+    fn name(&self) -> String {
+        "zernike".into()
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{joule, millimeter, nanometer};
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+    #[test]
+    fn noll_indices() {
+        assert!(noll_to_nm(0).is_err());
+        let expected = [
+            (0, 0),
+            (1, 1),
+            (1, -1),
+            (2, 0),
+            (2, -2),
+            (2, 2),
+            (3, -1),
+            (3, 1),
+            (3, -3),
+            (3, 3),
+            (4, 0),
+        ];
+        for (j, nm) in expected.iter().enumerate() {
+            assert_eq!(noll_to_nm(j + 1).unwrap(), *nm);
+        }
+    }
+    #[test]
+    fn osa_indices() {
+        let expected = [
+            (0, 0),
+            (1, -1),
+            (1, 1),
+            (2, -2),
+            (2, 0),
+            (2, 2),
+            (3, -3),
+            (3, -1),
+            (3, 1),
+            (3, 3),
+            (4, -4),
+        ];
+        for (j, nm) in expected.iter().enumerate() {
+            assert_eq!(osa_to_nm(j).unwrap(), *nm);
+        }
+    }
+    #[test]
+    fn new() {
+        let iso = Isometry::identity();
+        let terms = [(2, 0, millimeter!(0.001))];
+        assert!(ZernikeSurface::new(millimeter!(0.0), &terms, iso.clone()).is_err());
+        assert!(ZernikeSurface::new(millimeter!(-1.0), &terms, iso.clone()).is_err());
+        assert!(ZernikeSurface::new(millimeter!(f64::NAN), &terms, iso.clone()).is_err());
+        assert!(ZernikeSurface::new(millimeter!(f64::INFINITY), &terms, iso.clone()).is_err());
+        assert!(
+            ZernikeSurface::new(millimeter!(1.0), &[(2, 1, millimeter!(0.001))], iso.clone())
+                .is_err()
+        );
+        assert!(
+            ZernikeSurface::new(
+                millimeter!(1.0),
+                &[(2, -4, millimeter!(0.001))],
+                iso.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            ZernikeSurface::new(
+                millimeter!(1.0),
+                &[(2, 0, millimeter!(f64::NAN))],
+                iso.clone()
+            )
+            .is_err()
+        );
+        let s = ZernikeSurface::new(millimeter!(2.0), &terms, iso).unwrap();
+        assert_eq!(s.normalization_radius(), millimeter!(2.0));
+        assert_eq!(s.name(), "zernike");
+    }
+    #[test]
+    fn sag_normalization() {
+        let c = millimeter!(0.001);
+        let s = ZernikeSurface::new(
+            millimeter!(10.0),
+            &[(2, 0, c), (2, 2, c), (4, 0, c)],
+            Isometry::identity(),
+        )
+        .unwrap();
+        // R_n^m(1) = 1 and cos(2θ) = 1 for θ = 0
+        assert_relative_eq!(
+            s.sag(millimeter!(10.0), millimeter!(0.0)).unwrap().value,
+            3.0 * c.value
+        );
+        // center: defocus = -1, astigmatism = 0, spherical = 1
+        assert_abs_diff_eq!(
+            s.sag(millimeter!(0.0), millimeter!(0.0)).unwrap().value,
+            0.0
+        );
+        // outside of normalization radius
+        assert!(s.sag(millimeter!(7.1), millimeter!(7.1)).is_none());
+    }
+    #[test]
+    fn polynomial() {
+        assert!(zernike_polynomial(2, 1, 0.0, 0.0).is_err());
+        assert_eq!(zernike_polynomial(0, 0, 0.3, 0.2).unwrap(), 1.0);
+        assert_eq!(zernike_polynomial(2, 0, 0.0, 0.0).unwrap(), -1.0);
+        assert_abs_diff_eq!(zernike_polynomial(2, 2, 1.0, 0.0).unwrap(), 1.0);
+        let h = 0.5_f64.sqrt();
+        assert_abs_diff_eq!(
+            zernike_polynomial(2, -2, h, h).unwrap(),
+            1.0,
+            epsilon = 1e-12
+        );
+        assert_abs_diff_eq!(zernike_polynomial(1, -1, 0.0, 0.5).unwrap(), 0.5);
+    }
+    #[test]
+    fn gradient() {
+        let terms: Vec<(usize, i32, Length)> = (1..=15)
+            .map(|j| {
+                let (n, m) = noll_to_nm(j).unwrap();
+                (n, m, millimeter!(0.001 * j as f64))
+            })
+            .collect();
+        let s = ZernikeSurface::new(millimeter!(5.0), &terms, Isometry::identity()).unwrap();
+        let h = 1.0e-9;
+        for (x, y) in [(0.0, 0.0), (1.0e-3, -2.0e-3), (-3.0e-3, 2.5e-3)] {
+            let (_, grad_x, grad_y) = s.sag_and_gradient(x, y).unwrap();
+            let num_x = (s.sag_and_gradient(x + h, y).unwrap().0
+                - s.sag_and_gradient(x - h, y).unwrap().0)
+                / (2.0 * h);
+            let num_y = (s.sag_and_gradient(x, y + h).unwrap().0
+                - s.sag_and_gradient(x, y - h).unwrap().0)
+                / (2.0 * h);
+            assert_abs_diff_eq!(grad_x, num_x, epsilon = 1e-6);
+            assert_abs_diff_eq!(grad_y, num_y, epsilon = 1e-6);
+        }
+    }
+    #[test]
+    fn intersect_tilt() {
+        // tilt Z_1^1 = x / r_0 => plane with slope c / r_0
+        let s = ZernikeSurface::new(
+            millimeter!(10.0),
+            &[(1, 1, millimeter!(1.0))],
+            Isometry::identity(),
+        )
+        .unwrap();
+        let ray = Ray::new_collimated(millimeter!(2.0, 1.0, -5.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        let (point, normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        assert_abs_diff_eq!(point.x.value, 2.0e-3, epsilon = 1e-12);
+        assert_abs_diff_eq!(point.z.value, 0.2e-3, epsilon = 1e-12);
+        assert_abs_diff_eq!(normal, vector![0.1, 0.0, -1.0].normalize(), epsilon = 1e-12);
+    }
+    #[test]
+    fn intersect() {
+        let s = ZernikeSurface::new(
+            millimeter!(10.0),
+            &[(2, 0, millimeter!(0.01)), (3, -1, millimeter!(0.005))],
+            Isometry::new_along_z(millimeter!(5.0)).unwrap(),
+        )
+        .unwrap();
+        let ray = Ray::new(
+            millimeter!(1.0, 2.0, 0.0),
+            vector![0.05, -0.1, 1.0],
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (point, normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        let sag = s.sag(point.x, point.y).unwrap();
+        assert_abs_diff_eq!(point.z.value, 5.0e-3 + sag.value, epsilon = 1e-12);
+        assert!(normal.z < 0.0);
+        let ray = Ray::new(
+            millimeter!(1.0, 2.0, 10.0),
+            vector![0.0, 0.0, -1.0],
+            nanometer!(1053.0),
+            joule!(1.0),
+        )
+        .unwrap();
+        let (_, normal) = s.calc_intersect_and_normal(&ray).unwrap();
+        assert!(normal.z > 0.0);
+        // outside of normalization radius
+        let ray = Ray::new_collimated(millimeter!(11.0, 0.0, 0.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        assert!(s.calc_intersect_and_normal(&ray).is_none());
+        // surface behind ray
+        let ray = Ray::new_collimated(millimeter!(0.0, 0.0, 6.0), nanometer!(1053.0), joule!(1.0))
+            .unwrap();
+        assert!(s.calc_intersect_and_normal(&ray).is_none());
+    }
+}
@@ -0,0 +1,231 @@
+//! Module for the calculation of contour lines (isolines) of gridded data
+
+#![warn(missing_docs)]
+use std::collections::{HashMap, VecDeque};
+
+use nalgebra::{DMatrix, DVector, MatrixXx2};
+
+use crate::{
+    error::{OpmResult, OpossumError},
+    utils::usize_to_f64,
+};
+
+/// Edge of a grid cell identified by its start index (row, column)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum GridEdge {
+    /// edge from (row, col) to (row, col + 1)
+    Horizontal(usize, usize),
+    /// edge from (row, col) to (row + 1, col)
+    Vertical(usize, usize),
+}
+
+impl GridEdge {
+    /// Returns the linearly interpolated position on this edge, where the data equals `level`
+    fn crossing(
+        self,
+        x: &DVector<f64>,
+        y: &DVector<f64>,
+        z: &DMatrix<f64>,
+        level: f64,
+    ) -> (f64, f64) {
+        match self {
+            Self::Horizontal(row, col) => {
+                let t = (level - z[(row, col)]) / (z[(row, col + 1)] - z[(row, col)]);
+                (t.mul_add(x[col + 1] - x[col], x[col]), y[row])
+            }
+            Self::Vertical(row, col) => {
+                let t = (level - z[(row, col)]) / (z[(row + 1, col)] - z[(row, col)]);
+                (x[col], t.mul_add(y[row + 1] - y[row], y[row]))
+            }
+        }
+    }
+}
+
+/// Returns the segments (as pairs of crossed edges) of the cell with the lower left corner (row, col)
+fn cell_segments(
+    z: &DMatrix<f64>,
+    row: usize,
+    col: usize,
+    level: f64,
+) -> Vec<(GridEdge, GridEdge)> {
+    let corners = [
+        z[(row, col)],
+        z[(row, col + 1)],
+        z[(row + 1, col + 1)],
+        z[(row + 1, col)],
+    ];
+    if corners.iter().any(|v| !v.is_finite()) {
+        return Vec::new();
+    }
+    let bottom = GridEdge::Horizontal(row, col);
+    let right = GridEdge::Vertical(row, col + 1);
+    let top = GridEdge::Horizontal(row + 1, col);
+    let left = GridEdge::Vertical(row, col);
+    let case = corners
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v >= level)
+        .fold(0_u8, |case, (i, _)| case | (1 << i));
+    // saddle cells are resolved using the mean value of the cell center
+    let center_above = corners.iter().sum::<f64>() / 4. >= level;
+    match case {
+        1 | 14 => vec![(left, bottom)],
+        2 | 13 => vec![(bottom, right)],
+        3 | 12 => vec![(left, right)],
+        4 | 11 => vec![(right, top)],
+        6 | 9 => vec![(bottom, top)],
+        7 | 8 => vec![(left, top)],
+        5 if center_above => vec![(bottom, right), (top, left)],
+        10 if !center_above => vec![(bottom, right), (top, left)],
+        5 | 10 => vec![(left, bottom), (right, top)],
+        _ => Vec::new(),
+    }
+}
+
+/// Calculates the contour lines of gridded data at the given `level` using the marching squares algorithm.
+///
+/// The individual line segments of all grid cells are joined to polylines. Closed contours start and end at the same point.
+/// Grid cells containing non-finite values are skipped.
+/// # Attributes
+/// - `x`: x values of the grid with `N` entries
+/// - `y`: y values of the grid with `M` entries
+/// - `z`: data matrix with `M` rows and `N` columns
+/// - `level`: z value of the contour
+/// # Returns
+/// This function returns a vector of polylines, each structured as a matrix with the (x, y) points as rows.
+/// # Errors
+/// This function errors if the shape of `z` does not match the lengths of `x` and `y`
+pub fn contour_lines(
+    x: &DVector<f64>,
+    y: &DVector<f64>,
+    z: &DMatrix<f64>,
+    level: f64,
+) -> OpmResult<Vec<MatrixXx2<f64>>> {
+    if z.shape() != (y.len(), x.len()) {
+        return Err(OpossumError::Other(
+            "shape of x, y and z does not match! z must be x.len() columns and y.len() rows!"
+                .into(),
+        ));
+    }
+    let mut segments = Vec::new();
+    for row in 0..y.len().saturating_sub(1) {
+        for col in 0..x.len().saturating_sub(1) {
+            segments.extend(cell_segments(z, row, col, level));
+        }
+    }
+    let mut adjacency: HashMap<GridEdge, Vec<usize>> = HashMap::new();
+    for (idx, (start, end)) in segments.iter().enumerate() {
+        adjacency.entry(*start).or_default().push(idx);
+        adjacency.entry(*end).or_default().push(idx);
+    }
+    let mut used = vec![false; segments.len()];
+    let next_edge = |edge: GridEdge, used: &mut Vec<bool>| {
+        let idx = adjacency
+            .get(&edge)?
+            .iter()
+            .copied()
+            .find(|idx| !used[*idx])?;
+        used[idx] = true;
+        let (start, end) = segments[idx];
+        Some(if start == edge { end } else { start })
+    };
+    let mut lines = Vec::new();
+    for idx in 0..segments.len() {
+        if used[idx] {
+            continue;
+        }
+        used[idx] = true;
+        let mut line = VecDeque::from([segments[idx].0, segments[idx].1]);
+        while let Some(edge) = next_edge(*line.back().unwrap(), &mut used) {
+            line.push_back(edge);
+        }
+        while let Some(edge) = next_edge(*line.front().unwrap(), &mut used) {
+            line.push_front(edge);
+        }
+        let points = line
+            .iter()
+            .flat_map(|edge| {
+                let (x, y) = edge.crossing(x, y, z, level);
+                [x, y]
+            })
+            .collect::<Vec<f64>>();
+        lines.push(MatrixXx2::from_row_slice(&points));
+    }
+    Ok(lines)
+}
+
+/// Chooses `nr_of_levels` equidistant contour levels in between (excluding) `min` and `max`.
+///
+/// Returns an empty vector if the range is not finite or empty.
+#[must_use]
+pub fn auto_contour_levels(min: f64, max: f64, nr_of_levels: usize) -> Vec<f64> {
+    if !min.is_finite() || !max.is_finite() || max <= min {
+        return Vec::new();
+    }
+    let step = (max - min) / usize_to_f64(nr_of_levels + 1);
+    (1..=nr_of_levels)
+        .map(|i| usize_to_f64(i).mul_add(step, min))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn cone() -> (DVector<f64>, DVector<f64>, DMatrix<f64>) {
+        let x = DVector::from_fn(21, |i, _| f64::from(u32::try_from(i).unwrap()) - 10.);
+        let y = x.clone();
+        let z = DMatrix::from_fn(21, 21, |row, col| 10. - f64::hypot(x[col], y[row]));
+        (x, y, z)
+    }
+    #[test]
+    fn contour_lines_wrong_shape() {
+        let x = DVector::from_vec(vec![0., 1., 2.]);
+        let y = DVector::from_vec(vec![0., 1.]);
+        assert!(contour_lines(&x, &y, &DMatrix::zeros(3, 2), 0.5).is_err());
+        assert!(contour_lines(&x, &y, &DMatrix::zeros(2, 3), 0.5).is_ok());
+    }
+    #[test]
+    fn contour_lines_single_cell() {
+        let x = DVector::from_vec(vec![0., 2.]);
+        let y = DVector::from_vec(vec![0., 1.]);
+        let z = DMatrix::from_row_slice(2, 2, &[0., 1., 0., 1.]);
+        let lines = contour_lines(&x, &y, &z, 0.5).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].nrows(), 2);
+        assert_relative_eq!(lines[0][(0, 0)], 1.);
+        assert_relative_eq!(lines[0][(1, 0)], 1.);
+        assert!(contour_lines(&x, &y, &z, 2.).unwrap().is_empty());
+    }
+    #[test]
+    fn contour_lines_closed_circle() {
+        let (x, y, z) = cone();
+        let lines = contour_lines(&x, &y, &z, 5.).unwrap();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line.nrows() > 10);
+        assert_relative_eq!(line[(0, 0)], line[(line.nrows() - 1, 0)]);
+        assert_relative_eq!(line[(0, 1)], line[(line.nrows() - 1, 1)]);
+        for point in line.row_iter() {
+            assert_relative_eq!(f64::hypot(point[0], point[1]), 5., max_relative = 0.05);
+        }
+    }
+    #[test]
+    fn contour_lines_skip_nan() {
+        let (x, y, mut z) = cone();
+        z[(10, 10)] = f64::NAN;
+        assert!(contour_lines(&x, &y, &z, 9.5).unwrap().is_empty());
+        assert_eq!(contour_lines(&x, &y, &z, 5.).unwrap().len(), 1);
+    }
+    #[test]
+    fn auto_levels() {
+        let levels = auto_contour_levels(0., 6., 5);
+        assert_eq!(levels.len(), 5);
+        for (level, expected) in levels.iter().zip([1., 2., 3., 4., 5.]) {
+            assert_relative_eq!(*level, expected);
+        }
+        assert!(auto_contour_levels(1., 1., 5).is_empty());
+        assert!(auto_contour_levels(0., f64::NAN, 5).is_empty());
+    }
+}
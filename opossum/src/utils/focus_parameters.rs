@@ -0,0 +1,185 @@
+#![warn(missing_docs)]
+//! Beam parameters at the focus (waist) of a ray bundle.
+use std::f64::consts::PI;
+
+use num::Zero;
+use uom::si::f64::{Angle, Length};
+
+use crate::{
+    error::{OpmResult, OpossumError},
+    meter, radian,
+};
+
+/// Calculate the Rayleigh range of a Gaussian beam.
+///
+/// The Rayleigh range is given by `z_R = π * w_0² / (M² * λ)` with the waist radius `w_0` (1/e² intensity radius),
+/// the wavelength `λ` and the beam quality factor `M²`.
+///
+/// # Errors
+///
+/// This function will return an error if
+///   - the waist radius is negative or not finite.
+///   - the wavelength is not positive or not finite.
+///   - `m_squared` is < 1.0 or not finite.
+pub fn rayleigh_range(
+    waist_radius: Length,
+    wavelength: Length,
+    m_squared: f64,
+) -> OpmResult<Length> {
+    if waist_radius.is_sign_negative() || !waist_radius.is_finite() {
+        return Err(OpossumError::Other(
+            "waist radius must be >= 0.0 and finite".into(),
+        ));
+    }
+    if wavelength.is_zero() || wavelength.is_sign_negative() || !wavelength.is_finite() {
+        return Err(OpossumError::Other(
+            "wavelength must be positive and finite".into(),
+        ));
+    }
+    if m_squared < 1.0 || !m_squared.is_finite() {
+        return Err(OpossumError::Other("M² must be >= 1.0 and finite".into()));
+    }
+    Ok(PI * waist_radius * waist_radius / (m_squared * wavelength))
+}
+/// Parameters of a ray bundle at its best-focus plane.
+///
+/// The parameters are derived from a fit of the (energy-weighted) second moments of the ray positions and
+/// directions. The waist position is given along the local z axis relative to the plane the rays were evaluated in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusParameters {
+    waist_position: Length,
+    rms_waist_radius: Length,
+    rms_divergence: Angle,
+}
+impl FocusParameters {
+    /// Create new [`FocusParameters`] from the waist position, the rms waist radius and the rms divergence.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - the waist position is not finite.
+    ///   - the rms waist radius is negative or not finite.
+    ///   - the rms divergence is negative or not finite.
+    pub fn new(
+        waist_position: Length,
+        rms_waist_radius: Length,
+        rms_divergence: Angle,
+    ) -> OpmResult<Self> {
+        if !waist_position.is_finite() {
+            return Err(OpossumError::Other("waist position must be finite".into()));
+        }
+        if rms_waist_radius.is_sign_negative() || !rms_waist_radius.is_finite() {
+            return Err(OpossumError::Other(
+                "waist radius must be >= 0.0 and finite".into(),
+            ));
+        }
+        if rms_divergence.is_sign_negative() || !rms_divergence.is_finite() {
+            return Err(OpossumError::Other(
+                "divergence must be >= 0.0 and finite".into(),
+            ));
+        }
+        Ok(Self {
+            waist_position,
+            rms_waist_radius,
+            rms_divergence,
+        })
+    }
+    /// Returns the position of the waist (best focus) along the local z axis.
+    #[must_use]
+    pub const fn waist_position(&self) -> Length {
+        self.waist_position
+    }
+    /// Returns the rms radius at the waist.
+    #[must_use]
+    pub const fn rms_waist_radius(&self) -> Length {
+        self.rms_waist_radius
+    }
+    /// Returns the rms divergence (half angle).
+    #[must_use]
+    pub const fn rms_divergence(&self) -> Angle {
+        self.rms_divergence
+    }
+    /// Returns the waist radius (1/e² intensity radius) of a Gaussian beam with the same second moments.
+    #[must_use]
+    pub fn waist_radius(&self) -> Length {
+        f64::sqrt(2.0) * self.rms_waist_radius
+    }
+    /// Returns the geometric Rayleigh range.
+    ///
+    /// This is the distance from the waist, at which the rms beam radius has grown by a factor of √2. It is infinite for
+    /// a collimated ray bundle.
+    #[must_use]
+    pub fn geometric_rayleigh_range(&self) -> Length {
+        if self.rms_divergence.is_zero() {
+            return meter!(f64::INFINITY);
+        }
+        self.rms_waist_radius / self.rms_divergence.value
+    }
+    /// Returns the geometric depth of focus.
+    ///
+    /// The depth of focus is defined as twice the [geometric Rayleigh range](Self::geometric_rayleigh_range).
+    #[must_use]
+    pub fn depth_of_focus(&self) -> Length {
+        2.0 * self.geometric_rayleigh_range()
+    }
+    /// Returns the (diffraction-limited) Rayleigh range of a Gaussian beam with the fitted waist at the given wavelength.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the wavelength is not positive or not finite.
+    pub fn rayleigh_range(&self, wavelength: Length) -> OpmResult<Length> {
+        rayleigh_range(self.waist_radius(), wavelength, 1.0)
+    }
+}
+impl Default for FocusParameters {
+    fn default() -> Self {
+        Self {
+            waist_position: Length::zero(),
+            rms_waist_radius: Length::zero(),
+            rms_divergence: radian!(0.0),
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{micrometer, millimeter};
+    use approx::assert_relative_eq;
+    use uom::si::length::micrometer;
+
+    #[test]
+    fn rayleigh_range_gaussian() {
+        let z_r = rayleigh_range(micrometer!(10.0), micrometer!(1.0), 1.0).unwrap();
+        assert_relative_eq!(z_r.get::<micrometer>(), 100.0 * PI);
+        let z_r = rayleigh_range(micrometer!(10.0), micrometer!(1.0), 2.0).unwrap();
+        assert_relative_eq!(z_r.get::<micrometer>(), 50.0 * PI);
+        assert!(rayleigh_range(micrometer!(-1.0), micrometer!(1.0), 1.0).is_err());
+        assert!(rayleigh_range(micrometer!(f64::NAN), micrometer!(1.0), 1.0).is_err());
+        assert!(rayleigh_range(micrometer!(1.0), micrometer!(0.0), 1.0).is_err());
+        assert!(rayleigh_range(micrometer!(1.0), micrometer!(f64::INFINITY), 1.0).is_err());
+        assert!(rayleigh_range(micrometer!(1.0), micrometer!(1.0), 0.9).is_err());
+    }
+    #[test]
+    fn new() {
+        let p = FocusParameters::new(millimeter!(1.0), micrometer!(5.0), radian!(0.1)).unwrap();
+        assert_eq!(p.waist_position(), millimeter!(1.0));
+        assert_eq!(p.rms_waist_radius(), micrometer!(5.0));
+        assert_eq!(p.rms_divergence(), radian!(0.1));
+        assert!(
+            FocusParameters::new(millimeter!(f64::NAN), micrometer!(5.0), radian!(0.1)).is_err()
+        );
+        assert!(FocusParameters::new(millimeter!(1.0), micrometer!(-5.0), radian!(0.1)).is_err());
+        assert!(FocusParameters::new(millimeter!(1.0), micrometer!(5.0), radian!(-0.1)).is_err());
+    }
+    #[test]
+    fn ranges() {
+        let p = FocusParameters::new(millimeter!(0.0), micrometer!(5.0), radian!(0.01)).unwrap();
+        assert_relative_eq!(p.waist_radius().value, f64::sqrt(2.0) * 5.0e-6);
+        assert_relative_eq!(p.geometric_rayleigh_range().value, 5.0e-4);
+        assert_relative_eq!(p.depth_of_focus().value, 1.0e-3);
+        let z_r = p.rayleigh_range(micrometer!(1.0)).unwrap();
+        assert_relative_eq!(z_r.value, PI * 50.0e-12 / 1.0e-6);
+        let collimated = FocusParameters::default();
+        assert!(collimated.geometric_rayleigh_range().is_infinite());
+    }
+}
@@ -24,11 +24,23 @@ use uom::si::{
 
 /// Struct to store the isometric transofmeation matrix and its inverse
 #[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[cfg_attr(feature = "json_schema", derive(schemars::JsonSchema))]
 pub struct Isometry {
+    #[cfg_attr(feature = "json_schema", schemars(with = "Isometry3Layout"))]
     transform: Isometry3<f64>,
     #[serde(skip_serializing)]
+    #[cfg_attr(feature = "json_schema", schemars(skip))]
     inverse: Isometry3<f64>,
 }
+/// Serialized layout of an [`Isometry3`]: rotation as unit quaternion (i, j, k, w) and translation in meter.
+#[cfg(feature = "json_schema")]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "Isometry3")]
+#[allow(dead_code)]
+struct Isometry3Layout {
+    rotation: [f64; 4],
+    translation: [f64; 3],
+}
 impl Isometry {
     /// Creates a new [`Isometry`] which stores the rotation and translation as a transform matrix and its inverse.
     /// Internally, translation is handled in meter, rotation in radians
@@ -209,6 +221,78 @@ impl Isometry {
             &up_direction,
         ))
     }
+    /// Creates a new [`Isometry`] from roll, pitch and yaw angles and a translation.
+    ///
+    /// The coordinate system is right-handed and the rotations are active (counter-clockwise for positive angles when
+    /// looking along the respective axis towards the origin):
+    ///   - `roll`: rotation around the x axis
+    ///   - `pitch`: rotation around the y axis
+    ///   - `yaw`: rotation around the z axis
+    ///
+    /// The rotations are applied in the order roll -> pitch -> yaw around the fixed (global) axes, i.e. the resulting
+    /// rotation matrix is `R = R_z(yaw) * R_y(pitch) * R_x(roll)`. This is the same convention as used in [`Isometry::new`].
+    /// Afterwards, the translation is applied. The angles can be retrieved again using [`Isometry::rotation`]. For pitch
+    /// angles within (-90°, 90°), and roll / yaw angles within (-180°, 180°], the returned angles are identical.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the angles or the translation coordinates are not finite.
+    pub fn from_euler(
+        roll: Angle,
+        pitch: Angle,
+        yaw: Angle,
+        translation: Point3<Length>,
+    ) -> OpmResult<Self> {
+        Self::new(translation, Point3::new(roll, pitch, yaw))
+    }
+    /// Creates a new [`Isometry`] located at `eye` with its local z axis pointing towards `target`.
+    ///
+    /// The local y axis is oriented such that it lies in the plane spanned by the local z axis and the given `up`
+    /// direction. This is useful for orienting e.g. steering mirrors by their intended beam path.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    ///   - `eye` or `target` contain non-finite coordinates.
+    ///   - `eye` and `target` are identical.
+    ///   - `up` is zero, not finite or collinear with the viewing direction.
+    pub fn look_at(
+        eye: Point3<Length>,
+        target: Point3<Length>,
+        up: Vector3<f64>,
+    ) -> OpmResult<Self> {
+        if eye.iter().chain(target.iter()).any(|x| !x.is_finite()) {
+            return Err(OpossumError::Other(
+                "eye and target coordinates must be finite".into(),
+            ));
+        }
+        let eye_in_m =
+            Point3::from_slice(&eye.iter().map(Length::get::<meter>).collect::<Vec<f64>>());
+        let target_in_m = Point3::from_slice(
+            &target
+                .iter()
+                .map(Length::get::<meter>)
+                .collect::<Vec<f64>>(),
+        );
+        let Some(direction) = (target_in_m - eye_in_m).try_normalize(f64::EPSILON) else {
+            return Err(OpossumError::Other(
+                "eye and target must not be identical".into(),
+            ));
+        };
+        if up.iter().any(|x| !x.is_finite())
+            || up.cross(&direction).norm() <= f64::EPSILON * up.norm()
+        {
+            return Err(OpossumError::Other(
+                "up direction must be finite, non-zero and not collinear to the view direction"
+                    .into(),
+            ));
+        }
+        Ok(Self::new_from_transform(Isometry3::face_towards(
+            &eye_in_m,
+            &target_in_m,
+            &up,
+        )))
+    }
     /// Add another [`Isometry`] to this [`Isometry`].
     ///
     /// This function "chains" two isometries (translation & rotation)
@@ -704,6 +788,92 @@ mod test {
         assert_eq!(i.transform.rotation.k, 0.0);
     }
     #[test]
+    fn from_euler() {
+        assert!(
+            Isometry::from_euler(
+                degree!(f64::NAN),
+                degree!(0.0),
+                degree!(0.0),
+                millimeter!(0., 0., 0.)
+            )
+            .is_err()
+        );
+        assert!(
+            Isometry::from_euler(
+                degree!(0.0),
+                degree!(0.0),
+                degree!(0.0),
+                millimeter!(0., f64::INFINITY, 0.)
+            )
+            .is_err()
+        );
+        for (roll, pitch, yaw) in [
+            (0.0, 0.0, 0.0),
+            (10.0, 0.0, 0.0),
+            (0.0, -20.0, 0.0),
+            (0.0, 0.0, 135.0),
+            (30.0, 45.0, -60.0),
+            (-170.0, 80.0, 170.0),
+        ] {
+            let iso = Isometry::from_euler(
+                degree!(roll),
+                degree!(pitch),
+                degree!(yaw),
+                millimeter!(1.0, 2.0, 3.0),
+            )
+            .unwrap();
+            let rot = iso.rotation();
+            assert_relative_eq!(rot.x.value, degree!(roll).value, epsilon = 1e-12);
+            assert_relative_eq!(rot.y.value, degree!(pitch).value, epsilon = 1e-12);
+            assert_relative_eq!(rot.z.value, degree!(yaw).value, epsilon = 1e-12);
+            assert_relative_eq!(iso.translation().x.value, 0.001);
+            assert_relative_eq!(iso.translation().y.value, 0.002);
+            assert_relative_eq!(iso.translation().z.value, 0.003);
+        }
+        // yaw rotates the x axis towards the y axis (right-handed)
+        let iso = Isometry::from_euler(
+            degree!(0.0),
+            degree!(0.0),
+            degree!(90.0),
+            millimeter!(0., 0., 0.),
+        )
+        .unwrap();
+        let x_axis = iso.transform_vector_f64(&Vector3::x());
+        assert_abs_diff_eq!(x_axis, Vector3::y(), epsilon = 1e-12);
+    }
+    #[test]
+    fn look_at() {
+        let origin = millimeter!(0., 0., 0.);
+        assert!(Isometry::look_at(origin, origin, Vector3::y()).is_err());
+        assert!(Isometry::look_at(millimeter!(f64::NAN, 0., 0.), origin, Vector3::y()).is_err());
+        assert!(Isometry::look_at(origin, millimeter!(0., 0., 1.), Vector3::zeros()).is_err());
+        assert!(Isometry::look_at(origin, millimeter!(0., 0., 1.), Vector3::z()).is_err());
+        assert!(
+            Isometry::look_at(origin, millimeter!(0., 0., 1.), vector![f64::NAN, 1.0, 0.0])
+                .is_err()
+        );
+        let iso = Isometry::look_at(
+            millimeter!(0., 0., 10.),
+            millimeter!(0., 10., 20.),
+            Vector3::y(),
+        )
+        .unwrap();
+        assert_relative_eq!(iso.translation().z.value, 0.01);
+        let z_axis = iso.transform_vector_f64(&Vector3::z());
+        assert_abs_diff_eq!(z_axis, vector![0.0, 1.0, 1.0].normalize(), epsilon = 1e-12);
+        let rot = iso.rotation();
+        assert_relative_eq!(rot.x.value, degree!(-45.0).value, epsilon = 1e-12);
+        assert_abs_diff_eq!(rot.y.value, 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(rot.z.value, 0.0, epsilon = 1e-12);
+        // round trip via euler angles
+        let iso2 = Isometry::from_euler(rot.x, rot.y, rot.z, iso.translation()).unwrap();
+        assert_abs_diff_eq!(
+            iso2.transform_vector_f64(&Vector3::z()),
+            z_axis,
+            epsilon = 1e-12
+        );
+    }
+    #[test]
     fn new_translation() {
         assert!(Isometry::new_translation(meter!(f64::NAN, 0.0, 0.0)).is_err());
         assert!(Isometry::new_translation(meter!(f64::NEG_INFINITY, 0.0, 0.0)).is_err());
@@ -0,0 +1,459 @@
+//! Export of sceneries and ray paths to binary glTF (`.glb`) files.
+//!
+//! The exported files can be imported into external 3D tools such as Blender or web viewers. All coordinates are given
+//! in meters.
+use crate::{
+    error::{OpmResult, OpossumError},
+    joule, nanometer,
+    nodes::{NodeGroup, ray_propagation_visualizer::RayPolylines},
+    optic_ports::PortType,
+    ray::Ray,
+    surface::optic_surface::OpticSurface,
+    utils::{geom_transformation::Isometry, usize_to_f64},
+};
+use nalgebra::{Point2, Point3, Vector3};
+use std::{fs::File, path::Path};
+use uom::si::f64::Length;
+
+/// Number of grid points per axis used for sampling a surface.
+const SURFACE_GRID_POINTS: usize = 33;
+/// Vertex color of all exported surfaces.
+const SURFACE_COLOR: [f32; 3] = [0.7, 0.7, 0.7];
+
+/// glTF primitive mode for line segments.
+pub(crate) const LINES: u32 = 1;
+/// glTF primitive mode for triangles.
+pub(crate) const TRIANGLES: u32 = 4;
+
+/// A single (colored) mesh to be written to a glTF file.
+pub(crate) struct GltfMesh {
+    /// name of the mesh (and its node)
+    pub name: String,
+    /// vertex positions in meters
+    pub positions: Vec<[f32; 3]>,
+    /// RGB vertex colors (0.0..=1.0)
+    pub colors: Vec<[f32; 3]>,
+    /// vertex indices of the primitives
+    pub indices: Vec<u32>,
+    /// glTF primitive mode ([`LINES`] or [`TRIANGLES`])
+    pub mode: u32,
+}
+
+/// Converts a vertex index into a glTF index.
+pub(crate) fn gltf_index(index: usize) -> OpmResult<u32> {
+    u32::try_from(index)
+        .map_err(|_| OpossumError::Other("too many vertices for glTF export".into()))
+}
+
+/// Returns the mesh of the ray paths of the given [`RayPolylines`] colored by the number of bounces of each ray.
+///
+/// # Errors
+///
+/// This function will return an error if the number of vertices is too large.
+pub(crate) fn rays_mesh(polylines: &RayPolylines) -> OpmResult<GltfMesh> {
+    let mut positions = Vec::<[f32; 3]>::new();
+    let mut colors = Vec::<[f32; 3]>::new();
+    let mut indices = Vec::<u32>::new();
+    for polyline in polylines.polylines() {
+        let color = colorous::CATEGORY10[polyline.number_of_bounces() % colorous::CATEGORY10.len()];
+        let first_index = positions.len();
+        for (i, pos) in polyline.positions().iter().enumerate() {
+            positions.push(to_f32(pos));
+            colors.push([
+                f32::from(color.r) / 255.0,
+                f32::from(color.g) / 255.0,
+                f32::from(color.b) / 255.0,
+            ]);
+            if i > 0 {
+                indices.extend([
+                    gltf_index(first_index + i - 1)?,
+                    gltf_index(first_index + i)?,
+                ]);
+            }
+        }
+    }
+    Ok(GltfMesh {
+        name: "rays".into(),
+        positions,
+        colors,
+        indices,
+        mode: LINES,
+    })
+}
+
+/// Export the geometry of a scenery together with the given ray paths to a binary glTF (`.glb`) file.
+///
+/// All optical surfaces (including those of nested groups) are exported as separate triangle meshes named
+/// `<node name>: <port name>`. Each surface is sampled on a square grid with a side length of `surface_size` in its local
+/// x-y plane. Regions blocked by the aperture of the surface are cut out. The ray paths are exported as a single mesh of
+/// line primitives colored by the number of bounces (see [`RayPolylines::to_gltf`]).
+///
+/// The surfaces are exported at their current position. Hence, this function should be called after a ray tracing
+/// analysis has been performed on the scenery. Nodes which have not been positioned are omitted.
+///
+/// # Errors
+///
+/// This function will return an error if
+///   - `surface_size` is not positive and finite.
+///   - a node of the scenery cannot be locked.
+///   - the number of vertices is too large.
+///   - the file could not be written.
+pub fn scenery_to_gltf(
+    scenery: &NodeGroup,
+    rays: &RayPolylines,
+    surface_size: Length,
+    path: &Path,
+) -> OpmResult<()> {
+    if !surface_size.is_normal() || surface_size.is_sign_negative() {
+        return Err(OpossumError::Other(
+            "surface size must be positive and finite".into(),
+        ));
+    }
+    let mut meshes = surface_meshes(scenery, surface_size)?;
+    meshes.push(rays_mesh(rays)?);
+    let file = File::create(path)
+        .map_err(|e| OpossumError::Other(format!("could not create glTF file: {e}")))?;
+    write_glb(file, &meshes)
+        .map_err(|e| OpossumError::Other(format!("could not write glTF file: {e}")))
+}
+
+/// Returns the meshes of all surfaces of the given [`NodeGroup`] including its subgroups.
+fn surface_meshes(group: &NodeGroup, surface_size: Length) -> OpmResult<Vec<GltfMesh>> {
+    let mut meshes = Vec::new();
+    for node_ref in group.nodes() {
+        let mut node = node_ref
+            .optical_ref
+            .lock()
+            .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+        if let Ok(subgroup) = node.as_group_mut() {
+            meshes.append(&mut surface_meshes(subgroup, surface_size)?);
+            continue;
+        }
+        // reference nodes share the surfaces of the referenced node
+        if node.node_type() == "reference" {
+            continue;
+        }
+        let name = node.name();
+        let ports = node.node_attr().ports();
+        for (port_name, surf) in ports
+            .ports(&PortType::Input)
+            .iter()
+            .chain(ports.ports(&PortType::Output))
+        {
+            let (Some(node_iso), Ok(iso)) = (
+                node.effective_node_iso(),
+                node.effective_surface_iso(port_name),
+            ) else {
+                continue;
+            };
+            // the anchor point of a curved surface might be its center of curvature. Hence, the sampling rays start
+            // in front of the node position.
+            let start_z = iso.inverse_transform_point(&node_iso.translation()).z - surface_size;
+            let mesh = surface_mesh(
+                &format!("{name}: {port_name}"),
+                surf,
+                &iso,
+                start_z,
+                surface_size,
+            )?;
+            if !mesh.indices.is_empty() {
+                meshes.push(mesh);
+            }
+        }
+    }
+    Ok(meshes)
+}
+
+/// Sample the given [`OpticSurface`] on a square grid in the x-y plane of its anchor point [`Isometry`].
+///
+/// The z coordinate of each grid point is determined by intersecting a ray starting at the local z position `start_z` and
+/// propagating along the local z axis with the surface. Grid cells with a corner outside the surface or blocked by the
+/// aperture are omitted.
+fn surface_mesh(
+    name: &str,
+    surf: &OpticSurface,
+    iso: &Isometry,
+    start_z: Length,
+    surface_size: Length,
+) -> OpmResult<GltfMesh> {
+    let geo_surface = surf.geo_surface();
+    let geo_surface = geo_surface
+        .0
+        .lock()
+        .map_err(|_| OpossumError::Other("Mutex lock failed".into()))?;
+    let direction = iso.transform_vector_f64(&Vector3::z());
+    let step = surface_size / usize_to_f64(SURFACE_GRID_POINTS - 1);
+    let mut grid_indices = vec![None; SURFACE_GRID_POINTS * SURFACE_GRID_POINTS];
+    let mut positions = Vec::<[f32; 3]>::new();
+    for (i, grid_index) in grid_indices.iter_mut().enumerate() {
+        let local_xy = Point2::new(
+            step * usize_to_f64(i % SURFACE_GRID_POINTS) - surface_size / 2.0,
+            step * usize_to_f64(i / SURFACE_GRID_POINTS) - surface_size / 2.0,
+        );
+        if surf.aperture().apodization_factor(&local_xy) <= 0.0 {
+            continue;
+        }
+        let start = iso.transform_point(&Point3::new(local_xy.x, local_xy.y, start_z));
+        let ray = Ray::new(start, direction, nanometer!(1000.0), joule!(1.0))?;
+        if let Some((intersection, _)) = geo_surface.calc_intersect_and_normal(&ray) {
+            *grid_index = Some(gltf_index(positions.len())?);
+            positions.push(to_f32(&intersection));
+        }
+    }
+    let mut indices = Vec::<u32>::new();
+    for row in 0..SURFACE_GRID_POINTS - 1 {
+        for col in 0..SURFACE_GRID_POINTS - 1 {
+            let corner = |r: usize, c: usize| grid_indices[r * SURFACE_GRID_POINTS + c];
+            if let (Some(a), Some(b), Some(c), Some(d)) = (
+                corner(row, col),
+                corner(row, col + 1),
+                corner(row + 1, col + 1),
+                corner(row + 1, col),
+            ) {
+                indices.extend([a, b, c, a, c, d]);
+            }
+        }
+    }
+    Ok(GltfMesh {
+        name: name.into(),
+        colors: vec![SURFACE_COLOR; positions.len()],
+        positions,
+        indices,
+        mode: TRIANGLES,
+    })
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_f32(pos: &Point3<Length>) -> [f32; 3] {
+    [pos.x.value as f32, pos.y.value as f32, pos.z.value as f32]
+}
+
+/// Write a binary glTF (`.glb`) file containing the given meshes.
+///
+/// Each mesh is stored as a separate glTF node with its vertex positions, vertex colors and indices. Meshes without
+/// indices are skipped.
+pub(crate) fn write_glb<W: std::io::Write>(
+    mut writer: W,
+    meshes: &[GltfMesh],
+) -> std::io::Result<()> {
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+    let mut bin = Vec::<u8>::new();
+    let mut nodes = Vec::new();
+    let mut gltf_meshes = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    for mesh in meshes.iter().filter(|mesh| !mesh.indices.is_empty()) {
+        let (min, max) = mesh.positions.iter().fold(
+            ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+            |(mut min, mut max), pos| {
+                for ((min, max), value) in min.iter_mut().zip(max.iter_mut()).zip(pos) {
+                    *min = min.min(*value);
+                    *max = max.max(*value);
+                }
+                (min, max)
+            },
+        );
+        let first_accessor = accessors.len();
+        for (data, target) in [
+            (&mesh.positions, ARRAY_BUFFER),
+            (&mesh.colors, ARRAY_BUFFER),
+        ] {
+            let offset = bin.len();
+            for value in data.iter().flatten() {
+                bin.extend_from_slice(&value.to_le_bytes());
+            }
+            buffer_views.push(serde_json::json!({
+                "buffer": 0, "byteOffset": offset, "byteLength": bin.len() - offset, "target": target
+            }));
+        }
+        let offset = bin.len();
+        for index in &mesh.indices {
+            bin.extend_from_slice(&index.to_le_bytes());
+        }
+        buffer_views.push(serde_json::json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": bin.len() - offset,
+            "target": ELEMENT_ARRAY_BUFFER,
+        }));
+        accessors.extend([
+            serde_json::json!({
+                "bufferView": first_accessor,
+                "componentType": FLOAT,
+                "count": mesh.positions.len(),
+                "type": "VEC3",
+                "min": min,
+                "max": max,
+            }),
+            serde_json::json!({
+                "bufferView": first_accessor + 1,
+                "componentType": FLOAT,
+                "count": mesh.colors.len(),
+                "type": "VEC3",
+            }),
+            serde_json::json!({
+                "bufferView": first_accessor + 2,
+                "componentType": UNSIGNED_INT,
+                "count": mesh.indices.len(),
+                "type": "SCALAR",
+            }),
+        ]);
+        nodes.push(serde_json::json!({ "name": mesh.name, "mesh": gltf_meshes.len() }));
+        gltf_meshes.push(serde_json::json!({
+            "name": mesh.name,
+            "primitives": [{
+                "attributes": { "POSITION": first_accessor, "COLOR_0": first_accessor + 1 },
+                "indices": first_accessor + 2,
+                "mode": mesh.mode,
+            }],
+        }));
+    }
+    let buffers = if bin.is_empty() {
+        serde_json::json!([])
+    } else {
+        serde_json::json!([{ "byteLength": bin.len() }])
+    };
+    let json = serde_json::json!({
+        "asset": { "version": "2.0", "generator": "OPOSSUM" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": gltf_meshes,
+        "buffers": buffers,
+        "bufferViews": buffer_views,
+        "accessors": accessors,
+    });
+    let mut json = serde_json::to_vec(&json)?;
+    // both chunks must be aligned to 4 bytes (JSON padded with spaces, binary data with zeros)
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let to_u32 = |value: usize| {
+        u32::try_from(value)
+            .map_err(|_| std::io::Error::other("glTF file exceeds the maximum size"))
+    };
+    let bin_chunk_length = if bin.is_empty() { 0 } else { 8 + bin.len() };
+    writer.write_all(b"glTF")?;
+    writer.write_all(&2_u32.to_le_bytes())?;
+    writer.write_all(&to_u32(12 + 8 + json.len() + bin_chunk_length)?.to_le_bytes())?;
+    writer.write_all(&to_u32(json.len())?.to_le_bytes())?;
+    writer.write_all(b"JSON")?;
+    writer.write_all(&json)?;
+    if !bin.is_empty() {
+        writer.write_all(&to_u32(bin.len())?.to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&bin)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        analyzers::{Analyzer, raytrace::RayTracingAnalyzer},
+        aperture::{Aperture, CircleConfig},
+        millimeter,
+        nodes::{Lens, RayPropagationVisualizer, round_collimated_ray_source},
+        properties::Proptype,
+        refractive_index::RefrIndexConst,
+    };
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn read_gltf_json(path: &Path) -> serde_json::Value {
+        let glb = fs::read(path).unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        let json_length = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        serde_json::from_slice(&glb[20..20 + json_length]).unwrap()
+    }
+    #[test]
+    fn scenery_to_gltf_lens() {
+        let mut scenery = NodeGroup::default();
+        let i_src = scenery
+            .add_node(round_collimated_ray_source(millimeter!(5.0), joule!(1.0), 3).unwrap())
+            .unwrap();
+        let lens = Lens::new(
+            "lens",
+            millimeter!(100.0),
+            millimeter!(-100.0),
+            millimeter!(5.0),
+            &RefrIndexConst::new(1.5).unwrap(),
+        )
+        .unwrap()
+        .with_clear_aperture(&Aperture::BinaryCircle(
+            CircleConfig::new(millimeter!(5.0), Point2::origin()).unwrap(),
+        ))
+        .unwrap();
+        let i_l = scenery.add_node(lens).unwrap();
+        let i_rpv = scenery
+            .add_node(
+                RayPropagationVisualizer::default()
+                    .with_polyline_export(10)
+                    .unwrap(),
+            )
+            .unwrap();
+        scenery
+            .connect_nodes(i_src, "output_1", i_l, "input_1", millimeter!(50.0))
+            .unwrap();
+        scenery
+            .connect_nodes(i_l, "output_1", i_rpv, "input_1", millimeter!(50.0))
+            .unwrap();
+        RayTracingAnalyzer::default().analyze(&mut scenery).unwrap();
+        let node_report = scenery
+            .node(i_rpv)
+            .unwrap()
+            .optical_ref
+            .lock()
+            .unwrap()
+            .node_report("")
+            .unwrap();
+        let Ok(Proptype::RayPolylines(polylines)) = node_report.properties().get("Ray polylines")
+        else {
+            panic!("property `Ray polylines` not found");
+        };
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("scenery.glb");
+        assert!(scenery_to_gltf(&scenery, polylines, millimeter!(0.0), &path).is_err());
+        assert!(scenery_to_gltf(&scenery, polylines, millimeter!(f64::NAN), &path).is_err());
+        scenery_to_gltf(&scenery, polylines, millimeter!(20.0), &path).unwrap();
+        let gltf = read_gltf_json(&path);
+        let names = gltf["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["name"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"lens: input_1".to_owned()));
+        assert!(names.contains(&"lens: output_1".to_owned()));
+        assert_eq!(names.last().unwrap(), "rays");
+        let lens_idx = names.iter().position(|n| n == "lens: input_1").unwrap();
+        let lens_mesh = &gltf["meshes"][lens_idx];
+        assert_eq!(lens_mesh["primitives"][0]["mode"], TRIANGLES);
+        // the aperture cuts out the outer region of the sampled square
+        let index_count = gltf["accessors"][lens_idx * 3 + 2]["count"]
+            .as_u64()
+            .unwrap();
+        let full_count = ((SURFACE_GRID_POINTS - 1) * (SURFACE_GRID_POINTS - 1) * 6) as u64;
+        assert!(index_count > 0 && index_count < full_count / 2);
+        // the lens vertices are all within the aperture
+        let max = gltf["accessors"][lens_idx * 3]["max"].as_array().unwrap();
+        assert!(max[0].as_f64().unwrap() <= 0.005 + 1e-6);
+        assert_eq!(
+            gltf["meshes"][names.len() - 1]["primitives"][0]["mode"],
+            LINES
+        );
+    }
+    #[test]
+    fn write_glb_empty() {
+        let mut glb = Vec::<u8>::new();
+        write_glb(&mut glb, &[]).unwrap();
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+    }
+}
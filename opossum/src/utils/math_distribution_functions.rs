@@ -175,6 +175,25 @@ pub fn gaussian(points: &[f64], mu: f64, fwhm: f64, power: f64) -> Vec<f64> {
     gaussian
 }
 
+/// Generate a 1-dimensional Lorentzian (Cauchy) distribution from a vector of input `points`
+/// Each point will be assigned the respective value of this Lorentzian distribution normalized to a peak value of 1.
+/// # Attributes
+/// - `points`: Vector of input points
+/// - `mu`: the center value -> Shifts the distribution to be centered at `mu`
+/// - `fwhm`: the full-width at half maximum of the distribution
+///
+/// # Remarks
+/// This function does not check the usefulness of the input arguments,
+/// meaning that passing values of NaN, Infinity, zero or negative numbers may result in an unexpected outcome of this function.
+#[must_use]
+pub fn lorentzian(points: &[f64], mu: f64, fwhm: f64) -> Vec<f64> {
+    let half_width = fwhm / 2.;
+    points
+        .iter()
+        .map(|p| 1. / (1. + ((p - mu) / half_width).powi(2)))
+        .collect()
+}
+
 /// Creates Points that lie on a circle with given radius and center
 ///
 /// # Errors
@@ -225,6 +244,12 @@ mod test {
         assert_eq!(y_values, vec![0.0625, 0.5, 1.0, 0.5]);
     }
     #[test]
+    fn test_lorentzian() {
+        let x_values = vec![-1.0, 0.0, 1.0, 2.0];
+        let y_values = lorentzian(&x_values, 1.0, 2.0);
+        assert_eq!(y_values, vec![0.2, 0.5, 1.0, 0.5]);
+    }
+    #[test]
     fn ellipse_wrong() {
         for val in vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
             assert!(ellipse((val, 0.0), (0.0, 0.0), 1).is_err());
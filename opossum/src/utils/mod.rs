@@ -1,6 +1,9 @@
 //! Module for additional computational capabilities
+pub mod contour;
 pub mod filter_data;
+pub mod focus_parameters;
 pub mod geom_transformation;
+pub mod gltf;
 pub mod griddata;
 pub mod math_distribution_functions;
 pub mod math_utils;
@@ -8,3 +11,16 @@ pub mod test_helper;
 pub mod unit_format;
 pub mod uom_macros;
 pub use math_utils::{f64_to_usize, isize_to_f64, usize_to_f64};
+
+use serde::{Deserialize, Deserializer};
+
+/// Deserialize an optional value, which is stored without a `Some(...)` wrapper.
+///
+/// This is used together with `#[serde(default)]` for fields that are simply omitted if not present.
+pub(crate) fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -60,6 +60,9 @@ impl From<OpossumError> for ErrorResponse {
             OpossumError::OpticScenery(_) => (StatusCode::BAD_REQUEST, "OpticScenery".to_string()),
             OpossumError::OpticGroup(_) => (StatusCode::BAD_REQUEST, "OpticGroup".to_string()),
             OpossumError::OpticPort(_) => (StatusCode::BAD_REQUEST, "OpticPort".to_string()),
+            OpossumError::PortAlreadyConnected(_) => {
+                (StatusCode::BAD_REQUEST, "PortAlreadyConnected".to_string())
+            }
             OpossumError::Analysis(_) => (StatusCode::BAD_REQUEST, "Analysis".to_string()),
             OpossumError::Spectrum(_) => (StatusCode::BAD_REQUEST, "Spectrum".to_string()),
             OpossumError::Console(_) => (StatusCode::BAD_REQUEST, "Console".to_string()),
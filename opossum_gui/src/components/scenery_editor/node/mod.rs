@@ -54,7 +54,7 @@ impl NodeType {
                 "energy meter" => Some(NODE_ENERGY_METER),
                 "group" => Some(NODE_GROUP),
                 "ideal filter" => Some(NODE_FILTER),
-                "reflective grating" => Some(NODE_GRATING),
+                "reflective grating" | "transmission grating" => Some(NODE_GRATING),
                 // "reference" => Some(NODE_UNKNOWN),
                 "lens" => Some(NODE_LENS),
                 "cylindric lens" => Some(NODE_CYLINDRIC_LENS),
@@ -65,7 +65,7 @@ impl NodeType {
                 "paraxial surface" => Some(NODE_PARAXIAL),
                 "ray propagation" => Some(NODE_PROPAGATION),
                 "fluence detector" => Some(NODE_FLUENCE),
-                "wedge" => Some(NODE_WEDGE),
+                "wedge" | "prism" => Some(NODE_WEDGE),
                 "mirror" => Some(NODE_MIRROR),
                 "parabolic mirror" => Some(NODE_PARABOLA),
                 _ => Some(NODE_UNKNOWN),